@@ -20,6 +20,20 @@ pub enum AdmissionControlStatus {
     Blacklisted(String),
     /// The transaction is rejected, e.g. due to incorrect signature.
     Rejected(String),
+    /// Validator accepted the transaction, but it waits for the transaction of the same account
+    /// with the given sequence number, which is missing.
+    AcceptedWithSequenceNumberGap(u64),
+}
+
+impl AdmissionControlStatus {
+    /// Whether the transaction was accepted, regardless of sequence number gaps.
+    pub fn is_accepted(&self) -> bool {
+        match self {
+            AdmissionControlStatus::Accepted
+            | AdmissionControlStatus::AcceptedWithSequenceNumberGap(_) => true,
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<crate::proto::admission_control::AdmissionControlStatus> for AdmissionControlStatus {
@@ -37,6 +51,9 @@ impl TryFrom<crate::proto::admission_control::AdmissionControlStatus> for Admiss
                 let msg = proto.message;
                 AdmissionControlStatus::Rejected(msg)
             }
+            ProtoStatusCode::AcceptedWithSequenceNumberGap => {
+                AdmissionControlStatus::AcceptedWithSequenceNumberGap(proto.missing_sequence_number)
+            }
         };
         Ok(ret)
     }
@@ -58,6 +75,10 @@ impl From<AdmissionControlStatus> for crate::proto::admission_control::Admission
                 admission_control_status.message = msg;
                 admission_control_status.set_code(ProtoStatusCode::Rejected)
             }
+            AdmissionControlStatus::AcceptedWithSequenceNumberGap(missing_sequence_number) => {
+                admission_control_status.missing_sequence_number = missing_sequence_number;
+                admission_control_status.set_code(ProtoStatusCode::AcceptedWithSequenceNumberGap)
+            }
         }
         admission_control_status
     }
@@ -66,7 +87,8 @@ impl From<AdmissionControlStatus> for crate::proto::admission_control::Admission
 /// Rust structure for SubmitTransactionResponse protobuf definition.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SubmitTransactionResponse {
-    /// AC status returned to client if any - it can be one of: accepted, blacklisted, or rejected.
+    /// AC status returned to client if any - it can be one of: accepted (possibly with a sequence
+    /// number gap), blacklisted, or rejected.
     pub ac_status: Option<AdmissionControlStatus>,
     /// Mempool error status if any.
     pub mempool_error: Option<MempoolAddTransactionStatus>,
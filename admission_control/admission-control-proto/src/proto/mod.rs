@@ -12,8 +12,8 @@ pub mod admission_control {
 }
 
 pub use self::admission_control::{
-    admission_control_client::AdmissionControlClient, AdmissionControlMsg,
-    SubmitTransactionRequest, SubmitTransactionResponse,
+    admission_control_client::AdmissionControlClient, AdmissionControlMsg, QueryMempoolRequest,
+    QueryMempoolResponse, SubmitTransactionRequest, SubmitTransactionResponse,
 };
 
 pub struct AdmissionControlClientBlocking {
@@ -93,6 +93,22 @@ impl AdmissionControlClientBlocking {
         .map_err(|_| tonic::Status::new(tonic::Code::DeadlineExceeded, ""))?
         .map(tonic::Response::into_inner)
     }
+
+    pub fn query_mempool(
+        &mut self,
+        request: QueryMempoolRequest,
+    ) -> Result<QueryMempoolResponse, tonic::Status> {
+        let (rt, client) = self.client()?;
+        rt.block_on(async {
+            tokio::time::timeout(
+                std::time::Duration::from_millis(5000),
+                client.query_mempool(request),
+            )
+            .await
+        })
+        .map_err(|_| tonic::Status::new(tonic::Code::DeadlineExceeded, ""))?
+        .map(tonic::Response::into_inner)
+    }
 }
 
 // Allow for lazily creating a Client
@@ -151,4 +167,18 @@ impl AdmissionControlClientAsync {
         .map_err(|_| tonic::Status::new(tonic::Code::DeadlineExceeded, ""))?
         .map(tonic::Response::into_inner)
     }
+
+    pub async fn query_mempool(
+        &mut self,
+        request: QueryMempoolRequest,
+    ) -> Result<QueryMempoolResponse, tonic::Status> {
+        let client = self.client().await?;
+        tokio::time::timeout(
+            std::time::Duration::from_millis(5000),
+            client.query_mempool(request),
+        )
+        .await
+        .map_err(|_| tonic::Status::new(tonic::Code::DeadlineExceeded, ""))?
+        .map(tonic::Response::into_inner)
+    }
 }
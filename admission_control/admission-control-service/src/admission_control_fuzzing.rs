@@ -5,7 +5,7 @@ use crate::admission_control_service::AdmissionControlService;
 use admission_control_proto::proto::admission_control::{
     admission_control_server::AdmissionControl, SubmitTransactionRequest,
 };
-use futures::executor::block_on;
+use futures::{channel::mpsc, executor::block_on};
 use libra_mempool::mocks::mock_shared_mempool;
 use libra_proptest_helpers::ValueGenerator;
 use libra_prost_ext::MessageExt;
@@ -39,7 +39,9 @@ pub fn generate_corpus(gen: &mut ValueGenerator) -> Vec<u8> {
 pub fn fuzzer(data: &[u8]) {
     // set up AC backed by SMP
     let (_runtime, ac_sender) = mock_shared_mempool();
-    let ac_service = AdmissionControlService::new(ac_sender, Arc::new(MockStorageReadClient));
+    let (mempool_sender, _mempool_requests) = mpsc::channel(1_024);
+    let ac_service =
+        AdmissionControlService::new(ac_sender, mempool_sender, Arc::new(MockStorageReadClient));
 
     // parse SubmitTransactionRequest
     let req = match SubmitTransactionRequest::decode(data) {
@@ -8,16 +8,23 @@
 use crate::counters;
 use admission_control_proto::proto::admission_control::{
     admission_control_server::{AdmissionControl, AdmissionControlServer},
+    query_mempool_request::Query,
+    query_mempool_response::Response,
+    GetAccountTransactionsResponse, MempoolTransaction, QueryMempoolRequest, QueryMempoolResponse,
     SubmitTransactionRequest, SubmitTransactionResponse,
 };
-use anyhow::Result;
+use anyhow::{bail, format_err, Result};
 use futures::{
     channel::{mpsc, oneshot},
     SinkExt,
 };
 use libra_config::config::NodeConfig;
 use libra_logger::prelude::*;
-use libra_types::proto::types::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse};
+use libra_mempool::{MempoolRequest, MempoolResponse};
+use libra_types::{
+    account_address::AccountAddress,
+    proto::types::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse},
+};
 use std::{convert::TryFrom, sync::Arc};
 use storage_client::{StorageRead, StorageReadServiceClient};
 use tokio::runtime::{Builder, Runtime};
@@ -29,6 +36,8 @@ pub struct AdmissionControlService {
         SubmitTransactionRequest,
         oneshot::Sender<Result<SubmitTransactionResponse>>,
     )>,
+    /// Channel to send queries to Mempool.
+    mempool_sender: mpsc::Sender<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
     /// gRPC client to send read requests to Storage.
     storage_read_client: Arc<dyn StorageRead>,
 }
@@ -40,10 +49,12 @@ impl AdmissionControlService {
             SubmitTransactionRequest,
             oneshot::Sender<Result<SubmitTransactionResponse>>,
         )>,
+        mempool_sender: mpsc::Sender<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
         storage_read_client: Arc<dyn StorageRead>,
     ) -> Self {
         AdmissionControlService {
             ac_sender,
+            mempool_sender,
             storage_read_client,
         }
     }
@@ -56,6 +67,7 @@ impl AdmissionControlService {
             SubmitTransactionRequest,
             oneshot::Sender<Result<SubmitTransactionResponse>>,
         )>,
+        mempool_sender: mpsc::Sender<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
     ) -> Runtime {
         let runtime = Builder::new()
             .thread_name("ac-service-")
@@ -67,7 +79,8 @@ impl AdmissionControlService {
         // Create storage read client
         let storage_client: Arc<dyn StorageRead> =
             Arc::new(StorageReadServiceClient::new(&config.storage.address));
-        let admission_control_service = AdmissionControlService::new(ac_sender, storage_client);
+        let admission_control_service =
+            AdmissionControlService::new(ac_sender, mempool_sender, storage_client);
 
         runtime.spawn(
             tonic::transport::Server::builder()
@@ -100,6 +113,51 @@ impl AdmissionControlService {
         );
         Ok(rust_resp.into())
     }
+
+    /// Pass the QueryMempoolRequest to Mempool.
+    async fn query_mempool_inner(&self, req: QueryMempoolRequest) -> Result<QueryMempoolResponse> {
+        let mempool_req = match req.query.ok_or_else(|| format_err!("Missing query"))? {
+            Query::GetAccountTransactionsRequest(req) => {
+                MempoolRequest::GetAccountTransactionsRequest {
+                    address: AccountAddress::try_from(req.address)?,
+                }
+            }
+            Query::GetTransactionStatusRequest(req) => {
+                MempoolRequest::GetTransactionStatusRequest {
+                    address: AccountAddress::try_from(req.address)?,
+                    sequence_number: req.sequence_number,
+                }
+            }
+            Query::GetMempoolStatsRequest(_) => MempoolRequest::GetStatsRequest {},
+        };
+
+        let (req_sender, res_receiver) = oneshot::channel();
+        self.mempool_sender
+            .clone()
+            .send((mempool_req, req_sender))
+            .await?;
+        let response = match res_receiver.await?? {
+            MempoolResponse::GetAccountTransactionsResponse { transactions } => {
+                Response::GetAccountTransactionsResponse(GetAccountTransactionsResponse {
+                    transactions: transactions
+                        .into_iter()
+                        .map(|(txn, status)| MempoolTransaction {
+                            transaction: Some(txn.into()),
+                            status: Some(status.into()),
+                        })
+                        .collect(),
+                })
+            }
+            MempoolResponse::GetTransactionStatusResponse { status } => {
+                Response::TransactionStatus(status.into())
+            }
+            MempoolResponse::GetStatsResponse { stats } => Response::MempoolStats(stats.into()),
+            _ => bail!("Unexpected response from mempool"),
+        };
+        Ok(QueryMempoolResponse {
+            response: Some(response),
+        })
+    }
 }
 
 #[tonic::async_trait]
@@ -166,4 +224,21 @@ impl AdmissionControl for AdmissionControlService {
             .map_err(|e| tonic::Status::new(tonic::Code::InvalidArgument, e.to_string()))?;
         Ok(tonic::Response::new(resp))
     }
+    /// Query the content of the mempool of this validator: the pending transactions of an
+    /// account, the status of a transaction or mempool-wide statistics.
+    async fn query_mempool(
+        &self,
+        request: tonic::Request<QueryMempoolRequest>,
+    ) -> Result<tonic::Response<QueryMempoolResponse>, tonic::Status> {
+        debug!("[GRPC] AdmissionControl::query_mempool");
+        counters::REQUESTS
+            .with_label_values(&["query_mempool"])
+            .inc();
+        let req = request.into_inner();
+        let resp = self
+            .query_mempool_inner(req)
+            .await
+            .map_err(|e| tonic::Status::new(tonic::Code::InvalidArgument, e.to_string()))?;
+        Ok(tonic::Response::new(resp))
+    }
 }
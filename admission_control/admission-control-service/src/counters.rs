@@ -9,7 +9,8 @@ pub static REQUESTS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "libra_admission_control_service_requests_count",
         "Cumulative number of requests that admission control receives",
-        &["type"] // type of request: "submit_transaction", "update_to_latest_ledger"
+        // type of request: "submit_transaction", "update_to_latest_ledger", "query_mempool"
+        &["type"]
     )
     .unwrap()
 });
@@ -3,7 +3,8 @@
 
 use admission_control_proto::proto::admission_control::{
     admission_control_server::AdmissionControl, submit_transaction_response::Status,
-    SubmitTransactionRequest, SubmitTransactionResponse as ProtoSubmitTransactionResponse,
+    QueryMempoolRequest, QueryMempoolResponse, SubmitTransactionRequest,
+    SubmitTransactionResponse as ProtoSubmitTransactionResponse,
 };
 use admission_control_proto::{AdmissionControlStatus, SubmitTransactionResponse};
 use anyhow::Result;
@@ -160,6 +161,13 @@ impl AdmissionControl for MockAdmissionControlService {
     ) -> Result<tonic::Response<UpdateToLatestLedgerResponse>, tonic::Status> {
         unimplemented!("This method is not needed for this test");
     }
+
+    async fn query_mempool(
+        &self,
+        _request: tonic::Request<QueryMempoolRequest>,
+    ) -> Result<tonic::Response<QueryMempoolResponse>, tonic::Status> {
+        unimplemented!("This method is not needed for this test");
+    }
 }
 
 #[test]
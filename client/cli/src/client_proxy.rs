@@ -9,6 +9,7 @@ use libra_crypto::{
     test_utils::KeyPair,
 };
 use libra_logger::prelude::*;
+use libra_mempool_shared_proto::{MempoolStats, MempoolTransactionStatus};
 use libra_temppath::TempPath;
 use libra_types::crypto_proxies::LedgerInfoWithSignatures;
 use libra_types::waypoint::Waypoint;
@@ -727,6 +728,50 @@ impl ClientProxy {
             .get_txn_by_range(start_version, limit, fetch_events)
     }
 
+    /// Get the transactions of an account pending in mempool, along with their status
+    pub fn get_mempool_account_transactions(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<Vec<(SignedTransaction, MempoolTransactionStatus)>> {
+        ensure!(
+            space_delim_strings.len() == 2,
+            "Invalid number of arguments to get pending transactions of account"
+        );
+        let account = self.get_account_address_from_parameter(space_delim_strings[1])?;
+        self.client.get_mempool_account_transactions(account)
+    }
+
+    /// Get the mempool status of a transaction by account and sequence number
+    pub fn get_mempool_transaction_status(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<MempoolTransactionStatus> {
+        ensure!(
+            space_delim_strings.len() == 3,
+            "Invalid number of arguments to get mempool status of transaction"
+        );
+        let account = self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let sequence_number = space_delim_strings[2].parse::<u64>().map_err(|error| {
+            format_parse_data_error(
+                "account_sequence_number",
+                InputType::UnsignedInt,
+                space_delim_strings[2],
+                error,
+            )
+        })?;
+        self.client
+            .get_mempool_transaction_status(account, sequence_number)
+    }
+
+    /// Get mempool-wide statistics
+    pub fn get_mempool_stats(&mut self, space_delim_strings: &[&str]) -> Result<MempoolStats> {
+        ensure!(
+            space_delim_strings.len() == 1,
+            "Invalid number of arguments to get mempool stats"
+        );
+        self.client.get_mempool_stats()
+    }
+
     /// Get account address from parameter. If the parameter is string of address, try to convert
     /// it to address, otherwise, try to convert to u64 and looking at TestClient::accounts.
     pub fn get_account_address_from_parameter(&self, para: &str) -> Result<AccountAddress> {
@@ -3,7 +3,8 @@
 
 use crate::{
     account_commands::AccountCommand, client_proxy::ClientProxy, dev_commands::DevCommand,
-    query_commands::QueryCommand, transfer_commands::TransferCommand,
+    mempool_commands::MempoolCommand, query_commands::QueryCommand,
+    transfer_commands::TransferCommand,
 };
 use anyhow::Error;
 use libra_metrics::counters::*;
@@ -61,6 +62,7 @@ pub fn get_commands(
         Arc::new(AccountCommand {}),
         Arc::new(QueryCommand {}),
         Arc::new(TransferCommand {}),
+        Arc::new(MempoolCommand {}),
     ];
     if include_dev {
         commands.push(Arc::new(DevCommand {}));
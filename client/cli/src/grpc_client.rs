@@ -3,11 +3,17 @@
 
 use crate::AccountData;
 use admission_control_proto::{
-    proto::admission_control::SubmitTransactionRequest, proto::AdmissionControlClientBlocking,
+    proto::admission_control::{
+        query_mempool_request::Query, query_mempool_response::Response,
+        GetAccountTransactionsRequest, GetMempoolStatsRequest, GetTransactionStatusRequest,
+        QueryMempoolRequest, SubmitTransactionRequest,
+    },
+    proto::AdmissionControlClientBlocking,
     AdmissionControlStatus, SubmitTransactionResponse,
 };
-use anyhow::{bail, Result};
+use anyhow::{bail, format_err, Result};
 use libra_logger::prelude::*;
+use libra_mempool_shared_proto::{MempoolStats, MempoolTransactionStatus};
 use libra_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
@@ -18,7 +24,7 @@ use libra_types::{
     get_with_proof::{
         RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
-    transaction::{Transaction, Version},
+    transaction::{SignedTransaction, Transaction, Version},
     validator_change::VerifierType,
     vm_error::StatusCode,
     waypoint::Waypoint,
@@ -85,7 +91,17 @@ impl GRPCClient {
         let completed_resp = SubmitTransactionResponse::try_from(resp?)?;
 
         if let Some(ac_status) = completed_resp.ac_status {
-            if ac_status == AdmissionControlStatus::Accepted {
+            if ac_status.is_accepted() {
+                if let AdmissionControlStatus::AcceptedWithSequenceNumberGap(
+                    missing_sequence_number,
+                ) = ac_status
+                {
+                    warn!(
+                        "Transaction accepted, but it waits for the missing transaction with \
+                         sequence number {}",
+                        missing_sequence_number
+                    );
+                }
                 if let Some(sender_account) = sender_account_opt {
                     // Bump up sequence_number if transaction is accepted.
                     sender_account.sequence_number += 1;
@@ -291,4 +307,70 @@ impl GRPCClient {
             ),
         }
     }
+    fn query_mempool(&mut self, query: Query) -> Result<Response> {
+        let mut req = QueryMempoolRequest::default();
+        req.query = Some(query);
+        let mut resp = self.client.query_mempool(req.clone()).map_err(Into::into);
+
+        let mut try_cnt = 0;
+        while Self::need_to_retry(try_cnt, &resp) {
+            resp = self.client.query_mempool(req.clone()).map_err(Into::into);
+            try_cnt += 1;
+        }
+
+        resp?
+            .response
+            .ok_or_else(|| format_err!("Malformed QueryMempoolResponse which has no response set"))
+    }
+
+    /// Get the transactions of an account pending in the mempool of the validator, along with
+    /// their status.
+    pub fn get_mempool_account_transactions(
+        &mut self,
+        address: AccountAddress,
+    ) -> Result<Vec<(SignedTransaction, MempoolTransactionStatus)>> {
+        let mut req = GetAccountTransactionsRequest::default();
+        req.address = address.to_vec();
+        match self.query_mempool(Query::GetAccountTransactionsRequest(req))? {
+            Response::GetAccountTransactionsResponse(resp) => resp
+                .transactions
+                .into_iter()
+                .map(|txn| {
+                    let signed_txn = txn
+                        .transaction
+                        .ok_or_else(|| format_err!("Missing transaction"))?;
+                    let status = txn.status.ok_or_else(|| format_err!("Missing status"))?;
+                    Ok((
+                        SignedTransaction::try_from(signed_txn)?,
+                        MempoolTransactionStatus::try_from(status)?,
+                    ))
+                })
+                .collect(),
+            resp => bail!("Incorrect type of response returned: {:?}", resp),
+        }
+    }
+
+    /// Get the status in the mempool of the validator of a transaction by account and sequence
+    /// number.
+    pub fn get_mempool_transaction_status(
+        &mut self,
+        address: AccountAddress,
+        sequence_number: u64,
+    ) -> Result<MempoolTransactionStatus> {
+        let mut req = GetTransactionStatusRequest::default();
+        req.address = address.to_vec();
+        req.sequence_number = sequence_number;
+        match self.query_mempool(Query::GetTransactionStatusRequest(req))? {
+            Response::TransactionStatus(status) => MempoolTransactionStatus::try_from(status),
+            resp => bail!("Incorrect type of response returned: {:?}", resp),
+        }
+    }
+
+    /// Get statistics of the mempool of the validator.
+    pub fn get_mempool_stats(&mut self) -> Result<MempoolStats> {
+        match self.query_mempool(Query::GetMempoolStatsRequest(GetMempoolStatsRequest {}))? {
+            Response::MempoolStats(stats) => Ok(stats.into()),
+            resp => bail!("Incorrect type of response returned: {:?}", resp),
+        }
+    }
 }
@@ -25,6 +25,7 @@ pub mod commands;
 mod dev_commands;
 /// gRPC client wrapper to connect to validator.
 mod grpc_client;
+mod mempool_commands;
 mod query_commands;
 mod transfer_commands;
 
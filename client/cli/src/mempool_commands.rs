@@ -0,0 +1,149 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    client_proxy::ClientProxy,
+    commands::{report_error, subcommand_execute, Command},
+};
+use libra_mempool_shared_proto::{
+    proto::mempool_status::MempoolRemovalReason, MempoolTransactionStatus,
+};
+use transaction_builder::get_transaction_name;
+
+/// Major command for mempool queries.
+pub struct MempoolCommand {}
+
+impl Command for MempoolCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["mempool", "mp"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Mempool queries"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(MempoolCommandGetAccountTxns {}),
+            Box::new(MempoolCommandGetTxnStatus {}),
+            Box::new(MempoolCommandGetStats {}),
+        ];
+
+        subcommand_execute(&params[0], commands, client, &params[1..]);
+    }
+}
+
+fn format_status(status: MempoolTransactionStatus) -> String {
+    match status {
+        MempoolTransactionStatus::Unknown => "Unknown to mempool".to_string(),
+        MempoolTransactionStatus::Ready => "Ready to be included in the next block".to_string(),
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number,
+        } => format!(
+            "Parked, waiting for the transaction with sequence number {}",
+            missing_sequence_number
+        ),
+        MempoolTransactionStatus::Removed(reason) => {
+            let reason = match reason {
+                MempoolRemovalReason::Committed => "committed",
+                MempoolRemovalReason::Rejected => "rejected",
+                MempoolRemovalReason::Expired => "expired",
+                MempoolRemovalReason::SystemTtlExpired => "stayed in mempool for too long",
+                MempoolRemovalReason::Evicted => "evicted because mempool is full",
+                MempoolRemovalReason::ParkedTtlExpired => {
+                    "waited on a sequence number gap for too long"
+                }
+            };
+            format!("Removed from mempool: {}", reason)
+        }
+    }
+}
+
+/// Sub command to list the transactions of an account pending in mempool.
+pub struct MempoolCommandGetAccountTxns {}
+
+impl Command for MempoolCommandGetAccountTxns {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["account", "a"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<account_ref_id>|<account_address>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Get the transactions of an account pending in mempool, along with their status"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Getting pending transactions of account");
+        match client.get_mempool_account_transactions(&params) {
+            Ok(txns) => {
+                if txns.is_empty() {
+                    println!("No pending transactions");
+                }
+                for (txn, status) in txns {
+                    println!(
+                        "Transaction with sequence number {}: {}\n{}",
+                        txn.sequence_number(),
+                        format_status(status),
+                        txn.format_for_client(get_transaction_name)
+                    );
+                }
+            }
+            Err(e) => report_error("Error getting pending transactions of account", e),
+        }
+    }
+}
+
+/// Sub command to get the mempool status of a transaction by account and sequence number.
+pub struct MempoolCommandGetTxnStatus {}
+
+impl Command for MempoolCommandGetTxnStatus {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["status", "s"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<account_ref_id>|<account_address> <sequence_number>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Get the mempool status of a transaction by account and sequence number: ready, \
+         parked on a sequence number gap, or recently removed from mempool"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Getting mempool status of transaction");
+        match client.get_mempool_transaction_status(&params) {
+            Ok(status) => println!("{}", format_status(status)),
+            Err(e) => report_error("Error getting mempool status of transaction", e),
+        }
+    }
+}
+
+/// Sub command to get mempool-wide statistics.
+pub struct MempoolCommandGetStats {}
+
+impl Command for MempoolCommandGetStats {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["stats", "st"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Get the number of ready and parked transactions in mempool, by gas price"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Getting mempool stats");
+        match client.get_mempool_stats(&params) {
+            Ok(stats) => {
+                println!(
+                    "Ready: {}, parked: {}, accounts: {}",
+                    stats.ready, stats.parked, stats.accounts
+                );
+                for bucket in stats.buckets {
+                    let max_gas_price = bucket
+                        .min_gas_price
+                        .checked_mul(2)
+                        .map_or(u64::max_value(), |price| price.max(1) - 1);
+                    println!(
+                        "Gas price {}-{}: ready: {}, parked: {}",
+                        bucket.min_gas_price, max_gas_price, bucket.ready, bucket.parked
+                    );
+                }
+            }
+            Err(e) => report_error("Error getting mempool stats", e),
+        }
+    }
+}
@@ -18,6 +18,7 @@ pub struct BoundedExecutor {
 }
 
 /// Returned by [`BoundedExecutor::try_spawn`] if it is at capacity.
+#[derive(Debug)]
 pub enum SpawnError {
     AtCapacity,
 }
@@ -48,6 +49,23 @@ impl BoundedExecutor {
         });
         self.executor.spawn(f)
     }
+
+    /// Try to spawn a [`Future`] on the `BoundedExecutor`. If the executor is
+    /// at capacity, the future is dropped and [`SpawnError::AtCapacity`] is
+    /// returned instead of waiting for a task to complete.
+    pub fn try_spawn<F>(&self, f: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let spawn_permit = self.semaphore.try_acquire().ok_or(SpawnError::AtCapacity)?;
+        let f = f.map(move |ret| {
+            // Release the permit back to the semaphore when this task completes.
+            drop(spawn_permit);
+            ret
+        });
+        Ok(self.executor.spawn(f))
+    }
 }
 
 #[cfg(test)]
@@ -105,4 +123,20 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn try_spawn_at_capacity() {
+        let mut rt = Runtime::new().unwrap();
+        let executor = BoundedExecutor::new(1, rt.handle().clone());
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+
+        let handle = executor.try_spawn(rx).unwrap();
+        assert!(executor.try_spawn(async {}).is_err());
+
+        // The permit is released once the task completes.
+        tx.send(()).unwrap();
+        rt.block_on(handle).unwrap().unwrap();
+        let handle = executor.try_spawn(async {}).unwrap();
+        rt.block_on(handle).unwrap();
+    }
 }
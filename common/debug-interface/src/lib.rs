@@ -5,7 +5,7 @@
 
 use crate::proto::{
     node_debug_interface_client::NodeDebugInterfaceClient, GetEventsRequest, GetEventsResponse,
-    GetNodeDetailsRequest,
+    GetNodeDetailsRequest, GetSyncStatusRequest, GetSyncStatusResponse,
 };
 use anyhow::{Context, Result};
 use std::collections::HashMap;
@@ -95,4 +95,12 @@ impl NodeDebugClient {
             .context("Unable to query Node events")?;
         Ok(response.into_inner())
     }
+
+    pub fn get_sync_status(&mut self) -> Result<GetSyncStatusResponse> {
+        let (rt, client) = self.client()?;
+        let response = rt
+            .block_on(client.get_sync_status(GetSyncStatusRequest::default()))
+            .context("Unable to query Node sync status")?;
+        Ok(response.into_inner())
+    }
 }
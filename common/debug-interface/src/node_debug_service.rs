@@ -7,19 +7,38 @@ use crate::{
     json_log,
     proto::{
         node_debug_interface_server::NodeDebugInterface, Event, GetEventsRequest,
-        GetEventsResponse, GetNodeDetailsRequest, GetNodeDetailsResponse,
+        GetEventsResponse, GetNodeDetailsRequest, GetNodeDetailsResponse, GetSyncStatusRequest,
+        GetSyncStatusResponse,
     },
 };
+use anyhow::Result;
 use libra_logger::prelude::*;
+use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
+/// Reports the progress of state synchronization to the debug interface.
+#[tonic::async_trait]
+pub trait SyncStatusProvider: Send + Sync {
+    async fn sync_status(&self) -> Result<GetSyncStatusResponse>;
+}
+
 #[derive(Clone, Default)]
-pub struct NodeDebugService;
+pub struct NodeDebugService {
+    sync_status_provider: Option<Arc<dyn SyncStatusProvider>>,
+}
 
 impl NodeDebugService {
     pub fn new() -> Self {
         Default::default()
     }
+
+    pub fn with_sync_status_provider(
+        mut self,
+        sync_status_provider: Arc<dyn SyncStatusProvider>,
+    ) -> Self {
+        self.sync_status_provider = Some(sync_status_provider);
+        self
+    }
 }
 
 #[tonic::async_trait]
@@ -51,4 +70,19 @@ impl NodeDebugInterface for NodeDebugService {
         }
         Ok(Response::new(response))
     }
+
+    async fn get_sync_status(
+        &self,
+        _request: Request<GetSyncStatusRequest>,
+    ) -> Result<Response<GetSyncStatusResponse>, Status> {
+        let provider = self
+            .sync_status_provider
+            .as_ref()
+            .ok_or_else(|| Status::unavailable("State sync status is not available"))?;
+        let response = provider
+            .sync_status()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(response))
+    }
 }
@@ -39,6 +39,9 @@ pub enum SecurityEvent {
     /// Consensus received an invalid sync info message
     InvalidSyncInfoMsg,
 
+    /// Consensus collected evidence of a misbehaving validator
+    ConsensusEquivocation,
+
     /// HealthChecker received an invalid network event
     InvalidNetworkEventHC,
 
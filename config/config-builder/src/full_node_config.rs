@@ -125,8 +125,8 @@ impl FullNodeConfig {
             .full_node_networks
             .last_mut()
             .ok_or(Error::MissingFullNodeNetwork)?;
-        network.advertised_address = self.advertised.clone();
-        network.listen_address = self.listen.clone();
+        network.advertised_addresses = vec![self.advertised.clone()];
+        network.listen_addresses = vec![self.listen.clone()];
         network.seed_peers = seed_peers;
 
         Ok(config)
@@ -137,8 +137,8 @@ impl FullNodeConfig {
         let mut new_net = configs.swap_remove(configs.len() - 1);
         let seed_peers = self.build_seed_peers(&new_net)?;
         let mut network = new_net.full_node_networks.swap_remove(0);
-        network.advertised_address = self.advertised.clone();
-        network.listen_address = self.listen.clone();
+        network.advertised_addresses = vec![self.advertised.clone()];
+        network.listen_addresses = vec![self.listen.clone()];
         network.seed_peers = seed_peers;
         config.full_node_networks.push(network);
         Ok(())
@@ -195,8 +195,8 @@ impl FullNodeConfig {
                 .full_node_networks
                 .get_mut(0)
                 .ok_or(Error::MissingFullNodeNetwork)?;
-            network.listen_address = utils::get_available_port_in_multiaddr(true);
-            network.advertised_address = network.listen_address.clone();
+            network.listen_addresses = vec![utils::get_available_port_in_multiaddr(true)];
+            network.advertised_addresses = network.listen_addresses.clone();
             network.enable_remote_authentication = self.enable_remote_authentication;
 
             network_peers.peers.insert(
@@ -266,14 +266,14 @@ mod test {
         let (seed_peer_id, seed_peer_ips) = network.seed_peers.seed_peers.iter().next().unwrap();
         assert!(&network.peer_id != seed_peer_id);
         // This is true because  we didn't update the DEFAULT_ADVERTISED
-        assert_eq!(network.advertised_address, seed_peer_ips[0]);
+        assert_eq!(network.advertised_addresses[0], seed_peer_ips[0]);
         assert_eq!(
-            network.advertised_address,
-            DEFAULT_ADVERTISED.parse::<Multiaddr>().unwrap()
+            network.advertised_addresses,
+            vec![DEFAULT_ADVERTISED.parse::<Multiaddr>().unwrap()]
         );
         assert_eq!(
-            network.listen_address,
-            DEFAULT_LISTEN.parse::<Multiaddr>().unwrap()
+            network.listen_addresses,
+            vec![DEFAULT_LISTEN.parse::<Multiaddr>().unwrap()]
         );
         assert!(config.execution.genesis.is_some());
     }
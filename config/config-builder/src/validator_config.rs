@@ -95,8 +95,8 @@ impl ValidatorConfig {
             .validator_network
             .as_mut()
             .ok_or(Error::MissingValidatorNetwork)?;
-        validator_network.listen_address = self.listen.clone();
-        validator_network.advertised_address = self.advertised.clone();
+        validator_network.listen_addresses = vec![self.listen.clone()];
+        validator_network.advertised_addresses = vec![self.advertised.clone()];
 
         let mut seed_peers = HashMap::new();
         seed_peers.insert(first_peer_id, vec![self.bootstrap.clone()]);
@@ -189,14 +189,14 @@ mod test {
         let (seed_peer_id, seed_peer_ips) = network.seed_peers.seed_peers.iter().next().unwrap();
         assert!(&network.peer_id != seed_peer_id);
         // These equal cause we didn't set
-        assert_eq!(network.advertised_address, seed_peer_ips[0]);
+        assert_eq!(network.advertised_addresses[0], seed_peer_ips[0]);
         assert_eq!(
-            network.advertised_address,
-            DEFAULT_ADVERTISED.parse::<Multiaddr>().unwrap()
+            network.advertised_addresses,
+            vec![DEFAULT_ADVERTISED.parse::<Multiaddr>().unwrap()]
         );
         assert_eq!(
-            network.listen_address,
-            DEFAULT_LISTEN.parse::<Multiaddr>().unwrap()
+            network.listen_addresses,
+            vec![DEFAULT_LISTEN.parse::<Multiaddr>().unwrap()]
         );
         assert!(config.execution.genesis.is_some());
     }
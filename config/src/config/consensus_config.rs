@@ -14,6 +14,12 @@ pub struct ConsensusConfig {
     pub max_pruned_blocks_in_mem: usize,
     pub pacemaker_initial_timeout_ms: u64,
     pub safety_rules: SafetyRulesConfig,
+    // Full nodes only: follow the consensus traffic published on the full-node network as a
+    // read-only observer, instead of relying solely on state sync to learn about new blocks.
+    pub observer_mode: bool,
+    // Validators only: publish proposals and commit certificates to the observers connected on
+    // the full-node network.
+    pub publish_to_observers: bool,
 }
 
 impl Default for ConsensusConfig {
@@ -25,6 +31,8 @@ impl Default for ConsensusConfig {
             max_pruned_blocks_in_mem: 10000,
             pacemaker_initial_timeout_ms: 1000,
             safety_rules: SafetyRulesConfig::default(),
+            observer_mode: false,
+            publish_to_observers: false,
         }
     }
 }
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::vm_config::{deserialize_script_hashes, serialize_script_hashes};
+use libra_types::{account_address::AccountAddress, transaction::SCRIPT_HASH_LENGTH};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::PathBuf};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -10,11 +13,46 @@ pub struct MempoolConfig {
     pub shared_mempool_tick_interval_ms: u64,
     pub shared_mempool_batch_size: usize,
     pub shared_mempool_max_concurrent_inbound_syncs: usize,
+    // max number of peers transactions are broadcast to at each tick, picked by health and latency
+    pub shared_mempool_broadcast_fanout: usize,
+    // time to wait for a peer to acknowledge a batch of transactions before sending it again
+    pub shared_mempool_ack_timeout_ms: u64,
+    // max number of batches sent to a peer and not acknowledged yet
+    pub shared_mempool_max_inflight_batches: usize,
+    // time to wait before broadcasting to a peer again once it reported its mempool is full
+    pub shared_mempool_backoff_interval_ms: u64,
+    // max number of times a batch is sent again to a peer that didn't acknowledge it in time or
+    // couldn't add its transactions to its Mempool, before the batch is dropped
+    pub shared_mempool_max_batch_retries: usize,
+    // max number of client queries of Mempool content served per second
+    pub shared_mempool_max_queries_per_sec: u64,
     pub capacity: usize,
     // max number of transactions per user in Mempool
     pub capacity_per_user: usize,
+    // max number of transactions per user waiting on a sequence number gap
+    pub parked_capacity_per_user: usize,
+    // once Mempool, or an account, reaches its capacity, make room for new transactions by
+    // evicting the transactions paying the lowest gas price, instead of rejecting the new ones
+    pub evict_by_gas_price: bool,
+    // min increase of gas price, in percent, for a transaction to replace a pending transaction
+    // with the same sequence number
+    pub replacement_gas_price_bump_percent: u64,
     pub system_transaction_timeout_secs: u64,
+    // time a transaction can wait on a sequence number gap before it's removed from Mempool
+    pub parked_transaction_timeout_secs: u64,
     pub system_transaction_gc_interval_ms: u64,
+    // persist pending transactions on disk, so that they survive restarts
+    pub persist_transactions: bool,
+    pub persistence_path: PathBuf,
+    // interval at which newly added transactions are appended to the on-disk log
+    pub persistence_snapshot_interval_ms: u64,
+    // interval at which the on-disk log is rewritten with the content of mempool
+    pub persistence_compaction_interval_ms: u64,
+    // transactions with block space and Mempool capacity reserved for them, e.g. validator set
+    // management transactions that shouldn't be starved by spam
+    pub priority_lanes: Vec<PriorityLaneConfig>,
+    #[serde(skip)]
+    data_dir: PathBuf,
 }
 
 impl Default for MempoolConfig {
@@ -24,10 +62,57 @@ impl Default for MempoolConfig {
             shared_mempool_tick_interval_ms: 50,
             shared_mempool_batch_size: 100,
             shared_mempool_max_concurrent_inbound_syncs: 100,
+            shared_mempool_broadcast_fanout: 8,
+            shared_mempool_ack_timeout_ms: 2_000,
+            shared_mempool_max_inflight_batches: 4,
+            shared_mempool_backoff_interval_ms: 5_000,
+            shared_mempool_max_batch_retries: 3,
+            shared_mempool_max_queries_per_sec: 100,
             capacity: 1_000_000,
             capacity_per_user: 100,
+            parked_capacity_per_user: 20,
+            evict_by_gas_price: true,
+            replacement_gas_price_bump_percent: 10,
             system_transaction_timeout_secs: 86400,
+            parked_transaction_timeout_secs: 600,
             system_transaction_gc_interval_ms: 180_000,
+            persist_transactions: false,
+            persistence_path: PathBuf::from("mempool/transactions.log"),
+            persistence_snapshot_interval_ms: 1_000,
+            persistence_compaction_interval_ms: 600_000,
+            priority_lanes: vec![],
+            data_dir: PathBuf::from("/opt/libra/data/common"),
         }
     }
 }
+
+impl MempoolConfig {
+    pub fn persistence_path(&self) -> PathBuf {
+        if self.persistence_path.is_relative() {
+            self.data_dir.join(&self.persistence_path)
+        } else {
+            self.persistence_path.clone()
+        }
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriorityLaneConfig {
+    pub name: String,
+    // senders allowed in the lane
+    pub addresses: HashSet<AccountAddress>,
+    // if not empty, only transactions of allowed senders running these scripts belong to the lane
+    #[serde(deserialize_with = "deserialize_script_hashes")]
+    #[serde(serialize_with = "serialize_script_hashes")]
+    pub script_hashes: HashSet<[u8; SCRIPT_HASH_LENGTH]>,
+    // number of transactions of each block reserved for the lane
+    pub reserved_block_space: u64,
+    // max number of transactions of the lane in Mempool, on top of Mempool capacity. Once the
+    // lane is full, its transactions are added to the rest of Mempool
+    pub capacity: usize,
+}
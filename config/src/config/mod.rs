@@ -148,6 +148,7 @@ impl NodeConfig {
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
         self.base.data_dir = data_dir.clone();
         self.consensus.set_data_dir(data_dir.clone());
+        self.mempool.set_data_dir(data_dir.clone());
         self.metrics.set_data_dir(data_dir.clone());
         self.storage.set_data_dir(data_dir);
     }
@@ -217,6 +218,7 @@ impl NodeConfig {
                 "Provided a validator network config for a full_node node"
             );
         }
+        config.consensus.safety_rules.verify()?;
 
         let input_dir = RootPath::new(input_path);
         config.execution.load(&input_dir)?;
@@ -386,8 +388,8 @@ mod test {
             .as_mut()
             .expect("Missing expected network config");
 
-        expected_network.advertised_address = actual_network.advertised_address.clone();
-        expected_network.listen_address = actual_network.listen_address.clone();
+        expected_network.advertised_addresses = actual_network.advertised_addresses.clone();
+        expected_network.listen_addresses = actual_network.listen_addresses.clone();
         expected_network.network_keypairs = actual_network.network_keypairs.clone();
         expected_network.network_peers = actual_network.network_peers.clone();
         expected_network.seed_peers = actual_network.seed_peers.clone();
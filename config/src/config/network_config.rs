@@ -15,29 +15,51 @@ use libra_crypto::{
 use libra_types::PeerId;
 use parity_multiaddr::Multiaddr;
 use rand::rngs::StdRng;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, convert::TryFrom, path::PathBuf, string::ToString};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    path::PathBuf,
+    string::ToString,
+};
 
 const NETWORK_PEERS_DEFAULT: &str = "network_peers.config.toml";
 const SEED_PEERS_DEFAULT: &str = "seed_peers.toml";
+// Protocol ids of the rate limited direct-send protocols. The network crate depends on this one,
+// so they are defined here, and the ids used by the network interfaces are derived from them.
+pub const MEMPOOL_DIRECT_SEND_PROTOCOL: &str = "/libra/direct-send/0.1.0/mempool/0.1.0";
+pub const STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL: &str =
+    "/libra/direct-send/0.1.0/state-synchronizer/0.1.0";
 
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Clone))]
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct NetworkConfig {
     pub peer_id: PeerId,
-    // TODO: Add support for multiple listen/advertised addresses in config.
-    // The address that this node is listening on for new connections.
-    pub listen_address: Multiaddr,
-    // The address that this node advertises to other nodes for the discovery protocol.
-    pub advertised_address: Multiaddr,
+    // The addresses that this node is listening on for new connections. Each address is served by
+    // the transport which supports it. An empty address is replaced by the local IP.
+    #[serde(alias = "listen_address", deserialize_with = "deserialize_addresses")]
+    pub listen_addresses: Vec<Multiaddr>,
+    // The addresses that this node advertises to other nodes for the discovery protocol, in the
+    // order in which they should be dialed. If empty, the listen addresses are advertised.
+    #[serde(
+        alias = "advertised_address",
+        deserialize_with = "deserialize_addresses"
+    )]
+    pub advertised_addresses: Vec<Multiaddr>,
     pub discovery_interval_ms: u64,
     pub connectivity_check_interval_ms: u64,
     // Flag to toggle if Noise is used for encryption and authentication.
     pub enable_noise: bool,
+    // Flag to toggle if QUIC is used. QUIC connections are encrypted and authenticated with the
+    // network signing keys, and their addresses take the form /ip4/<ip>/udp/<port>/quic. If Noise
+    // is also enabled, TCP addresses keep being served with TCP + Noise. Peers are identified by
+    // their signing keys, so QUIC requires remote authentication. The TLS stack holds a copy of the
+    // private signing key, which is then no longer confined to the secure storage of discovery.
+    pub enable_quic: bool,
     // If the network uses remote authentication, only trusted peers are allowed to connect.
-    // Otherwise, any node can connect. If this flag is set to true, `enable_noise` must
-    // also be set to true.
+    // Otherwise, any node can connect. If this flag is set to true, `enable_noise` or
+    // `enable_quic` must also be set to true.
     pub enable_remote_authentication: bool,
     // network peers are the nodes allowed to connect when the network is started in authenticated
     // mode.
@@ -48,6 +70,13 @@ pub struct NetworkConfig {
     #[serde(skip)]
     pub seed_peers: SeedPeersConfig,
     pub seed_peers_file: PathBuf,
+    // Per peer rate limits of direct-send protocols. Protocols without a rate limit are
+    // unlimited.
+    pub rate_limits: Vec<RateLimitConfig>,
+    // Penalties and ban thresholds used to score misbehaving peers.
+    pub peer_scores: PeerScoreConfig,
+    // The policy used by the health checker to decide when to disconnect from a peer.
+    pub health_policy: HealthPolicyConfig,
     pub network_keypairs: Option<NetworkKeyPairs>,
 }
 
@@ -55,17 +84,30 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             peer_id: PeerId::default(),
-            listen_address: "/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap(),
-            advertised_address: "/ip4/127.0.0.1/tcp/6180".parse::<Multiaddr>().unwrap(),
+            listen_addresses: vec!["/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap()],
+            advertised_addresses: vec!["/ip4/127.0.0.1/tcp/6180".parse::<Multiaddr>().unwrap()],
             discovery_interval_ms: 1000,
             connectivity_check_interval_ms: 5000,
             enable_noise: true,
+            enable_quic: false,
             enable_remote_authentication: true,
             network_keypairs: None,
             network_peers_file: PathBuf::new(),
             network_peers: NetworkPeersConfig::default(),
             seed_peers_file: PathBuf::new(),
             seed_peers: SeedPeersConfig::default(),
+            rate_limits: vec![
+                RateLimitConfig::new(
+                    MEMPOOL_DIRECT_SEND_PROTOCOL,
+                    TokenBucketConfig::new(200, 4 * 1024 * 1024),
+                ),
+                RateLimitConfig::new(
+                    STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL,
+                    TokenBucketConfig::new(100, 32 * 1024 * 1024),
+                ),
+            ],
+            peer_scores: PeerScoreConfig::default(),
+            health_policy: HealthPolicyConfig::default(),
         }
     }
 }
@@ -76,17 +118,21 @@ impl NetworkConfig {
     pub fn clone_for_template(&self) -> Self {
         Self {
             peer_id: self.peer_id,
-            listen_address: self.listen_address.clone(),
-            advertised_address: self.advertised_address.clone(),
+            listen_addresses: self.listen_addresses.clone(),
+            advertised_addresses: self.advertised_addresses.clone(),
             discovery_interval_ms: self.discovery_interval_ms,
             connectivity_check_interval_ms: self.connectivity_check_interval_ms,
             enable_noise: self.enable_noise,
+            enable_quic: self.enable_quic,
             enable_remote_authentication: self.enable_remote_authentication,
             network_keypairs: None,
             network_peers_file: self.network_peers_file.clone(),
             network_peers: self.network_peers.clone(),
             seed_peers_file: self.seed_peers_file.clone(),
             seed_peers: self.seed_peers.clone(),
+            rate_limits: self.rate_limits.clone(),
+            peer_scores: self.peer_scores.clone(),
+            health_policy: self.health_policy.clone(),
         }
     }
 
@@ -99,18 +145,61 @@ impl NetworkConfig {
             let path = root_dir.full_path(&self.seed_peers_file);
             self.seed_peers = SeedPeersConfig::load_config(&path)?;
         }
-        if self.advertised_address.to_string().is_empty() {
-            self.advertised_address =
-                utils::get_local_ip().ok_or_else(|| anyhow!("No local IP"))?;
+        ensure!(
+            !self.listen_addresses.is_empty(),
+            "At least one listen address must be defined",
+        );
+        for address in self
+            .listen_addresses
+            .iter_mut()
+            .chain(self.advertised_addresses.iter_mut())
+        {
+            if address.to_string().is_empty() {
+                *address = utils::get_local_ip().ok_or_else(|| anyhow!("No local IP"))?;
+            }
         }
-        if self.listen_address.to_string().is_empty() {
-            self.listen_address = utils::get_local_ip().ok_or_else(|| anyhow!("No local IP"))?;
+        if self.advertised_addresses.is_empty() {
+            self.advertised_addresses = self.listen_addresses.clone();
+        }
+
+        let mut rate_limited_protocols = HashSet::new();
+        for rate_limit in &self.rate_limits {
+            ensure!(
+                rate_limited_protocols.insert(&rate_limit.protocol),
+                "Duplicate rate limit for protocol: {}",
+                rate_limit.protocol,
+            );
+        }
+
+        ensure!(
+            self.peer_scores.ban_threshold < 0.0,
+            "The peer score ban threshold must be negative, as scores start at zero.",
+        );
+
+        match self.health_policy {
+            HealthPolicyConfig::SlidingWindow {
+                window,
+                max_failures,
+            } => ensure!(
+                max_failures < window,
+                "The health policy's max_failures must be less than its window.",
+            ),
+            HealthPolicyConfig::LatencyThreshold { window, .. } => {
+                ensure!(window > 0, "The health policy's window must be positive.",)
+            }
+            HealthPolicyConfig::ConsecutiveFailures { .. } => (),
         }
 
         if self.enable_remote_authentication {
             ensure!(
-                self.enable_noise,
-                "For a node to enforce remote authentication, noise must be enabled.",
+                self.enable_noise || self.enable_quic,
+                "For a node to enforce remote authentication, noise or QUIC must be enabled.",
+            );
+        }
+        if self.enable_quic {
+            ensure!(
+                self.enable_remote_authentication,
+                "QUIC is only supported with remote authentication.",
             );
         }
 
@@ -185,6 +274,99 @@ impl NetworkConfig {
     }
 }
 
+// Rate limits of a single direct-send protocol, enforced separately for every peer.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub protocol: String,
+    // Limits on the traffic a peer sends us. Messages in excess are dropped.
+    pub inbound: TokenBucketConfig,
+    // Limits on the traffic we send to a peer. Messages in excess are delayed.
+    pub outbound: TokenBucketConfig,
+}
+
+impl RateLimitConfig {
+    // Applies the same limits to inbound and outbound traffic, so that a peer sending at our
+    // outbound rate does not exceed its inbound limits.
+    pub fn new(protocol: &str, limits: TokenBucketConfig) -> Self {
+        Self {
+            protocol: protocol.to_string(),
+            inbound: limits.clone(),
+            outbound: limits,
+        }
+    }
+}
+
+// Each misbehavior of a peer lowers its score by a fixed penalty, and scores recover over time.
+// Peers whose score drops to the ban threshold are banned for a cool-down period.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerScoreConfig {
+    // Penalty for a message which fails to parse or verify.
+    pub invalid_message_penalty: f64,
+    // Penalty for a failed liveness probe.
+    pub ping_failure_penalty: f64,
+    // Penalty for an rpc request which was not answered in time.
+    pub rpc_timeout_penalty: f64,
+    // Score regained per second, up to the initial score of zero.
+    pub recovery_per_sec: f64,
+    // Peers whose score drops to this value or below are banned.
+    pub ban_threshold: f64,
+    pub ban_duration_ms: u64,
+}
+
+impl Default for PeerScoreConfig {
+    fn default() -> Self {
+        Self {
+            invalid_message_penalty: 50.0,
+            ping_failure_penalty: 10.0,
+            rpc_timeout_penalty: 5.0,
+            recovery_per_sec: 0.1,
+            ban_threshold: -100.0,
+            ban_duration_ms: 5 * 60 * 1000,
+        }
+    }
+}
+
+/// Defines how the health checker decides, from the outcomes of its pings, that a peer is
+/// unhealthy and should be disconnected
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum HealthPolicyConfig {
+    /// A peer is unhealthy after more than `tolerated` successive ping failures
+    ConsecutiveFailures { tolerated: u64 },
+    /// A peer is unhealthy once more than `max_failures` of its last `window` pings failed
+    SlidingWindow { window: usize, max_failures: usize },
+    /// A peer is unhealthy once each of its last `window` pings either failed or took longer than
+    /// `threshold_ms`
+    LatencyThreshold { threshold_ms: u64, window: usize },
+}
+
+impl Default for HealthPolicyConfig {
+    fn default() -> Self {
+        HealthPolicyConfig::ConsecutiveFailures { tolerated: 10 }
+    }
+}
+
+// A token bucket refilled at a fixed rate, which can hold up to one second worth of tokens. A
+// rate of zero disables the corresponding limit.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenBucketConfig {
+    pub messages_per_sec: u64,
+    pub bytes_per_sec: u64,
+}
+
+impl TokenBucketConfig {
+    pub fn new(messages_per_sec: u64, bytes_per_sec: u64) -> Self {
+        Self {
+            messages_per_sec,
+            bytes_per_sec,
+        }
+    }
+}
+
 // This is separated to another config so that it can be written to its own file
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct SeedPeersConfig {
@@ -241,6 +423,25 @@ pub struct NetworkPeerInfo {
     pub identity_public_key: X25519StaticPublicKey,
 }
 
+/// Accepts a single address as well as a list, as configs written before nodes could listen on
+/// several addresses define `listen_address` and `advertised_address`.
+fn deserialize_addresses<'de, D>(deserializer: D) -> Result<Vec<Multiaddr>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Addresses {
+        Many(Vec<Multiaddr>),
+        One(Multiaddr),
+    }
+
+    Ok(match Addresses::deserialize(deserializer)? {
+        Addresses::Many(addresses) => addresses,
+        Addresses::One(address) => vec![address],
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -353,6 +554,41 @@ mod test {
         assert_eq!(config.peer_id, actual_peer_id);
     }
 
+    #[test]
+    fn test_single_addresses() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            listen_address = "/ip4/0.0.0.0/tcp/6180"
+            advertised_address = ""
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.listen_addresses,
+            vec!["/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap()]
+        );
+        assert_eq!(config.advertised_addresses, vec![Multiaddr::empty()]);
+    }
+
+    #[test]
+    fn test_no_listen_addresses() {
+        let (mut config, path) = generate_config();
+        config.listen_addresses = vec![];
+        let root_dir = RootPath::new_path(path.path());
+        config.load(&root_dir, RoleType::FullNode).unwrap_err();
+    }
+
+    #[test]
+    fn test_invalid_health_policy() {
+        let (mut config, path) = generate_config();
+        config.health_policy = HealthPolicyConfig::SlidingWindow {
+            window: 5,
+            max_failures: 5,
+        };
+        let root_dir = RootPath::new_path(path.path());
+        config.load(&root_dir, RoleType::FullNode).unwrap_err();
+    }
+
     fn generate_config() -> (NetworkConfig, TempPath) {
         let temp_dir = TempPath::new();
         temp_dir.create_as_dir().expect("error creating tempdir");
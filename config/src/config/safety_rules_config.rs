@@ -1,8 +1,17 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use anyhow::{ensure, Result};
+use libra_crypto::x25519::{X25519StaticPrivateKey, X25519StaticPublicKey};
+#[cfg(any(test, feature = "fuzzing"))]
+use libra_crypto::{PrivateKey, Uniform};
+#[cfg(any(test, feature = "fuzzing"))]
+use rand::rngs::StdRng;
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -22,8 +31,39 @@ impl Default for SafetyRulesConfig {
 
 impl SafetyRulesConfig {
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
-        if let SafetyRulesBackend::OnDiskStorage(backend) = &mut self.backend {
-            backend.set_data_dir(data_dir);
+        match &mut self.backend {
+            SafetyRulesBackend::OnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            SafetyRulesBackend::EncryptedOnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            _ => (),
+        }
+    }
+
+    /// Checks that the remote services have the private key of at least one end of their
+    /// connection, as the consensus and safety rules ends each need their own. The Vault backend
+    /// makes blocking HTTP requests, which can't run on the threads of the consensus runtime:
+    /// it requires safety rules to run on a thread or a process of its own.
+    pub fn verify(&self) -> Result<()> {
+        if let SafetyRulesBackend::Vault(_) = self.backend {
+            ensure!(
+                match self.service {
+                    SafetyRulesService::Local | SafetyRulesService::Serializer => false,
+                    SafetyRulesService::Process(_)
+                    | SafetyRulesService::SpawnedProcess(_)
+                    | SafetyRulesService::Thread => true,
+                },
+                "The Vault backend requires the SafetyRules service to run in a thread or a process"
+            );
+        }
+        match &self.service {
+            SafetyRulesService::Process(service) | SafetyRulesService::SpawnedProcess(service) => {
+                let keys = &service.network_keys;
+                ensure!(
+                    keys.consensus_private_key.is_some() || keys.safety_rules_private_key.is_some(),
+                    "Missing the private network keys of the SafetyRules service"
+                );
+                Ok(())
+            }
+            _ => Ok(()),
         }
     }
 }
@@ -34,6 +74,8 @@ impl SafetyRulesConfig {
 pub enum SafetyRulesBackend {
     InMemoryStorage,
     OnDiskStorage(OnDiskStorageConfig),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig),
+    Vault(VaultConfig),
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -71,6 +113,66 @@ impl OnDiskStorageConfig {
     }
 }
 
+/// Stores the SafetyRules state in a file encrypted with a key read from key_file, see
+/// libra_secure_storage::EncryptedOnDiskStorage.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EncryptedOnDiskStorageConfig {
+    // In testing scenarios this implies that the default state is okay if
+    // a state is not specified.
+    pub default: bool,
+    // Required path for on disk storage
+    pub path: PathBuf,
+    // Required path for the file holding the hex encoded unlock key of SafetyRules
+    pub key_file: PathBuf,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+impl Default for EncryptedOnDiskStorageConfig {
+    fn default() -> Self {
+        Self {
+            default: false,
+            path: PathBuf::from("safety_rules.enc"),
+            key_file: PathBuf::from("safety_rules.key"),
+            data_dir: PathBuf::from("/opt/libra/data/common"),
+        }
+    }
+}
+
+impl EncryptedOnDiskStorageConfig {
+    pub fn path(&self) -> PathBuf {
+        self.resolve(&self.path)
+    }
+
+    pub fn key_file(&self) -> PathBuf {
+        self.resolve(&self.key_file)
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            self.data_dir.join(path)
+        } else {
+            path.to_path_buf()
+        }
+    }
+}
+
+/// Stores the SafetyRules state in a Vault server, see libra_secure_storage::VaultStorage.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct VaultConfig {
+    // In testing scenarios this implies that the default state is okay if
+    // a state is not specified.
+    pub default: bool,
+    // The address of the Vault server, e.g. http://127.0.0.1:8200
+    pub server: String,
+    // The token used to authenticate SafetyRules to Vault
+    pub token: String,
+}
+
 /// Defines how safety rules should be executed
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(tag = "type")]
@@ -94,6 +196,47 @@ pub enum SafetyRulesService {
 pub struct RemoteService {
     pub server_address: SocketAddr,
     pub consensus_type: ConsensusType,
+    // Consensus and safety rules authenticate each other with Noise and encrypt their traffic,
+    // connections presenting any other key are rejected.
+    pub network_keys: RemoteServiceKeys,
+}
+
+/// The static keys of the two ends of the connection between consensus and safety rules. Each end
+/// only needs its own private key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RemoteServiceKeys {
+    pub consensus_private_key: Option<X25519StaticPrivateKey>,
+    pub consensus_public_key: X25519StaticPublicKey,
+    pub safety_rules_private_key: Option<X25519StaticPrivateKey>,
+    pub safety_rules_public_key: X25519StaticPublicKey,
+}
+
+impl RemoteServiceKeys {
+    /// Generates the keys of both ends from the entropy of the system.
+    pub fn generate() -> Self {
+        let (consensus_private_key, consensus_public_key) =
+            X25519StaticPrivateKey::generate_keypair_hybrid(None, &[], None);
+        let (safety_rules_private_key, safety_rules_public_key) =
+            X25519StaticPrivateKey::generate_keypair_hybrid(None, &[], None);
+        Self {
+            consensus_private_key: Some(consensus_private_key),
+            consensus_public_key,
+            safety_rules_private_key: Some(safety_rules_private_key),
+            safety_rules_public_key,
+        }
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn random(rng: &mut StdRng) -> Self {
+        let consensus_private_key = X25519StaticPrivateKey::generate_for_testing(rng);
+        let safety_rules_private_key = X25519StaticPrivateKey::generate_for_testing(rng);
+        Self {
+            consensus_public_key: consensus_private_key.public_key(),
+            consensus_private_key: Some(consensus_private_key),
+            safety_rules_public_key: safety_rules_private_key.public_key(),
+            safety_rules_private_key: Some(safety_rules_private_key),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
@@ -102,3 +245,51 @@ pub enum ConsensusType {
     Bytes,
     Rounds,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn verify_remote_service_keys() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut service = RemoteService {
+            server_address: "127.0.0.1:8888".parse().unwrap(),
+            consensus_type: ConsensusType::Rounds,
+            network_keys: RemoteServiceKeys::random(&mut rng),
+        };
+        let mut config = SafetyRulesConfig::default();
+        config.service = SafetyRulesService::Process(service.clone());
+        assert!(config.verify().is_ok());
+
+        // Each end only needs its own private key.
+        service.network_keys.consensus_private_key = None;
+        config.service = SafetyRulesService::Process(service.clone());
+        assert!(config.verify().is_ok());
+
+        service.network_keys.safety_rules_private_key = None;
+        config.service = SafetyRulesService::Process(service);
+        assert!(config.verify().is_err());
+
+        // The keys are required.
+        let serialized = "type = \"process\"\nserver_address = \"127.0.0.1:8888\"\n\
+                          consensus_type = \"Rounds\"\n";
+        assert!(toml::from_str::<SafetyRulesService>(serialized).is_err());
+    }
+
+    #[test]
+    fn verify_vault_service() {
+        let mut config = SafetyRulesConfig::default();
+        config.backend = SafetyRulesBackend::Vault(VaultConfig {
+            default: false,
+            server: "http://127.0.0.1:8200".to_string(),
+            token: "root_token".to_string(),
+        });
+        assert!(config.verify().is_err());
+        config.service = SafetyRulesService::Serializer;
+        assert!(config.verify().is_err());
+        config.service = SafetyRulesService::Thread;
+        assert!(config.verify().is_ok());
+    }
+}
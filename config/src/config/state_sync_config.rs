@@ -3,6 +3,7 @@
 
 use libra_types::PeerId;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -17,6 +18,17 @@ pub struct StateSyncConfig {
     pub max_chunk_limit: u64,
     // valid maximum timeout limit for sanity check
     pub max_timeout_ms: u64,
+    // maximum number of chunk requests in flight at the same time, each to a different peer
+    // if possible, when catching up within the current epoch
+    pub max_in_flight_chunk_requests: usize,
+    // full nodes only: fetch the outputs (write sets and events) along with the transactions and
+    // apply them without re-execution, trusting the signers of the verified ledger infos
+    pub apply_transaction_outputs: bool,
+    // raise the stall alarm if no progress is made for this long while behind the sync target
+    pub stall_alarm_timeout_ms: u64,
+    // manifest of a transaction backup in a local directory (as written by the backup tool): the
+    // node syncs from the backup first and from the network for the versions it doesn't cover
+    pub backup_manifest: Option<PathBuf>,
     // List of peers to use as upstream in state sync protocols.
     #[serde(flatten)]
     pub upstream_peers: UpstreamPeersConfig,
@@ -30,6 +42,10 @@ impl Default for StateSyncConfig {
             long_poll_timeout_ms: 30000,
             max_chunk_limit: 1000,
             max_timeout_ms: 120_000,
+            max_in_flight_chunk_requests: 4,
+            apply_transaction_outputs: false,
+            stall_alarm_timeout_ms: 60_000,
+            backup_manifest: None,
             upstream_peers: UpstreamPeersConfig::default(),
         }
     }
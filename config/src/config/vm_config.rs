@@ -58,8 +58,8 @@ impl VMConfig {
 #[serde(tag = "type", content = "whitelist")]
 pub enum VMPublishingOption {
     /// Only allow scripts on a whitelist to be run
-    #[serde(deserialize_with = "deserialize_whitelist")]
-    #[serde(serialize_with = "serialize_whitelist")]
+    #[serde(deserialize_with = "deserialize_script_hashes")]
+    #[serde(serialize_with = "serialize_script_hashes")]
     Locked(HashSet<[u8; SCRIPT_HASH_LENGTH]>),
     /// Allow custom scripts, but _not_ custom module publishing
     CustomScripts,
@@ -92,7 +92,7 @@ fn string_to_script_hash(input: &str) -> [u8; SCRIPT_HASH_LENGTH] {
     hash
 }
 
-fn deserialize_whitelist<'de, D>(
+pub(crate) fn deserialize_script_hashes<'de, D>(
     deserializer: D,
 ) -> Result<HashSet<[u8; SCRIPT_HASH_LENGTH]>, D::Error>
 where
@@ -106,7 +106,7 @@ where
     Ok(whitelist)
 }
 
-fn serialize_whitelist<S, H>(
+pub(crate) fn serialize_script_hashes<S, H>(
     whitelist: &HashSet<[u8; SCRIPT_HASH_LENGTH], H>,
     serializer: S,
 ) -> Result<S::Ok, S::Error>
@@ -39,8 +39,8 @@ pub fn validator_swarm(
         node.consensus.safety_rules.backend = SafetyRulesBackend::OnDiskStorage(storage_config);
 
         let network = node.validator_network.as_mut().unwrap();
-        network.listen_address = utils::get_available_port_in_multiaddr(true);
-        network.advertised_address = network.listen_address.clone();
+        network.listen_addresses = vec![utils::get_available_port_in_multiaddr(true)];
+        network.advertised_addresses = network.listen_addresses.clone();
 
         let test = node.test.as_ref().unwrap();
         let consensus_pubkey = test.consensus_keypair.as_ref().unwrap().public().clone();
@@ -64,7 +64,7 @@ pub fn validator_swarm(
     let network = nodes[0].validator_network.as_ref().unwrap();
     seed_peers
         .seed_peers
-        .insert(network.peer_id, vec![network.listen_address.clone()]);
+        .insert(network.peer_id, network.listen_addresses.clone());
 
     for node in &mut nodes {
         let network = node.validator_network.as_mut().unwrap();
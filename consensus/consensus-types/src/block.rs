@@ -213,15 +213,21 @@ where
         block_data: BlockData<T>,
         validator_signer: &ValidatorSigner,
     ) -> Self {
-        let id = block_data.hash();
         let signature = validator_signer
-            .sign_message(id)
+            .sign_message(block_data.hash())
             .expect("Failed to sign message");
 
+        Self::new_proposal_from_block_data_and_signature(block_data, signature.into())
+    }
+
+    pub fn new_proposal_from_block_data_and_signature(
+        block_data: BlockData<T>,
+        signature: Signature,
+    ) -> Self {
         Block {
-            id,
+            id: block_data.hash(),
             block_data,
-            signature: Some(signature.into()),
+            signature: Some(signature),
         }
     }
 
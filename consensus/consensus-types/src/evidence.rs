@@ -0,0 +1,243 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::Block,
+    common::{Author, Round},
+    quorum_cert::QuorumCert,
+    vote::Vote,
+};
+use anyhow::{ensure, format_err, Context};
+use libra_crypto::HashValue;
+use libra_types::crypto_proxies::{ValidatorSet, ValidatorVerifier};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+#[path = "evidence_test.rs"]
+mod evidence_test;
+
+/// Evidence is a self-contained proof that a validator misbehaved in consensus.
+/// Every variant carries the conflicting signed messages, so that the evidence can be checked
+/// by anyone knowing the validator set of the epoch, independently of the node that collected it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = "Block<T>: Deserialize<'de>"))]
+pub enum Evidence<T> {
+    /// The author signed two votes carrying different LedgerInfos for the same round.
+    DoubleVote { first: Vote, second: Vote },
+    /// The author signed two different proposals for the same round.
+    DoubleProposal { first: Block<T>, second: Block<T> },
+    /// The author voted for a block whose parent round is lower than its preferred round.
+    /// The preferred round is proven by `locking_qc`: the QC carried by the block voted
+    /// in `locking_vote`, which the author must have seen (and adopted) before voting.
+    PreferredRoundViolation {
+        locking_vote: Vote,
+        locking_qc: QuorumCert,
+        violating_vote: Vote,
+    },
+}
+
+impl<T> Display for Evidence<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Evidence: [kind: {}, author: {}, epoch: {}, round: {}]",
+            self.kind(),
+            self.author().short_str(),
+            self.epoch(),
+            self.round(),
+        )
+    }
+}
+
+impl<T> Evidence<T> {
+    /// A short name of the misbehavior, used for logging and metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Evidence::DoubleVote { .. } => "double_vote",
+            Evidence::DoubleProposal { .. } => "double_proposal",
+            Evidence::PreferredRoundViolation { .. } => "preferred_round_violation",
+        }
+    }
+
+    /// The validator that misbehaved.
+    pub fn author(&self) -> Author {
+        match self {
+            Evidence::DoubleVote { first, .. } => first.author(),
+            Evidence::DoubleProposal { first, .. } => first
+                .author()
+                .expect("Proposals in evidence always have an author"),
+            Evidence::PreferredRoundViolation { violating_vote, .. } => violating_vote.author(),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        match self {
+            Evidence::DoubleVote { first, .. } => first.epoch(),
+            Evidence::DoubleProposal { first, .. } => first.epoch(),
+            Evidence::PreferredRoundViolation { violating_vote, .. } => violating_vote.epoch(),
+        }
+    }
+
+    /// The round in which the misbehavior happened.
+    pub fn round(&self) -> Round {
+        match self {
+            Evidence::DoubleVote { first, .. } => first.vote_data().proposed().round(),
+            Evidence::DoubleProposal { first, .. } => first.round(),
+            Evidence::PreferredRoundViolation { violating_vote, .. } => {
+                violating_vote.vote_data().proposed().round()
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Serialize> Evidence<T> {
+    /// Unique identifier of the evidence: the hash of its canonical serialization.
+    pub fn id(&self) -> HashValue {
+        HashValue::from_sha3_256(&lcs::to_bytes(self).expect("Should serialize."))
+    }
+
+    /// Verifies that the messages are correctly signed by the same author and that they indeed
+    /// conflict with each other.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        match self {
+            Evidence::DoubleVote { first, second } => {
+                ensure!(
+                    first.author() == second.author(),
+                    "Double vote evidence has votes from different authors"
+                );
+                ensure!(
+                    first.epoch() == second.epoch()
+                        && first.vote_data().proposed().round()
+                            == second.vote_data().proposed().round(),
+                    "Double vote evidence has votes for different rounds"
+                );
+                ensure!(
+                    first.ledger_info() != second.ledger_info(),
+                    "Double vote evidence has votes for the same LedgerInfo"
+                );
+                first
+                    .verify(validator)
+                    .context("Fail to verify first vote")?;
+                second
+                    .verify(validator)
+                    .context("Fail to verify second vote")?;
+            }
+            Evidence::DoubleProposal { first, second } => {
+                let author = first
+                    .author()
+                    .ok_or_else(|| format_err!("Double proposal evidence has no author"))?;
+                ensure!(
+                    second.author() == Some(author),
+                    "Double proposal evidence has proposals from different authors"
+                );
+                ensure!(
+                    first.epoch() == second.epoch() && first.round() == second.round(),
+                    "Double proposal evidence has proposals for different rounds"
+                );
+                ensure!(
+                    first.id() != second.id(),
+                    "Double proposal evidence has identical proposals"
+                );
+                first
+                    .validate_signatures(validator)
+                    .context("Fail to verify first proposal")?;
+                second
+                    .validate_signatures(validator)
+                    .context("Fail to verify second proposal")?;
+            }
+            Evidence::PreferredRoundViolation {
+                locking_vote,
+                locking_qc,
+                violating_vote,
+            } => {
+                ensure!(
+                    locking_vote.author() == violating_vote.author(),
+                    "Preferred round evidence has votes from different authors"
+                );
+                ensure!(
+                    locking_vote.epoch() == violating_vote.epoch()
+                        && locking_qc.certified_block().epoch() == locking_vote.epoch(),
+                    "Preferred round evidence spans multiple epochs"
+                );
+                ensure!(
+                    locking_qc.certified_block().id() == locking_vote.vote_data().parent().id(),
+                    "Preferred round evidence has a QC not carried by the locking vote's block"
+                );
+                ensure!(
+                    locking_vote.vote_data().proposed().round()
+                        < violating_vote.vote_data().proposed().round(),
+                    "Preferred round evidence has the violating vote before the locking vote"
+                );
+                ensure!(
+                    violating_vote.vote_data().parent().round() < locking_qc.parent_block().round(),
+                    "Preferred round evidence does not violate the preferred round"
+                );
+                locking_qc
+                    .verify(validator)
+                    .context("Fail to verify locking QC")?;
+                locking_vote
+                    .verify(validator)
+                    .context("Fail to verify locking vote")?;
+                violating_vote
+                    .verify(validator)
+                    .context("Fail to verify violating vote")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// EvidenceBundle is the exportable form of the evidence collected within a single epoch.
+/// It carries the validator set of that epoch, so that it can be verified offline (e.g. by
+/// governance) without access to the node that collected it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = "Evidence<T>: Deserialize<'de>"))]
+pub struct EvidenceBundle<T> {
+    epoch: u64,
+    validator_set: ValidatorSet,
+    evidence: Vec<Evidence<T>>,
+}
+
+impl<T: DeserializeOwned + Serialize> EvidenceBundle<T> {
+    /// Creates a bundle out of all the pieces of evidence that belong to the given epoch.
+    pub fn new(epoch: u64, validator_set: ValidatorSet, evidence: Vec<Evidence<T>>) -> Self {
+        Self {
+            epoch,
+            validator_set,
+            evidence: evidence
+                .into_iter()
+                .filter(|e| e.epoch() == epoch)
+                .collect(),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn validator_set(&self) -> &ValidatorSet {
+        &self.validator_set
+    }
+
+    pub fn evidence(&self) -> &[Evidence<T>] {
+        &self.evidence
+    }
+
+    /// Verifies every piece of evidence against the validator set of the bundle.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let validator: ValidatorVerifier = (&self.validator_set).into();
+        for evidence in &self.evidence {
+            ensure!(
+                evidence.epoch() == self.epoch,
+                "{} does not belong to epoch {}",
+                evidence,
+                self.epoch
+            );
+            evidence
+                .verify(&validator)
+                .with_context(|| format!("Fail to verify {}", evidence))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::{block_test_utils::*, Block},
+    common::Round,
+    evidence::Evidence,
+    vote::Vote,
+    vote_data::VoteData,
+};
+use libra_crypto::HashValue;
+use libra_types::{
+    block_info::BlockInfo,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+};
+
+fn random_ledger_info() -> LedgerInfo {
+    LedgerInfo::new(
+        BlockInfo::new(1, 0, HashValue::random(), HashValue::random(), 0, 0, None),
+        HashValue::zero(),
+    )
+}
+
+fn vote_for(signer: &ValidatorSigner, round: Round, parent_round: Round) -> Vote {
+    Vote::new(
+        VoteData::new(BlockInfo::random(round), BlockInfo::random(parent_round)),
+        signer.author(),
+        random_ledger_info(),
+        signer,
+    )
+}
+
+#[test]
+fn test_double_vote() {
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+
+    let first = vote_for(&signer, 2, 1);
+    let second = vote_for(&signer, 2, 1);
+    let evidence = Evidence::<u64>::DoubleVote {
+        first: first.clone(),
+        second,
+    };
+    assert_eq!(evidence.author(), signer.author());
+    assert_eq!(evidence.round(), 2);
+    assert!(evidence.verify(&validator).is_ok());
+
+    // The same vote twice is not an equivocation.
+    let evidence = Evidence::<u64>::DoubleVote {
+        first: first.clone(),
+        second: first,
+    };
+    assert!(evidence.verify(&validator).is_err());
+
+    // Votes for different rounds are not an equivocation.
+    let evidence = Evidence::<u64>::DoubleVote {
+        first: vote_for(&signer, 2, 1),
+        second: vote_for(&signer, 3, 2),
+    };
+    assert!(evidence.verify(&validator).is_err());
+}
+
+#[test]
+fn test_double_proposal() {
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let genesis_qc = certificate_for_genesis();
+
+    let first = Block::new_proposal(1, 1, 1, genesis_qc.clone(), &signer);
+    let second = Block::new_proposal(2, 1, 1, genesis_qc.clone(), &signer);
+    let evidence = Evidence::DoubleProposal {
+        first: first.clone(),
+        second,
+    };
+    assert!(evidence.verify(&validator).is_ok());
+
+    let other_round = Block::new_proposal(2, 2, 1, genesis_qc, &signer);
+    let evidence = Evidence::DoubleProposal {
+        first,
+        second: other_round,
+    };
+    assert!(evidence.verify(&validator).is_err());
+}
+
+#[test]
+fn test_preferred_round_violation() {
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+
+    // The QC certifies a block at round 3 whose parent is at round 2: voting on top of it sets
+    // the preferred round to 2.
+    let locking_qc = gen_test_certificate(
+        vec![&signer],
+        BlockInfo::random(3),
+        BlockInfo::random(2),
+        None,
+    );
+    let locking_vote = Vote::new(
+        VoteData::new(BlockInfo::random(4), locking_qc.certified_block().clone()),
+        signer.author(),
+        random_ledger_info(),
+        &signer,
+    );
+
+    // Voting in round 5 for a block extending round 1 breaks the preferred round rule.
+    let evidence = Evidence::<u64>::PreferredRoundViolation {
+        locking_vote: locking_vote.clone(),
+        locking_qc: locking_qc.clone(),
+        violating_vote: vote_for(&signer, 5, 1),
+    };
+    assert!(evidence.verify(&validator).is_ok());
+
+    // Voting in round 5 for a block extending round 2 is fine.
+    let evidence = Evidence::<u64>::PreferredRoundViolation {
+        locking_vote,
+        locking_qc,
+        violating_vote: vote_for(&signer, 5, 2),
+    };
+    assert!(evidence.verify(&validator).is_err());
+}
@@ -55,11 +55,22 @@ impl Vote {
         let li_sig = validator_signer
             .sign_message(ledger_info_placeholder.hash())
             .expect("Failed to sign LedgerInfo");
+        Self::new_with_signature(vote_data, author, ledger_info_placeholder, li_sig.into())
+    }
+
+    /// Generates a new Vote using a signature over the specified ledger_info, whose consensus
+    /// data hash must already be set to the hash of vote_data.
+    pub fn new_with_signature(
+        vote_data: VoteData,
+        author: Author,
+        ledger_info: LedgerInfo,
+        signature: Signature,
+    ) -> Self {
         Self {
             vote_data,
             author,
-            ledger_info: ledger_info_placeholder,
-            signature: li_sig.into(),
+            ledger_info,
+            signature,
             timeout_signature: None,
         }
     }
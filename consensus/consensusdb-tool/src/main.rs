@@ -0,0 +1,254 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::{ensure, format_err, Result};
+use consensus::consensusdb_inspector::{ConsensusDBInspector, NodePayload, RecoveredRoot};
+use consensus_types::evidence::EvidenceBundle;
+use libra_config::config::{NodeConfig, SafetyRulesBackend};
+use libra_crypto::HashValue;
+use libra_secure_storage::{
+    EncryptedOnDiskStorage, OnDiskStorage, Storage, UnlockKey, VaultStorage,
+};
+use libra_types::{crypto_proxies::ValidatorSet, ledger_info::LedgerInfo};
+use safety_rules::{ConsensusState, PersistentStorage, SAFETY_RULES_ID};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use storage_client::{StorageRead, StorageReadServiceClient};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ConsensusDB Tool")]
+/// Tool to inspect and repair the consensus state persisted by a validator.
+///
+/// The ConsensusDB is opened read-only so that a running node can be inspected, except for
+/// `prune-to-root --execute` which requires the node to be stopped.
+struct Args {
+    #[structopt(short = "f", long, parse(from_os_str))]
+    /// Path to the NodeConfig of the validator
+    config: PathBuf,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the blocks, the quorum certs, the last vote, the highest timeout certificate and the
+    /// SafetyRules state as JSON
+    Dump,
+    /// Print the root consensus recovers from at startup, using the latest ledger info from the
+    /// storage service of the node
+    Root,
+    /// Delete the blocks that do not descend from the root, only lists them unless --execute is
+    /// set
+    PruneToRoot {
+        #[structopt(long)]
+        /// Id of the root block in hex, the root recovered using the storage service by default
+        root: Option<String>,
+        #[structopt(long)]
+        /// Delete the blocks, the node must be stopped
+        execute: bool,
+    },
+    /// Write the evidence of misbehaving validators collected in an epoch to a file, along with
+    /// the validator set of the epoch read from the storage service of the node
+    ExportEvidence {
+        #[structopt(long)]
+        epoch: u64,
+        #[structopt(long, parse(from_os_str))]
+        /// File the LCS-serialized evidence bundle is written to
+        output: PathBuf,
+    },
+    /// Verify an evidence bundle written by export-evidence and print its content
+    VerifyEvidence {
+        #[structopt(long, parse(from_os_str))]
+        input: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::from_args();
+    if let Command::VerifyEvidence { input } = &args.command {
+        let bundle: EvidenceBundle<NodePayload> = lcs::from_bytes(&std::fs::read(input)?)?;
+        bundle.verify()?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&bundle_to_json(&bundle)?)?
+        );
+        return Ok(());
+    }
+    let config = NodeConfig::load(&args.config)?;
+
+    let output = match args.command {
+        Command::Dump => dump(&config)?,
+        Command::Root => {
+            let inspector = ConsensusDBInspector::open_readonly(&config)?;
+            root_to_json(&inspector.find_root(&storage_ledger_info(&config)?)?)
+        }
+        Command::PruneToRoot { root, execute } => {
+            let root_id = match root {
+                Some(root) => HashValue::from_hex(&root)?,
+                None => {
+                    let inspector = ConsensusDBInspector::open_readonly(&config)?;
+                    inspector
+                        .find_root(&storage_ledger_info(&config)?)?
+                        .block
+                        .id()
+                }
+            };
+            let block_ids = if execute {
+                ConsensusDBInspector::open(&config).prune_to_root(root_id)?
+            } else {
+                ConsensusDBInspector::open_readonly(&config)?.blocks_to_prune(root_id)?
+            };
+            json!({
+                "root": root_id.to_hex(),
+                "pruned": execute,
+                "blocks": block_ids.iter().map(HashValue::to_hex).collect::<Vec<_>>(),
+            })
+        }
+        Command::ExportEvidence { epoch, output } => {
+            let validator_set = storage_validator_set(&config, epoch)?;
+            let bundle = ConsensusDBInspector::open_readonly(&config)?
+                .export_evidence(epoch, validator_set)?;
+            std::fs::write(&output, lcs::to_bytes(&bundle)?)?;
+            bundle_to_json(&bundle)?
+        }
+        Command::VerifyEvidence { .. } => unreachable!("Handled without a NodeConfig"),
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn dump(config: &NodeConfig) -> Result<Value> {
+    let data = ConsensusDBInspector::open_readonly(config)?.persisted_data()?;
+    let blocks = data
+        .blocks
+        .iter()
+        .map(|block| {
+            Ok(json!({
+                "id": block.id().to_hex(),
+                "parent_id": block.parent_id().to_hex(),
+                "epoch": block.epoch(),
+                "round": block.round(),
+                "author": block.author().map(|author| author.to_string()),
+                "payload_size": block.payload().map_or(0, NodePayload::len),
+                "block": serde_json::to_value(block)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let quorum_certs = data
+        .quorum_certs
+        .iter()
+        .map(|qc| {
+            Ok(json!({
+                "certified_block_id": qc.certified_block().id().to_hex(),
+                "certified_round": qc.certified_block().round(),
+                "committed_block_id": qc.commit_info().id().to_hex(),
+                "quorum_cert": serde_json::to_value(qc)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({
+        "blocks": blocks,
+        "quorum_certs": quorum_certs,
+        "last_vote": serde_json::to_value(&data.last_vote)?,
+        "highest_timeout_certificate": serde_json::to_value(&data.highest_timeout_certificate)?,
+        "safety_rules": serde_json::to_value(&safety_rules_state(config)?)?,
+    }))
+}
+
+fn root_to_json(root: &RecoveredRoot) -> Value {
+    json!({
+        "id": root.block.id().to_hex(),
+        "epoch": root.block.epoch(),
+        "round": root.block.round(),
+        "certified_by_round": root.quorum_cert.certified_block().round(),
+        "ledger_info_version": root.ledger_info.commit_info().version(),
+    })
+}
+
+/// Reads the latest ledger info committed in storage, through the storage service of the node.
+fn storage_ledger_info(config: &NodeConfig) -> Result<LedgerInfo> {
+    let mut runtime = tokio::runtime::Runtime::new()?;
+    let startup_info = runtime.block_on(async {
+        let client = StorageReadServiceClient::new(&config.storage.address);
+        client.get_startup_info().await
+    })?;
+    startup_info
+        .map(|startup_info| startup_info.latest_ledger_info.ledger_info().clone())
+        .ok_or_else(|| format_err!("Storage is not bootstrapped"))
+}
+
+fn bundle_to_json(bundle: &EvidenceBundle<NodePayload>) -> Result<Value> {
+    let evidence = bundle
+        .evidence()
+        .iter()
+        .map(|evidence| {
+            Ok(json!({
+                "id": evidence.id().to_hex(),
+                "kind": evidence.kind(),
+                "author": evidence.author().to_string(),
+                "round": evidence.round(),
+                "evidence": serde_json::to_value(evidence)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({
+        "epoch": bundle.epoch(),
+        "validators": bundle.validator_set().len(),
+        "evidence": evidence,
+    }))
+}
+
+/// Reads the validator set of the given epoch, carried by the ledger info ending the previous
+/// epoch, through the storage service of the node.
+fn storage_validator_set(config: &NodeConfig, epoch: u64) -> Result<ValidatorSet> {
+    ensure!(epoch > 0, "Epoch 0 only has the genesis block");
+    let mut runtime = tokio::runtime::Runtime::new()?;
+    let proof = runtime.block_on(async {
+        let client = StorageReadServiceClient::new(&config.storage.address);
+        client.get_epoch_change_ledger_infos(epoch - 1, epoch).await
+    })?;
+    proof
+        .ledger_info_with_sigs
+        .first()
+        .and_then(|li| li.ledger_info().next_validator_set().cloned())
+        .ok_or_else(|| format_err!("No validator set found for epoch {}", epoch))
+}
+
+/// Reads the SafetyRules state, None when the node keeps it in memory.
+fn safety_rules_state(config: &NodeConfig) -> Result<Option<ConsensusState>> {
+    let internal_storage: Box<dyn Storage> = match &config.consensus.safety_rules.backend {
+        SafetyRulesBackend::InMemoryStorage => return Ok(None),
+        SafetyRulesBackend::OnDiskStorage(on_disk_config) => {
+            let path = on_disk_config.path();
+            if !path.exists() {
+                return Err(format_err!("No SafetyRules storage found at {:?}", path));
+            }
+            Box::new(OnDiskStorage::new(path))
+        }
+        SafetyRulesBackend::EncryptedOnDiskStorage(encrypted_config) => {
+            let path = encrypted_config.path();
+            if !path.exists() {
+                return Err(format_err!("No SafetyRules storage found at {:?}", path));
+            }
+            let unlock_key = UnlockKey::KeyFile(encrypted_config.key_file());
+            Box::new(EncryptedOnDiskStorage::new(
+                path,
+                &unlock_key,
+                SAFETY_RULES_ID.into(),
+            )?)
+        }
+        SafetyRulesBackend::Vault(vault_config) => Box::new(VaultStorage::new(
+            vault_config.server.clone(),
+            vault_config.token.clone(),
+        )),
+    };
+    let storage = PersistentStorage::new(internal_storage);
+    Ok(Some(ConsensusState::new(
+        storage.epoch()?,
+        storage.last_voted_round()?,
+        storage.preferred_round()?,
+    )))
+}
@@ -3,18 +3,23 @@
 
 use anyhow::Result;
 use consensus_types::common::Round;
-use libra_crypto::ed25519::Ed25519PrivateKey;
-use libra_secure_storage::{InMemoryStorage, Permissions, Storage, Value};
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue,
+};
+use libra_secure_storage::{Error, InMemoryStorage, Permission, Permissions, Storage, Value};
 
 /// SafetyRules needs an abstract storage interface to act as a common utility for storing
 /// persistent data to local disk, cloud, secrets managers, or even memory (for tests)
 /// Any set function is expected to sync to the remote system before returning.
-/// @TODO add access to private key from persistent store
-/// @TODO add retrieval of private key based upon public key to persistent store
+/// The consensus key never leaves the data store, which signs on behalf of SafetyRules.
 pub struct PersistentStorage {
     internal_store: Box<dyn Storage>,
 }
 
+/// The Id under which SafetyRules accesses its storage.
+pub const SAFETY_RULES_ID: &str = "safety_rules";
+
 const CONSENSUS_KEY: &str = "consensus_key";
 const EPOCH: &str = "epoch";
 const LAST_VOTED_ROUND: &str = "last_voted_round";
@@ -27,15 +32,28 @@ impl PersistentStorage {
     }
 
     /// Use this to instantiate a PersistentStorage for a new data store, one that has no
-    /// SafetyRules values set.
+    /// SafetyRules values set. Backends that cannot import keys, such as Vault, generate their own
+    /// consensus key instead, in which case the given one is left unused. The values may only be
+    /// read and written by SafetyRules: rolling back the last voted round would allow it to vote
+    /// twice in a round.
     pub fn initialize(
         mut internal_store: Box<dyn Storage>,
         private_key: Ed25519PrivateKey,
     ) -> Self {
-        let perms = Permissions::anyone();
-        internal_store
-            .create_if_not_exists(CONSENSUS_KEY, Value::Ed25519PrivateKey(private_key), &perms)
-            .expect("Unable to initialize backend storage");
+        let perms = Permissions {
+            readers: Permission::Users(vec![SAFETY_RULES_ID.into()]),
+            writers: Permission::Users(vec![SAFETY_RULES_ID.into()]),
+        };
+        let result = match internal_store.import_key(CONSENSUS_KEY, private_key) {
+            Err(Error::KeyImportNotSupported(_)) => {
+                internal_store.generate_key(CONSENSUS_KEY).map(|_| ())
+            }
+            result => result,
+        };
+        match result {
+            Ok(()) | Err(Error::KeyAlreadyExists(_)) => (),
+            Err(e) => panic!("Unable to initialize backend storage: {}", e),
+        }
         internal_store
             .create_if_not_exists(EPOCH, Value::U64(1), &perms)
             .expect("Unable to initialize backend storage");
@@ -54,17 +72,20 @@ impl PersistentStorage {
         Self { internal_store }
     }
 
-    pub fn consensus_key(&self) -> Result<Ed25519PrivateKey> {
+    pub fn consensus_public_key(&self) -> Result<Ed25519PublicKey> {
         Ok(self
             .internal_store
-            .get(CONSENSUS_KEY)
-            .and_then(|value| value.ed25519_private_key())?)
+            .get_public_key(CONSENSUS_KEY)?
+            .public_key)
     }
 
-    pub fn set_consensus_key(&mut self, consensus_key: Ed25519PrivateKey) -> Result<()> {
-        self.internal_store
-            .set(CONSENSUS_KEY, Value::Ed25519PrivateKey(consensus_key))?;
-        Ok(())
+    /// Generates a new consensus key, the previous one is no longer used to sign.
+    pub fn rotate_consensus_key(&mut self) -> Result<Ed25519PublicKey> {
+        Ok(self.internal_store.rotate_key(CONSENSUS_KEY)?)
+    }
+
+    pub fn sign_message(&self, message: &HashValue) -> Result<Ed25519Signature> {
+        Ok(self.internal_store.sign_message(CONSENSUS_KEY, message)?)
     }
 
     pub fn epoch(&self) -> Result<u64> {
@@ -109,14 +130,21 @@ impl PersistentStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use libra_secure_storage::InMemoryStorage;
+    use libra_crypto::Signature;
+    use libra_secure_storage::{mock_vault, InMemoryStorage, VaultStorage};
     use libra_types::crypto_proxies::ValidatorSigner;
 
     #[test]
     fn test() {
-        let private_key = ValidatorSigner::from_int(0).private_key().clone();
+        let signer = ValidatorSigner::from_int(0);
         let internal = Box::new(InMemoryStorage::new());
-        let mut storage = PersistentStorage::initialize(internal, private_key);
+        let mut storage = PersistentStorage::initialize(internal, signer.private_key().clone());
+        assert_eq!(storage.consensus_public_key().unwrap(), signer.public_key());
+        let message = HashValue::random();
+        let signature = storage.sign_message(&message).unwrap();
+        signature.verify(&message, &signer.public_key()).unwrap();
+        let public_key = storage.rotate_consensus_key().unwrap();
+        assert_eq!(storage.consensus_public_key().unwrap(), public_key);
         assert_eq!(storage.epoch().unwrap(), 1);
         assert_eq!(storage.last_voted_round().unwrap(), 0);
         assert_eq!(storage.preferred_round().unwrap(), 0);
@@ -127,4 +155,37 @@ mod tests {
         assert_eq!(storage.last_voted_round().unwrap(), 8);
         assert_eq!(storage.preferred_round().unwrap(), 1);
     }
+
+    #[test]
+    fn test_vault() {
+        let host = mock_vault::start();
+        let signer = ValidatorSigner::from_int(0);
+        let internal = Box::new(VaultStorage::new(
+            host.clone(),
+            mock_vault::ROOT_TOKEN.into(),
+        ));
+        let storage = PersistentStorage::initialize(internal, signer.private_key().clone());
+        let public_key = storage.consensus_public_key().unwrap();
+        assert_ne!(public_key, signer.public_key());
+        let message = HashValue::random();
+        let signature = storage.sign_message(&message).unwrap();
+        signature.verify(&message, &public_key).unwrap();
+        assert_eq!(storage.epoch().unwrap(), 1);
+
+        // Initializing an existing store keeps its key and values.
+        let root = VaultStorage::new(host.clone(), mock_vault::ROOT_TOKEN.into());
+        let storage = PersistentStorage::initialize(Box::new(root), signer.private_key().clone());
+        assert_eq!(storage.consensus_public_key().unwrap(), public_key);
+
+        // Only the tokens of SafetyRules may access its values.
+        let root = VaultStorage::new(host.clone(), mock_vault::ROOT_TOKEN.into());
+        let token = root.create_token(vec!["key_manager"]).unwrap();
+        let mut other = PersistentStorage::new(Box::new(VaultStorage::new(host.clone(), token)));
+        assert!(other.last_voted_round().is_err());
+        assert!(other.set_last_voted_round(0).is_err());
+        let token = root.create_token(vec![SAFETY_RULES_ID]).unwrap();
+        let mut safety_rules = PersistentStorage::new(Box::new(VaultStorage::new(host, token)));
+        safety_rules.set_last_voted_round(5).unwrap();
+        assert_eq!(safety_rules.last_voted_round().unwrap(), 5);
+    }
 }
@@ -7,7 +7,7 @@ use crate::{
     safety_rules_manager,
 };
 use consensus_types::common::{Author, Payload, Round};
-use libra_config::config::{ConsensusType, NodeConfig, SafetyRulesService};
+use libra_config::config::{ConsensusType, NodeConfig, RemoteServiceKeys, SafetyRulesService};
 use libra_types::transaction::SignedTransaction;
 use std::net::SocketAddr;
 
@@ -15,6 +15,7 @@ pub struct ProcessService {
     consensus_type: ConsensusType,
     data: Option<ProcessServiceData>,
     server_addr: SocketAddr,
+    network_keys: RemoteServiceKeys,
 }
 
 impl ProcessService {
@@ -32,6 +33,7 @@ impl ProcessService {
             consensus_type: service.consensus_type,
             data: Some(ProcessServiceData { author, storage }),
             server_addr: service.server_address,
+            network_keys: service.network_keys.clone(),
         }
     }
 
@@ -48,7 +50,8 @@ impl ProcessService {
             .data
             .take()
             .expect("Unable to retrieve ProcessServiceData");
-        remote_service::execute::<T>(data.author, data.storage, self.server_addr);
+        let network_keys = remote_service::server_keys(&self.network_keys);
+        remote_service::execute::<T>(data.author, data.storage, self.server_addr, network_keys);
     }
 }
 
@@ -56,6 +59,10 @@ impl<T: Payload> RemoteService<T> for ProcessService {
     fn server_address(&self) -> SocketAddr {
         self.server_addr
     }
+
+    fn network_keys(&self) -> &RemoteServiceKeys {
+        &self.network_keys
+    }
 }
 
 struct ProcessServiceData {
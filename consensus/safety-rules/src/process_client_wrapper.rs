@@ -12,7 +12,10 @@ use consensus_types::{
     vote_proposal::VoteProposal,
 };
 use libra_config::{
-    config::{ConsensusType, NodeConfig, RemoteService, SafetyRulesBackend, SafetyRulesService},
+    config::{
+        ConsensusType, NodeConfig, RemoteService, RemoteServiceKeys, SafetyRulesBackend,
+        SafetyRulesService,
+    },
     utils,
 };
 use libra_types::crypto_proxies::{Signature, ValidatorSigner};
@@ -47,6 +50,7 @@ impl<T: Payload> ProcessClientWrapper<T> {
         let remote_service = RemoteService {
             server_address,
             consensus_type,
+            network_keys: RemoteServiceKeys::generate(),
         };
         let mut config = NodeConfig::random();
         config.consensus.safety_rules.backend = backend;
@@ -7,26 +7,65 @@ use crate::{
     Error, SafetyRules,
 };
 use consensus_types::common::{Author, Payload};
-use libra_secure_net::{NetworkClient, NetworkServer};
+use libra_config::config::RemoteServiceKeys;
+use libra_secure_net::{NetworkClient, NetworkServer, NoiseKeys};
 use std::{marker::PhantomData, net::SocketAddr};
 
 pub trait RemoteService<T: Payload> {
     fn client(&self) -> SerializerClient<T> {
-        let network_client = NetworkClient::connect(self.server_address()).unwrap();
+        let network_client = NetworkClient::connect_with_noise(
+            self.server_address(),
+            &client_keys(self.network_keys()),
+        )
+        .unwrap();
         let service = Box::new(RemoteClient::new(network_client));
         SerializerClient::new_client(service)
     }
 
     fn server_address(&self) -> SocketAddr;
+
+    /// The keys authenticating consensus and safety rules to each other.
+    fn network_keys(&self) -> &RemoteServiceKeys;
+}
+
+/// The Noise keys of consensus, the client of the safety rules service.
+pub fn client_keys(keys: &RemoteServiceKeys) -> NoiseKeys {
+    NoiseKeys {
+        private_key: keys
+            .consensus_private_key
+            .clone()
+            .expect("Missing consensus private key for the SafetyRules service"),
+        remote_public_key: keys.safety_rules_public_key.clone(),
+    }
+}
+
+/// The Noise keys of the safety rules service.
+pub fn server_keys(keys: &RemoteServiceKeys) -> NoiseKeys {
+    NoiseKeys {
+        private_key: keys
+            .safety_rules_private_key
+            .clone()
+            .expect("Missing SafetyRules private key for the SafetyRules service"),
+        remote_public_key: keys.consensus_public_key.clone(),
+    }
 }
 
-pub fn execute<T: Payload>(author: Author, storage: PersistentStorage, listen_addr: SocketAddr) {
+pub fn execute<T: Payload>(
+    author: Author,
+    storage: PersistentStorage,
+    listen_addr: SocketAddr,
+    network_keys: NoiseKeys,
+) {
     let safety_rules = SafetyRules::<T>::new(author, storage);
     let mut serializer_service = SerializerService::new(safety_rules);
-    let mut network_server = NetworkServer::new(listen_addr);
+    let mut network_server = NetworkServer::new_with_noise(listen_addr, network_keys);
 
     loop {
-        let request = network_server.read().unwrap();
+        // A failed read means the client went away, the server then waits for a new one.
+        let request = match network_server.read() {
+            Ok(request) => request,
+            Err(_) => continue,
+        };
         let response = serializer_service.handle_message(request).unwrap();
         network_server.write(&response).unwrap();
     }
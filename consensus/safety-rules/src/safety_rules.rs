@@ -15,12 +15,8 @@ use consensus_types::{
     vote_data::VoteData,
     vote_proposal::VoteProposal,
 };
-use libra_crypto::hash::HashValue;
-use libra_types::{
-    block_info::BlockInfo,
-    crypto_proxies::{Signature, ValidatorSigner},
-    ledger_info::LedgerInfo,
-};
+use libra_crypto::hash::{CryptoHash, HashValue};
+use libra_types::{block_info::BlockInfo, crypto_proxies::Signature, ledger_info::LedgerInfo};
 use std::marker::PhantomData;
 
 /// SafetyRules is responsible for the safety of the consensus:
@@ -34,22 +30,18 @@ use std::marker::PhantomData;
 /// set)
 pub struct SafetyRules<T> {
     persistent_storage: PersistentStorage,
-    validator_signer: ValidatorSigner,
+    author: Author,
     marker: PhantomData<T>,
 }
 
 impl<T: Payload> SafetyRules<T> {
-    /// Constructs a new instance of SafetyRules with the given persistent storage and the
-    /// consensus private keys
+    /// Constructs a new instance of SafetyRules with the given persistent storage, which holds
+    /// the consensus private key and signs with it
     /// @TODO replace this with an API that takes in a SafetyRulesConfig
     pub fn new(author: Author, persistent_storage: PersistentStorage) -> Self {
-        let consensus_key = persistent_storage
-            .consensus_key()
-            .expect("Unable to retrieve consensus private key");
-        let validator_signer = ValidatorSigner::new(author, consensus_key);
         Self {
             persistent_storage,
-            validator_signer,
+            author,
             marker: PhantomData,
         }
     }
@@ -76,8 +68,8 @@ impl<T: Payload> SafetyRules<T> {
         }
     }
 
-    pub fn signer(&self) -> &ValidatorSigner {
-        &self.validator_signer
+    fn sign(&self, message: HashValue) -> Result<Signature, Error> {
+        Ok(self.persistent_storage.sign_message(&message)?.into())
     }
 }
 
@@ -155,18 +147,22 @@ impl<T: Payload> TSafetyRules<T> for SafetyRules<T> {
         self.persistent_storage
             .set_last_voted_round(proposed_block.round())?;
 
-        Ok(Vote::new(
-            VoteData::new(
-                proposed_block.gen_block_info(
-                    new_tree.root_hash(),
-                    new_tree.version(),
-                    vote_proposal.next_validator_set().cloned(),
-                ),
-                proposed_block.quorum_cert().certified_block().clone(),
+        let vote_data = VoteData::new(
+            proposed_block.gen_block_info(
+                new_tree.root_hash(),
+                new_tree.version(),
+                vote_proposal.next_validator_set().cloned(),
             ),
-            self.validator_signer.author(),
-            self.construct_ledger_info(proposed_block),
-            &self.validator_signer,
+            proposed_block.quorum_cert().certified_block().clone(),
+        );
+        let mut ledger_info = self.construct_ledger_info(proposed_block);
+        ledger_info.set_consensus_data_hash(vote_data.hash());
+        let signature = self.sign(ledger_info.hash())?;
+        Ok(Vote::new_with_signature(
+            vote_data,
+            self.author,
+            ledger_info,
+            signature,
         ))
     }
 
@@ -174,15 +170,15 @@ impl<T: Payload> TSafetyRules<T> for SafetyRules<T> {
     /// @TODO verify QC correctness
     /// @TODO verify QC matches preferred round
     fn sign_proposal(&mut self, block_data: BlockData<T>) -> Result<Block<T>, Error> {
-        Ok(Block::new_proposal_from_block_data(
-            block_data,
-            &self.validator_signer,
+        let signature = self.sign(block_data.hash())?;
+        Ok(Block::new_proposal_from_block_data_and_signature(
+            block_data, signature,
         ))
     }
 
     /// @TODO only sign a timeout if it matches last_voted_round or last_voted_round + 1
     /// @TODO update last_voted_round
     fn sign_timeout(&mut self, timeout: &Timeout) -> Result<Signature, Error> {
-        Ok(timeout.sign(&self.validator_signer))
+        self.sign(timeout.hash())
     }
 }
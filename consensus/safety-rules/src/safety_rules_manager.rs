@@ -3,7 +3,7 @@
 
 use crate::{
     local_client::LocalClient,
-    persistent_storage::PersistentStorage,
+    persistent_storage::{PersistentStorage, SAFETY_RULES_ID},
     remote_service::RemoteService,
     serializer::{SerializerClient, SerializerService},
     spawned_process::SpawnedProcess,
@@ -12,7 +12,9 @@ use crate::{
 };
 use consensus_types::common::{Author, Payload};
 use libra_config::config::{NodeConfig, SafetyRulesBackend, SafetyRulesService};
-use libra_secure_storage::{InMemoryStorage, OnDiskStorage, Storage};
+use libra_secure_storage::{
+    EncryptedOnDiskStorage, InMemoryStorage, OnDiskStorage, Storage, UnlockKey, VaultStorage,
+};
 use std::sync::{Arc, RwLock};
 
 pub fn extract_service_inputs(config: &mut NodeConfig) -> (Author, PersistentStorage) {
@@ -28,6 +30,22 @@ pub fn extract_service_inputs(config: &mut NodeConfig) -> (Author, PersistentSto
         SafetyRulesBackend::OnDiskStorage(config) => {
             (config.default, Box::new(OnDiskStorage::new(config.path())))
         }
+        SafetyRulesBackend::EncryptedOnDiskStorage(config) => {
+            let storage = EncryptedOnDiskStorage::new(
+                config.path(),
+                &UnlockKey::KeyFile(config.key_file()),
+                SAFETY_RULES_ID.into(),
+            )
+            .expect("Unable to open encrypted storage");
+            (config.default, Box::new(storage))
+        }
+        SafetyRulesBackend::Vault(config) => (
+            config.default,
+            Box::new(VaultStorage::new(
+                config.server.clone(),
+                config.token.clone(),
+            )),
+        ),
     };
 
     let storage = if initialize {
@@ -3,7 +3,7 @@
 
 use crate::remote_service::RemoteService;
 use consensus_types::common::Payload;
-use libra_config::config::{NodeConfig, PersistableConfig, SafetyRulesService};
+use libra_config::config::{NodeConfig, PersistableConfig, RemoteServiceKeys, SafetyRulesService};
 use libra_temppath::TempPath;
 use std::{
     marker::PhantomData,
@@ -17,6 +17,7 @@ const BINARY: &str = "safety-rules";
 pub struct SpawnedProcess<T> {
     handle: Child,
     server_addr: SocketAddr,
+    network_keys: RemoteServiceKeys,
     _config_path: TempPath,
     marker: PhantomData<T>,
 }
@@ -29,8 +30,8 @@ impl<T: Payload> SpawnedProcess<T> {
         config.save_config(&config_path).unwrap();
 
         let service = &config.consensus.safety_rules.service;
-        let server_addr = if let SafetyRulesService::SpawnedProcess(process_config) = service {
-            process_config.server_address
+        let process_config = if let SafetyRulesService::SpawnedProcess(process_config) = service {
+            process_config
         } else {
             panic!("Invalid SafeRulesService, expected SpawnedProcess.");
         };
@@ -45,7 +46,8 @@ impl<T: Payload> SpawnedProcess<T> {
 
         Self {
             handle,
-            server_addr,
+            server_addr: process_config.server_address,
+            network_keys: process_config.network_keys.clone(),
             _config_path: config_path,
             marker: PhantomData,
         }
@@ -56,6 +58,10 @@ impl<T: Payload> RemoteService<T> for SpawnedProcess<T> {
     fn server_address(&self) -> SocketAddr {
         self.server_addr
     }
+
+    fn network_keys(&self) -> &RemoteServiceKeys {
+        &self.network_keys
+    }
 }
 
 /// Kill SafetyRules process upon this object going out of scope
@@ -12,7 +12,7 @@ use crate::{
     remote_service::{self, RemoteService},
 };
 use consensus_types::common::{Author, Payload};
-use libra_config::utils;
+use libra_config::{config::RemoteServiceKeys, utils};
 use std::{
     marker::PhantomData,
     net::{IpAddr, Ipv4Addr, SocketAddr},
@@ -24,6 +24,7 @@ use std::{
 pub struct ThreadService<T> {
     _child: JoinHandle<()>,
     server_addr: SocketAddr,
+    network_keys: RemoteServiceKeys,
     marker: PhantomData<T>,
 }
 
@@ -32,13 +33,18 @@ impl<T: Payload> ThreadService<T> {
         let listen_port = utils::get_available_port();
         let listen_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen_port);
         let server_addr = listen_addr;
+        // Both ends live in this process, the keys only need to last as long as it.
+        let network_keys = RemoteServiceKeys::generate();
+        let server_keys = remote_service::server_keys(&network_keys);
 
-        let child =
-            thread::spawn(move || remote_service::execute::<T>(author, storage, listen_addr));
+        let child = thread::spawn(move || {
+            remote_service::execute::<T>(author, storage, listen_addr, server_keys)
+        });
 
         Self {
             _child: child,
             server_addr,
+            network_keys,
             marker: PhantomData,
         }
     }
@@ -48,4 +54,8 @@ impl<T: Payload> RemoteService<T> for ThreadService<T> {
     fn server_address(&self) -> SocketAddr {
         self.server_addr
     }
+
+    fn network_keys(&self) -> &RemoteServiceKeys {
+        &self.network_keys
+    }
 }
@@ -20,12 +20,9 @@ use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
 use libra_types::validator_change::ValidatorChangeProof;
 use mirai_annotations::checked_precondition;
-use rand::{prelude::*, Rng};
-use std::{
-    clone::Clone,
-    time::{Duration, Instant},
-};
+use std::{clone::Clone, time::Duration};
 use termion::color::*;
+use tokio::time::Instant;
 
 #[derive(Debug, PartialEq)]
 /// Whether we need to do block retrieval if we want to insert a Quorum Cert.
@@ -291,7 +288,7 @@ impl BlockRetriever {
             return self.preferred_peer;
         }
 
-        let peer_idx = thread_rng().gen_range(0, peers.len());
+        let peer_idx = self.network.random_peer_index(peers.len());
         *peers.remove(peer_idx)
     }
 }
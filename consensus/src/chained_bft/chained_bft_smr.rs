@@ -6,26 +6,37 @@ use crate::{
         block_storage::BlockStore,
         epoch_manager::{EpochCheck, EpochManager, EpochMsg},
         event_processor::EventProcessor,
-        network::{ConsensusDataRequest, FromNetworkMsg, NetworkTask},
+        network::{ConsensusDataRequest, FromNetworkMsg, NetworkTask, ObserverSender},
         persistent_storage::PersistentStorage,
     },
     consensus_provider::ConsensusProvider,
     counters,
     state_replication::{StateComputer, TxnManager},
-    util::time_service::ClockTimeService,
+    util::time_service::{ClockTimeService, TimeService},
 };
 use anyhow::Result;
 use channel;
 use consensus_types::common::{Author, Payload, Round};
-use futures::{channel::mpsc, select, stream::select, stream::StreamExt, Stream, TryStreamExt};
+use futures::{
+    channel::mpsc,
+    executor::block_on,
+    future::{abortable, AbortHandle},
+    select,
+    stream::{self, select, StreamExt},
+    Stream, TryStreamExt,
+};
 use libra_config::config::{ConsensusConfig, NodeConfig};
 use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
 use libra_types::crypto_proxies::EpochInfo;
 use network::proto::ConsensusMsg;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event};
+use rand::{prelude::*, rngs::StdRng};
 use safety_rules::SafetyRulesManager;
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use tokio::runtime::{self, Handle, Runtime};
 
 /// All these structures need to be moved into EpochManager. Rather than make each one an option
@@ -33,6 +44,7 @@ use tokio::runtime::{self, Handle, Runtime};
 pub struct ChainedBftSMRInput<T> {
     network_sender: ConsensusNetworkSender,
     network_events: ConsensusNetworkEvents,
+    observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
     safety_rules_manager: SafetyRulesManager<T>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     txn_manager: Box<dyn TxnManager<Payload = T>>,
@@ -45,8 +57,13 @@ pub struct ChainedBftSMRInput<T> {
 pub struct ChainedBftSMR<T> {
     author: Author,
     runtime: Option<Runtime>,
+    // Runs the consensus tasks instead of a runtime of their own, e.g. in the simulations.
+    executor: Option<Handle>,
+    tasks: Vec<AbortHandle>,
     block_store: Option<Arc<BlockStore<T>>>,
     storage: Arc<dyn PersistentStorage<T>>,
+    time_service: Option<Arc<dyn TimeService>>,
+    peer_selection_seed: Option<u64>,
     input: Option<ChainedBftSMRInput<T>>,
 }
 
@@ -54,6 +71,7 @@ impl<T: Payload> ChainedBftSMR<T> {
     pub fn new(
         network_sender: ConsensusNetworkSender,
         network_events: ConsensusNetworkEvents,
+        observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
         node_config: &mut NodeConfig,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         storage: Arc<dyn PersistentStorage<T>>,
@@ -62,6 +80,7 @@ impl<T: Payload> ChainedBftSMR<T> {
         let input = ChainedBftSMRInput {
             network_sender,
             network_events,
+            observer_network,
             safety_rules_manager: SafetyRulesManager::new(node_config),
             state_computer,
             txn_manager,
@@ -71,8 +90,12 @@ impl<T: Payload> ChainedBftSMR<T> {
         Self {
             author: node_config.validator_network.as_ref().unwrap().peer_id,
             runtime: None,
+            executor: None,
+            tasks: vec![],
             block_store: None,
             storage,
+            time_service: None,
+            peer_selection_seed: None,
             input: Some(input),
         }
     }
@@ -82,11 +105,52 @@ impl<T: Payload> ChainedBftSMR<T> {
         self.author
     }
 
+    /// Replaces the clock driving the pacemaker and the proposal timestamps, must be called
+    /// before start.
+    #[cfg(test)]
+    pub fn set_time_service(&mut self, time_service: Arc<dyn TimeService>) {
+        self.time_service = Some(time_service);
+    }
+
+    /// Spawns the consensus tasks on the given executor rather than on a runtime owned by the
+    /// SMR, must be called before start.
+    #[cfg(test)]
+    pub fn set_executor(&mut self, executor: Handle) {
+        self.executor = Some(executor);
+    }
+
+    /// Seeds the choice of the peers to retrieve blocks from, must be called before start.
+    #[cfg(test)]
+    pub fn set_peer_selection_seed(&mut self, seed: u64) {
+        self.peer_selection_seed = Some(seed);
+    }
+
     #[cfg(test)]
     pub fn block_store(&self) -> Option<Arc<BlockStore<T>>> {
         self.block_store.clone()
     }
 
+    fn build_runtime() -> Runtime {
+        runtime::Builder::new()
+            .thread_name("consensus-")
+            // Question: How many, if more than one at all, threads, does this runtime need?
+            //
+            // I find it somewhat hard to reason about opportunities for parallelism here.
+            // The EpochManager and EventProcessor own a buch of other things, like the StorageWriteProxy,
+            // but all calls into those "other things" are essentially sync, right?
+            // So at the end of the day, we have only two tasks, NetworkTask and then the other "consensus" task.
+            // So maybe two threads is enough, maybe even only one?
+            //
+            // The only opportunities for parallelism between those to tasks seem to be about
+            // having NetworkTask buffer/drop incoming messages while the "consensus" task is processing a message.
+            //
+            // Are there any other opportunities, or need, for parallelism?
+            .threaded_scheduler()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime!")
+    }
+
     fn start_event_processing(
         executor: Handle,
         mut epoch_manager: EpochManager<T>,
@@ -96,7 +160,7 @@ impl<T: Payload> ChainedBftSMR<T> {
         all_events: Box<dyn Stream<Item = anyhow::Result<Event<ConsensusMsg>>> + Send + Unpin>,
         data_request_sender: mpsc::UnboundedSender<ConsensusDataRequest>,
         mut data_receiver: mpsc::UnboundedReceiver<(AccountAddress, FromNetworkMsg<T>)>,
-    ) {
+    ) -> Vec<AbortHandle> {
         let fut = async move {
             event_processor.start().await;
 
@@ -181,8 +245,11 @@ impl<T: Payload> ChainedBftSMR<T> {
                 counters::EVENT_PROCESSING_LOOP_IDLE_DURATION_S.observe_duration(idle_duration);
             }
         };
-        executor.spawn(network_task.start(all_events));
+        let (network_fut, network_handle) = abortable(network_task.start(all_events));
+        let (fut, handle) = abortable(fut);
+        executor.spawn(network_fut);
         executor.spawn(fut);
+        vec![network_handle, handle]
     }
 }
 
@@ -192,31 +259,26 @@ impl<T: Payload> ConsensusProvider for ChainedBftSMR<T> {
     /// 2. Construct per-epoch component with the fixed Validators provided by EpochManager including
     /// ProposerElection, Pacemaker, SafetyRules, Network(Populate with known validators), EventProcessor
     fn start(&mut self) -> Result<()> {
-        let mut runtime = runtime::Builder::new()
-            .thread_name("consensus-")
-            // Question: How many, if more than one at all, threads, does this runtime need?
-            //
-            // I find it somewhat hard to reason about opportunities for parallelism here.
-            // The EpochManager and EventProcessor own a buch of other things, like the StorageWriteProxy,
-            // but all calls into those "other things" are essentially sync, right?
-            // So at the end of the day, we have only two tasks, NetworkTask and then the other "consensus" task.
-            // So maybe two threads is enough, maybe even only one?
-            //
-            // The only opportunities for parallelism between those to tasks seem to be about
-            // having NetworkTask buffer/drop incoming messages while the "consensus" task is processing a message.
-            //
-            // Are there any other opportunities, or need, for parallelism?
-            .threaded_scheduler()
-            .enable_all()
-            .build()
-            .expect("Failed to create Tokio runtime!");
-
-        let initial_data = runtime.block_on(self.storage.start());
+        let (executor, initial_data) = match self.executor.clone() {
+            Some(executor) => (executor, block_on(self.storage.start())),
+            None => {
+                let mut runtime = Self::build_runtime();
+                let initial_data = runtime.block_on(self.storage.start());
+                let executor = runtime.handle().clone();
+                self.runtime = Some(runtime);
+                (executor, initial_data)
+            }
+        };
         let input = self.input.take().expect("already started, input is None");
 
-        let executor = runtime.handle().clone();
-        let time_service = Arc::new(ClockTimeService::new(executor.clone()));
-        self.runtime = Some(runtime);
+        let time_service = match self.time_service.take() {
+            Some(time_service) => time_service,
+            None => Arc::new(ClockTimeService::new(executor.clone())),
+        };
+        let peer_rng = match self.peer_selection_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
 
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
@@ -226,6 +288,21 @@ impl<T: Payload> ConsensusProvider for ChainedBftSMR<T> {
             verifier: initial_data.validators(),
         };
 
+        // The observers are served the block retrieval requests by the event processor, in the
+        // same way as the validators.
+        let (observer_sender, observer_events) = match input.observer_network {
+            Some((observer_network_sender, observer_network_events)) => {
+                let (observer_sender, observer_events) =
+                    ObserverSender::new(observer_network_sender, observer_network_events);
+                (Some(observer_sender), observer_events)
+            }
+            None => (
+                None,
+                Box::new(stream::empty())
+                    as Box<dyn Stream<Item = anyhow::Result<Event<ConsensusMsg>>> + Send + Unpin>,
+            ),
+        };
+
         let mut epoch_mgr = EpochManager::new(
             self.author,
             epoch_info,
@@ -238,6 +315,8 @@ impl<T: Payload> ConsensusProvider for ChainedBftSMR<T> {
             input.state_computer,
             self.storage.clone(),
             input.safety_rules_manager,
+            observer_sender,
+            Arc::new(Mutex::new(peer_rng)),
         );
 
         // Step 2
@@ -252,9 +331,12 @@ impl<T: Payload> ConsensusProvider for ChainedBftSMR<T> {
         let network_task = NetworkTask::new(network_data_request_receiver, network_data_sender);
 
         let network_events = input.network_events.map_err(Into::<anyhow::Error>::into);
-        let all_events = Box::new(select(network_events, self_receiver));
+        let all_events = Box::new(select(
+            select(network_events, self_receiver),
+            observer_events,
+        ));
 
-        Self::start_event_processing(
+        self.tasks = Self::start_event_processing(
             executor,
             epoch_mgr,
             event_processor,
@@ -271,6 +353,9 @@ impl<T: Payload> ConsensusProvider for ChainedBftSMR<T> {
 
     /// Stop is synchronous: waits for all the worker threads to terminate.
     fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
         if let Some(_rt) = self.runtime.take() {
             debug!("Chained BFT SMR stopped.")
         }
@@ -68,6 +68,7 @@ impl SMRNode {
         let mut smr = ChainedBftSMR::new(
             network_sender,
             network_events,
+            None,
             &mut config.clone(),
             Arc::new(MockStateComputer::new(
                 commit_cb_sender,
@@ -0,0 +1,378 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        block_storage::{BlockReader, BlockRetriever, BlockStore},
+        network::NetworkSender,
+        persistent_storage::{PersistentStorage, RecoveryData},
+    },
+    consensus_provider::ConsensusProvider,
+    counters,
+    state_replication::StateComputer,
+};
+use anyhow::{bail, ensure, Result};
+use channel;
+use consensus_types::{
+    common::{Author, Payload},
+    proposal_msg::ProposalUncheckedSignatures,
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+};
+use futures::{stream::select, StreamExt, TryStreamExt};
+use libra_config::config::{ConsensusConfig, NodeConfig};
+use libra_logger::prelude::*;
+use libra_types::{
+    crypto_proxies::{EpochInfo, ValidatorChangeProof},
+    validator_change::VerifierType,
+};
+use network::{
+    proto::{ConsensusMsg, ConsensusMsg_oneof},
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event},
+};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+use tokio::{
+    runtime::{self, Runtime},
+    time::Instant,
+};
+
+#[cfg(test)]
+#[path = "consensus_observer_test.rs"]
+mod consensus_observer_test;
+
+/// The time given to the retrieval of the blocks an observed message depends on.
+const BLOCK_RETRIEVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct ConsensusObserverInput<T> {
+    network_sender: ConsensusNetworkSender,
+    network_events: ConsensusNetworkEvents,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    config: ConsensusConfig,
+}
+
+/// ConsensusObserver follows the consensus of the validators without taking part in it: it is
+/// meant for the full nodes, which receive the proposals and commit certificates published by
+/// their upstream peers on the full-node network. ConsensusObserver implements the
+/// ConsensusProvider, it is going to be used instead of ChainedBftSMR on the full nodes.
+pub struct ConsensusObserver<T> {
+    author: Author,
+    runtime: Option<Runtime>,
+    storage: Arc<dyn PersistentStorage<T>>,
+    input: Option<ConsensusObserverInput<T>>,
+}
+
+impl<T: Payload> ConsensusObserver<T> {
+    pub fn new(
+        author: Author,
+        network_sender: ConsensusNetworkSender,
+        network_events: ConsensusNetworkEvents,
+        node_config: &NodeConfig,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        storage: Arc<dyn PersistentStorage<T>>,
+    ) -> Self {
+        let input = ConsensusObserverInput {
+            network_sender,
+            network_events,
+            state_computer,
+            config: node_config.consensus.clone(),
+        };
+
+        Self {
+            author,
+            runtime: None,
+            storage,
+            input: Some(input),
+        }
+    }
+}
+
+impl<T: Payload> ConsensusProvider for ConsensusObserver<T> {
+    /// Recovers the block tree from the storage and starts processing the observed messages.
+    fn start(&mut self) -> Result<()> {
+        let mut runtime = runtime::Builder::new()
+            .thread_name("consensus-observer-")
+            .threaded_scheduler()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime!");
+
+        let initial_data = runtime.block_on(self.storage.start());
+        let input = self.input.take().expect("already started, input is None");
+
+        let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
+        let mut processor = ObserverProcessor::new(
+            self.author,
+            input.network_sender,
+            self_sender,
+            input.state_computer,
+            self.storage.clone(),
+            initial_data,
+            input.config.max_pruned_blocks_in_mem,
+        );
+
+        let network_events = input.network_events.map_err(Into::<anyhow::Error>::into);
+        let mut all_events = select(network_events, self_receiver);
+        runtime.spawn(async move {
+            while let Some(event) = all_events.next().await {
+                match event {
+                    Ok(event) => processor.process_event(event).await,
+                    Err(e) => warn!("Received an error instead of a consensus msg: {:?}", e),
+                }
+            }
+        });
+        self.runtime = Some(runtime);
+
+        debug!("Consensus observer started.");
+        Ok(())
+    }
+
+    /// Stop is synchronous: waits for all the worker threads to terminate.
+    fn stop(&mut self) {
+        if let Some(_rt) = self.runtime.take() {
+            debug!("Consensus observer stopped.")
+        }
+    }
+}
+
+/// ObserverProcessor builds the local block tree out of the observed messages:
+/// * the blocks of the verified proposals are executed and inserted into the BlockStore,
+/// * the missing ancestors are retrieved from the peer that sent the message,
+/// * the blocks are committed once a commit certificate for them is observed.
+/// There is neither voting nor safety rules involved: every message is verified against the
+/// validator set of the current epoch, which is all the trust the observer needs.
+pub struct ObserverProcessor<T> {
+    author: Author,
+    epoch_info: EpochInfo,
+    block_store: Arc<BlockStore<T>>,
+    network: NetworkSender,
+    network_sender: ConsensusNetworkSender,
+    self_sender: channel::Sender<anyhow::Result<Event<ConsensusMsg>>>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    storage: Arc<dyn PersistentStorage<T>>,
+    max_pruned_blocks_in_mem: usize,
+}
+
+impl<T: Payload> ObserverProcessor<T> {
+    pub fn new(
+        author: Author,
+        network_sender: ConsensusNetworkSender,
+        self_sender: channel::Sender<anyhow::Result<Event<ConsensusMsg>>>,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        storage: Arc<dyn PersistentStorage<T>>,
+        initial_data: RecoveryData<T>,
+        max_pruned_blocks_in_mem: usize,
+    ) -> Self {
+        let epoch_info = EpochInfo {
+            epoch: initial_data.epoch(),
+            verifier: initial_data.validators(),
+        };
+        let network = NetworkSender::new(
+            author,
+            network_sender.clone(),
+            self_sender.clone(),
+            initial_data.validators(),
+        );
+        let block_store = Arc::new(BlockStore::new(
+            Arc::clone(&storage),
+            initial_data,
+            Arc::clone(&state_computer),
+            max_pruned_blocks_in_mem,
+        ));
+        Self {
+            author,
+            epoch_info,
+            block_store,
+            network,
+            network_sender,
+            self_sender,
+            state_computer,
+            storage,
+            max_pruned_blocks_in_mem,
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch_info.epoch
+    }
+
+    pub fn block_store(&self) -> Arc<BlockStore<T>> {
+        self.block_store.clone()
+    }
+
+    pub async fn process_event(&mut self, event: Event<ConsensusMsg>) {
+        match event {
+            Event::Message((peer_id, msg)) => {
+                let r = match msg.message {
+                    Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+                        match ProposalUncheckedSignatures::<T>::try_from(proposal) {
+                            Ok(proposal) => self.process_proposal(proposal, peer_id).await,
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Some(ConsensusMsg_oneof::SyncInfo(sync_info)) => {
+                        match SyncInfo::try_from(sync_info) {
+                            Ok(sync_info) => self.process_sync_info(sync_info, peer_id).await,
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Some(ConsensusMsg_oneof::EpochChange(proof)) => {
+                        match ValidatorChangeProof::try_from(proof) {
+                            Ok(proof) => self.process_epoch_change(proof).await,
+                            Err(e) => Err(e),
+                        }
+                    }
+                    msg => {
+                        debug!("Ignore msg from {}: {:?}", peer_id, msg);
+                        counters::OBSERVER_PROCESSED_MSGS
+                            .with_label_values(&["ignored"])
+                            .inc();
+                        return;
+                    }
+                };
+                match r {
+                    Ok(()) => counters::OBSERVER_PROCESSED_MSGS
+                        .with_label_values(&["processed"])
+                        .inc(),
+                    Err(e) => {
+                        warn!("Failed to process observed msg from {}: {:?}", peer_id, e);
+                        counters::OBSERVER_PROCESSED_MSGS
+                            .with_label_values(&["failed"])
+                            .inc();
+                    }
+                }
+            }
+            Event::RpcRequest((peer_id, msg, _))
+            | Event::StreamingRpcRequest((peer_id, msg, _)) => {
+                // Observers do not serve the blocks they observe.
+                debug!("Ignore RPC from {}: {:?}", peer_id, msg);
+            }
+            Event::NewPeer(peer_id) => {
+                debug!("Peer {} connected", peer_id);
+            }
+            Event::LostPeer(peer_id) => {
+                debug!("Peer {} dis-connected", peer_id);
+            }
+        }
+    }
+
+    /// Executes and inserts the proposed block once its dependencies are retrieved, and commits
+    /// the blocks certified by the commit certificate it carries.
+    pub async fn process_proposal(
+        &mut self,
+        proposal: ProposalUncheckedSignatures<T>,
+        peer: Author,
+    ) -> Result<()> {
+        if proposal.epoch() != self.epoch() {
+            return self.process_different_epoch(proposal.epoch());
+        }
+        let proposal = proposal
+            .validate_signatures(&self.epoch_info.verifier)?
+            .verify_well_formed()?;
+        debug!("Observed proposal {}", proposal);
+        self.sync_up(proposal.sync_info(), peer).await?;
+        if proposal.round() > self.block_store.root().round() {
+            self.block_store
+                .execute_and_insert_block(proposal.take_proposal())?;
+        }
+        Ok(())
+    }
+
+    /// Retrieves the blocks certified by the sync info and commits its commit certificate.
+    pub async fn process_sync_info(&mut self, sync_info: SyncInfo, peer: Author) -> Result<()> {
+        if sync_info.epoch() != self.epoch() {
+            return self.process_different_epoch(sync_info.epoch());
+        }
+        sync_info.verify(&self.epoch_info.verifier)?;
+        self.sync_up(&sync_info, peer).await
+    }
+
+    /// Syncs to the ledger info ending the current epoch, and starts following the next one.
+    pub async fn process_epoch_change(&mut self, proof: ValidatorChangeProof) -> Result<()> {
+        let epoch = proof.epoch()?;
+        if epoch != self.epoch() {
+            return self.process_different_epoch(epoch);
+        }
+        let verifier = VerifierType::TrustedVerifier(self.epoch_info.clone());
+        let target_ledger_info = proof.verify(&verifier)?;
+        if let Err(e) = self.state_computer.sync_to(target_ledger_info).await {
+            error!(
+                "State sync to new epoch failed with {:?}, we'll try to start from current libradb",
+                e
+            );
+        }
+        self.restart().await;
+        Ok(())
+    }
+
+    /// The observer cannot verify the messages of the other epochs: it only starts following a
+    /// newer epoch once it has verified the epoch change (through the proof of the epoch change or
+    /// the commit of the block ending the epoch) and synced its storage to it. The messages of the
+    /// newer epochs are dropped until then, without touching the storage.
+    fn process_different_epoch(&self, different_epoch: u64) -> Result<()> {
+        ensure!(
+            different_epoch > self.epoch(),
+            "Observed msg from old epoch {}",
+            different_epoch
+        );
+        bail!(
+            "Observed msg from epoch {} before the change from local epoch {}",
+            different_epoch,
+            self.epoch()
+        )
+    }
+
+    async fn sync_up(&mut self, sync_info: &SyncInfo, peer: Author) -> Result<()> {
+        let deadline = Instant::now() + BLOCK_RETRIEVAL_TIMEOUT;
+        let retriever = BlockRetriever::new(self.network.clone(), deadline, peer);
+        self.block_store.sync_to(sync_info, retriever).await?;
+        self.process_commit_cert(sync_info.highest_commit_cert())
+            .await;
+        Ok(())
+    }
+
+    async fn process_commit_cert(&mut self, commit_cert: &QuorumCert) {
+        let block = match self.block_store.get_block(commit_cert.commit_info().id()) {
+            Some(block) => block,
+            None => return,
+        };
+        if block.round() <= self.block_store.root().round() {
+            return;
+        }
+        if let Err(e) = self
+            .block_store
+            .commit(commit_cert.ledger_info().clone())
+            .await
+        {
+            error!("Failed to commit observed block {}: {:?}", block, e);
+            return;
+        }
+        if commit_cert.ends_epoch() {
+            self.restart().await;
+        }
+    }
+
+    /// Rebuilds the block tree from the storage, possibly for a new epoch.
+    async fn restart(&mut self) {
+        let initial_data = self.storage.start().await;
+        self.start_epoch(initial_data);
+    }
+
+    fn start_epoch(&mut self, initial_data: RecoveryData<T>) {
+        if initial_data.epoch() != self.epoch() {
+            info!(
+                "Observer moves from epoch {} to epoch {}",
+                self.epoch(),
+                initial_data.epoch()
+            );
+        }
+        *self = Self::new(
+            self.author,
+            self.network_sender.clone(),
+            self.self_sender.clone(),
+            Arc::clone(&self.state_computer),
+            Arc::clone(&self.storage),
+            initial_data,
+            self.max_pruned_blocks_in_mem,
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::{BlockReader, BlockStore},
+    consensus_observer::ObserverProcessor,
+    test_utils::{consensus_runtime, MockStateComputer, MockStorage, TestPayload, TreeInserter},
+};
+use channel;
+use consensus_types::{
+    block::block_test_utils::certificate_for_genesis,
+    block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus},
+    executed_block::ExecutedBlock,
+    proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+};
+use futures::{channel::mpsc, executor::block_on, StreamExt};
+use libra_prost_ext::MessageExt;
+use libra_types::crypto_proxies::{
+    random_validator_verifier, LedgerInfoWithSignatures, ValidatorSigner,
+};
+use network::{
+    interface::NetworkRequest,
+    proto::{ConsensusMsg, ConsensusMsg_oneof, Proposal},
+    validator_network::ConsensusNetworkSender,
+};
+use prost::Message as _;
+use std::{
+    convert::{TryFrom, TryInto},
+    sync::Arc,
+};
+
+struct ObserverSetup {
+    observer: ObserverProcessor<TestPayload>,
+    commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    // The validator the observed messages come from.
+    validator: TreeInserter,
+}
+
+impl ObserverSetup {
+    fn new(executor: &tokio::runtime::Handle) -> Self {
+        let (signers, validators) = random_validator_verifier(1, None, false);
+        let signer: ValidatorSigner = signers[0].clone();
+        let validator = TreeInserter::new(signer);
+
+        let (initial_data, storage) =
+            MockStorage::<TestPayload>::start_for_testing((&validators).into());
+        let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(MockStateComputer::new(
+            commit_cb_sender,
+            Arc::clone(&storage),
+            None,
+        ));
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (self_sender, _self_receiver) = channel::new_test(8);
+        executor.spawn(serve_block_retrieval(
+            network_reqs_rx,
+            validator.block_store(),
+        ));
+
+        let observer = ObserverProcessor::new(
+            ValidatorSigner::random(None).author(),
+            ConsensusNetworkSender::new(network_reqs_tx),
+            self_sender,
+            state_computer,
+            storage,
+            initial_data,
+            10, // max pruned blocks in mem
+        );
+        Self {
+            observer,
+            commit_cb_receiver,
+            validator,
+        }
+    }
+
+    fn proposal_for(
+        &self,
+        block: &ExecutedBlock<TestPayload>,
+        highest_commit_cert: QuorumCert,
+    ) -> ProposalUncheckedSignatures<TestPayload> {
+        let proposal = ProposalMsg::new(
+            block.block().clone(),
+            SyncInfo::new(block.quorum_cert().clone(), highest_commit_cert, None),
+        );
+        let proto: Proposal = proposal.try_into().unwrap();
+        ProposalUncheckedSignatures::try_from(proto).unwrap()
+    }
+}
+
+/// Answers the block retrieval requests of the observer out of the given block store.
+async fn serve_block_retrieval(
+    mut network_reqs_rx: channel::Receiver<NetworkRequest>,
+    block_store: Arc<BlockStore<TestPayload>>,
+) {
+    while let Some(request) = network_reqs_rx.next().await {
+        let rpc = match request {
+            NetworkRequest::SendRpc(_, rpc) => rpc,
+            _ => continue,
+        };
+        let request = match ConsensusMsg::decode(rpc.data.as_ref()).unwrap().message {
+            Some(ConsensusMsg_oneof::RequestBlock(request)) => {
+                BlockRetrievalRequest::try_from(request).unwrap()
+            }
+            msg => panic!("Unexpected RPC {:?}", msg),
+        };
+        let mut blocks = vec![];
+        let mut id = request.block_id();
+        while (blocks.len() as u64) < request.num_blocks() {
+            match block_store.get_block(id) {
+                Some(executed_block) => {
+                    id = executed_block.parent_id();
+                    blocks.push(executed_block.block().clone());
+                }
+                None => break,
+            }
+        }
+        let response = BlockRetrievalResponse::new(BlockRetrievalStatus::Succeeded, blocks);
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::RespondBlock(
+                response.try_into().unwrap(),
+            )),
+        };
+        rpc.res_tx.send(Ok(msg.to_bytes().unwrap())).unwrap();
+    }
+}
+
+#[test]
+fn test_observer_follows_proposals() {
+    let runtime = consensus_runtime();
+    let mut setup = ObserverSetup::new(runtime.handle());
+    let genesis = setup.validator.block_store().root();
+    let peer = setup.validator.signer().author();
+
+    // genesis <- a1 <- a2 <- a3 <- a4, the QC for a3 carried by a4 commits a1.
+    let a1 = setup
+        .validator
+        .insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let a2 = setup.validator.insert_block(&a1, 2, None);
+    let a3 = setup.validator.insert_block(&a2, 3, None);
+    let a4 = setup.validator.insert_block(&a3, 4, Some(a1.block_info()));
+
+    for block in &[&a1, &a2, &a3] {
+        let proposal = setup.proposal_for(block, certificate_for_genesis());
+        block_on(setup.observer.process_proposal(proposal, peer))
+            .expect("Observer should process the proposal");
+        assert!(setup.observer.block_store().block_exists(block.id()));
+    }
+    assert_eq!(setup.observer.block_store().root().id(), genesis.id());
+
+    let proposal = setup.proposal_for(&a4, a4.quorum_cert().clone());
+    block_on(setup.observer.process_proposal(proposal, peer))
+        .expect("Observer should process the proposal");
+    let block_store = setup.observer.block_store();
+    assert!(block_store.block_exists(a4.id()));
+    assert_eq!(block_store.root().id(), a1.id());
+
+    let committed = block_on(setup.commit_cb_receiver.next()).unwrap();
+    assert_eq!(committed.ledger_info().consensus_block_id(), a1.id());
+}
+
+#[test]
+fn test_observer_retrieves_missing_blocks() {
+    let runtime = consensus_runtime();
+    let mut setup = ObserverSetup::new(runtime.handle());
+    let genesis = setup.validator.block_store().root();
+    let peer = setup.validator.signer().author();
+
+    let a1 = setup
+        .validator
+        .insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let a2 = setup.validator.insert_block(&a1, 2, None);
+    let a3 = setup.validator.insert_block(&a2, 3, None);
+    let a4 = setup.validator.insert_block(&a3, 4, Some(a1.block_info()));
+
+    // The observer missed the first proposals: it fetches the committed 3-chain from the peer.
+    let proposal = setup.proposal_for(&a4, a4.quorum_cert().clone());
+    block_on(setup.observer.process_proposal(proposal, peer))
+        .expect("Observer should process the proposal");
+    let block_store = setup.observer.block_store();
+    assert_eq!(block_store.root().id(), a1.id());
+    assert!(block_store.block_exists(a3.id()));
+    assert!(block_store.block_exists(a4.id()));
+
+    let synced = block_on(setup.commit_cb_receiver.next()).unwrap();
+    assert_eq!(synced.ledger_info().consensus_block_id(), a1.id());
+}
+
+#[test]
+fn test_observer_rejects_unsigned_proposals() {
+    let runtime = consensus_runtime();
+    let mut setup = ObserverSetup::new(runtime.handle());
+    let genesis = setup.validator.block_store().root();
+    let peer = setup.validator.signer().author();
+
+    // A block proposed by someone outside of the validator set.
+    let mut impostor = TreeInserter::new(ValidatorSigner::random(None));
+    let b1 = impostor.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let proposal = setup.proposal_for(&b1, certificate_for_genesis());
+    assert!(block_on(setup.observer.process_proposal(proposal, peer)).is_err());
+    assert!(!setup.observer.block_store().block_exists(b1.id()));
+}
@@ -4,6 +4,7 @@
 use super::*;
 use consensus_types::block::block_test_utils::certificate_for_genesis;
 use libra_temppath::TempPath;
+use libra_types::crypto_proxies::ValidatorSigner;
 
 #[test]
 fn test_put_get() {
@@ -48,3 +49,77 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_evidence() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+    assert_eq!(db.get_evidence::<i64>().unwrap().len(), 0);
+
+    let signer = ValidatorSigner::random(None);
+    let evidence = Evidence::DoubleProposal {
+        first: Block::<i64>::new_proposal(1, 1, 1, certificate_for_genesis(), &signer),
+        second: Block::<i64>::new_proposal(2, 1, 1, certificate_for_genesis(), &signer),
+    };
+    db.save_evidence(evidence.clone()).unwrap();
+    // The same evidence is stored only once
+    db.save_evidence(evidence.clone()).unwrap();
+    assert_eq!(db.get_evidence::<i64>().unwrap(), vec![evidence]);
+}
+
+#[test]
+fn test_open_readonly() {
+    let tmp_dir = TempPath::new();
+    assert!(ConsensusDB::open_readonly(&tmp_dir).is_err());
+
+    let db = ConsensusDB::new(&tmp_dir);
+    let blocks = vec![Block::<i64>::make_genesis_block()];
+    let qcs = vec![certificate_for_genesis()];
+    db.save_blocks_and_quorum_certificates(blocks, qcs).unwrap();
+
+    let readonly_db = ConsensusDB::open_readonly(&tmp_dir).unwrap();
+    assert_eq!(readonly_db.get_blocks::<i64>().unwrap().len(), 1);
+    assert_eq!(readonly_db.get_quorum_certificates().unwrap().len(), 1);
+    assert!(readonly_db.delete_last_vote_msg().is_err());
+}
+
+#[test]
+fn test_open_db_without_evidence() {
+    let tmp_dir = TempPath::new();
+    // A db written before the evidence column family was added
+    let cf_opts_map: ColumnFamilyOptionsMap = [
+        DEFAULT_CF_NAME,
+        BLOCK_CF_NAME,
+        QC_CF_NAME,
+        SINGLE_ENTRY_CF_NAME,
+    ]
+    .iter()
+    .map(|cf_name| (*cf_name, ColumnFamilyOptions::default()))
+    .collect();
+    let db = DB::open(tmp_dir.path().join("consensusdb"), cf_opts_map).unwrap();
+    let mut batch = SchemaBatch::new();
+    batch
+        .put::<BlockSchema<i64>>(
+            &HashValue::zero(),
+            &SchemaBlock::from_block(Block::<i64>::make_genesis_block()),
+        )
+        .unwrap();
+    db.write_schemas(batch).unwrap();
+    drop(db);
+
+    let readonly_db = ConsensusDB::open_readonly(&tmp_dir).unwrap();
+    assert_eq!(readonly_db.get_blocks::<i64>().unwrap().len(), 1);
+    assert!(readonly_db.get_evidence::<i64>().unwrap().is_empty());
+    drop(readonly_db);
+
+    // The read-write open creates the evidence column family
+    let signer = ValidatorSigner::random(None);
+    let evidence = Evidence::DoubleProposal {
+        first: Block::<i64>::new_proposal(1, 1, 1, certificate_for_genesis(), &signer),
+        second: Block::<i64>::new_proposal(2, 1, 1, certificate_for_genesis(), &signer),
+    };
+    let db = ConsensusDB::new(&tmp_dir);
+    assert_eq!(db.get_blocks::<i64>().unwrap().len(), 1);
+    db.save_evidence(evidence.clone()).unwrap();
+    assert_eq!(db.get_evidence::<i64>().unwrap(), vec![evidence]);
+}
@@ -7,14 +7,15 @@ mod schema;
 
 use crate::chained_bft::consensusdb::schema::{
     block::{BlockSchema, SchemaBlock},
+    evidence::{EvidenceSchema, SchemaEvidence},
     quorum_certificate::QCSchema,
     single_entry::{SingleEntryKey, SingleEntrySchema},
 };
 use anyhow::{ensure, Result};
-use consensus_types::{block::Block, common::Payload, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, common::Payload, evidence::Evidence, quorum_cert::QuorumCert};
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BLOCK_CF_NAME, EVIDENCE_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{
     ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME,
 };
@@ -35,6 +36,7 @@ impl ConsensusDB {
                 ColumnFamilyOptions::default(),
             ),
             (BLOCK_CF_NAME, ColumnFamilyOptions::default()),
+            (EVIDENCE_CF_NAME, ColumnFamilyOptions::default()),
             (QC_CF_NAME, ColumnFamilyOptions::default()),
             (SINGLE_ENTRY_CF_NAME, ColumnFamilyOptions::default()),
         ]
@@ -56,6 +58,23 @@ impl ConsensusDB {
         Self { db }
     }
 
+    /// Open the ConsensusDB of a node without taking the write lock, e.g. to inspect it while
+    /// the node is running. All writes fail.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("consensusdb");
+        let db = DB::open_readonly(
+            path,
+            vec![
+                DEFAULT_CF_NAME,
+                BLOCK_CF_NAME,
+                EVIDENCE_CF_NAME,
+                QC_CF_NAME,
+                SINGLE_ENTRY_CF_NAME,
+            ],
+        )?;
+        Ok(Self { db })
+    }
+
     pub fn get_data<T: Payload>(
         &self,
     ) -> Result<(
@@ -144,6 +163,27 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist the evidence of a misbehaving validator, keyed by the evidence id so that the same
+    /// evidence is only stored once.
+    pub fn save_evidence<T: Payload>(&self, evidence: Evidence<T>) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<EvidenceSchema<T>>(&evidence.id(), &SchemaEvidence::from_evidence(evidence))?;
+        self.commit(batch)
+    }
+
+    /// Get all the persisted evidence.
+    pub fn get_evidence<T: Payload>(&self) -> Result<Vec<Evidence<T>>> {
+        // A db written before the evidence was persisted has no evidence column family when it's
+        // opened read-only.
+        if !self.db.has_cf(EVIDENCE_CF_NAME) {
+            return Ok(vec![]);
+        }
+        let mut iter = self.db.iter::<EvidenceSchema<T>>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|value| value.map(|(_, v)| v.borrow_into_evidence().clone()))
+            .collect()
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<()> {
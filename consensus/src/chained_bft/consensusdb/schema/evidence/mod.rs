@@ -0,0 +1,79 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the evidence of misbehaving validators.
+//!
+//! Serialized evidence bytes identified by the evidence id (hash of the evidence).
+//! ```text
+//! |<---key---->|<---value--->|
+//! | evidence_id|   evidence  |
+//! ```
+
+use super::EVIDENCE_CF_NAME;
+use anyhow::Result;
+use consensus_types::{common::Payload, evidence::Evidence};
+use libra_crypto::HashValue;
+use schemadb::schema::{KeyCodec, Schema, ValueCodec};
+use std::{cmp, fmt, marker::PhantomData};
+
+pub struct EvidenceSchema<T: Payload> {
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> Schema for EvidenceSchema<T> {
+    const COLUMN_FAMILY_NAME: schemadb::ColumnFamilyName = EVIDENCE_CF_NAME;
+    type Key = HashValue;
+    type Value = SchemaEvidence<T>;
+}
+
+impl<T: Payload> KeyCodec<EvidenceSchema<T>> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+#[derive(Clone)]
+/// SchemaEvidence is a crate wrapper for Evidence that is defined outside this crate.
+/// ValueCodec cannot be implemented for Evidence here as Evidence is defined in
+/// consensus_types crate (E0210).
+pub struct SchemaEvidence<T: Payload>(Evidence<T>);
+
+impl<T: Payload> SchemaEvidence<T> {
+    pub fn from_evidence(evidence: Evidence<T>) -> SchemaEvidence<T> {
+        Self(evidence)
+    }
+
+    pub fn borrow_into_evidence(&self) -> &Evidence<T> {
+        &self.0
+    }
+}
+
+impl<T: Payload> cmp::PartialEq for SchemaEvidence<T> {
+    fn eq(&self, other: &SchemaEvidence<T>) -> bool {
+        self.borrow_into_evidence()
+            .eq(&other.borrow_into_evidence())
+    }
+}
+
+impl<T: Payload> fmt::Debug for SchemaEvidence<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.borrow_into_evidence().fmt(f)
+    }
+}
+
+impl<T: Payload> ValueCodec<EvidenceSchema<T>> for SchemaEvidence<T> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(lcs::to_bytes(&self.0)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(SchemaEvidence(lcs::from_bytes(data)?))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,20 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
+use libra_types::crypto_proxies::ValidatorSigner;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let signer = ValidatorSigner::random(None);
+    let evidence = Evidence::DoubleProposal {
+        first: Block::<i64>::new_proposal(1, 1, 1, certificate_for_genesis(), &signer),
+        second: Block::<i64>::new_proposal(2, 1, 1, certificate_for_genesis(), &signer),
+    };
+    assert_encode_decode::<EvidenceSchema<i64>>(
+        &evidence.id(),
+        &SchemaEvidence::from_evidence(evidence),
+    );
+}
@@ -0,0 +1,153 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline access to the data persisted in the ConsensusDB of a node, to inspect it and to repair
+//! it when consensus can not recover from it.
+
+use crate::chained_bft::{consensusdb::ConsensusDB, persistent_storage::RecoveryData};
+use anyhow::{format_err, Result};
+use consensus_types::{
+    block::Block, evidence::EvidenceBundle, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
+};
+use libra_config::config::NodeConfig;
+use libra_crypto::HashValue;
+use libra_types::{
+    crypto_proxies::ValidatorSet, ledger_info::LedgerInfo, transaction::SignedTransaction,
+};
+
+/// The payload of the blocks proposed by the validators.
+pub type NodePayload = Vec<SignedTransaction>;
+
+/// All the data persisted in a ConsensusDB.
+pub struct PersistedData {
+    /// The last vote sent by the node.
+    pub last_vote: Option<Vote>,
+    /// The highest timeout certificate known by the node.
+    pub highest_timeout_certificate: Option<TimeoutCertificate>,
+    /// The blocks, sorted by (epoch, round).
+    pub blocks: Vec<Block<NodePayload>>,
+    /// The quorum certificates, sorted by the (epoch, round) of the certified block.
+    pub quorum_certs: Vec<QuorumCert>,
+}
+
+/// The root consensus recovers from at startup.
+pub struct RecoveredRoot {
+    /// The last committed block.
+    pub block: Block<NodePayload>,
+    /// The quorum certificate certifying the root block.
+    pub quorum_cert: QuorumCert,
+    /// The quorum certificate whose ledger info commits the root block.
+    pub ledger_info: QuorumCert,
+}
+
+/// Reads the ConsensusDB of a node, and prunes it when opened for writes.
+pub struct ConsensusDBInspector {
+    db: ConsensusDB,
+}
+
+impl ConsensusDBInspector {
+    /// Opens the ConsensusDB of the node without locking it, so that the node can keep running.
+    pub fn open_readonly(config: &NodeConfig) -> Result<Self> {
+        Ok(Self {
+            db: ConsensusDB::open_readonly(config.storage.dir())?,
+        })
+    }
+
+    /// Opens the ConsensusDB of the node for writes, which requires the node to be stopped.
+    pub fn open(config: &NodeConfig) -> Self {
+        Self {
+            db: ConsensusDB::new(config.storage.dir()),
+        }
+    }
+
+    /// Reads all the data persisted by consensus. The vote and the timeout certificate that fail
+    /// to deserialize are reported as errors.
+    pub fn persisted_data(&self) -> Result<PersistedData> {
+        let (last_vote, highest_timeout_certificate, mut blocks, mut quorum_certs) =
+            self.db.get_data::<NodePayload>()?;
+        blocks.sort_by_key(|block| (block.epoch(), block.round()));
+        quorum_certs.sort_by_key(|qc| (qc.certified_block().epoch(), qc.certified_block().round()));
+        Ok(PersistedData {
+            last_vote: last_vote.map(|vote| lcs::from_bytes(&vote)).transpose()?,
+            highest_timeout_certificate: highest_timeout_certificate
+                .map(|tc| lcs::from_bytes(&tc))
+                .transpose()?,
+            blocks,
+            quorum_certs,
+        })
+    }
+
+    /// Finds the root the same way consensus does at startup, given the latest ledger info
+    /// committed in storage.
+    pub fn find_root(&self, storage_ledger: &LedgerInfo) -> Result<RecoveredRoot> {
+        let PersistedData {
+            mut blocks,
+            mut quorum_certs,
+            ..
+        } = self.persisted_data()?;
+        let (block, quorum_cert, ledger_info) =
+            RecoveryData::find_root(&mut blocks, &mut quorum_certs, storage_ledger)?;
+        Ok(RecoveredRoot {
+            block,
+            quorum_cert,
+            ledger_info,
+        })
+    }
+
+    /// Bundles the evidence of misbehavior collected in the given epoch, along with the validator
+    /// set of that epoch, and verifies the bundle before returning it.
+    pub fn export_evidence(
+        &self,
+        epoch: u64,
+        validator_set: ValidatorSet,
+    ) -> Result<EvidenceBundle<NodePayload>> {
+        let bundle = EvidenceBundle::new(epoch, validator_set, self.db.get_evidence()?);
+        bundle.verify()?;
+        Ok(bundle)
+    }
+
+    /// Returns the ids of the blocks that do not descend from the given root, which must be
+    /// persisted along with its quorum certificate.
+    pub fn blocks_to_prune(&self, root_id: HashValue) -> Result<Vec<HashValue>> {
+        let PersistedData {
+            mut blocks,
+            mut quorum_certs,
+            ..
+        } = self.persisted_data()?;
+        let root_idx = blocks
+            .iter()
+            .position(|block| block.id() == root_id)
+            .ok_or_else(|| format_err!("Root {} not found in ConsensusDB", root_id))?;
+        if !quorum_certs
+            .iter()
+            .any(|qc| qc.certified_block().id() == root_id)
+        {
+            return Err(format_err!(
+                "No QC found in ConsensusDB for root {}",
+                root_id
+            ));
+        }
+        blocks.remove(root_idx);
+        Ok(RecoveryData::find_blocks_to_prune(
+            root_id,
+            &mut blocks,
+            &mut quorum_certs,
+        ))
+    }
+
+    /// Deletes the blocks that do not descend from the given root along with their quorum
+    /// certificates, and returns their ids.
+    pub fn prune_to_root(&self, root_id: HashValue) -> Result<Vec<HashValue>> {
+        let block_ids = self.blocks_to_prune(root_id)?;
+        if !block_ids.is_empty() {
+            self.db
+                .delete_blocks_and_quorum_certificates::<NodePayload>(block_ids.clone())?;
+        }
+        Ok(block_ids)
+    }
+}
+
+#[cfg(test)]
+#[path = "consensusdb_inspector_test.rs"]
+mod consensusdb_inspector_test;
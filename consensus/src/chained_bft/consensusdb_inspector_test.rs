@@ -0,0 +1,129 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use consensus_types::block::block_test_utils::{
+    certificate_for_genesis, placeholder_certificate_for_block,
+};
+use consensus_types::evidence::Evidence;
+use libra_temppath::TempPath;
+use libra_types::{crypto_proxies::ValidatorSigner, validator_public_keys::ValidatorPublicKeys};
+
+fn node_config(tmp_dir: &TempPath) -> NodeConfig {
+    let mut config = NodeConfig::default();
+    config.storage.dir = tmp_dir.path().to_path_buf();
+    config
+}
+
+/// Persists genesis <- a1 and genesis <- b1, only a1 is certified.
+fn populate(inspector: &ConsensusDBInspector) -> (Block<NodePayload>, Block<NodePayload>) {
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let genesis = Block::<NodePayload>::make_genesis_block();
+    let a1 = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer);
+    let b1 = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &signer);
+    let a1_qc = placeholder_certificate_for_block(
+        vec![&signer],
+        a1.id(),
+        a1.round(),
+        genesis_qc.certified_block().id(),
+        0,
+    );
+    inspector
+        .db
+        .save_blocks_and_quorum_certificates(
+            vec![b1.clone(), a1.clone(), genesis],
+            vec![a1_qc, genesis_qc],
+        )
+        .unwrap();
+    (a1, b1)
+}
+
+#[test]
+fn test_persisted_data_and_root() {
+    let tmp_dir = TempPath::new();
+    let config = node_config(&tmp_dir);
+    let (a1, b1) = populate(&ConsensusDBInspector::open(&config));
+
+    let inspector = ConsensusDBInspector::open_readonly(&config).unwrap();
+    let data = inspector.persisted_data().unwrap();
+    assert!(data.last_vote.is_none());
+    assert!(data.highest_timeout_certificate.is_none());
+    let rounds: Vec<_> = data.blocks.iter().map(|block| block.round()).collect();
+    assert_eq!(rounds, vec![0, 1, 2]);
+    assert_eq!(data.quorum_certs.len(), 2);
+
+    let root = inspector.find_root(&LedgerInfo::genesis()).unwrap();
+    assert_eq!(
+        root.block.id(),
+        certificate_for_genesis().certified_block().id()
+    );
+
+    let mut to_prune = inspector.blocks_to_prune(a1.id()).unwrap();
+    to_prune.sort();
+    let mut expected = vec![root.block.id(), b1.id()];
+    expected.sort();
+    assert_eq!(to_prune, expected);
+    // b1 has no QC, it can't be a root.
+    assert!(inspector.blocks_to_prune(b1.id()).is_err());
+    // The read-only instance does not prune.
+    assert!(inspector.prune_to_root(a1.id()).is_err());
+}
+
+#[test]
+fn test_prune_to_root() {
+    let tmp_dir = TempPath::new();
+    let config = node_config(&tmp_dir);
+    let inspector = ConsensusDBInspector::open(&config);
+    let (a1, _) = populate(&inspector);
+
+    assert_eq!(inspector.prune_to_root(a1.id()).unwrap().len(), 2);
+    let data = inspector.persisted_data().unwrap();
+    assert_eq!(data.blocks, vec![a1]);
+    assert_eq!(data.quorum_certs.len(), 1);
+    assert!(inspector.prune_to_root(HashValue::zero()).is_err());
+}
+
+#[test]
+fn test_export_evidence() {
+    let tmp_dir = TempPath::new();
+    let config = node_config(&tmp_dir);
+    let inspector = ConsensusDBInspector::open(&config);
+
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let evidence = Evidence::DoubleProposal {
+        first: Block::<NodePayload>::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer),
+        second: Block::<NodePayload>::new_proposal(vec![], 1, 2, genesis_qc, &signer),
+    };
+    inspector.db.save_evidence(evidence.clone()).unwrap();
+    let validator_set = ValidatorSet::new(vec![ValidatorPublicKeys::new_with_random_network_keys(
+        signer.author(),
+        signer.public_key(),
+        1,
+    )]);
+
+    let bundle = inspector
+        .export_evidence(evidence.epoch(), validator_set.clone())
+        .unwrap();
+    assert_eq!(bundle.evidence(), &[evidence.clone()]);
+    // The exported bundle can be verified by anyone once deserialized.
+    let bytes = lcs::to_bytes(&bundle).unwrap();
+    let imported: EvidenceBundle<NodePayload> = lcs::from_bytes(&bytes).unwrap();
+    assert!(imported.verify().is_ok());
+
+    // Evidence of other epochs is left out.
+    let bundle = inspector
+        .export_evidence(evidence.epoch() + 1, validator_set)
+        .unwrap();
+    assert!(bundle.evidence().is_empty());
+    // Evidence that can't be verified against the validator set is not exported.
+    let other_set = ValidatorSet::new(vec![ValidatorPublicKeys::new_with_random_network_keys(
+        signer.author(),
+        ValidatorSigner::random(None).public_key(),
+        1,
+    )]);
+    assert!(inspector
+        .export_evidence(evidence.epoch(), other_set)
+        .is_err());
+}
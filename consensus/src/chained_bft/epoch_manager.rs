@@ -12,12 +12,12 @@ use crate::{
             proposer_election::ProposerElection,
             rotating_proposer_election::{choose_leader, RotatingProposer},
         },
-        network::{FromNetworkMsg, IncomingBlockRetrievalRequest, NetworkSender},
+        network::{FromNetworkMsg, IncomingBlockRetrievalRequest, NetworkSender, ObserverSender},
         persistent_storage::{PersistentStorage, RecoveryData},
     },
     counters,
     state_replication::{StateComputer, TxnManager},
-    util::time_service::{ClockTimeService, TimeService},
+    util::time_service::TimeService,
 };
 use anyhow::ensure;
 use consensus_types::{
@@ -39,8 +39,14 @@ use network::{
     proto::{ConsensusMsg, ConsensusMsg_oneof},
     validator_network::{ConsensusNetworkSender, Event},
 };
+use rand::rngs::StdRng;
 use safety_rules::SafetyRulesManager;
-use std::{cmp::Ordering, convert::TryInto, sync::Arc, time::Duration};
+use std::{
+    cmp::Ordering,
+    convert::TryInto,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 // Manager the components that shared across epoch and spawn per-epoch EventProcessor with
 // epoch-specific input.
@@ -48,7 +54,7 @@ pub struct EpochManager<T> {
     author: Author,
     epoch_info: EpochInfo,
     config: ConsensusConfig,
-    time_service: Arc<ClockTimeService>,
+    time_service: Arc<dyn TimeService>,
     self_sender: channel::Sender<anyhow::Result<Event<ConsensusMsg>>>,
     network_sender: ConsensusNetworkSender,
     timeout_sender: channel::Sender<Round>,
@@ -56,6 +62,8 @@ pub struct EpochManager<T> {
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     storage: Arc<dyn PersistentStorage<T>>,
     safety_rules_manager: SafetyRulesManager<T>,
+    observer_sender: Option<ObserverSender>,
+    peer_rng: Arc<Mutex<StdRng>>,
 }
 
 /// Message for the current epoch.
@@ -83,7 +91,7 @@ impl<T: Payload> EpochManager<T> {
         author: Author,
         epoch_info: EpochInfo,
         config: ConsensusConfig,
-        time_service: Arc<ClockTimeService>,
+        time_service: Arc<dyn TimeService>,
         self_sender: channel::Sender<anyhow::Result<Event<ConsensusMsg>>>,
         network_sender: ConsensusNetworkSender,
         timeout_sender: channel::Sender<Round>,
@@ -91,6 +99,8 @@ impl<T: Payload> EpochManager<T> {
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         storage: Arc<dyn PersistentStorage<T>>,
         safety_rules_manager: SafetyRulesManager<T>,
+        observer_sender: Option<ObserverSender>,
+        peer_rng: Arc<Mutex<StdRng>>,
     ) -> Self {
         Self {
             author,
@@ -104,6 +114,8 @@ impl<T: Payload> EpochManager<T> {
             state_computer,
             storage,
             safety_rules_manager,
+            observer_sender,
+            peer_rng,
         }
     }
 
@@ -377,7 +389,7 @@ impl<T: Payload> EpochManager<T> {
         );
         block_on(
             self.network_sender
-                .update_eligible_nodes(initial_data.validator_keys()),
+                .update_eligible_nodes(epoch, initial_data.validator_keys()),
         )
         .expect("Unable to update network's eligible peers");
         let last_vote = initial_data.last_vote();
@@ -408,12 +420,16 @@ impl<T: Payload> EpochManager<T> {
             self.create_pacemaker(self.time_service.clone(), self.timeout_sender.clone());
 
         let proposer_election = self.create_proposer_election(epoch, &validators);
-        let network_sender = NetworkSender::new(
+        let mut network_sender = NetworkSender::new(
             self.author,
             self.network_sender.clone(),
             self.self_sender.clone(),
             validators.clone(),
         );
+        if let Some(observer_sender) = &self.observer_sender {
+            network_sender.set_observer_sender(observer_sender.clone());
+        }
+        network_sender.set_peer_rng(Arc::clone(&self.peer_rng));
 
         let event_processor = EventProcessor::new(
             block_store,
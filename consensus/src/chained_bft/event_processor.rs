@@ -6,6 +6,7 @@ use crate::{
         block_storage::{
             BlockReader, BlockRetriever, BlockStore, PendingVotes, VoteReceptionResult,
         },
+        evidence_collector::EvidenceCollector,
         liveness::{
             pacemaker::{NewRoundEvent, NewRoundReason, Pacemaker},
             proposal_generator::ProposalGenerator,
@@ -25,6 +26,7 @@ use consensus_types::{
     accumulator_extension_proof::AccumulatorExtensionProof,
     block::Block,
     common::{Author, Payload, Round},
+    evidence::Evidence,
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
@@ -33,6 +35,7 @@ use consensus_types::{
     vote_msg::VoteMsg,
     vote_proposal::VoteProposal,
 };
+use debug_interface::event;
 use libra_crypto::hash::TransactionAccumulatorHasher;
 use libra_logger::prelude::*;
 use libra_prost_ext::MessageExt;
@@ -51,9 +54,9 @@ use consensus_types::block_retrieval::{BlockRetrievalResponse, BlockRetrievalSta
 use safety_rules::ConsensusState;
 use safety_rules::TSafetyRules;
 use std::convert::TryInto;
-use std::time::Instant;
 use std::{sync::Arc, time::Duration};
 use termion::color::*;
+use tokio::time::Instant;
 
 #[cfg(test)]
 #[path = "event_processor_test.rs"]
@@ -71,6 +74,7 @@ pub mod event_processor_fuzzing;
 pub struct EventProcessor<T> {
     block_store: Arc<BlockStore<T>>,
     pending_votes: PendingVotes,
+    evidence_collector: EvidenceCollector<T>,
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
@@ -105,10 +109,12 @@ impl<T: Payload> EventProcessor<T> {
             (v, round)
         });
         let pending_votes = PendingVotes::new();
+        let evidence_collector = EvidenceCollector::new();
 
         Self {
             block_store,
             pending_votes,
+            evidence_collector,
             pacemaker,
             proposer_election,
             proposal_generator,
@@ -190,7 +196,10 @@ impl<T: Payload> EventProcessor<T> {
     /// Process a ProposalMsg, pre_process would bring all the dependencies and filter out invalid
     /// proposal, process_proposed_block would execute and decide whether to vote for it.
     pub async fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<T>) {
+        let observed_proposal = proposal_msg.clone();
         if let Some(block) = self.pre_process_proposal(proposal_msg).await {
+            // The dependencies of the proposal are available: observers can process it too.
+            self.network.publish_proposal(observed_proposal).await;
             self.process_proposed_block(block).await
         }
     }
@@ -221,6 +230,12 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        if let Some(evidence) = self
+            .evidence_collector
+            .observe_proposal(proposal_msg.proposal())
+        {
+            self.report_evidence(evidence);
+        }
         if let Err(e) = self
             .sync_up(proposal_msg.sync_info(), proposal_msg.proposer(), true)
             .await
@@ -462,6 +477,8 @@ impl<T: Payload> EventProcessor<T> {
                 }
                 let finality_proof = qc.ledger_info().clone();
                 self.process_commit(finality_proof).await;
+                self.evidence_collector
+                    .prune(self.block_store.root().round());
             }
         }
         let mut tc_round = None;
@@ -704,6 +721,15 @@ impl<T: Payload> EventProcessor<T> {
     /// 2. Add the vote to the store and check whether it finishes a QC.
     /// 3. Once the QC successfully formed, notify the Pacemaker.
     pub async fn process_vote(&mut self, vote_msg: Box<VoteMsg>) {
+        let voted_block = self
+            .block_store
+            .get_block(vote_msg.vote().vote_data().proposed().id());
+        for evidence in self
+            .evidence_collector
+            .observe_vote(vote_msg.vote(), voted_block.as_ref().map(|b| b.block()))
+        {
+            self.report_evidence(evidence);
+        }
         // Check whether this validator is a valid recipient of the vote.
         if !vote_msg.vote().is_timeout() {
             // Unlike timeout votes regular votes are sent to the leaders of the next round only.
@@ -740,6 +766,28 @@ impl<T: Payload> EventProcessor<T> {
         }
     }
 
+    /// Record the evidence of a misbehaving validator: it is counted, logged, exposed to the debug
+    /// interface and persisted so that it can be exported later on.
+    fn report_evidence(&self, evidence: Evidence<T>) {
+        counters::EVIDENCE_COUNT
+            .with_label_values(&[evidence.kind()])
+            .inc();
+        security_log(SecurityEvent::ConsensusEquivocation)
+            .error(evidence.kind())
+            .data(&evidence)
+            .log();
+        event!("evidence",
+            "id": evidence.id().short_str(),
+            "kind": evidence.kind(),
+            "author": evidence.author().short_str(),
+            "epoch": evidence.epoch(),
+            "round": evidence.round(),
+        );
+        if let Err(e) = self.storage.save_evidence(evidence) {
+            error!("Failed to persist evidence: {:?}", e);
+        }
+    }
+
     /// Add a vote to the pending votes.
     /// If a new QC / TC is formed then
     /// 1) fetch missing dependencies if required, and then
@@ -847,6 +895,9 @@ impl<T: Payload> EventProcessor<T> {
                     /* more = */ false,
                 ))
                 .await
+        } else {
+            let sync_info = self.gen_sync_info();
+            self.network.publish_sync_info(sync_info).await
         }
     }
 
@@ -0,0 +1,140 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    evidence::Evidence,
+    quorum_cert::QuorumCert,
+    vote::Vote,
+};
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+#[path = "evidence_collector_test.rs"]
+mod evidence_collector_test;
+
+/// EvidenceCollector keeps track of the signed messages seen from the other validators in the
+/// current epoch and produces `Evidence` whenever two of them conflict:
+/// * two votes of the same author with different LedgerInfos in the same round,
+/// * two different proposals of the same author in the same round,
+/// * a vote that extends a block below the preferred round implied by an earlier vote of the
+/// same author.
+/// The collector only reports each kind of misbehavior once per (author, round).
+/// It should be recreated upon reconfiguration.
+pub struct EvidenceCollector<T> {
+    /// The first proposal seen from an author in a round.
+    proposals: HashMap<(Author, Round), Block<T>>,
+    /// The first vote seen from an author in a round.
+    votes: HashMap<(Author, Round), Vote>,
+    /// The vote establishing the highest preferred round known for an author, along with the
+    /// QC carried by the voted block (its parent round is the preferred round).
+    locks: HashMap<Author, (Vote, QuorumCert)>,
+    /// The misbehaviors already reported, to avoid reporting duplicates.
+    reported: HashSet<(Author, Round, &'static str)>,
+}
+
+impl<T: Payload> EvidenceCollector<T> {
+    pub fn new() -> Self {
+        Self {
+            proposals: HashMap::new(),
+            votes: HashMap::new(),
+            locks: HashMap::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Records a proposal and returns evidence if its author already proposed a different block
+    /// in the same round. Nil blocks do not have an author and are ignored.
+    pub fn observe_proposal(&mut self, proposal: &Block<T>) -> Option<Evidence<T>> {
+        let author = proposal.author()?;
+        let first = self
+            .proposals
+            .entry((author, proposal.round()))
+            .or_insert_with(|| proposal.clone());
+        if first.id() == proposal.id() {
+            return None;
+        }
+        let evidence = Evidence::DoubleProposal {
+            first: first.clone(),
+            second: proposal.clone(),
+        };
+        self.filter_reported(evidence)
+    }
+
+    /// Records a vote and returns all the evidence it completes. `voted_block` is the block the
+    /// vote is for, if it is known locally: its QC is required to track the preferred round of
+    /// the author.
+    pub fn observe_vote(
+        &mut self,
+        vote: &Vote,
+        voted_block: Option<&Block<T>>,
+    ) -> Vec<Evidence<T>> {
+        let mut evidence = vec![];
+        let round = vote.vote_data().proposed().round();
+        let first = self
+            .votes
+            .entry((vote.author(), round))
+            .or_insert_with(|| vote.clone());
+        if first.ledger_info() != vote.ledger_info() {
+            evidence.push(Evidence::DoubleVote {
+                first: first.clone(),
+                second: vote.clone(),
+            });
+        }
+
+        if let Some((locking_vote, locking_qc)) = self.locks.get(&vote.author()) {
+            if locking_vote.vote_data().proposed().round() < round
+                && vote.vote_data().parent().round() < locking_qc.parent_block().round()
+            {
+                evidence.push(Evidence::PreferredRoundViolation {
+                    locking_vote: locking_vote.clone(),
+                    locking_qc: locking_qc.clone(),
+                    violating_vote: vote.clone(),
+                });
+            }
+        }
+
+        if let Some(block) = voted_block {
+            self.update_lock(vote, block.quorum_cert());
+        }
+
+        evidence
+            .into_iter()
+            .filter_map(|e| self.filter_reported(e))
+            .collect()
+    }
+
+    /// Drops the messages for rounds lower than `min_round`: equivocations can only be detected
+    /// for the rounds that are still in flight.
+    pub fn prune(&mut self, min_round: Round) {
+        self.proposals.retain(|(_, round), _| *round >= min_round);
+        self.votes.retain(|(_, round), _| *round >= min_round);
+        self.reported.retain(|(_, round, _)| *round >= min_round);
+    }
+
+    fn update_lock(&mut self, vote: &Vote, qc: &QuorumCert) {
+        if qc.certified_block().id() != vote.vote_data().parent().id() {
+            return;
+        }
+        let preferred_round = qc.parent_block().round();
+        let current_preferred_round = self
+            .locks
+            .get(&vote.author())
+            .map_or(0, |(_, locking_qc)| locking_qc.parent_block().round());
+        if preferred_round > current_preferred_round {
+            self.locks.insert(vote.author(), (vote.clone(), qc.clone()));
+        }
+    }
+
+    fn filter_reported(&mut self, evidence: Evidence<T>) -> Option<Evidence<T>> {
+        if self
+            .reported
+            .insert((evidence.author(), evidence.round(), evidence.kind()))
+        {
+            Some(evidence)
+        } else {
+            None
+        }
+    }
+}
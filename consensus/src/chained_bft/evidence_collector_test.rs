@@ -0,0 +1,120 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::evidence_collector::EvidenceCollector;
+use consensus_types::{
+    block::{block_test_utils::*, Block},
+    common::Round,
+    evidence::Evidence,
+    vote::Vote,
+    vote_data::VoteData,
+};
+use libra_crypto::HashValue;
+use libra_types::{
+    block_info::BlockInfo,
+    crypto_proxies::{random_validator_verifier, ValidatorSigner},
+    ledger_info::LedgerInfo,
+};
+
+fn random_ledger_info() -> LedgerInfo {
+    LedgerInfo::new(
+        BlockInfo::new(1, 0, HashValue::random(), HashValue::random(), 0, 0, None),
+        HashValue::zero(),
+    )
+}
+
+fn vote_for(signer: &ValidatorSigner, proposed: BlockInfo, parent: BlockInfo) -> Vote {
+    Vote::new(
+        VoteData::new(proposed, parent),
+        signer.author(),
+        random_ledger_info(),
+        signer,
+    )
+}
+
+fn block_info(round: Round) -> BlockInfo {
+    BlockInfo::new(1, round, HashValue::random(), HashValue::zero(), 0, 0, None)
+}
+
+#[test]
+fn test_double_vote_detection() {
+    let (signers, validator) = random_validator_verifier(4, None, false);
+    let mut collector = EvidenceCollector::<u64>::new();
+
+    let vote = vote_for(&signers[0], block_info(1), block_info(0));
+    assert!(collector.observe_vote(&vote, None).is_empty());
+    // The very same vote is not an equivocation
+    assert!(collector.observe_vote(&vote, None).is_empty());
+    // Another author voting for something else is fine
+    let other = vote_for(&signers[1], block_info(1), block_info(0));
+    assert!(collector.observe_vote(&other, None).is_empty());
+
+    let conflicting = vote_for(&signers[0], block_info(1), block_info(0));
+    let evidence = collector.observe_vote(&conflicting, None);
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence[0].kind(), "double_vote");
+    assert!(evidence[0].verify(&validator).is_ok());
+
+    // Already reported equivocations are not reported again
+    let conflicting = vote_for(&signers[0], block_info(1), block_info(0));
+    assert!(collector.observe_vote(&conflicting, None).is_empty());
+
+    // Pruning forgets about the old rounds
+    collector.prune(2);
+    assert!(collector.observe_vote(&vote, None).is_empty());
+}
+
+#[test]
+fn test_double_proposal_detection() {
+    let (signers, validator) = random_validator_verifier(4, None, false);
+    let mut collector = EvidenceCollector::new();
+    let genesis_qc = certificate_for_genesis();
+
+    let proposal = Block::new_proposal(1, 1, 1, genesis_qc.clone(), &signers[0]);
+    assert!(collector.observe_proposal(&proposal).is_none());
+    assert!(collector.observe_proposal(&proposal).is_none());
+    let nil_block = Block::new_nil(1, genesis_qc.clone());
+    assert!(collector.observe_proposal(&nil_block).is_none());
+
+    let conflicting = Block::new_proposal(2, 1, 1, genesis_qc, &signers[0]);
+    let evidence = collector
+        .observe_proposal(&conflicting)
+        .expect("Double proposal should be detected");
+    assert_eq!(evidence.kind(), "double_proposal");
+    assert!(evidence.verify(&validator).is_ok());
+}
+
+#[test]
+fn test_preferred_round_violation_detection() {
+    let (signers, validator) = random_validator_verifier(4, None, false);
+    let mut collector = EvidenceCollector::<u64>::new();
+
+    // A block at round 4 carrying a QC for round 3 (whose parent is round 2).
+    let locking_qc =
+        gen_test_certificate(signers.iter().collect(), block_info(3), block_info(2), None);
+    let locked_block = Block::new_proposal(0, 4, 1, locking_qc.clone(), &signers[1]);
+    let locking_vote = vote_for(
+        &signers[0],
+        block_info(4),
+        locking_qc.certified_block().clone(),
+    );
+    assert!(collector
+        .observe_vote(&locking_vote, Some(&locked_block))
+        .is_empty());
+
+    // Voting in round 5 for a block extending round 2 respects the preferred round.
+    let vote = vote_for(&signers[0], block_info(5), block_info(2));
+    assert!(collector.observe_vote(&vote, None).is_empty());
+
+    // Voting in round 6 for a block extending round 1 does not.
+    let vote = vote_for(&signers[0], block_info(6), block_info(1));
+    let evidence = collector.observe_vote(&vote, None);
+    assert_eq!(evidence.len(), 1);
+    match &evidence[0] {
+        Evidence::PreferredRoundViolation {
+            locking_vote: v, ..
+        } => assert_eq!(v, &locking_vote),
+        e => panic!("Unexpected evidence {}", e),
+    }
+    assert!(evidence[0].verify(&validator).is_ok());
+}
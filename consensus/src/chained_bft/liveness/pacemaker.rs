@@ -8,11 +8,8 @@ use crate::{
 use channel;
 use consensus_types::common::Round;
 use libra_logger::prelude::*;
-use std::{
-    fmt,
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::time::Instant;
 
 /// A reason for starting a new round: introduced for monitoring / debug purposes.
 #[derive(Eq, Debug, PartialEq)]
@@ -17,8 +17,9 @@ use consensus_types::{
 use libra_logger::prelude::*;
 use std::{
     sync::{Arc, Mutex},
-    time::{Duration, Instant},
+    time::Duration,
 };
+use tokio::time::Instant;
 
 #[cfg(test)]
 #[path = "proposal_generator_test.rs"]
@@ -13,10 +13,8 @@ use consensus_types::block::block_test_utils::{certificate_for_genesis, gen_test
 use consensus_types::block::Block;
 use futures::executor::block_on;
 use libra_types::crypto_proxies::ValidatorSigner;
-use std::{
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
 
 fn minute_from_now() -> Instant {
     Instant::now() + Duration::new(60, 0)
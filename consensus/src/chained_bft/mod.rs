@@ -5,6 +5,9 @@ mod consensusdb;
 
 mod block_storage;
 pub mod chained_bft_smr;
+pub mod consensus_observer;
+pub mod consensusdb_inspector;
+mod evidence_collector;
 mod network;
 
 pub mod epoch_manager;
@@ -14,6 +17,8 @@ pub mod persistent_storage;
 mod chained_bft_smr_test;
 #[cfg(test)]
 mod network_tests;
+#[cfg(test)]
+mod simulation;
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod test_utils;
@@ -15,7 +15,7 @@ use consensus_types::{
 };
 use futures::{
     channel::{mpsc, oneshot},
-    select, SinkExt, Stream, StreamExt,
+    future, select, SinkExt, Stream, StreamExt,
 };
 use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
@@ -27,13 +27,14 @@ use network::{
         ConsensusMsg, ConsensusMsg_oneof, Proposal, RequestBlock, RequestEpoch,
         SyncInfo as SyncInfoProto, VoteMsg as VoteMsgProto,
     },
-    validator_network::{ConsensusNetworkSender, Event, RpcError},
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
 };
+use rand::{prelude::*, rngs::StdRng};
 use smallvec::SmallVec;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     convert::{TryFrom, TryInto},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
@@ -55,6 +56,10 @@ pub struct NetworkSender {
     // Note that we do not support self rpc requests as it might cause infinite recursive calls.
     self_sender: channel::Sender<anyhow::Result<Event<ConsensusMsg>>>,
     validators: Arc<ValidatorVerifier>,
+    // The observers following consensus on the full-node network, if any.
+    observer_sender: Option<ObserverSender>,
+    // Picks the peers to retrieve blocks from: seeded by the simulations to be reproducible.
+    peer_rng: Arc<Mutex<StdRng>>,
 }
 
 impl NetworkSender {
@@ -69,9 +74,27 @@ impl NetworkSender {
             network_sender,
             self_sender,
             validators,
+            observer_sender: None,
+            peer_rng: Arc::new(Mutex::new(StdRng::from_entropy())),
         }
     }
 
+    /// Publishes the proposals, commit certificates and epoch changes to the given observers in
+    /// addition to the validators.
+    pub fn set_observer_sender(&mut self, observer_sender: ObserverSender) {
+        self.observer_sender = Some(observer_sender);
+    }
+
+    /// Shares the random generator picking the block retrieval peers, e.g. across the epochs.
+    pub fn set_peer_rng(&mut self, peer_rng: Arc<Mutex<StdRng>>) {
+        self.peer_rng = peer_rng;
+    }
+
+    /// Picks the index of a random peer among `num_peers`.
+    pub fn random_peer_index(&self, num_peers: usize) -> usize {
+        self.peer_rng.lock().unwrap().gen_range(0, num_peers)
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
     pub async fn request_block<T: Payload>(
@@ -238,9 +261,52 @@ impl NetworkSender {
         let msg = ConsensusMsg {
             message: Some(ConsensusMsg_oneof::EpochChange(proof.into())),
         };
+        if let Some(observer_sender) = self.observer_sender.as_mut() {
+            observer_sender.publish(msg.clone()).await;
+        }
         self.broadcast(msg).await
     }
 
+    /// Publishes a proposal accepted by this validator to the observers (no-op without
+    /// observers), so that they can execute it ahead of its commit.
+    pub async fn publish_proposal<T: Payload>(&mut self, proposal: ProposalMsg<T>) {
+        let observer_sender = match self.observer_sender.as_mut() {
+            Some(observer_sender) => observer_sender,
+            None => return,
+        };
+        let proposal = match proposal.try_into() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Fail to serialize ProposalMsg: {:?}", e);
+                return;
+            }
+        };
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::Proposal(proposal)),
+        };
+        observer_sender.publish(msg).await
+    }
+
+    /// Publishes the sync info carrying the latest commit certificate to the observers (no-op
+    /// without observers), so that they can commit without waiting for the next proposal.
+    pub async fn publish_sync_info(&mut self, sync_info: SyncInfo) {
+        let observer_sender = match self.observer_sender.as_mut() {
+            Some(observer_sender) => observer_sender,
+            None => return,
+        };
+        let sync_info = match sync_info.try_into() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Fail to serialize SyncInfo: {:?}", e);
+                return;
+            }
+        };
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::SyncInfo(sync_info)),
+        };
+        observer_sender.publish(msg).await
+    }
+
     pub async fn notify_epoch_change(&mut self, proof: ValidatorChangeProof) {
         let msg = ConsensusMsg {
             message: Some(ConsensusMsg_oneof::EpochChange(proof.into())),
@@ -252,6 +318,83 @@ impl NetworkSender {
     }
 }
 
+/// ObserverSender publishes consensus messages to the observers: the full nodes following
+/// consensus on a full-node network. Every peer connected on that network is considered an
+/// observer: the published messages are signed by the validators, observers do not need to trust
+/// the peer relaying them.
+#[derive(Clone)]
+pub struct ObserverSender {
+    network_sender: ConsensusNetworkSender,
+    observers: Arc<Mutex<HashSet<AccountAddress>>>,
+}
+
+impl ObserverSender {
+    /// Returns the sender along with the events of the full-node network that consensus has to
+    /// handle: the block retrieval requests of the observers catching up. The connection events
+    /// are consumed to keep track of the connected observers, other messages are dropped.
+    pub fn new(
+        network_sender: ConsensusNetworkSender,
+        network_events: ConsensusNetworkEvents,
+    ) -> (
+        Self,
+        Box<dyn Stream<Item = anyhow::Result<Event<ConsensusMsg>>> + Send + Unpin>,
+    ) {
+        let observers = Arc::new(Mutex::new(HashSet::new()));
+        let connected = Arc::clone(&observers);
+        let events = network_events.filter_map(move |event| {
+            let event = match event {
+                Ok(Event::NewPeer(peer_id)) => {
+                    debug!("Observer {} connected", peer_id);
+                    connected.lock().unwrap().insert(peer_id);
+                    None
+                }
+                Ok(Event::LostPeer(peer_id)) => {
+                    debug!("Observer {} dis-connected", peer_id);
+                    connected.lock().unwrap().remove(&peer_id);
+                    None
+                }
+                Ok(Event::RpcRequest(request)) => Some(Ok(Event::RpcRequest(request))),
+                Ok(Event::Message((peer_id, msg))) => {
+                    debug!("Ignore msg from observer {}: {:?}", peer_id, msg);
+                    None
+                }
+                Ok(Event::StreamingRpcRequest((peer_id, msg, _))) => {
+                    debug!("Ignore streaming RPC from observer {}: {:?}", peer_id, msg);
+                    None
+                }
+                Err(e) => Some(Err(e.into())),
+            };
+            future::ready(event)
+        });
+        let observer_sender = Self {
+            network_sender,
+            observers,
+        };
+        (observer_sender, Box::new(events))
+    }
+
+    async fn publish(&mut self, msg: ConsensusMsg) {
+        let observers = self
+            .observers
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        if observers.is_empty() {
+            return;
+        }
+        counters::OBSERVER_PUBLISHED_MSGS.inc_by(observers.len() as i64);
+        if let Err(err) = self
+            .network_sender
+            .send_to_many(observers.into_iter(), msg)
+            .await
+        {
+            error!("Error publishing message to observers: {:?}", err);
+        }
+    }
+}
+
 #[derive(Clone, Eq, Hash, PartialEq)]
 enum MessageType {
     Proposal,
@@ -394,6 +537,9 @@ impl<T: Payload> NetworkTask<T> {
                     warn!("Failed to process RPC {:?}", e)
                 }
             }
+            Event::StreamingRpcRequest((peer_id, msg, _)) => {
+                warn!("Unexpected streaming RPC from {}: {:?}", peer_id, msg);
+            }
             Event::NewPeer(peer_id) => {
                 debug!("Peer {} connected", peer_id);
             }
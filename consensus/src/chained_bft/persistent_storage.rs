@@ -6,7 +6,7 @@ use crate::{
 };
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
-    block::Block, common::Payload, quorum_cert::QuorumCert,
+    block::Block, common::Payload, evidence::Evidence, quorum_cert::QuorumCert,
     timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use executor::ExecutedTrees;
@@ -43,6 +43,12 @@ pub trait PersistentStorage<T>: Send + Sync {
     /// Persist the highest timeout certificate for improved liveness - proof for other replicas
     /// to jump to this round
     fn save_highest_timeout_cert(&self, highest_timeout_cert: TimeoutCertificate) -> Result<()>;
+
+    /// Persist the evidence of a misbehaving validator so that it can be exported later on.
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<()>;
+
+    /// Retrieve all the persisted evidence of misbehaving validators.
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence<T>>>;
 }
 
 /// The recovery data constructed from raw consensusdb data, it'll find the root value and
@@ -171,7 +177,7 @@ impl<T: Payload> RecoveryData<T> {
     ///
     /// We guarantee that the block corresponding to the storage's latest ledger info always exists.
     /// In the case of an epoch boundary ledger info(i.e. it has validator set), we generate the virtual genesis block.
-    fn find_root(
+    pub(crate) fn find_root(
         blocks: &mut Vec<Block<T>>,
         quorum_certs: &mut Vec<QuorumCert>,
         storage_ledger: &LedgerInfo,
@@ -220,7 +226,7 @@ impl<T: Payload> RecoveryData<T> {
         Ok((root_block, root_quorum_cert, root_ledger_info))
     }
 
-    fn find_blocks_to_prune(
+    pub(crate) fn find_blocks_to_prune(
         root_id: HashValue,
         blocks: &mut Vec<Block<T>>,
         quorum_certs: &mut Vec<QuorumCert>,
@@ -354,4 +360,12 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         self.db
             .save_highest_timeout_certificate(lcs::to_bytes(&highest_timeout_cert)?)
     }
+
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<()> {
+        self.db.save_evidence(evidence)
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence<T>>> {
+        self.db.get_evidence()
+    }
 }
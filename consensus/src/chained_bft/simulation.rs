@@ -0,0 +1,815 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A seeded simulator running several `ChainedBftSMR` instances over a simulated network.
+//!
+//! The simulation is event driven: all the nodes run on a single threaded runtime whose clock is
+//! paused, hence the virtual time only moves forward once every node is done reacting to the
+//! previous event (message delivery, timer, scenario event), jumping straight to the next one.
+//! All the decisions of the simulated network (delays, drops, duplicates), as well as the choice
+//! of the peers to retrieve blocks from, are drawn from random generators seeded by the caller:
+//! running a seed twice gives the same trace, so that a failing seed can be replayed.
+//!
+//! The RPC requests and their responses go through the simulated network as well, the requests
+//! time out in virtual time.
+
+use crate::{
+    chained_bft::{
+        chained_bft_smr::ChainedBftSMR,
+        test_utils::{
+            simulation_runtime, MockStateComputer, MockStorage, MockTransactionManager, TestPayload,
+        },
+    },
+    consensus_provider::ConsensusProvider,
+    util::time_service::{ScheduledTask, TimeService},
+};
+use bytes::Bytes;
+use channel;
+use consensus_types::common::{Author, Round};
+use futures::{
+    channel::{mpsc, oneshot},
+    executor::block_on,
+    Future, FutureExt, SinkExt, StreamExt,
+};
+use libra_config::{
+    config::{ConsensusProposerType, NodeConfig, SafetyRulesConfig},
+    generator::{self, ValidatorSwarm},
+};
+use libra_crypto::HashValue;
+use libra_types::crypto_proxies::LedgerInfoWithSignatures;
+use network::{
+    interface::{NetworkNotification, NetworkRequest},
+    protocols::rpc::{error::RpcError, InboundRpcRequest},
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    runtime::{Handle, Runtime},
+    time::{self, delay_for, timeout_at, Instant},
+};
+
+/// Environment variable pinning the seed run by `check_scenario`.
+const SEED_ENV_VAR: &str = "CONSENSUS_SIM_SEED";
+
+/// Something happening to the network or to the nodes at a given virtual time.
+#[derive(Clone, Debug)]
+pub enum ScenarioEvent {
+    /// Only the nodes of the same group can talk to each other, the nodes that are not listed
+    /// are isolated.
+    Partition(Vec<Vec<usize>>),
+    /// Ends the current partition.
+    Heal,
+    /// Stops the node, the messages sent to it are lost.
+    Crash(usize),
+    /// Starts the node again, recovering from its MockStorage.
+    Restart(usize),
+}
+
+/// The description of a simulation: the nodes, the network and the expected outcome.
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    num_nodes: usize,
+    proposer_type: ConsensusProposerType,
+    duration: Duration,
+    link_delay: Duration,
+    link_jitter: Duration,
+    link_delays: HashMap<(usize, usize), Duration>,
+    drop_rate: f64,
+    duplication_rate: f64,
+    clock_skews: HashMap<usize, Duration>,
+    events: Vec<(Duration, ScenarioEvent)>,
+    min_committed_round: Round,
+}
+
+impl Scenario {
+    /// A healthy network of `num_nodes` validators with rotating proposers, expected to commit
+    /// round 10 within a minute of virtual time.
+    pub fn new(num_nodes: usize) -> Self {
+        Self {
+            num_nodes,
+            proposer_type: ConsensusProposerType::RotatingProposer,
+            duration: Duration::from_secs(60),
+            link_delay: Duration::from_millis(10),
+            link_jitter: Duration::from_millis(0),
+            link_delays: HashMap::new(),
+            drop_rate: 0.0,
+            duplication_rate: 0.0,
+            clock_skews: HashMap::new(),
+            events: vec![],
+            min_committed_round: 10,
+        }
+    }
+
+    pub fn proposer_type(mut self, proposer_type: ConsensusProposerType) -> Self {
+        self.proposer_type = proposer_type;
+        self
+    }
+
+    /// Maximum virtual time given to the nodes to reach the expected committed round.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Every message is delayed by `delay` plus a random jitter up to `jitter`: the messages
+    /// sent on the same link are reordered when the jitter exceeds the time between them.
+    pub fn link_delay(mut self, delay: Duration, jitter: Duration) -> Self {
+        self.link_delay = delay;
+        self.link_jitter = jitter;
+        self
+    }
+
+    /// Overrides the base delay of the messages sent from `src` to `dst`.
+    pub fn link(mut self, src: usize, dst: usize, delay: Duration) -> Self {
+        self.link_delays.insert((src, dst), delay);
+        self
+    }
+
+    pub fn drop_rate(mut self, drop_rate: f64) -> Self {
+        self.drop_rate = drop_rate;
+        self
+    }
+
+    /// Probability for a direct-send message to be delivered twice.
+    pub fn duplication_rate(mut self, duplication_rate: f64) -> Self {
+        self.duplication_rate = duplication_rate;
+        self
+    }
+
+    /// The clock of `node` runs `skew` ahead of the virtual time.
+    pub fn clock_skew(mut self, node: usize, skew: Duration) -> Self {
+        self.clock_skews.insert(node, skew);
+        self
+    }
+
+    pub fn at(mut self, time: Duration, event: ScenarioEvent) -> Self {
+        self.events.push((time, event));
+        self
+    }
+
+    /// All the nodes up at the end of the simulation must have committed at least this round.
+    pub fn min_committed_round(mut self, round: Round) -> Self {
+        self.min_committed_round = round;
+        self
+    }
+}
+
+/// A violation of the properties checked by the simulation.
+#[derive(Debug, Error)]
+pub enum SimulationFailure {
+    #[error(
+        "Node {} committed block {} and node {} committed block {} at epoch {} round {}",
+        first_node,
+        first_block,
+        second_node,
+        second_block,
+        epoch,
+        round
+    )]
+    ConflictingCommits {
+        epoch: u64,
+        round: Round,
+        first_node: usize,
+        first_block: HashValue,
+        second_node: usize,
+        second_block: HashValue,
+    },
+    #[error("Node {} committed round {} after round {}", node, round, previous)]
+    CommitsOutOfOrder {
+        node: usize,
+        round: Round,
+        previous: Round,
+    },
+    #[error(
+        "Expected committed round {} after {:?}, got {:?}",
+        expected,
+        elapsed,
+        committed_rounds
+    )]
+    NoProgress {
+        expected: Round,
+        elapsed: Duration,
+        committed_rounds: Vec<Option<Round>>,
+    },
+}
+
+/// A step of the simulation, at a given virtual time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    Delivered {
+        time: Duration,
+        src: usize,
+        dst: usize,
+    },
+    Dropped {
+        time: Duration,
+        src: usize,
+        dst: usize,
+    },
+    Committed {
+        time: Duration,
+        node: usize,
+        round: Round,
+        block: HashValue,
+    },
+}
+
+/// The outcome of a successful simulation.
+#[derive(Debug)]
+pub struct SimulationReport {
+    pub seed: u64,
+    /// Virtual time at which the simulation ended.
+    pub elapsed: Duration,
+    /// Highest round committed by each node, None for the nodes down at the end.
+    pub committed_rounds: Vec<Option<Round>>,
+    pub delivered: usize,
+    pub dropped: usize,
+    /// The deliveries, drops and commits in the order they happened.
+    pub trace: Vec<TraceEvent>,
+}
+
+/// Runs `scenario` once for every seed, or only for the seed set in `CONSENSUS_SIM_SEED`, and
+/// panics with the first seed failing.
+pub fn check_scenario(scenario: &Scenario, num_seeds: u64) {
+    let seeds = match std::env::var(SEED_ENV_VAR) {
+        Ok(seed) => {
+            let seed = seed.parse().expect("Invalid simulation seed");
+            seed..seed + 1
+        }
+        Err(_) => 0..num_seeds,
+    };
+    for seed in seeds {
+        if let Err(e) = Simulation::new(scenario.clone(), seed).run() {
+            panic!(
+                "Simulation failed with seed {}: {}. Run again with {}={}",
+                seed, e, SEED_ENV_VAR, seed
+            );
+        }
+    }
+}
+
+/// The time service of a simulated node: the virtual time shifted by the skew of the node. The
+/// timers of a node are dropped once it is stopped.
+struct SkewedTimeService {
+    executor: Handle,
+    start: Instant,
+    skew: Duration,
+    alive: Arc<AtomicBool>,
+}
+
+impl TimeService for SkewedTimeService {
+    fn run_after(&self, timeout: Duration, mut task: Box<dyn ScheduledTask>) {
+        let alive = Arc::clone(&self.alive);
+        self.executor.spawn(async move {
+            delay_for(timeout).await;
+            if alive.load(Ordering::SeqCst) {
+                task.run().await;
+            }
+        });
+    }
+
+    fn get_current_timestamp(&self) -> Duration {
+        let now = self.executor.enter(Instant::now);
+        now.duration_since(self.start) + self.skew
+    }
+
+    fn sleep(&self, t: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        delay_for(t).boxed()
+    }
+}
+
+struct SimulatedNode {
+    config: NodeConfig,
+    author: Author,
+    storage: Arc<MockStorage<TestPayload>>,
+    running: Option<RunningNode>,
+}
+
+struct RunningNode {
+    smr: ChainedBftSMR<TestPayload>,
+    alive: Arc<AtomicBool>,
+    consensus_tx: channel::Sender<NetworkNotification>,
+    commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    mempool_notif_receiver: mpsc::Receiver<usize>,
+}
+
+/// What the nodes hand to the simulated network.
+enum Outbound {
+    Request(NetworkRequest),
+    /// The response of the node to the RPC request `rpc_id` of node `dst`.
+    RpcResponse {
+        dst: usize,
+        rpc_id: u64,
+        response: Result<Bytes, RpcError>,
+    },
+}
+
+/// What the simulated network hands to the nodes.
+enum Message {
+    Notification(NetworkNotification),
+    RpcResponse(u64, Result<Bytes, RpcError>),
+    /// Fails the RPC request if it is still waiting for its response.
+    RpcTimeout(u64),
+}
+
+struct Delivery {
+    src: usize,
+    dst: usize,
+    message: Message,
+}
+
+pub struct Simulation {
+    scenario: Scenario,
+    seed: u64,
+    rng: StdRng,
+    runtime: Runtime,
+    // The virtual time origin and the virtual time elapsed since.
+    start: Instant,
+    now: Duration,
+    nodes: Vec<SimulatedNode>,
+    node_indices: HashMap<Author, usize>,
+    outbound_tx: mpsc::UnboundedSender<(usize, Outbound)>,
+    outbound_rx: mpsc::UnboundedReceiver<(usize, Outbound)>,
+    in_flight: BTreeMap<(Duration, u64), Delivery>,
+    next_delivery_seq: u64,
+    // The response channels of the RPC requests waiting for their response.
+    pending_rpcs: HashMap<u64, oneshot::Sender<Result<Bytes, RpcError>>>,
+    next_rpc_id: u64,
+    pending_events: VecDeque<(Duration, ScenarioEvent)>,
+    // The group of every node while partitioned.
+    groups: Option<Vec<usize>>,
+    committed_rounds: Vec<Option<Round>>,
+    commits: HashMap<(u64, Round), (usize, HashValue)>,
+    delivered: usize,
+    dropped: usize,
+    trace: Vec<TraceEvent>,
+}
+
+impl Simulation {
+    pub fn new(scenario: Scenario, seed: u64) -> Self {
+        let ValidatorSwarm {
+            nodes: mut configs,
+            validator_set,
+        } = generator::validator_swarm_for_testing(scenario.num_nodes);
+        // Sort the nodes by author so that the node indices of the scenario are stable.
+        configs.sort_by_key(|config| config.validator_network.as_ref().unwrap().peer_id);
+
+        let mut nodes = vec![];
+        let mut node_indices = HashMap::new();
+        for (index, mut config) in configs.into_iter().enumerate() {
+            config.consensus.proposer_type = scenario.proposer_type;
+            // Use in memory storage for testing
+            config.consensus.safety_rules = SafetyRulesConfig::default();
+            let author = config.validator_network.as_ref().unwrap().peer_id;
+            let (_, storage) = MockStorage::start_for_testing(validator_set.clone());
+            node_indices.insert(author, index);
+            nodes.push(SimulatedNode {
+                config,
+                author,
+                storage,
+                running: None,
+            });
+        }
+
+        // A single threaded runtime with a paused clock: the clock jumps to the next timer as
+        // soon as no task can make progress.
+        let mut runtime = simulation_runtime();
+        let start = runtime.block_on(async {
+            time::pause();
+            Instant::now()
+        });
+
+        let mut pending_events: Vec<_> = scenario.events.clone();
+        pending_events.sort_by_key(|(time, _)| *time);
+        let (outbound_tx, outbound_rx) = mpsc::unbounded();
+        let num_nodes = scenario.num_nodes;
+        Self {
+            scenario,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            runtime,
+            start,
+            now: Duration::from_secs(0),
+            nodes,
+            node_indices,
+            outbound_tx,
+            outbound_rx,
+            in_flight: BTreeMap::new(),
+            next_delivery_seq: 0,
+            pending_rpcs: HashMap::new(),
+            next_rpc_id: 0,
+            pending_events: pending_events.into(),
+            groups: None,
+            committed_rounds: vec![None; num_nodes],
+            commits: HashMap::new(),
+            delivered: 0,
+            dropped: 0,
+            trace: vec![],
+        }
+    }
+
+    /// Runs the nodes until all of them committed the expected round or the virtual time is
+    /// over, checking that no conflicting blocks get committed along the way.
+    pub fn run(mut self) -> Result<SimulationReport, SimulationFailure> {
+        for node in 0..self.nodes.len() {
+            self.start_node(node);
+        }
+        let result = self.run_to_completion();
+        // The nodes down at the end of the run are None, hence the rounds are read before all
+        // the nodes are stopped.
+        let committed_rounds = self.live_committed_rounds();
+        for node in 0..self.nodes.len() {
+            self.stop_node(node);
+        }
+        result?;
+
+        let elapsed = self.now;
+        if committed_rounds
+            .iter()
+            .any(|round| round.map_or(false, |round| round < self.scenario.min_committed_round))
+        {
+            return Err(SimulationFailure::NoProgress {
+                expected: self.scenario.min_committed_round,
+                elapsed,
+                committed_rounds,
+            });
+        }
+        Ok(SimulationReport {
+            seed: self.seed,
+            elapsed,
+            committed_rounds,
+            delivered: self.delivered,
+            dropped: self.dropped,
+            trace: self.trace,
+        })
+    }
+
+    fn run_to_completion(&mut self) -> Result<(), SimulationFailure> {
+        loop {
+            // Let the nodes run until they send something or the next event is due, the timers
+            // of the nodes fire along the way.
+            let next_event = self.pending_events.front().map(|(time, _)| *time);
+            let next_delivery = self.in_flight.keys().next().map(|(time, _)| *time);
+            let next = [next_event, next_delivery]
+                .iter()
+                .filter_map(|time| *time)
+                .min()
+                .unwrap_or(self.scenario.duration)
+                .min(self.scenario.duration);
+            let wake_up = self.start + next;
+            let outbound_rx = &mut self.outbound_rx;
+            let (outbound, now) = self.runtime.block_on(async move {
+                let outbound = timeout_at(wake_up, outbound_rx.next()).await;
+                (outbound, Instant::now())
+            });
+            self.now = now.duration_since(self.start);
+
+            self.collect_commits()?;
+            if self.pending_events.is_empty() && self.progress_reached() {
+                return Ok(());
+            }
+
+            match outbound {
+                Ok(Some((src, outbound))) => self.send(src, outbound),
+                Ok(None) => unreachable!("[simulation] The outbound channel is never closed"),
+                // The scenario events go first and the deliveries last.
+                Err(_) if next_event == Some(next) => {
+                    let (_, event) = self.pending_events.pop_front().unwrap();
+                    self.apply(event);
+                }
+                Err(_) if next_delivery == Some(next) => {
+                    let key = *self.in_flight.keys().next().unwrap();
+                    let delivery = self.in_flight.remove(&key).unwrap();
+                    self.deliver(delivery);
+                }
+                Err(_) => return Ok(()),
+            }
+        }
+    }
+
+    fn start_node(&mut self, index: usize) {
+        let skew = self
+            .scenario
+            .clock_skews
+            .get(&index)
+            .cloned()
+            .unwrap_or_else(|| Duration::from_secs(0));
+        let node = &mut self.nodes[index];
+
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(1_024);
+        let (consensus_tx, consensus_rx) = channel::new_test(1_024);
+        let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded();
+        let (mempool, mempool_notif_receiver) = MockTransactionManager::new();
+        self.runtime.spawn(forward_outbound(
+            index,
+            network_reqs_rx,
+            self.outbound_tx.clone(),
+        ));
+
+        let mut smr = ChainedBftSMR::new(
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            None,
+            &mut node.config.clone(),
+            Arc::new(MockStateComputer::new(
+                commit_cb_sender,
+                Arc::clone(&node.storage),
+                None,
+            )),
+            node.storage.clone(),
+            Box::new(mempool),
+        );
+        let alive = Arc::new(AtomicBool::new(true));
+        smr.set_time_service(Arc::new(SkewedTimeService {
+            executor: self.runtime.handle().clone(),
+            start: self.start,
+            skew,
+            alive: Arc::clone(&alive),
+        }));
+        smr.set_executor(self.runtime.handle().clone());
+        // Every node gets its own seed, derived from the seed of the simulation.
+        smr.set_peer_selection_seed(self.rng.gen());
+        smr.start().expect("Failed to start SMR!");
+        node.running = Some(RunningNode {
+            smr,
+            alive,
+            consensus_tx,
+            commit_cb_receiver,
+            mempool_notif_receiver,
+        });
+    }
+
+    fn stop_node(&mut self, index: usize) {
+        if let Some(mut node) = self.nodes[index].running.take() {
+            node.alive.store(false, Ordering::SeqCst);
+            node.smr.stop();
+        }
+    }
+
+    fn apply(&mut self, event: ScenarioEvent) {
+        match event {
+            ScenarioEvent::Partition(partition) => {
+                // The nodes that are not listed get a group of their own.
+                let mut groups: Vec<_> = (0..self.nodes.len())
+                    .map(|node| partition.len() + node)
+                    .collect();
+                for (group, nodes) in partition.iter().enumerate() {
+                    for node in nodes {
+                        groups[*node] = group;
+                    }
+                }
+                self.groups = Some(groups);
+            }
+            ScenarioEvent::Heal => self.groups = None,
+            ScenarioEvent::Crash(node) => self.stop_node(node),
+            ScenarioEvent::Restart(node) => {
+                if self.nodes[node].running.is_none() {
+                    self.start_node(node);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, src: usize, outbound: Outbound) {
+        let author = self.nodes[src].author;
+        let (dst, message) = match outbound {
+            Outbound::Request(NetworkRequest::SendMessage(dst, msg)) => (
+                self.node_indices[&dst],
+                Message::Notification(NetworkNotification::RecvMessage(author, msg)),
+            ),
+            Outbound::Request(NetworkRequest::SendRpc(dst, outbound_req)) => {
+                let dst = self.node_indices[&dst];
+                let rpc_id = self.next_rpc_id;
+                self.next_rpc_id += 1;
+                self.pending_rpcs.insert(rpc_id, outbound_req.res_tx);
+                self.schedule(
+                    self.now + outbound_req.timeout,
+                    src,
+                    src,
+                    Message::RpcTimeout(rpc_id),
+                );
+
+                // The response of the destination goes back through the simulated network.
+                let (res_tx, res_rx) = oneshot::channel();
+                let outbound_tx = self.outbound_tx.clone();
+                self.runtime.spawn(async move {
+                    let response = res_rx.await.unwrap_or_else(|e| Err(e.into()));
+                    let _ = outbound_tx.unbounded_send((
+                        dst,
+                        Outbound::RpcResponse {
+                            dst: src,
+                            rpc_id,
+                            response,
+                        },
+                    ));
+                });
+                let inbound_req = InboundRpcRequest {
+                    protocol: outbound_req.protocol,
+                    data: outbound_req.data,
+                    res_tx,
+                };
+                (
+                    dst,
+                    Message::Notification(NetworkNotification::RecvRpc(author, inbound_req)),
+                )
+            }
+            // The simulated network connects all the validators.
+            Outbound::Request(NetworkRequest::UpdateEligibleNodes(..)) => return,
+            Outbound::Request(request) => {
+                panic!("[simulation] Unexpected NetworkRequest: {:?}", request)
+            }
+            Outbound::RpcResponse {
+                dst,
+                rpc_id,
+                response,
+            } => (dst, Message::RpcResponse(rpc_id, response)),
+        };
+
+        if self.rng.gen_bool(self.scenario.drop_rate) {
+            self.dropped += 1;
+            self.trace.push(TraceEvent::Dropped {
+                time: self.now,
+                src,
+                dst,
+            });
+            return;
+        }
+        if let Message::Notification(NetworkNotification::RecvMessage(_, msg)) = &message {
+            if self.rng.gen_bool(self.scenario.duplication_rate) {
+                let duplicate = NetworkNotification::RecvMessage(author, msg.clone());
+                self.schedule_delivery(src, dst, Message::Notification(duplicate));
+            }
+        }
+        self.schedule_delivery(src, dst, message);
+    }
+
+    fn schedule_delivery(&mut self, src: usize, dst: usize, message: Message) {
+        let base_delay = self
+            .scenario
+            .link_delays
+            .get(&(src, dst))
+            .cloned()
+            .unwrap_or(self.scenario.link_delay);
+        let max_jitter = self.scenario.link_jitter.as_millis() as u64;
+        let jitter = if max_jitter > 0 {
+            Duration::from_millis(self.rng.gen_range(0, max_jitter + 1))
+        } else {
+            Duration::from_millis(0)
+        };
+        self.schedule(self.now + base_delay + jitter, src, dst, message);
+    }
+
+    fn schedule(&mut self, time: Duration, src: usize, dst: usize, message: Message) {
+        self.in_flight.insert(
+            (time, self.next_delivery_seq),
+            Delivery { src, dst, message },
+        );
+        self.next_delivery_seq += 1;
+    }
+
+    /// Hands the message to its destination, unless the link is cut or the destination is down.
+    fn deliver(&mut self, delivery: Delivery) {
+        let Delivery { src, dst, message } = delivery;
+        if let Message::RpcTimeout(rpc_id) = message {
+            if let Some(res_tx) = self.pending_rpcs.remove(&rpc_id) {
+                let _ = res_tx.send(Err(RpcError::TimedOut));
+            }
+            return;
+        }
+
+        let connected = self
+            .groups
+            .as_ref()
+            .map_or(true, |groups| groups[src] == groups[dst]);
+        let delivered = match (&mut self.nodes[dst].running, message) {
+            (Some(node), Message::Notification(notification)) if connected => {
+                block_on(node.consensus_tx.send(notification)).is_ok()
+            }
+            (Some(_), Message::RpcResponse(rpc_id, response)) if connected => self
+                .pending_rpcs
+                .remove(&rpc_id)
+                .map_or(false, |res_tx| res_tx.send(response).is_ok()),
+            _ => false,
+        };
+        if delivered {
+            self.delivered += 1;
+            self.trace.push(TraceEvent::Delivered {
+                time: self.now,
+                src,
+                dst,
+            });
+        } else {
+            self.dropped += 1;
+            self.trace.push(TraceEvent::Dropped {
+                time: self.now,
+                src,
+                dst,
+            });
+        }
+    }
+
+    fn collect_commits(&mut self) -> Result<(), SimulationFailure> {
+        for index in 0..self.nodes.len() {
+            let mut committed = vec![];
+            if let Some(node) = &mut self.nodes[index].running {
+                while let Ok(Some(ledger_info)) = node.commit_cb_receiver.try_next() {
+                    committed.push(ledger_info);
+                }
+                while let Ok(Some(_)) = node.mempool_notif_receiver.try_next() {}
+            }
+            for ledger_info in committed {
+                self.record_commit(index, &ledger_info)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn record_commit(
+        &mut self,
+        node: usize,
+        ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<(), SimulationFailure> {
+        let ledger_info = ledger_info.ledger_info();
+        let (epoch, round) = (ledger_info.epoch(), ledger_info.round());
+        let block = ledger_info.consensus_block_id();
+        if let Some(previous) = self.committed_rounds[node] {
+            if round < previous {
+                return Err(SimulationFailure::CommitsOutOfOrder {
+                    node,
+                    round,
+                    previous,
+                });
+            }
+        }
+        self.committed_rounds[node] = Some(round);
+        self.trace.push(TraceEvent::Committed {
+            time: self.now,
+            node,
+            round,
+            block,
+        });
+
+        match self.commits.get(&(epoch, round)) {
+            Some((first_node, first_block)) if *first_block != block => {
+                Err(SimulationFailure::ConflictingCommits {
+                    epoch,
+                    round,
+                    first_node: *first_node,
+                    first_block: *first_block,
+                    second_node: node,
+                    second_block: block,
+                })
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.commits.insert((epoch, round), (node, block));
+                Ok(())
+            }
+        }
+    }
+
+    /// The committed round of the nodes that are up, the nodes down are None.
+    fn live_committed_rounds(&self) -> Vec<Option<Round>> {
+        self.nodes
+            .iter()
+            .zip(self.committed_rounds.iter())
+            .map(|(node, round)| node.running.as_ref().map(|_| round.unwrap_or_default()))
+            .collect()
+    }
+
+    fn progress_reached(&self) -> bool {
+        self.live_committed_rounds()
+            .iter()
+            .all(|round| round.map_or(true, |round| round >= self.scenario.min_committed_round))
+    }
+}
+
+/// Forwards the requests of a node to the simulation, tagged with the index of the node.
+async fn forward_outbound(
+    index: usize,
+    mut network_reqs_rx: channel::Receiver<NetworkRequest>,
+    outbound_tx: mpsc::UnboundedSender<(usize, Outbound)>,
+) {
+    while let Some(request) = network_reqs_rx.next().await {
+        if outbound_tx
+            .unbounded_send((index, Outbound::Request(request)))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "simulation_test.rs"]
+mod simulation_test;
@@ -0,0 +1,123 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::simulation::{
+    check_scenario, Scenario, ScenarioEvent, Simulation, SimulationFailure,
+};
+use libra_config::config::ConsensusProposerType::FixedProposer;
+use std::time::Duration;
+
+const NUM_SEEDS: u64 = 3;
+
+#[test]
+fn test_healthy_network_commits() {
+    let report = Simulation::new(Scenario::new(4), 0)
+        .run()
+        .expect("Simulation failed");
+    assert_eq!(report.seed, 0);
+    assert!(report.elapsed <= Duration::from_secs(60));
+    assert!(report.delivered > 0);
+    assert_eq!(report.dropped, 0);
+    assert_eq!(report.committed_rounds.len(), 4);
+    for round in report.committed_rounds {
+        assert!(round.expect("All the nodes are up") >= 10);
+    }
+}
+
+#[test]
+fn test_no_progress_fails() {
+    // Neither half has a quorum and the partition never heals.
+    let scenario = Scenario::new(4)
+        .at(
+            Duration::from_secs(0),
+            ScenarioEvent::Partition(vec![vec![0, 1], vec![2, 3]]),
+        )
+        .duration(Duration::from_secs(30));
+    match Simulation::new(scenario, 0).run() {
+        Err(SimulationFailure::NoProgress {
+            committed_rounds, ..
+        }) => assert!(committed_rounds.iter().all(Option::is_some)),
+        result => panic!("Expected no progress, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_same_seed_same_trace() {
+    // Block retrievals and their timeouts are part of the trace once node 3 catches up.
+    let scenario = Scenario::new(4)
+        .link_delay(Duration::from_millis(10), Duration::from_millis(50))
+        .drop_rate(0.05)
+        .at(
+            Duration::from_secs(1),
+            ScenarioEvent::Partition(vec![vec![0, 1, 2]]),
+        )
+        .at(Duration::from_secs(5), ScenarioEvent::Heal);
+    let first = Simulation::new(scenario.clone(), 7)
+        .run()
+        .expect("Simulation failed");
+    let second = Simulation::new(scenario, 7)
+        .run()
+        .expect("Simulation failed");
+    assert!(!first.trace.is_empty());
+    assert_eq!(first.elapsed, second.elapsed);
+    assert_eq!(first.trace, second.trace);
+}
+
+#[test]
+fn test_lossy_network_with_reordering_and_duplicates() {
+    let scenario = Scenario::new(4)
+        .link_delay(Duration::from_millis(10), Duration::from_millis(100))
+        .drop_rate(0.05)
+        .duplication_rate(0.1)
+        .min_committed_round(5);
+    check_scenario(&scenario, NUM_SEEDS);
+}
+
+#[test]
+fn test_minority_partition() {
+    // The majority keeps committing while node 3 is isolated, which catches up after the heal.
+    let scenario = Scenario::new(4)
+        .at(
+            Duration::from_secs(1),
+            ScenarioEvent::Partition(vec![vec![0, 1, 2]]),
+        )
+        .at(Duration::from_secs(10), ScenarioEvent::Heal)
+        .min_committed_round(15);
+    check_scenario(&scenario, NUM_SEEDS);
+}
+
+#[test]
+fn test_split_brain_partition() {
+    // Neither half has a quorum until the partition heals.
+    let scenario = Scenario::new(4)
+        .at(
+            Duration::from_secs(1),
+            ScenarioEvent::Partition(vec![vec![0, 1], vec![2, 3]]),
+        )
+        .at(Duration::from_secs(10), ScenarioEvent::Heal)
+        .duration(Duration::from_secs(120));
+    check_scenario(&scenario, NUM_SEEDS);
+}
+
+#[test]
+fn test_crash_and_restart() {
+    let scenario = Scenario::new(4)
+        .at(Duration::from_secs(1), ScenarioEvent::Crash(1))
+        .at(Duration::from_secs(5), ScenarioEvent::Restart(1))
+        .at(Duration::from_secs(6), ScenarioEvent::Crash(2))
+        .at(Duration::from_secs(10), ScenarioEvent::Restart(2))
+        .min_committed_round(15);
+    check_scenario(&scenario, NUM_SEEDS);
+}
+
+#[test]
+fn test_clock_skew_and_slow_link() {
+    // The fixed proposer runs ahead: the other nodes wait for its timestamps before voting.
+    let scenario = Scenario::new(4)
+        .proposer_type(FixedProposer)
+        .clock_skew(0, Duration::from_millis(300))
+        .clock_skew(3, Duration::from_millis(50))
+        .link(1, 2, Duration::from_millis(400))
+        .link(2, 1, Duration::from_millis(400));
+    check_scenario(&scenario, NUM_SEEDS);
+}
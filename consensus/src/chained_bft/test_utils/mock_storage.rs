@@ -5,7 +5,7 @@ use crate::chained_bft::persistent_storage::{PersistentStorage, RecoveryData};
 
 use anyhow::Result;
 use consensus_types::{
-    block::Block, common::Payload, quorum_cert::QuorumCert,
+    block::Block, common::Payload, evidence::Evidence, quorum_cert::QuorumCert,
     timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use executor::ExecutedTrees;
@@ -23,6 +23,7 @@ pub struct MockSharedStorage<T> {
     pub block: Mutex<HashMap<HashValue, Block<T>>>,
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub last_vote: Mutex<Option<Vote>>,
+    pub evidence: Mutex<Vec<Evidence<T>>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -96,6 +97,7 @@ impl<T: Payload> MockStorage<T> {
             block: Mutex::new(HashMap::new()),
             qc: Mutex::new(HashMap::new()),
             last_vote: Mutex::new(None),
+            evidence: Mutex::new(vec![]),
             highest_timeout_certificate: Mutex::new(None),
             validator_set,
         });
@@ -164,6 +166,18 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
             .replace(highest_timeout_certificate);
         Ok(())
     }
+
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<()> {
+        let mut stored = self.shared_storage.evidence.lock().unwrap();
+        if !stored.contains(&evidence) {
+            stored.push(evidence);
+        }
+        Ok(())
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence<T>>> {
+        Ok(self.shared_storage.evidence.lock().unwrap().clone())
+    }
 }
 
 /// A storage that ignores any requests, used in the tests that don't care about the storage.
@@ -205,4 +219,12 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
     fn save_highest_timeout_cert(&self, _: TimeoutCertificate) -> Result<()> {
         Ok(())
     }
+
+    fn save_evidence(&self, _: Evidence<T>) -> Result<()> {
+        Ok(())
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence<T>>> {
+        Ok(vec![])
+    }
 }
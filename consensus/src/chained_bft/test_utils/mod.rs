@@ -227,6 +227,20 @@ pub fn consensus_runtime() -> runtime::Runtime {
         .expect("Failed to create Tokio runtime!")
 }
 
+/// A single threaded runtime, for the simulations to run the nodes deterministically.
+#[cfg(test)]
+pub fn simulation_runtime() -> runtime::Runtime {
+    if nocapture() {
+        set_simple_logger("consensus");
+    }
+
+    runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime!")
+}
+
 pub fn with_smr_id(id: String) -> impl Fn() {
     move || set_simple_logger_prefix(format!("{}[{}]{}", Fg(LightBlack), id, Fg(Reset)))
 }
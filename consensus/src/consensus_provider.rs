@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    chained_bft::{chained_bft_smr::ChainedBftSMR, persistent_storage::StorageWriteProxy},
+    chained_bft::{
+        chained_bft_smr::ChainedBftSMR, consensus_observer::ConsensusObserver,
+        persistent_storage::StorageWriteProxy,
+    },
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
 };
@@ -11,6 +14,7 @@ use executor::Executor;
 use futures::channel::{mpsc, oneshot};
 use libra_config::config::NodeConfig;
 use libra_mempool::{MempoolRequest, MempoolResponse};
+use libra_types::PeerId;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 use state_synchronizer::StateSyncClient;
 use std::sync::Arc;
@@ -35,6 +39,7 @@ pub fn make_consensus_provider(
     node_config: &mut NodeConfig,
     network_sender: ConsensusNetworkSender,
     network_receiver: ConsensusNetworkEvents,
+    observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
     executor: Arc<Executor<LibraVM>>,
     state_sync_client: Arc<StateSyncClient>,
     mempool_channel: mpsc::Sender<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
@@ -46,6 +51,7 @@ pub fn make_consensus_provider(
     Box::new(ChainedBftSMR::new(
         network_sender,
         network_receiver,
+        observer_network,
         node_config,
         state_computer,
         storage,
@@ -53,6 +59,29 @@ pub fn make_consensus_provider(
     ))
 }
 
+/// Helper function to create a read-only ConsensusProvider following the consensus traffic
+/// published on the given full-node network
+pub fn make_consensus_observer_provider(
+    node_config: &NodeConfig,
+    peer_id: PeerId,
+    network_sender: ConsensusNetworkSender,
+    network_receiver: ConsensusNetworkEvents,
+    executor: Arc<Executor<LibraVM>>,
+    state_sync_client: Arc<StateSyncClient>,
+) -> Box<dyn ConsensusProvider> {
+    let storage = Arc::new(StorageWriteProxy::new(node_config));
+    let state_computer = Arc::new(ExecutionProxy::new(executor, state_sync_client));
+
+    Box::new(ConsensusObserver::new(
+        peer_id,
+        network_sender,
+        network_receiver,
+        node_config,
+        state_computer,
+        storage,
+    ))
+}
+
 /// Create a storage read client based on the config
 pub fn create_storage_read_client(config: &NodeConfig) -> Arc<dyn StorageRead> {
     Arc::new(StorageReadServiceClient::new(&config.storage.address))
@@ -144,6 +144,17 @@ pub static EPOCH_CHANGE_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the evidence of misbehaving validators collected since last restart. kind can be:
+/// double_vote, double_proposal, preferred_round_violation
+pub static EVIDENCE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_evidence_count",
+        "Count of the evidence of misbehaving validators collected since last restart",
+        &["kind"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // PROPOSAL ELECTION
 //////////////////////
@@ -268,6 +279,29 @@ pub static SYNC_INFO_MSGS_RECEIVED_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+//////////////////////
+// OBSERVER COUNTERS
+//////////////////////
+/// Count the number of messages published to the observers since last restart.
+pub static OBSERVER_PUBLISHED_MSGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "libra_consensus_observer_published_msgs",
+        "Count the number of messages published to the observers since last restart."
+    )
+    .unwrap()
+});
+
+/// Count the number of observed messages processed by an observer since last restart. status can
+/// be: processed, ignored, failed
+pub static OBSERVER_PROCESSED_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_observer_processed_msgs",
+        "Count the number of observed messages processed by an observer since last restart",
+        &["status"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RECONFIGURATION COUNTERS
 //////////////////////
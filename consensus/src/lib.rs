@@ -23,6 +23,8 @@ mod util;
 #[cfg(feature = "fuzzing")]
 pub use chained_bft::event_processor_fuzzing;
 
+pub use chained_bft::consensusdb_inspector;
+
 /// Defines the public consensus provider traits to implement for
 /// use in the Libra Core blockchain.
 pub mod consensus_provider;
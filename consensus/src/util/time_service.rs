@@ -6,10 +6,13 @@ use futures::{Future, FutureExt, SinkExt};
 use libra_logger::prelude::*;
 use std::{
     pin::Pin,
-    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
-use tokio::{runtime::Handle, time::delay_for};
+use tokio::{
+    runtime::Handle,
+    time::{delay_for, Instant},
+};
 
 /// Time service is an abstraction for operations that depend on time
 /// It supports implementations that can simulated time or depend on actual time
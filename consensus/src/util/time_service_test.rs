@@ -6,7 +6,8 @@ use crate::util::{
     time_service::{wait_if_possible, TimeService, WaitingError, WaitingSuccess},
 };
 use futures::executor::block_on;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use tokio::time::Instant;
 
 #[test]
 fn wait_if_possible_test_waiting_required() {
@@ -27,16 +27,17 @@
 //! **Note**: The above example generates a private key using a private function intended only for
 //! testing purposes. Production code should generate the key according to the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
 //!
-//! This module is not currently used, but could be included in the future for improved
-//! performance in consensus.
+//! Signatures of the same message can be aggregated, see
+//! [`AggregatableSignature`](../traits/trait.AggregatableSignature.html), once the public keys of
+//! the signers come with a proof of possession of their private key.
 
 use crate::{traits::*, HashValue};
-use anyhow::{bail, Result};
+use anyhow::{bail, format_err, Result};
 use core::convert::TryFrom;
 use libra_crypto_derive::{Deref, SilentDebug, SilentDisplay};
 use pairing::{
-    bls12_381::{Fr, FrRepr},
-    PrimeField,
+    bls12_381::{Fr, FrRepr, G1Compressed, G2Compressed, G1, G2},
+    CurveAffine, CurveProjective, EncodedPoint, PrimeField,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -269,3 +270,61 @@ impl TryFrom<&[u8]> for BLS12381Signature {
         Ok(BLS12381Signature(sig))
     }
 }
+
+/// The message signed by a proof of possession, distinct from the hashes signed by the protocols
+/// so that a proof of possession can not be replayed as the signature of anything else.
+fn proof_of_possession_message(public_key: &BLS12381PublicKey) -> HashValue {
+    let mut message = b"LIBRA::BLS12381ProofOfPossession".to_vec();
+    message.extend_from_slice(&public_key.to_bytes());
+    HashValue::from_sha3_256(&message)
+}
+
+impl AggregatableSignature for BLS12381Signature {
+    fn prove_possession(private_key: &BLS12381PrivateKey) -> Self {
+        private_key.sign_message(&proof_of_possession_message(&private_key.public_key()))
+    }
+
+    fn verify_possession(public_key: &BLS12381PublicKey, proof: &Self) -> Result<()> {
+        public_key.verify_signature(&proof_of_possession_message(public_key), proof)
+    }
+
+    fn aggregate(signatures: Vec<&Self>) -> Result<Self> {
+        if signatures.is_empty() {
+            bail!("Cannot aggregate an empty set of signatures");
+        }
+        let mut sum = G2::zero();
+        for signature in signatures {
+            let mut point = G2Compressed::empty();
+            point.as_mut().copy_from_slice(&signature.0.to_bytes());
+            let point = point
+                .into_affine()
+                .map_err(|e| format_err!("Invalid BLS12381Signature: {}", e))?;
+            sum.add_assign_mixed(&point);
+        }
+        let mut bytes = [0u8; BLS12381_SIGNATURE_LENGTH];
+        bytes.copy_from_slice(sum.into_affine().into_compressed().as_ref());
+        threshold_crypto::Signature::from_bytes(&bytes)
+            .map(BLS12381Signature)
+            .map_err(|_| format_err!("Failed to encode the aggregated BLS12381Signature"))
+    }
+
+    fn aggregate_public_keys(public_keys: Vec<&BLS12381PublicKey>) -> Result<BLS12381PublicKey> {
+        if public_keys.is_empty() {
+            bail!("Cannot aggregate an empty set of public keys");
+        }
+        let mut sum = G1::zero();
+        for public_key in public_keys {
+            let mut point = G1Compressed::empty();
+            point.as_mut().copy_from_slice(&public_key.to_bytes());
+            let point = point
+                .into_affine()
+                .map_err(|e| format_err!("Invalid BLS12381PublicKey: {}", e))?;
+            sum.add_assign_mixed(&point);
+        }
+        let mut bytes = [0u8; BLS12381_PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(sum.into_affine().into_compressed().as_ref());
+        threshold_crypto::PublicKey::from_bytes(&bytes)
+            .map(BLS12381PublicKey)
+            .map_err(|_| format_err!("Failed to encode the aggregated BLS12381PublicKey"))
+    }
+}
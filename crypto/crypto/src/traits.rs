@@ -220,6 +220,30 @@ pub trait Signature:
     }
 }
 
+/// A type family for signature schemes in which the signatures of the same message by different
+/// signers can be combined into a single signature. The aggregated signature verifies against
+/// the combination of the public keys of the signers.
+///
+/// Aggregation alone does not protect against rogue key attacks, in which a signer picks its
+/// public key as a function of the others' to forge their aggregated signature: a public key must
+/// only be aggregated once its proof of possession has been verified.
+pub trait AggregatableSignature: Signature {
+    /// Proves the knowledge of the private key, by signing its own public key.
+    fn prove_possession(private_key: &Self::SigningKeyMaterial) -> Self;
+
+    /// Verifies a proof of possession of the private key matching `public_key`.
+    fn verify_possession(public_key: &Self::VerifyingKeyMaterial, proof: &Self) -> Result<()>;
+
+    /// Combines signatures of the same message into a single signature.
+    fn aggregate(signatures: Vec<&Self>) -> Result<Self>;
+
+    /// Combines the public keys of the signers into the key verifying their aggregated
+    /// signature.
+    fn aggregate_public_keys(
+        public_keys: Vec<&Self::VerifyingKeyMaterial>,
+    ) -> Result<Self::VerifyingKeyMaterial>;
+}
+
 /// A type family for schemes which know how to generate key material from
 /// a cryptographically-secure [`CryptoRng`][::rand::CryptoRng].
 pub trait Uniform {
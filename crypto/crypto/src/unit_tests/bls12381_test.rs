@@ -3,7 +3,7 @@
 
 use crate::{
     bls12381::{
-        BLS12381PrivateKey, BLS12381PublicKey, BLS12381_PRIVATE_KEY_LENGTH,
+        BLS12381PrivateKey, BLS12381PublicKey, BLS12381Signature, BLS12381_PRIVATE_KEY_LENGTH,
         BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
     },
     hash::HashValue,
@@ -89,3 +89,64 @@ proptest! {
         prop_assert!(keypair.public_key.verify_signature(&hash, &deserialized).is_ok());
     }
 }
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_aggregate_and_verify(
+        hash in any::<HashValue>(),
+        other_hash in any::<HashValue>(),
+        keypairs in proptest::collection::vec(
+            uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+            1..5,
+        )
+    ) {
+        prop_assume!(hash != other_hash);
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.private_key.sign_message(&hash))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|keypair| &keypair.public_key).collect();
+
+        let signature = BLS12381Signature::aggregate(signatures.iter().collect()).unwrap();
+        let public_key = BLS12381Signature::aggregate_public_keys(public_keys.clone()).unwrap();
+        prop_assert!(public_key.verify_signature(&hash, &signature).is_ok());
+        prop_assert!(public_key.verify_signature(&other_hash, &signature).is_err());
+
+        // The aggregated signature does not verify once a signer is missing.
+        if public_keys.len() > 1 {
+            let partial_key =
+                BLS12381Signature::aggregate_public_keys(public_keys[1..].to_vec()).unwrap();
+            prop_assert!(partial_key.verify_signature(&hash, &signature).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_aggregate_empty() {
+    assert!(BLS12381Signature::aggregate(vec![]).is_err());
+    assert!(BLS12381Signature::aggregate_public_keys(vec![]).is_err());
+}
+
+proptest! {
+    #[test]
+    fn test_proof_of_possession(
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+        other_keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+        hash in any::<HashValue>(),
+    ) {
+        prop_assume!(keypair.public_key != other_keypair.public_key);
+        let proof = BLS12381Signature::prove_possession(&keypair.private_key);
+        prop_assert!(BLS12381Signature::verify_possession(&keypair.public_key, &proof).is_ok());
+        prop_assert!(
+            BLS12381Signature::verify_possession(&other_keypair.public_key, &proof).is_err()
+        );
+
+        // The signature of anything else is not a proof of possession.
+        let signature = keypair.private_key.sign_message(&hash);
+        prop_assert!(
+            BLS12381Signature::verify_possession(&keypair.public_key, &signature).is_err()
+        );
+    }
+}
@@ -16,7 +16,7 @@ use libra_types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     crypto_proxies::LedgerInfoWithSignatures,
     ledger_info::LedgerInfo,
-    transaction::{Transaction, TransactionListWithProof, Version},
+    transaction::{Transaction, TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
 use proptest::prelude::*;
 use rusty_fork::{rusty_fork_id, rusty_fork_test, rusty_fork_test_name};
@@ -274,6 +274,43 @@ rusty_fork_test! {
 fn create_transaction_chunks(
     chunk_ranges: Vec<std::ops::Range<Version>>,
 ) -> (Vec<TransactionListWithProof>, LedgerInfoWithSignatures) {
+    create_chunks(chunk_ranges, |rt, storage_client, range, ledger_version| {
+        rt.block_on(storage_client.get_transactions(
+            range.start,
+            range.end - range.start,
+            ledger_version,
+            false, /* fetch_events */
+        ))
+        .unwrap()
+    })
+}
+
+/// Generates a list of `TransactionOutputListWithProof`s according to the given ranges.
+fn create_transaction_output_chunks(
+    chunk_ranges: Vec<std::ops::Range<Version>>,
+) -> (
+    Vec<TransactionOutputListWithProof>,
+    LedgerInfoWithSignatures,
+) {
+    create_chunks(chunk_ranges, |rt, storage_client, range, ledger_version| {
+        rt.block_on(storage_client.get_transaction_outputs(
+            range.start,
+            range.end - range.start,
+            ledger_version,
+        ))
+        .unwrap()
+    })
+}
+
+fn create_chunks<T>(
+    chunk_ranges: Vec<std::ops::Range<Version>>,
+    fetch_chunk: impl Fn(
+        &mut Runtime,
+        &StorageReadServiceClient,
+        std::ops::Range<Version>,
+        Version,
+    ) -> T,
+) -> (Vec<T>, LedgerInfoWithSignatures) {
     let mut rt = Runtime::new().unwrap();
     assert_eq!(chunk_ranges.first().unwrap().start, 1);
     for i in 1..chunk_ranges.len() {
@@ -286,7 +323,7 @@ fn create_transaction_chunks(
     }
 
     // To obtain the batches of transactions, we first execute and save all these transactions in a
-    // separate DB. Then we call get_transactions to retrieve them.
+    // separate DB. Then we fetch them from storage.
     let (mut config, _) = config_builder::test_config();
     let storage_server = create_storage_server(&mut config);
     let (executor, root_trees) = create_executor(&config);
@@ -315,15 +352,7 @@ fn create_transaction_chunks(
 
     let batches: Vec<_> = chunk_ranges
         .into_iter()
-        .map(|range| {
-            rt.block_on(storage_client.get_transactions(
-                range.start,
-                range.end - range.start,
-                ledger_version,
-                false, /* fetch_events */
-            ))
-            .unwrap()
-        })
+        .map(|range| fetch_chunk(&mut rt, &storage_client, range, ledger_version))
         .collect();
 
     drop(storage_server);
@@ -433,6 +462,98 @@ fn test_executor_execute_and_commit_chunk() {
     drop(storage_server);
 }
 
+#[test]
+fn test_executor_apply_and_commit_chunk() {
+    let mut rt = Runtime::new().unwrap();
+    let first_batch_size = 30;
+    let second_batch_size = 40;
+    let overlapping_size = 5;
+
+    let (chunks, ledger_info) = {
+        let first_batch_start = 1;
+        let second_batch_start = first_batch_start + first_batch_size - overlapping_size;
+        create_transaction_output_chunks(vec![
+            first_batch_start..first_batch_start + first_batch_size,
+            second_batch_start..second_batch_start + second_batch_size,
+        ])
+    };
+
+    let (mut config, _) = config_builder::test_config();
+    let storage_server = create_storage_server(&mut config);
+    let (executor, mut committed_trees) = create_executor(&config);
+    let storage_client = StorageReadServiceClient::new(&config.storage.address);
+
+    // Apply the first chunk. After that we should still get the genesis ledger info from DB.
+    executor
+        .apply_and_commit_chunk(
+            chunks[0].clone(),
+            ledger_info.clone(),
+            None,
+            &mut committed_trees,
+        )
+        .unwrap();
+    let (_, li, _, _) = rt
+        .block_on(storage_client.update_to_latest_ledger(0, vec![]))
+        .unwrap();
+    assert_eq!(li.ledger_info().version(), 0);
+    assert_eq!(li.ledger_info().consensus_block_id(), *PRE_GENESIS_BLOCK_ID);
+
+    // Apply an empty chunk. Nothing should change.
+    executor
+        .apply_and_commit_chunk(
+            TransactionOutputListWithProof::new_empty(),
+            ledger_info.clone(),
+            None,
+            &mut committed_trees,
+        )
+        .unwrap();
+    assert_eq!(committed_trees.version(), Some(first_batch_size));
+
+    // Apply the overlapping second chunk. After that we should get the new ledger info, and the
+    // applied write sets should be available for other nodes to sync from.
+    executor
+        .apply_and_commit_chunk(
+            chunks[1].clone(),
+            ledger_info.clone(),
+            None,
+            &mut committed_trees,
+        )
+        .unwrap();
+    let (_, li, _, _) = rt
+        .block_on(storage_client.update_to_latest_ledger(0, vec![]))
+        .unwrap();
+    assert_eq!(li, ledger_info);
+    let outputs = rt
+        .block_on(storage_client.get_transaction_outputs(
+            1,
+            first_batch_size,
+            li.ledger_info().version(),
+        ))
+        .unwrap();
+    assert_eq!(outputs, chunks[0]);
+
+    drop(storage_server);
+}
+
+#[test]
+fn test_executor_apply_chunk_with_mismatched_write_set() {
+    let (mut chunks, ledger_info) = create_transaction_output_chunks(vec![1..11]);
+    let chunk = &mut chunks[0];
+    // Each write set is still well formed, but no longer the one the transaction produced.
+    chunk.write_sets.swap(1, 2);
+
+    let (mut config, _) = config_builder::test_config();
+    let storage_server = create_storage_server(&mut config);
+    let (executor, mut committed_trees) = create_executor(&config);
+
+    assert!(executor
+        .apply_and_commit_chunk(chunks.remove(0), ledger_info, None, &mut committed_trees)
+        .is_err());
+    assert_eq!(committed_trees.version(), Some(0));
+
+    drop(storage_server);
+}
+
 #[test]
 fn test_executor_execute_and_commit_chunk_restart() {
     let mut rt = Runtime::new().unwrap();
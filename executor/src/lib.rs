@@ -21,6 +21,7 @@ use libra_crypto::{
     HashValue,
 };
 use libra_logger::prelude::*;
+use libra_state_view::StateView;
 use libra_types::{
     account_address::AccountAddress,
     account_state_blob::AccountStateBlob,
@@ -32,7 +33,8 @@ use libra_types::{
     proof::{accumulator::InMemoryAccumulator, definition::LeafCount, SparseMerkleProof},
     transaction::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
-        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
+        TransactionOutputListWithProof, TransactionPayload, TransactionStatus, TransactionToCommit,
+        Version,
     },
     write_set::{WriteOp, WriteSet},
 };
@@ -126,6 +128,10 @@ pub struct TransactionData {
     /// new blob.
     account_blobs: HashMap<AccountAddress, AccountStateBlob>,
 
+    /// The writes this transaction did, persisted so that the transaction can be synced to other
+    /// nodes without executing it.
+    write_set: WriteSet,
+
     /// The list of events emitted during this transaction.
     events: Vec<ContractEvent>,
 
@@ -153,6 +159,7 @@ pub struct TransactionData {
 impl TransactionData {
     fn new(
         account_blobs: HashMap<AccountAddress, AccountStateBlob>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         status: TransactionStatus,
         state_tree: Arc<SparseMerkleTree>,
@@ -163,6 +170,7 @@ impl TransactionData {
     ) -> Self {
         TransactionData {
             account_blobs,
+            write_set,
             events,
             status,
             state_tree,
@@ -177,6 +185,10 @@ impl TransactionData {
         &self.account_blobs
     }
 
+    fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -456,6 +468,7 @@ where
                     TransactionToCommit::new(
                         txn.clone(),
                         txn_data.account_blobs().clone(),
+                        txn_data.write_set().clone(),
                         txn_data.events().to_vec(),
                         txn_data.gas_used(),
                         txn_data.status().vm_status().major_status,
@@ -607,6 +620,128 @@ where
             synced_trees,
         )?;
 
+        self.commit_chunk(
+            transactions,
+            output,
+            first_version,
+            verified_target_li,
+            epoch_change_li,
+            synced_trees,
+        )
+    }
+
+    /// Verifies the transactions and their outputs based on the provided proofs and ledger info.
+    /// If they are valid, applies the write sets directly without running the VM and commits
+    /// immediately if the resulting transaction infos match the proofs. This is only meant for
+    /// nodes that trust the signers of the ledger info to have executed the transactions.
+    pub fn apply_and_commit_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        // Target LI that has been verified independently: the proofs are relative to this version.
+        verified_target_li: LedgerInfoWithSignatures,
+        // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
+        // carrying any epoch change LI.
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+        synced_trees: &mut ExecutedTrees,
+    ) -> Result<()> {
+        let first_transaction_version = txn_output_list_with_proof
+            .txn_list_with_proof
+            .first_transaction_version;
+        info!(
+            "Local synced version: {}. First transaction version in request: {:?}. \
+             Number of transaction outputs in request: {}.",
+            synced_trees.txn_accumulator().num_leaves() - 1,
+            first_transaction_version,
+            txn_output_list_with_proof.len(),
+        );
+
+        txn_output_list_with_proof
+            .verify(verified_target_li.ledger_info(), first_transaction_version)?;
+        let (num_txns_to_skip, first_version) = Self::find_txns_to_skip(
+            &txn_output_list_with_proof.txn_list_with_proof,
+            synced_trees.txn_accumulator().num_leaves(),
+        )?;
+
+        info!("Skipping the first {} transactions.", num_txns_to_skip);
+        let vm_outputs: Vec<_> = txn_output_list_with_proof
+            .outputs()
+            .into_iter()
+            .skip(num_txns_to_skip as usize)
+            .collect();
+        let txn_list_with_proof = txn_output_list_with_proof.txn_list_with_proof;
+        let expected_txn_infos: Vec<_> = txn_list_with_proof
+            .proof
+            .transaction_infos()
+            .iter()
+            .skip(num_txns_to_skip as usize)
+            .cloned()
+            .collect();
+        let transactions: Vec<_> = txn_list_with_proof
+            .transactions
+            .into_iter()
+            .skip(num_txns_to_skip as usize)
+            .collect();
+
+        // Read every account the write sets touch, so that the state tree gets updated from
+        // verified account blobs exactly as if the VM had read them during execution.
+        let state_view = VerifiedStateView::new(
+            Arc::clone(&self.storage_read_client),
+            self.rt.handle().clone(),
+            synced_trees.version(),
+            synced_trees.state_root(),
+            synced_trees.state_tree(),
+        );
+        {
+            let _timer = OP_COUNTERS.timer("read_chunk_write_set_accounts_time_s");
+            for output in &vm_outputs {
+                for (access_path, _) in output.write_set() {
+                    state_view.get(access_path)?;
+                }
+            }
+        }
+        let (account_to_btree, account_to_proof) = state_view.into();
+
+        let output = Self::process_vm_outputs(
+            account_to_btree,
+            account_to_proof,
+            &transactions,
+            vm_outputs,
+            synced_trees,
+        )?;
+
+        // The write sets themselves are not covered by the proofs, so the state roots computed
+        // from them have to be checked against the verified transaction infos.
+        for (i, (txn_data, expected_txn_info)) in
+            itertools::zip_eq(output.transaction_data(), expected_txn_infos).enumerate()
+        {
+            ensure!(
+                txn_data.txn_info_hash() == Some(expected_txn_info.hash()),
+                "Applied write set of transaction {} does not match its transaction info.",
+                first_version + i as Version,
+            );
+        }
+
+        self.commit_chunk(
+            transactions,
+            output,
+            first_version,
+            verified_target_li,
+            epoch_change_li,
+            synced_trees,
+        )
+    }
+
+    /// Persists the processed chunk, along with the ledger info it ends with if there is one,
+    /// and advances the synced trees.
+    fn commit_chunk(
+        &self,
+        transactions: Vec<Transaction>,
+        output: ProcessedVMOutput,
+        first_version: Version,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+        synced_trees: &mut ExecutedTrees,
+    ) -> Result<()> {
         // Since we have verified the proofs, we just need to verify that each TransactionInfo
         // object matches what we have computed locally.
         let mut txns_to_commit = vec![];
@@ -614,6 +749,7 @@ where
             txns_to_commit.push(TransactionToCommit::new(
                 txn,
                 txn_data.account_blobs().clone(),
+                txn_data.write_set().clone(),
                 txn_data.events().to_vec(),
                 txn_data.gas_used(),
                 txn_data.status().vm_status().major_status,
@@ -707,7 +843,15 @@ where
             ledger_info_with_sigs.ledger_info(),
             txn_list_with_proof.first_transaction_version,
         )?;
+        Self::find_txns_to_skip(txn_list_with_proof, num_committed_txns)
+    }
 
+    /// Returns how many transactions at the front of a verified chunk are already committed
+    /// locally, as well as the first version to be committed.
+    fn find_txns_to_skip(
+        txn_list_with_proof: &TransactionListWithProof,
+        num_committed_txns: u64,
+    ) -> Result<(LeafCount, Version)> {
         if txn_list_with_proof.transactions.is_empty() {
             return Ok((0, num_committed_txns as Version /* first_version */));
         }
@@ -799,6 +943,7 @@ where
 
             txn_data.push(TransactionData::new(
                 blobs,
+                vm_output.write_set().clone(),
                 vm_output.events().to_vec(),
                 vm_output.status().clone(),
                 Arc::clone(&state_tree),
@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use admission_control_service::admission_control_service::AdmissionControlService;
-use consensus::consensus_provider::{make_consensus_provider, ConsensusProvider};
+use consensus::consensus_provider::{
+    make_consensus_observer_provider, make_consensus_provider, ConsensusProvider,
+};
 use debug_interface::{
-    node_debug_service::NodeDebugService,
+    node_debug_service::{NodeDebugService, SyncStatusProvider},
     proto::node_debug_interface_server::NodeDebugInterfaceServer,
 };
 use executor::Executor;
 use futures::{channel::mpsc::channel, executor::block_on};
 use libra_config::config::{NetworkConfig, NodeConfig, RoleType};
+use libra_crypto::ed25519::Ed25519PrivateKey;
 use libra_logger::prelude::*;
 use libra_metrics::metric_server;
+use libra_secure_storage::{CryptoStorage, InMemoryStorage};
 use network::{
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
@@ -20,6 +24,7 @@ use network::{
         // .direct_send_protocols or .rpc_protocols vector of network_builder in setup_network()
         CONSENSUS_DIRECT_SEND_PROTOCOL,
         CONSENSUS_RPC_PROTOCOL,
+        DISCOVERY_SIGNING_KEY,
         MEMPOOL_DIRECT_SEND_PROTOCOL,
         STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL,
     },
@@ -27,6 +32,7 @@ use network::{
 };
 use state_synchronizer::StateSynchronizer;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::net::ToSocketAddrs;
 use std::{sync::Arc, thread, time::Instant};
 use storage_client::{StorageReadServiceClient, StorageWriteServiceClient};
@@ -35,6 +41,7 @@ use tokio::runtime::{Builder, Runtime};
 use vm_runtime::LibraVM;
 
 const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
+const AC_SMP_QUERY_CHANNEL_BUFFER_SIZE: usize = 64;
 
 pub struct LibraHandle {
     _ac: Runtime,
@@ -65,7 +72,10 @@ fn setup_executor(config: &NodeConfig) -> Arc<Executor<LibraVM>> {
     ))
 }
 
-fn setup_debug_interface(config: &NodeConfig) -> Runtime {
+fn setup_debug_interface(
+    config: &NodeConfig,
+    sync_status_provider: Arc<dyn SyncStatusProvider>,
+) -> Runtime {
     let rt = tokio::runtime::Runtime::new().unwrap();
     let addr = format!(
         "{}:{}",
@@ -77,12 +87,23 @@ fn setup_debug_interface(config: &NodeConfig) -> Runtime {
     .unwrap();
     rt.spawn(
         tonic::transport::Server::builder()
-            .add_service(NodeDebugInterfaceServer::new(NodeDebugService::new()))
+            .add_service(NodeDebugInterfaceServer::new(
+                NodeDebugService::new().with_sync_status_provider(sync_status_provider),
+            ))
             .serve(addr),
     );
     rt
 }
 
+/// Moves the network signing key into the storage through which discovery signs its notes.
+fn load_signing_key(signing_private: Ed25519PrivateKey) -> Box<dyn CryptoStorage> {
+    let mut storage = InMemoryStorage::new();
+    storage
+        .import_key(DISCOVERY_SIGNING_KEY, signing_private)
+        .expect("Unable to load Network signing private key into storage");
+    Box::new(storage)
+}
+
 // TODO(abhayb): Move to network crate (similar to consensus).
 pub fn setup_network(
     config: &mut NetworkConfig,
@@ -97,12 +118,15 @@ pub fn setup_network(
     let mut network_builder = NetworkBuilder::new(
         runtime.handle().clone(),
         config.peer_id,
-        config.listen_address.clone(),
+        config.listen_addresses.clone(),
         role,
     );
     network_builder
         .enable_remote_authentication(config.enable_remote_authentication)
-        .advertised_address(config.advertised_address.clone())
+        .advertised_addresses(config.advertised_addresses.clone())
+        .rate_limits(config.rate_limits.clone())
+        .peer_scores(config.peer_scores.clone())
+        .health_policy(config.health_policy.clone())
         .direct_send_protocols(vec![
             ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
             ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL),
@@ -110,10 +134,10 @@ pub fn setup_network(
         ])
         .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)]);
     if config.enable_remote_authentication {
-        // If the node wants to run in enable_remote_authentication mode, it should also use noise for
-        // authentication and encryption.
+        // If the node wants to run in enable_remote_authentication mode, it should also use noise
+        // or QUIC for authentication and encryption.
         assert!(
-            config.enable_noise,
+            config.enable_noise || config.enable_quic,
             "Permissioned network end-points should use authentication"
         );
         let seed_peers = config.seed_peers.seed_peers.clone();
@@ -138,15 +162,35 @@ pub fn setup_network(
         } else {
             config.network_peers.peers.clone()
         };
-        network_builder
-            .transport(TransportType::TcpNoise(Some((
+        if config.enable_quic {
+            // QUIC authenticates with the signing keys, which are also used by discovery. rustls
+            // signs the TLS handshakes itself, so unlike discovery, which only signs through the
+            // secure storage, it is handed a copy of the private key.
+            let quic_private = Ed25519PrivateKey::try_from(&signing_private.to_bytes()[..])
+                .expect("Failed to copy Network signing private key");
+            if config.enable_noise {
+                // TCP listen addresses are served with Noise and QUIC ones with QUIC.
+                network_builder.transport(TransportType::TcpNoiseOrQuic(Some((
+                    (identity_private, identity_public),
+                    (quic_private, signing_public.clone()),
+                ))));
+            } else {
+                network_builder.transport(TransportType::Quic(Some((
+                    quic_private,
+                    signing_public.clone(),
+                ))));
+            }
+        } else {
+            network_builder.transport(TransportType::TcpNoise(Some((
                 identity_private,
                 identity_public,
-            ))))
+            ))));
+        }
+        network_builder
             .connectivity_check_interval_ms(config.connectivity_check_interval_ms)
             .seed_peers(seed_peers)
             .trusted_peers(trusted_peers)
-            .signing_keys((signing_private, signing_public))
+            .signing_storage(load_signing_key(signing_private))
             .discovery_interval_ms(config.discovery_interval_ms);
     } else if config.enable_noise {
         let identity_keys = &mut config
@@ -168,7 +212,7 @@ pub fn setup_network(
     } else {
         network_builder.transport(TransportType::Tcp);
     }
-    let (_listen_addr, network_provider) = network_builder.build();
+    let (_listen_addrs, network_provider) = network_builder.build();
     (runtime, network_provider)
 }
 
@@ -196,6 +240,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut validator_network_provider = None;
+    let mut observer_network_handles = None;
 
     if let Some(network) = node_config.validator_network.as_mut() {
         let (runtime, mut network_provider) = setup_network(network, RoleType::Validator);
@@ -225,6 +270,20 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             mempool_events,
         ));
 
+        // The first full-node network carries the consensus traffic between the validators
+        // publishing it and the full nodes observing it.
+        if i == 0
+            && (node_config.consensus.publish_to_observers || node_config.consensus.observer_mode)
+        {
+            observer_network_handles = Some((
+                node_config.full_node_networks[i].peer_id,
+                network_provider.add_consensus(vec![
+                    ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL),
+                    ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
+                ]),
+            ));
+        }
+
         let network = &node_config.full_node_networks[i];
         // Start the network provider.
         runtime.handle().spawn(network_provider.start());
@@ -232,8 +291,6 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         debug!("Network started for peer_id: {}", network.peer_id);
     }
 
-    let debug_if = setup_debug_interface(&node_config);
-
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
     thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
@@ -248,8 +305,13 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         Arc::clone(&executor),
         &node_config,
     );
+    let debug_if = setup_debug_interface(&node_config, state_synchronizer.create_client());
     let (ac_sender, client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
-    let admission_control_runtime = AdmissionControlService::bootstrap(&node_config, ac_sender);
+    // Mempool queries received by AC are served on their own channel, so that they can't delay
+    // the requests of consensus.
+    let (mempool_query_sender, query_events) = channel(AC_SMP_QUERY_CHANNEL_BUFFER_SIZE);
+    let admission_control_runtime =
+        AdmissionControlService::bootstrap(&node_config, ac_sender, mempool_query_sender);
 
     let mut consensus = None;
     let (_, rcv) = channel(1_024); // TODO replace this placeholder with connection with state sync for full nodes
@@ -285,13 +347,22 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             .expect("State synchronizer initialization failure");
         debug!("State synchronizer initialization complete.");
 
+        let observer_network = if node_config.consensus.publish_to_observers {
+            observer_network_handles
+                .take()
+                .map(|(_peer_id, observer_network)| observer_network)
+        } else {
+            None
+        };
+
         // Initialize and start consensus.
         instant = Instant::now();
         let mut consensus_provider = make_consensus_provider(
             node_config,
             consensus_network_sender,
             consensus_network_events,
-            executor,
+            observer_network,
+            Arc::clone(&executor),
             state_synchronizer.create_client(),
             mempool_channel,
         );
@@ -300,6 +371,35 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             .expect("Failed to start consensus. Can't proceed.");
         consensus = Some(consensus_provider);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if node_config.consensus.observer_mode {
+        if let Some((peer_id, (observer_network_sender, observer_network_events))) =
+            observer_network_handles.take()
+        {
+            // Same as for the validators: the observer builds its initial state from the
+            // storage, which has to be caught up at least to the waypoint.
+            debug!("Wait until state synchronizer is initialized");
+            block_on(state_synchronizer.wait_until_initialized())
+                .expect("State synchronizer initialization failure");
+            debug!("State synchronizer initialization complete.");
+
+            instant = Instant::now();
+            let mut observer_provider = make_consensus_observer_provider(
+                node_config,
+                peer_id,
+                observer_network_sender,
+                observer_network_events,
+                executor,
+                state_synchronizer.create_client(),
+            );
+            observer_provider
+                .start()
+                .expect("Failed to start consensus observer. Can't proceed.");
+            consensus = Some(observer_provider);
+            debug!(
+                "Consensus observer started in {} ms",
+                instant.elapsed().as_millis()
+            );
+        }
     }
 
     instant = Instant::now();
@@ -308,6 +408,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         mempool_network_handles,
         client_events,
         consensus_events,
+        query_events,
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
@@ -326,11 +326,8 @@ impl LibraSwarm {
                 .template(node_config);
             full_node_builder.extend_validator(&mut validator_config)?;
             validator_config.save(&upstream_config_file)?;
-            full_node_builder.bootstrap(
-                validator_config.full_node_networks[0]
-                    .advertised_address
-                    .clone(),
-            );
+            full_node_builder
+                .bootstrap(validator_config.full_node_networks[0].advertised_addresses[0].clone());
             SwarmConfig::build(&full_node_builder, config_path)?
         };
 
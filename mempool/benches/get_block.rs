@@ -0,0 +1,70 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libra_config::config::NodeConfig;
+use libra_crypto::ed25519::*;
+use libra_mempool::{CoreMempool, TimelineState};
+use libra_types::{
+    account_address::AccountAddress,
+    transaction::{RawTransaction, Script},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{collections::HashSet, time::Duration};
+
+const NUM_TXNS: u64 = 10_000;
+const BLOCK_SIZE: u64 = 1_000;
+
+/// Creates a mempool holding `NUM_TXNS` transactions, `depth` per account. Later transactions of
+/// an account pay a higher gas price, so that `get_block` observes them before their ancestors.
+/// If `with_gaps` is set, every other account misses its first transaction, so that its other
+/// transactions are parked.
+fn setup_mempool(depth: u64, with_gaps: bool) -> CoreMempool {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = NUM_TXNS as usize;
+    config.mempool.capacity_per_user = depth as usize;
+    config.mempool.parked_capacity_per_user = depth as usize;
+    let mut pool = CoreMempool::new(&config);
+
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let (private_key, public_key) = compat::generate_keypair(&mut rng);
+    for account in 0..NUM_TXNS / depth {
+        let sender = AccountAddress::random();
+        let first_sequence_number = if with_gaps && account % 2 == 0 { 1 } else { 0 };
+        for sequence_number in first_sequence_number..depth {
+            let txn = RawTransaction::new_script(
+                sender,
+                sequence_number,
+                Script::new(vec![], vec![]),
+                100,
+                1 + sequence_number,
+                Duration::from_secs(u64::max_value()),
+            )
+            .sign(&private_key, public_key.clone())
+            .expect("Failed to sign raw transaction.")
+            .into_inner();
+            pool.add_txn(txn, 0, 0, 1000, TimelineState::NotReady);
+        }
+    }
+    pool
+}
+
+fn bench_get_block(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_block");
+    for depth in &[1, 10, 100] {
+        let mut pool = setup_mempool(*depth, false);
+        group.bench_function(format!("depth_{}", depth), |b| {
+            b.iter(|| pool.get_block(black_box(BLOCK_SIZE), HashSet::new()))
+        });
+        if *depth > 1 {
+            let mut pool = setup_mempool(*depth, true);
+            group.bench_function(format!("depth_{}_with_gaps", depth), |b| {
+                b.iter(|| pool.get_block(black_box(BLOCK_SIZE), HashSet::new()))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_block);
+criterion_main!(benches);
@@ -6,8 +6,10 @@
 //! Proto crate for shared mempool
 
 pub mod proto;
-use crate::proto::mempool_status::MempoolAddTransactionStatusCode;
-use anyhow::{Error, Result};
+use crate::proto::mempool_status::{
+    mempool_transaction_status::State, MempoolAddTransactionStatusCode, MempoolRemovalReason,
+};
+use anyhow::{format_err, Error, Result};
 use std::convert::TryFrom;
 
 /// Status of transaction insertion operation
@@ -26,6 +28,59 @@ impl MempoolAddTransactionStatus {
     }
 }
 
+/// Status of a transaction in Mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MempoolTransactionStatus {
+    /// Mempool has no record of the transaction
+    Unknown,
+    /// Transaction can be included in the next block
+    Ready,
+    /// Transaction is waiting for the transaction of the same account with sequence number
+    /// `missing_sequence_number`
+    Parked {
+        /// Sequence number of the missing transaction
+        missing_sequence_number: u64,
+    },
+    /// Transaction was recently removed from Mempool
+    Removed(MempoolRemovalReason),
+}
+
+/// Number of transactions in Mempool within a range of gas prices
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolGasPriceBucket {
+    /// Lowest gas price of the bucket. The bucket holds transactions with a gas price lower than
+    /// twice this price, or lower than 1 for the first bucket
+    pub min_gas_price: u64,
+    /// Number of ready transactions
+    pub ready: u64,
+    /// Number of parked transactions
+    pub parked: u64,
+}
+
+impl MempoolGasPriceBucket {
+    /// Lowest gas price of the bucket holding transactions with the given gas price
+    pub fn min_gas_price_of(gas_price: u64) -> u64 {
+        if gas_price == 0 {
+            0
+        } else {
+            1 << (63 - gas_price.leading_zeros())
+        }
+    }
+}
+
+/// Mempool-wide statistics
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolStats {
+    /// Number of transactions which can be included in the next block
+    pub ready: u64,
+    /// Number of transactions waiting on a sequence number gap
+    pub parked: u64,
+    /// Number of accounts with transactions in Mempool
+    pub accounts: u64,
+    /// Non-empty gas price buckets, in increasing order of gas price
+    pub buckets: Vec<MempoolGasPriceBucket>,
+}
+
 //***********************************
 // Decoding/Encoding to Protobuffers
 //***********************************
@@ -52,3 +107,87 @@ impl From<MempoolAddTransactionStatus>
         mempool_add_transaction_status
     }
 }
+
+impl TryFrom<crate::proto::mempool_status::MempoolTransactionStatus> for MempoolTransactionStatus {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::mempool_status::MempoolTransactionStatus) -> Result<Self> {
+        let state = State::from_i32(proto.state)
+            .ok_or_else(|| format_err!("Invalid transaction state {}", proto.state))?;
+        Ok(match state {
+            State::Unknown => MempoolTransactionStatus::Unknown,
+            State::Ready => MempoolTransactionStatus::Ready,
+            State::Parked => MempoolTransactionStatus::Parked {
+                missing_sequence_number: proto.missing_sequence_number,
+            },
+            State::Removed => MempoolTransactionStatus::Removed(
+                MempoolRemovalReason::from_i32(proto.removal_reason).ok_or_else(|| {
+                    format_err!("Invalid removal reason {}", proto.removal_reason)
+                })?,
+            ),
+        })
+    }
+}
+
+impl From<MempoolTransactionStatus> for crate::proto::mempool_status::MempoolTransactionStatus {
+    fn from(status: MempoolTransactionStatus) -> Self {
+        let mut proto = Self::default();
+        match status {
+            MempoolTransactionStatus::Unknown => proto.set_state(State::Unknown),
+            MempoolTransactionStatus::Ready => proto.set_state(State::Ready),
+            MempoolTransactionStatus::Parked {
+                missing_sequence_number,
+            } => {
+                proto.set_state(State::Parked);
+                proto.missing_sequence_number = missing_sequence_number;
+            }
+            MempoolTransactionStatus::Removed(reason) => {
+                proto.set_state(State::Removed);
+                proto.set_removal_reason(reason);
+            }
+        }
+        proto
+    }
+}
+
+impl From<crate::proto::mempool_status::MempoolGasPriceBucket> for MempoolGasPriceBucket {
+    fn from(proto: crate::proto::mempool_status::MempoolGasPriceBucket) -> Self {
+        Self {
+            min_gas_price: proto.min_gas_price,
+            ready: proto.ready,
+            parked: proto.parked,
+        }
+    }
+}
+
+impl From<MempoolGasPriceBucket> for crate::proto::mempool_status::MempoolGasPriceBucket {
+    fn from(bucket: MempoolGasPriceBucket) -> Self {
+        Self {
+            min_gas_price: bucket.min_gas_price,
+            ready: bucket.ready,
+            parked: bucket.parked,
+        }
+    }
+}
+
+impl From<crate::proto::mempool_status::MempoolStats> for MempoolStats {
+    fn from(proto: crate::proto::mempool_status::MempoolStats) -> Self {
+        Self {
+            ready: proto.ready,
+            parked: proto.parked,
+            accounts: proto.accounts,
+            buckets: proto.buckets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<MempoolStats> for crate::proto::mempool_status::MempoolStats {
+    fn from(stats: MempoolStats) -> Self {
+        Self {
+            ready: stats.ready,
+            parked: stats.parked,
+            accounts: stats.accounts,
+            buckets: stats.buckets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -3,13 +3,16 @@
 
 /// This module provides various indexes used by Mempool
 use crate::core_mempool::transaction::{MempoolTransaction, TimelineState};
-use libra_types::account_address::AccountAddress;
+use libra_config::config::PriorityLaneConfig;
+use libra_crypto::HashValue;
+use libra_mempool_shared_proto::{MempoolGasPriceBucket, MempoolStats};
+use libra_types::{account_address::AccountAddress, transaction::TransactionPayload};
 use std::{
     cmp::Ordering,
-    collections::{btree_set::Iter, BTreeMap, BTreeSet},
+    collections::{btree_set::Iter, hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
     iter::Rev,
     ops::Bound,
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 pub type AccountTransactions = BTreeMap<u64, MempoolTransaction>;
@@ -22,6 +25,8 @@ pub type AccountTransactions = BTreeMap<u64, MempoolTransaction>;
 /// Instead we use `OrderedQueueKey` - logical reference to transaction in main store
 pub struct PriorityIndex {
     data: BTreeSet<OrderedQueueKey>,
+    // number of transactions by gas price bucket, see `MempoolGasPriceBucket`
+    buckets: BTreeMap<u64, u64>,
 }
 
 pub type PriorityQueueIter<'a> = Rev<Iter<'a, OrderedQueueKey>>;
@@ -30,30 +35,26 @@ impl PriorityIndex {
     pub(crate) fn new() -> Self {
         Self {
             data: BTreeSet::new(),
+            buckets: BTreeMap::new(),
         }
     }
 
     /// add transaction to index
     pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
-        self.data.insert(self.make_key(&txn));
+        if self.data.insert(OrderedQueueKey::from(txn)) {
+            inc_bucket(&mut self.buckets, txn.get_gas_price());
+        }
     }
 
     /// remove transaction from index
     pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
-        self.data.remove(&self.make_key(&txn));
+        if self.data.remove(&OrderedQueueKey::from(txn)) {
+            dec_bucket(&mut self.buckets, txn.get_gas_price());
+        }
     }
 
     pub(crate) fn contains(&self, txn: &MempoolTransaction) -> bool {
-        self.data.contains(&self.make_key(txn))
-    }
-
-    fn make_key(&self, txn: &MempoolTransaction) -> OrderedQueueKey {
-        OrderedQueueKey {
-            gas_price: txn.get_gas_price(),
-            expiration_time: txn.expiration_time,
-            address: txn.get_sender(),
-            sequence_number: txn.get_sequence_number(),
-        }
+        self.data.contains(&OrderedQueueKey::from(txn))
     }
 
     /// returns iterator over priority queue
@@ -61,9 +62,34 @@ impl PriorityIndex {
         self.data.iter().rev()
     }
 
+    /// returns iterator over priority queue, starting from the lowest priority transaction
+    pub(crate) fn iter_lowest(&self) -> Iter<OrderedQueueKey> {
+        self.data.iter()
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.data.len()
     }
+
+    /// number of transactions in the gas price bucket starting at `min_gas_price`
+    fn bucket_size(&self, min_gas_price: u64) -> u64 {
+        self.buckets.get(&min_gas_price).cloned().unwrap_or(0)
+    }
+}
+
+fn inc_bucket(buckets: &mut BTreeMap<u64, u64>, gas_price: u64) {
+    let min_gas_price = MempoolGasPriceBucket::min_gas_price_of(gas_price);
+    *buckets.entry(min_gas_price).or_insert(0) += 1;
+}
+
+fn dec_bucket(buckets: &mut BTreeMap<u64, u64>, gas_price: u64) {
+    let min_gas_price = MempoolGasPriceBucket::min_gas_price_of(gas_price);
+    if let Entry::Occupied(mut entry) = buckets.entry(min_gas_price) {
+        *entry.get_mut() -= 1;
+        if *entry.get() == 0 {
+            entry.remove();
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
@@ -74,6 +100,17 @@ pub struct OrderedQueueKey {
     pub sequence_number: u64,
 }
 
+impl From<&MempoolTransaction> for OrderedQueueKey {
+    fn from(txn: &MempoolTransaction) -> Self {
+        Self {
+            gas_price: txn.get_gas_price(),
+            expiration_time: txn.expiration_time,
+            address: txn.get_sender(),
+            sequence_number: txn.get_sequence_number(),
+        }
+    }
+}
+
 impl PartialOrd for OrderedQueueKey {
     fn partial_cmp(&self, other: &OrderedQueueKey) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -214,6 +251,21 @@ impl TimelineIndex {
         batch
     }
 
+    /// read all transactions from timeline in range (<start_timeline_id>, <end_timeline_id>]
+    pub(crate) fn timeline_range(
+        &self,
+        start_timeline_id: u64,
+        end_timeline_id: u64,
+    ) -> Vec<(AccountAddress, u64)> {
+        self.timeline
+            .range((
+                Bound::Excluded(start_timeline_id),
+                Bound::Included(end_timeline_id),
+            ))
+            .map(|(_, &txn)| txn)
+            .collect()
+    }
+
     /// add transaction to index
     pub(crate) fn insert(&mut self, txn: &mut MempoolTransaction) {
         self.timeline.insert(
@@ -236,30 +288,99 @@ impl TimelineIndex {
 /// e.g. transactions that can't be included in next block
 /// (because their sequence number is too high)
 /// we keep separate index to be able to efficiently evict them when Mempool is full
+/// Index is ordered like `PriorityIndex`, so that the transactions paying the lowest gas price are
+/// evicted first
+/// Transactions only stay parked for a limited time (their own TTL, counted from the moment they
+/// were parked), and the number of parked transactions of each account is tracked so that it can
+/// be bounded
 pub struct ParkingLotIndex {
-    data: BTreeSet<TxnPointer>,
+    data: BTreeSet<OrderedQueueKey>,
+    // time by which parked transactions have to be removed from Mempool, if still parked
+    expiration_times: HashMap<TxnPointer, Duration>,
+    ttl_index: BTreeSet<TTLOrderingKey>,
+    // number of parked transactions of each account
+    account_sizes: HashMap<AccountAddress, usize>,
+    ttl: Duration,
 }
 
 impl ParkingLotIndex {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(ttl: Duration) -> Self {
         Self {
             data: BTreeSet::new(),
+            expiration_times: HashMap::new(),
+            ttl_index: BTreeSet::new(),
+            account_sizes: HashMap::new(),
+            ttl,
         }
     }
 
     /// add transaction to index
+    /// parking an already parked transaction doesn't reset its TTL
     pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
-        self.data.insert(TxnPointer::from(txn));
+        if !self.data.insert(OrderedQueueKey::from(txn)) {
+            return;
+        }
+        let expiration_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("init timestamp failure")
+            + self.ttl;
+        self.ttl_index.insert(TTLOrderingKey {
+            expiration_time,
+            address: txn.get_sender(),
+            sequence_number: txn.get_sequence_number(),
+        });
+        self.expiration_times
+            .insert(TxnPointer::from(txn), expiration_time);
+        *self.account_sizes.entry(txn.get_sender()).or_insert(0) += 1;
     }
 
     /// remove transaction from index
-    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
-        self.data.remove(&TxnPointer::from(txn));
+    /// returns whether the transaction was parked
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) -> bool {
+        if !self.data.remove(&OrderedQueueKey::from(txn)) {
+            return false;
+        }
+        let (address, sequence_number) = TxnPointer::from(txn);
+        if let Some(expiration_time) = self.expiration_times.remove(&(address, sequence_number)) {
+            self.ttl_index.remove(&TTLOrderingKey {
+                expiration_time,
+                address,
+                sequence_number,
+            });
+        }
+        if let Entry::Occupied(mut entry) = self.account_sizes.entry(address) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+        true
+    }
+
+    pub(crate) fn contains(&self, txn: &MempoolTransaction) -> bool {
+        self.data.contains(&OrderedQueueKey::from(txn))
     }
 
-    /// returns random "non-ready" transaction (with highest sequence number for that account)
-    pub(crate) fn pop(&mut self) -> Option<TxnPointer> {
-        self.data.iter().rev().next().cloned()
+    /// returns iterator over "non-ready" transactions, starting from the lowest gas price
+    /// (and highest sequence number for a given account)
+    pub(crate) fn iter_lowest(&self) -> Iter<OrderedQueueKey> {
+        self.data.iter()
+    }
+
+    /// returns transactions parked for longer than the TTL of the index
+    /// They stay in the index until they are removed from Mempool
+    pub(crate) fn gc(&self, now: Duration) -> Vec<TTLOrderingKey> {
+        let ttl_key = TTLOrderingKey {
+            expiration_time: now,
+            address: AccountAddress::default(),
+            sequence_number: 0,
+        };
+        self.ttl_index.range(..ttl_key).cloned().collect()
+    }
+
+    /// number of parked transactions of `address`
+    pub(crate) fn account_size(&self, address: &AccountAddress) -> usize {
+        self.account_sizes.get(address).cloned().unwrap_or(0)
     }
 
     pub(crate) fn size(&self) -> usize {
@@ -267,6 +388,203 @@ impl ParkingLotIndex {
     }
 }
 
+/// PriorityLaneIndex keeps track of transactions of priority lanes
+/// e.g. transactions of given accounts, or running given scripts, that have block space
+/// and Mempool capacity reserved for them
+/// Each lane has its own `PriorityIndex` of "ready" transactions, on top of the main one
+pub struct PriorityLaneIndex {
+    lanes: Vec<PriorityLane>,
+}
+
+struct PriorityLane {
+    config: PriorityLaneConfig,
+    transactions: HashSet<TxnPointer>,
+    priority_index: PriorityIndex,
+}
+
+impl PriorityLaneIndex {
+    pub(crate) fn new(configs: &[PriorityLaneConfig]) -> Self {
+        Self {
+            lanes: configs
+                .iter()
+                .map(|config| PriorityLane {
+                    config: config.clone(),
+                    transactions: HashSet::new(),
+                    priority_index: PriorityIndex::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// returns the first lane transaction belongs to, if any
+    /// Transactions belong to a lane only if their sender is allowed in it, and if the lane lists
+    /// scripts, only if they run one of them
+    pub(crate) fn lane_of(&self, txn: &MempoolTransaction) -> Option<usize> {
+        let script_hash = match txn.txn.payload() {
+            TransactionPayload::Script(script) => {
+                Some(*HashValue::from_sha3_256(script.code()).as_ref())
+            }
+            _ => None,
+        };
+        self.lanes.iter().position(|lane| {
+            lane.config.addresses.contains(&txn.get_sender())
+                && (lane.config.script_hashes.is_empty()
+                    || script_hash.map_or(false, |hash| lane.config.script_hashes.contains(&hash)))
+        })
+    }
+
+    /// add transaction to lane
+    pub(crate) fn insert(&mut self, lane: usize, txn: &MempoolTransaction) {
+        if let Some(lane) = self.lanes.get_mut(lane) {
+            lane.transactions.insert(TxnPointer::from(txn));
+        }
+    }
+
+    /// remove transaction from its lane
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
+        let ptr = TxnPointer::from(txn);
+        for lane in self.lanes.iter_mut() {
+            if lane.transactions.remove(&ptr) {
+                lane.priority_index.remove(txn);
+            }
+        }
+    }
+
+    /// add transaction to priority index of its lane, once it's "ready"
+    pub(crate) fn insert_ready(&mut self, txn: &MempoolTransaction) {
+        let ptr = TxnPointer::from(txn);
+        for lane in self.lanes.iter_mut() {
+            if lane.transactions.contains(&ptr) {
+                lane.priority_index.insert(txn);
+            }
+        }
+    }
+
+    /// remove transaction from priority index of its lane, once it's "non-ready"
+    pub(crate) fn remove_ready(&mut self, txn: &MempoolTransaction) {
+        for lane in self.lanes.iter_mut() {
+            lane.priority_index.remove(txn);
+        }
+    }
+
+    pub(crate) fn contains(&self, ptr: &TxnPointer) -> bool {
+        self.lanes
+            .iter()
+            .any(|lane| lane.transactions.contains(ptr))
+    }
+
+    pub(crate) fn lane_contains(&self, lane: usize, ptr: &TxnPointer) -> bool {
+        self.lanes
+            .get(lane)
+            .map_or(false, |lane| lane.transactions.contains(ptr))
+    }
+
+    pub(crate) fn is_full(&self, lane: usize) -> bool {
+        self.lanes
+            .get(lane)
+            .map_or(true, |lane| lane.transactions.len() >= lane.config.capacity)
+    }
+
+    pub(crate) fn name(&self, lane: usize) -> &str {
+        self.lanes
+            .get(lane)
+            .map_or("", |lane| lane.config.name.as_str())
+    }
+
+    /// returns name, number of transactions and number of "ready" transactions of each lane
+    pub(crate) fn sizes(&self) -> impl Iterator<Item = (&str, usize, usize)> {
+        self.lanes.iter().map(|lane| {
+            (
+                lane.config.name.as_str(),
+                lane.transactions.len(),
+                lane.priority_index.size(),
+            )
+        })
+    }
+
+    /// returns name, reserved block space and priority queue of each lane
+    pub(crate) fn iter_queues(&self) -> impl Iterator<Item = (&str, u64, PriorityQueueIter)> {
+        self.lanes.iter().map(|lane| {
+            (
+                lane.config.name.as_str(),
+                lane.config.reserved_block_space,
+                lane.priority_index.iter(),
+            )
+        })
+    }
+
+    /// total number of transactions in lanes
+    pub(crate) fn size(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.transactions.len()).sum()
+    }
+}
+
+/// StatsIndex counts transactions by account and by gas price bucket as they enter and leave
+/// Mempool, so that Mempool statistics are computed without iterating over all transactions
+pub struct StatsIndex {
+    // number of transactions by gas price bucket, see `MempoolGasPriceBucket`
+    buckets: BTreeMap<u64, u64>,
+    // number of transactions by account
+    accounts: HashMap<AccountAddress, u64>,
+    size: u64,
+}
+
+impl StatsIndex {
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: BTreeMap::new(),
+            accounts: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    /// add transaction to index
+    pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
+        inc_bucket(&mut self.buckets, txn.get_gas_price());
+        *self.accounts.entry(txn.get_sender()).or_insert(0) += 1;
+        self.size += 1;
+    }
+
+    /// remove transaction from index
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
+        dec_bucket(&mut self.buckets, txn.get_gas_price());
+        if let Entry::Occupied(mut entry) = self.accounts.entry(txn.get_sender()) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+        self.size = self.size.saturating_sub(1);
+    }
+
+    /// returns statistics of Mempool, given the index of its "ready" transactions
+    pub(crate) fn stats(&self, ready: &PriorityIndex) -> MempoolStats {
+        let buckets: Vec<_> = self
+            .buckets
+            .iter()
+            .map(|(min_gas_price, size)| {
+                let ready = ready.bucket_size(*min_gas_price);
+                MempoolGasPriceBucket {
+                    min_gas_price: *min_gas_price,
+                    ready,
+                    parked: size.saturating_sub(ready),
+                }
+            })
+            .collect();
+        let ready = ready.size() as u64;
+        MempoolStats {
+            ready,
+            parked: self.size.saturating_sub(ready),
+            accounts: self.accounts.len() as u64,
+            buckets,
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size as usize
+    }
+}
+
 /// Logical pointer to `MempoolTransaction`
 /// Includes Account's address and transaction sequence number
 pub type TxnPointer = (AccountAddress, u64);
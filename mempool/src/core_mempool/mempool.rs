@@ -7,7 +7,8 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{
     core_mempool::{
-        index::TxnPointer,
+        index::{OrderedQueueKey, TxnPointer},
+        persistence::PersistedTransaction,
         transaction::{MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
     },
@@ -18,12 +19,18 @@ use libra_config::config::NodeConfig;
 use libra_logger::prelude::*;
 use libra_mempool_shared_proto::{
     proto::mempool_status::MempoolAddTransactionStatusCode, MempoolAddTransactionStatus,
+    MempoolStats, MempoolTransactionStatus,
 };
 use libra_types::{account_address::AccountAddress, transaction::SignedTransaction};
 use lru_cache::LruCache;
-use std::{cmp::max, collections::HashSet, convert::TryFrom};
+use std::{
+    cmp::{max, min},
+    collections::HashSet,
+    convert::TryFrom,
+};
 use ttl_cache::TtlCache;
 
+/// Mempool holds transactions that have been submitted but not yet agreed upon
 pub struct Mempool {
     // stores metadata of all transactions in mempool (of all states)
     transactions: TransactionStore,
@@ -34,18 +41,28 @@ pub struct Mempool {
     // used to measure e2e latency of transaction in system, as well as time it takes to pick it up
     // by consensus
     pub(crate) metrics_cache: TtlCache<(AccountAddress, u64), i64>,
+    /// time transactions can stay in Mempool before being garbage collected
     pub system_transaction_timeout: Duration,
+    // transactions added since they were last taken to be persisted on disk,
+    // `None` if mempool persistence is disabled
+    new_transactions: Option<Vec<PersistedTransaction>>,
 }
 
 impl Mempool {
+    /// Creates an empty Mempool
     pub fn new(config: &NodeConfig) -> Self {
         Mempool {
-            transactions: TransactionStore::new(&config.mempool),
+            transactions: TransactionStore::new(&config.mempool, !config.base.role.is_validator()),
             sequence_number_cache: LruCache::new(config.mempool.capacity),
             metrics_cache: TtlCache::new(config.mempool.capacity),
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
+            new_transactions: if config.mempool.persist_transactions {
+                Some(vec![])
+            } else {
+                None
+            },
         }
     }
 
@@ -104,7 +121,7 @@ impl Mempool {
 
     /// Used to add a transaction to the Mempool
     /// Performs basic validation: checks account's balance and sequence number
-    pub(crate) fn add_txn(
+    pub fn add_txn(
         &mut self,
         txn: SignedTransaction,
         gas_amount: u64,
@@ -160,18 +177,50 @@ impl Mempool {
             );
         }
 
+        let persisted_txn = self
+            .new_transactions
+            .as_ref()
+            .map(|_| PersistedTransaction {
+                txn: txn.clone(),
+                broadcast: timeline_state != TimelineState::NonQualified,
+            });
         let txn_info = MempoolTransaction::new(txn, expiration_time, gas_amount, timeline_state);
 
         let status = self.transactions.insert(txn_info, sequence_number);
         OP_COUNTERS.inc(&format!("insert.{:?}", status));
+        if status.code == MempoolAddTransactionStatusCode::Valid {
+            if let (Some(new_transactions), Some(persisted_txn)) =
+                (self.new_transactions.as_mut(), persisted_txn)
+            {
+                new_transactions.push(persisted_txn);
+            }
+        }
         status
     }
 
+    /// Takes the transactions added since the last call, to be appended to the on-disk log
+    pub(crate) fn take_new_transactions(&mut self) -> Vec<PersistedTransaction> {
+        self.new_transactions
+            .as_mut()
+            .map_or_else(Vec::new, std::mem::take)
+    }
+
+    /// Returns all transactions currently in Mempool, to be persisted on disk
+    pub(crate) fn persisted_transactions(&self) -> Vec<PersistedTransaction> {
+        self.transactions
+            .iter()
+            .map(|txn| PersistedTransaction {
+                txn: txn.txn.clone(),
+                broadcast: txn.timeline_state != TimelineState::NonQualified,
+            })
+            .collect()
+    }
+
     /// Fetches next block of transactions for consensus
     /// `batch_size` - size of requested block
     /// `seen_txns` - transactions that were sent to Consensus but were not committed yet
     ///  Mempool should filter out such transactions
-    pub(crate) fn get_block(
+    pub fn get_block(
         &mut self,
         batch_size: u64,
         mut seen: HashSet<TxnPointer>,
@@ -185,39 +234,33 @@ impl Mempool {
         // `skipped` DS and rechecked once it's ancestor becomes available
         let mut skipped = HashSet::new();
 
-        // iterate over the queue of transactions based on gas price
-        'main: for txn in self.transactions.iter_queue() {
-            if seen.contains(&TxnPointer::from(txn)) {
-                continue;
-            }
-            let mut seq = txn.sequence_number;
-            let account_sequence_number = self.sequence_number_cache.get_mut(&txn.address);
-            let seen_previous = seq > 0 && seen.contains(&(txn.address, seq - 1));
-            // include transaction if it's "next" for given account or
-            // we've already sent its ancestor to Consensus
-            if seen_previous || account_sequence_number == Some(&mut seq) {
-                let ptr = TxnPointer::from(txn);
-                seen.insert(ptr);
-                result.push(ptr);
-                if (result.len() as u64) == batch_size {
-                    break;
-                }
-
-                // check if we can now include some transactions
-                // that were skipped before for given account
-                let mut skipped_txn = (txn.address, seq + 1);
-                while skipped.contains(&skipped_txn) {
-                    seen.insert(skipped_txn);
-                    result.push(skipped_txn);
-                    if (result.len() as u64) == batch_size {
-                        break 'main;
-                    }
-                    skipped_txn = (txn.address, skipped_txn.1 + 1);
-                }
-            } else {
-                skipped.insert(TxnPointer::from(txn));
-            }
+        // transactions of priority lanes are picked first, up to the block space reserved for them
+        for (name, reserved_block_space, queue) in self.transactions.iter_priority_lanes() {
+            let block_size = result.len();
+            let max_block_size = min(batch_size, block_size as u64 + reserved_block_space);
+            pick_transactions(
+                queue,
+                max_block_size,
+                &mut self.sequence_number_cache,
+                &mut seen,
+                &mut skipped,
+                &mut result,
+            );
+            OP_COUNTERS.inc_by(
+                &format!("get_block.priority_lane.{}", name),
+                result.len() - block_size,
+            );
         }
+
+        // iterate over the queue of transactions based on gas price
+        pick_transactions(
+            self.transactions.iter_queue(),
+            batch_size,
+            &mut self.sequence_number_cache,
+            &mut seen,
+            &mut skipped,
+            &mut result,
+        );
         // convert transaction pointers to real values
         let block: Vec<_> = result
             .into_iter()
@@ -243,6 +286,28 @@ impl Mempool {
         self.transactions.gc_by_expiration_time(block_time);
     }
 
+    /// Returns the status of the transaction of `sender` with `sequence_number`
+    pub(crate) fn get_transaction_status(
+        &mut self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+    ) -> MempoolTransactionStatus {
+        self.transactions.get_status(sender, sequence_number)
+    }
+
+    /// Returns the transactions of `sender` in Mempool along with their status
+    pub(crate) fn get_account_transactions(
+        &self,
+        sender: &AccountAddress,
+    ) -> Vec<(SignedTransaction, MempoolTransactionStatus)> {
+        self.transactions.get_account_transactions(sender)
+    }
+
+    /// Returns Mempool-wide statistics
+    pub(crate) fn get_stats(&self) -> MempoolStats {
+        self.transactions.get_stats()
+    }
+
     /// Read `count` transactions from timeline since `timeline_id`
     /// Returns block of transactions and new last_timeline_id
     pub(crate) fn read_timeline(
@@ -252,4 +317,67 @@ impl Mempool {
     ) -> (Vec<SignedTransaction>, u64) {
         self.transactions.read_timeline(timeline_id, count)
     }
+
+    /// Read transactions from timeline in range (`start_timeline_id`, `end_timeline_id`]
+    /// Used to send again a batch of transactions previously read from timeline
+    pub(crate) fn timeline_range(
+        &self,
+        start_timeline_id: u64,
+        end_timeline_id: u64,
+    ) -> Vec<SignedTransaction> {
+        self.transactions
+            .timeline_range(start_timeline_id, end_timeline_id)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn check_consistency(&self) {
+        self.transactions.check_consistency()
+    }
+}
+
+/// picks transactions from `queue` for a block, until it holds `max_block_size` transactions
+/// A transaction is picked if it's "next" for its account, or if its ancestor was picked before
+fn pick_transactions<'a>(
+    queue: impl Iterator<Item = &'a OrderedQueueKey>,
+    max_block_size: u64,
+    sequence_number_cache: &mut LruCache<AccountAddress, u64>,
+    seen: &mut HashSet<TxnPointer>,
+    skipped: &mut HashSet<TxnPointer>,
+    result: &mut Vec<TxnPointer>,
+) {
+    if (result.len() as u64) >= max_block_size {
+        return;
+    }
+    'main: for txn in queue {
+        if seen.contains(&TxnPointer::from(txn)) {
+            continue;
+        }
+        let mut seq = txn.sequence_number;
+        let account_sequence_number = sequence_number_cache.get_mut(&txn.address);
+        let seen_previous = seq > 0 && seen.contains(&(txn.address, seq - 1));
+        // include transaction if it's "next" for given account or
+        // we've already sent its ancestor to Consensus
+        if seen_previous || account_sequence_number == Some(&mut seq) {
+            let ptr = TxnPointer::from(txn);
+            seen.insert(ptr);
+            result.push(ptr);
+            if (result.len() as u64) == max_block_size {
+                break;
+            }
+
+            // check if we can now include some transactions
+            // that were skipped before for given account
+            let mut skipped_txn = (txn.address, seq + 1);
+            while skipped.contains(&skipped_txn) {
+                seen.insert(skipped_txn);
+                result.push(skipped_txn);
+                if (result.len() as u64) == max_block_size {
+                    break 'main;
+                }
+                skipped_txn = (txn.address, skipped_txn.1 + 1);
+            }
+        } else {
+            skipped.insert(TxnPointer::from(txn));
+        }
+    }
 }
@@ -3,10 +3,16 @@
 
 mod index;
 mod mempool;
+mod persistence;
 mod transaction;
 mod transaction_store;
 
-pub use self::{index::TxnPointer, mempool::Mempool as CoreMempool, transaction::TimelineState};
+pub use self::{
+    index::TxnPointer,
+    mempool::Mempool as CoreMempool,
+    persistence::{PersistedTransaction, TransactionLog},
+    transaction::TimelineState,
+};
 
 #[cfg(test)]
 mod unit_tests;
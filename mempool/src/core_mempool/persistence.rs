@@ -0,0 +1,127 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! On-disk log of the transactions in mempool, so that pending transactions survive restarts.
+//!
+//! The log is append-only: transactions are appended once they enter mempool and are never
+//! removed. Instead, the log is periodically compacted by rewriting it with the current content of
+//! mempool. Entries of transactions which have since been committed, rejected or expired are
+//! harmless, as every transaction reloaded from the log is re-validated against the latest state
+//! before being added back to mempool.
+//!
+//! Each entry is the LCS serialization of a [`PersistedTransaction`], prefixed by its length as a
+//! little-endian u32. A partially written entry at the end of the log, e.g. after a crash, is
+//! discarded when the log is opened.
+use anyhow::Result;
+use libra_logger::prelude::*;
+use libra_types::transaction::SignedTransaction;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    mem::size_of,
+    path::{Path, PathBuf},
+};
+
+/// A transaction persisted in the log.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PersistedTransaction {
+    pub txn: SignedTransaction,
+    // whether the transaction is broadcast to peers, i.e. whether it was not received from an
+    // upstream peer
+    pub broadcast: bool,
+}
+
+/// Append-only log of the transactions added to mempool.
+pub struct TransactionLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl TransactionLog {
+    /// Opens the log at `path`, creating it if it doesn't exist yet, and returns it along with
+    /// the transactions it holds.
+    pub fn open(path: &Path) -> Result<(Self, Vec<PersistedTransaction>)> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+
+        let (txns, len) = decode(&bytes);
+        if len < bytes.len() {
+            warn!(
+                "[mempool] Discarding {} trailing bytes of transaction log {:?}",
+                bytes.len() - len,
+                path
+            );
+            file.set_len(len as u64)?;
+        }
+        let log = Self {
+            path: path.to_path_buf(),
+            file,
+        };
+        Ok((log, txns))
+    }
+
+    /// Appends `txns` to the log.
+    pub fn append(&mut self, txns: &[PersistedTransaction]) -> Result<()> {
+        if txns.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(&encode(txns)?)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Replaces the content of the log with `txns`.
+    ///
+    /// The new log is written next to the current one before replacing it, so that a crash while
+    /// compacting leaves either of the two logs in place.
+    pub fn compact(&mut self, txns: &[PersistedTransaction]) -> Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&encode(txns)?)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+fn encode(txns: &[PersistedTransaction]) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    for txn in txns {
+        let entry = lcs::to_bytes(txn)?;
+        bytes.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&entry);
+    }
+    Ok(bytes)
+}
+
+/// Decodes the entries of a log, stopping at the first incomplete or corrupted entry. Returns the
+/// transactions along with the length of the valid prefix of the log.
+fn decode(bytes: &[u8]) -> (Vec<PersistedTransaction>, usize) {
+    let mut txns = vec![];
+    let mut offset = 0;
+    while bytes.len() - offset >= size_of::<u32>() {
+        let len_bytes = &bytes[offset..offset + size_of::<u32>()];
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let start = offset + size_of::<u32>();
+        if bytes.len() - start < len {
+            break;
+        }
+        match lcs::from_bytes(&bytes[start..start + len]) {
+            Ok(txn) => txns.push(txn),
+            Err(_) => break,
+        }
+        offset = start + len;
+    }
+    (txns, offset)
+}
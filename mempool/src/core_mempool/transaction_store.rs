@@ -4,8 +4,9 @@
 use crate::{
     core_mempool::{
         index::{
-            AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
-            TimelineIndex,
+            AccountTransactions, OrderedQueueKey, ParkingLotIndex, PriorityIndex,
+            PriorityLaneIndex, PriorityQueueIter, StatsIndex, TTLIndex, TTLOrderingKey,
+            TimelineIndex, TxnPointer,
         },
         transaction::{MempoolTransaction, TimelineState},
     },
@@ -15,9 +16,11 @@ use anyhow::{format_err, Result};
 use libra_config::config::MempoolConfig;
 use libra_logger::prelude::*;
 use libra_mempool_shared_proto::{
-    proto::mempool_status::MempoolAddTransactionStatusCode, MempoolAddTransactionStatus,
+    proto::mempool_status::{MempoolAddTransactionStatusCode, MempoolRemovalReason},
+    MempoolAddTransactionStatus, MempoolStats, MempoolTransactionStatus,
 };
 use libra_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use lru_cache::LruCache;
 use mirai_annotations::*;
 use std::{
     collections::HashMap,
@@ -40,15 +43,29 @@ pub struct TransactionStore {
     system_ttl_index: TTLIndex,
     timeline_index: TimelineIndex,
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
+    // and removes them once they're parked for too long
     parking_lot_index: ParkingLotIndex,
+    // keeps track of transactions of priority lanes, which have reserved block space and capacity
+    priority_lanes: PriorityLaneIndex,
+    // counts transactions by account and gas price, reported to clients querying Mempool stats
+    stats_index: StatsIndex,
+
+    // why recently removed transactions left mempool, reported to clients querying their status
+    removed_transactions: LruCache<TxnPointer, MempoolRemovalReason>,
 
     // configuration
     capacity: usize,
     capacity_per_user: usize,
+    parked_capacity_per_user: usize,
+    evict_by_gas_price: bool,
+    replacement_gas_price_bump_percent: u64,
+    // broadcast transactions waiting on a sequence number gap too, so that the upstream peers of
+    // a full node know about the gap and hold the transactions until it's filled
+    broadcast_parked: bool,
 }
 
 impl TransactionStore {
-    pub(crate) fn new(config: &MempoolConfig) -> Self {
+    pub(crate) fn new(config: &MempoolConfig, broadcast_parked: bool) -> Self {
         Self {
             // main DS
             transactions: HashMap::new(),
@@ -60,11 +77,21 @@ impl TransactionStore {
             })),
             priority_index: PriorityIndex::new(),
             timeline_index: TimelineIndex::new(),
-            parking_lot_index: ParkingLotIndex::new(),
+            parking_lot_index: ParkingLotIndex::new(Duration::from_secs(
+                config.parked_transaction_timeout_secs,
+            )),
+            priority_lanes: PriorityLaneIndex::new(&config.priority_lanes),
+            stats_index: StatsIndex::new(),
+
+            removed_transactions: LruCache::new(config.capacity),
 
             // configuration
             capacity: config.capacity,
             capacity_per_user: config.capacity_per_user,
+            parked_capacity_per_user: config.parked_capacity_per_user,
+            evict_by_gas_price: config.evict_by_gas_price,
+            replacement_gas_price_bump_percent: config.replacement_gas_price_bump_percent,
+            broadcast_parked,
         }
     }
 
@@ -91,20 +118,13 @@ impl TransactionStore {
         txn: MempoolTransaction,
         current_sequence_number: u64,
     ) -> MempoolAddTransactionStatus {
-        if self.handle_gas_price_update(&txn).is_err() {
+        if let Err(e) = self.handle_gas_price_update(&txn) {
             return MempoolAddTransactionStatus::new(
                 MempoolAddTransactionStatusCode::InvalidUpdate,
-                format!("Failed to update gas price to {}", txn.get_gas_price()),
-            );
-        }
-
-        if self.check_if_full() {
-            return MempoolAddTransactionStatus::new(
-                MempoolAddTransactionStatusCode::MempoolIsFull,
                 format!(
-                    "mempool size: {}, capacity: {}",
-                    self.system_ttl_index.size(),
-                    self.capacity,
+                    "Failed to update gas price to {}: {}",
+                    txn.get_gas_price(),
+                    e
                 ),
             );
         }
@@ -118,22 +138,64 @@ impl TransactionStore {
 
         self.clean_committed_transactions(&address, current_sequence_number);
 
-        if let Some(txns) = self.transactions.get_mut(&address) {
-            // capacity check
-            if txns.len() >= self.capacity_per_user {
-                return MempoolAddTransactionStatus::new(
-                    MempoolAddTransactionStatusCode::TooManyTransactions,
-                    format!(
-                        "txns length: {} capacity per user: {}",
-                        txns.len(),
-                        self.capacity_per_user,
-                    ),
-                );
+        if self.check_if_account_full(&txn) {
+            return MempoolAddTransactionStatus::new(
+                MempoolAddTransactionStatusCode::TooManyTransactions,
+                format!(
+                    "txns length: {} capacity per user: {}",
+                    self.transactions
+                        .get(&address)
+                        .map_or(0, AccountTransactions::len),
+                    self.capacity_per_user,
+                ),
+            );
+        }
+
+        if self.check_if_account_parking_lot_full(&txn, current_sequence_number) {
+            return MempoolAddTransactionStatus::new(
+                MempoolAddTransactionStatusCode::TooManyTransactions,
+                format!(
+                    "txn sequence number: {}, first missing sequence number: {}, parked txns: {}, \
+                     parked capacity per user: {}",
+                    sequence_number,
+                    self.first_missing_sequence_number(&address, current_sequence_number),
+                    self.parking_lot_index.account_size(&address),
+                    self.parked_capacity_per_user,
+                ),
+            );
+        }
+
+        // transactions of priority lanes only count towards the capacity of their lane, unless
+        // it's full, in which case they compete for the rest of Mempool like any transaction
+        let lane = match self.priority_lanes.lane_of(&txn) {
+            Some(lane) if self.check_if_lane_full(lane, &txn) => {
+                OP_COUNTERS.inc(&format!(
+                    "priority_lane.{}.full",
+                    self.priority_lanes.name(lane)
+                ));
+                None
             }
+            lane => lane,
+        };
+        if lane.is_none() && self.check_if_full(&txn) {
+            return MempoolAddTransactionStatus::new(
+                MempoolAddTransactionStatusCode::MempoolIsFull,
+                format!(
+                    "mempool size: {}, capacity: {}",
+                    self.unreserved_size(),
+                    self.capacity,
+                ),
+            );
+        }
 
+        if let Some(txns) = self.transactions.get_mut(&address) {
             // insert into storage and other indexes
             self.system_ttl_index.insert(&txn);
             self.expiration_time_index.insert(&txn);
+            self.stats_index.insert(&txn);
+            if let Some(lane) = lane {
+                self.priority_lanes.insert(lane, &txn);
+            }
             txns.insert(sequence_number, txn);
             self.track_indices();
         }
@@ -145,44 +207,204 @@ impl TransactionStore {
         OP_COUNTERS.set("txn.system_ttl_index", self.system_ttl_index.size());
         OP_COUNTERS.set("txn.parking_lot_index", self.parking_lot_index.size());
         OP_COUNTERS.set("txn.priority_index", self.priority_index.size());
+        for (name, size, ready) in self.priority_lanes.sizes() {
+            OP_COUNTERS.set(&format!("txn.priority_lane.{}", name), size);
+            OP_COUNTERS.set(&format!("txn.priority_lane.{}.ready", name), ready);
+        }
+    }
+
+    /// number of transactions in Mempool, except the ones of priority lanes
+    fn unreserved_size(&self) -> usize {
+        self.system_ttl_index.size() - self.priority_lanes.size()
     }
 
     /// checks if Mempool is full
-    /// If it's full, tries to free some space by evicting transactions from ParkingLot
-    fn check_if_full(&mut self) -> bool {
-        if self.system_ttl_index.size() >= self.capacity {
-            // try to free some space in Mempool from ParkingLot
-            if let Some((address, sequence_number)) = self.parking_lot_index.pop() {
-                if let Some(txn) = self
-                    .transactions
-                    .get_mut(&address)
-                    .and_then(|txns| txns.remove(&sequence_number))
-                {
-                    self.index_remove(&txn);
+    /// If it's full, tries to free some space for `txn` by evicting a transaction
+    /// (see `eviction_candidate`)
+    fn check_if_full(&mut self, txn: &MempoolTransaction) -> bool {
+        if self.unreserved_size() >= self.capacity {
+            // transactions of priority lanes have their own capacity
+            let candidate = self.eviction_candidate(txn, |ptr| !self.priority_lanes.contains(ptr));
+            if let Some((address, sequence_number)) = candidate {
+                self.evict(&address, sequence_number);
+            }
+        }
+        self.unreserved_size() >= self.capacity
+    }
+
+    /// checks if priority lane `lane` is full
+    /// If it's full, tries to free some space for `txn` by evicting a transaction of the lane
+    /// (see `eviction_candidate`)
+    fn check_if_lane_full(&mut self, lane: usize, txn: &MempoolTransaction) -> bool {
+        if self.priority_lanes.is_full(lane) {
+            let candidate =
+                self.eviction_candidate(txn, |ptr| self.priority_lanes.lane_contains(lane, ptr));
+            if let Some((address, sequence_number)) = candidate {
+                self.evict(&address, sequence_number);
+            }
+        }
+        self.priority_lanes.is_full(lane)
+    }
+
+    /// picks the transaction to evict among the ones matching `in_pool` to make room for `txn`
+    /// Non-ready transactions from ParkingLot are evicted first. If `evict_by_gas_price` is set,
+    /// only transactions paying a lower gas price than `txn` can be evicted (or the same gas price
+    /// for non-ready ones), and ready transactions are evicted if they pay less than non-ready
+    fn eviction_candidate<F>(&self, txn: &MempoolTransaction, in_pool: F) -> Option<TxnPointer>
+    where
+        F: Fn(&TxnPointer) -> bool,
+    {
+        // `txn` can't be included in a block before preceding transactions of its account
+        let evictable = |key: &&OrderedQueueKey| {
+            (key.address != txn.get_sender() || key.sequence_number > txn.get_sequence_number())
+                && in_pool(&TxnPointer::from(*key))
+        };
+        let parked = self.parking_lot_index.iter_lowest().find(evictable);
+        if !self.evict_by_gas_price {
+            return parked.map(TxnPointer::from);
+        }
+        let gas_price = txn.get_gas_price();
+        let parked = parked.filter(|key| key.gas_price <= gas_price);
+        let ready = self
+            .priority_index
+            .iter_lowest()
+            .find(evictable)
+            .filter(|key| key.gas_price < gas_price);
+        let candidate = match (parked, ready) {
+            (Some(parked), Some(ready)) if ready.gas_price < parked.gas_price => Some(ready),
+            (Some(parked), _) => Some(parked),
+            (None, ready) => ready,
+        };
+        candidate.map(TxnPointer::from)
+    }
+
+    /// checks if `txn` would wait on a sequence number gap, while its account already reached its
+    /// capacity of such transactions
+    /// If it did and `evict_by_gas_price` is set, tries to make room for `txn` by evicting
+    /// the parked transaction of the account paying the lowest gas price
+    fn check_if_account_parking_lot_full(
+        &mut self,
+        txn: &MempoolTransaction,
+        current_sequence_number: u64,
+    ) -> bool {
+        let address = txn.get_sender();
+        if self.parking_lot_index.account_size(&address) < self.parked_capacity_per_user
+            || txn.get_sequence_number()
+                <= self.first_missing_sequence_number(&address, current_sequence_number)
+        {
+            return false;
+        }
+        if !self.evict_by_gas_price {
+            return true;
+        }
+        let parking_lot_index = &self.parking_lot_index;
+        let victim = self.transactions.get(&address).and_then(|txns| {
+            txns.values()
+                .filter(|t| parking_lot_index.contains(t))
+                .map(OrderedQueueKey::from)
+                .min()
+        });
+        match victim {
+            Some(key) if key.gas_price < txn.get_gas_price() => {
+                self.evict(&key.address, key.sequence_number);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// returns the sequence number following the "ready" transactions of `address`:
+    /// transactions with a higher sequence number wait for it
+    fn first_missing_sequence_number(
+        &self,
+        address: &AccountAddress,
+        current_sequence_number: u64,
+    ) -> u64 {
+        let mut sequence_number = current_sequence_number;
+        if let Some(txns) = self.transactions.get(address) {
+            while txns.contains_key(&sequence_number) {
+                sequence_number += 1;
+            }
+        }
+        sequence_number
+    }
+
+    /// checks if account of `txn` reached its capacity
+    /// If it did and `evict_by_gas_price` is set, tries to make room for `txn` by evicting
+    /// the account transaction paying the lowest gas price among the ones following `txn`
+    fn check_if_account_full(&mut self, txn: &MempoolTransaction) -> bool {
+        let victim = match self.transactions.get(&txn.get_sender()) {
+            Some(txns) if txns.len() >= self.capacity_per_user => {
+                if !self.evict_by_gas_price {
+                    return true;
                 }
+                txns.range((Bound::Excluded(txn.get_sequence_number()), Bound::Unbounded))
+                    .map(|(_, t)| OrderedQueueKey::from(t))
+                    .min()
+            }
+            _ => return false,
+        };
+        match victim {
+            Some(key) if key.gas_price < txn.get_gas_price() => {
+                self.evict(&key.address, key.sequence_number);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// removes transaction to make room for a new one
+    /// and marks all following transactions of the account as non-ready
+    fn evict(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some(txns) = self.transactions.get_mut(address) {
+            for (_, t) in txns.range((Bound::Excluded(sequence_number), Bound::Unbounded)) {
+                self.parking_lot_index.insert(&t);
+                self.priority_index.remove(&t);
+                self.priority_lanes.remove_ready(&t);
+                self.timeline_index.remove(&t);
+            }
+            if let Some(txn) = txns.remove(&sequence_number) {
+                let status = if self.priority_index.contains(&txn) {
+                    "active"
+                } else {
+                    "parked"
+                };
+                OP_COUNTERS.inc(&format!("evict.{}", status));
+                self.index_remove(&txn);
+                self.removed_transactions
+                    .insert((*address, sequence_number), MempoolRemovalReason::Evicted);
             }
         }
-        self.system_ttl_index.size() >= self.capacity
     }
 
     /// check if transaction is already present in Mempool
     /// e.g. given request is update
-    /// we allow increase in gas price to speed up process
+    /// we allow increase in gas price to speed up process,
+    /// by at least `replacement_gas_price_bump_percent`
     fn handle_gas_price_update(&mut self, txn: &MempoolTransaction) -> Result<()> {
+        let bump_percent = self.replacement_gas_price_bump_percent;
         if let Some(txns) = self.transactions.get_mut(&txn.get_sender()) {
             if let Some(current_version) = txns.get_mut(&txn.get_sequence_number()) {
+                let current_gas_price = current_version.get_gas_price();
+                let is_bumped = current_gas_price < txn.get_gas_price()
+                    && u128::from(txn.get_gas_price()) * 100
+                        >= u128::from(current_gas_price) * (100 + u128::from(bump_percent));
                 if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
                     && current_version.txn.payload() == txn.txn.payload()
                     && current_version.txn.expiration_time() == txn.txn.expiration_time()
-                    && current_version.get_gas_price() < txn.get_gas_price()
+                    && is_bumped
                 {
                     if let Some(txn) = txns.remove(&txn.get_sequence_number()) {
                         self.index_remove(&txn);
                     }
                 } else {
-                    return Err(format_err!("Invalid gas price update. txn gas price: {}, current_version gas price: {}",
-                            txn.get_gas_price(),
-                            current_version.get_gas_price()));
+                    return Err(format_err!(
+                        "Invalid gas price update. txn gas price: {}, current_version gas price: \
+                         {}, min bump: {}%",
+                        txn.get_gas_price(),
+                        current_gas_price,
+                        bump_percent
+                    ));
                 }
             }
         }
@@ -194,6 +416,8 @@ impl TransactionStore {
     /// supposed to be included in both PriorityIndex (ordering for Consensus) and
     /// TimelineIndex (txns for SharedMempool)
     /// Other txns are considered to be "non-ready" and should be added to ParkingLotIndex
+    /// Once the sequence number gap a parked transaction waits on is filled (e.g. the missing
+    /// transaction is submitted or committed), it's promoted from ParkingLotIndex
     fn process_ready_transactions(
         &mut self,
         address: &AccountAddress,
@@ -201,8 +425,13 @@ impl TransactionStore {
     ) {
         if let Some(txns) = self.transactions.get_mut(&address) {
             let mut sequence_number = current_sequence_number;
+            let mut promoted_txns = 0;
             while let Some(txn) = txns.get_mut(&sequence_number) {
                 self.priority_index.insert(txn);
+                self.priority_lanes.insert_ready(txn);
+                if self.parking_lot_index.remove(txn) {
+                    promoted_txns += 1;
+                }
 
                 if txn.timeline_state == TimelineState::NotReady {
                     self.timeline_index.insert(txn);
@@ -210,18 +439,26 @@ impl TransactionStore {
                 sequence_number += 1;
             }
 
+            if promoted_txns > 0 {
+                OP_COUNTERS.inc_by("promote.gap_filled", promoted_txns);
+            }
+
             let mut parking_lot_txns = 0;
             for (_, txn) in txns.range_mut((Bound::Excluded(sequence_number), Bound::Unbounded)) {
-                match txn.timeline_state {
-                    TimelineState::Ready(_) => {}
-                    _ => {
-                        self.parking_lot_index.insert(&txn);
-                        parking_lot_txns += 1;
-                    }
+                self.parking_lot_index.insert(&txn);
+                if self.broadcast_parked && txn.timeline_state == TimelineState::NotReady {
+                    self.timeline_index.insert(txn);
                 }
+                parking_lot_txns += 1;
             }
-            debug!("[Mempool] txns for account {:?}. Current sequence_number: {}, length: {}, parking lot: {}",
-                address, current_sequence_number, txns.len(), parking_lot_txns,
+            debug!(
+                "[Mempool] txns for account {:?}. Current sequence_number: {}, length: {}, \
+                 parking lot: {}, promoted: {}",
+                address,
+                current_sequence_number,
+                txns.len(),
+                parking_lot_txns,
+                promoted_txns,
             );
         }
     }
@@ -239,6 +476,10 @@ impl TransactionStore {
 
             for transaction in txns_for_removal.values() {
                 self.index_remove(transaction);
+                self.removed_transactions.insert(
+                    TxnPointer::from(transaction),
+                    MempoolRemovalReason::Committed,
+                );
             }
         }
     }
@@ -259,6 +500,10 @@ impl TransactionStore {
         if let Some(txns) = self.transactions.remove(&account) {
             for transaction in txns.values() {
                 self.index_remove(&transaction);
+                self.removed_transactions.insert(
+                    TxnPointer::from(transaction),
+                    MempoolRemovalReason::Rejected,
+                );
             }
         }
     }
@@ -270,6 +515,8 @@ impl TransactionStore {
         self.priority_index.remove(&txn);
         self.timeline_index.remove(&txn);
         self.parking_lot_index.remove(&txn);
+        self.priority_lanes.remove(&txn);
+        self.stats_index.remove(&txn);
         self.track_indices();
     }
 
@@ -309,34 +556,64 @@ impl TransactionStore {
         (batch, last_timeline_id)
     }
 
-    /// GC old transactions
+    /// Read transactions from timeline in range (`start_timeline_id`, `end_timeline_id`]
+    pub(crate) fn timeline_range(
+        &self,
+        start_timeline_id: u64,
+        end_timeline_id: u64,
+    ) -> Vec<SignedTransaction> {
+        self.timeline_index
+            .timeline_range(start_timeline_id, end_timeline_id)
+            .into_iter()
+            .filter_map(|(address, sequence_number)| self.get(&address, sequence_number))
+            .collect()
+    }
+
+    /// GC old transactions, and transactions parked for too long
     pub(crate) fn gc_by_system_ttl(&mut self) {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("init timestamp failure");
 
-        self.gc(now, true);
+        let expired = self.system_ttl_index.gc(now);
+        self.gc(
+            "gc.system_ttl_index",
+            expired,
+            MempoolRemovalReason::SystemTtlExpired,
+        );
+        let expired = self.parking_lot_index.gc(now);
+        self.gc(
+            "gc.parking_lot_index",
+            expired,
+            MempoolRemovalReason::ParkedTtlExpired,
+        );
     }
 
     /// GC old transactions based on client-specified expiration time
     pub(crate) fn gc_by_expiration_time(&mut self, block_time: Duration) {
-        self.gc(block_time, false);
+        let expired = self.expiration_time_index.gc(block_time);
+        self.gc(
+            "gc.expiration_time_index",
+            expired,
+            MempoolRemovalReason::Expired,
+        );
     }
 
-    fn gc(&mut self, now: Duration, by_system_ttl: bool) {
-        let (index_name, index) = if by_system_ttl {
-            ("gc.system_ttl_index", &mut self.system_ttl_index)
-        } else {
-            ("gc.expiration_time_index", &mut self.expiration_time_index)
-        };
+    fn gc(
+        &mut self,
+        index_name: &str,
+        expired: Vec<TTLOrderingKey>,
+        removal_reason: MempoolRemovalReason,
+    ) {
         OP_COUNTERS.inc(index_name);
 
-        for key in index.gc(now) {
+        for key in expired {
             if let Some(txns) = self.transactions.get_mut(&key.address) {
                 // mark all following transactions as non-ready
                 for (_, t) in txns.range((Bound::Excluded(key.sequence_number), Bound::Unbounded)) {
                     self.parking_lot_index.insert(&t);
                     self.priority_index.remove(&t);
+                    self.priority_lanes.remove_ready(&t);
                     self.timeline_index.remove(&t);
                 }
                 if let Some(txn) = txns.remove(&key.sequence_number) {
@@ -344,13 +621,123 @@ impl TransactionStore {
                     let status = if is_active { "active" } else { "parked" };
                     OP_COUNTERS.inc(&format!("{}.{}", index_name, status));
                     self.index_remove(&txn);
+                    self.removed_transactions
+                        .insert(TxnPointer::from(&txn), removal_reason);
                 }
             }
         }
         self.track_indices();
     }
 
+    /// returns the status of the transaction of `address` with `sequence_number`, which may have
+    /// recently left mempool
+    pub(crate) fn get_status(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> MempoolTransactionStatus {
+        if let Some(txn) = self
+            .transactions
+            .get(address)
+            .and_then(|txns| txns.get(&sequence_number))
+        {
+            return self.status(txn);
+        }
+        match self
+            .removed_transactions
+            .get_mut(&(*address, sequence_number))
+        {
+            Some(reason) => MempoolTransactionStatus::Removed(*reason),
+            None => MempoolTransactionStatus::Unknown,
+        }
+    }
+
+    /// returns all transactions of `address` along with their status,
+    /// ordered by sequence number
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> Vec<(SignedTransaction, MempoolTransactionStatus)> {
+        self.transactions
+            .get(address)
+            .map_or_else(Vec::new, |txns| {
+                txns.values()
+                    .map(|txn| (txn.txn.clone(), self.status(txn)))
+                    .collect()
+            })
+    }
+
+    /// returns number of ready and parked transactions, overall and by gas price
+    pub(crate) fn get_stats(&self) -> MempoolStats {
+        self.stats_index.stats(&self.priority_index)
+    }
+
+    /// status of a transaction in mempool
+    fn status(&self, txn: &MempoolTransaction) -> MempoolTransactionStatus {
+        if self.priority_index.contains(txn) {
+            return MempoolTransactionStatus::Ready;
+        }
+        // non-ready transactions wait for the transaction preceding
+        // the sequential transactions of the account they belong to
+        let mut sequence_number = txn.get_sequence_number();
+        if let Some(txns) = self.transactions.get(&txn.get_sender()) {
+            while sequence_number > 0 && txns.contains_key(&(sequence_number - 1)) {
+                sequence_number -= 1;
+            }
+        }
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number: sequence_number.saturating_sub(1),
+        }
+    }
+
+    /// iterates over all transactions, in order of sequence number within each account
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &MempoolTransaction> {
+        self.transactions.values().flat_map(|txns| txns.values())
+    }
+
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
+
+    /// iterates over priority lanes: their name, reserved block space and priority queue
+    pub(crate) fn iter_priority_lanes(
+        &self,
+    ) -> impl Iterator<Item = (&str, u64, PriorityQueueIter)> {
+        self.priority_lanes.iter_queues()
+    }
+
+    /// checks that indexes are consistent with stored transactions: every transaction is either
+    /// ready (sequential to the first ready transaction of its account) or parked
+    #[cfg(test)]
+    pub(crate) fn check_consistency(&self) {
+        let mut size = 0;
+        for (address, txns) in &self.transactions {
+            let mut parked = 0;
+            let mut previous: Option<(u64, bool)> = None;
+            for (sequence_number, txn) in txns {
+                let is_ready = match previous {
+                    Some((previous_sequence_number, previous_is_ready)) => {
+                        previous_is_ready && previous_sequence_number + 1 == *sequence_number
+                    }
+                    None => self.priority_index.contains(txn),
+                };
+                assert_eq!(self.priority_index.contains(txn), is_ready);
+                assert_eq!(self.parking_lot_index.contains(txn), !is_ready);
+                previous = Some((*sequence_number, is_ready));
+                if !is_ready {
+                    parked += 1;
+                }
+                size += 1;
+            }
+            assert_eq!(self.parking_lot_index.account_size(address), parked);
+        }
+        assert_eq!(self.system_ttl_index.size(), size);
+        assert_eq!(self.expiration_time_index.size(), size);
+        assert_eq!(self.stats_index.size(), size);
+        assert_eq!(
+            self.priority_index.size() + self.parking_lot_index.size(),
+            size
+        );
+        assert!(self.unreserved_size() <= self.capacity);
+    }
 }
@@ -22,7 +22,7 @@ pub(crate) fn setup_mempool() -> (CoreMempool, ConsensusMock) {
 }
 
 static ACCOUNTS: Lazy<Vec<AccountAddress>> =
-    Lazy::new(|| vec![AccountAddress::random(), AccountAddress::random()]);
+    Lazy::new(|| (0..3).map(|_| AccountAddress::random()).collect());
 
 #[derive(Clone)]
 pub struct TestTransaction {
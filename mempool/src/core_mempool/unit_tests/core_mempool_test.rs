@@ -8,10 +8,14 @@ use crate::core_mempool::{
     },
     CoreMempool, TimelineState,
 };
-use libra_config::config::NodeConfig;
-use libra_mempool_shared_proto::proto::mempool_status::MempoolAddTransactionStatusCode;
+use libra_config::config::{NodeConfig, PriorityLaneConfig, RoleType};
+use libra_crypto::HashValue;
+use libra_mempool_shared_proto::{
+    proto::mempool_status::{MempoolAddTransactionStatusCode, MempoolRemovalReason},
+    MempoolGasPriceBucket, MempoolStats, MempoolTransactionStatus,
+};
 use libra_types::transaction::SignedTransaction;
-use std::{collections::HashSet, time::Duration};
+use std::{collections::HashSet, iter::FromIterator, time::Duration};
 
 #[test]
 fn test_transaction_ordering() {
@@ -281,6 +285,34 @@ fn test_timeline() {
     assert_eq!(view(timeline), vec![5]);
 }
 
+#[test]
+fn test_full_node_timeline() {
+    let mut config = NodeConfig::random();
+    config.base.role = RoleType::FullNode;
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(1, 0, 1), TestTransaction::new(1, 2, 1)],
+    );
+
+    // full nodes forward transactions waiting on a sequence number gap to their upstream peers
+    let (timeline, _) = pool.read_timeline(0, 10);
+    let sequence_numbers: Vec<_> = timeline
+        .iter()
+        .map(SignedTransaction::sequence_number)
+        .collect();
+    assert_eq!(sequence_numbers, vec![0, 2]);
+
+    // and don't broadcast them again once the gap is filled
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 1, 1)]);
+    let (timeline, _) = pool.read_timeline(0, 10);
+    let sequence_numbers: Vec<_> = timeline
+        .iter()
+        .map(SignedTransaction::sequence_number)
+        .collect();
+    assert_eq!(sequence_numbers, vec![0, 2, 1]);
+}
+
 #[test]
 fn test_capacity() {
     let mut config = NodeConfig::random();
@@ -373,3 +405,391 @@ fn test_clean_stuck_transactions() {
     assert_eq!(block.len(), 1);
     assert_eq!(block[0].sequence_number(), 10);
 }
+
+#[test]
+fn test_transaction_status() {
+    let mut pool = setup_mempool().0;
+    let address = TestTransaction::get_address(1);
+    for seq in &[0, 1, 3, 4] {
+        add_txn(&mut pool, TestTransaction::new(1, *seq, 1)).unwrap();
+    }
+    let txn = TestTransaction::new(0, 0, 1)
+        .make_signed_transaction_with_expiration_time(Duration::from_secs(0));
+    pool.add_txn(txn, 0, 0, 100, TimelineState::NotReady);
+
+    assert_eq!(
+        pool.get_transaction_status(&address, 1),
+        MempoolTransactionStatus::Ready
+    );
+    assert_eq!(
+        pool.get_transaction_status(&address, 4),
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number: 2
+        }
+    );
+    assert_eq!(
+        pool.get_transaction_status(&address, 2),
+        MempoolTransactionStatus::Unknown
+    );
+    let statuses: Vec<_> = pool
+        .get_account_transactions(&address)
+        .into_iter()
+        .map(|(txn, status)| (txn.sequence_number(), status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (0, MempoolTransactionStatus::Ready),
+            (1, MempoolTransactionStatus::Ready),
+            (
+                3,
+                MempoolTransactionStatus::Parked {
+                    missing_sequence_number: 2
+                }
+            ),
+            (
+                4,
+                MempoolTransactionStatus::Parked {
+                    missing_sequence_number: 2
+                }
+            ),
+        ]
+    );
+
+    // removed transactions are reported along with the reason of their removal
+    pool.remove_transaction(&address, 0, false);
+    assert_eq!(
+        pool.get_transaction_status(&address, 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Committed)
+    );
+    pool.gc_by_expiration_time(Duration::from_secs(1));
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Expired)
+    );
+    pool.remove_transaction(&address, 1, true);
+    assert_eq!(
+        pool.get_transaction_status(&address, 3),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Rejected)
+    );
+    assert!(pool.get_account_transactions(&address).is_empty());
+}
+
+#[test]
+fn test_eviction_status() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 2;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 2, 1)).unwrap();
+    // make room for a new transaction by evicting the parked one
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(1), 2),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+}
+
+#[test]
+fn test_stats() {
+    let mut pool = setup_mempool().0;
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 3)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 2)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 1, 10)).unwrap();
+
+    assert_eq!(
+        pool.get_stats(),
+        MempoolStats {
+            ready: 3,
+            parked: 1,
+            accounts: 2,
+            buckets: vec![
+                MempoolGasPriceBucket {
+                    min_gas_price: 1,
+                    ready: 1,
+                    parked: 0,
+                },
+                MempoolGasPriceBucket {
+                    min_gas_price: 2,
+                    ready: 1,
+                    parked: 1,
+                },
+                MempoolGasPriceBucket {
+                    min_gas_price: 8,
+                    ready: 1,
+                    parked: 0,
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_stats_after_commit() {
+    let mut pool = setup_mempool().0;
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 3)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 2)).unwrap();
+
+    // committing the first transaction of account 0 leaves its parked transaction waiting
+    pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+    // committing the transaction of account 1 leaves it without transactions
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+
+    assert_eq!(
+        pool.get_stats(),
+        MempoolStats {
+            ready: 0,
+            parked: 1,
+            accounts: 1,
+            buckets: vec![MempoolGasPriceBucket {
+                min_gas_price: 2,
+                ready: 0,
+                parked: 1,
+            }],
+        }
+    );
+
+    // filling the gap makes the parked transaction ready
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    let stats = pool.get_stats();
+    assert_eq!((stats.ready, stats.parked, stats.accounts), (2, 0, 1));
+}
+
+fn priority_lane(
+    addresses: Vec<usize>,
+    reserved_block_space: u64,
+    capacity: usize,
+) -> PriorityLaneConfig {
+    PriorityLaneConfig {
+        name: "test".to_string(),
+        addresses: addresses
+            .into_iter()
+            .map(TestTransaction::get_address)
+            .collect(),
+        script_hashes: HashSet::new(),
+        reserved_block_space,
+        capacity,
+    }
+}
+
+#[test]
+fn test_priority_lane_reserved_block_space() {
+    let mut config = NodeConfig::random();
+    config.mempool.priority_lanes = vec![priority_lane(vec![0], 1, 10)];
+    let mut pool = CoreMempool::new(&config);
+    let lane_txns = add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 0, 1), TestTransaction::new(0, 1, 1)],
+    );
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(1, 0, 10),
+            TestTransaction::new(1, 1, 10),
+        ],
+    );
+
+    // lane transaction gets in the block despite its lower gas price
+    let block = pool.get_block(2, HashSet::new());
+    assert_eq!(block, vec![lane_txns[0].clone(), txns[0].clone()]);
+}
+
+#[test]
+fn test_priority_lane_capacity() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    config.mempool.priority_lanes = vec![priority_lane(vec![0], 1, 1)];
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+
+    // lane transactions don't count towards Mempool capacity, but towards lane capacity
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+
+    // lane transactions can't be evicted to make room for other transactions
+    add_txn(&mut pool, TestTransaction::new(2, 0, 100)).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(1), 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 0),
+        MempoolTransactionStatus::Ready
+    );
+
+    // once the lane is full, lane transactions compete for the rest of Mempool
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 1, 1)).is_err());
+    pool.check_consistency();
+}
+
+#[test]
+fn test_priority_lane_eviction() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 10;
+    config.mempool.priority_lanes = vec![priority_lane(vec![0, 1], 1, 1)];
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+
+    // full lane makes room for a transaction paying a higher gas price
+    add_txn(&mut pool, TestTransaction::new(1, 0, 5)).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+
+    // otherwise the transaction falls back to the rest of Mempool
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 0),
+        MempoolTransactionStatus::Ready
+    );
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(1), 0),
+        MempoolTransactionStatus::Ready
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_priority_lane_by_script_hash() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    let mut lane = priority_lane(vec![0], 1, 1);
+    // test transactions run an empty script
+    lane.script_hashes = HashSet::from_iter(vec![*HashValue::from_sha3_256(&[]).as_ref()]);
+    config.mempool.priority_lanes = vec![lane];
+    let mut pool = CoreMempool::new(&config);
+
+    // transactions running the script of the lane don't belong to it unless their sender is
+    // allowed in it
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert_eq!(
+        pool.add_txn(
+            TestTransaction::new(2, 0, 1).make_signed_transaction(),
+            0,
+            0,
+            1000,
+            TimelineState::NotReady,
+        )
+        .code,
+        MempoolAddTransactionStatusCode::MempoolIsFull
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_parked_capacity_per_user() {
+    let mut config = NodeConfig::random();
+    config.mempool.parked_capacity_per_user = 2;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 3, 1)).unwrap();
+
+    // no room for more transactions waiting on the sequence number gap
+    assert_eq!(
+        pool.add_txn(
+            TestTransaction::new(0, 5, 1).make_signed_transaction(),
+            0,
+            0,
+            1000,
+            TimelineState::NotReady,
+        )
+        .code,
+        MempoolAddTransactionStatusCode::TooManyTransactions
+    );
+    // transactions of other accounts are not affected
+    add_txn(&mut pool, TestTransaction::new(1, 2, 1)).unwrap();
+
+    // transactions filling the gap are accepted, and promote the parked ones
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 3),
+        MempoolTransactionStatus::Ready
+    );
+    add_txn(&mut pool, TestTransaction::new(0, 5, 1)).unwrap();
+    pool.check_consistency();
+}
+
+#[test]
+fn test_parked_capacity_per_user_eviction() {
+    let mut config = NodeConfig::random();
+    config.mempool.parked_capacity_per_user = 2;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 3, 5)).unwrap();
+
+    // a parked transaction paying a higher gas price takes the place of the cheapest one
+    add_txn(&mut pool, TestTransaction::new(0, 5, 2)).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 2),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+
+    // otherwise it's rejected
+    assert_eq!(
+        pool.add_txn(
+            TestTransaction::new(0, 6, 2).make_signed_transaction(),
+            0,
+            0,
+            1000,
+            TimelineState::NotReady,
+        )
+        .code,
+        MempoolAddTransactionStatusCode::TooManyTransactions
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_parked_transaction_ttl() {
+    // created mempool with parked_transaction_timeout_secs = 0
+    // All parked transactions are supposed to be removed on next gc run
+    let mut config = NodeConfig::random();
+    config.mempool.parked_transaction_timeout_secs = 0;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 1, 1)).unwrap();
+
+    pool.gc_by_system_ttl();
+    for (address, sequence_number) in vec![(0, 2), (1, 1)] {
+        assert_eq!(
+            pool.get_transaction_status(&TestTransaction::get_address(address), sequence_number),
+            MempoolTransactionStatus::Removed(MempoolRemovalReason::ParkedTtlExpired)
+        );
+    }
+    // ready transactions are only subject to system TTL
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 0),
+        MempoolTransactionStatus::Ready
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_gap_filled_promotion() {
+    let mut pool = setup_mempool().0;
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 1, 5), TestTransaction::new(0, 2, 1)],
+    );
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 2),
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number: 0
+        }
+    );
+    assert!(pool.get_block(3, HashSet::new()).is_empty());
+
+    // filling the gap makes all following transactions ready, in order of sequence number
+    let gap = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    add_signed_txn(&mut pool, gap.clone()).unwrap();
+    let mut expected = vec![gap];
+    expected.extend(txns);
+    assert_eq!(pool.get_block(3, HashSet::new()), expected);
+    pool.check_consistency();
+}
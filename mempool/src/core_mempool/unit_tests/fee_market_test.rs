@@ -0,0 +1,269 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::core_mempool::{
+    unit_tests::common::{add_txn, TestTransaction},
+    CoreMempool, TimelineState,
+};
+use libra_config::config::NodeConfig;
+use libra_mempool_shared_proto::{
+    proto::mempool_status::{MempoolAddTransactionStatusCode, MempoolRemovalReason},
+    MempoolTransactionStatus,
+};
+use proptest::prelude::*;
+use std::time::Duration;
+
+fn setup_mempool(capacity: usize, capacity_per_user: usize) -> CoreMempool {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = capacity;
+    config.mempool.capacity_per_user = capacity_per_user;
+    CoreMempool::new(&config)
+}
+
+fn add_txn_with_status(
+    pool: &mut CoreMempool,
+    transaction: TestTransaction,
+) -> MempoolAddTransactionStatusCode {
+    pool.add_txn(
+        transaction.make_signed_transaction(),
+        0,
+        0,
+        1000,
+        TimelineState::NotReady,
+    )
+    .code
+}
+
+fn status(
+    pool: &mut CoreMempool,
+    address: usize,
+    sequence_number: u64,
+) -> MempoolTransactionStatus {
+    pool.get_transaction_status(&TestTransaction::get_address(address), sequence_number)
+}
+
+#[test]
+fn test_replacement_gas_price_bump() {
+    let mut pool = setup_mempool(100, 100);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 100)).unwrap();
+
+    // gas price has to increase by at least 10%
+    for gas_price in &[100, 105, 109] {
+        assert_eq!(
+            add_txn_with_status(&mut pool, TestTransaction::new(0, 0, *gas_price)),
+            MempoolAddTransactionStatusCode::InvalidUpdate
+        );
+    }
+    add_txn(&mut pool, TestTransaction::new(0, 0, 110)).unwrap();
+    assert_eq!(
+        add_txn_with_status(&mut pool, TestTransaction::new(0, 0, 120)),
+        MempoolAddTransactionStatusCode::InvalidUpdate
+    );
+    add_txn(&mut pool, TestTransaction::new(0, 0, 121)).unwrap();
+    pool.check_consistency();
+}
+
+#[test]
+fn test_ready_eviction_by_gas_price() {
+    let mut pool = setup_mempool(2, 100);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+
+    // transactions paying the same gas price don't evict ready ones
+    assert_eq!(
+        add_txn_with_status(&mut pool, TestTransaction::new(1, 0, 1)),
+        MempoolAddTransactionStatusCode::MempoolIsFull
+    );
+    // the furthest-from-ready transaction is evicted first
+    add_txn(&mut pool, TestTransaction::new(1, 0, 5)).unwrap();
+    assert_eq!(
+        status(&mut pool, 0, 1),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+    assert_eq!(status(&mut pool, 0, 0), MempoolTransactionStatus::Ready);
+    pool.check_consistency();
+}
+
+#[test]
+fn test_eviction_prefers_lowest_gas_price() {
+    let mut pool = setup_mempool(2, 100);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 2, 5)).unwrap();
+
+    // parked transaction pays more than new one, so ready transaction is evicted
+    add_txn(&mut pool, TestTransaction::new(2, 0, 3)).unwrap();
+    assert_eq!(
+        status(&mut pool, 0, 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+    assert_eq!(
+        status(&mut pool, 1, 2),
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number: 1
+        }
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_eviction_parks_following_transactions() {
+    let mut pool = setup_mempool(3, 100);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 10)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 5)).unwrap();
+
+    add_txn(&mut pool, TestTransaction::new(2, 0, 5)).unwrap();
+    assert_eq!(
+        status(&mut pool, 0, 0),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+    assert_eq!(
+        status(&mut pool, 0, 1),
+        MempoolTransactionStatus::Parked {
+            missing_sequence_number: 0
+        }
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_account_capacity_eviction() {
+    let mut pool = setup_mempool(100, 2);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+
+    // following transaction paying less is evicted to make room
+    add_txn(&mut pool, TestTransaction::new(0, 1, 5)).unwrap();
+    assert_eq!(
+        status(&mut pool, 0, 2),
+        MempoolTransactionStatus::Removed(MempoolRemovalReason::Evicted)
+    );
+    // preceding transactions are never evicted
+    assert_eq!(
+        add_txn_with_status(&mut pool, TestTransaction::new(0, 2, 100)),
+        MempoolAddTransactionStatusCode::TooManyTransactions
+    );
+    pool.check_consistency();
+}
+
+#[test]
+fn test_eviction_by_gas_price_disabled() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 2;
+    config.mempool.capacity_per_user = 2;
+    config.mempool.evict_by_gas_price = false;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+
+    assert_eq!(
+        add_txn_with_status(&mut pool, TestTransaction::new(0, 1, 100)),
+        MempoolAddTransactionStatusCode::TooManyTransactions
+    );
+    // parked transactions are still evicted regardless of gas price
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    assert_eq!(
+        add_txn_with_status(&mut pool, TestTransaction::new(2, 0, 100)),
+        MempoolAddTransactionStatusCode::MempoolIsFull
+    );
+    pool.check_consistency();
+}
+
+#[derive(Clone, Debug)]
+enum Operation {
+    AddTxn {
+        address: usize,
+        // offset from the committed sequence number of the account
+        sequence_number: u64,
+        gas_price: u64,
+        expiration_time: u64,
+    },
+    Commit(usize),
+    Reject(usize),
+    GcByExpirationTime(u64),
+}
+
+fn operation_strategy() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        6 => (0..3usize, 0..5u64, 1..20u64, 1..10u64).prop_map(
+            |(address, sequence_number, gas_price, expiration_time)| Operation::AddTxn {
+                address,
+                sequence_number,
+                gas_price,
+                expiration_time,
+            }
+        ),
+        2 => (0..3usize).prop_map(Operation::Commit),
+        1 => (0..3usize).prop_map(Operation::Reject),
+        1 => (0..10u64).prop_map(Operation::GcByExpirationTime),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_indices_consistency(
+        operations in prop::collection::vec(operation_strategy(), 1..100),
+        evict_by_gas_price in any::<bool>(),
+    ) {
+        let mut config = NodeConfig::random();
+        config.mempool.capacity = 6;
+        config.mempool.capacity_per_user = 4;
+        config.mempool.evict_by_gas_price = evict_by_gas_price;
+        let mut pool = CoreMempool::new(&config);
+        let mut committed = [0u64; 3];
+
+        for operation in operations {
+            match operation {
+                Operation::AddTxn { address, sequence_number, gas_price, expiration_time } => {
+                    let sequence_number = committed[address] + sequence_number;
+                    let txn = TestTransaction::new(address, sequence_number, gas_price)
+                        .make_signed_transaction_with_expiration_time(
+                            Duration::from_secs(expiration_time),
+                        );
+                    let code = pool
+                        .add_txn(txn, 0, committed[address], 1000, TimelineState::NotReady)
+                        .code;
+                    if evict_by_gas_price
+                        && code == MempoolAddTransactionStatusCode::MempoolIsFull
+                    {
+                        // no transaction could be evicted to make room for the new one
+                        for account in 0..3 {
+                            let txns = pool
+                                .get_account_transactions(&TestTransaction::get_address(account));
+                            for (txn, status) in txns {
+                                if account == address && txn.sequence_number() < sequence_number {
+                                    continue;
+                                }
+                                match status {
+                                    MempoolTransactionStatus::Ready => {
+                                        prop_assert!(txn.gas_unit_price() >= gas_price)
+                                    }
+                                    _ => prop_assert!(txn.gas_unit_price() > gas_price),
+                                }
+                            }
+                        }
+                    }
+                }
+                Operation::Commit(address) => {
+                    pool.remove_transaction(
+                        &TestTransaction::get_address(address),
+                        committed[address],
+                        false,
+                    );
+                    committed[address] += 1;
+                }
+                Operation::Reject(address) => {
+                    pool.remove_transaction(
+                        &TestTransaction::get_address(address),
+                        committed[address],
+                        true,
+                    );
+                }
+                Operation::GcByExpirationTime(block_time) => {
+                    pool.gc_by_expiration_time(Duration::from_secs(block_time));
+                }
+            }
+            pool.check_consistency();
+        }
+    }
+}
@@ -3,4 +3,6 @@
 
 mod common;
 mod core_mempool_test;
+mod fee_market_test;
+mod persistence_test;
 mod shared_mempool_test;
@@ -0,0 +1,132 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::core_mempool::{
+    unit_tests::common::{add_txn, TestTransaction},
+    CoreMempool, PersistedTransaction, TimelineState, TransactionLog,
+};
+use libra_config::config::NodeConfig;
+use libra_temppath::TempPath;
+use std::fs::{self, OpenOptions};
+
+fn persisted_txn(address: usize, sequence_number: u64, broadcast: bool) -> PersistedTransaction {
+    PersistedTransaction {
+        txn: TestTransaction::new(address, sequence_number, 1).make_signed_transaction(),
+        broadcast,
+    }
+}
+
+fn setup_persistent_mempool() -> CoreMempool {
+    let mut config = NodeConfig::random();
+    config.mempool.persist_transactions = true;
+    CoreMempool::new(&config)
+}
+
+#[test]
+fn test_log_round_trip() {
+    let dir = TempPath::new();
+    let path = dir.path().join("transactions.log");
+    let txns = vec![
+        persisted_txn(0, 0, true),
+        persisted_txn(0, 1, false),
+        persisted_txn(1, 0, true),
+    ];
+
+    let (mut log, persisted) = TransactionLog::open(&path).unwrap();
+    assert!(persisted.is_empty());
+    log.append(&txns[..2]).unwrap();
+    log.append(&txns[2..]).unwrap();
+    drop(log);
+
+    let (_log, persisted) = TransactionLog::open(&path).unwrap();
+    assert_eq!(persisted, txns);
+}
+
+#[test]
+fn test_log_compaction() {
+    let dir = TempPath::new();
+    let path = dir.path().join("transactions.log");
+    let (mut log, _) = TransactionLog::open(&path).unwrap();
+    log.append(&[persisted_txn(0, 0, true), persisted_txn(0, 1, true)])
+        .unwrap();
+
+    log.compact(&[persisted_txn(0, 1, true)]).unwrap();
+    // appends go to the compacted log
+    log.append(&[persisted_txn(1, 0, false)]).unwrap();
+    drop(log);
+
+    let (_log, persisted) = TransactionLog::open(&path).unwrap();
+    assert_eq!(
+        persisted,
+        vec![persisted_txn(0, 1, true), persisted_txn(1, 0, false)]
+    );
+}
+
+#[test]
+fn test_log_truncates_partial_entry() {
+    let dir = TempPath::new();
+    let path = dir.path().join("transactions.log");
+    let (mut log, _) = TransactionLog::open(&path).unwrap();
+    log.append(&[persisted_txn(0, 0, true), persisted_txn(0, 1, true)])
+        .unwrap();
+    drop(log);
+
+    // simulate a crash in the middle of writing the last entry
+    let len = fs::metadata(&path).unwrap().len();
+    OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(len - 1)
+        .unwrap();
+
+    let (mut log, persisted) = TransactionLog::open(&path).unwrap();
+    assert_eq!(persisted, vec![persisted_txn(0, 0, true)]);
+    log.append(&[persisted_txn(1, 0, true)]).unwrap();
+    drop(log);
+
+    let (_log, persisted) = TransactionLog::open(&path).unwrap();
+    assert_eq!(
+        persisted,
+        vec![persisted_txn(0, 0, true), persisted_txn(1, 0, true)]
+    );
+}
+
+#[test]
+fn test_take_new_transactions() {
+    let mut pool = setup_persistent_mempool();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    let txn = TestTransaction::new(1, 0, 1).make_signed_transaction();
+    pool.add_txn(txn, 0, 0, 1000, TimelineState::NonQualified);
+    // rejected transactions are not persisted
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 0, 1)).is_err());
+
+    assert_eq!(
+        pool.take_new_transactions(),
+        vec![persisted_txn(0, 0, true), persisted_txn(1, 0, false)]
+    );
+    assert!(pool.take_new_transactions().is_empty());
+}
+
+#[test]
+fn test_persisted_transactions() {
+    let mut pool = setup_persistent_mempool();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    pool.take_new_transactions();
+
+    // transactions remain persisted until they leave Mempool
+    let mut persisted = pool.persisted_transactions();
+    persisted.sort_by_key(|txn| txn.txn.sequence_number());
+    assert_eq!(
+        persisted,
+        vec![persisted_txn(0, 0, true), persisted_txn(0, 1, true)]
+    );
+}
+
+#[test]
+fn test_persistence_disabled() {
+    let mut pool = CoreMempool::new(&NodeConfig::random());
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert!(pool.take_new_transactions().is_empty());
+}
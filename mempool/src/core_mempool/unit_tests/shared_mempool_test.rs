@@ -2,20 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{unit_tests::common::TestTransaction, CoreMempool, TimelineState},
+    core_mempool::{
+        unit_tests::common::TestTransaction, CoreMempool, PersistedTransaction, TimelineState,
+        TransactionLog,
+    },
     shared_mempool::{start_shared_mempool, SharedMempoolNotification, SyncEvent},
 };
 use channel;
 use futures::{
     channel::mpsc::{self, unbounded, UnboundedReceiver, UnboundedSender},
     executor::block_on,
-    SinkExt, StreamExt,
+    FutureExt, SinkExt, StreamExt,
 };
-use libra_config::config::{NetworkConfig, NodeConfig};
+use libra_config::config::{MempoolConfig, NetworkConfig, NodeConfig};
+use libra_temppath::TempPath;
 use libra_types::{transaction::SignedTransaction, PeerId};
 use network::{
     interface::{NetworkNotification, NetworkRequest},
-    proto::MempoolSyncMsg,
+    proto::{BroadcastAck, MempoolSyncMsg},
+    protocols::direct_send::Message as NetworkMessage,
     validator_network::{MempoolNetworkEvents, MempoolNetworkSender},
 };
 use prost::Message;
@@ -40,6 +45,16 @@ struct SharedMempoolNetwork {
 
 impl SharedMempoolNetwork {
     fn bootstrap_validator_network_smp(validator_nodes_count: u32) -> (Self, Vec<PeerId>) {
+        Self::bootstrap_validator_network_smp_with_config(
+            validator_nodes_count,
+            MempoolConfig::default(),
+        )
+    }
+
+    fn bootstrap_validator_network_smp_with_config(
+        validator_nodes_count: u32,
+        mempool_config: MempoolConfig,
+    ) -> (Self, Vec<PeerId>) {
         let mut smp = Self::default();
         let mut peers = vec![];
 
@@ -49,6 +64,7 @@ impl SharedMempoolNetwork {
             validator_network_config.peer_id = peer_id;
             let mut config = NodeConfig::random();
             config.validator_network = Some(validator_network_config);
+            config.mempool = mempool_config.clone();
             config.mempool.shared_mempool_batch_size = 1;
 
             let mempool = Arc::new(Mutex::new(CoreMempool::new(&config)));
@@ -61,6 +77,7 @@ impl SharedMempoolNetwork {
             let (_ac_endpoint_sender, ac_endpoint_receiver) = mpsc::channel(1_024);
             let network_handles = vec![(peer_id, network_sender, network_events)];
             let (_consensus_sender, consensus_events) = mpsc::channel(1_024);
+            let (_query_sender, query_events) = mpsc::channel(1_024);
 
             let runtime = Builder::new()
                 .thread_name("shared-mem-")
@@ -75,6 +92,7 @@ impl SharedMempoolNetwork {
                 network_handles,
                 ac_endpoint_receiver,
                 consensus_events,
+                query_events,
                 Arc::new(MockStorageReadClient),
                 Arc::new(MockVMValidator),
                 vec![sender],
@@ -113,44 +131,74 @@ impl SharedMempoolNetwork {
         }
     }
 
-    /// deliveres next message from given node to it's peer
-    fn deliver_message(&mut self, peer: &PeerId) -> (SignedTransaction, PeerId) {
-        // emulate timer tick
+    /// emulates timer tick on given node and waits for the sync to complete
+    fn tick(&mut self, peer: &PeerId) {
         self.timers
             .get(peer)
             .unwrap()
             .unbounded_send(SyncEvent)
             .unwrap();
+        self.wait_for_event(peer, SharedMempoolNotification::Sync);
+    }
 
-        // await next message from node
+    /// awaits next message sent by given node
+    fn next_message(&mut self, peer: &PeerId) -> (PeerId, NetworkMessage) {
         let network_reqs_rx = self.network_reqs_rxs.get_mut(peer).unwrap();
-        let network_req = block_on(network_reqs_rx.next()).unwrap();
-
-        match network_req {
-            NetworkRequest::SendMessage(peer_id, msg) => {
-                let mut sync_msg = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap();
-                let transaction =
-                    SignedTransaction::try_from(sync_msg.transactions.pop().unwrap()).unwrap();
-                // send it to peer
-                let receiver_network_notif_tx = self.network_notifs_txs.get_mut(&peer_id).unwrap();
-                block_on(
-                    receiver_network_notif_tx.send(NetworkNotification::RecvMessage(*peer, msg)),
-                )
-                .unwrap();
-
-                // await message delivery
-                self.wait_for_event(&peer_id, SharedMempoolNotification::NewTransactions);
-
-                // verify transaction was inserted into Mempool
-                let mempool = self.mempools.get(&peer_id).unwrap();
-                let block = mempool.lock().unwrap().get_block(100, HashSet::new());
-                assert!(block.iter().any(|t| t == &transaction));
-                (transaction, peer_id)
-            }
-            _ => panic!("peer {:?} didn't broadcast transaction", peer),
+        match block_on(network_reqs_rx.next()).unwrap() {
+            NetworkRequest::SendMessage(peer_id, msg) => (peer_id, msg),
+            _ => panic!("peer {:?} didn't send message", peer),
         }
     }
 
+    /// checks that given node has no message waiting to be sent
+    fn has_no_message(&mut self, peer: &PeerId) -> bool {
+        let network_reqs_rx = self.network_reqs_rxs.get_mut(peer).unwrap();
+        network_reqs_rx.next().now_or_never().is_none()
+    }
+
+    fn send_message(&mut self, sender: &PeerId, receiver: &PeerId, msg: NetworkMessage) {
+        let receiver_network_notif_tx = self.network_notifs_txs.get_mut(receiver).unwrap();
+        block_on(receiver_network_notif_tx.send(NetworkNotification::RecvMessage(*sender, msg)))
+            .unwrap();
+    }
+
+    /// deliveres next message from given node to it's peer
+    fn deliver_message(&mut self, peer: &PeerId) -> (SignedTransaction, PeerId) {
+        self.tick(peer);
+
+        // await next message from node
+        let (peer_id, msg) = self.next_message(peer);
+        let mut sync_msg = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap();
+        let transaction =
+            SignedTransaction::try_from(sync_msg.transactions.pop().unwrap()).unwrap();
+        // send it to peer
+        self.send_message(peer, &peer_id, msg);
+
+        // await message delivery
+        self.wait_for_event(&peer_id, SharedMempoolNotification::NewTransactions);
+
+        // verify transaction was inserted into Mempool
+        let mempool = self.mempools.get(&peer_id).unwrap();
+        let block = mempool.lock().unwrap().get_block(100, HashSet::new());
+        assert!(block.iter().any(|t| t == &transaction));
+
+        self.deliver_ack(&peer_id, peer);
+        (transaction, peer_id)
+    }
+
+    /// delivers acknowledgement sent by given node back to the sender of a batch
+    fn deliver_ack(&mut self, peer: &PeerId, sender: &PeerId) -> BroadcastAck {
+        let (peer_id, msg) = self.next_message(peer);
+        assert_eq!(peer_id, *sender);
+        let ack = MempoolSyncMsg::decode(msg.mdata.as_ref())
+            .unwrap()
+            .ack
+            .unwrap();
+        self.send_message(peer, sender, msg);
+        self.wait_for_event(sender, SharedMempoolNotification::Ack);
+        ack
+    }
+
     fn exist_in_metrics_cache(&self, peer_id: &PeerId, txn: &TestTransaction) -> bool {
         let mempool = self.mempools.get(peer_id).unwrap().lock().unwrap();
         mempool
@@ -355,3 +403,222 @@ fn test_broadcast_updated_transaction() {
     assert_eq!(txn.sequence_number(), 0);
     assert_eq!(txn.gas_unit_price(), 5);
 }
+
+#[test]
+fn test_broadcast_retry_on_timeout() {
+    let mut mempool_config = MempoolConfig::default();
+    mempool_config.shared_mempool_ack_timeout_ms = 0;
+    let (mut smp, peers) =
+        SharedMempoolNetwork::bootstrap_validator_network_smp_with_config(2, mempool_config);
+    let (peer_a, peer_b) = (peers.get(0).unwrap(), peers.get(1).unwrap());
+    smp.add_txns(&peer_a, vec![TestTransaction::new(0, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+
+    // A sends txn to B, but the message gets lost
+    smp.tick(&peer_a);
+    let (peer_id, msg) = smp.next_message(&peer_a);
+    assert_eq!(peer_id, *peer_b);
+    let lost_batch_id = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap().batch_id;
+    assert_ne!(lost_batch_id, 0);
+
+    // B didn't acknowledge the batch in time, so A sends it again
+    let (txn, peer_id) = smp.deliver_message(&peer_a);
+    assert_eq!(peer_id, *peer_b);
+    assert_eq!(txn.sequence_number(), 0);
+
+    // once acknowledged, the batch isn't sent again
+    smp.tick(&peer_a);
+    assert!(smp.has_no_message(&peer_a));
+}
+
+#[test]
+fn test_broadcast_drop_on_timeouts() {
+    let mut mempool_config = MempoolConfig::default();
+    mempool_config.shared_mempool_ack_timeout_ms = 0;
+    mempool_config.shared_mempool_max_batch_retries = 1;
+    let (mut smp, peers) =
+        SharedMempoolNetwork::bootstrap_validator_network_smp_with_config(2, mempool_config);
+    let (peer_a, peer_b) = (peers.get(0).unwrap(), peers.get(1).unwrap());
+    smp.add_txns(&peer_a, vec![TestTransaction::new(0, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+
+    // A sends the batch and sends it again once it timed out, but both messages get lost
+    for _ in 0..2 {
+        smp.tick(&peer_a);
+        let (peer_id, _) = smp.next_message(&peer_a);
+        assert_eq!(peer_id, *peer_b);
+    }
+
+    // A gives up on the batch once it timed out after `shared_mempool_max_batch_retries` retries
+    smp.tick(&peer_a);
+    assert!(smp.has_no_message(&peer_a));
+}
+
+#[test]
+fn test_broadcast_after_reconnect() {
+    let (mut smp, peers) = SharedMempoolNetwork::bootstrap_validator_network_smp(2);
+    let (peer_a, peer_b) = (peers.get(0).unwrap(), peers.get(1).unwrap());
+    smp.add_txns(&peer_a, vec![TestTransaction::new(0, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+
+    // A sends txn to B, but loses connection to B before the batch is acknowledged
+    smp.tick(&peer_a);
+    let (peer_id, msg) = smp.next_message(&peer_a);
+    assert_eq!(peer_id, *peer_b);
+    let lost_batch_id = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap().batch_id;
+    smp.send_event(&peer_a, NetworkNotification::LostPeer(*peer_b));
+
+    // once B is back, A sends the txn again right away, without waiting for the lost batch to
+    // time out
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+    smp.tick(&peer_a);
+    let (peer_id, msg) = smp.next_message(&peer_a);
+    assert_eq!(peer_id, *peer_b);
+    let sync_msg = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap();
+    assert!(sync_msg.batch_id > lost_batch_id);
+    assert_eq!(sync_msg.transactions.len(), 1);
+}
+
+#[test]
+fn test_broadcast_backoff() {
+    let mut mempool_config = MempoolConfig::default();
+    mempool_config.capacity = 1;
+    let (mut smp, peers) =
+        SharedMempoolNetwork::bootstrap_validator_network_smp_with_config(2, mempool_config);
+    let (peer_a, peer_b) = (peers.get(0).unwrap(), peers.get(1).unwrap());
+    smp.add_txns(&peer_a, vec![TestTransaction::new(0, 0, 1)]);
+    // B's Mempool is full
+    smp.add_txns(&peer_b, vec![TestTransaction::new(1, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+
+    smp.tick(&peer_a);
+    let (peer_id, msg) = smp.next_message(&peer_a);
+    smp.send_message(&peer_a, &peer_id, msg);
+    smp.wait_for_event(&peer_id, SharedMempoolNotification::NewTransactions);
+    let ack = smp.deliver_ack(&peer_b, &peer_a);
+    assert!(ack.retry);
+    assert!(ack.backoff);
+
+    // A doesn't send the batch again to B while it is backing off
+    smp.tick(&peer_a);
+    assert!(smp.has_no_message(&peer_a));
+}
+
+#[test]
+fn test_broadcast_retry_rejected_transactions() {
+    let mut mempool_config = MempoolConfig::default();
+    mempool_config.capacity_per_user = 1;
+    mempool_config.evict_by_gas_price = false;
+    mempool_config.shared_mempool_backoff_interval_ms = 0;
+    mempool_config.shared_mempool_max_batch_retries = 1;
+    let (mut smp, peers) =
+        SharedMempoolNetwork::bootstrap_validator_network_smp_with_config(2, mempool_config);
+    let (peer_a, peer_b) = (peers.get(0).unwrap(), peers.get(1).unwrap());
+    smp.add_txns(
+        &peer_a,
+        vec![TestTransaction::new(0, 0, 1), TestTransaction::new(1, 0, 1)],
+    );
+    // account 1 is full in B's Mempool
+    smp.add_txns(&peer_b, vec![TestTransaction::new(1, 1, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+
+    // A sends the whole batch first, then only the transaction B couldn't add
+    for expected_txns in vec![2, 1] {
+        smp.tick(&peer_a);
+        let (peer_id, msg) = smp.next_message(&peer_a);
+        let sync_msg = MempoolSyncMsg::decode(msg.mdata.as_ref()).unwrap();
+        assert_eq!(sync_msg.transactions.len(), expected_txns);
+        smp.send_message(&peer_a, &peer_id, msg);
+        smp.wait_for_event(&peer_id, SharedMempoolNotification::NewTransactions);
+        let ack = smp.deliver_ack(&peer_b, &peer_a);
+        assert!(ack.retry);
+        assert!(ack.backoff);
+        assert_eq!(ack.retry_transactions.len(), 1);
+        assert_eq!(ack.retry_transactions[0].sequence_number, 0);
+    }
+
+    // A gives up on the batch once it was sent again `shared_mempool_max_batch_retries` times
+    smp.tick(&peer_a);
+    assert!(smp.has_no_message(&peer_a));
+}
+
+#[test]
+fn test_broadcast_fanout() {
+    let mut mempool_config = MempoolConfig::default();
+    mempool_config.shared_mempool_broadcast_fanout = 1;
+    let (mut smp, peers) =
+        SharedMempoolNetwork::bootstrap_validator_network_smp_with_config(3, mempool_config);
+    let (peer_a, peer_b, peer_c) = (
+        peers.get(0).unwrap(),
+        peers.get(1).unwrap(),
+        peers.get(2).unwrap(),
+    );
+    smp.add_txns(&peer_a, vec![TestTransaction::new(0, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_b));
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(*peer_c));
+
+    // A only sends txn to one of its peers
+    smp.tick(&peer_a);
+    let (peer_id, _) = smp.next_message(&peer_a);
+    assert!(peer_id == *peer_b || peer_id == *peer_c);
+    assert!(smp.has_no_message(&peer_a));
+}
+
+#[test]
+fn test_reload_persisted_transactions() {
+    let dir = TempPath::new();
+    let mut config = NodeConfig::random();
+    config.mempool.persist_transactions = true;
+    config.mempool.persistence_path = dir.path().join("transactions.log");
+    let txns = vec![
+        TestTransaction::new(0, 0, 1).make_signed_transaction_with_max_gas_amount(5),
+        TestTransaction::new(1, 0, 1).make_signed_transaction_with_max_gas_amount(5),
+    ];
+    let (mut log, _) = TransactionLog::open(&config.mempool.persistence_path()).unwrap();
+    log.append(&[
+        PersistedTransaction {
+            txn: txns[0].clone(),
+            broadcast: true,
+        },
+        PersistedTransaction {
+            txn: txns[1].clone(),
+            broadcast: false,
+        },
+    ])
+    .unwrap();
+    drop(log);
+
+    let mempool = Arc::new(Mutex::new(CoreMempool::new(&config)));
+    let (sender, mut subscriber) = unbounded();
+    let (_ac_endpoint_sender, ac_endpoint_receiver) = mpsc::channel(1_024);
+    let (_consensus_sender, consensus_events) = mpsc::channel(1_024);
+    let (_query_sender, query_events) = mpsc::channel(1_024);
+    let runtime = Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+    start_shared_mempool(
+        runtime.handle(),
+        &config,
+        Arc::clone(&mempool),
+        vec![],
+        ac_endpoint_receiver,
+        consensus_events,
+        query_events,
+        Arc::new(MockStorageReadClient),
+        Arc::new(MockVMValidator),
+        vec![sender],
+        None,
+    );
+
+    // transactions are reloaded in two batches, depending on whether they are broadcast
+    for _ in 0..2 {
+        while block_on(subscriber.next()).unwrap() != SharedMempoolNotification::NewTransactions {
+            continue;
+        }
+    }
+    let block = mempool.lock().unwrap().get_block(100, HashSet::new());
+    assert_eq!(block.len(), 2);
+    assert!(txns.iter().all(|txn| block.contains(txn)));
+}
@@ -10,7 +10,8 @@ pub static MEMPOOL_SERVICE: Lazy<IntCounterVec> = Lazy::new(|| {
         "Number of txns processed by Mempool Service",
         &[
             // type of request through which the transactions were delivered to MempoolService:
-            // "get_block", "commit_transactions"
+            // "get_block", "commit_transactions", "get_account_transactions",
+            // "get_transaction_status", "get_stats"
             "req_type",
             // direction of the request: "requested", "returned"
             "req_status",
@@ -23,7 +24,7 @@ pub static SHARED_MEMPOOL_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "libra_shared_mempool_events",
         "Number of network events received by shared mempool",
-        &["event"] // type of event: "new_peer", "lost_peer", "message"
+        &["event"] // type of event: "new_peer", "lost_peer", "message", "ack"
     )
     .unwrap()
 });
@@ -48,3 +49,22 @@ pub static SHARED_MEMPOOL_TRANSACTION_BROADCAST: Lazy<IntCounter> = Lazy::new(||
     )
     .unwrap()
 });
+
+pub static SHARED_MEMPOOL_PERSISTED_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_shared_mempool_persisted_transactions",
+        "Number of transactions persisted on disk and reloaded by shared mempool",
+        &["op"] // type of operation: "appended", "reloaded"
+    )
+    .unwrap()
+});
+
+pub static SHARED_MEMPOOL_BATCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_shared_mempool_batches",
+        "Number of transaction batches broadcast by shared mempool, and of their acknowledgements",
+        // type of operation: "sent", "retried", "timed_out", "acked", "backoff"
+        &["op"]
+    )
+    .unwrap()
+});
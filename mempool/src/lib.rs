@@ -22,6 +22,10 @@
 //! local mempool contains transactions with sequence numbers 2,3,4,7,8, then only transactions 2, 3
 //! and 4 will be broadcast.
 //!
+//! Transactions are broadcast in batches to the healthiest peers (the ones acknowledging batches on
+//! time, with the lowest latency). Batches that aren't acknowledged in time are sent again, and a
+//! peer reporting that its Mempool is full doesn't receive new batches for a while.
+//!
 //! Consensus pulls transactions from mempool rather than mempool pushing into consensus. This is
 //! done so that while consensus is not yet ready for transactions, we keep ordering based on gas
 //! and consensus can let transactions build up.  This allows for batching of transactions into a
@@ -45,16 +49,31 @@
 //! example, Mempool has transaction with sequence number 4, while current sequence number for that
 //! account is 3. Such transaction is considered to be “non-ready”. Then callback from Consensus
 //! notifies that transaction was committed(i.e. transaction 3 was submitted to different node).
-//! Such event “unblocks” local transaction and txn4 will be moved to OrderedQueue.
+//! Such event “unblocks” local transaction and txn4 will be moved to OrderedQueue. AC lets the
+//! submitter of a transaction know when it's parked, and which sequence number it waits for. Full
+//! nodes forward parked transactions to their upstream peers, so that the gap can be filled there.
+//! The number of parked transactions per account is bounded, and they have their own TTL, so that
+//! transactions waiting on a gap that's never filled don't take up Mempool.
 //!
 //! Mempool only holds a limited number of transactions to prevent OOMing the system. Additionally
-//! there's a limit of number of transactions per account to prevent different abuses/attacks
+//! there's a limit of number of transactions per account to prevent different abuses/attacks.
+//! Once a limit is reached, a new transaction can take the place of the transaction paying the
+//! lowest gas price (non-ready transactions are evicted first). An account can also replace one of
+//! its pending transactions by resubmitting it with a higher gas price.
+//!
+//! Priority lanes reserve some Mempool capacity and block space for the transactions of given
+//! accounts or scripts (e.g. validator set management), so that spam can't starve them. Their
+//! transactions are picked first for a block, up to the block space reserved for their lane.
 //!
 //! Transactions in Mempool have two types of expirations: systemTTL and client-specified
 //! expiration. Once we hit either of those, the transaction is removed from Mempool. SystemTTL is
 //! checked periodically in the background, while the client-specified expiration is checked on
 //! every Consensus commit request. We use a separate system TTL to ensure that a transaction won't
 //! remain stuck in Mempool forever, even if Consensus doesn't make progress
+//!
+//! Clients and operators can inspect Mempool through AC: they can list the pending transactions of
+//! an account, check whether a transaction is ready, parked on a sequence number gap or was
+//! recently removed (and why), and get Mempool-wide statistics by gas price.
 
 #[macro_use]
 extern crate prometheus;
@@ -62,6 +81,9 @@ extern crate prometheus;
 /// This module provides mocks of shared mempool for tests.
 #[cfg(feature = "fuzzing")]
 pub mod mocks;
+/// Core mempool, exposed for benchmarks.
+#[cfg(feature = "fuzzing")]
+pub use core_mempool::{CoreMempool, TimelineState};
 pub use shared_mempool::{
     bootstrap, CommittedTransaction, MempoolRequest, MempoolResponse, TransactionExclusion,
 };
@@ -49,6 +49,7 @@ pub fn mock_shared_mempool() -> (
     let (sender, _subscriber) = unbounded();
     let (ac_sender, client_events) = mpsc::channel(1_024);
     let (_consensus_sender, consensus_events) = mpsc::channel(1_024);
+    let (_query_sender, query_events) = mpsc::channel(1_024);
     let network_handles = vec![(peer_id, network_sender, network_events)];
 
     start_shared_mempool(
@@ -58,6 +59,7 @@ pub fn mock_shared_mempool() -> (
         network_handles,
         client_events,
         consensus_events,
+        query_events,
         Arc::new(MockStorageReadClient),
         Arc::new(MockVMValidator),
         vec![sender],
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TxnPointer},
+    core_mempool::{CoreMempool, PersistedTransaction, TimelineState, TransactionLog, TxnPointer},
     counters,
 };
 use admission_control_proto::{
@@ -25,23 +25,27 @@ use futures::{
 };
 use libra_config::config::{MempoolConfig, NodeConfig};
 use libra_logger::prelude::*;
-use libra_mempool_shared_proto::proto::mempool_status::{
-    MempoolAddTransactionStatus as MempoolAddTransactionStatusProto,
-    MempoolAddTransactionStatusCode,
+use libra_mempool_shared_proto::{
+    proto::mempool_status::{
+        MempoolAddTransactionStatus as MempoolAddTransactionStatusProto,
+        MempoolAddTransactionStatusCode,
+    },
+    MempoolStats, MempoolTransactionStatus,
 };
 use libra_types::{
+    account_address::AccountAddress,
     proto::types::{SignedTransaction as SignedTransactionProto, VmStatus as VmStatusProto},
     transaction::SignedTransaction,
     vm_error::{StatusCode::RESOURCE_DOES_NOT_EXIST, VMStatus},
     PeerId,
 };
 use network::{
-    proto::MempoolSyncMsg,
+    proto::{BroadcastAck, MempoolSyncMsg, TxnPointer as TxnPointerProto},
     validator_network::{Event, MempoolNetworkEvents, MempoolNetworkSender},
 };
 use std::{
     cmp,
-    collections::{HashMap, HashSet},
+    collections::{btree_map::Entry, BTreeMap, HashMap, HashSet},
     convert::{TryFrom, TryInto},
     ops::Deref,
     pin::Pin,
@@ -51,18 +55,176 @@ use std::{
 use storage_client::{StorageRead, StorageReadServiceClient};
 use tokio::{
     runtime::{Builder, Handle, Runtime},
-    time::interval,
+    time::{interval, interval_at, Instant},
 };
 use vm_validator::vm_validator::{get_account_state, TransactionValidation, VMValidator};
 
 /// state of last sync with peer
 /// `timeline_id` is position in log of ready transactions
 /// `is_alive` - is connection healthy
+/// `broadcast` - batches sent to peer and not acknowledged yet
 #[derive(Clone)]
 struct PeerSyncState {
     timeline_id: u64,
     is_alive: bool,
     network_id: PeerId,
+    broadcast: BroadcastState,
+}
+
+/// state of broadcasts to a peer, used to retry them and to pick the peers to broadcast to
+#[derive(Clone, Default)]
+struct BroadcastState {
+    // id of last batch sent to peer
+    batch_id: u64,
+    // batches sent to peer and not acknowledged yet, by batch id
+    pending: BTreeMap<u64, PendingBatch>,
+    // peer reported its Mempool is full, so nothing is sent to it until then
+    backoff_until: Option<Instant>,
+    // smoothed round-trip time of acknowledgements
+    rtt: Option<Duration>,
+    // number of batches in a row that weren't acknowledged in time
+    timeouts: u64,
+}
+
+/// batch of transactions from timeline range (`start_timeline_id`, `end_timeline_id`]
+#[derive(Clone)]
+struct PendingBatch {
+    start_timeline_id: u64,
+    end_timeline_id: u64,
+    sent_at: Instant,
+    // transactions peer asked to be sent again, because it couldn't add them to its Mempool
+    retry_transactions: Option<HashSet<TxnPointer>>,
+    // number of times batch was sent again, because it timed out or peer asked for it
+    retries: usize,
+}
+
+impl PeerSyncState {
+    /// returns batches to send to peer: pending batches to send again, because they timed out or
+    /// peer asked for it, and a batch of new transactions if there aren't too many pending ones
+    /// A batch that times out after being sent again `shared_mempool_max_batch_retries` times is
+    /// dropped
+    fn next_batches(
+        &mut self,
+        mempool: &mut CoreMempool,
+        config: &MempoolConfig,
+        now: Instant,
+    ) -> Vec<(u64, Vec<SignedTransaction>)> {
+        let ack_timeout = Duration::from_millis(config.shared_mempool_ack_timeout_ms);
+        let broadcast = &mut self.broadcast;
+        let mut batches = vec![];
+        let mut dropped_batches = vec![];
+        for (batch_id, batch) in broadcast.pending.iter_mut() {
+            let timed_out = batch.sent_at + ack_timeout <= now;
+            if !timed_out && batch.retry_transactions.is_none() {
+                continue;
+            }
+            if timed_out {
+                broadcast.timeouts += 1;
+                counters::SHARED_MEMPOOL_BATCHES
+                    .with_label_values(&["timed_out"])
+                    .inc();
+                if batch.retries >= config.shared_mempool_max_batch_retries {
+                    counters::SHARED_MEMPOOL_BATCHES
+                        .with_label_values(&["dropped"])
+                        .inc();
+                    dropped_batches.push(*batch_id);
+                    continue;
+                }
+                batch.retries += 1;
+            }
+            // transactions of batch may have left Mempool since it was sent
+            let mut transactions =
+                mempool.timeline_range(batch.start_timeline_id, batch.end_timeline_id);
+            // a batch that timed out is sent again as a whole, otherwise only the transactions
+            // peer asked for
+            if let Some(retry_transactions) = batch.retry_transactions.take() {
+                if !timed_out {
+                    transactions.retain(|txn| {
+                        retry_transactions.contains(&(txn.sender(), txn.sequence_number()))
+                    });
+                }
+            }
+            if transactions.is_empty() {
+                dropped_batches.push(*batch_id);
+                continue;
+            }
+            counters::SHARED_MEMPOOL_BATCHES
+                .with_label_values(&["retried"])
+                .inc();
+            batch.sent_at = now;
+            batches.push((*batch_id, transactions));
+        }
+        for batch_id in dropped_batches {
+            broadcast.pending.remove(&batch_id);
+        }
+
+        if broadcast.pending.len() < config.shared_mempool_max_inflight_batches {
+            let (transactions, new_timeline_id) =
+                mempool.read_timeline(self.timeline_id, config.shared_mempool_batch_size);
+            if !transactions.is_empty() {
+                broadcast.batch_id += 1;
+                broadcast.pending.insert(
+                    broadcast.batch_id,
+                    PendingBatch {
+                        start_timeline_id: self.timeline_id,
+                        end_timeline_id: new_timeline_id,
+                        sent_at: now,
+                        retry_transactions: None,
+                        retries: 0,
+                    },
+                );
+                counters::SHARED_MEMPOOL_BATCHES
+                    .with_label_values(&["sent"])
+                    .inc();
+                batches.push((broadcast.batch_id, transactions));
+            }
+            self.timeline_id = new_timeline_id;
+        }
+        batches
+    }
+
+    /// acknowledgement handler
+    /// clears acknowledged batch, or marks the transactions peer asked for to be sent again, up
+    /// to `shared_mempool_max_batch_retries` times
+    fn process_ack(&mut self, ack: &BroadcastAck, config: &MempoolConfig, now: Instant) {
+        let broadcast = &mut self.broadcast;
+        if let Entry::Occupied(mut entry) = broadcast.pending.entry(ack.batch_id) {
+            let rtt = now.duration_since(entry.get().sent_at);
+            broadcast.rtt = Some(broadcast.rtt.map_or(rtt, |avg| (avg * 7 + rtt) / 8));
+            let retry_transactions: HashSet<_> = ack
+                .retry_transactions
+                .iter()
+                .filter_map(|ptr| {
+                    AccountAddress::try_from(ptr.sender.clone())
+                        .ok()
+                        .map(|sender| (sender, ptr.sequence_number))
+                })
+                .collect();
+            if !ack.retry || retry_transactions.is_empty() {
+                entry.remove();
+            } else if entry.get().retries >= config.shared_mempool_max_batch_retries {
+                counters::SHARED_MEMPOOL_BATCHES
+                    .with_label_values(&["dropped"])
+                    .inc();
+                entry.remove();
+            } else {
+                let batch = entry.get_mut();
+                batch.retries += 1;
+                batch.retry_transactions = Some(retry_transactions);
+            }
+        }
+        broadcast.timeouts = 0;
+        if ack.backoff {
+            broadcast.backoff_until =
+                Some(now + Duration::from_millis(config.shared_mempool_backoff_interval_ms));
+        }
+    }
+
+    fn is_backing_off(&self, now: Instant) -> bool {
+        self.broadcast
+            .backoff_until
+            .map_or(false, |backoff_until| now < backoff_until)
+    }
 }
 
 /// stores only peers that receive txns from this node
@@ -79,6 +241,7 @@ pub enum SharedMempoolNotification {
     Sync,
     PeerStateChange,
     NewTransactions,
+    Ack,
 }
 
 /// Struct that owns all dependencies required by shared mempool routines
@@ -96,7 +259,7 @@ where
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
 }
 
-/// Message sent from Consensus to Mempool
+/// Message sent from Consensus, or from AC for queries, to Mempool
 pub enum MempoolRequest {
     /// get block req
     GetBlockRequest {
@@ -112,9 +275,23 @@ pub enum MempoolRequest {
         /// timestamp of block
         block_timestamp_usecs: u64,
     },
+    /// get pending txns of account req
+    GetAccountTransactionsRequest {
+        /// account
+        address: AccountAddress,
+    },
+    /// get txn status req
+    GetTransactionStatusRequest {
+        /// sender
+        address: AccountAddress,
+        /// sequence number
+        sequence_number: u64,
+    },
+    /// get mempool stats req
+    GetStatsRequest {},
 }
 
-/// Message sent from Mempool to Consensus
+/// Message sent from Mempool to Consensus, or to AC for queries
 pub enum MempoolResponse {
     /// response to get block req
     GetBlockResponse {
@@ -123,6 +300,21 @@ pub enum MempoolResponse {
     },
     /// response to commit txns req
     CommitTransactionsResponse {},
+    /// response to get pending txns of account req
+    GetAccountTransactionsResponse {
+        /// txns of account in mempool along with their status
+        transactions: Vec<(SignedTransaction, MempoolTransactionStatus)>,
+    },
+    /// response to get txn status req
+    GetTransactionStatusResponse {
+        /// txn status
+        status: MempoolTransactionStatus,
+    },
+    /// response to get mempool stats req
+    GetStatsResponse {
+        /// mempool stats
+        stats: MempoolStats,
+    },
 }
 
 /// excluded txn
@@ -161,17 +353,29 @@ fn default_timer(tick_ms: u64) -> IntervalStream {
 /// new peer discovery handler
 /// adds new entry to `peer_info`
 /// `network_id` is the ID of the mempool network the peer belongs to
+/// A peer that reconnects starts over from its first batch that wasn't acknowledged, with a
+/// fresh broadcast state, since acknowledgements of batches sent before won't arrive
 fn new_peer(peer_info: &Mutex<PeerInfo>, peer_id: PeerId, network_id: PeerId) {
-    peer_info
+    let mut peer_info = peer_info
         .lock()
-        .expect("[shared mempool] failed to acquire peer_info lock")
-        .entry(peer_id)
-        .or_insert(PeerSyncState {
-            timeline_id: 0,
-            is_alive: true,
-            network_id,
-        })
-        .is_alive = true;
+        .expect("[shared mempool] failed to acquire peer_info lock");
+    let state = peer_info.entry(peer_id).or_insert(PeerSyncState {
+        timeline_id: 0,
+        is_alive: true,
+        network_id,
+        broadcast: BroadcastState::default(),
+    });
+    if !state.is_alive {
+        if let Some(batch) = state.broadcast.pending.values().next() {
+            state.timeline_id = batch.start_timeline_id;
+        }
+        // batch ids keep increasing, so that late acknowledgements can't match new batches
+        state.broadcast = BroadcastState {
+            batch_id: state.broadcast.batch_id,
+            ..BroadcastState::default()
+        };
+        state.is_alive = true;
+    }
 }
 
 /// lost peer handler. Marks connection as dead
@@ -185,65 +389,101 @@ fn lost_peer(peer_info: &Mutex<PeerInfo>, peer_id: PeerId) {
     }
 }
 
+/// acknowledgement handler
+/// updates state of broadcasts to peer
+fn process_broadcast_ack(
+    peer_info: &Mutex<PeerInfo>,
+    peer_id: PeerId,
+    ack: &BroadcastAck,
+    config: &MempoolConfig,
+) {
+    counters::SHARED_MEMPOOL_BATCHES
+        .with_label_values(&["acked"])
+        .inc();
+    if ack.backoff {
+        counters::SHARED_MEMPOOL_BATCHES
+            .with_label_values(&["backoff"])
+            .inc();
+    }
+    if let Some(state) = peer_info
+        .lock()
+        .expect("[shared mempool] failed to acquire peer_info lock")
+        .get_mut(&peer_id)
+    {
+        state.process_ack(ack, config, Instant::now());
+    }
+}
+
+/// picks up to `fanout` peers to broadcast transactions to, among the ones that are alive and
+/// aren't backing off
+/// Peers with the fewest timed out broadcasts in a row come first, then the ones with the lowest
+/// acknowledgement latency
+fn pick_broadcast_peers(peer_info: &PeerInfo, fanout: usize, now: Instant) -> Vec<PeerId> {
+    let mut peers: Vec<_> = peer_info
+        .iter()
+        .filter(|(_, state)| state.is_alive && !state.is_backing_off(now))
+        .collect();
+    peers.sort_by_key(|(_, state)| {
+        (
+            state.broadcast.timeouts,
+            state.broadcast.rtt.unwrap_or_default(),
+        )
+    });
+    peers
+        .into_iter()
+        .take(fanout)
+        .map(|(peer_id, _)| *peer_id)
+        .collect()
+}
+
 /// sync routine
 /// used to periodically broadcast ready to go transactions to peers
 async fn sync_with_peers<'a>(
     peer_info: &'a Mutex<PeerInfo>,
     mempool: &'a Mutex<CoreMempool>,
     mut network_senders: HashMap<PeerId, MempoolNetworkSender>,
-    batch_size: usize,
+    config: &'a MempoolConfig,
 ) {
-    // Clone the underlying peer_info map and use this to sync and collect
-    // state updates. We do this instead of holding the lock for the whole
-    // function since that would hold the lock across await points which is bad.
-    let peer_info_copy = peer_info
-        .lock()
-        .expect("[shared mempool] failed to acquire peer_info lock")
-        .deref()
-        .clone();
-    let mut state_updates = vec![];
-
-    for (peer_id, peer_state) in peer_info_copy.into_iter() {
-        if peer_state.is_alive {
-            let timeline_id = peer_state.timeline_id;
-
-            let (transactions, new_timeline_id) = mempool
-                .lock()
-                .expect("[shared mempool] failed to acquire mempool lock")
-                .read_timeline(timeline_id, batch_size);
-
-            if !transactions.is_empty() {
-                counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST.inc_by(transactions.len() as i64);
-
-                let mut msg = MempoolSyncMsg::default();
-                msg.peer_id = peer_id.into();
-                msg.transactions = transactions
-                    .into_iter()
-                    .map(|txn| txn.try_into().unwrap())
-                    .collect();
-
-                // Since this is a direct-send, this will only error if the network
-                // module has unexpectedly crashed or shutdown.
-                let network_sender = network_senders.get_mut(&peer_state.network_id).unwrap();
-                network_sender
-                    .clone()
-                    .send_to(peer_id, msg)
-                    .await
-                    .expect("[shared mempool] failed to direct-send mempool sync message");
+    // Collect the batches to send and update peer states while holding the peer_info lock,
+    // but send them once it is released, since holding the lock across await points is bad.
+    let mut messages = vec![];
+    {
+        let mut peer_info = peer_info
+            .lock()
+            .expect("[shared mempool] failed to acquire peer_info lock");
+        let mut mempool = mempool
+            .lock()
+            .expect("[shared mempool] failed to acquire mempool lock");
+        let now = Instant::now();
+        for peer_id in pick_broadcast_peers(&peer_info, config.shared_mempool_broadcast_fanout, now)
+        {
+            if let Some(peer_state) = peer_info.get_mut(&peer_id) {
+                for (batch_id, transactions) in peer_state.next_batches(&mut mempool, config, now) {
+                    messages.push((peer_id, peer_state.network_id, batch_id, transactions));
+                }
             }
-
-            state_updates.push((peer_id, new_timeline_id));
         }
     }
 
-    // Lock the shared peer_info and apply state updates.
-    let mut peer_info = peer_info
-        .lock()
-        .expect("[shared mempool] failed to acquire peer_info lock");
-    for (peer_id, new_timeline_id) in state_updates {
-        peer_info
-            .entry(peer_id)
-            .and_modify(|t| t.timeline_id = new_timeline_id);
+    for (peer_id, network_id, batch_id, transactions) in messages {
+        counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST.inc_by(transactions.len() as i64);
+
+        let mut msg = MempoolSyncMsg::default();
+        msg.peer_id = peer_id.into();
+        msg.batch_id = batch_id;
+        msg.transactions = transactions
+            .into_iter()
+            .map(|txn| txn.try_into().unwrap())
+            .collect();
+
+        // Since this is a direct-send, this will only error if the network
+        // module has unexpectedly crashed or shutdown.
+        let network_sender = network_senders.get_mut(&network_id).unwrap();
+        network_sender
+            .clone()
+            .send_to(peer_id, msg)
+            .await
+            .expect("[shared mempool] failed to direct-send mempool sync message");
     }
 }
 
@@ -266,7 +506,7 @@ async fn process_incoming_transactions<V>(
     smp: SharedMempool<V>,
     transactions: Vec<SignedTransaction>,
     timeline_state: TimelineState,
-) -> Vec<Status>
+) -> Vec<(TxnPointer, Status)>
 where
     V: TransactionValidation,
 {
@@ -290,10 +530,14 @@ where
                 }
             } else {
                 // failed to get transaction
-                statuses.push(Status::VmStatus(VmStatusProto::from(
-                    VMStatus::new(RESOURCE_DOES_NOT_EXIST)
-                        .with_message("[shared mempool] failed to get account state".to_string()),
-                )));
+                statuses.push((
+                    (t.sender(), t.sequence_number()),
+                    Status::VmStatus(VmStatusProto::from(
+                        VMStatus::new(RESOURCE_DOES_NOT_EXIST).with_message(
+                            "[shared mempool] failed to get account state".to_string(),
+                        ),
+                    )),
+                ));
             }
             None
         })
@@ -312,8 +556,10 @@ where
             .lock()
             .expect("[shared mempool] failed to acquire mempool lock");
         for (idx, (transaction, sequence_number, balance)) in transactions.into_iter().enumerate() {
+            let txn_pointer = (transaction.sender(), transaction.sequence_number());
             if let Ok(None) = validations[idx] {
                 let gas_cost = transaction.max_gas_amount();
+                let (sender, txn_sequence_number) = txn_pointer;
 
                 let mempool_status = mempool.add_txn(
                     transaction,
@@ -324,16 +570,30 @@ where
                 );
 
                 if mempool_status.code == MempoolAddTransactionStatusCode::Valid {
-                    statuses.push(Status::AcStatus(AdmissionControlStatus::Accepted.into()));
+                    // let the submitter know if the transaction waits on a sequence number gap
+                    let ac_status =
+                        match mempool.get_transaction_status(&sender, txn_sequence_number) {
+                            MempoolTransactionStatus::Parked {
+                                missing_sequence_number,
+                            } => AdmissionControlStatus::AcceptedWithSequenceNumberGap(
+                                missing_sequence_number,
+                            ),
+                            _ => AdmissionControlStatus::Accepted,
+                        };
+                    statuses.push((txn_pointer, Status::AcStatus(ac_status.into())));
                 } else {
-                    statuses.push(Status::MempoolStatus(
-                        MempoolAddTransactionStatusProto::from(mempool_status),
+                    statuses.push((
+                        txn_pointer,
+                        Status::MempoolStatus(MempoolAddTransactionStatusProto::from(
+                            mempool_status,
+                        )),
                     ));
                 }
             } else if let Ok(Some(validation_status)) = &validations[idx] {
-                statuses.push(Status::VmStatus(VmStatusProto::from(
-                    validation_status.clone(),
-                )));
+                statuses.push((
+                    txn_pointer,
+                    Status::VmStatus(VmStatusProto::from(validation_status.clone())),
+                ));
             }
         }
     }
@@ -365,11 +625,11 @@ async fn process_client_transaction_submission<V>(
             let mut statuses =
                 process_incoming_transactions(smp.clone(), vec![txn], TimelineState::NotReady)
                     .await;
-            log_txn_process_results(statuses.clone(), None);
+            log_txn_process_results(&statuses, None);
             if statuses.is_empty() {
                 error!("[shared mempool] unexpected error happened");
             } else {
-                response.status = Some(statuses.remove(0));
+                response.status = Some(statuses.remove(0).1);
             }
         }
     }
@@ -382,20 +642,25 @@ async fn process_client_transaction_submission<V>(
     }
 }
 
-fn log_txn_process_results(results: Vec<Status>, sender: Option<PeerId>) {
+fn log_txn_process_results(results: &[(TxnPointer, Status)], sender: Option<PeerId>) {
     let sender = match sender {
         Some(peer) => peer.to_string(),
         None => "client".to_string(),
     };
-    for result in results.iter() {
+    for (_, result) in results.iter() {
         match result {
             Status::AcStatus(ac_status) => {
                 // log success
-                if ac_status.code() == AdmissionControlStatusCode::Accepted {
-                    counters::SHARED_MEMPOOL_TRANSACTIONS_PROCESSED
-                        .with_label_values(&["success".to_string().deref(), &sender])
-                        .inc();
-                }
+                let label = match ac_status.code() {
+                    AdmissionControlStatusCode::Accepted => "success",
+                    AdmissionControlStatusCode::AcceptedWithSequenceNumberGap => {
+                        "sequence_number_gap"
+                    }
+                    _ => continue,
+                };
+                counters::SHARED_MEMPOOL_TRANSACTIONS_PROCESSED
+                    .with_label_values(&[label, &sender])
+                    .inc();
             }
             Status::VmStatus(_) => {
                 // log vm validation failure
@@ -421,11 +686,53 @@ async fn process_transaction_broadcast<V>(
     transactions: Vec<SignedTransaction>,
     timeline_state: TimelineState,
     peer_id: PeerId,
+    network_id: PeerId,
+    batch_id: u64,
 ) where
     V: TransactionValidation,
 {
+    let network_sender = smp.network_senders.get(&network_id).cloned();
     let results = process_incoming_transactions(smp, transactions, timeline_state).await;
-    log_txn_process_results(results, Some(peer_id));
+
+    // batches with id 0 come from peers that don't expect an acknowledgement
+    if batch_id != 0 {
+        let mut msg = MempoolSyncMsg::default();
+        msg.peer_id = peer_id.into();
+        msg.ack = Some(broadcast_ack(batch_id, &results));
+        if let Some(mut network_sender) = network_sender {
+            if let Err(e) = network_sender.send_to(peer_id, msg).await {
+                error!(
+                    "[shared mempool] failed to acknowledge batch {} to peer {}: {:?}",
+                    batch_id, peer_id, e
+                );
+            }
+        }
+    }
+    log_txn_process_results(&results, Some(peer_id));
+}
+
+/// acknowledgement of a batch of transactions, asking the sender to back off and to send again
+/// the transactions that were rejected because Mempool, or the account they belong to, is full
+fn broadcast_ack(batch_id: u64, results: &[(TxnPointer, Status)]) -> BroadcastAck {
+    let mut ack = BroadcastAck::default();
+    ack.batch_id = batch_id;
+    for ((sender, sequence_number), result) in results {
+        if let Status::MempoolStatus(mempool_status) = result {
+            match mempool_status.code() {
+                MempoolAddTransactionStatusCode::MempoolIsFull
+                | MempoolAddTransactionStatusCode::TooManyTransactions => {
+                    ack.retry = true;
+                    ack.backoff = true;
+                    ack.retry_transactions.push(TxnPointerProto {
+                        sender: (*sender).into(),
+                        sequence_number: *sequence_number,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    ack
 }
 
 /// This task handles [`SyncEvent`], which is periodically emitted for us to
@@ -437,12 +744,12 @@ where
     let peer_info = smp.peer_info;
     let mempool = smp.mempool;
     let network_senders = smp.network_senders;
-    let batch_size = smp.config.shared_mempool_batch_size;
+    let config = smp.config;
     let subscribers = smp.subscribers;
 
     while let Some(sync_event) = interval.next().await {
         trace!("SyncEvent: {:?}", sync_event);
-        sync_with_peers(&peer_info, &mempool, network_senders.clone(), batch_size).await;
+        sync_with_peers(&peer_info, &mempool, network_senders.clone(), &config).await;
         notify_subscribers(SharedMempoolNotification::Sync, &subscribers);
     }
 
@@ -500,6 +807,11 @@ async fn process_consensus_request<V>(
 
             MempoolResponse::CommitTransactionsResponse {}
         }
+        _ => {
+            // queries of Mempool content are served by `query_task`
+            error!("[shared mempool] unexpected mempool query from consensus");
+            return;
+        }
     };
 
     if let Err(e) = callback
@@ -513,6 +825,89 @@ async fn process_consensus_request<V>(
     }
 }
 
+/// Serves a query of Mempool content, received by AC.
+fn process_query_request<V>(smp: &SharedMempool<V>, msg: MempoolRequest) -> Result<MempoolResponse>
+where
+    V: TransactionValidation,
+{
+    let resp = match msg {
+        MempoolRequest::GetAccountTransactionsRequest { address } => {
+            counters::MEMPOOL_SERVICE
+                .with_label_values(&["get_account_transactions", "requested"])
+                .inc();
+            let transactions = smp
+                .mempool
+                .lock()
+                .expect("[get_account_transactions] acquire mempool lock")
+                .get_account_transactions(&address);
+            MempoolResponse::GetAccountTransactionsResponse { transactions }
+        }
+        MempoolRequest::GetTransactionStatusRequest {
+            address,
+            sequence_number,
+        } => {
+            counters::MEMPOOL_SERVICE
+                .with_label_values(&["get_transaction_status", "requested"])
+                .inc();
+            let status = smp
+                .mempool
+                .lock()
+                .expect("[get_transaction_status] acquire mempool lock")
+                .get_transaction_status(&address, sequence_number);
+            MempoolResponse::GetTransactionStatusResponse { status }
+        }
+        MempoolRequest::GetStatsRequest {} => {
+            counters::MEMPOOL_SERVICE
+                .with_label_values(&["get_stats", "requested"])
+                .inc();
+            let stats = smp
+                .mempool
+                .lock()
+                .expect("[get_stats] acquire mempool lock")
+                .get_stats();
+            MempoolResponse::GetStatsResponse { stats }
+        }
+        _ => return Err(format_err!("[shared mempool] unexpected mempool query")),
+    };
+    Ok(resp)
+}
+
+/// This task serves the queries of Mempool content received by AC, separately from the requests
+/// of consensus. Queries are served one at a time, and the ones exceeding
+/// `shared_mempool_max_queries_per_sec` are rejected, so that clients can't hold the mempool lock
+/// at the expense of consensus and transaction submission.
+async fn query_task<V>(
+    smp: SharedMempool<V>,
+    mut query_events: mpsc::Receiver<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
+) where
+    V: TransactionValidation,
+{
+    let max_queries_per_sec = smp.config.shared_mempool_max_queries_per_sec;
+    let mut window_start = Instant::now();
+    let mut served = 0;
+    while let Some((msg, callback)) = query_events.next().await {
+        let now = Instant::now();
+        if now.duration_since(window_start) >= Duration::from_secs(1) {
+            window_start = now;
+            served = 0;
+        }
+        let resp = if served < max_queries_per_sec {
+            served += 1;
+            process_query_request(&smp, msg)
+        } else {
+            counters::MEMPOOL_SERVICE
+                .with_label_values(&["query", "rate_limited"])
+                .inc();
+            Err(format_err!("Too many mempool queries, retry later"))
+        };
+        if callback.send(resp).is_err() {
+            debug!("[shared mempool] mempool query dropped by client");
+        }
+    }
+
+    crit!("SharedMempool query_task terminated");
+}
+
 /// This task handles inbound network events.
 async fn inbound_network_task<V>(
     smp: SharedMempool<V>,
@@ -585,6 +980,18 @@ async fn inbound_network_task<V>(
                                 }
                                 notify_subscribers(SharedMempoolNotification::PeerStateChange, &subscribers);
                             }
+                            Event::Message((peer_id, MempoolSyncMsg { ack: Some(ack), .. })) => {
+                                counters::SHARED_MEMPOOL_EVENTS
+                                    .with_label_values(&["ack".to_string().deref()])
+                                    .inc();
+                                process_broadcast_ack(
+                                    &peer_info,
+                                    peer_id,
+                                    &ack,
+                                    &smp.config,
+                                );
+                                notify_subscribers(SharedMempoolNotification::Ack, &subscribers);
+                            }
                             Event::Message((peer_id, msg)) => {
                                 counters::SHARED_MEMPOOL_EVENTS
                                     .with_label_values(&["message".to_string().deref()])
@@ -608,7 +1015,9 @@ async fn inbound_network_task<V>(
                                         smp_clone,
                                         transactions,
                                         timeline_state,
-                                        peer_id
+                                        peer_id,
+                                        network_id,
+                                        msg.batch_id,
                                     ))
                                     .await;
                             }
@@ -647,11 +1056,105 @@ async fn gc_task(mempool: Arc<Mutex<CoreMempool>>, gc_interval_ms: u64) {
     crit!("SharedMempool gc_task terminated");
 }
 
+/// reloads the transactions persisted in `log` into Mempool, re-validating them against the
+/// latest state, then periodically appends new transactions to the log and compacts it
+async fn persistence_task<V>(
+    smp: SharedMempool<V>,
+    mut log: TransactionLog,
+    persisted_txns: Vec<PersistedTransaction>,
+    snapshot_interval_ms: u64,
+    compaction_interval_ms: u64,
+) where
+    V: TransactionValidation,
+{
+    let (broadcast_txns, non_broadcast_txns): (Vec<_>, Vec<_>) =
+        persisted_txns.into_iter().partition(|txn| txn.broadcast);
+    for (txns, timeline_state) in vec![
+        (broadcast_txns, TimelineState::NotReady),
+        (non_broadcast_txns, TimelineState::NonQualified),
+    ] {
+        let txns: Vec<_> = txns.into_iter().map(|txn| txn.txn).collect();
+        if txns.is_empty() {
+            continue;
+        }
+        let num_txns = txns.len();
+        let statuses = process_incoming_transactions(smp.clone(), txns, timeline_state).await;
+        let num_reloaded = statuses
+            .iter()
+            .filter(|(_, status)| match status {
+                Status::AcStatus(ac_status) => match ac_status.code() {
+                    AdmissionControlStatusCode::Accepted
+                    | AdmissionControlStatusCode::AcceptedWithSequenceNumberGap => true,
+                    _ => false,
+                },
+                _ => false,
+            })
+            .count();
+        info!(
+            "[shared mempool] reloaded {} of {} persisted transactions",
+            num_reloaded, num_txns
+        );
+        counters::SHARED_MEMPOOL_PERSISTED_TRANSACTIONS
+            .with_label_values(&["reloaded"])
+            .inc_by(num_reloaded as i64);
+    }
+    // the log is rewritten right away, so that it only holds the transactions reloaded above
+    compact_transaction_log(&smp.mempool, &mut log);
+
+    let delayed_interval = |interval_ms| {
+        let period = Duration::from_millis(interval_ms);
+        interval_at(Instant::now() + period, period).fuse()
+    };
+    let mut snapshots = delayed_interval(snapshot_interval_ms);
+    let mut compactions = delayed_interval(compaction_interval_ms);
+    loop {
+        ::futures::select! {
+            _ = snapshots.select_next_some() => {
+                let txns = smp
+                    .mempool
+                    .lock()
+                    .expect("[shared mempool] failed to acquire mempool lock")
+                    .take_new_transactions();
+                match log.append(&txns) {
+                    Ok(()) => counters::SHARED_MEMPOOL_PERSISTED_TRANSACTIONS
+                        .with_label_values(&["appended"])
+                        .inc_by(txns.len() as i64),
+                    Err(e) => error!("[shared mempool] failed to persist transactions: {:?}", e),
+                }
+            },
+            _ = compactions.select_next_some() => compact_transaction_log(&smp.mempool, &mut log),
+            complete => break,
+        }
+    }
+
+    crit!("SharedMempool persistence_task terminated");
+}
+
+/// replaces the content of the transaction log with the transactions currently in Mempool
+fn compact_transaction_log(mempool: &Mutex<CoreMempool>, log: &mut TransactionLog) {
+    let txns = {
+        let mut mempool = mempool
+            .lock()
+            .expect("[shared mempool] failed to acquire mempool lock");
+        // new transactions are part of the compacted log
+        mempool.take_new_transactions();
+        mempool.persisted_transactions()
+    };
+    if let Err(e) = log.compact(&txns) {
+        error!(
+            "[shared mempool] failed to compact transaction log: {:?}",
+            e
+        );
+    }
+}
+
 /// bootstrap of SharedMempool
 /// creates separate Tokio Runtime that runs following routines:
-///   - outbound_sync_task (task that periodically broadcasts transactions to peers)
+///   - outbound_sync_task (task that periodically broadcasts transactions to peers, and sends
+///     again the ones they didn't acknowledge)
 ///   - inbound_network_task (task that handles inbound mempool messages and network events)
 ///   - gc_task (task that performs GC of all expired transactions by SystemTTL)
+///   - persistence_task (task that reloads and persists transactions on disk, if enabled)
 pub(crate) fn start_shared_mempool<V>(
     executor: &Handle,
     config: &NodeConfig,
@@ -664,6 +1167,7 @@ pub(crate) fn start_shared_mempool<V>(
         oneshot::Sender<Result<SubmitTransactionResponse>>,
     )>,
     consensus_events: mpsc::Receiver<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
+    query_events: mpsc::Receiver<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
     storage_read_client: Arc<dyn StorageRead>,
     validator: Arc<V>,
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
@@ -691,6 +1195,21 @@ pub(crate) fn start_shared_mempool<V>(
         subscribers,
     };
 
+    if config.mempool.persist_transactions {
+        match TransactionLog::open(&config.mempool.persistence_path()) {
+            Ok((log, txns)) => {
+                executor.spawn(persistence_task(
+                    smp.clone(),
+                    log,
+                    txns,
+                    config.mempool.persistence_snapshot_interval_ms,
+                    config.mempool.persistence_compaction_interval_ms,
+                ));
+            }
+            Err(e) => error!("[shared mempool] failed to open transaction log: {:?}", e),
+        }
+    }
+
     let interval_ms = config.mempool.shared_mempool_tick_interval_ms;
     let smp_outbound = smp.clone();
     let f = async move {
@@ -701,7 +1220,7 @@ pub(crate) fn start_shared_mempool<V>(
     executor.spawn(f);
 
     executor.spawn(inbound_network_task(
-        smp,
+        smp.clone(),
         executor.clone(),
         all_network_events,
         client_events,
@@ -709,6 +1228,8 @@ pub(crate) fn start_shared_mempool<V>(
         config_clone,
     ));
 
+    executor.spawn(query_task(smp, query_events));
+
     executor.spawn(gc_task(
         mempool,
         config.mempool.system_transaction_gc_interval_ms,
@@ -726,6 +1247,7 @@ pub fn bootstrap(
         oneshot::Sender<Result<SubmitTransactionResponse>>,
     )>,
     consensus_events: Receiver<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
+    query_events: Receiver<(MempoolRequest, oneshot::Sender<Result<MempoolResponse>>)>,
 ) -> Runtime {
     let runtime = Builder::new()
         .thread_name("shared-mem-")
@@ -749,6 +1271,7 @@ pub fn bootstrap(
         mempool_network_handles,
         client_events,
         consensus_events,
+        query_events,
         storage_client,
         vm_validator,
         vec![],
@@ -89,10 +89,10 @@ fn direct_send_bench(b: &mut Bencher, msg_len: &usize) {
     .collect();
 
     // Set up the listener network
-    let (listen_addr, mut network_provider) = NetworkBuilder::new(
+    let (listen_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         listener_peer_id,
-        listener_addr,
+        vec![listener_addr],
         RoleType::Validator,
     )
     .transport(TransportType::TcpNoise(Some((
@@ -113,10 +113,10 @@ fn direct_send_bench(b: &mut Bencher, msg_len: &usize) {
     runtime.handle().clone().spawn(network_provider.start());
 
     // Set up the dialer network
-    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         dialer_peer_id,
-        dialer_addr,
+        vec![dialer_addr],
         RoleType::Validator,
     )
     .transport(TransportType::TcpNoise(Some((
@@ -125,12 +125,7 @@ fn direct_send_bench(b: &mut Bencher, msg_len: &usize) {
     ))))
     .trusted_peers(trusted_peers)
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
-    .seed_peers(
-        [(listener_peer_id, vec![listen_addr])]
-            .iter()
-            .cloned()
-            .collect(),
-    )
+    .seed_peers([(listener_peer_id, listen_addrs)].iter().cloned().collect())
     .discovery_interval_ms(HOUR_IN_MS)
     .direct_send_protocols(vec![ProtocolId::from_static(
         CONSENSUS_DIRECT_SEND_PROTOCOL,
@@ -231,10 +226,10 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
     .collect();
 
     // Set up the listener network
-    let (listen_addr, mut network_provider) = NetworkBuilder::new(
+    let (listen_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         listener_peer_id,
-        listener_addr,
+        vec![listener_addr],
         RoleType::Validator,
     )
     .transport(TransportType::TcpNoise(Some((
@@ -251,10 +246,10 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
     runtime.handle().clone().spawn(network_provider.start());
 
     // Set up the dialer network
-    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         dialer_peer_id,
-        dialer_addr,
+        vec![dialer_addr],
         RoleType::Validator,
     )
     .transport(TransportType::TcpNoise(Some((
@@ -263,12 +258,7 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
     ))))
     .trusted_peers(trusted_peers)
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
-    .seed_peers(
-        [(listener_peer_id, vec![listen_addr])]
-            .iter()
-            .cloned()
-            .collect(),
-    )
+    .seed_peers([(listener_peer_id, listen_addrs)].iter().cloned().collect())
     .discovery_interval_ms(HOUR_IN_MS)
     .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)])
     .build();
@@ -15,9 +15,9 @@
 //! [`Yamux`]: crate::multiplexing::yamux::Yamux
 
 use futures::{
-    future::Future,
+    future::{BoxFuture, Either, Future, FutureExt, TryFutureExt},
     io::{AsyncRead, AsyncWrite},
-    stream::Stream,
+    stream::{BoxStream, Stream, StreamExt, TryStreamExt},
 };
 use std::{fmt::Debug, io};
 
@@ -59,3 +59,43 @@ pub trait StreamMultiplexer: Debug + Send + Sync {
     /// must return EOF (in the case of a read), or an error.
     fn close(&self) -> Self::Close;
 }
+
+/// Either of two [`StreamMultiplexer`]s, e.g. the connections of an
+/// [`EitherTransport`](crate::transport::either::EitherTransport).
+impl<A, B> StreamMultiplexer for Either<A, B>
+where
+    A: StreamMultiplexer,
+    A::Listener: 'static,
+    A::Outbound: 'static,
+    A::Close: 'static,
+    B: StreamMultiplexer,
+    B::Listener: 'static,
+    B::Outbound: 'static,
+    B::Close: 'static,
+{
+    type Substream = Either<A::Substream, B::Substream>;
+    type Listener = BoxStream<'static, io::Result<Self::Substream>>;
+    type Outbound = BoxFuture<'static, io::Result<Self::Substream>>;
+    type Close = BoxFuture<'static, io::Result<()>>;
+
+    fn listen_for_inbound(&self) -> Self::Listener {
+        match self {
+            Either::Left(muxer) => muxer.listen_for_inbound().map_ok(Either::Left).boxed(),
+            Either::Right(muxer) => muxer.listen_for_inbound().map_ok(Either::Right).boxed(),
+        }
+    }
+
+    fn open_outbound(&self) -> Self::Outbound {
+        match self {
+            Either::Left(muxer) => muxer.open_outbound().map_ok(Either::Left).boxed(),
+            Either::Right(muxer) => muxer.open_outbound().map_ok(Either::Right).boxed(),
+        }
+    }
+
+    fn close(&self) -> Self::Close {
+        match self {
+            Either::Left(muxer) => muxer.close().boxed(),
+            Either::Right(muxer) => muxer.close().boxed(),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A transport combining two transports, e.g. TCP and QUIC, so that each address is served by the
+//! transport which supports it.
+
+use crate::transport::Transport;
+use futures::{
+    future::{BoxFuture, Either, FutureExt, TryFutureExt},
+    stream::{BoxStream, StreamExt, TryStreamExt},
+};
+use parity_multiaddr::Multiaddr;
+use std::io;
+
+/// Listens and dials with the left transport, falling back to the right transport for the
+/// addresses the left one rejects. Connections are produced as [`Either`] of the outputs.
+///
+/// The errors of both transports are converted into [`io::Error`]s.
+pub struct EitherTransport<A, B> {
+    left: A,
+    right: B,
+}
+
+impl<A, B> EitherTransport<A, B> {
+    pub(crate) fn new(left: A, right: B) -> Self {
+        Self { left, right }
+    }
+}
+
+impl<A, B> Transport for EitherTransport<A, B>
+where
+    A: Transport,
+    A::Listener: 'static,
+    A::Inbound: 'static,
+    A::Outbound: 'static,
+    B: Transport,
+    B::Listener: 'static,
+    B::Inbound: 'static,
+    B::Outbound: 'static,
+{
+    type Output = Either<A::Output, B::Output>;
+    type Error = io::Error;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, Multiaddr)>>;
+    type Inbound = BoxFuture<'static, io::Result<Self::Output>>;
+    type Outbound = BoxFuture<'static, io::Result<Self::Output>>;
+
+    fn listen_on(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let left_error = match self.left.listen_on(addr.clone()) {
+            Ok((listener, addr)) => {
+                let listener = listener.map_err(other).map_ok(|(inbound, addr)| {
+                    let inbound = inbound.map_ok(Either::Left).map_err(other).boxed();
+                    (inbound, addr)
+                });
+                return Ok((listener.boxed(), addr));
+            }
+            Err(e) => e,
+        };
+        match self.right.listen_on(addr) {
+            Ok((listener, addr)) => {
+                let listener = listener.map_err(other).map_ok(|(inbound, addr)| {
+                    let inbound = inbound.map_ok(Either::Right).map_err(other).boxed();
+                    (inbound, addr)
+                });
+                Ok((listener.boxed(), addr))
+            }
+            Err(right_error) => Err(both(left_error, right_error)),
+        }
+    }
+
+    fn dial(&self, addr: Multiaddr) -> Result<Self::Outbound, Self::Error> {
+        let left_error = match self.left.dial(addr.clone()) {
+            Ok(outbound) => return Ok(outbound.map_ok(Either::Left).map_err(other).boxed()),
+            Err(e) => e,
+        };
+        match self.right.dial(addr) {
+            Ok(outbound) => Ok(outbound.map_ok(Either::Right).map_err(other).boxed()),
+            Err(right_error) => Err(both(left_error, right_error)),
+        }
+    }
+}
+
+fn other<E>(e: E) -> io::Error
+where
+    E: ::std::error::Error + Send + Sync + 'static,
+{
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+fn both<A, B>(left_error: A, right_error: B) -> io::Error
+where
+    A: ::std::error::Error,
+    B: ::std::error::Error,
+{
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "Neither transport succeeded: {}; {}",
+            left_error, right_error
+        ),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transport::{memory::MemoryTransport, tcp::TcpTransport, Transport, TransportExt};
+    use futures::{
+        future::{join, Either, FutureExt},
+        stream::StreamExt,
+    };
+
+    #[tokio::test]
+    async fn listen_and_dial_with_either_transport() {
+        let t = MemoryTransport::default().or_transport(TcpTransport::default());
+
+        let (memory_listener, memory_addr) = t.listen_on("/memory/0".parse().unwrap()).unwrap();
+        let (tcp_listener, tcp_addr) = t
+            .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .unwrap();
+
+        let memory_listener = memory_listener
+            .into_future()
+            .then(|(maybe_result, _stream)| {
+                let (incoming, _addr) = maybe_result.unwrap().unwrap();
+                incoming
+            });
+        let (outgoing, incoming) = join(t.dial(memory_addr).unwrap(), memory_listener).await;
+        assert!(matches_left(&outgoing.unwrap()));
+        assert!(matches_left(&incoming.unwrap()));
+
+        let tcp_listener = tcp_listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming
+        });
+        let (outgoing, incoming) = join(t.dial(tcp_addr).unwrap(), tcp_listener).await;
+        assert!(!matches_left(&outgoing.unwrap()));
+        assert!(!matches_left(&incoming.unwrap()));
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = MemoryTransport::default().or_transport(TcpTransport::default());
+
+        let result = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial("/ip4/127.0.0.1/udp/0/quic".parse().unwrap());
+        assert!(result.is_err());
+    }
+
+    fn matches_left<A, B>(either: &Either<A, B>) -> bool {
+        match either {
+            Either::Left(_) => true,
+            Either::Right(_) => false,
+        }
+    }
+}
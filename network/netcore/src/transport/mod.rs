@@ -17,7 +17,9 @@ use std::time::Duration;
 
 pub mod and_then;
 pub mod boxed;
+pub mod either;
 pub mod memory;
+pub mod quic;
 pub mod tcp;
 pub mod timeout;
 
@@ -140,4 +142,14 @@ pub trait TransportExt: Transport {
     {
         timeout::TimeoutTransport::new(self, timeout)
     }
+
+    /// Combines two [`Transport`]s, using `other` for the addresses this [`Transport`] rejects,
+    /// e.g. to listen on both TCP and QUIC addresses.
+    fn or_transport<T>(self, other: T) -> either::EitherTransport<Self, T>
+    where
+        Self: Sized,
+        T: Transport,
+    {
+        either::EitherTransport::new(self, other)
+    }
 }
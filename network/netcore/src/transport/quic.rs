@@ -0,0 +1,498 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Unlike TCP, QUIC natively multiplexes independent streams over a single connection, so the
+//! connections produced by [`QuicTransport`] are [`StreamMultiplexer`]s themselves and a slow or
+//! lossy substream does not block the others (no head-of-line blocking between substreams).
+//!
+//! QUIC mandates TLS 1.3, which is used to bind the connection to the Ed25519 network signing
+//! keys of the peers: each side presents a self-signed certificate carrying its key and proves
+//! ownership of it during the handshake. Any such certificate is accepted, it is up to the upper
+//! layers to check the public key of the remote, see [`QuicConnection::remote_public_key`].
+//!
+//! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
+
+use crate::{multiplexing::StreamMultiplexer, transport::Transport};
+use futures::{
+    future::{self, BoxFuture, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    stream::{BoxStream, Stream, StreamExt},
+};
+use parity_multiaddr::{Multiaddr, Protocol};
+use quinn::{
+    ClientConfig, Connecting, Connection, Endpoint, EndpointDriver, IncomingBiStreams,
+    NewConnection, RecvStream, SendStream, ServerConfig, VarInt,
+};
+use rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, DistinguishedNames, PrivateKey,
+    ProtocolVersion, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// The application protocol negotiated during the TLS handshake.
+const LIBRA_QUIC_ALPN: &[u8] = b"/libra/quic/1.0.0";
+/// The name in the certificates, which is not verified as peers are identified by their keys.
+const SERVER_NAME: &str = "libra";
+/// PKCS#8 (version 1) encoding of an Ed25519 private key, which is followed by the 32 bytes key.
+const PKCS8_ED25519_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+/// The object identifier of Ed25519 keys, see RFC 8410.
+const ED25519_OID: &str = "1.3.101.112";
+const ED25519_KEY_LENGTH: usize = 32;
+
+/// Transport to build QUIC connections, authenticated by an Ed25519 key
+#[derive(Clone)]
+pub struct QuicTransport {
+    server_config: ServerConfig,
+    client_config: ClientConfig,
+    public_key: [u8; ED25519_KEY_LENGTH],
+    client_endpoints: Arc<Mutex<ClientEndpoints>>,
+}
+
+/// The endpoints outbound connections are made from, one per address family, so that dialing
+/// does not bind a new socket each time. The endpoint of a listener bound to all the interfaces of
+/// a family is used for that family, which lets dialed peers see connections from its port.
+#[derive(Default)]
+struct ClientEndpoints {
+    ipv4: Option<Endpoint>,
+    ipv6: Option<Endpoint>,
+}
+
+impl ClientEndpoints {
+    fn get_mut(&mut self, ipv4: bool) -> &mut Option<Endpoint> {
+        if ipv4 {
+            &mut self.ipv4
+        } else {
+            &mut self.ipv6
+        }
+    }
+}
+
+impl QuicTransport {
+    /// Creates a transport that authenticates with the given Ed25519 private key.
+    pub fn new(private_key: &[u8; ED25519_KEY_LENGTH]) -> io::Result<Self> {
+        let (certificate, private_key) = self_signed_certificate(private_key)?;
+        let public_key = ed25519_public_key(&certificate.0)
+            .ok_or_else(|| invalid_data("Unable to read the public key of the certificate"))?;
+        let verifier = Arc::new(PeerCertificateVerifier);
+
+        let mut server_crypto = rustls::ServerConfig::new(verifier.clone());
+        server_crypto.versions = vec![ProtocolVersion::TLSv1_3];
+        server_crypto.alpn_protocols = vec![LIBRA_QUIC_ALPN.to_vec()];
+        server_crypto
+            .set_single_cert(vec![certificate.clone()], private_key.clone())
+            .map_err(other)?;
+        let mut server_config = ServerConfig::default();
+        server_config.crypto = Arc::new(server_crypto);
+
+        let mut client_crypto = rustls::ClientConfig::new();
+        client_crypto.versions = vec![ProtocolVersion::TLSv1_3];
+        client_crypto.alpn_protocols = vec![LIBRA_QUIC_ALPN.to_vec()];
+        client_crypto.set_single_client_cert(vec![certificate], private_key);
+        client_crypto.dangerous().set_certificate_verifier(verifier);
+        let mut client_config = ClientConfig::default();
+        client_config.crypto = Arc::new(client_crypto);
+
+        Ok(Self {
+            server_config,
+            client_config,
+            public_key,
+            client_endpoints: Arc::new(Mutex::new(ClientEndpoints::default())),
+        })
+    }
+
+    /// The Ed25519 public key this transport authenticates with.
+    pub fn public_key(&self) -> [u8; ED25519_KEY_LENGTH] {
+        self.public_key
+    }
+
+    /// Returns the endpoint to dial addresses of the given family from, binding it to an ephemeral
+    /// port the first time.
+    fn client_endpoint(&self, ipv4: bool) -> io::Result<Endpoint> {
+        let mut client_endpoints = self.client_endpoints.lock().unwrap();
+        let client_endpoint = client_endpoints.get_mut(ipv4);
+        if let Some(endpoint) = client_endpoint.as_ref() {
+            return Ok(endpoint.clone());
+        }
+
+        let bind_addr = if ipv4 {
+            SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+        } else {
+            SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+        };
+        let mut builder = Endpoint::builder();
+        builder.default_client_config(self.client_config.clone());
+        let (driver, endpoint, _incoming) = builder.bind(&bind_addr).map_err(other)?;
+        spawn_endpoint_driver(driver);
+        *client_endpoint = Some(endpoint.clone());
+        Ok(endpoint)
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicConnection;
+    type Error = io::Error;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, Multiaddr)>>;
+    type Inbound = BoxFuture<'static, io::Result<QuicConnection>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicConnection>>;
+
+    fn listen_on(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let mut builder = Endpoint::builder();
+        builder.listen(self.server_config.clone());
+        builder.default_client_config(self.client_config.clone());
+        let (driver, endpoint, incoming) = builder.bind(&socket_addr).map_err(other)?;
+        spawn_endpoint_driver(driver);
+        let local_addr = endpoint.local_addr()?;
+        if local_addr.ip().is_unspecified() {
+            self.client_endpoints
+                .lock()
+                .unwrap()
+                .get_mut(local_addr.is_ipv4())
+                .get_or_insert_with(|| endpoint.clone());
+        }
+        let local_addr = socketaddr_to_multiaddr(local_addr);
+
+        let listener = incoming.map(move |connecting| {
+            let dialer_addr = socketaddr_to_multiaddr(connecting.remote_address());
+            let inbound = QuicConnection::establish(connecting, endpoint.clone()).boxed();
+            Ok((inbound, dialer_addr))
+        });
+        Ok((listener.boxed(), local_addr))
+    }
+
+    fn dial(&self, addr: Multiaddr) -> Result<Self::Outbound, Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let endpoint = self.client_endpoint(socket_addr.is_ipv4())?;
+        let connecting = endpoint.connect(&socket_addr, SERVER_NAME).map_err(other)?;
+        Ok(QuicConnection::establish(connecting, endpoint).boxed())
+    }
+}
+
+fn spawn_endpoint_driver(driver: EndpointDriver) {
+    tokio::spawn(driver.map(|_| ()));
+}
+
+/// An established QUIC connection, whose streams are used as substreams
+pub struct QuicConnection {
+    connection: Connection,
+    // Shared by all the listeners of the connection.
+    bi_streams: Arc<Mutex<IncomingBiStreams>>,
+    remote_public_key: [u8; ED25519_KEY_LENGTH],
+    // Keeps the endpoint, and so its socket, alive for as long as the connection.
+    _endpoint: Endpoint,
+}
+
+impl QuicConnection {
+    async fn establish(connecting: Connecting, endpoint: Endpoint) -> io::Result<Self> {
+        let NewConnection {
+            driver,
+            connection,
+            bi_streams,
+            ..
+        } = connecting.await.map_err(other)?;
+        tokio::spawn(driver.map(|_| ()));
+
+        let remote_public_key = connection
+            .authentication_data()
+            .peer_certificates
+            .and_then(|certificates| {
+                certificates
+                    .iter()
+                    .next()
+                    .and_then(|certificate| ed25519_public_key(&certificate.0))
+            })
+            .ok_or_else(|| invalid_data("Remote peer did not present an Ed25519 certificate"))?;
+
+        Ok(Self {
+            connection,
+            bi_streams: Arc::new(Mutex::new(bi_streams)),
+            remote_public_key,
+            _endpoint: endpoint,
+        })
+    }
+
+    /// The Ed25519 public key the remote peer authenticated with.
+    pub fn remote_public_key(&self) -> [u8; ED25519_KEY_LENGTH] {
+        self.remote_public_key
+    }
+}
+
+impl fmt::Debug for QuicConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicConnection")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+impl StreamMultiplexer for QuicConnection {
+    type Substream = QuicSubstream;
+    type Listener = QuicListener;
+    type Outbound = BoxFuture<'static, io::Result<QuicSubstream>>;
+    type Close = future::Ready<io::Result<()>>;
+
+    fn listen_for_inbound(&self) -> Self::Listener {
+        QuicListener {
+            bi_streams: self.bi_streams.clone(),
+        }
+    }
+
+    /// Opens a bidirectional stream. Note that the remote side only learns about the stream once
+    /// data is written to it.
+    fn open_outbound(&self) -> Self::Outbound {
+        let open = self.connection.open_bi();
+        async move {
+            let (send, recv) = open.await.map_err(other)?;
+            Ok(QuicSubstream { send, recv })
+        }
+        .boxed()
+    }
+
+    fn close(&self) -> Self::Close {
+        self.connection.close(VarInt::from_u32(0), b"");
+        future::ready(Ok(()))
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListener {
+    bi_streams: Arc<Mutex<IncomingBiStreams>>,
+}
+
+impl Stream for QuicListener {
+    type Item = io::Result<QuicSubstream>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut bi_streams = self.bi_streams.lock().unwrap();
+        match ready!(Pin::new(&mut *bi_streams).poll_next(context)) {
+            Some(Ok((send, recv))) => Poll::Ready(Some(Ok(QuicSubstream { send, recv }))),
+            Some(Err(e)) => Poll::Ready(Some(Err(other(e)))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A bidirectional QUIC stream
+#[derive(Debug)]
+pub struct QuicSubstream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl AsyncRead for QuicSubstream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSubstream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    /// Finishes the sending side of the stream.
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_close(context)
+    }
+}
+
+/// Accepts any single certificate carrying an Ed25519 key, for both servers and clients. The
+/// handshake itself checks that the peer owns the key of its certificate.
+struct PeerCertificateVerifier;
+
+impl PeerCertificateVerifier {
+    fn verify(presented_certs: &[Certificate]) -> Result<(), TLSError> {
+        match presented_certs {
+            [certificate] if ed25519_public_key(&certificate.0).is_some() => Ok(()),
+            _ => Err(TLSError::General(
+                "Expected a single certificate with an Ed25519 key".into(),
+            )),
+        }
+    }
+}
+
+impl ServerCertVerifier for PeerCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        Self::verify(presented_certs).map(|_| ServerCertVerified::assertion())
+    }
+}
+
+impl ClientCertVerifier for PeerCertificateVerifier {
+    fn offer_client_auth(&self) -> bool {
+        true
+    }
+
+    fn client_auth_mandatory(&self) -> bool {
+        true
+    }
+
+    fn client_auth_root_subjects(&self) -> DistinguishedNames {
+        DistinguishedNames::new()
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[Certificate],
+    ) -> Result<ClientCertVerified, TLSError> {
+        Self::verify(presented_certs).map(|_| ClientCertVerified::assertion())
+    }
+}
+
+fn self_signed_certificate(
+    private_key: &[u8; ED25519_KEY_LENGTH],
+) -> io::Result<(Certificate, PrivateKey)> {
+    let mut pkcs8 = PKCS8_ED25519_PREFIX.to_vec();
+    pkcs8.extend_from_slice(private_key);
+    let key_pair = rcgen::KeyPair::from_der(&pkcs8).map_err(other)?;
+
+    let mut params = rcgen::CertificateParams::new(vec![SERVER_NAME.to_string()]);
+    params.alg = &rcgen::PKCS_ED25519;
+    params.key_pair = Some(key_pair);
+    let certificate = rcgen::Certificate::from_params(params).map_err(other)?;
+    let certificate = certificate.serialize_der().map_err(other)?;
+    Ok((Certificate(certificate), PrivateKey(pkcs8)))
+}
+
+/// Extracts the Ed25519 key of a DER encoded X.509 certificate. The handshake checks that the
+/// peer owns the key of its certificate.
+fn ed25519_public_key(certificate: &[u8]) -> Option<[u8; ED25519_KEY_LENGTH]> {
+    let (rest, certificate) = x509_parser::parse_x509_der(certificate).ok()?;
+    let spki = &certificate.tbs_certificate.subject_pki;
+    if !rest.is_empty()
+        || spki.algorithm.algorithm.to_string() != ED25519_OID
+        || spki.subject_public_key.data.len() != ED25519_KEY_LENGTH
+    {
+        return None;
+    }
+    let mut public_key = [0u8; ED25519_KEY_LENGTH];
+    public_key.copy_from_slice(spki.subject_public_key.data);
+    Some(public_key)
+}
+
+fn other<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn ::std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn socketaddr_to_multiaddr(socketaddr: SocketAddr) -> Multiaddr {
+    let ipaddr: Multiaddr = socketaddr.ip().into();
+    ipaddr
+        .with(Protocol::Udp(socketaddr.port()))
+        .with(Protocol::Quic)
+}
+
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> io::Result<SocketAddr> {
+    let protocols: Vec<_> = addr.iter().collect();
+    match protocols.as_slice() {
+        [Protocol::Ip4(ip), Protocol::Udp(port), Protocol::Quic] => {
+            Ok(SocketAddr::new((*ip).into(), *port))
+        }
+        [Protocol::Ip6(ip), Protocol::Udp(port), Protocol::Quic] => {
+            Ok(SocketAddr::new((*ip).into(), *port))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid Multiaddr '{:?}'", addr),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        multiplexing::StreamMultiplexer,
+        transport::{quic::QuicTransport, Transport},
+    };
+    use futures::{
+        future::{join, FutureExt},
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let listener_transport = QuicTransport::new(&[1u8; 32])?;
+        let dialer_transport = QuicTransport::new(&[2u8; 32])?;
+
+        let (listener, addr) =
+            listener_transport.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        let dial = dialer_transport.dial(addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming
+        });
+        let (outbound, inbound) = join(dial, listener).await;
+        let (outbound, inbound) = (outbound?, inbound?);
+
+        // Both sides are authenticated by their keys.
+        assert_eq!(
+            outbound.remote_public_key(),
+            listener_transport.public_key()
+        );
+        assert_eq!(inbound.remote_public_key(), dialer_transport.public_key());
+
+        let mut outbound_substream = outbound.open_outbound().await?;
+        outbound_substream.write_all(b"Earth").await?;
+        outbound_substream.flush().await?;
+
+        let mut inbound_substream = inbound.listen_for_inbound().next().await.unwrap()?;
+        let mut buf = [0; 5];
+        inbound_substream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"Earth");
+        inbound_substream.write_all(b"Air").await?;
+        inbound_substream.close().await?;
+
+        let mut buf = [0; 3];
+        outbound_substream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"Air");
+
+        outbound.close().await?;
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::new(&[1u8; 32]).unwrap();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial("/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -10,12 +10,17 @@
 //! eligible nodes, and the Discovery actor infroms it about updates to addresses of eligible
 //! nodes.
 //!
+//! Peers banned by the shared [`PeerScores`] for misbehaving are treated like ineligible
+//! nodes until their ban expires: we disconnect from them and do not dial them.
+//!
 //! When dialing a peer with a given list of addresses, we attempt each address
-//! in order with a capped exponential backoff delay until we eventually connect
-//! to the peer.
+//! in order until we eventually connect to the peer. Each address keeps its own
+//! capped exponential backoff delay, so that an unreachable address does not
+//! delay dials to the peer's other addresses.
 use crate::{
     common::NetworkPublicKeys,
     peer_manager::{PeerManagerError, PeerManagerNotification, PeerManagerRequestSender},
+    peer_score::PeerScores,
 };
 use channel;
 use futures::{
@@ -30,7 +35,10 @@ use std::{
     cmp::min,
     collections::HashMap,
     fmt::Debug,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
     time::{Duration, Instant},
 };
 use tokio::time;
@@ -42,6 +50,10 @@ mod test;
 pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     /// Nodes which are eligible to join the network.
     eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    /// The consensus epoch of the eligible nodes, shared with Discovery.
+    consensus_epoch: Arc<AtomicU64>,
+    /// Scores of remote peers. Banned peers are neither dialed nor kept connected.
+    peer_scores: PeerScores,
     /// PeerId and address of remote peers to which this peer is connected.
     connected: HashMap<PeerId, Multiaddr>,
     /// Addresses of peers received from Discovery module.
@@ -74,8 +86,8 @@ pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
 pub enum ConnectivityRequest {
     /// Request to update known addresses of peer with id `PeerId` to given list.
     UpdateAddresses(PeerId, Vec<Multiaddr>),
-    /// Update set of nodes eligible to join the network.
-    UpdateEligibleNodes(HashMap<PeerId, NetworkPublicKeys>),
+    /// Update set of nodes eligible to join the network in the given consensus epoch.
+    UpdateEligibleNodes(u64, HashMap<PeerId, NetworkPublicKeys>),
     /// Gets current size of dial queue. This is useful in tests.
     GetDialQueueSize(oneshot::Sender<usize>),
 }
@@ -91,8 +103,10 @@ enum DialResult {
 /// peer.
 #[derive(Debug, Clone)]
 struct DialState<TBackoff> {
-    /// The current state of this peer's backoff delay.
-    backoff: TBackoff,
+    /// The backoff strategy used for addresses which have not been dialed yet.
+    backoff_strategy: TBackoff,
+    /// The current state of the backoff delay of each dialed address.
+    backoffs: HashMap<Multiaddr, TBackoff>,
     /// The index of the next address to dial. Index of an address in the peer's
     /// `peer_addresses` entry.
     addr_idx: usize,
@@ -107,6 +121,8 @@ where
     /// Creates a new instance of the [`ConnectivityManager`] actor.
     pub fn new(
         eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+        consensus_epoch: Arc<AtomicU64>,
+        peer_scores: PeerScores,
         ticker: TTicker,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
@@ -116,6 +132,8 @@ where
     ) -> Self {
         Self {
             eligible,
+            consensus_epoch,
+            peer_scores,
             connected: HashMap::new(),
             peer_addresses: HashMap::new(),
             ticker,
@@ -166,17 +184,18 @@ where
         }
     }
 
-    /// Disconnect from all peers that are no longer eligible.
+    /// Disconnect from all peers that are no longer eligible or are banned.
     ///
     /// For instance, a validator might leave the validator set after a
     /// reconfiguration. If we are currently connected to this validator, calling
     /// this function will close our connection to it.
     async fn close_stale_connections(&mut self) {
         let eligible = self.eligible.read().unwrap().clone();
+        let peer_scores = &self.peer_scores;
         let stale_connections: Vec<_> = self
             .connected
             .keys()
-            .filter(|peer_id| !eligible.contains_key(peer_id))
+            .filter(|peer_id| !eligible.contains_key(peer_id) || peer_scores.is_banned(peer_id))
             .cloned()
             .collect();
         for p in stale_connections.into_iter() {
@@ -192,17 +211,18 @@ where
         }
     }
 
-    /// Cancel all pending dials to peers that are no longer eligible.
+    /// Cancel all pending dials to peers that are no longer eligible or are banned.
     ///
     /// For instance, a validator might leave the validator set after a
     /// reconfiguration. If there is a pending dial to this validator, calling
     /// this function will remove it from the dial queue.
     async fn cancel_stale_dials(&mut self) {
         let eligible = self.eligible.read().unwrap().clone();
+        let peer_scores = &self.peer_scores;
         let stale_dials: Vec<_> = self
             .dial_queue
             .keys()
-            .filter(|peer_id| !eligible.contains_key(peer_id))
+            .filter(|peer_id| !eligible.contains_key(peer_id) || peer_scores.is_banned(peer_id))
             .cloned()
             .collect();
         for p in stale_dials.into_iter() {
@@ -220,6 +240,7 @@ where
             .iter()
             .filter(|(peer_id, addrs)| {
                 eligible.contains_key(peer_id)  // The node is eligible to be dialed.
+                    && !self.peer_scores.is_banned(peer_id) // The node is not banned.
                     && self.connected.get(peer_id).is_none() // The node is not already connected.
                     && self.dial_queue.get(peer_id).is_none() // There is no pending dial to this node.
                     && !addrs.is_empty() // There is an address to dial.
@@ -256,10 +277,10 @@ where
             // addr[0], .., addr[len-1], addr[0], ..
             let addr = dial_state.next_addr(&addrs).clone();
 
-            // Using the DialState's backoff strategy, compute the delay until
+            // Using the backoff of the chosen address, compute the delay until
             // the next dial attempt for this peer.
             let now = Instant::now();
-            let dial_delay = dial_state.next_backoff_delay(max_delay);
+            let dial_delay = dial_state.next_backoff_delay(&addr, max_delay);
             let f_delay = time::delay_for(dial_delay);
 
             let (cancel_tx, cancel_rx) = oneshot::channel();
@@ -328,15 +349,20 @@ where
                     "Received updated addresses for peer: {}",
                     peer_id.short_str()
                 );
-                self.peer_addresses.insert(peer_id, addrs);
-                // Ensure that the next dial attempt starts from the first addr.
+                // Ensure that the next dial attempt starts from the first addr, and forget the
+                // backoffs of addresses which are no longer advertised.
                 if let Some(dial_state) = self.dial_states.get_mut(&peer_id) {
-                    dial_state.reset_addr();
+                    dial_state.reset_addrs(&addrs);
                 }
+                self.peer_addresses.insert(peer_id, addrs);
             }
-            ConnectivityRequest::UpdateEligibleNodes(nodes) => {
-                trace!("Received updated list of eligible nodes",);
+            ConnectivityRequest::UpdateEligibleNodes(epoch, nodes) => {
+                trace!(
+                    "Received updated list of eligible nodes for epoch: {}",
+                    epoch
+                );
                 *self.eligible.write().unwrap() = nodes;
+                self.consensus_epoch.store(epoch, Ordering::Relaxed);
             }
             ConnectivityRequest::GetDialQueueSize(sender) => {
                 sender.send(self.dial_queue.len()).unwrap();
@@ -410,15 +436,17 @@ impl<TBackoff> DialState<TBackoff>
 where
     TBackoff: Iterator<Item = Duration> + Clone,
 {
-    fn new(backoff: TBackoff) -> Self {
+    fn new(backoff_strategy: TBackoff) -> Self {
         Self {
-            backoff,
+            backoff_strategy,
+            backoffs: HashMap::new(),
             addr_idx: 0,
         }
     }
 
-    fn reset_addr(&mut self) {
+    fn reset_addrs(&mut self, addrs: &[Multiaddr]) {
         self.addr_idx = 0;
+        self.backoffs.retain(|addr, _| addrs.contains(addr));
     }
 
     fn next_addr<'a>(&mut self, addrs: &'a [Multiaddr]) -> &'a Multiaddr {
@@ -428,7 +456,12 @@ where
         &addrs[addr_idx % addrs.len()]
     }
 
-    fn next_backoff_delay(&mut self, max_delay: Duration) -> Duration {
-        min(max_delay, self.backoff.next().unwrap_or(max_delay))
+    fn next_backoff_delay(&mut self, addr: &Multiaddr, max_delay: Duration) -> Duration {
+        let backoff_strategy = &self.backoff_strategy;
+        let backoff = self
+            .backoffs
+            .entry(addr.clone())
+            .or_insert_with(|| backoff_strategy.clone());
+        min(max_delay, backoff.next().unwrap_or(max_delay))
     }
 }
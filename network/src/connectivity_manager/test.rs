@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::peer_manager::PeerManagerRequest;
+use crate::{peer_manager::PeerManagerRequest, peer_score::Misbehavior};
 use core::str::FromStr;
 use futures::SinkExt;
 use libra_crypto::{ed25519::compat, test_utils::TEST_SEED, x25519};
@@ -10,7 +10,7 @@ use memsocket::MemorySocket;
 use rand::{rngs::StdRng, SeedableRng};
 use std::io;
 use tokio::runtime::Runtime;
-use tokio_retry::strategy::FixedInterval;
+use tokio_retry::strategy::{ExponentialBackoff, FixedInterval};
 
 fn setup_conn_mgr(
     rt: &mut Runtime,
@@ -20,6 +20,19 @@ fn setup_conn_mgr(
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Sender<ConnectivityRequest>,
     channel::Sender<()>,
+) {
+    setup_conn_mgr_with_peer_scores(rt, seed_peer_id, PeerScores::default())
+}
+
+fn setup_conn_mgr_with_peer_scores(
+    rt: &mut Runtime,
+    seed_peer_id: PeerId,
+    peer_scores: PeerScores,
+) -> (
+    channel::Receiver<PeerManagerRequest<MemorySocket>>,
+    channel::Sender<PeerManagerNotification<MemorySocket>>,
+    channel::Sender<ConnectivityRequest>,
+    channel::Sender<()>,
 ) {
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx): (
         channel::Sender<PeerManagerRequest<MemorySocket>>,
@@ -44,6 +57,8 @@ fn setup_conn_mgr(
                 .into_iter()
                 .collect(),
             )),
+            Arc::new(AtomicU64::new(0)),
+            peer_scores,
             ticker_rx,
             PeerManagerRequestSender::new(peer_mgr_reqs_tx),
             peer_mgr_notifs_rx,
@@ -365,7 +380,7 @@ fn disconnect() {
         // Send request to make seed peer ineligible.
         info!("Sending request to make seed peer ineligible");
         conn_mgr_reqs_tx
-            .send(ConnectivityRequest::UpdateEligibleNodes(HashMap::new()))
+            .send(ConnectivityRequest::UpdateEligibleNodes(0, HashMap::new()))
             .await
             .unwrap();
 
@@ -387,6 +402,67 @@ fn disconnect() {
     rt.block_on(events_f);
 }
 
+#[test]
+fn disconnect_banned_peer() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let seed_peer_id = PeerId::random();
+    info!("Seed peer_id is {}", seed_peer_id.short_str());
+    let peer_scores = PeerScores::default();
+    let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut conn_mgr_reqs_tx, mut ticker_tx) =
+        setup_conn_mgr_with_peer_scores(&mut rt, seed_peer_id, peer_scores.clone());
+
+    let events_f = async move {
+        let seed_address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+
+        // Send address of seed peer.
+        info!("Sending address of seed peer");
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                seed_peer_id,
+                vec![seed_address.clone()],
+            ))
+            .await
+            .unwrap();
+
+        // Trigger connectivity check.
+        info!("Sending tick to trigger connectivity check");
+        ticker_tx.send(()).await.unwrap();
+
+        // Peer manager receives a request to connect to the seed peer.
+        info!("Waiting to receive dial request");
+        expect_dial_request(
+            &mut peer_mgr_reqs_rx,
+            &mut peer_mgr_notifs_tx,
+            &mut conn_mgr_reqs_tx,
+            seed_peer_id,
+            seed_address.clone(),
+            Ok(()),
+        )
+        .await;
+
+        // The seed peer misbehaves until it gets banned.
+        info!("Banning seed peer");
+        while !peer_scores.penalize(seed_peer_id, Misbehavior::InvalidMessage) {}
+
+        // Trigger connectivity check.
+        info!("Sending tick to trigger connectivity check");
+        ticker_tx.send(()).await.unwrap();
+
+        // Peer manager receives a request to disconnect from the banned seed peer.
+        info!("Waiting to receive disconnect request");
+        expect_disconnect_request(
+            &mut peer_mgr_reqs_rx,
+            &mut peer_mgr_notifs_tx,
+            seed_peer_id,
+            seed_address.clone(),
+            Ok(()),
+        )
+        .await;
+    };
+    rt.block_on(events_f);
+}
+
 // Tests that connectivity manager retries dials and disconnects on failure.
 #[test]
 fn retry_on_failure() {
@@ -447,7 +523,7 @@ fn retry_on_failure() {
         // Send request to make seed peer ineligible.
         info!("Sending request to make seed peer ineligible");
         conn_mgr_reqs_tx
-            .send(ConnectivityRequest::UpdateEligibleNodes(HashMap::new()))
+            .send(ConnectivityRequest::UpdateEligibleNodes(0, HashMap::new()))
             .await
             .unwrap();
 
@@ -544,7 +620,7 @@ fn no_op_requests() {
         // Send request to make seed peer ineligible.
         info!("Sending request to make seed peer ineligible");
         conn_mgr_reqs_tx
-            .send(ConnectivityRequest::UpdateEligibleNodes(HashMap::new()))
+            .send(ConnectivityRequest::UpdateEligibleNodes(0, HashMap::new()))
             .await
             .unwrap();
 
@@ -599,6 +675,7 @@ fn backoff_on_failure() {
         info!("Sending list of eligible peers");
         conn_mgr_reqs_tx
             .send(ConnectivityRequest::UpdateEligibleNodes(
+                0,
                 [(peer_a, peer_a_keys), (peer_b, peer_b_keys)]
                     .iter()
                     .cloned()
@@ -894,3 +971,49 @@ fn multiple_addrs_shrinking() {
     };
     rt.block_on(f_peer_mgr);
 }
+
+// Test that each address of a peer keeps its own backoff, so that failing to connect
+// to one address does not delay the first dial to the peer's other addresses.
+#[test]
+fn per_address_backoff() {
+    let addr_1 = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9091").unwrap();
+    let addr_2 = Multiaddr::from_str("/ip6/::1/tcp/9092").unwrap();
+    let addrs = vec![addr_1.clone(), addr_2.clone()];
+    let max_delay = Duration::from_secs(10);
+    let mut dial_state = DialState::new(ExponentialBackoff::from_millis(10));
+
+    // Both addresses start with the initial backoff delay.
+    let addr = dial_state.next_addr(&addrs).clone();
+    assert_eq!(addr, addr_1);
+    assert_eq!(
+        dial_state.next_backoff_delay(&addr, max_delay),
+        Duration::from_millis(10)
+    );
+    let addr = dial_state.next_addr(&addrs).clone();
+    assert_eq!(addr, addr_2);
+    assert_eq!(
+        dial_state.next_backoff_delay(&addr, max_delay),
+        Duration::from_millis(10)
+    );
+
+    // Wrapping around, the first address continues from its own backoff.
+    let addr = dial_state.next_addr(&addrs).clone();
+    assert_eq!(addr, addr_1);
+    assert_eq!(
+        dial_state.next_backoff_delay(&addr, max_delay),
+        Duration::from_millis(100)
+    );
+
+    // Once the first address is no longer advertised, its backoff is forgotten.
+    let addrs = vec![addr_2.clone(), addr_1.clone()];
+    dial_state.reset_addrs(&addrs[..1]);
+    assert_eq!(dial_state.next_addr(&addrs), &addr_2);
+    assert_eq!(
+        dial_state.next_backoff_delay(&addr_1, max_delay),
+        Duration::from_millis(10)
+    );
+    assert_eq!(
+        dial_state.next_backoff_delay(&addr_2, max_delay),
+        Duration::from_millis(100)
+    );
+}
@@ -3,7 +3,7 @@
 
 use libra_metrics::{Histogram, IntGauge, OpMetrics};
 use once_cell::sync::Lazy;
-use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec};
+use prometheus::{HistogramVec, IntCounter, IntCounterVec, IntGaugeVec};
 
 pub static LIBRA_NETWORK_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -29,6 +29,50 @@ pub static LIBRA_NETWORK_DISCOVERY_NOTES: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static LIBRA_NETWORK_PEER_PENALTIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "libra_network_peer_penalties",
+        // metric description
+        "Libra network peer misbehavior penalties counter",
+        // metric labels (dimensions)
+        &["misbehavior"]
+    )
+    .unwrap()
+});
+
+pub static LIBRA_NETWORK_PEER_BANS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "libra_network_peer_bans",
+        "Libra network counter of peers banned for a low score"
+    )
+    .unwrap()
+});
+
+pub static LIBRA_NETWORK_PING_RTT: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "libra_network_ping_rtt_seconds",
+        // metric description
+        "Libra network health checker ping round trip time per peer",
+        // metric labels (dimensions)
+        &["peer_id"]
+    )
+    .unwrap()
+});
+
+pub static LIBRA_NETWORK_RATE_LIMITED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "libra_network_rate_limited_messages",
+        // metric description
+        "Libra network counter of direct-send messages dropped or delayed by rate limits",
+        // metric labels (dimensions)
+        &["protocol", "direction"]
+    )
+    .unwrap()
+});
+
 pub static LIBRA_NETWORK_RPC_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "libra_network_rpc_messages",
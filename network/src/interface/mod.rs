@@ -19,6 +19,7 @@ use crate::{
     protocols::{
         direct_send::{DirectSendNotification, DirectSendRequest, Message},
         rpc::{InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest},
+        streaming_rpc::{InboundStreamingRpcRequest, OutboundStreamingRpcRequest},
     },
     validator_network::{
         ConsensusNetworkEvents, ConsensusNetworkSender, DiscoveryNetworkEvents,
@@ -48,10 +49,12 @@ pub const DISCOVERY_INBOUND_MSG_TIMEOUT_MS: u64 = 10 * 1000; // 10 seconds.
 pub enum NetworkRequest {
     /// Send an RPC request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send a streaming RPC request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
     /// Fire-and-forget style message send to a remote peer.
     SendMessage(PeerId, Message),
-    /// Update set of nodes eligible to join the network.
-    UpdateEligibleNodes(HashMap<PeerId, NetworkPublicKeys>),
+    /// Update set of nodes eligible to join the network in the given consensus epoch.
+    UpdateEligibleNodes(u64, HashMap<PeerId, NetworkPublicKeys>),
     /// Dial the peer at the given `Multiaddr` to establish a connection. When
     /// the dial attempt succeeds or fails, the result will be returned over the
     /// oneshot channel.
@@ -77,6 +80,8 @@ pub enum NetworkNotification {
     LostPeer(PeerId),
     /// A new RPC request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new streaming RPC request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
     /// A new message has been received from a remote peer.
     RecvMessage(PeerId, Message),
 }
@@ -344,6 +349,12 @@ where
                     .await
                     .unwrap();
             }
+            NetworkRequest::SendStreamingRpc(peer_id, req) => {
+                rpc_reqs_tx
+                    .send(RpcRequest::SendStreamingRpc(peer_id, req))
+                    .await
+                    .unwrap();
+            }
             NetworkRequest::SendMessage(peer_id, msg) => {
                 counters::LIBRA_NETWORK_DIRECT_SEND_MESSAGES
                     .with_label_values(&["sent"])
@@ -356,11 +367,11 @@ where
                     .await
                     .unwrap();
             }
-            NetworkRequest::UpdateEligibleNodes(nodes) => {
+            NetworkRequest::UpdateEligibleNodes(epoch, nodes) => {
                 let mut conn_mgr_reqs_tx = conn_mgr_reqs_tx
                     .expect("Received request to update eligible nodes in network operating without remote authentication");
                 conn_mgr_reqs_tx
-                    .send(ConnectivityRequest::UpdateEligibleNodes(nodes))
+                    .send(ConnectivityRequest::UpdateEligibleNodes(epoch, nodes))
                     .await
                     .unwrap();
             }
@@ -420,6 +431,14 @@ where
                     unreachable!();
                 }
             }
+            RpcNotification::RecvStreamingRpc(peer_id, req) => {
+                let ch = upstream_handlers
+                    .get_mut(&req.protocol)
+                    .expect("Streaming rpc protocol not registered");
+                ch.send(NetworkNotification::RecvStreamingRpc(peer_id, req))
+                    .await
+                    .unwrap();
+            }
         }
     }
 
@@ -23,6 +23,8 @@ mod connectivity_manager;
 mod counters;
 mod error;
 mod peer_manager;
+mod peer_score;
+mod rate_limit;
 mod sink;
 mod transport;
 mod utils;
@@ -0,0 +1,71 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// A cloneable handle to the time of the last inbound traffic from each connected peer.
+///
+/// The PeerManager records every inbound substream, i.e., every inbound rpc request, and the
+/// DirectSend actor records every inbound message, so that the HealthChecker can treat
+/// application-level traffic as a sign of liveness.
+///
+/// Recording happens on the hot path of every inbound message, so each peer gets its own atomic
+/// timestamp: the map is only locked for writing when a peer is seen for the first time or
+/// removed.
+#[derive(Clone, Debug)]
+pub struct PeerActivity {
+    /// The instant timestamps are measured from.
+    start: Instant,
+    /// Milliseconds from `start` to the last inbound activity of each peer.
+    last_inbound: Arc<RwLock<HashMap<PeerId, Arc<AtomicU64>>>>,
+}
+
+impl Default for PeerActivity {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            last_inbound: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl PeerActivity {
+    /// Record inbound activity from `peer_id`.
+    pub fn record(&self, peer_id: PeerId) {
+        let now = self.start.elapsed().as_millis() as u64;
+        if let Some(last_inbound) = self.last_inbound.read().unwrap().get(&peer_id) {
+            last_inbound.store(now, Ordering::Relaxed);
+            return;
+        }
+        self.last_inbound
+            .write()
+            .unwrap()
+            .entry(peer_id)
+            .or_insert_with(|| Arc::new(AtomicU64::new(0)))
+            .store(now, Ordering::Relaxed);
+    }
+
+    /// The time of the last inbound activity from `peer_id`, if any.
+    pub fn last_inbound(&self, peer_id: &PeerId) -> Option<Instant> {
+        self.last_inbound
+            .read()
+            .unwrap()
+            .get(peer_id)
+            .map(|last_inbound| {
+                self.start + Duration::from_millis(last_inbound.load(Ordering::Relaxed))
+            })
+    }
+
+    /// Forget about `peer_id`, e.g., once we are no longer connected to it.
+    pub fn remove_peer(&self, peer_id: &PeerId) {
+        self.last_inbound.write().unwrap().remove(peer_id);
+    }
+}
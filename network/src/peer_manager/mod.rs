@@ -13,14 +13,15 @@
 //!  * An actor per Peer which owns the underlying connection and is responsible for listening for
 //!  and opening substreams as well as negotiating particular protocols on those substreams.
 use crate::{
-    common::NegotiatedSubstream, counters, protocols::identity::Identity, transport, ProtocolId,
+    common::NegotiatedSubstream, counters, peer_score::PeerScores, protocols::identity::Identity,
+    transport, ProtocolId,
 };
 use channel;
 use futures::{
     channel::oneshot,
     future::{BoxFuture, FutureExt},
     sink::SinkExt,
-    stream::{Fuse, FuturesUnordered, StreamExt},
+    stream::{select_all, Fuse, FuturesUnordered, SelectAll, StreamExt},
 };
 use libra_config::config::RoleType;
 use libra_logger::prelude::*;
@@ -34,11 +35,12 @@ use parity_multiaddr::Multiaddr;
 use std::{collections::HashMap, marker::PhantomData};
 use tokio::runtime::Handle;
 
+mod activity;
 mod error;
 #[cfg(test)]
 mod tests;
 
-pub use self::error::PeerManagerError;
+pub use self::{activity::PeerActivity, error::PeerManagerError};
 
 /// Notifications about new/lost peers.
 #[derive(Debug)]
@@ -138,9 +140,9 @@ where
     executor: Handle,
     /// PeerId of "self".
     own_peer_id: PeerId,
-    /// Address to listen on for incoming connections.
-    listen_addr: Multiaddr,
-    /// Connection Listener, listening on `listen_addr`
+    /// Addresses to listen on for incoming connections.
+    listen_addrs: Vec<Multiaddr>,
+    /// Connection Listener, listening on `listen_addrs`
     connection_handler: Option<ConnectionHandler<TTransport, TMuxer>>,
     /// Map from PeerId to corresponding Peer object.
     active_peers: HashMap<PeerId, PeerHandle<TMuxer::Substream>>,
@@ -160,6 +162,10 @@ where
     peer_notifs_tx: channel::Sender<PeerNotification<TMuxer::Substream>>,
     /// Receiver for peer events.
     peer_notifs_rx: channel::Receiver<PeerNotification<TMuxer::Substream>>,
+    /// Time of the last inbound substream from each connected peer.
+    peer_activity: PeerActivity,
+    /// Scores of remote peers. Inbound connections from banned peers are rejected.
+    peer_scores: PeerScores,
     /// A map of outstanding disconnect requests
     outstanding_disconnect_requests: HashMap<PeerId, oneshot::Sender<Result<(), PeerManagerError>>>,
     /// Pin the transport type corresponding to this PeerManager instance
@@ -176,13 +182,15 @@ where
         transport: TTransport,
         executor: Handle,
         own_peer_id: PeerId,
-        listen_addr: Multiaddr,
+        listen_addrs: Vec<Multiaddr>,
         requests_rx: channel::Receiver<PeerManagerRequest<TMuxer::Substream>>,
         protocol_handlers: HashMap<
             ProtocolId,
             channel::Sender<PeerManagerNotification<TMuxer::Substream>>,
         >,
         peer_event_handlers: Vec<channel::Sender<PeerManagerNotification<TMuxer::Substream>>>,
+        peer_activity: PeerActivity,
+        peer_scores: PeerScores,
     ) -> Self {
         let (connection_handler_notifs_tx, connection_handler_notifs_rx) =
             channel::new(1024, &counters::PENDING_CONNECTION_HANDLER_NOTIFICATIONS);
@@ -192,11 +200,11 @@ where
             channel::new(1024, &counters::PENDING_PEER_MANAGER_DIAL_REQUESTS);
         //TODO now that you can only listen on a socket inside of a tokio runtime we'll need to
         // rethink how we init the PeerManager so we don't have to do this funny thing.
-        let (connection_handler, listen_addr) =
+        let (connection_handler, listen_addrs) =
             futures::executor::block_on(executor.spawn(async move {
                 ConnectionHandler::new(
                     transport,
-                    listen_addr,
+                    listen_addrs,
                     dial_request_rx,
                     connection_handler_notifs_tx,
                 )
@@ -206,7 +214,7 @@ where
         Self {
             executor,
             own_peer_id,
-            listen_addr,
+            listen_addrs,
             connection_handler: Some(connection_handler),
             active_peers: HashMap::new(),
             requests_rx,
@@ -216,14 +224,16 @@ where
             connection_handler_notifs_rx,
             peer_notifs_rx,
             peer_notifs_tx,
+            peer_activity,
+            peer_scores,
             outstanding_disconnect_requests: HashMap::new(),
             phantom_transport: PhantomData,
         }
     }
 
-    /// Get the [`Multiaddr`] we're listening for incoming connections on
-    pub fn listen_addr(&self) -> &Multiaddr {
-        &self.listen_addr
+    /// Get the [`Multiaddr`]s we're listening for incoming connections on
+    pub fn listen_addrs(&self) -> &[Multiaddr] {
+        &self.listen_addrs
     }
 
     /// Start listening on the set address and return a future which runs PeerManager
@@ -262,6 +272,9 @@ where
         trace!("PeerEvent::{:?}", event);
         match event {
             PeerNotification::NewSubstream(peer_id, substream) => {
+                // Every inbound rpc request arrives on its own substream. DirectSend reuses its
+                // substreams and records each inbound message itself.
+                self.peer_activity.record(peer_id);
                 let ch = self
                     .protocol_handlers
                     .get_mut(&substream.protocol)
@@ -284,6 +297,7 @@ where
                     return;
                 }
                 info!("Disconnected from peer: {}", peer_id.short_str());
+                self.peer_activity.remove_peer(&peer_id);
                 if let Some(oneshot_tx) = self.outstanding_disconnect_requests.remove(&peer_id) {
                     if oneshot_tx.send(Ok(())).is_err() {
                         error!("oneshot channel receiver dropped");
@@ -404,6 +418,25 @@ where
         let role = identity.role();
         assert_ne!(self.own_peer_id, peer_id);
 
+        // We never dial banned peers, but they may still try to connect to us until their ban
+        // expires.
+        if origin == ConnectionOrigin::Inbound && self.peer_scores.is_banned(&peer_id) {
+            info!(
+                "Closing incoming connection with banned Peer {}",
+                peer_id.short_str()
+            );
+            if let Err(e) =
+                tokio::time::timeout(transport::TRANSPORT_TIMEOUT, connection.close()).await
+            {
+                error!(
+                    "Closing connection with Peer {} failed with error: {}",
+                    peer_id.short_str(),
+                    e
+                );
+            };
+            return;
+        }
+
         let mut send_new_peer_notification = true;
 
         // Check for and handle simultaneous dialing
@@ -535,7 +568,8 @@ where
 {
     /// [`Transport`] that is used to establish connections
     transport: TTransport,
-    listener: Fuse<TTransport::Listener>,
+    /// The listeners of all the listen addresses.
+    listener: Fuse<SelectAll<TTransport::Listener>>,
     dial_request_rx: channel::Receiver<ConnectionHandlerRequest>,
     connection_handler_notifs_tx: channel::Sender<ConnectionHandlerNotification<TMuxer>>,
 }
@@ -550,23 +584,29 @@ where
 {
     fn new(
         transport: TTransport,
-        listen_addr: Multiaddr,
+        listen_addrs: Vec<Multiaddr>,
         dial_request_rx: channel::Receiver<ConnectionHandlerRequest>,
         connection_handler_notifs_tx: channel::Sender<ConnectionHandlerNotification<TMuxer>>,
-    ) -> (Self, Multiaddr) {
-        let (listener, listen_addr) = transport
-            .listen_on(listen_addr)
-            .expect("Transport listen on fails");
-        debug!("listening on {:?}", listen_addr);
+    ) -> (Self, Vec<Multiaddr>) {
+        let (listeners, listen_addrs): (Vec<_>, Vec<_>) = listen_addrs
+            .into_iter()
+            .map(|listen_addr| {
+                let (listener, listen_addr) = transport
+                    .listen_on(listen_addr)
+                    .expect("Transport listen on fails");
+                debug!("listening on {:?}", listen_addr);
+                (listener, listen_addr)
+            })
+            .unzip();
 
         (
             Self {
                 transport,
-                listener: listener.fuse(),
+                listener: select_all(listeners).fuse(),
                 dial_request_rx,
                 connection_handler_notifs_tx,
             },
-            listen_addr,
+            listen_addrs,
         )
     }
 
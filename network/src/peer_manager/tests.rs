@@ -3,9 +3,10 @@
 
 use crate::{
     peer_manager::{
-        DisconnectReason, Peer, PeerHandle, PeerManager, PeerManagerNotification,
+        DisconnectReason, Peer, PeerActivity, PeerHandle, PeerManager, PeerManagerNotification,
         PeerManagerRequest, PeerNotification,
     },
+    peer_score::{Misbehavior, PeerScores},
     protocols::identity::{exchange_identity, Identity},
     ProtocolId,
 };
@@ -291,6 +292,21 @@ fn build_test_peer_manager(
     >,
     channel::Sender<PeerManagerRequest<impl AsyncRead + AsyncWrite>>,
     channel::Receiver<PeerManagerNotification<impl AsyncRead + AsyncWrite>>,
+) {
+    build_test_peer_manager_with_scores(executor, peer_id, PeerScores::default())
+}
+
+fn build_test_peer_manager_with_scores(
+    executor: Handle,
+    peer_id: PeerId,
+    peer_scores: PeerScores,
+) -> (
+    PeerManager<
+        BoxedTransport<(Identity, Yamux<MemorySocket>), impl std::error::Error>,
+        Yamux<MemorySocket>,
+    >,
+    channel::Sender<PeerManagerRequest<impl AsyncRead + AsyncWrite>>,
+    channel::Receiver<PeerManagerNotification<impl AsyncRead + AsyncWrite>>,
 ) {
     let protocol = ProtocolId::from_static(HELLO_PROTOCOL);
     let (peer_manager_request_tx, peer_manager_request_rx) = channel::new_test(0);
@@ -302,10 +318,12 @@ fn build_test_peer_manager(
         build_test_transport(Identity::new(peer_id, vec![], RoleType::Validator)),
         executor,
         peer_id,
-        "/memory/0".parse().unwrap(),
+        vec!["/memory/0".parse().unwrap()],
         peer_manager_request_rx,
         protocol_handlers,
         Vec::new(),
+        PeerActivity::default(),
+        peer_scores,
     );
 
     (peer_manager, peer_manager_request_tx, hello_rx)
@@ -730,3 +748,33 @@ fn peer_manager_simultaneous_dial_disconnect_event() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn peer_manager_rejects_banned_inbound_peer() {
+    let mut runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let peer_scores = PeerScores::default();
+    let (mut peer_manager, _request_tx, _hello_rx) =
+        build_test_peer_manager_with_scores(runtime.handle().clone(), ids[1], peer_scores.clone());
+    // Two invalid messages are enough to cross the default ban threshold.
+    assert!(!peer_scores.penalize(ids[0], Misbehavior::InvalidMessage));
+    assert!(peer_scores.penalize(ids[0], Misbehavior::InvalidMessage));
+
+    let test = async move {
+        let (outbound, inbound) = build_test_connection();
+        peer_manager
+            .add_peer(
+                build_test_identity(ids[0]),
+                Multiaddr::empty(),
+                ConnectionOrigin::Inbound,
+                inbound,
+            )
+            .await;
+
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+        assert!(open_hello_substream(&outbound).await.is_err());
+    };
+
+    runtime.block_on(test);
+}
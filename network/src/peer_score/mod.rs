@@ -0,0 +1,178 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scores of remote peers, shared by the network actors.
+//!
+//! Discovery, the HealthChecker and the Rpc actor report misbehaving peers to a shared
+//! [`PeerScores`] handle: every [`Misbehavior`] lowers the peer's score by a fixed penalty, and
+//! scores slowly recover over time so that rare failures are eventually forgiven. Once a peer's
+//! score drops to the ban threshold, the peer is banned for a cool-down period. While a peer is
+//! banned, the [`ConnectivityManager`] disconnects from it and does not dial it, and the
+//! [`PeerManager`] rejects its inbound connections. When the ban expires, the peer starts over
+//! with a clean score. The penalties and thresholds are set by the node's [`PeerScoreConfig`].
+//!
+//! [`ConnectivityManager`]: ../connectivity_manager
+//! [`PeerManager`]: ../peer_manager
+use crate::counters;
+use libra_config::config::PeerScoreConfig;
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The kinds of misbehavior a peer is penalized for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// The peer sent a message which failed to parse or verify.
+    InvalidMessage,
+    /// The peer failed to respond to a liveness probe.
+    PingFailure,
+    /// The peer failed to respond to an rpc request in time.
+    RpcTimeout,
+}
+
+impl Misbehavior {
+    fn as_str(self) -> &'static str {
+        match self {
+            Misbehavior::InvalidMessage => "invalid_message",
+            Misbehavior::PingFailure => "ping_failure",
+            Misbehavior::RpcTimeout => "rpc_timeout",
+        }
+    }
+}
+
+fn penalty(config: &PeerScoreConfig, misbehavior: Misbehavior) -> f64 {
+    match misbehavior {
+        Misbehavior::InvalidMessage => config.invalid_message_penalty,
+        Misbehavior::PingFailure => config.ping_failure_penalty,
+        Misbehavior::RpcTimeout => config.rpc_timeout_penalty,
+    }
+}
+
+#[derive(Debug)]
+struct PeerScore {
+    /// The score as of `last_update`. Scores are never positive.
+    score: f64,
+    last_update: Instant,
+    /// Set while the peer is banned.
+    banned_until: Option<Instant>,
+}
+
+impl PeerScore {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: 0.0,
+            last_update: now,
+            banned_until: None,
+        }
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.map_or(false, |until| now < until)
+    }
+
+    fn current_score(&self, config: &PeerScoreConfig, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        (self.score + config.recovery_per_sec * elapsed.as_secs_f64()).min(0.0)
+    }
+}
+
+/// A cloneable handle to the scores of all remote peers.
+#[derive(Clone, Debug)]
+pub struct PeerScores {
+    config: PeerScoreConfig,
+    scores: Arc<RwLock<HashMap<PeerId, PeerScore>>>,
+}
+
+impl Default for PeerScores {
+    fn default() -> Self {
+        Self::new(PeerScoreConfig::default())
+    }
+}
+
+impl PeerScores {
+    pub fn new(config: PeerScoreConfig) -> Self {
+        Self {
+            config,
+            scores: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Penalize `peer_id` for `misbehavior`. Returns true if the peer got banned as a result.
+    pub fn penalize(&self, peer_id: PeerId, misbehavior: Misbehavior) -> bool {
+        self.penalize_at(peer_id, misbehavior, Instant::now())
+    }
+
+    /// The current score of `peer_id`. Well-behaved and unknown peers have a score of zero.
+    pub fn score(&self, peer_id: &PeerId) -> f64 {
+        self.score_at(peer_id, Instant::now())
+    }
+
+    /// Whether `peer_id` is currently banned.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.is_banned_at(peer_id, Instant::now())
+    }
+
+    fn penalize_at(&self, peer_id: PeerId, misbehavior: Misbehavior, now: Instant) -> bool {
+        counters::LIBRA_NETWORK_PEER_PENALTIES
+            .with_label_values(&[misbehavior.as_str()])
+            .inc();
+        let mut scores = self.scores.write().unwrap();
+        let peer_score = scores.entry(peer_id).or_insert_with(|| PeerScore::new(now));
+        if peer_score.is_banned(now) {
+            // The peer is already serving its ban.
+            return false;
+        }
+        if peer_score.banned_until.is_some() {
+            // The previous ban expired; start over with a clean score.
+            *peer_score = PeerScore::new(now);
+        }
+
+        peer_score.score =
+            peer_score.current_score(&self.config, now) - penalty(&self.config, misbehavior);
+        peer_score.last_update = now;
+        debug!(
+            "Penalized peer: {} for {:?}, score: {}",
+            peer_id.short_str(),
+            misbehavior,
+            peer_score.score
+        );
+        if peer_score.score > self.config.ban_threshold {
+            return false;
+        }
+
+        let ban_duration = Duration::from_millis(self.config.ban_duration_ms);
+        warn!(
+            "Banning peer: {} for {:?}, score: {}",
+            peer_id.short_str(),
+            ban_duration,
+            peer_score.score
+        );
+        counters::LIBRA_NETWORK_PEER_BANS.inc();
+        peer_score.banned_until = Some(now + ban_duration);
+        true
+    }
+
+    fn score_at(&self, peer_id: &PeerId, now: Instant) -> f64 {
+        match self.scores.read().unwrap().get(peer_id) {
+            Some(peer_score) if peer_score.banned_until.map_or(true, |until| now < until) => {
+                peer_score.current_score(&self.config, now)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn is_banned_at(&self, peer_id: &PeerId, now: Instant) -> bool {
+        self.scores
+            .read()
+            .unwrap()
+            .get(peer_id)
+            .map_or(false, |peer_score| peer_score.is_banned(now))
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+fn test_config() -> PeerScoreConfig {
+    PeerScoreConfig {
+        invalid_message_penalty: 50.0,
+        ping_failure_penalty: 10.0,
+        rpc_timeout_penalty: 5.0,
+        recovery_per_sec: 1.0,
+        ban_threshold: -100.0,
+        ban_duration_ms: 60_000,
+    }
+}
+
+#[test]
+fn penalties_lower_score() {
+    let scores = PeerScores::new(test_config());
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert_eq!(scores.score_at(&peer_id, now), 0.0);
+    assert!(!scores.penalize_at(peer_id, Misbehavior::PingFailure, now));
+    assert!(!scores.penalize_at(peer_id, Misbehavior::RpcTimeout, now));
+    assert_eq!(scores.score_at(&peer_id, now), -15.0);
+
+    // Other peers are unaffected.
+    assert_eq!(scores.score_at(&PeerId::random(), now), 0.0);
+}
+
+#[test]
+fn scores_recover_over_time() {
+    let scores = PeerScores::new(test_config());
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    scores.penalize_at(peer_id, Misbehavior::InvalidMessage, now);
+    assert_eq!(
+        scores.score_at(&peer_id, now + Duration::from_secs(20)),
+        -30.0
+    );
+    // Scores never recover above zero.
+    assert_eq!(
+        scores.score_at(&peer_id, now + Duration::from_secs(100)),
+        0.0
+    );
+}
+
+#[test]
+fn ban_on_low_score() {
+    let scores = PeerScores::new(test_config());
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert!(!scores.penalize_at(peer_id, Misbehavior::InvalidMessage, now));
+    assert!(!scores.is_banned_at(&peer_id, now));
+    assert!(scores.penalize_at(peer_id, Misbehavior::InvalidMessage, now));
+    assert!(scores.is_banned_at(&peer_id, now));
+
+    // Further misbehavior during the ban does not extend it.
+    let later = now + Duration::from_secs(30);
+    assert!(!scores.penalize_at(peer_id, Misbehavior::InvalidMessage, later));
+    assert!(scores.is_banned_at(&peer_id, later));
+
+    // Once the ban expires, the peer starts over with a clean score.
+    let expired = now + Duration::from_secs(60);
+    assert!(!scores.is_banned_at(&peer_id, expired));
+    assert_eq!(scores.score_at(&peer_id, expired), 0.0);
+    assert!(!scores.penalize_at(peer_id, Misbehavior::PingFailure, expired));
+    assert_eq!(scores.score_at(&peer_id, expired), -10.0);
+}
+
+#[test]
+fn clones_share_scores() {
+    let scores = PeerScores::new(test_config());
+    let other = scores.clone();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    other.penalize_at(peer_id, Misbehavior::InvalidMessage, now);
+    other.penalize_at(peer_id, Misbehavior::InvalidMessage, now);
+    assert!(scores.is_banned_at(&peer_id, now));
+}
@@ -31,7 +31,7 @@ pub use self::{
     health_checker::{
         health_checker_msg::Message as HealthCheckerMsg_oneof, HealthCheckerMsg, Ping, Pong,
     },
-    mempool::MempoolSyncMsg,
+    mempool::{BroadcastAck, MempoolSyncMsg, TxnPointer},
     network::{
         identity_msg::Role as IdentityMsg_Role, DiscoveryMsg, FullNodePayload, IdentityMsg, Note,
         PeerInfo, SignedFullNodePayload, SignedPeerInfo,
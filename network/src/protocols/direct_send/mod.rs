@@ -30,6 +30,12 @@
 //! 3. Sends the serialized message on the newly negotiated substream.
 //! 4. Drops the substream.
 //!
+//! ## Rate limits
+//!
+//! Protocols can be rate limited per peer. Inbound messages exceeding the limits of the sending
+//! peer are dropped, whereas outbound messages wait for the limits to allow them, which backs up
+//! the outbound message queue of the peer. See the [`rate_limit`] module.
+//!
 //! ## Wire Protocol (listener):
 //!
 //! To receive a message from remote peers, the listener
@@ -43,17 +49,21 @@
 //! [muxers]: ../../../netcore/multiplexing/index.html
 //! [substream negotiation]: ../../../netcore/negotiate/index.html
 //! [`protocol-select`]: ../../../netcore/negotiate/index.html
+//! [`rate_limit`]: ../../rate_limit/index.html
 use crate::{
     counters,
     error::NetworkError,
-    peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_manager::{PeerActivity, PeerManagerNotification, PeerManagerRequestSender},
+    rate_limit::{InboundRateLimiters, RateLimiter, RateLimits},
+    sink::NetworkSinkExt,
     ProtocolId,
 };
 use bytes::Bytes;
 use channel;
 use futures::{
+    future::FutureExt,
     io::{AsyncRead, AsyncWrite},
-    sink::SinkExt,
+    sink::{Sink, SinkExt},
     stream::StreamExt,
 };
 use libra_logger::prelude::*;
@@ -62,8 +72,9 @@ use netcore::compat::IoCompat;
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::Debug,
+    time::Duration,
 };
-use tokio::runtime::Handle;
+use tokio::{runtime::Handle, time::delay_for};
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 #[cfg(test)]
@@ -118,6 +129,12 @@ pub struct DirectSend<TSubstream> {
     peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
     /// Outbound message queues for each (PeerId, ProtocolId) pair.
     message_queues: HashMap<(PeerId, ProtocolId), channel::Sender<Bytes>>,
+    /// Time of the last inbound traffic from each peer, updated on every received message.
+    peer_activity: PeerActivity,
+    /// Rate limits of the direct-send protocols.
+    rate_limits: RateLimits,
+    /// Limiters of the inbound messages of each peer, shared by all its inbound substreams.
+    inbound_rate_limiters: InboundRateLimiters,
 }
 
 impl<TSubstream> DirectSend<TSubstream>
@@ -130,6 +147,8 @@ where
         ds_notifs_tx: channel::Sender<DirectSendNotification>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        peer_activity: PeerActivity,
+        rate_limits: RateLimits,
     ) -> Self {
         Self {
             executor,
@@ -138,6 +157,9 @@ where
             peer_mgr_notifs_rx,
             peer_mgr_reqs_tx,
             message_queues: HashMap::new(),
+            peer_activity,
+            inbound_rate_limiters: InboundRateLimiters::new(rate_limits.clone()),
+            rate_limits,
         }
     }
 
@@ -158,7 +180,8 @@ where
         }
     }
 
-    // Handle PeerManagerNotification, which can only be NewInboundSubstream for now.
+    // Handle PeerManagerNotification. Inbound substreams are served on their own task, while the
+    // inbound rate limiters of lost peers are dropped.
     fn handle_peer_mgr_notification(&self, notif: PeerManagerNotification<TSubstream>) {
         trace!("PeerManagerNotification::{:?}", notif);
         match notif {
@@ -168,9 +191,14 @@ where
                     substream.protocol,
                     substream.substream,
                     self.ds_notifs_tx.clone(),
+                    self.peer_activity.clone(),
+                    self.inbound_rate_limiters.clone(),
                 ));
             }
-            _ => unreachable!("Unexpected PeerManagerNotification"),
+            PeerManagerNotification::LostPeer(peer_id, _) => {
+                self.inbound_rate_limiters.remove_peer(&peer_id);
+            }
+            PeerManagerNotification::NewPeer(_, _) => {}
         }
     }
 
@@ -180,11 +208,22 @@ where
         protocol: ProtocolId,
         substream: TSubstream,
         mut ds_notifs_tx: channel::Sender<DirectSendNotification>,
+        peer_activity: PeerActivity,
+        inbound_rate_limiters: InboundRateLimiters,
     ) {
         let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
         while let Some(item) = substream.next().await {
             match item {
                 Ok(data) => {
+                    if !inbound_rate_limiters.allow(peer_id, &protocol, data.len()) {
+                        debug!(
+                            "Dropping DirectSend message from peer {} exceeding rate limits",
+                            peer_id.short_str()
+                        );
+                        continue;
+                    }
+                    // Only messages within the limits show that the peer is healthy.
+                    peer_activity.record(peer_id);
                     let notif = DirectSendNotification::RecvMessage(
                         peer_id,
                         Message {
@@ -220,6 +259,7 @@ where
         mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_id: PeerId,
         protocol: ProtocolId,
+        rate_limiter: Option<RateLimiter>,
     ) -> Result<channel::Sender<Bytes>, NetworkError> {
         // Create a channel for the (PeerId, ProtocolId) pair.
         let (msg_tx, msg_rx) = channel::new::<Bytes>(
@@ -231,12 +271,14 @@ where
         );
 
         // Open a new substream for the (PeerId, ProtocolId) pair
-        let raw_substream = peer_mgr_reqs_tx.open_substream(peer_id, protocol).await?;
+        let raw_substream = peer_mgr_reqs_tx
+            .open_substream(peer_id, protocol.clone())
+            .await?;
         let substream = Framed::new(IoCompat::new(raw_substream), LengthDelimitedCodec::new());
 
         // Spawn a task to forward the messages from the queue to the substream.
         let f_substream = async move {
-            if let Err(e) = msg_rx.map(Ok).forward(substream).await {
+            if let Err(e) = forward_messages(msg_rx, substream, rate_limiter, &protocol).await {
                 warn!(
                     "Forward messages to peer {} error {:?}",
                     peer_id.short_str(),
@@ -277,6 +319,7 @@ where
                     peer_mgr_reqs_tx,
                     peer_id,
                     protocol.clone(),
+                    self.rate_limits.outbound_limiter(&protocol),
                 )
                 .await?;
                 entry.insert(msg_tx)
@@ -311,3 +354,39 @@ where
         }
     }
 }
+
+// Forward the messages from the queue to the substream, waiting for the rate limiter, if any, to
+// allow each message. Messages are written out in batches: the substream is only flushed once the
+// queue is drained, or before waiting for the rate limiter.
+async fn forward_messages<TSink>(
+    mut msg_rx: channel::Receiver<Bytes>,
+    mut substream: TSink,
+    mut rate_limiter: Option<RateLimiter>,
+    protocol: &ProtocolId,
+) -> Result<(), TSink::Error>
+where
+    TSink: Sink<Bytes> + Unpin,
+{
+    let mut next_msg = msg_rx.next().await;
+    while let Some(msg) = next_msg {
+        if let Some(rate_limiter) = rate_limiter.as_mut() {
+            let delay = rate_limiter.reserve(msg.len());
+            if delay > Duration::from_secs(0) {
+                counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+                    .with_label_values(&[&*String::from_utf8_lossy(protocol), "outbound"])
+                    .inc();
+                substream.flush().await?;
+                delay_for(delay).await;
+            }
+        }
+        substream.buffered_send(msg).await?;
+        next_msg = match msg_rx.next().now_or_never() {
+            Some(next_msg) => next_msg,
+            None => {
+                substream.flush().await?;
+                msg_rx.next().await
+            }
+        };
+    }
+    substream.close().await
+}
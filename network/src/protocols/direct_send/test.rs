@@ -4,18 +4,28 @@
 use crate::{
     common::NegotiatedSubstream,
     peer_manager::{
-        PeerManagerError, PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        PeerActivity, PeerManagerError, PeerManagerNotification, PeerManagerRequest,
+        PeerManagerRequestSender,
     },
     protocols::direct_send::{DirectSend, DirectSendNotification, DirectSendRequest, Message},
+    rate_limit::RateLimits,
     ProtocolId,
 };
 use bytes::Bytes;
 use channel;
 use futures::{sink::SinkExt, stream::StreamExt};
+use libra_config::config::{RateLimitConfig, TokenBucketConfig};
 use libra_types::PeerId;
 use memsocket::MemorySocket;
 use netcore::compat::IoCompat;
-use tokio::runtime::{Handle, Runtime};
+use std::{
+    str,
+    time::{Duration, Instant},
+};
+use tokio::{
+    runtime::{Handle, Runtime},
+    time::delay_for,
+};
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 const PROTOCOL_1: &[u8] = b"/direct_send/1.0.0";
@@ -31,6 +41,18 @@ fn start_direct_send_actor(
     channel::Receiver<DirectSendNotification>,
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Receiver<PeerManagerRequest<MemorySocket>>,
+) {
+    start_direct_send_actor_with_rate_limits(executor, RateLimits::default())
+}
+
+fn start_direct_send_actor_with_rate_limits(
+    executor: Handle,
+    rate_limits: RateLimits,
+) -> (
+    channel::Sender<DirectSendRequest>,
+    channel::Receiver<DirectSendNotification>,
+    channel::Sender<PeerManagerNotification<MemorySocket>>,
+    channel::Receiver<PeerManagerRequest<MemorySocket>>,
 ) {
     let (ds_requests_tx, ds_requests_rx) = channel::new_test(8);
     let (ds_notifs_tx, ds_notifs_rx) = channel::new_test(8);
@@ -42,6 +64,8 @@ fn start_direct_send_actor(
         ds_notifs_tx,
         peer_mgr_notifs_rx,
         PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+        PeerActivity::default(),
+        rate_limits,
     );
     executor.spawn(direct_send.start());
 
@@ -509,3 +533,120 @@ fn test_outbound_connection_closed() {
     };
     rt.block_on(f_second_substream);
 }
+
+fn protocol_1_rate_limits(messages_per_sec: u64) -> RateLimits {
+    RateLimits::new(vec![RateLimitConfig::new(
+        str::from_utf8(PROTOCOL_1).unwrap(),
+        TokenBucketConfig::new(messages_per_sec, 0),
+    )])
+}
+
+#[test]
+fn test_inbound_rate_limit() {
+    let mut rt = Runtime::new().unwrap();
+
+    let (_ds_requests_tx, mut ds_notifs_rx, mut peer_mgr_notifs_tx, _peer_mgr_reqs_rx) =
+        start_direct_send_actor_with_rate_limits(rt.handle().clone(), protocol_1_rate_limits(2));
+
+    let peer_id = PeerId::random();
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // The dialer sends three messages in a burst, which exceeds the limit of two messages per
+    // second, and another message once the limit allows it again.
+    let f_substream = async move {
+        let mut dialer_substream =
+            Framed::new(IoCompat::new(dialer_substream), LengthDelimitedCodec::new());
+        for msg in &[MESSAGE_1, MESSAGE_2, MESSAGE_2] {
+            dialer_substream
+                .send(Bytes::from_static(*msg))
+                .await
+                .unwrap();
+        }
+        delay_for(Duration::from_millis(600)).await;
+        dialer_substream
+            .send(Bytes::from_static(MESSAGE_3))
+            .await
+            .unwrap();
+    };
+
+    // Fake the listener NetworkProvider to notify DirectSend of the inbound substream.
+    let f_network_provider = async move {
+        peer_mgr_notifs_tx
+            .send(PeerManagerNotification::NewInboundSubstream(
+                peer_id,
+                NegotiatedSubstream {
+                    protocol: ProtocolId::from_static(&PROTOCOL_1[..]),
+                    substream: listener_substream,
+                },
+            ))
+            .await
+            .unwrap();
+
+        // The third message of the burst is dropped.
+        expect_network_provider_recv_message(&mut ds_notifs_rx, peer_id, PROTOCOL_1, MESSAGE_1)
+            .await;
+        expect_network_provider_recv_message(&mut ds_notifs_rx, peer_id, PROTOCOL_1, MESSAGE_2)
+            .await;
+        expect_network_provider_recv_message(&mut ds_notifs_rx, peer_id, PROTOCOL_1, MESSAGE_3)
+            .await;
+    };
+
+    rt.spawn(f_substream);
+    rt.block_on(f_network_provider);
+}
+
+#[test]
+fn test_outbound_rate_limit() {
+    let mut rt = Runtime::new().unwrap();
+
+    let (mut ds_requests_tx, _ds_notifs_rx, _peer_mgr_notifs_tx, mut peer_mgr_reqs_rx) =
+        start_direct_send_actor_with_rate_limits(rt.handle().clone(), protocol_1_rate_limits(2));
+
+    let peer_id = PeerId::random();
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let start = Instant::now();
+
+    // Fake the dialer NetworkProvider
+    let f_network_provider = async move {
+        // Send 3 messages in a burst, which exceeds the limit of two messages per second.
+        for msg in &[MESSAGE_1, MESSAGE_2, MESSAGE_3] {
+            ds_requests_tx
+                .send(DirectSendRequest::SendMessage(
+                    peer_id,
+                    Message {
+                        protocol: Bytes::from_static(&PROTOCOL_1[..]),
+                        mdata: Bytes::from_static(*msg),
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+
+        expect_open_substream_request(
+            &mut peer_mgr_reqs_rx,
+            peer_id,
+            PROTOCOL_1,
+            Ok(dialer_substream),
+        )
+        .await;
+    };
+
+    // The listener should receive all three messages, but the last one is held back until the
+    // limit allows it.
+    let f_substream = async move {
+        let mut listener_substream = Framed::new(
+            IoCompat::new(listener_substream),
+            LengthDelimitedCodec::new(),
+        );
+        let msg = listener_substream.next().await.unwrap().unwrap();
+        assert_eq!(msg.as_ref(), MESSAGE_1);
+        let msg = listener_substream.next().await.unwrap().unwrap();
+        assert_eq!(msg.as_ref(), MESSAGE_2);
+        let msg = listener_substream.next().await.unwrap().unwrap();
+        assert_eq!(msg.as_ref(), MESSAGE_3);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    };
+
+    rt.spawn(f_network_provider);
+    rt.block_on(f_substream);
+}
@@ -20,23 +20,45 @@
 //! Currently we do not use this mechanism to detect peer failures - instead, we simply connect to
 //! all the peers in the network, and hope to learn about their failure on connection errors.
 //!
-//! TODO: We need to handle to case of peers who may no longer be a part of the network.
+//! ## Byzantine peers
+//!
+//! Every note is signed by the network signing key of the peer it describes, which never leaves
+//! the secure storage backing the discovery actor unless QUIC is enabled: QUIC authenticates
+//! peers with the same key, and the TLS stack needs its own copy of it. A note is only accepted
+//! for peers in the trusted peer set, which for validators mirrors the on-chain validator set of
+//! the current epoch. Notes of peers outside that set, e.g. validators which left in a
+//! reconfiguration, are dropped, and the notes we already know for such peers are forgotten so
+//! that we stop gossiping them. Every note is also bound to the consensus epoch of the trusted peer
+//! set it was signed in: notes from other consensus epochs are dropped, the notes we know from
+//! past epochs are forgotten, and we re-sign our own note when the epoch changes. A peer thus
+//! cannot lock in an advertisement beyond the end of the current epoch, however far in the future
+//! the incarnation number of its note lies. The consensus epoch of a note is checked before its
+//! signature, so that the note of an honest peer which already signs with its key of another epoch
+//! is dropped without rejecting the message it came in.
+//!
+//! A peer relaying a message with a note which fails to parse or verify is penalized in the
+//! shared [`PeerScores`], which eventually gets it banned by the [`ConnectivityManager`].
 //!
 //! ## Future work
 //!
 //! - Currently, we do not try to detect/punish nodes which are just lurking (without contributing
-//! to the protocol), or actively trying to spread misinformation in the network. In the future, we
-//! plan to remedy this by introducing a module dedicated to detecting byzantine behavior, and by
-//! making the discovery protocol itself tolerant to byzantine faults.
+//! to the protocol).
+//! - The addresses of the notes are not checked against the on-chain `DiscoverySet`, which isn't
+//! delivered on reconfiguration yet: only the signing keys of the peers are, through the trusted
+//! peer set.
 //!
 //! [`ConnectivityManager`]: ../../connectivity_manager
+//! [`PeerScores`]: ../../peer_score
 use crate::{
     connectivity_manager::ConnectivityRequest,
     counters,
     error::{NetworkError, NetworkErrorKind},
+    peer_score::{Misbehavior, PeerScores},
     proto::{DiscoveryMsg, FullNodePayload, Note, PeerInfo, SignedFullNodePayload, SignedPeerInfo},
     utils::MessageExt,
-    validator_network::{DiscoveryNetworkEvents, DiscoveryNetworkSender, Event},
+    validator_network::{
+        DiscoveryNetworkEvents, DiscoveryNetworkSender, Event, DISCOVERY_SIGNING_KEY,
+    },
     NetworkPublicKeys,
 };
 use anyhow::anyhow;
@@ -53,7 +75,8 @@ use libra_crypto::{
     HashValue, Signature,
 };
 use libra_logger::prelude::*;
-use libra_types::{crypto_proxies::ValidatorSigner as Signer, PeerId};
+use libra_secure_storage::CryptoStorage;
+use libra_types::PeerId;
 use parity_multiaddr::Multiaddr;
 use prost::Message;
 use rand::{rngs::SmallRng, FromEntropy, Rng};
@@ -61,7 +84,10 @@ use std::pin::Pin;
 use std::{
     collections::{HashMap, HashSet},
     convert::{TryFrom, TryInto},
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
     time::{Duration, SystemTime},
 };
 
@@ -70,15 +96,20 @@ mod test;
 
 /// The actor running the discovery protocol.
 pub struct Discovery<TTicker> {
-    /// Note for self, which is prefixed with an underscore as this is not used but is in
-    /// preparation for logic that changes the advertised Note while the validator is running.
-    _note: Note,
     /// PeerId for self.
     peer_id: PeerId,
     /// Our node type.
     role: RoleType,
+    /// Addresses advertised in the note for self.
+    addrs: Vec<Multiaddr>,
+    /// Storage holding the key which signs the note for self.
+    signer: Box<dyn CryptoStorage>,
     /// Validator for verifying signatures on messages.
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    /// The consensus epoch of `trusted_peers`, which all notes are bound to.
+    consensus_epoch: Arc<AtomicU64>,
+    /// Scores of remote peers, lowered when they relay invalid notes.
+    peer_scores: PeerScores,
     /// Current state, maintaining the most recent Note for each peer, alongside parsed PeerInfo.
     known_peers: HashMap<PeerId, VerifiedNote>,
     /// Info for seed peers.
@@ -108,44 +139,34 @@ where
         self_peer_id: PeerId,
         role: RoleType,
         self_addrs: Vec<Multiaddr>,
-        signer: Signer,
+        signer: Box<dyn CryptoStorage>,
         seed_peers: HashMap<PeerId, PeerInfo>,
         trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+        consensus_epoch: Arc<AtomicU64>,
+        peer_scores: PeerScores,
         ticker: TTicker,
         network_reqs_tx: DiscoveryNetworkSender,
         network_notifs_rx: DiscoveryNetworkEvents,
         conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
         msg_timeout: Duration,
     ) -> Self {
-        // TODO(philiphayes): wire through config
-        let dns_seed_addr = b"example.com";
-
-        let self_peer_info = create_peer_info(self_addrs.clone());
-        let self_full_node_payload = create_full_node_payload(dns_seed_addr);
-        let self_note = create_note(
-            &signer,
+        let self_note = create_self_note(
+            signer.as_ref(),
             self_peer_id,
-            self_peer_info.clone(),
-            self_full_node_payload,
+            self_addrs.clone(),
+            consensus_epoch.load(Ordering::Relaxed),
         );
-        // We don't verify the self note because trusted_peers may not be populated yet
-        let self_verified_note = VerifiedNote {
-            peer_id: self_peer_id,
-            addrs: self_addrs,
-            epoch: self_peer_info.epoch,
-            raw_note: self_note.clone(),
-        };
-
-        let known_peers = vec![(self_peer_id, self_verified_note)]
-            .into_iter()
-            .collect();
+        let known_peers = vec![(self_peer_id, self_note)].into_iter().collect();
 
         Self {
-            _note: self_note,
             peer_id: self_peer_id,
             role,
+            addrs: self_addrs,
+            signer,
             seed_peers,
             trusted_peers,
+            consensus_epoch,
+            peer_scores,
             known_peers,
             connected_peers: HashSet::new(),
             ticker,
@@ -212,13 +233,18 @@ where
     }
 
     // Handles a clock "tick" by:
-    // 1. Selecting a random peer to send state to.
-    // 2. Compose the msg to send.
-    // 3. Spawn off a new task to push the msg to the peer.
+    // 1. Re-signing our own note if the consensus epoch changed.
+    // 2. Forgetting peers which are no longer trusted, or whose notes are from past epochs.
+    // 3. Selecting a random peer to send state to.
+    // 4. Compose the msg to send.
+    // 5. Spawn off a new task to push the msg to the peer.
     fn handle_tick<'a>(
         &'a mut self,
         unprocessed_outbound: &'a mut FuturesUnordered<Pin<Box<dyn Future<Output = ()> + Send>>>,
     ) {
+        let consensus_epoch = self.consensus_epoch.load(Ordering::Relaxed);
+        self.refresh_self_note(consensus_epoch);
+        self.remove_untrusted_peers(consensus_epoch);
         // On each tick, we choose a random neighbor and push our state to it.
         if let Some(peer) = self.choose_random_neighbor() {
             // We clone `peer_mgr_reqs_tx` member of Self, since using `self` inside fut below
@@ -257,7 +283,13 @@ where
                         self.connected_peers.remove(&peer_id);
                     }
                     Event::Message((peer_id, msg)) => {
-                        match handle_discovery_msg(msg, self.trusted_peers.clone(), peer_id) {
+                        let consensus_epoch = self.consensus_epoch.load(Ordering::Relaxed);
+                        match handle_discovery_msg(
+                            msg,
+                            self.trusted_peers.clone(),
+                            consensus_epoch,
+                            peer_id,
+                        ) {
                             Ok(verified_notes) => {
                                 self.reconcile(peer_id, verified_notes).await;
                                 self.record_num_discovery_notes();
@@ -268,6 +300,8 @@ where
                                     peer_id.short_str(),
                                     e
                                 );
+                                self.peer_scores
+                                    .penalize(peer_id, Misbehavior::InvalidMessage);
                             }
                         }
                     }
@@ -275,6 +309,10 @@ where
                         warn!("Unexpected notification from network: {:?}", req);
                         debug_assert!(false);
                     }
+                    Event::StreamingRpcRequest(req) => {
+                        warn!("Unexpected notification from network: {:?}", req);
+                        debug_assert!(false);
+                    }
                 }
             }
             Err(err) => {
@@ -283,6 +321,55 @@ where
         }
     }
 
+    // Signs a new note for self if ours was signed in a different consensus epoch.
+    fn refresh_self_note(&mut self, consensus_epoch: u64) {
+        let self_note = &self.known_peers[&self.peer_id];
+        if self_note.consensus_epoch == consensus_epoch {
+            return;
+        }
+        info!(
+            "Signing note for consensus epoch: {} (was: {})",
+            consensus_epoch, self_note.consensus_epoch
+        );
+        let self_note = create_self_note(
+            self.signer.as_ref(),
+            self.peer_id,
+            self.addrs.clone(),
+            consensus_epoch,
+        );
+        self.known_peers.insert(self.peer_id, self_note);
+    }
+
+    // Forgets the notes of peers which are no longer trusted, e.g. validators which left the
+    // validator set in a reconfiguration, as well as notes signed in other consensus epochs.
+    fn remove_untrusted_peers(&mut self, consensus_epoch: u64) {
+        let self_peer_id = self.peer_id;
+        let trusted_peers = self.trusted_peers.read().unwrap();
+        let num_known_peers = self.known_peers.len();
+        self.known_peers.retain(|peer_id, note| {
+            if *peer_id == self_peer_id {
+                return true;
+            }
+            if !trusted_peers.contains_key(peer_id) {
+                info!("Removing untrusted peer: {}", peer_id.short_str());
+                return false;
+            }
+            if note.consensus_epoch != consensus_epoch {
+                info!(
+                    "Removing note for peer: {} from consensus epoch: {}",
+                    peer_id.short_str(),
+                    note.consensus_epoch
+                );
+                return false;
+            }
+            true
+        });
+        drop(trusted_peers);
+        if self.known_peers.len() != num_known_peers {
+            self.record_num_discovery_notes();
+        }
+    }
+
     // Chooses a random connected neighbour.
     fn choose_random_neighbor(&mut self) -> Option<PeerId> {
         if !self.connected_peers.is_empty() {
@@ -381,49 +468,74 @@ struct VerifiedNote {
     peer_id: PeerId,
     addrs: Vec<Multiaddr>,
     epoch: u64,
+    /// The consensus epoch the note was signed in.
+    consensus_epoch: u64,
     /// the raw `Note` sent from remote
     raw_note: Note,
 }
 
-// Creates a PeerInfo combining the given addresses with the current unix timestamp as epoch.
-fn create_peer_info(addrs: Vec<Multiaddr>) -> PeerInfo {
-    let mut peer_info = PeerInfo::default();
+// The current unix timestamp in milliseconds, used as the epoch of our notes.
+fn current_epoch() -> u64 {
     // TODO: Currently, SystemTime::now() in Rust is not guaranteed to use a monotonic clock.
     // At the moment, it's unclear how to do this in a platform-agnostic way. For Linux, we
     // could use something like the [timerfd trait](https://docs.rs/crate/timerfd/1.0.0).
-    let time_since_epoch = SystemTime::now()
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .expect("System clock reset to before unix epoch")
-        .as_millis() as u64;
-    peer_info.epoch = time_since_epoch;
+        .as_millis() as u64
+}
+
+// Creates a PeerInfo combining the given addresses with the current unix timestamp as epoch,
+// bound to the given consensus epoch.
+fn create_peer_info(addrs: Vec<Multiaddr>, consensus_epoch: u64) -> PeerInfo {
+    let mut peer_info = PeerInfo::default();
+    peer_info.epoch = current_epoch();
+    peer_info.consensus_epoch = consensus_epoch;
     peer_info.addrs = addrs.into_iter().map(|addr| addr.as_ref().into()).collect();
     peer_info
 }
 
 fn create_full_node_payload(dns_seed_addr: &[u8]) -> FullNodePayload {
     let mut full_node_payload = FullNodePayload::default();
-    // TODO: Currently, SystemTime::now() in Rust is not guaranteed to use a monotonic clock.
-    // At the moment, it's unclear how to do this in a platform-agnostic way. For Linux, we
-    // could use something like the [timerfd trait](https://docs.rs/crate/timerfd/1.0.0).
-    let time_since_epoch = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .expect("System clock reset to before unix epoch")
-        .as_millis() as u64;
-    full_node_payload.epoch = time_since_epoch;
+    full_node_payload.epoch = current_epoch();
     full_node_payload.dns_seed_addr = dns_seed_addr.into();
     full_node_payload
 }
 
+// Creates and signs the note for self. We don't verify the self note because trusted_peers may
+// not be populated yet.
+fn create_self_note(
+    signer: &dyn CryptoStorage,
+    peer_id: PeerId,
+    addrs: Vec<Multiaddr>,
+    consensus_epoch: u64,
+) -> VerifiedNote {
+    // TODO(philiphayes): wire through config
+    let dns_seed_addr = b"example.com";
+
+    let peer_info = create_peer_info(addrs.clone(), consensus_epoch);
+    let full_node_payload = create_full_node_payload(dns_seed_addr);
+    let epoch = peer_info.epoch;
+    let raw_note = create_note(signer, peer_id, peer_info, full_node_payload);
+    VerifiedNote {
+        peer_id,
+        addrs,
+        epoch,
+        consensus_epoch,
+        raw_note,
+    }
+}
+
 // Creates a note by signing the given peer info, and combining the signature, peer_info and
 // peer_id into a note.
 fn create_note(
-    signer: &Signer,
+    signer: &dyn CryptoStorage,
     peer_id: PeerId,
     peer_info: PeerInfo,
     full_node_payload: FullNodePayload,
 ) -> Note {
     let peer_info_bytes = peer_info.to_bytes().expect("Protobuf serialization fails");
-    let peer_info_signature = sign(&signer, &peer_info_bytes);
+    let peer_info_signature = sign(signer, &peer_info_bytes);
 
     let mut signed_peer_info = SignedPeerInfo::default();
     signed_peer_info.peer_info = peer_info_bytes.to_vec();
@@ -432,7 +544,7 @@ fn create_note(
     let payload_bytes = full_node_payload
         .to_bytes()
         .expect("Protobuf serialization fails");
-    let payload_signature = sign(&signer, &payload_bytes);
+    let payload_signature = sign(signer, &payload_bytes);
 
     let mut signed_full_node_payload = SignedFullNodePayload::default();
     signed_full_node_payload.payload = payload_bytes.to_vec();
@@ -446,19 +558,21 @@ fn create_note(
 }
 
 // Handles an inbound message from a remote peer as follows:
-// Verifies signatures on all notes contained in the message.
+// Verifies signatures on all notes contained in the message, dropping the notes of untrusted
+// peers and the notes from other consensus epochs.
 fn handle_discovery_msg(
     msg: DiscoveryMsg,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    consensus_epoch: u64,
     peer_id: PeerId,
 ) -> Result<Vec<VerifiedNote>, NetworkError> {
     // Check that all received `Note`s are valid -- reject the whole message
     // if any `Note` is invalid.
     let mut verified_notes = vec![];
     msg.notes.iter().try_for_each(|note| {
-        verify_note(&note, &trusted_peers)
+        verify_note(&note, &trusted_peers, consensus_epoch)
             .and_then(|verified_note| {
-                verified_notes.push(verified_note);
+                verified_notes.extend(verified_note);
                 Ok(())
             })
             .map_err(|err| {
@@ -475,18 +589,30 @@ fn handle_discovery_msg(
 }
 
 // Verifies validity of notes. Following conditions should be met for validity:
-// 1. We should be able to correctly parse the peer id in each note.
+// 1. We should be able to correctly parse the peer id and the peer info in each note.
 // 2. The signature of the serialized peer info should be valid for the given peer_id.
 // 3. The address(es) in the PeerInfo should be correctly parsable as Multiaddrs.
 // 4. The signature of the serialized full node payload should be valid for the given peer_id.
+// Notes are dropped, returning `None`, if the peer is not trusted or if they were signed in a
+// consensus epoch other than `consensus_epoch`. Peers in a different epoch than ours are expected
+// during reconfigurations, so this is not treated as an error. The epoch is checked before the
+// signatures, since the notes of another epoch may be signed with keys we don't trust yet.
 fn verify_note(
     note: &Note,
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
-) -> Result<VerifiedNote, NetworkError> {
+    consensus_epoch: u64,
+) -> Result<Option<VerifiedNote>, NetworkError> {
     // validate PeerId
 
     let peer_id = PeerId::try_from(note.peer_id.clone())
         .map_err(|err| anyhow!(err).context(NetworkErrorKind::ParsingError))?;
+    if !trusted_peers.read().unwrap().contains_key(&peer_id) {
+        debug!(
+            "Dropping discovery note for untrusted peer: {}",
+            peer_id.short_str()
+        );
+        return Ok(None);
+    }
 
     // validate PeerInfo
 
@@ -496,14 +622,21 @@ fn verify_note(
     })?;
     let peer_info_bytes = &signed_peer_info.peer_info;
     let peer_info_signature = &signed_peer_info.signature;
+    let peer_info = PeerInfo::decode(peer_info_bytes.as_ref())?;
+    if peer_info.consensus_epoch != consensus_epoch {
+        debug!(
+            "Dropping discovery note for peer: {} from consensus epoch: {}",
+            peer_id.short_str(),
+            peer_info.consensus_epoch
+        );
+        return Ok(None);
+    }
     verify_signature(
         trusted_peers,
         peer_id,
         &peer_info_signature,
         &peer_info_bytes,
     )?;
-
-    let peer_info = PeerInfo::decode(peer_info_bytes.as_ref())?;
     let mut verified_addrs = vec![];
     for addr in &peer_info.addrs {
         verified_addrs.push(Multiaddr::try_from(addr.clone())?)
@@ -525,12 +658,13 @@ fn verify_note(
         // TODO(philiphayes): validate internal fields
     }
 
-    Ok(VerifiedNote {
+    Ok(Some(VerifiedNote {
         peer_id,
         addrs: verified_addrs,
         epoch: peer_info.epoch,
+        consensus_epoch,
         raw_note: note.clone(),
-    })
+    }))
 }
 
 fn get_hash(msg: &[u8]) -> HashValue {
@@ -557,9 +691,9 @@ fn verify_signature(
     Ok(())
 }
 
-fn sign(signer: &Signer, msg: &[u8]) -> Vec<u8> {
+fn sign(signer: &dyn CryptoStorage, msg: &[u8]) -> Vec<u8> {
     let signature: Ed25519Signature = signer
-        .sign_message(get_hash(msg))
+        .sign_message(DISCOVERY_SIGNING_KEY, &get_hash(msg))
         .expect("Message signing fails");
     signature.to_bytes().to_vec()
 }
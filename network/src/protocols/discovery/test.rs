@@ -10,6 +10,7 @@ use crate::ProtocolId;
 use core::str::FromStr;
 use libra_config::config::RoleType;
 use libra_crypto::{test_utils::TEST_SEED, *};
+use libra_secure_storage::InMemoryStorage;
 use prost::Message as _;
 use rand::{rngs::StdRng, SeedableRng};
 use tokio::runtime::Runtime;
@@ -70,8 +71,10 @@ fn setup_discovery(
     addrs: Vec<Multiaddr>,
     seed_peer_id: PeerId,
     seed_peer_info: PeerInfo,
-    signer: Signer,
+    signer: Box<dyn CryptoStorage>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    consensus_epoch: Arc<AtomicU64>,
+    peer_scores: PeerScores,
 ) -> (
     channel::Receiver<NetworkRequest>,
     channel::Receiver<ConnectivityRequest>,
@@ -91,6 +94,8 @@ fn setup_discovery(
             signer,
             vec![(seed_peer_id, seed_peer_info)].into_iter().collect(),
             trusted_peers,
+            consensus_epoch,
+            peer_scores,
             ticker_rx,
             DiscoveryNetworkSender::new(network_reqs_tx),
             DiscoveryNetworkEvents::new(network_notifs_rx),
@@ -123,16 +128,21 @@ async fn expect_address_update(
     }
 }
 
-fn generate_network_pub_keys_and_signer(peer_id: PeerId) -> (NetworkPublicKeys, Signer) {
+fn generate_network_pub_keys_and_signer() -> (NetworkPublicKeys, Box<dyn CryptoStorage>) {
     let mut rng = StdRng::from_seed(TEST_SEED);
     let (signing_priv_key, _) = compat::generate_keypair(&mut rng);
     let (_, identity_pub_key) = x25519::compat::generate_keypair(&mut rng);
+    let signing_pub_key = signing_priv_key.public_key();
+    let mut signer = InMemoryStorage::new();
+    signer
+        .import_key(DISCOVERY_SIGNING_KEY, signing_priv_key)
+        .unwrap();
     (
         NetworkPublicKeys {
-            signing_public_key: signing_priv_key.public_key(),
+            signing_public_key: signing_pub_key,
             identity_public_key: identity_pub_key,
         },
-        Signer::new(peer_id, signing_priv_key),
+        Box::new(signer),
     )
 }
 
@@ -145,14 +155,14 @@ fn inbound() {
     // Setup self.
     let peer_id = PeerId::random();
     let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
-    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
 
     // Setup seed.
     let seed_peer_info = gen_peer_info();
     let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
     let seed_peer_payload = gen_full_node_payload();
     let seed_peer_id = PeerId::random();
-    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer();
     let trusted_peers = Arc::new(RwLock::new(
         vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
             .into_iter()
@@ -168,6 +178,8 @@ fn inbound() {
         seed_peer_info.clone(),
         self_signer,
         trusted_peers.clone(),
+        Arc::new(AtomicU64::new(0)),
+        PeerScores::default(),
     );
 
     // Fake connectivity manager and dialer.
@@ -186,7 +198,7 @@ fn inbound() {
             seed_peer_info.clone(),
             seed_peer_payload.clone(),
         );
-        let (pub_keys_other, signer_other) = generate_network_pub_keys_and_signer(peer_id_other);
+        let (pub_keys_other, signer_other) = generate_network_pub_keys_and_signer();
         trusted_peers
             .write()
             .unwrap()
@@ -233,7 +245,7 @@ fn inbound() {
         msg.notes.push(note_other);
         let new_seed_addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/8098").unwrap()];
         {
-            let seed_peer_info = create_peer_info(new_seed_addrs.clone());
+            let seed_peer_info = create_peer_info(new_seed_addrs.clone(), 0);
             let seed_note = create_note(
                 &seed_signer,
                 seed_peer_id,
@@ -275,12 +287,12 @@ fn outbound() {
     // Setup self.
     let peer_id = PeerId::random();
     let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
-    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
 
     // Setup seed.
     let seed_peer_id = PeerId::random();
     let seed_peer_info = gen_peer_info();
-    let (seed_pub_keys, _) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let (seed_pub_keys, _) = generate_network_pub_keys_and_signer();
     let trusted_peers = Arc::new(RwLock::new(
         vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
             .into_iter()
@@ -297,6 +309,8 @@ fn outbound() {
             seed_peer_info,
             self_signer,
             trusted_peers,
+            Arc::new(AtomicU64::new(0)),
+            PeerScores::default(),
         );
 
     // Fake connectivity manager and dialer.
@@ -338,14 +352,14 @@ fn addr_update_includes_seed_addrs() {
     // Setup self.
     let peer_id = PeerId::random();
     let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
-    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
 
     // Setup seed.
     let seed_peer_info = gen_peer_info();
     let mut seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
     let seed_peer_payload = gen_full_node_payload();
     let seed_peer_id = PeerId::random();
-    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer();
     let trusted_peers = Arc::new(RwLock::new(
         vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
             .into_iter()
@@ -361,6 +375,8 @@ fn addr_update_includes_seed_addrs() {
         seed_peer_info,
         self_signer,
         trusted_peers,
+        Arc::new(AtomicU64::new(0)),
+        PeerScores::default(),
     );
 
     // Fake connectivity manager and dialer.
@@ -372,7 +388,7 @@ fn addr_update_includes_seed_addrs() {
         // The discovery actor should send the addrs in the new seed peer note
         // _and_ the configured seed addrs to the connectivity manager.
         let new_seed_addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9091").unwrap()];
-        let new_seed_info = create_peer_info(new_seed_addrs.clone());
+        let new_seed_info = create_peer_info(new_seed_addrs.clone(), 0);
         let seed_note = create_note(&seed_signer, seed_peer_id, new_seed_info, seed_peer_payload);
         let mut msg = DiscoveryMsg::default();
         msg.notes.push(seed_note.clone());
@@ -400,3 +416,351 @@ fn addr_update_includes_seed_addrs() {
     };
     rt.block_on(f_network);
 }
+
+#[test]
+// Test that notes of untrusted peers and notes from other consensus epochs are dropped, and that
+// peers relaying invalid notes are penalized.
+fn drop_untrusted_and_penalize_invalid_notes() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+
+    // Setup self.
+    let peer_id = PeerId::random();
+    let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
+
+    // Setup seed.
+    let seed_peer_info = gen_peer_info();
+    let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
+    let seed_peer_payload = gen_full_node_payload();
+    let seed_peer_id = PeerId::random();
+    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer();
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
+            .into_iter()
+            .collect(),
+    ));
+    let peer_scores = PeerScores::default();
+
+    // Setup discovery.
+    let (_, mut conn_mgr_reqs_rx, mut network_notifs_tx, _) = setup_discovery(
+        &mut rt,
+        peer_id,
+        addrs,
+        seed_peer_id,
+        seed_peer_info,
+        self_signer,
+        trusted_peers,
+        Arc::new(AtomicU64::new(0)),
+        peer_scores.clone(),
+    );
+
+    // Fake connectivity manager and dialer.
+    let f_network = async move {
+        // Connectivity manager receives addresses of the seed peer during bootstrap.
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, &seed_peer_addrs[..]).await;
+
+        // Send DiscoveryMsg consisting of a note for an untrusted peer, a note for the seed peer
+        // from the next consensus epoch, signed with its key of that epoch, and a valid note for
+        // the seed peer. Only the valid note should make it to the connectivity manager, and the
+        // seed peer should not be penalized.
+        let untrusted_peer_id = PeerId::random();
+        let (_, untrusted_signer) = generate_network_pub_keys_and_signer();
+        let untrusted_note = create_note(
+            &untrusted_signer,
+            untrusted_peer_id,
+            gen_peer_info(),
+            gen_full_node_payload(),
+        );
+        let future_seed_note = {
+            let (_, rotated_seed_signer) = generate_network_pub_keys_and_signer();
+            let future_addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9092").unwrap()];
+            let mut peer_info = create_peer_info(future_addrs, 1);
+            peer_info.epoch = u64::max_value();
+            create_note(
+                &rotated_seed_signer,
+                seed_peer_id,
+                peer_info,
+                seed_peer_payload.clone(),
+            )
+        };
+        let new_seed_addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9091").unwrap()];
+        let new_seed_info = create_peer_info(new_seed_addrs.clone(), 0);
+        let new_seed_epoch = new_seed_info.epoch;
+        let seed_note = create_note(
+            &seed_signer,
+            seed_peer_id,
+            new_seed_info,
+            seed_peer_payload.clone(),
+        );
+        let mut msg = DiscoveryMsg::default();
+        msg.notes.push(untrusted_note);
+        msg.notes.push(future_seed_note);
+        msg.notes.push(seed_note);
+        network_notifs_tx
+            .send(NetworkNotification::RecvMessage(
+                seed_peer_id,
+                get_raw_message(msg),
+            ))
+            .await
+            .unwrap();
+
+        let mut expected_seed_addrs = new_seed_addrs.clone();
+        expected_seed_addrs.extend_from_slice(&seed_peer_addrs[..]);
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            &expected_seed_addrs[..],
+        )
+        .await;
+        assert_eq!(peer_scores.score(&seed_peer_id), 0.0);
+
+        // Send DiscoveryMsg consisting of a note with a tampered peer info.
+        let mut invalid_note = create_note(
+            &seed_signer,
+            seed_peer_id,
+            create_peer_info(new_seed_addrs.clone(), 0),
+            seed_peer_payload.clone(),
+        );
+        invalid_note
+            .signed_peer_info
+            .as_mut()
+            .unwrap()
+            .peer_info
+            .push(0);
+        let mut msg = DiscoveryMsg::default();
+        msg.notes.push(invalid_note);
+        network_notifs_tx
+            .send(NetworkNotification::RecvMessage(
+                seed_peer_id,
+                get_raw_message(msg),
+            ))
+            .await
+            .unwrap();
+
+        // Send a newer valid note, to make sure the invalid message has been processed.
+        let newer_seed_addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9093").unwrap()];
+        let mut newer_seed_info = create_peer_info(newer_seed_addrs.clone(), 0);
+        newer_seed_info.epoch = new_seed_epoch + 1;
+        let seed_note = create_note(
+            &seed_signer,
+            seed_peer_id,
+            newer_seed_info,
+            seed_peer_payload,
+        );
+        let mut msg = DiscoveryMsg::default();
+        msg.notes.push(seed_note);
+        network_notifs_tx
+            .send(NetworkNotification::RecvMessage(
+                seed_peer_id,
+                get_raw_message(msg),
+            ))
+            .await
+            .unwrap();
+
+        let mut expected_seed_addrs = newer_seed_addrs.clone();
+        expected_seed_addrs.extend_from_slice(&seed_peer_addrs[..]);
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            &expected_seed_addrs[..],
+        )
+        .await;
+
+        // The seed peer was penalized for the invalid message.
+        assert!(peer_scores.score(&seed_peer_id) < 0.0);
+    };
+    rt.block_on(f_network);
+}
+
+#[test]
+// Test that notes of peers which are no longer trusted are not gossiped anymore.
+fn remove_untrusted_peers() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+
+    // Setup self.
+    let peer_id = PeerId::random();
+    let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
+
+    // Setup seed.
+    let seed_peer_info = gen_peer_info();
+    let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
+    let seed_peer_id = PeerId::random();
+    let (seed_pub_keys, _) = generate_network_pub_keys_and_signer();
+
+    // Setup a peer which is about to leave.
+    let peer_id_other = PeerId::random();
+    let addrs_other = vec![Multiaddr::from_str("/ip4/172.29.52.192/tcp/8080").unwrap()];
+    let (pub_keys_other, signer_other) = generate_network_pub_keys_and_signer();
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![
+            (seed_peer_id, seed_pub_keys),
+            (peer_id, self_pub_keys),
+            (peer_id_other, pub_keys_other),
+        ]
+        .into_iter()
+        .collect(),
+    ));
+
+    // Setup discovery.
+    let (mut network_reqs_rx, mut conn_mgr_reqs_rx, mut network_notifs_tx, mut ticker_tx) =
+        setup_discovery(
+            &mut rt,
+            peer_id,
+            addrs,
+            seed_peer_id,
+            seed_peer_info,
+            self_signer,
+            trusted_peers.clone(),
+            Arc::new(AtomicU64::new(0)),
+            PeerScores::default(),
+        );
+
+    // Fake connectivity manager and dialer.
+    let f_network = async move {
+        // Connectivity manager receives addresses of the seed peer during bootstrap.
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, &seed_peer_addrs[..]).await;
+
+        // Learn about the other peer from the seed peer.
+        let note_other = create_note(
+            &signer_other,
+            peer_id_other,
+            create_peer_info(addrs_other.clone(), 0),
+            gen_full_node_payload(),
+        );
+        let mut msg = DiscoveryMsg::default();
+        msg.notes.push(note_other);
+        network_notifs_tx
+            .send(NetworkNotification::RecvMessage(
+                seed_peer_id,
+                get_raw_message(msg),
+            ))
+            .await
+            .unwrap();
+        expect_address_update(&mut conn_mgr_reqs_rx, peer_id_other, &addrs_other[..]).await;
+
+        // The other peer leaves the trusted set, e.g. in a reconfiguration.
+        trusted_peers.write().unwrap().remove(&peer_id_other);
+
+        // Notify discovery actor of connection to seed peer and trigger outbound msg.
+        network_notifs_tx
+            .send(NetworkNotification::NewPeer(seed_peer_id))
+            .await
+            .unwrap();
+        ticker_tx.send(()).await.unwrap();
+
+        // The message only contains our own note.
+        match network_reqs_rx.next().await.unwrap() {
+            NetworkRequest::SendMessage(peer, raw_msg) => {
+                assert_eq!(peer, seed_peer_id);
+                let msg = parse_raw_message(raw_msg).unwrap();
+                assert_eq!(1, msg.notes.len());
+                assert_eq!(Vec::from(peer_id), msg.notes[0].peer_id);
+            }
+            req => {
+                panic!("Unexpected request to peer manager: {:?}", req);
+            }
+        }
+    };
+    rt.block_on(f_network);
+}
+
+#[test]
+// Test that notes are re-signed and notes from past epochs are forgotten on reconfiguration.
+fn reconfiguration_refreshes_notes() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+
+    // Setup self.
+    let peer_id = PeerId::random();
+    let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer();
+
+    // Setup seed.
+    let seed_peer_info = gen_peer_info();
+    let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
+    let seed_peer_id = PeerId::random();
+    let (seed_pub_keys, _) = generate_network_pub_keys_and_signer();
+
+    // Setup another peer, which stays in the trusted set.
+    let peer_id_other = PeerId::random();
+    let addrs_other = vec![Multiaddr::from_str("/ip4/172.29.52.192/tcp/8080").unwrap()];
+    let (pub_keys_other, signer_other) = generate_network_pub_keys_and_signer();
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![
+            (seed_peer_id, seed_pub_keys),
+            (peer_id, self_pub_keys),
+            (peer_id_other, pub_keys_other),
+        ]
+        .into_iter()
+        .collect(),
+    ));
+    let consensus_epoch = Arc::new(AtomicU64::new(0));
+
+    // Setup discovery.
+    let (mut network_reqs_rx, mut conn_mgr_reqs_rx, mut network_notifs_tx, mut ticker_tx) =
+        setup_discovery(
+            &mut rt,
+            peer_id,
+            addrs,
+            seed_peer_id,
+            seed_peer_info,
+            self_signer,
+            trusted_peers,
+            consensus_epoch.clone(),
+            PeerScores::default(),
+        );
+
+    // Fake connectivity manager and dialer.
+    let f_network = async move {
+        // Connectivity manager receives addresses of the seed peer during bootstrap.
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, &seed_peer_addrs[..]).await;
+
+        // Learn about the other peer from the seed peer.
+        let note_other = create_note(
+            &signer_other,
+            peer_id_other,
+            create_peer_info(addrs_other.clone(), 0),
+            gen_full_node_payload(),
+        );
+        let mut msg = DiscoveryMsg::default();
+        msg.notes.push(note_other);
+        network_notifs_tx
+            .send(NetworkNotification::RecvMessage(
+                seed_peer_id,
+                get_raw_message(msg),
+            ))
+            .await
+            .unwrap();
+        expect_address_update(&mut conn_mgr_reqs_rx, peer_id_other, &addrs_other[..]).await;
+
+        // The network moves on to the next epoch.
+        consensus_epoch.store(1, Ordering::Relaxed);
+
+        // Notify discovery actor of connection to seed peer and trigger outbound msg.
+        network_notifs_tx
+            .send(NetworkNotification::NewPeer(seed_peer_id))
+            .await
+            .unwrap();
+        ticker_tx.send(()).await.unwrap();
+
+        // The message only contains our own note, signed for the new epoch.
+        match network_reqs_rx.next().await.unwrap() {
+            NetworkRequest::SendMessage(peer, raw_msg) => {
+                assert_eq!(peer, seed_peer_id);
+                let msg = parse_raw_message(raw_msg).unwrap();
+                assert_eq!(1, msg.notes.len());
+                assert_eq!(Vec::from(peer_id), msg.notes[0].peer_id);
+                let signed_peer_info = msg.notes[0].signed_peer_info.as_ref().unwrap();
+                let peer_info = PeerInfo::decode(signed_peer_info.peer_info.as_ref()).unwrap();
+                assert_eq!(1, peer_info.consensus_epoch);
+            }
+            req => {
+                panic!("Unexpected request to peer manager: {:?}", req);
+            }
+        }
+    };
+    rt.block_on(f_network);
+}
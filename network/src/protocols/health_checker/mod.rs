@@ -7,17 +7,24 @@
 //! It does so by periodically selecting a random connected peer and sending a Ping probe. A
 //! healthy peer is expected to respond with a corresponding Pong message.
 //!
-//! If a certain number of successive liveness probes for a peer fail, the HealthChecker initiates a
-//! disconnect from the peer. It relies on ConnectivityManager or the remote peer to re-establish
-//! the connection.
+//! The outcome of every probe is reported to a pluggable [`HealthPolicy`]. Once the policy
+//! declares a peer unhealthy, e.g., after a certain number of successive failed probes, the
+//! HealthChecker initiates a disconnect from the peer. It relies on ConnectivityManager or the
+//! remote peer to re-establish the connection. Every failed probe, as well as every invalid Ping
+//! or Pong, also lowers the peer's score in the shared [`PeerScores`]; a peer banned for its low
+//! score is disconnected right away.
 //!
-//! Future Work
-//! -----------
-//! We can make a few other improvements to the health checker. These are:
-//! - Make the policy for interpreting ping failures pluggable
-//! - Use successful inbound pings as a sign of remote note being healthy
-//! - Ping a peer only in periods of no application-level communication with the peer
+//! Inbound traffic from a peer, i.e., any rpc request, direct-send message or Ping it sends us,
+//! is as good a sign of liveness as a Pong. The PeerManager and the DirectSend actor record such
+//! traffic in the shared [`PeerActivity`], and the HealthChecker only pings peers which have been
+//! silent since the previous round.
+//!
+//! [`PeerScores`]: ../../peer_score
+//! [`PeerActivity`]: ../../peer_manager
 use crate::{
+    counters,
+    peer_manager::PeerActivity,
+    peer_score::{Misbehavior, PeerScores},
     proto::{HealthCheckerMsg, HealthCheckerMsg_oneof, Ping, Pong},
     protocols::rpc::error::RpcError,
     utils::MessageExt,
@@ -31,11 +38,17 @@ use futures::{
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use rand::{rngs::SmallRng, seq::SliceRandom, FromEntropy, Rng};
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
+pub mod policy;
 #[cfg(test)]
 mod test;
 
+pub use self::policy::{ConsecutiveFailures, HealthPolicy, LatencyThreshold, SlidingWindow};
+
 /// The actor performing health checks by running the Ping protocol
 pub struct HealthChecker<TTicker> {
     /// Ticker to trigger ping to a random peer. In production, the ticker is likely to be
@@ -45,19 +58,22 @@ pub struct HealthChecker<TTicker> {
     network_tx: HealthCheckerNetworkSender,
     /// Channel to receive notifications from Network layer about new/lost connections.
     network_rx: HealthCheckerNetworkEvents,
-    /// Map from connected peer to last round of successful ping, and number of failures since
-    /// then.
-    connected: HashMap<PeerId, (u64, u64)>,
+    /// Scores of remote peers, lowered on ping failures and invalid messages.
+    peer_scores: PeerScores,
+    /// Time of the last inbound traffic from each peer, recorded by the PeerManager.
+    peer_activity: PeerActivity,
+    /// Map from connected peer to the last round in which it was known to be alive.
+    connected: HashMap<PeerId, u64>,
     /// Random-number generator.
     rng: SmallRng,
     /// Ping timmeout duration.
     ping_timeout: Duration,
-    /// Number of successive ping failures we tolerate before declaring a node as unhealthy and
-    /// disconnecting from it. In the future, this can be replaced with a more general failure
-    /// detection policy.
-    ping_failures_tolerated: u64,
+    /// Policy deciding when a peer is unhealthy and should be disconnected.
+    policy: Box<dyn HealthPolicy>,
     /// Counter incremented in each round of health checks
     round: u64,
+    /// Start of the current round of health checks.
+    round_start: Instant,
 }
 
 impl<TTicker> HealthChecker<TTicker>
@@ -69,18 +85,23 @@ where
         ticker: TTicker,
         network_tx: HealthCheckerNetworkSender,
         network_rx: HealthCheckerNetworkEvents,
+        peer_scores: PeerScores,
+        peer_activity: PeerActivity,
         ping_timeout: Duration,
-        ping_failures_tolerated: u64,
+        policy: Box<dyn HealthPolicy>,
     ) -> Self {
         HealthChecker {
             ticker,
             network_tx,
             network_rx,
+            peer_scores,
+            peer_activity,
             connected: HashMap::new(),
             rng: SmallRng::from_entropy(),
             ping_timeout,
-            ping_failures_tolerated,
+            policy,
             round: 0,
+            round_start: Instant::now(),
         }
     }
 
@@ -91,10 +112,13 @@ where
                 event = self.network_rx.select_next_some() => {
                     match event {
                         Ok(Event::NewPeer(peer_id)) => {
-                            self.connected.insert(peer_id, (self.round, 0));
+                            self.connected.insert(peer_id, self.round);
                         },
                         Ok(Event::LostPeer(peer_id)) => {
                             self.connected.remove(&peer_id);
+                            self.policy.remove_peer(&peer_id);
+                            let _ = counters::LIBRA_NETWORK_PING_RTT
+                                .remove_label_values(&[&*peer_id.short_str()]);
                         },
                         Ok(Event::RpcRequest((peer_id, msg, res_tx))) => {
                             if let Some(HealthCheckerMsg_oneof::Ping(ping_msg)) = msg.message {
@@ -105,10 +129,11 @@ where
                                     .data(&msg)
                                     .data(&peer_id)
                                     .log();
+                                self.peer_scores.penalize(peer_id, Misbehavior::InvalidMessage);
                                 debug_assert!(false, "Unexpected rpc message");
                             }
                         }
-                        Ok(Event::Message(_)) => {
+                        Ok(Event::Message(_)) | Ok(Event::StreamingRpcRequest(_)) => {
                             security_log(SecurityEvent::InvalidNetworkEventHC)
                                 .error("Unexpected network event")
                                 .data(&event)
@@ -126,7 +151,8 @@ where
                 _ = self.ticker.select_next_some() => {
                     self.round += 1;
                     debug!("Tick: Round number: {}", self.round);
-                    match self.sample_random_peer() {
+                    let silent_peers = self.record_activity();
+                    match silent_peers.choose(&mut self.rng).cloned() {
                         Some(peer_id) => {
                             debug!("Will ping: {}", peer_id.short_str());
 
@@ -141,13 +167,13 @@ where
                                     self.ping_timeout.clone()));
                         }
                         None => {
-                            debug!("No connected peer to ping");
+                            debug!("No silent connected peer to ping");
                         }
                     }
                 }
                 res = tick_handlers.select_next_some() => {
-                    let (peer_id, round, nonce, ping_result) = res;
-                    self.handle_ping_response(peer_id, round, nonce, ping_result).await;
+                    let (peer_id, round, nonce, rtt, ping_result) = res;
+                    self.handle_ping_response(peer_id, round, nonce, rtt, ping_result).await;
                 }
                 complete => {
                     crit!("Health checker actor terminated");
@@ -157,6 +183,29 @@ where
         }
     }
 
+    /// Record inbound traffic since the start of the previous round as a sign of liveness, and
+    /// return the connected peers which have been silent since then.
+    fn record_activity(&mut self) -> Vec<PeerId> {
+        let prev_round_start = std::mem::replace(&mut self.round_start, Instant::now());
+        let mut silent_peers = vec![];
+        for (peer_id, last_alive_round) in self.connected.iter_mut() {
+            match self.peer_activity.last_inbound(peer_id) {
+                Some(last_inbound) if last_inbound >= prev_round_start => {
+                    debug!(
+                        "Skipping ping to recently active peer: {}",
+                        peer_id.short_str()
+                    );
+                    *last_alive_round = self.round;
+                    self.policy.record_activity(*peer_id);
+                }
+                _ => silent_peers.push(*peer_id),
+            }
+        }
+        // Sort so that sampling only depends on the rng, not on the map's iteration order.
+        silent_peers.sort();
+        silent_peers
+    }
+
     fn handle_ping_request(
         &mut self,
         peer_id: PeerId,
@@ -182,6 +231,7 @@ where
         peer_id: PeerId,
         round: u64,
         req_nonce: u32,
+        rtt: Duration,
         ping_result: Result<Pong, RpcError>,
     ) {
         debug!("Got result for ping round: {}", round);
@@ -189,16 +239,21 @@ where
             Ok(pong_msg) => {
                 let res_nonce = pong_msg.nonce;
                 if res_nonce == req_nonce {
-                    debug!("Ping successful for peer: {}", peer_id.short_str());
+                    debug!(
+                        "Ping successful for peer: {} in {:?}",
+                        peer_id.short_str(),
+                        rtt
+                    );
+                    counters::LIBRA_NETWORK_PING_RTT
+                        .with_label_values(&[&*peer_id.short_str()])
+                        .observe(rtt.as_secs_f64());
                     // Update last successful ping to current round.
-                    self.connected
-                        .entry(peer_id)
-                        .and_modify(|(ref mut r, ref mut count)| {
-                            if round > *r {
-                                *r = round;
-                                *count = 0;
-                            }
-                        });
+                    if let Some(last_alive_round) = self.connected.get_mut(&peer_id) {
+                        if round > *last_alive_round {
+                            *last_alive_round = round;
+                            self.policy.record_success(peer_id, rtt);
+                        }
+                    }
                 } else {
                     security_log(SecurityEvent::InvalidHealthCheckerMsg)
                         .error("Pong nonce doesn't match our challenge Ping nonce")
@@ -206,6 +261,8 @@ where
                         .data(req_nonce)
                         .data(&pong_msg)
                         .log();
+                    self.peer_scores
+                        .penalize(peer_id, Misbehavior::InvalidMessage);
                     debug_assert!(false, "Pong nonce doesn't match our challenge Ping nonce");
                 }
             }
@@ -220,17 +277,19 @@ where
                         // If we are no longer connected to the peer, we ignore ping
                         // failure.
                     }
-                    Some((ref mut prev, ref mut failures)) => {
+                    Some(last_alive_round) => {
                         // If this is the result of an older ping, we ignore it.
-                        if *prev > round {
+                        if *last_alive_round > round {
                             return;
                         }
-                        // Increment num of failures. If the ping failures are now more than
-                        // `self.ping_failures_tolerated`, we disconnect from the node.
+                        // Report the failure to the policy. If the policy now considers the peer
+                        // unhealthy, or the peer got banned for its low score, we disconnect from
+                        // the node.
                         // The HealthChecker only performs the disconnect. It relies on
                         // ConnectivityManager or the remote peer to re-establish the connection.
-                        *failures += 1;
-                        if *failures > self.ping_failures_tolerated {
+                        self.policy.record_failure(peer_id);
+                        let banned = self.peer_scores.penalize(peer_id, Misbehavior::PingFailure);
+                        if self.policy.is_unhealthy(&peer_id) || banned {
                             info!("Disonnecting from peer: {}", peer_id.short_str());
                             if let Err(err) = self.network_tx.disconnect_peer(peer_id).await {
                                 warn!(
@@ -252,20 +311,16 @@ where
         round: u64,
         nonce: u32,
         ping_timeout: Duration,
-    ) -> (PeerId, u64, u32, Result<Pong, RpcError>) {
+    ) -> (PeerId, u64, u32, Duration, Result<Pong, RpcError>) {
         let ping_msg = Ping { nonce };
         debug!(
             "Sending Ping request to peer: {} with nonce: {}",
             peer_id.short_str(),
             nonce
         );
+        let start = Instant::now();
         let res_pong_msg = network_tx.ping(peer_id, ping_msg, ping_timeout).await;
-        (peer_id, round, nonce, res_pong_msg)
-    }
-
-    fn sample_random_peer(&mut self) -> Option<PeerId> {
-        let peers: Vec<_> = self.connected.keys().cloned().collect();
-        peers.choose(&mut self.rng).cloned()
+        (peer_id, round, nonce, start.elapsed(), res_pong_msg)
     }
 
     fn sample_nonce(&mut self) -> u32 {
@@ -0,0 +1,205 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Policies used by the HealthChecker to decide when a peer is unhealthy.
+use libra_config::config::HealthPolicyConfig;
+use libra_types::PeerId;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+/// A policy interpreting the results of liveness probes.
+///
+/// The HealthChecker reports the outcome of every ping, as well as inbound application-level
+/// traffic, to its policy, and disconnects from a peer once the policy declares it unhealthy.
+pub trait HealthPolicy: Send {
+    /// Record a successful ping to `peer_id` which took `rtt` to complete.
+    fn record_success(&mut self, peer_id: PeerId, rtt: Duration);
+
+    /// Record a failed ping to `peer_id`.
+    fn record_failure(&mut self, peer_id: PeerId);
+
+    /// Record inbound traffic from `peer_id` which shows that the peer is alive.
+    fn record_activity(&mut self, peer_id: PeerId);
+
+    /// Whether `peer_id` should be disconnected.
+    fn is_unhealthy(&self, peer_id: &PeerId) -> bool;
+
+    /// Forget about `peer_id`, e.g., once we are no longer connected to it.
+    fn remove_peer(&mut self, peer_id: &PeerId);
+}
+
+/// Builds the policy described by `config`.
+pub fn from_config(config: &HealthPolicyConfig) -> Box<dyn HealthPolicy> {
+    match *config {
+        HealthPolicyConfig::ConsecutiveFailures { tolerated } => {
+            Box::new(ConsecutiveFailures::new(tolerated))
+        }
+        HealthPolicyConfig::SlidingWindow {
+            window,
+            max_failures,
+        } => Box::new(SlidingWindow::new(window, max_failures)),
+        HealthPolicyConfig::LatencyThreshold {
+            threshold_ms,
+            window,
+        } => Box::new(LatencyThreshold::new(
+            Duration::from_millis(threshold_ms),
+            window,
+        )),
+    }
+}
+
+/// Declares a peer unhealthy after more than `tolerated` successive ping failures.
+#[derive(Debug)]
+pub struct ConsecutiveFailures {
+    tolerated: u64,
+    failures: HashMap<PeerId, u64>,
+}
+
+impl ConsecutiveFailures {
+    pub fn new(tolerated: u64) -> Self {
+        Self {
+            tolerated,
+            failures: HashMap::new(),
+        }
+    }
+}
+
+impl HealthPolicy for ConsecutiveFailures {
+    fn record_success(&mut self, peer_id: PeerId, _rtt: Duration) {
+        self.failures.remove(&peer_id);
+    }
+
+    fn record_failure(&mut self, peer_id: PeerId) {
+        *self.failures.entry(peer_id).or_insert(0) += 1;
+    }
+
+    fn record_activity(&mut self, peer_id: PeerId) {
+        self.failures.remove(&peer_id);
+    }
+
+    fn is_unhealthy(&self, peer_id: &PeerId) -> bool {
+        self.failures
+            .get(peer_id)
+            .map_or(false, |failures| *failures > self.tolerated)
+    }
+
+    fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.failures.remove(peer_id);
+    }
+}
+
+/// Declares a peer unhealthy once more than `max_failures` of its last `window` probes failed.
+///
+/// Unlike [`ConsecutiveFailures`], an occasional successful ping does not hide a peer which fails
+/// most of its probes.
+#[derive(Debug)]
+pub struct SlidingWindow {
+    window: usize,
+    max_failures: usize,
+    /// The outcomes of the last `window` probes of each peer, true for failures.
+    outcomes: HashMap<PeerId, VecDeque<bool>>,
+}
+
+impl SlidingWindow {
+    pub fn new(window: usize, max_failures: usize) -> Self {
+        assert!(
+            max_failures < window,
+            "max_failures must be less than window"
+        );
+        Self {
+            window,
+            max_failures,
+            outcomes: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, peer_id: PeerId, failed: bool) {
+        let outcomes = self.outcomes.entry(peer_id).or_insert_with(VecDeque::new);
+        if outcomes.len() == self.window {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(failed);
+    }
+}
+
+impl HealthPolicy for SlidingWindow {
+    fn record_success(&mut self, peer_id: PeerId, _rtt: Duration) {
+        self.push(peer_id, false);
+    }
+
+    fn record_failure(&mut self, peer_id: PeerId) {
+        self.push(peer_id, true);
+    }
+
+    fn record_activity(&mut self, peer_id: PeerId) {
+        self.push(peer_id, false);
+    }
+
+    fn is_unhealthy(&self, peer_id: &PeerId) -> bool {
+        self.outcomes.get(peer_id).map_or(false, |outcomes| {
+            outcomes.iter().filter(|failed| **failed).count() > self.max_failures
+        })
+    }
+
+    fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.outcomes.remove(peer_id);
+    }
+}
+
+/// Declares a peer unhealthy once each of its last `window` probes either failed or took longer
+/// than `threshold`.
+///
+/// Inbound traffic counts as a fast probe: a peer that is busy talking to us is slow to answer
+/// pings rather than unreachable.
+#[derive(Debug)]
+pub struct LatencyThreshold {
+    threshold: Duration,
+    window: usize,
+    /// Number of successive slow or failed probes of each peer.
+    slow_probes: HashMap<PeerId, usize>,
+}
+
+impl LatencyThreshold {
+    pub fn new(threshold: Duration, window: usize) -> Self {
+        assert!(window > 0, "window must be positive");
+        Self {
+            threshold,
+            window,
+            slow_probes: HashMap::new(),
+        }
+    }
+
+    fn record_slow(&mut self, peer_id: PeerId) {
+        *self.slow_probes.entry(peer_id).or_insert(0) += 1;
+    }
+}
+
+impl HealthPolicy for LatencyThreshold {
+    fn record_success(&mut self, peer_id: PeerId, rtt: Duration) {
+        if rtt > self.threshold {
+            self.record_slow(peer_id);
+        } else {
+            self.slow_probes.remove(&peer_id);
+        }
+    }
+
+    fn record_failure(&mut self, peer_id: PeerId) {
+        self.record_slow(peer_id);
+    }
+
+    fn record_activity(&mut self, peer_id: PeerId) {
+        self.slow_probes.remove(&peer_id);
+    }
+
+    fn is_unhealthy(&self, peer_id: &PeerId) -> bool {
+        self.slow_probes
+            .get(peer_id)
+            .map_or(false, |slow_probes| *slow_probes >= self.window)
+    }
+
+    fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.slow_probes.remove(peer_id);
+    }
+}
@@ -14,9 +14,10 @@ use tokio::runtime::Runtime;
 
 const PING_TIMEOUT: Duration = Duration::from_millis(500);
 
-fn setup_permissive_health_checker(
+fn setup_health_checker(
     rt: &mut Runtime,
-    ping_failures_tolerated: u64,
+    peer_activity: PeerActivity,
+    policy: Box<dyn HealthPolicy>,
 ) -> (
     channel::Receiver<NetworkRequest>,
     channel::Sender<NetworkNotification>,
@@ -34,13 +35,30 @@ fn setup_permissive_health_checker(
         ticker_rx,
         hc_network_tx,
         hc_network_rx,
+        PeerScores::default(),
+        peer_activity,
         PING_TIMEOUT,
-        ping_failures_tolerated,
+        policy,
     );
     rt.spawn(health_checker.start());
     (network_reqs_rx, network_notifs_tx, ticker_tx)
 }
 
+fn setup_permissive_health_checker(
+    rt: &mut Runtime,
+    ping_failures_tolerated: u64,
+) -> (
+    channel::Receiver<NetworkRequest>,
+    channel::Sender<NetworkNotification>,
+    channel::Sender<()>,
+) {
+    setup_health_checker(
+        rt,
+        PeerActivity::default(),
+        Box::new(ConsecutiveFailures::new(ping_failures_tolerated)),
+    )
+}
+
 fn setup_strict_health_checker(
     rt: &mut Runtime,
 ) -> (
@@ -341,3 +359,102 @@ fn ping_timeout() {
     };
     rt.block_on(events_f);
 }
+
+#[test]
+fn inbound_activity_skips_ping() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let peer_activity = PeerActivity::default();
+    let (mut network_reqs_rx, mut network_notifs_tx, mut ticker_tx) = setup_health_checker(
+        &mut rt,
+        peer_activity.clone(),
+        Box::new(ConsecutiveFailures::new(0)),
+    );
+
+    let events_f = async move {
+        // Notify HealthChecker of two new connected nodes.
+        let active_peer_id = PeerId::random();
+        let silent_peer_id = PeerId::random();
+        network_notifs_tx
+            .send(NetworkNotification::NewPeer(active_peer_id))
+            .await
+            .unwrap();
+        network_notifs_tx
+            .send(NetworkNotification::NewPeer(silent_peer_id))
+            .await
+            .unwrap();
+        // Wait for the HealthChecker to process both notifications.
+        let res_rx =
+            send_inbound_ping(silent_peer_id, Ping { nonce: 0 }, &mut network_notifs_tx).await;
+        expect_pong(res_rx).await;
+
+        // The PeerManager sees inbound traffic from one of the peers.
+        peer_activity.record(active_peer_id);
+
+        // Trigger ping. Only the silent peer should be pinged.
+        ticker_tx.send(()).await.unwrap();
+        match network_reqs_rx.next().await.unwrap() {
+            NetworkRequest::SendRpc(peer_id, _) => assert_eq!(peer_id, silent_peer_id),
+            req => panic!("Unexpected NetworkRequest: {:?}", req),
+        }
+    };
+    rt.block_on(events_f);
+}
+
+#[test]
+fn consecutive_failures_policy() {
+    let mut policy = ConsecutiveFailures::new(2);
+    let peer_id = PeerId::random();
+
+    policy.record_failure(peer_id);
+    policy.record_failure(peer_id);
+    assert!(!policy.is_unhealthy(&peer_id));
+    // Inbound traffic resets the failure count.
+    policy.record_activity(peer_id);
+    policy.record_failure(peer_id);
+    policy.record_failure(peer_id);
+    assert!(!policy.is_unhealthy(&peer_id));
+    policy.record_failure(peer_id);
+    assert!(policy.is_unhealthy(&peer_id));
+
+    policy.remove_peer(&peer_id);
+    assert!(!policy.is_unhealthy(&peer_id));
+}
+
+#[test]
+fn sliding_window_policy() {
+    let mut policy = SlidingWindow::new(4, 2);
+    let peer_id = PeerId::random();
+    let rtt = Duration::from_millis(10);
+
+    // Successes interleaved with failures do not hide an unhealthy peer.
+    policy.record_failure(peer_id);
+    policy.record_success(peer_id, rtt);
+    policy.record_failure(peer_id);
+    assert!(!policy.is_unhealthy(&peer_id));
+    policy.record_failure(peer_id);
+    assert!(policy.is_unhealthy(&peer_id));
+
+    // Old failures fall out of the window.
+    policy.record_success(peer_id, rtt);
+    assert!(!policy.is_unhealthy(&peer_id));
+}
+
+#[test]
+fn latency_threshold_policy() {
+    let mut policy = LatencyThreshold::new(Duration::from_millis(100), 3);
+    let peer_id = PeerId::random();
+    let fast = Duration::from_millis(10);
+    let slow = Duration::from_millis(500);
+
+    policy.record_success(peer_id, slow);
+    policy.record_failure(peer_id);
+    assert!(!policy.is_unhealthy(&peer_id));
+    // A fast ping resets the count of slow probes.
+    policy.record_success(peer_id, fast);
+    policy.record_success(peer_id, slow);
+    policy.record_success(peer_id, slow);
+    assert!(!policy.is_unhealthy(&peer_id));
+    policy.record_failure(peer_id);
+    assert!(policy.is_unhealthy(&peer_id));
+}
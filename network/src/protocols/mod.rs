@@ -6,6 +6,7 @@
 //! Each protocol corresponds to a certain order of messages
 pub mod direct_send;
 pub mod rpc;
+pub mod streaming_rpc;
 
 pub(crate) mod discovery;
 pub(crate) mod health_checker;
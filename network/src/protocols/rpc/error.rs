@@ -32,6 +32,18 @@ pub enum RpcError {
     #[error("Received unexpected rpc request message; expected remote to half-close.")]
     UnexpectedRpcRequest,
 
+    #[error("Received invalid streaming rpc frame")]
+    InvalidStreamFrame,
+
+    #[error("Received more streaming rpc responses than granted credits for")]
+    FlowControlViolation,
+
+    #[error("Remote aborted the streaming rpc response stream")]
+    StreamAborted,
+
+    #[error("Too many concurrent streaming rpcs")]
+    TooManyStreams,
+
     #[error("Application layer unexpectedly dropped response channel")]
     UnexpectedResponseChannelCancel,
 
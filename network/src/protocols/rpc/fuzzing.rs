@@ -92,6 +92,7 @@ pub fn fuzzer(data: &[u8]) {
                     assert_eq!(protocol.as_ref(), MOCK_PROTOCOL_ID);
                     res_tx.send(Ok(data)).unwrap();
                 }
+                notif => panic!("Unexpected RpcNotification: {:?}", notif),
             }
         }
 
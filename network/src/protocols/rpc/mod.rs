@@ -58,6 +58,8 @@
 use crate::{
     counters,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_score::{Misbehavior, PeerScores},
+    protocols::streaming_rpc::{self, InboundStreamingRpcRequest, OutboundStreamingRpcRequest},
     sink::NetworkSinkExt,
     ProtocolId,
 };
@@ -76,7 +78,7 @@ use futures::{
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::compat::IoCompat;
-use std::{fmt::Debug, io, time::Duration};
+use std::{collections::HashSet, fmt::Debug, io, time::Duration};
 use tokio::runtime::Handle;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
@@ -142,6 +144,8 @@ pub struct OutboundRpcRequest {
 pub enum RpcRequest {
     /// Send an outbound rpc request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send an outbound streaming rpc request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
 }
 
 /// Events sent from the [`Rpc`] actor to the
@@ -150,6 +154,8 @@ pub enum RpcRequest {
 pub enum RpcNotification {
     /// A new inbound rpc request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new inbound streaming rpc request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
 }
 
 /// The rpc actor.
@@ -164,6 +170,11 @@ pub struct Rpc<TSubstream> {
     peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
     /// Channels to send notifictions to upstream actors.
     rpc_handler_tx: channel::Sender<RpcNotification>,
+    /// Protocols of the inbound substreams which run the streaming rpc protocol
+    /// rather than the unary one.
+    streaming_protocols: HashSet<ProtocolId>,
+    /// Scores of remote peers, lowered when outbound rpcs time out.
+    peer_scores: PeerScores,
     /// The timeout duration for inbound rpc calls.
     inbound_rpc_timeout: Duration,
     /// The maximum number of concurrent outbound rpc requests that we will
@@ -174,6 +185,13 @@ pub struct Rpc<TSubstream> {
     // TODO(philiphayes): partition inbound queue by peer to prevent one peer
     // from starving other peers' rpcs?
     max_concurrent_inbound_rpcs: u32,
+    /// The maximum number of concurrent streaming rpcs in each direction.
+    /// Streams are long-lived, so they are limited separately from unary rpcs,
+    /// and new streams are rejected rather than queued once the limit is hit.
+    max_concurrent_streams: u32,
+    /// The maximum time an inbound streaming rpc may go without sending a
+    /// response, e.g., while the dialer grants no credits.
+    stream_idle_timeout: Duration,
 }
 
 impl<TSubstream> Rpc<TSubstream>
@@ -187,9 +205,13 @@ where
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         rpc_handler_tx: channel::Sender<RpcNotification>,
+        streaming_protocols: HashSet<ProtocolId>,
+        peer_scores: PeerScores,
         inbound_rpc_timeout: Duration,
         max_concurrent_outbound_rpcs: u32,
         max_concurrent_inbound_rpcs: u32,
+        max_concurrent_streams: u32,
+        stream_idle_timeout: Duration,
     ) -> Self {
         Self {
             executor,
@@ -197,9 +219,13 @@ where
             peer_mgr_notifs_rx,
             peer_mgr_reqs_tx,
             rpc_handler_tx,
+            streaming_protocols,
+            peer_scores,
             inbound_rpc_timeout,
             max_concurrent_outbound_rpcs,
             max_concurrent_inbound_rpcs,
+            max_concurrent_streams,
+            stream_idle_timeout,
         }
     }
 
@@ -211,25 +237,35 @@ where
         let peer_mgr_notifs_rx = self.peer_mgr_notifs_rx;
         let peer_mgr_reqs_tx = self.peer_mgr_reqs_tx;
         let rpc_handler_tx = self.rpc_handler_tx;
+        let streaming_protocols = self.streaming_protocols;
+        let peer_scores = self.peer_scores;
         let inbound_rpc_timeout = self.inbound_rpc_timeout;
         let max_concurrent_outbound_rpcs = self.max_concurrent_outbound_rpcs;
         let max_concurrent_inbound_rpcs = self.max_concurrent_inbound_rpcs;
+        let max_concurrent_streams = self.max_concurrent_streams as usize;
+        let stream_idle_timeout = self.stream_idle_timeout;
 
         // inbound and outbound requests use separate bounded executors to ensure
         // backpressure propagates independently and doesn't starve the other
-        // handler.
+        // handler. Likewise, streams get their own executors so that they
+        // cannot hold on to all the slots of unary rpcs.
 
         let outbound_handler = handle_outbounds(
             BoundedExecutor::new(max_concurrent_outbound_rpcs as usize, executor.clone()),
+            BoundedExecutor::new(max_concurrent_streams, executor.clone()),
             requests_rx,
             peer_mgr_reqs_tx,
+            peer_scores,
         );
 
         let inbound_handler = handle_inbounds(
-            BoundedExecutor::new(max_concurrent_inbound_rpcs as usize, executor),
+            BoundedExecutor::new(max_concurrent_inbound_rpcs as usize, executor.clone()),
+            BoundedExecutor::new(max_concurrent_streams, executor),
             peer_mgr_notifs_rx,
             rpc_handler_tx,
+            streaming_protocols,
             inbound_rpc_timeout,
+            stream_idle_timeout,
         );
 
         // drive inbound and outbound handlers to completion
@@ -239,38 +275,100 @@ where
     }
 }
 
-/// Handle all outbound rpcs.
+/// Handle all outbound rpcs. Streaming rpcs are rejected with
+/// [`RpcError::TooManyStreams`] while `stream_executor` is at capacity.
 async fn handle_outbounds<TSubstream>(
     executor: BoundedExecutor,
+    stream_executor: BoundedExecutor,
     mut requests_rx: channel::Receiver<RpcRequest>,
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_scores: PeerScores,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     while let Some(req) = requests_rx.next().await {
-        executor
-            .spawn(handle_outbound_rpc(peer_mgr_tx.clone(), req))
-            .await;
+        match req {
+            RpcRequest::SendRpc(peer_id, req) => {
+                executor
+                    .spawn(handle_outbound_rpc(
+                        peer_mgr_tx.clone(),
+                        peer_scores.clone(),
+                        peer_id,
+                        req,
+                    ))
+                    .await;
+            }
+            RpcRequest::SendStreamingRpc(peer_id, req) => {
+                let mut res_tx = req.res_tx.clone();
+                let f_stream = streaming_rpc::handle_outbound_rpc(
+                    peer_mgr_tx.clone(),
+                    peer_scores.clone(),
+                    peer_id,
+                    req,
+                );
+                if stream_executor.try_spawn(f_stream).is_err() {
+                    counters::LIBRA_NETWORK_RPC_MESSAGES
+                        .with_label_values(&["streaming_request", "rejected"])
+                        .inc();
+                    warn!(
+                        "Rejecting outbound streaming rpc to {}: too many concurrent streams",
+                        peer_id.short_str()
+                    );
+                    // The rpc client may already be gone.
+                    let _ = res_tx.try_send(Err(RpcError::TooManyStreams));
+                }
+            }
+        }
     }
 }
 
-/// Handle all inbound rpcs.
+/// Handle all inbound rpcs, dispatching the substreams of streaming rpc protocols
+/// to the streaming rpc protocol. Inbound streams are dropped, which resets
+/// their substream, while `stream_executor` is at capacity.
 async fn handle_inbounds<TSubstream>(
     executor: BoundedExecutor,
+    stream_executor: BoundedExecutor,
     mut peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
     rpc_handler_tx: channel::Sender<RpcNotification>,
+    streaming_protocols: HashSet<ProtocolId>,
     inbound_rpc_timeout: Duration,
+    stream_idle_timeout: Duration,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
 {
     while let Some(notif) = peer_mgr_notifs_rx.next().await {
-        executor
-            .spawn(handle_inbound_substream(
-                rpc_handler_tx.clone(),
-                notif,
-                inbound_rpc_timeout,
-            ))
-            .await;
+        match notif {
+            PeerManagerNotification::NewInboundSubstream(peer_id, substream)
+                if streaming_protocols.contains(&substream.protocol) =>
+            {
+                let f_stream = streaming_rpc::handle_inbound_substream(
+                    rpc_handler_tx.clone(),
+                    peer_id,
+                    substream.protocol,
+                    substream.substream,
+                    inbound_rpc_timeout,
+                    stream_idle_timeout,
+                );
+                if stream_executor.try_spawn(f_stream).is_err() {
+                    counters::LIBRA_NETWORK_RPC_MESSAGES
+                        .with_label_values(&["streaming_response", "rejected"])
+                        .inc();
+                    warn!(
+                        "Rejecting inbound streaming rpc from {}: too many concurrent streams",
+                        peer_id.short_str()
+                    );
+                }
+            }
+            notif => {
+                executor
+                    .spawn(handle_inbound_substream(
+                        rpc_handler_tx.clone(),
+                        notif,
+                        inbound_rpc_timeout,
+                    ))
+                    .await;
+            }
+        }
     }
 }
 
@@ -283,65 +381,67 @@ async fn handle_inbounds<TSubstream>(
 /// request is canceled, the substream will be dropped and a RST frame will be
 /// sent over the muxer closing the substream.
 ///
+/// Timed out requests lower the remote peer's score.
+///
 /// [`req.res_tx`]: OutboundRpcRequest::res_tx
 async fn handle_outbound_rpc<TSubstream>(
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
-    req: RpcRequest,
+    peer_scores: PeerScores,
+    peer_id: PeerId,
+    req: OutboundRpcRequest,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
-    match req {
-        RpcRequest::SendRpc(peer_id, req) => {
-            let protocol = req.protocol;
-            let req_data = req.data;
-            let mut res_tx = req.res_tx;
-            let timeout = req.timeout;
-
-            // Future to run the actual outbound rpc protocol and get the results.
-            let mut f_rpc_res = tokio::time::timeout(
-                timeout,
-                handle_outbound_rpc_inner(peer_mgr_tx, peer_id, protocol, req_data),
-            )
-            .map_err(Into::<RpcError>::into)
-            .map(|r| r.and_then(|x| x))
-            .boxed()
-            .fuse();
-
-            // If the rpc client drops their oneshot receiver, this future should
-            // cancel the request.
-            let mut f_rpc_cancel =
-                future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).fuse();
-
-            futures::select! {
-                res = f_rpc_res => {
-                    // Log any errors.
-                    if let Err(err) = &res {
-                        counters::LIBRA_NETWORK_RPC_MESSAGES
-                            .with_label_values(&["request", "failed"])
-                            .inc();
-                        warn!(
-                            "Error making outbound rpc request to {}: {:?}",
-                            peer_id.short_str(), err
-                        );
-                    }
-
-                    // Propagate the results to the rpc client layer.
-                    if res_tx.send(res).is_err() {
-                        counters::LIBRA_NETWORK_RPC_MESSAGES
-                            .with_label_values(&["request", "cancelled"])
-                            .inc();
-                        debug!("Rpc client canceled outbound rpc call to {}", peer_id.short_str());
-                    }
-                },
-                // The rpc client canceled the request
-                cancel = f_rpc_cancel => {
-                    counters::LIBRA_NETWORK_RPC_MESSAGES
-                        .with_label_values(&["request", "cancelled"])
-                        .inc();
-                    debug!("Rpc client canceled outbound rpc call to {}", peer_id.short_str());
-                },
+    let protocol = req.protocol;
+    let req_data = req.data;
+    let mut res_tx = req.res_tx;
+    let timeout = req.timeout;
+
+    // Future to run the actual outbound rpc protocol and get the results.
+    let mut f_rpc_res = tokio::time::timeout(
+        timeout,
+        handle_outbound_rpc_inner(peer_mgr_tx, peer_id, protocol, req_data),
+    )
+    .map_err(Into::<RpcError>::into)
+    .map(|r| r.and_then(|x| x))
+    .boxed()
+    .fuse();
+
+    // If the rpc client drops their oneshot receiver, this future should
+    // cancel the request.
+    let mut f_rpc_cancel = future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).fuse();
+
+    futures::select! {
+        res = f_rpc_res => {
+            // Log any errors.
+            if let Err(err) = &res {
+                counters::LIBRA_NETWORK_RPC_MESSAGES
+                    .with_label_values(&["request", "failed"])
+                    .inc();
+                warn!(
+                    "Error making outbound rpc request to {}: {:?}",
+                    peer_id.short_str(), err
+                );
+                if let RpcError::TimedOut = err {
+                    peer_scores.penalize(peer_id, Misbehavior::RpcTimeout);
+                }
             }
-        }
+
+            // Propagate the results to the rpc client layer.
+            if res_tx.send(res).is_err() {
+                counters::LIBRA_NETWORK_RPC_MESSAGES
+                    .with_label_values(&["request", "cancelled"])
+                    .inc();
+                debug!("Rpc client canceled outbound rpc call to {}", peer_id.short_str());
+            }
+        },
+        // The rpc client canceled the request
+        cancel = f_rpc_cancel => {
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&["request", "cancelled"])
+                .inc();
+            debug!("Rpc client canceled outbound rpc call to {}", peer_id.short_str());
+        },
     }
 }
 
@@ -12,6 +12,7 @@ use tokio::runtime::Runtime;
 
 async fn do_outbound_rpc_req<TSubstream>(
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_scores: PeerScores,
     recipient: PeerId,
     protocol: ProtocolId,
     data: Bytes,
@@ -27,8 +28,7 @@ where
         res_tx,
         timeout,
     };
-    let rpc_req = RpcRequest::SendRpc(recipient, outbound_req);
-    handle_outbound_rpc(peer_mgr_tx, rpc_req).await;
+    handle_outbound_rpc(peer_mgr_tx, peer_scores, recipient, outbound_req).await;
     res_rx.await.unwrap()
 }
 
@@ -78,6 +78,7 @@ fn upgrades() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -98,6 +99,7 @@ fn upgrades() {
     let f_dialer_upgrade = async move {
         let res = do_outbound_rpc_req(
             dialer_peer_mgr_reqs_tx,
+            PeerScores::default(),
             listener_peer_id,
             ProtocolId::from_static(protocol_id),
             Bytes::from_static(req_data),
@@ -140,6 +142,7 @@ fn listener_close_before_response() {
     let f_dialer_upgrade = async move {
         let res = do_outbound_rpc_req(
             dialer_peer_mgr_reqs_tx,
+            PeerScores::default(),
             listener_peer_id,
             ProtocolId::from_static(protocol_id),
             Bytes::from_static(req_data),
@@ -202,6 +205,7 @@ fn listener_close_before_dialer_send() {
     let f_dialer_upgrade = async move {
         let res = do_outbound_rpc_req(
             dialer_peer_mgr_reqs_tx,
+            PeerScores::default(),
             listener_peer_id,
             ProtocolId::from_static(protocol_id),
             Bytes::from_static(req_data),
@@ -282,6 +286,7 @@ fn dialer_close_before_listener_send() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -397,9 +402,11 @@ fn outbound_rpc_timeout() {
     let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
 
     // Make an outbound substream request; listener hangs so this should timeout.
+    let peer_scores = PeerScores::default();
     let f_dialer_upgrade = async move {
         let res = do_outbound_rpc_req(
             dialer_peer_mgr_reqs_tx,
+            peer_scores.clone(),
             listener_peer_id,
             ProtocolId::from_static(protocol_id),
             Bytes::from_static(req_data),
@@ -413,6 +420,9 @@ fn outbound_rpc_timeout() {
             RpcError::TimedOut => {}
             err => panic!("Unexpected error: {:?}, expected TimedOut", err),
         };
+
+        // The listener is penalized for the timeout.
+        assert!(peer_scores.score(&listener_peer_id) < 0.0);
     };
 
     let f = join(f_dialer_peer_mgr, f_dialer_upgrade);
@@ -470,8 +480,12 @@ fn outbound_cancellation_before_send() {
         res_tx,
         timeout: Duration::from_secs(1),
     };
-    let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-    let f_rpc = handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req);
+    let f_rpc = handle_outbound_rpc(
+        dialer_peer_mgr_reqs_tx,
+        PeerScores::default(),
+        listener_peer_id,
+        outbound_req,
+    );
 
     // drop res_rx to cancel the rpc request
     drop(res_rx);
@@ -518,9 +532,13 @@ fn outbound_cancellation_recv() {
             res_tx,
             timeout: Duration::from_secs(1),
         };
-        let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-        let (f_rpc, f_rpc_done) =
-            handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req).remote_handle();
+        let (f_rpc, f_rpc_done) = handle_outbound_rpc(
+            dialer_peer_mgr_reqs_tx,
+            PeerScores::default(),
+            listener_peer_id,
+            outbound_req,
+        )
+        .remote_handle();
         executor.spawn(f_rpc);
 
         futures::select! {
@@ -601,9 +619,13 @@ fn rpc_protocol() {
         dialer_peer_mgr_notifs_rx,
         dialer_peer_mgr_reqs_tx,
         rpc_handler_tx,
+        HashSet::new(),
+        PeerScores::default(),
         Duration::from_millis(500),
         10,
         10,
+        10,
+        Duration::from_secs(1),
     );
 
     // Fake the dialer NetworkProvider
@@ -652,9 +674,13 @@ fn rpc_protocol() {
         listener_peer_mgr_notifs_rx,
         listener_peer_mgr_reqs_tx,
         listener_rpc_notifs_tx,
+        HashSet::new(),
+        PeerScores::default(),
         Duration::from_millis(500),
         10,
         10,
+        10,
+        Duration::from_secs(1),
     );
 
     // Fake the listener NetworkProvider
@@ -680,6 +706,7 @@ fn rpc_protocol() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
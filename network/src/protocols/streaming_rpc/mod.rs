@@ -0,0 +1,468 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protocol for making and handling streaming Remote Procedure Calls
+//!
+//! # SLURPS: Simple Libra Unary Rpc Protocol, Streaming
+//!
+//! Like [SLURP](crate::protocols::rpc), every streaming rpc call runs in its own
+//! substream, negotiated to the rpc method being called, and the request and
+//! responses are opaque blobs left for higher layers to specify. Unlike SLURP,
+//! a single request is answered with a stream of response frames, e.g., the
+//! chunks of a state sync or backup transfer.
+//!
+//! The response stream is flow-controlled: the listener may only send as many
+//! response frames as the dialer granted it credits for, and the dialer only
+//! grants new credits once the frames received so far have been taken by the
+//! rpc client. A slow client therefore applies backpressure all the way to the
+//! remote application producing the responses.
+//!
+//! All messages are length-prefixed. Response frames start with a one byte
+//! kind: data frames carry a response, while an error frame
+//! notifies the dialer that the remote failed to produce the rest of the
+//! stream. Credit frames are a single big-endian `u32`.
+//!
+//! ## Wire Protocol (dialer):
+//!
+//! To make a streaming rpc request to a remote peer, the dialer
+//!
+//! 1. Requests a new outbound substream from the muxer.
+//! 2. Negotiates the substream using [`protocol-select`] to the rpc method they
+//!    wish to call.
+//! 3. Sends the serialized request arguments on the newly negotiated substream,
+//!    followed by a credit frame granting the initial [`RESPONSE_WINDOW`].
+//! 4. Awaits response frames, granting more credits as the rpc client consumes
+//!    them, until the listener half-closes.
+//! 5. Half-closes their output side to complete the substream close.
+//!
+//! The dialer cancels the call by dropping the substream.
+//!
+//! ## Wire Protocol (listener):
+//!
+//! To handle new streaming rpc requests from remote peers, the listener
+//!
+//! 1. Polls for new inbound substreams on the muxer.
+//! 2. Negotiates inbound substreams using [`protocol-select`].
+//! 3. Awaits the serialized request arguments on the newly negotiated substream.
+//! 4. Handles the request by sending it up through the
+//!    [`NetworkProvider`](crate::interface::NetworkProvider) actor to a higher
+//!    layer client, who then streams the serialized responses back down to the
+//!    rpc layer.
+//! 5. Sends each response to the dialer, as long as it has credits left.
+//! 6. Half-closes their output side once the client ends the stream.
+//!
+//! The dialer half-closing their side before the end of the stream cancels the
+//! call.
+//!
+//! [`protocol-select`]: ../../../netcore/negotiate/index.html
+
+use crate::{
+    counters,
+    peer_manager::PeerManagerRequestSender,
+    peer_score::{Misbehavior, PeerScores},
+    protocols::rpc::{error::RpcError, RpcNotification},
+    sink::NetworkSinkExt,
+    ProtocolId,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::FutureExt,
+    io::{AsyncRead, AsyncWrite},
+    sink::SinkExt,
+    stream::{FusedStream, Stream, StreamExt},
+    task::{Context, Poll},
+};
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use netcore::compat::IoCompat;
+use std::{cmp, io, pin::Pin, time::Duration};
+use tokio::time::Instant;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+#[cfg(test)]
+mod test;
+
+/// The number of response frames the listener may send ahead of the rpc client.
+pub const RESPONSE_WINDOW: u32 = 16;
+/// Response frame kind carrying a response.
+const DATA_FRAME: u8 = 0;
+/// Response frame kind ending the stream after the remote failed to handle the request.
+const ERROR_FRAME: u8 = 1;
+
+/// A wrapper struct for an inbound streaming rpc request and its associated context.
+#[derive(Debug)]
+pub struct InboundStreamingRpcRequest {
+    /// Rpc method identifier, e.g., `/libra/rpc/0.1.0/state-synchronizer/0.1.0`.
+    /// This is used to dispatch the request to the corresponding client handler.
+    pub protocol: ProtocolId,
+    /// The serialized request data received from the sender.
+    pub data: Bytes,
+    /// Channel over which the serialized responses are streamed from the upper
+    /// client layer to the rpc layer.
+    ///
+    /// The stream ends when the upper layer drops the channel. Sending an
+    /// [`RpcError`] aborts the stream instead. Sends block while the remote has
+    /// no credits left, and fail once the call is canceled or times out.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+}
+
+/// A wrapper struct for an outbound streaming rpc request and its associated context.
+#[derive(Debug)]
+pub struct OutboundStreamingRpcRequest {
+    /// Rpc method identifier. This is the protocol we will negotiate our outbound
+    /// substream to.
+    pub protocol: ProtocolId,
+    /// The serialized request data to be sent to the receiver.
+    pub data: Bytes,
+    /// Channel over which the responses are streamed from the rpc layer to the
+    /// upper client layer. Errors end the stream.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+    /// Resolves once the upper client layer drops its [`ResponseStream`],
+    /// canceling the call.
+    pub cancel_rx: oneshot::Receiver<()>,
+    /// The maximum time to wait for the substream to open and then for each
+    /// response frame. If the timeout elapses, the rpc layer will send an
+    /// [`RpcError::TimedOut`] error over the `res_tx` channel.
+    pub timeout: Duration,
+}
+
+impl OutboundStreamingRpcRequest {
+    /// Create a new request along with the stream its responses are received on.
+    pub fn new(protocol: ProtocolId, data: Bytes, timeout: Duration) -> (Self, ResponseStream) {
+        let (res_tx, res_rx) = mpsc::channel(1);
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let req = Self {
+            protocol,
+            data,
+            res_tx,
+            cancel_rx,
+            timeout,
+        };
+        let res_stream = ResponseStream {
+            res_rx,
+            _cancel_tx: cancel_tx,
+        };
+        (req, res_stream)
+    }
+}
+
+/// The responses to an outbound streaming rpc request. Dropping the stream
+/// cancels the call.
+#[derive(Debug)]
+pub struct ResponseStream {
+    res_rx: mpsc::Receiver<Result<Bytes, RpcError>>,
+    _cancel_tx: oneshot::Sender<()>,
+}
+
+impl Stream for ResponseStream {
+    type Item = Result<Bytes, RpcError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        self.res_rx.poll_next_unpin(context)
+    }
+}
+
+impl FusedStream for ResponseStream {
+    fn is_terminated(&self) -> bool {
+        self.res_rx.is_terminated()
+    }
+}
+
+fn credit_frame(credits: u32) -> Bytes {
+    let mut frame = BytesMut::with_capacity(4);
+    frame.put_u32(credits);
+    frame.freeze()
+}
+
+fn parse_credit_frame(mut frame: BytesMut) -> Result<u32, RpcError> {
+    if frame.len() != 4 {
+        return Err(RpcError::InvalidStreamFrame);
+    }
+    Ok(frame.get_u32())
+}
+
+fn data_frame(data: Bytes) -> Bytes {
+    let mut frame = BytesMut::with_capacity(1 + data.len());
+    frame.put_u8(DATA_FRAME);
+    frame.extend_from_slice(&data);
+    frame.freeze()
+}
+
+fn parse_response_frame(frame: Bytes) -> Result<Bytes, RpcError> {
+    match frame.first() {
+        Some(&DATA_FRAME) => Ok(frame.slice(1..)),
+        Some(&ERROR_FRAME) if frame.len() == 1 => Err(RpcError::StreamAborted),
+        _ => Err(RpcError::InvalidStreamFrame),
+    }
+}
+
+/// Handle an outbound streaming rpc request. Open a new substream then run the
+/// outbound streaming rpc protocol over the substream.
+///
+/// Responses and errors are propagated up to the rpc client through the
+/// [`req.res_tx`] channel. If the client cancels the request by dropping its
+/// [`ResponseStream`], the substream is dropped and a RST frame is sent over the
+/// muxer closing the substream.
+///
+/// Timed out requests lower the remote peer's score.
+///
+/// [`req.res_tx`]: OutboundStreamingRpcRequest::res_tx
+pub(crate) async fn handle_outbound_rpc<TSubstream>(
+    peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_scores: PeerScores,
+    peer_id: PeerId,
+    req: OutboundStreamingRpcRequest,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let mut res_tx = req.res_tx;
+    let mut f_rpc_res = handle_outbound_rpc_inner(
+        peer_mgr_tx,
+        peer_id,
+        req.protocol,
+        req.data,
+        res_tx.clone(),
+        req.timeout,
+    )
+    .boxed()
+    .fuse();
+    let mut f_rpc_cancel = req.cancel_rx.fuse();
+
+    futures::select! {
+        res = f_rpc_res => {
+            if let Err(err) = res {
+                counters::LIBRA_NETWORK_RPC_MESSAGES
+                    .with_label_values(&["streaming_request", "failed"])
+                    .inc();
+                warn!(
+                    "Error making outbound streaming rpc request to {}: {:?}",
+                    peer_id.short_str(), err
+                );
+                if let RpcError::TimedOut = err {
+                    peer_scores.penalize(peer_id, Misbehavior::RpcTimeout);
+                }
+                // Propagate the error to the rpc client layer, unless it is gone.
+                let _ = res_tx.send(Err(err)).await;
+            }
+        },
+        // The rpc client canceled the request
+        _ = f_rpc_cancel => {
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&["streaming_request", "cancelled"])
+                .inc();
+            debug!(
+                "Rpc client canceled outbound streaming rpc call to {}",
+                peer_id.short_str()
+            );
+        },
+    }
+}
+
+async fn handle_outbound_rpc_inner<TSubstream>(
+    mut peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    req_data: Bytes,
+    mut res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+    timeout: Duration,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    // Request a new substream with the peer.
+    let substream =
+        tokio::time::timeout(timeout, peer_mgr_tx.open_substream(peer_id, protocol)).await??;
+    let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
+    // Send the rpc request data, along with the initial credits.
+    let req_len = req_data.len();
+    substream.buffered_send(req_data).await?;
+    substream.send(credit_frame(RESPONSE_WINDOW)).await?;
+    counters::LIBRA_NETWORK_RPC_MESSAGES
+        .with_label_values(&["streaming_request", "sent"])
+        .inc();
+    counters::LIBRA_NETWORK_RPC_BYTES
+        .with_label_values(&["streaming_request", "sent"])
+        .observe(req_len as f64);
+
+    // Credits granted to the listener and not used yet.
+    let mut credits = RESPONSE_WINDOW;
+    // Responses taken by the rpc client since we last granted credits.
+    let mut consumed = 0;
+    loop {
+        let frame = match tokio::time::timeout(timeout, substream.next()).await? {
+            Some(frame) => frame?.freeze(),
+            // The listener half-closed their side: this is the end of the stream.
+            None => break,
+        };
+        if credits == 0 {
+            return Err(RpcError::FlowControlViolation);
+        }
+        credits -= 1;
+        let res_data = parse_response_frame(frame)?;
+        counters::LIBRA_NETWORK_RPC_MESSAGES
+            .with_label_values(&["streaming_response", "received"])
+            .inc();
+        counters::LIBRA_NETWORK_RPC_BYTES
+            .with_label_values(&["streaming_response", "received"])
+            .observe(res_data.len() as f64);
+
+        // Wait for the rpc client to make room for the response.
+        if res_tx.send(Ok(res_data)).await.is_err() {
+            // The rpc client dropped its stream; the cancellation is handled by the caller.
+            return Ok(());
+        }
+        consumed += 1;
+        if consumed >= RESPONSE_WINDOW / 2 {
+            substream.send(credit_frame(consumed)).await?;
+            credits += consumed;
+            consumed = 0;
+        }
+    }
+
+    // Half-close our side to complete the substream close.
+    substream.close().await?;
+    Ok(())
+}
+
+/// Events the listener waits for while streaming responses.
+enum ListenerEvent {
+    /// A credit frame from the dialer, or their half-close.
+    Credits(Option<Result<BytesMut, io::Error>>),
+    /// A response from the upper layer, or the end of the stream.
+    Response(Option<Result<Bytes, RpcError>>),
+}
+
+/// Handle a new inbound substream negotiated to a streaming rpc protocol. Run
+/// the inbound streaming rpc protocol over the substream.
+///
+/// The `timeout` bounds the time spent waiting for the request, and then for
+/// each response from the upper layer or for credits from the dialer. The
+/// `idle_timeout` bounds the time between two responses, so that a dialer
+/// cannot hold on to the stream without consuming it, e.g., by only granting
+/// zero credits.
+pub(crate) async fn handle_inbound_substream<TSubstream>(
+    notification_tx: channel::Sender<RpcNotification>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    substream: TSubstream,
+    timeout: Duration,
+    idle_timeout: Duration,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    if let Err(err) = handle_inbound_substream_inner(
+        notification_tx,
+        peer_id,
+        protocol,
+        substream,
+        timeout,
+        idle_timeout,
+    )
+    .await
+    {
+        counters::LIBRA_NETWORK_RPC_MESSAGES
+            .with_label_values(&["streaming_response", "failed"])
+            .inc();
+        warn!(
+            "Error handling inbound streaming rpc request from {}: {:?}",
+            peer_id.short_str(),
+            err
+        );
+    }
+}
+
+async fn handle_inbound_substream_inner<TSubstream>(
+    mut notification_tx: channel::Sender<RpcNotification>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    substream: TSubstream,
+    timeout: Duration,
+    idle_timeout: Duration,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
+    // Read the rpc request data.
+    let req_data = match tokio::time::timeout(timeout, substream.next()).await? {
+        Some(req_data) => req_data?.freeze(),
+        None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    };
+    counters::LIBRA_NETWORK_RPC_MESSAGES
+        .with_label_values(&["streaming_request", "received"])
+        .inc();
+
+    // Forward the request to the upper layer. The channel has no buffer beyond
+    // the slot of its sender, so that the upper layer only produces responses
+    // as fast as the dialer consumes them.
+    let (res_tx, mut res_rx) = mpsc::channel(0);
+    let notification = RpcNotification::RecvStreamingRpc(
+        peer_id,
+        InboundStreamingRpcRequest {
+            protocol,
+            data: req_data,
+            res_tx,
+        },
+    );
+    // TODO(philiphayes): impl correct shutdown process so this never panics
+    notification_tx.send(notification).await.unwrap();
+
+    let mut credits: u32 = 0;
+    // The stream is idle until the next response is sent.
+    let mut idle_deadline = Instant::now() + idle_timeout;
+    loop {
+        let f_event = async {
+            if credits == 0 {
+                ListenerEvent::Credits(substream.next().await)
+            } else {
+                futures::select! {
+                    frame = substream.next().fuse() => ListenerEvent::Credits(frame),
+                    res = res_rx.next() => ListenerEvent::Response(res),
+                }
+            }
+        };
+        let deadline = cmp::min(Instant::now() + timeout, idle_deadline);
+        match tokio::time::timeout_at(deadline, f_event).await? {
+            ListenerEvent::Credits(Some(frame)) => {
+                credits = credits
+                    .checked_add(parse_credit_frame(frame?)?)
+                    .ok_or(RpcError::InvalidStreamFrame)?;
+            }
+            ListenerEvent::Credits(None) => {
+                // The dialer half-closed before the end of the stream.
+                counters::LIBRA_NETWORK_RPC_MESSAGES
+                    .with_label_values(&["streaming_response", "cancelled"])
+                    .inc();
+                debug!(
+                    "Dialer canceled inbound streaming rpc call from {}",
+                    peer_id.short_str()
+                );
+                return Ok(());
+            }
+            ListenerEvent::Response(Some(Ok(res_data))) => {
+                let res_len = res_data.len();
+                substream.send(data_frame(res_data)).await?;
+                credits -= 1;
+                idle_deadline = Instant::now() + idle_timeout;
+                counters::LIBRA_NETWORK_RPC_MESSAGES
+                    .with_label_values(&["streaming_response", "sent"])
+                    .inc();
+                counters::LIBRA_NETWORK_RPC_BYTES
+                    .with_label_values(&["streaming_response", "sent"])
+                    .observe(res_len as f64);
+            }
+            ListenerEvent::Response(Some(Err(err))) => {
+                // Let the dialer know that the stream is incomplete.
+                substream.send(Bytes::from_static(&[ERROR_FRAME])).await?;
+                substream.close().await?;
+                return Err(err);
+            }
+            ListenerEvent::Response(None) => {
+                // The upper layer ended the stream.
+                substream.close().await?;
+                return Ok(());
+            }
+        }
+    }
+}
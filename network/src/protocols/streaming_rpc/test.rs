@@ -0,0 +1,280 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::peer_manager::PeerManagerRequest;
+use anyhow::anyhow;
+use futures::future::{join, join4, Future};
+use memsocket::MemorySocket;
+use std::fmt::Debug;
+use tokio::runtime::Runtime;
+
+const PROTOCOL: &[u8] = b"/libra/rpc/0.1.0/chunks/0.1.0";
+const TIMEOUT: Duration = Duration::from_secs(1);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// On the next OpenSubstream event, return the given substream.
+async fn mock_peer_manager<TSubstream: Debug>(
+    mut peer_mgr_rx: channel::Receiver<PeerManagerRequest<TSubstream>>,
+    substream: TSubstream,
+) {
+    match peer_mgr_rx.next().await.unwrap() {
+        PeerManagerRequest::OpenSubstream(_peer_id, protocol, substream_tx) => {
+            assert_eq!(protocol.as_ref(), PROTOCOL);
+            substream_tx.send(Ok(substream)).unwrap();
+        }
+        req => panic!(
+            "Unexpected PeerManagerRequest: {:?}, expected OpenSubstream",
+            req
+        ),
+    }
+}
+
+// Run the dialer side of a streaming rpc over `substream`, returning the stream of responses.
+fn dialer<TSubstream>(substream: TSubstream) -> (impl Future<Output = ((), ())>, ResponseStream)
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin + Debug,
+{
+    let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = channel::new_test(8);
+    let peer_mgr_reqs_tx = PeerManagerRequestSender::new(peer_mgr_reqs_tx);
+    let (req, res_stream) = OutboundStreamingRpcRequest::new(
+        ProtocolId::from_static(PROTOCOL),
+        Bytes::from_static(b"hello"),
+        TIMEOUT,
+    );
+    let f_dialer = join(
+        mock_peer_manager(peer_mgr_reqs_rx, substream),
+        handle_outbound_rpc(
+            peer_mgr_reqs_tx,
+            PeerScores::default(),
+            PeerId::random(),
+            req,
+        ),
+    );
+    (f_dialer, res_stream)
+}
+
+// Run the listener side of a streaming rpc over `substream`, returning the inbound request.
+fn listener<TSubstream>(
+    substream: TSubstream,
+) -> (
+    impl Future<Output = ()>,
+    impl Future<Output = InboundStreamingRpcRequest>,
+)
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let (notifs_tx, mut notifs_rx) = channel::new_test(8);
+    let f_listener = handle_inbound_substream(
+        notifs_tx,
+        PeerId::random(),
+        ProtocolId::from_static(PROTOCOL),
+        substream,
+        TIMEOUT,
+        IDLE_TIMEOUT,
+    );
+    let f_req = async move {
+        match notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvStreamingRpc(_peer_id, req) => {
+                assert_eq!(req.protocol.as_ref(), PROTOCOL);
+                assert_eq!(req.data.as_ref(), b"hello");
+                req
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+    (f_listener, f_req)
+}
+
+fn response(i: u32) -> Bytes {
+    Bytes::from(i.to_be_bytes().to_vec())
+}
+
+#[test]
+fn streaming_rpc() {
+    ::libra_logger::try_init_for_testing();
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let (f_dialer, res_stream) = dialer(dialer_substream);
+    let (f_listener, f_req) = listener(listener_substream);
+    // Stream more responses than fit in the initial window.
+    let num_responses = 3 * RESPONSE_WINDOW + 1;
+
+    let f_server = async move {
+        let mut req = f_req.await;
+        for i in 0..num_responses {
+            req.res_tx.send(Ok(response(i))).await.unwrap();
+        }
+    };
+    let f_client = async move {
+        let responses: Vec<_> = res_stream.map(Result::unwrap).collect().await;
+        let expected: Vec<_> = (0..num_responses).map(response).collect();
+        assert_eq!(responses, expected);
+    };
+
+    Runtime::new()
+        .unwrap()
+        .block_on(join4(f_dialer, f_listener, f_server, f_client));
+}
+
+#[test]
+fn listener_error_aborts_stream() {
+    ::libra_logger::try_init_for_testing();
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let (f_dialer, mut res_stream) = dialer(dialer_substream);
+    let (f_listener, f_req) = listener(listener_substream);
+
+    let f_server = async move {
+        let mut req = f_req.await;
+        req.res_tx.send(Ok(response(0))).await.unwrap();
+        req.res_tx
+            .send(Err(RpcError::ApplicationError(anyhow!("failed"))))
+            .await
+            .unwrap();
+    };
+    let f_client = async move {
+        assert_eq!(res_stream.next().await.unwrap().unwrap(), response(0));
+        match res_stream.next().await.unwrap() {
+            Err(RpcError::StreamAborted) => {}
+            res => panic!("Unexpected response: {:?}, expected StreamAborted", res),
+        }
+        assert!(res_stream.next().await.is_none());
+    };
+
+    Runtime::new()
+        .unwrap()
+        .block_on(join4(f_dialer, f_listener, f_server, f_client));
+}
+
+#[test]
+fn dialer_cancellation() {
+    ::libra_logger::try_init_for_testing();
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let (f_dialer, mut res_stream) = dialer(dialer_substream);
+    let (f_listener, f_req) = listener(listener_substream);
+
+    // The server keeps streaming responses until the call is canceled.
+    let f_server = async move {
+        let mut req = f_req.await;
+        let mut i = 0;
+        while req.res_tx.send(Ok(response(i))).await.is_ok() {
+            i += 1;
+        }
+    };
+    let f_client = async move {
+        assert_eq!(res_stream.next().await.unwrap().unwrap(), response(0));
+        drop(res_stream);
+    };
+
+    Runtime::new()
+        .unwrap()
+        .block_on(join4(f_dialer, f_listener, f_server, f_client));
+}
+
+#[test]
+fn listener_waits_for_credits() {
+    ::libra_logger::try_init_for_testing();
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let (f_listener, f_req) = listener(listener_substream);
+
+    let f_server = async move {
+        let mut req = f_req.await;
+        for i in 0..3 {
+            req.res_tx.send(Ok(response(i))).await.unwrap();
+        }
+    };
+    // Run the dialer side of the protocol by hand, granting as few credits as possible.
+    let f_dialer = async move {
+        let mut substream =
+            Framed::new(IoCompat::new(dialer_substream), LengthDelimitedCodec::new());
+        substream.send(Bytes::from_static(b"hello")).await.unwrap();
+        substream.send(credit_frame(2)).await.unwrap();
+        for i in 0..2 {
+            let frame = substream.next().await.unwrap().unwrap().freeze();
+            assert_eq!(parse_response_frame(frame).unwrap(), response(i));
+        }
+
+        // The listener has no credits left.
+        let res = tokio::time::timeout(Duration::from_millis(100), substream.next()).await;
+        assert!(res.is_err());
+
+        substream.send(credit_frame(2)).await.unwrap();
+        let frame = substream.next().await.unwrap().unwrap().freeze();
+        assert_eq!(parse_response_frame(frame).unwrap(), response(2));
+        // The listener half-closes at the end of the stream.
+        assert!(substream.next().await.is_none());
+        substream.close().await.unwrap();
+    };
+
+    Runtime::new()
+        .unwrap()
+        .block_on(join(join(f_dialer, f_listener), f_server));
+}
+
+#[test]
+fn listener_idle_timeout() {
+    ::libra_logger::try_init_for_testing();
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let (f_listener, f_req) = listener(listener_substream);
+
+    // The server keeps producing responses until the listener gives up on the stream.
+    let f_server = async move {
+        let mut req = f_req.await;
+        while req.res_tx.send(Ok(response(0))).await.is_ok() {}
+    };
+    // Run the dialer side of the protocol by hand, granting zero credits more often than the
+    // per-event timeout, so that only the idle timeout ends the stream.
+    let f_dialer = async move {
+        let start = std::time::Instant::now();
+        let mut substream =
+            Framed::new(IoCompat::new(dialer_substream), LengthDelimitedCodec::new());
+        substream.send(Bytes::from_static(b"hello")).await.unwrap();
+        loop {
+            if substream.send(credit_frame(0)).await.is_err() {
+                break;
+            }
+            match tokio::time::timeout(Duration::from_millis(300), substream.next()).await {
+                Ok(Some(Ok(frame))) => panic!("Unexpected frame: {:?}", frame),
+                // The listener dropped the substream.
+                Ok(_) => break,
+                Err(_) => {}
+            }
+        }
+        assert!(start.elapsed() >= IDLE_TIMEOUT);
+    };
+
+    Runtime::new()
+        .unwrap()
+        .block_on(join(join(f_dialer, f_listener), f_server));
+}
+
+#[test]
+fn invalid_frames() {
+    assert_eq!(
+        parse_response_frame(data_frame(response(7))).unwrap(),
+        response(7)
+    );
+    assert_eq!(
+        parse_credit_frame(credit_frame(7).as_ref().into()).unwrap(),
+        7
+    );
+
+    match parse_response_frame(Bytes::from_static(&[ERROR_FRAME])) {
+        Err(RpcError::StreamAborted) => {}
+        res => panic!("Unexpected result: {:?}, expected StreamAborted", res),
+    }
+    for frame in &[&[][..], &[ERROR_FRAME, 0], &[2, 0]] {
+        match parse_response_frame(Bytes::from_static(*frame)) {
+            Err(RpcError::InvalidStreamFrame) => {}
+            res => panic!("Unexpected result: {:?}, expected InvalidStreamFrame", res),
+        }
+    }
+    match parse_credit_frame(BytesMut::from(&[0, 0, 1][..])) {
+        Err(RpcError::InvalidStreamFrame) => {}
+        res => panic!("Unexpected result: {:?}, expected InvalidStreamFrame", res),
+    }
+}
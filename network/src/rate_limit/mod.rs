@@ -0,0 +1,215 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per peer and per protocol rate limits of direct-send traffic.
+//!
+//! Every (peer, protocol) pair with a configured limit gets a [`RateLimiter`], made of two token
+//! buckets: one counting messages and one counting bytes. Each bucket is refilled at its
+//! configured rate and holds up to one second worth of tokens, which bounds bursts. A message
+//! larger than a bucket's capacity is let through once the bucket is full, and leaves the bucket
+//! in debt until it has been paid back.
+//!
+//! Inbound messages exceeding the limits of the sending peer are dropped, see
+//! [`InboundRateLimiters`]. Outbound messages instead wait until the limiter has enough tokens,
+//! which applies backpressure to the outbound message queue of the peer.
+use crate::{counters, ProtocolId};
+use libra_config::config::{RateLimitConfig, TokenBucketConfig};
+use libra_types::PeerId;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+/// A token bucket refilled at `rate` tokens per second, holding up to `rate` tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    /// Available tokens. Negative while the bucket is in debt.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + self.rate * elapsed.as_secs_f64()).min(self.rate);
+        self.last_refill = now;
+    }
+
+    /// The tokens which must be available before taking `cost` tokens.
+    fn required(&self, cost: f64) -> f64 {
+        cost.min(self.rate)
+    }
+
+    /// How long to wait until `cost` tokens can be taken.
+    fn delay(&mut self, cost: f64, now: Instant) -> Duration {
+        self.refill(now);
+        let missing = self.required(cost) - self.tokens;
+        if missing > 0.0 {
+            Duration::from_secs_f64(missing / self.rate)
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost;
+    }
+}
+
+/// Rate limiter of the messages of a single protocol exchanged with a single peer.
+#[derive(Debug)]
+pub struct RateLimiter {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &TokenBucketConfig) -> Self {
+        Self::new_at(config, Instant::now())
+    }
+
+    /// Take the tokens for a message of `size` bytes, and return how long to wait before sending
+    /// the message so as to stay within the limits.
+    pub fn reserve(&mut self, size: usize) -> Duration {
+        self.reserve_at(size, Instant::now())
+    }
+
+    fn new_at(config: &TokenBucketConfig, now: Instant) -> Self {
+        let bucket = |rate| {
+            if rate == 0 {
+                None
+            } else {
+                Some(TokenBucket::new(rate, now))
+            }
+        };
+        Self {
+            messages: bucket(config.messages_per_sec),
+            bytes: bucket(config.bytes_per_sec),
+        }
+    }
+
+    fn buckets(&mut self, size: usize) -> impl Iterator<Item = (&mut TokenBucket, f64)> + '_ {
+        self.messages.iter_mut().map(|bucket| (bucket, 1.0)).chain(
+            self.bytes
+                .iter_mut()
+                .map(move |bucket| (bucket, size as f64)),
+        )
+    }
+
+    /// Take the tokens for a message of `size` bytes if they are available. Returns false if the
+    /// message exceeds the limits, in which case no tokens are taken.
+    fn try_acquire_at(&mut self, size: usize, now: Instant) -> bool {
+        if self.delay_at(size, now) > Duration::from_secs(0) {
+            return false;
+        }
+        self.buckets(size)
+            .for_each(|(bucket, cost)| bucket.take(cost));
+        true
+    }
+
+    fn reserve_at(&mut self, size: usize, now: Instant) -> Duration {
+        let delay = self.delay_at(size, now);
+        self.buckets(size)
+            .for_each(|(bucket, cost)| bucket.take(cost));
+        delay
+    }
+
+    fn delay_at(&mut self, size: usize, now: Instant) -> Duration {
+        self.buckets(size)
+            .map(|(bucket, cost)| bucket.delay(cost, now))
+            .max()
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+}
+
+/// The configured rate limits, keyed by protocol.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    limits: HashMap<ProtocolId, RateLimitConfig>,
+}
+
+impl RateLimits {
+    pub fn new(configs: Vec<RateLimitConfig>) -> Self {
+        let limits = configs
+            .into_iter()
+            .map(|config| (ProtocolId::from(config.protocol.clone()), config))
+            .collect();
+        Self { limits }
+    }
+
+    /// A limiter of outbound messages on `protocol`, if the protocol is rate limited.
+    pub fn outbound_limiter(&self, protocol: &ProtocolId) -> Option<RateLimiter> {
+        self.limits
+            .get(protocol)
+            .map(|config| RateLimiter::new(&config.outbound))
+    }
+
+    fn inbound_limiter(&self, protocol: &ProtocolId, now: Instant) -> Option<RateLimiter> {
+        self.limits
+            .get(protocol)
+            .map(|config| RateLimiter::new_at(&config.inbound, now))
+    }
+}
+
+/// A cloneable handle to the limiters of inbound messages, shared by all the inbound substreams
+/// of a peer, so that a peer cannot evade its limits by opening more substreams.
+#[derive(Clone, Debug, Default)]
+pub struct InboundRateLimiters {
+    limits: RateLimits,
+    limiters: Arc<Mutex<HashMap<(PeerId, ProtocolId), RateLimiter>>>,
+}
+
+impl InboundRateLimiters {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            limiters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Whether a message of `size` bytes received from `peer_id` on `protocol` is within the
+    /// limits. Messages exceeding the limits are counted and should be dropped.
+    pub fn allow(&self, peer_id: PeerId, protocol: &ProtocolId, size: usize) -> bool {
+        self.allow_at(peer_id, protocol, size, Instant::now())
+    }
+
+    fn allow_at(&self, peer_id: PeerId, protocol: &ProtocolId, size: usize, now: Instant) -> bool {
+        let mut limiters = self.limiters.lock().unwrap();
+        let limiter = match limiters.entry((peer_id, protocol.clone())) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match self.limits.inbound_limiter(protocol, now) {
+                Some(limiter) => entry.insert(limiter),
+                // The protocol is not rate limited.
+                None => return true,
+            },
+        };
+        let allowed = limiter.try_acquire_at(size, now);
+        if !allowed {
+            counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+                .with_label_values(&[&*String::from_utf8_lossy(protocol), "inbound"])
+                .inc();
+        }
+        allowed
+    }
+
+    /// Forget the limiters of `peer_id`, e.g., once we are no longer connected to it.
+    pub fn remove_peer(&self, peer_id: &PeerId) {
+        self.limiters
+            .lock()
+            .unwrap()
+            .retain(|(limited_peer_id, _), _| limited_peer_id != peer_id);
+    }
+}
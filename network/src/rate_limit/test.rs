@@ -0,0 +1,113 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+const PROTOCOL: &str = "/libra/direct-send/0.1.0/rate-limited/0.1.0";
+
+fn millis(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn message_limit() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new_at(&TokenBucketConfig::new(2, 0), now);
+
+    assert!(limiter.try_acquire_at(1000, now));
+    assert!(limiter.try_acquire_at(1000, now));
+    assert!(!limiter.try_acquire_at(1000, now));
+    // Tokens are refilled over time.
+    assert!(limiter.try_acquire_at(1000, now + millis(500)));
+    assert!(!limiter.try_acquire_at(1000, now + millis(500)));
+}
+
+#[test]
+fn byte_limit() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new_at(&TokenBucketConfig::new(10, 100), now);
+
+    assert!(limiter.try_acquire_at(60, now));
+    // A rejected message does not take any tokens, from either bucket.
+    assert!(!limiter.try_acquire_at(60, now));
+    assert!(limiter.try_acquire_at(40, now));
+    assert!(!limiter.try_acquire_at(1, now));
+}
+
+#[test]
+fn oversized_message() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new_at(&TokenBucketConfig::new(0, 100), now);
+
+    // A message larger than the bucket passes when the bucket is full, leaving it in debt.
+    assert!(limiter.try_acquire_at(150, now));
+    assert!(!limiter.try_acquire_at(150, now + millis(1000)));
+    assert!(limiter.try_acquire_at(150, now + millis(1500)));
+}
+
+#[test]
+fn reserve_delays() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new_at(&TokenBucketConfig::new(2, 0), now);
+
+    assert_eq!(limiter.reserve_at(1, now), millis(0));
+    assert_eq!(limiter.reserve_at(1, now), millis(0));
+    assert_eq!(limiter.reserve_at(1, now), millis(500));
+    assert_eq!(limiter.reserve_at(1, now), millis(1000));
+    assert_eq!(limiter.reserve_at(1, now + millis(1000)), millis(500));
+}
+
+#[test]
+fn unlimited() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new_at(&TokenBucketConfig::new(0, 0), now);
+
+    for _ in 0..1000 {
+        assert!(limiter.try_acquire_at(1 << 20, now));
+        assert_eq!(limiter.reserve_at(1 << 20, now), millis(0));
+    }
+}
+
+#[test]
+fn inbound_limits_per_peer_and_protocol() {
+    let limiters = InboundRateLimiters::new(RateLimits::new(vec![RateLimitConfig::new(
+        PROTOCOL,
+        TokenBucketConfig::new(1, 0),
+    )]));
+    let protocol = ProtocolId::from_static(PROTOCOL.as_bytes());
+    let other_protocol = ProtocolId::from_static(b"/libra/direct-send/0.1.0/other/0.1.0");
+    let peer_id = PeerId::random();
+    let other_peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert!(limiters.allow_at(peer_id, &protocol, 10, now));
+    assert!(!limiters.allow_at(peer_id, &protocol, 10, now));
+    // Clones share the limiters.
+    assert!(!limiters.clone().allow_at(peer_id, &protocol, 10, now));
+    // Each peer has its own limits.
+    assert!(limiters.allow_at(other_peer_id, &protocol, 10, now));
+    // Protocols without a rate limit are unlimited.
+    for _ in 0..10 {
+        assert!(limiters.allow_at(peer_id, &other_protocol, 10, now));
+    }
+}
+
+#[test]
+fn inbound_limits_reset_on_remove_peer() {
+    let limiters = InboundRateLimiters::new(RateLimits::new(vec![RateLimitConfig::new(
+        PROTOCOL,
+        TokenBucketConfig::new(1, 0),
+    )]));
+    let protocol = ProtocolId::from_static(PROTOCOL.as_bytes());
+    let peer_id = PeerId::random();
+    let other_peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert!(limiters.allow_at(peer_id, &protocol, 10, now));
+    assert!(limiters.allow_at(other_peer_id, &protocol, 10, now));
+    limiters.remove_peer(&peer_id);
+    assert_eq!(limiters.limiters.lock().unwrap().len(), 1);
+    // A reconnecting peer starts over with full buckets.
+    assert!(limiters.allow_at(peer_id, &protocol, 10, now));
+    assert!(!limiters.allow_at(other_peer_id, &protocol, 10, now));
+}
@@ -12,8 +12,6 @@ use std::pin::Pin;
 /// Future for the [`buffered_send`](super::NetworkSinkExt::buffered_send) method.
 #[derive(Debug)]
 #[must_use = "futures do nothing unless polled"]
-// TODO(philiphayes): remove
-#[allow(dead_code)]
 pub struct BufferedSend<'a, S: Sink<Item> + Unpin + ?Sized, Item> {
     sink: &'a mut S,
     item: Option<Item>,
@@ -23,8 +21,6 @@ pub struct BufferedSend<'a, S: Sink<Item> + Unpin + ?Sized, Item> {
 impl<S: Sink<Item> + Unpin + ?Sized, Item> Unpin for BufferedSend<'_, S, Item> {}
 
 impl<'a, S: Sink<Item> + Unpin + ?Sized, Item> BufferedSend<'a, S, Item> {
-    // TODO(philiphayes): remove
-    #[allow(dead_code)]
     pub fn new(sink: &'a mut S, item: Item) -> Self {
         Self {
             sink,
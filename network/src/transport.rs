@@ -5,7 +5,9 @@ use crate::{
     common::NetworkPublicKeys,
     protocols::identity::{exchange_identity, Identity},
 };
+use futures::future::Either;
 use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
     ValidKey,
 };
@@ -13,7 +15,7 @@ use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::{
     multiplexing::{yamux::Yamux, StreamMultiplexer},
-    transport::{boxed, memory, tcp, TransportExt},
+    transport::{boxed, memory, quic, tcp, TransportExt},
 };
 use noise::NoiseConfig;
 use std::{
@@ -50,6 +52,27 @@ fn identity_key_to_peer_id(
     None
 }
 
+fn signing_key_to_peer_id(
+    trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
+    remote_signing_key: &[u8; 32],
+) -> Option<PeerId> {
+    for (peer_id, public_keys) in trusted_peers.read().unwrap().iter() {
+        if public_keys.signing_public_key.to_bytes() == *remote_signing_key {
+            return Some(*peer_id);
+        }
+    }
+
+    None
+}
+
+// The private signing key is copied into the TLS configuration of the QUIC endpoints, it is the
+// one place the key is used outside of the secure storage.
+fn quic_transport(signing_keypair: (Ed25519PrivateKey, Ed25519PublicKey)) -> quic::QuicTransport {
+    let (signing_private_key, _signing_public_key) = signing_keypair;
+    quic::QuicTransport::new(&signing_private_key.to_bytes())
+        .expect("Unable to build the QUIC transport from the network signing key")
+}
+
 // Ensures that peer id in received identity is same as peer id derived from noise handshake.
 fn match_peer_id(identity: Identity, peer_id: PeerId) -> Result<Identity, io::Error> {
     if identity.peer_id() != peer_id {
@@ -291,3 +314,65 @@ pub fn build_tcp_transport(
         .with_timeout(TRANSPORT_TIMEOUT)
         .boxed()
 }
+
+// Transport based on QUIC, whose connections are authenticated by the network signing keys of the
+// peers and natively multiplex substreams, so that neither Noise nor Yamux are needed.
+pub fn build_quic_transport(
+    own_identity: Identity,
+    signing_keypair: (Ed25519PrivateKey, Ed25519PublicKey),
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
+    quic_transport(signing_keypair)
+        .and_then(move |muxer, _origin| {
+            async move {
+                let remote_signing_key = muxer.remote_public_key();
+                if let Some(peer_id) = signing_key_to_peer_id(&trusted_peers, &remote_signing_key) {
+                    Ok((peer_id, muxer))
+                } else {
+                    security_log(SecurityEvent::InvalidNetworkPeer)
+                        .error("UntrustedPeer")
+                        .data(&trusted_peers)
+                        .data(&remote_signing_key)
+                        .log();
+                    Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
+                }
+            }
+        })
+        .and_then(move |(peer_id, muxer), origin| {
+            async move {
+                let (identity, muxer) = exchange_identity(&own_identity, muxer, origin).await?;
+                match_peer_id(identity, peer_id)
+                    .and_then(|identity| check_role(&own_identity, identity))
+                    .and_then(|identity| Ok((identity, muxer)))
+            }
+        })
+        .with_timeout(TRANSPORT_TIMEOUT)
+        .boxed()
+}
+
+// Transport serving both TCP + Noise and QUIC addresses, each address being listened on or dialed
+// with the transport which supports it.
+pub fn build_tcp_noise_or_quic_transport(
+    own_identity: Identity,
+    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    signing_keypair: (Ed25519PrivateKey, Ed25519PublicKey),
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
+    let tcp_noise_transport = build_tcp_noise_transport(
+        own_identity.clone(),
+        identity_keypair,
+        trusted_peers.clone(),
+    );
+    let quic_transport = build_quic_transport(own_identity, signing_keypair, trusted_peers);
+    tcp_noise_transport
+        .or_transport(quic_transport)
+        .and_then(|output, _origin| {
+            async move {
+                Ok(match output {
+                    Either::Left((identity, muxer)) => (identity, Either::Left(muxer)),
+                    Either::Right((identity, muxer)) => (identity, Either::Right(muxer)),
+                })
+            }
+        })
+        .boxed()
+}
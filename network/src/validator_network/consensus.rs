@@ -93,10 +93,12 @@ impl ConsensusNetworkSender {
 
     pub async fn update_eligible_nodes(
         &mut self,
+        epoch: u64,
         validators: Vec<ValidatorPublicKeys>,
     ) -> Result<(), NetworkError> {
         self.inner
             .update_eligible_nodes(
+                epoch,
                 validators
                     .into_iter()
                     .map(|keys| {
@@ -13,6 +13,9 @@ use libra_types::PeerId;
 
 pub const DISCOVERY_DIRECT_SEND_PROTOCOL: &[u8] = b"/libra/direct-send/0.1.0/discovery/0.1.0";
 
+/// The name of the network signing key in the storage through which discovery signs its notes.
+pub const DISCOVERY_SIGNING_KEY: &str = "discovery_signing_key";
+
 /// The interface from Network to Discovery module.
 ///
 /// `DiscoveryNetworkEvents` is a `Stream` of `NetworkNotification` where the
@@ -8,16 +8,16 @@ use crate::{
     common::NetworkPublicKeys,
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
-    protocols::{direct_send, rpc::OutboundRpcRequest},
+    protocols::{direct_send, rpc::OutboundRpcRequest, streaming_rpc::OutboundStreamingRpcRequest},
     utils::MessageExt,
     ProtocolId,
 };
 use bytes::Bytes;
 use futures::{
-    channel::oneshot,
+    channel::{mpsc, oneshot},
     ready,
     sink::SinkExt,
-    stream::{FusedStream, Stream},
+    stream::{BoxStream, FusedStream, Stream, StreamExt},
     task::{Context, Poll},
 };
 use parity_multiaddr::Multiaddr;
@@ -37,13 +37,19 @@ mod state_synchronizer;
 mod test;
 
 // Public re-exports
-pub use crate::interface::LibraNetworkProvider;
+pub use crate::{
+    interface::LibraNetworkProvider,
+    protocols::health_checker::{
+        ConsecutiveFailures, HealthPolicy, LatencyThreshold, SlidingWindow,
+    },
+};
 pub use consensus::{
     ConsensusNetworkEvents, ConsensusNetworkSender, CONSENSUS_DIRECT_SEND_PROTOCOL,
     CONSENSUS_RPC_PROTOCOL,
 };
 pub use discovery::{
     DiscoveryNetworkEvents, DiscoveryNetworkSender, DISCOVERY_DIRECT_SEND_PROTOCOL,
+    DISCOVERY_SIGNING_KEY,
 };
 pub use health_checker::{
     HealthCheckerNetworkEvents, HealthCheckerNetworkSender, HEALTH_CHECKER_RPC_PROTOCOL,
@@ -71,6 +77,10 @@ pub enum Event<TMessage> {
     /// serialized response `Bytes` over the `oneshot::Sender`, where the network
     /// layer will handle sending the response over-the-wire.
     RpcRequest((PeerId, TMessage, oneshot::Sender<Result<Bytes, RpcError>>)),
+    /// New inbound streaming rpc request. The serialized responses are streamed
+    /// over the `mpsc::Sender`, which the network layer drains as fast as the
+    /// remote peer consumes them. Dropping the `mpsc::Sender` ends the stream.
+    StreamingRpcRequest((PeerId, TMessage, mpsc::Sender<Result<Bytes, RpcError>>)),
     /// Peer which we have a newly established connection with.
     NewPeer(PeerId),
     /// Peer with which we've lost our connection.
@@ -87,6 +97,10 @@ impl<TMessage: PartialEq> PartialEq for Event<TMessage> {
             (RpcRequest((pid1, msg1, _)), RpcRequest((pid2, msg2, _))) => {
                 pid1 == pid2 && msg1 == msg2
             }
+            // ignore mpsc::Sender in comparison
+            (StreamingRpcRequest((pid1, msg1, _)), StreamingRpcRequest((pid2, msg2, _))) => {
+                pid1 == pid2 && msg1 == msg2
+            }
             (NewPeer(pid1), NewPeer(pid2)) => pid1 == pid2,
             (LostPeer(pid1), LostPeer(pid2)) => pid1 == pid2,
             _ => false,
@@ -134,6 +148,14 @@ impl<TMessage: Message + Default> Stream for NetworkEvents<TMessage> {
                 let req_msg = TMessage::decode(rpc_req.data.as_ref())?;
                 Ok(Event::RpcRequest((peer_id, req_msg, rpc_req.res_tx)))
             }
+            NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                let req_msg = TMessage::decode(rpc_req.data.as_ref())?;
+                Ok(Event::StreamingRpcRequest((
+                    peer_id,
+                    req_msg,
+                    rpc_req.res_tx,
+                )))
+            }
             NetworkNotification::RecvMessage(peer_id, msg) => {
                 let msg = TMessage::decode(msg.mdata.as_ref())?;
                 Ok(Event::Message((peer_id, msg)))
@@ -278,14 +300,50 @@ impl<TMessage: Message + Default> NetworkSender<TMessage> {
         Ok(res_msg)
     }
 
+    /// Send a streaming rpc request to remote peer `recipient`, and return the
+    /// stream of its responses. Handles serialization and deserialization of the
+    /// message types, assuming that the request and responses all have the same
+    /// message type.
+    ///
+    /// The `timeout` bounds the wait for each response rather than the entire
+    /// call. The stream ends after the last response or the first error, and
+    /// dropping it cancels the call.
+    pub async fn streaming_rpc(
+        &mut self,
+        recipient: PeerId,
+        protocol: ProtocolId,
+        req_msg: TMessage,
+        timeout: Duration,
+    ) -> Result<BoxStream<'static, Result<TMessage, RpcError>>, RpcError>
+    where
+        TMessage: 'static,
+    {
+        // serialize request
+        let req_data = req_msg.to_bytes().unwrap();
+
+        // ask network to fulfill rpc request
+        let (req, res_stream) = OutboundStreamingRpcRequest::new(protocol, req_data, timeout);
+        self.inner
+            .send(NetworkRequest::SendStreamingRpc(recipient, req))
+            .await?;
+
+        // deserialize the responses as they arrive
+        Ok(res_stream
+            .map(|res| {
+                res.and_then(|res_data| TMessage::decode(res_data.as_ref()).map_err(RpcError::from))
+            })
+            .boxed())
+    }
+
     /// Update the set of eligible nodes that the network should accept
-    /// connections from.
+    /// connections from in the consensus epoch `epoch`.
     pub async fn update_eligible_nodes(
         &mut self,
+        epoch: u64,
         nodes: HashMap<PeerId, NetworkPublicKeys>,
     ) -> Result<(), NetworkError> {
         self.inner
-            .send(NetworkRequest::UpdateEligibleNodes(nodes))
+            .send(NetworkRequest::UpdateEligibleNodes(epoch, nodes))
             .await?;
         Ok(())
     }
@@ -14,30 +14,38 @@ use crate::{
     connectivity_manager::ConnectivityManager,
     counters,
     interface::{LibraNetworkProvider, NetworkProvider},
-    peer_manager::{PeerManager, PeerManagerRequestSender},
+    peer_manager::{PeerActivity, PeerManager, PeerManagerRequestSender},
+    peer_score::PeerScores,
     proto::PeerInfo,
     protocols::{
-        direct_send::DirectSend, discovery::Discovery, health_checker::HealthChecker,
-        identity::Identity, rpc::Rpc,
+        direct_send::DirectSend,
+        discovery::Discovery,
+        health_checker::{policy, HealthChecker},
+        identity::Identity,
+        rpc::Rpc,
     },
+    rate_limit::RateLimits,
     transport::*,
-    validator_network::{DISCOVERY_DIRECT_SEND_PROTOCOL, HEALTH_CHECKER_RPC_PROTOCOL},
+    validator_network::{
+        DISCOVERY_DIRECT_SEND_PROTOCOL, DISCOVERY_SIGNING_KEY, HEALTH_CHECKER_RPC_PROTOCOL,
+    },
     ProtocolId,
 };
 use channel;
 use futures::StreamExt;
-use libra_config::config::RoleType;
+use libra_config::config::{HealthPolicyConfig, PeerScoreConfig, RateLimitConfig, RoleType};
 use libra_crypto::{
     ed25519::*,
     x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
 };
 use libra_logger::prelude::*;
-use libra_types::{validator_signer::ValidatorSigner, PeerId};
+use libra_secure_storage::{CryptoStorage, InMemoryStorage};
+use libra_types::PeerId;
 use netcore::{multiplexing::StreamMultiplexer, transport::boxed::BoxedTransport};
 use parity_multiaddr::Multiaddr;
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    sync::{atomic::AtomicU64, Arc, RwLock},
     time::Duration,
 };
 use tokio::runtime::Handle;
@@ -53,13 +61,15 @@ pub const CONNECTIVITY_CHECK_INTERNAL_MS: u64 = 5000;
 pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
 pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+pub const MAX_CONCURRENT_STREAMS: u32 = 16;
+pub const STREAM_IDLE_TIMEOUT_MS: u64 = 60_000;
 pub const PING_FAILURES_TOLERATED: u64 = 10;
 pub const MAX_CONCURRENT_NETWORK_REQS: u32 = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: u32 = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 10 * 60 * 1000 /* 10 minutes */;
 
 /// The type of the transport layer, i.e., running on memory or TCP stream,
-/// with or without Noise encryption
+/// with or without Noise encryption, or on QUIC authenticated by the network signing keys
 pub enum TransportType {
     Memory,
     MemoryNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
@@ -67,6 +77,13 @@ pub enum TransportType {
     Tcp,
     TcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
     PermissionlessTcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
+    Quic(Option<(Ed25519PrivateKey, Ed25519PublicKey)>),
+    TcpNoiseOrQuic(
+        Option<(
+            (X25519StaticPrivateKey, X25519StaticPublicKey),
+            (Ed25519PrivateKey, Ed25519PublicKey),
+        )>,
+    ),
 }
 
 /// Build Network module with custom configuration values.
@@ -77,65 +94,78 @@ pub enum TransportType {
 pub struct NetworkBuilder {
     executor: Handle,
     peer_id: PeerId,
-    addr: Multiaddr,
+    listen_addrs: Vec<Multiaddr>,
     role: RoleType,
-    advertised_address: Option<Multiaddr>,
+    advertised_addrs: Vec<Multiaddr>,
     seed_peers: HashMap<PeerId, PeerInfo>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
     transport: TransportType,
     channel_size: usize,
     direct_send_protocols: Vec<ProtocolId>,
     rpc_protocols: Vec<ProtocolId>,
+    streaming_rpc_protocols: Vec<ProtocolId>,
     discovery_interval_ms: u64,
     discovery_msg_timeout_ms: u64,
     ping_interval_ms: u64,
     ping_timeout_ms: u64,
     ping_failures_tolerated: u64,
+    health_policy: Option<HealthPolicyConfig>,
+    rate_limits: RateLimits,
+    peer_score_config: PeerScoreConfig,
     connectivity_check_interval_ms: u64,
     inbound_rpc_timeout_ms: u64,
     max_concurrent_outbound_rpcs: u32,
     max_concurrent_inbound_rpcs: u32,
+    max_concurrent_streams: u32,
+    stream_idle_timeout_ms: u64,
     max_concurrent_network_reqs: u32,
     max_concurrent_network_notifs: u32,
     max_connection_delay_ms: u64,
-    signing_keys: Option<(Ed25519PrivateKey, Ed25519PublicKey)>,
+    signing_storage: Option<Box<dyn CryptoStorage>>,
     enable_remote_authentication: bool,
     health_checker_enabled: bool,
 }
 
 impl NetworkBuilder {
-    /// Return a new NetworkBuilder initialized with default configuration values.
+    /// Return a new NetworkBuilder initialized with default configuration values. The network
+    /// listens on each of the given addresses.
     pub fn new(
         executor: Handle,
         peer_id: PeerId,
-        addr: Multiaddr,
+        listen_addrs: Vec<Multiaddr>,
         role: RoleType,
     ) -> NetworkBuilder {
         NetworkBuilder {
             executor,
             peer_id,
-            addr,
+            listen_addrs,
             role,
-            advertised_address: None,
+            advertised_addrs: vec![],
             seed_peers: HashMap::new(),
             trusted_peers: Arc::new(RwLock::new(HashMap::new())),
             channel_size: NETWORK_CHANNEL_SIZE,
             direct_send_protocols: vec![ProtocolId::from_static(DISCOVERY_DIRECT_SEND_PROTOCOL)],
             rpc_protocols: vec![ProtocolId::from_static(HEALTH_CHECKER_RPC_PROTOCOL)],
+            streaming_rpc_protocols: vec![],
             transport: TransportType::Memory,
             discovery_interval_ms: DISCOVERY_INTERVAL_MS,
             discovery_msg_timeout_ms: DISOVERY_MSG_TIMEOUT_MS,
             ping_interval_ms: PING_INTERVAL_MS,
             ping_timeout_ms: PING_TIMEOUT_MS,
             ping_failures_tolerated: PING_FAILURES_TOLERATED,
+            health_policy: None,
+            rate_limits: RateLimits::default(),
+            peer_score_config: PeerScoreConfig::default(),
             connectivity_check_interval_ms: CONNECTIVITY_CHECK_INTERNAL_MS,
             inbound_rpc_timeout_ms: INBOUND_RPC_TIMEOUT_MS,
             max_concurrent_outbound_rpcs: MAX_CONCURRENT_OUTBOUND_RPCS,
             max_concurrent_inbound_rpcs: MAX_CONCURRENT_INBOUND_RPCS,
+            max_concurrent_streams: MAX_CONCURRENT_STREAMS,
+            stream_idle_timeout_ms: STREAM_IDLE_TIMEOUT_MS,
             max_concurrent_network_reqs: MAX_CONCURRENT_NETWORK_REQS,
             max_concurrent_network_notifs: MAX_CONCURRENT_NETWORK_NOTIFS,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
-            signing_keys: None,
+            signing_storage: None,
             enable_remote_authentication: true,
             health_checker_enabled: true,
         }
@@ -147,9 +177,10 @@ impl NetworkBuilder {
         self
     }
 
-    /// Set and address to advertise, if different from the listen address
-    pub fn advertised_address(&mut self, advertised_address: Multiaddr) -> &mut Self {
-        self.advertised_address = Some(advertised_address);
+    /// Set the addresses to advertise, in order of preference, if different from the listen
+    /// addresses
+    pub fn advertised_addresses(&mut self, advertised_addrs: Vec<Multiaddr>) -> &mut Self {
+        self.advertised_addrs = advertised_addrs;
         self
     }
 
@@ -162,9 +193,18 @@ impl NetworkBuilder {
         self
     }
 
-    /// Set signing keys of local node.
+    /// Set signing keys of local node, which are moved into an in-memory storage.
     pub fn signing_keys(&mut self, keys: (Ed25519PrivateKey, Ed25519PublicKey)) -> &mut Self {
-        self.signing_keys = Some(keys);
+        let mut storage = InMemoryStorage::new();
+        storage
+            .import_key(DISCOVERY_SIGNING_KEY, keys.0)
+            .expect("Unable to import signing key");
+        self.signing_storage(Box::new(storage))
+    }
+
+    /// Set the storage holding the signing key of local node under [`DISCOVERY_SIGNING_KEY`].
+    pub fn signing_storage(&mut self, storage: Box<dyn CryptoStorage>) -> &mut Self {
+        self.signing_storage = Some(storage);
         self
     }
 
@@ -203,6 +243,27 @@ impl NetworkBuilder {
         self
     }
 
+    /// Set the policy used by the health checker to decide when to disconnect from a peer. By
+    /// default, peers are disconnected after more than `ping_failures_tolerated` successive ping
+    /// failures.
+    pub fn health_policy(&mut self, health_policy: HealthPolicyConfig) -> &mut Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    /// Set per peer rate limits of direct-send protocols. Protocols without a rate limit are
+    /// unlimited.
+    pub fn rate_limits(&mut self, rate_limits: Vec<RateLimitConfig>) -> &mut Self {
+        self.rate_limits = RateLimits::new(rate_limits);
+        self
+    }
+
+    /// Set the penalties and ban thresholds used to score remote peers.
+    pub fn peer_scores(&mut self, peer_score_config: PeerScoreConfig) -> &mut Self {
+        self.peer_score_config = peer_score_config;
+        self
+    }
+
     /// Set ping timeout.
     pub fn ping_timeout_ms(&mut self, ping_timeout_ms: u64) -> &mut Self {
         self.ping_timeout_ms = ping_timeout_ms;
@@ -242,6 +303,19 @@ impl NetworkBuilder {
         self
     }
 
+    /// The maximum number of concurrent streaming rpcs we will service in each direction. Further
+    /// streams are rejected.
+    pub fn max_concurrent_streams(&mut self, max_concurrent_streams: u32) -> &mut Self {
+        self.max_concurrent_streams = max_concurrent_streams;
+        self
+    }
+
+    /// Set the maximum time an inbound streaming rpc may go without sending a response.
+    pub fn stream_idle_timeout_ms(&mut self, stream_idle_timeout_ms: u64) -> &mut Self {
+        self.stream_idle_timeout_ms = stream_idle_timeout_ms;
+        self
+    }
+
     /// The maximum number of concurrent NetworkRequests we will service in NetworkProvider.
     pub fn max_concurrent_network_reqs(&mut self, max_concurrent_network_reqs: u32) -> &mut Self {
         self.max_concurrent_network_reqs = max_concurrent_network_reqs;
@@ -287,6 +361,12 @@ impl NetworkBuilder {
         self
     }
 
+    /// Set the protocol IDs that RPC actor serves with the streaming rpc protocol.
+    pub fn streaming_rpc_protocols(&mut self, protocols: Vec<ProtocolId>) -> &mut Self {
+        self.streaming_rpc_protocols = protocols;
+        self
+    }
+
     /// Set the enable_remote_authentication flag to make the network operate with remote authentication.
     pub fn enable_remote_authentication(
         &mut self,
@@ -300,6 +380,7 @@ impl NetworkBuilder {
         self.direct_send_protocols
             .iter()
             .chain(&self.rpc_protocols)
+            .chain(&self.streaming_rpc_protocols)
             .cloned()
             .collect()
     }
@@ -324,8 +405,9 @@ impl NetworkBuilder {
     }
 
     /// Create the configured `NetworkBuilder`
-    /// Return the constructed Mempool and Consensus Sender+Events
-    pub fn build(&mut self) -> (Multiaddr, Box<dyn LibraNetworkProvider>) {
+    /// Return the addresses the network listens on, along with the constructed Mempool and
+    /// Consensus Sender+Events
+    pub fn build(&mut self) -> (Vec<Multiaddr>, Box<dyn LibraNetworkProvider>) {
         let identity = Identity::new(self.peer_id, self.supported_protocols(), self.role);
         // Build network based on the transport type
         let trusted_peers = self.trusted_peers.clone();
@@ -354,6 +436,19 @@ impl NetworkBuilder {
                 let keys = keys.take().expect("Identity keys not set");
                 self.build_with_transport(build_unauthenticated_tcp_noise_transport(identity, keys))
             }
+            TransportType::Quic(ref mut keys) => {
+                let keys = keys.take().expect("Signing keys not set");
+                self.build_with_transport(build_quic_transport(identity, keys, trusted_peers))
+            }
+            TransportType::TcpNoiseOrQuic(ref mut keys) => {
+                let (identity_keys, signing_keys) = keys.take().expect("Keys not set");
+                self.build_with_transport(build_tcp_noise_or_quic_transport(
+                    identity,
+                    identity_keys,
+                    signing_keys,
+                    trusted_peers,
+                ))
+            }
         }
     }
 
@@ -365,7 +460,7 @@ impl NetworkBuilder {
             (Identity, impl StreamMultiplexer + 'static),
             impl ::std::error::Error + Send + Sync + 'static,
         >,
-    ) -> (Multiaddr, Box<dyn LibraNetworkProvider>) {
+    ) -> (Vec<Multiaddr>, Box<dyn LibraNetworkProvider>) {
         // Initialize lists of protocol handlers and peer event handlers.
         let mut peer_event_handlers = vec![];
         let mut protocol_handlers = HashMap::new();
@@ -373,6 +468,13 @@ impl NetworkBuilder {
         let (pm_reqs_tx, pm_reqs_rx) =
             channel::new(self.channel_size, &counters::PENDING_PEER_MANAGER_REQUESTS);
 
+        // Scores of remote peers, shared by the actors which detect and act on misbehavior.
+        let peer_scores = PeerScores::new(self.peer_score_config.clone());
+        // The consensus epoch of the trusted peers, which discovery notes are bound to.
+        let consensus_epoch = Arc::new(AtomicU64::new(0));
+        // Inbound traffic from remote peers, recorded by the PeerManager for the HealthChecker.
+        let peer_activity = PeerActivity::default();
+
         // Initialize and start DirectSend actor.
         let (pm_ds_notifs_tx, pm_ds_notifs_rx) = channel::new(
             self.channel_size,
@@ -383,6 +485,8 @@ impl NetworkBuilder {
             .iter()
             .map(|p| (p.clone(), pm_ds_notifs_tx.clone()));
         protocol_handlers.extend(direct_send_handlers);
+        // DirectSend drops the inbound rate limiters of lost peers.
+        peer_event_handlers.push(pm_ds_notifs_tx);
         let (ds_reqs_tx, ds_reqs_rx) =
             channel::new(self.channel_size, &counters::PENDING_DIRECT_SEND_REQUESTS);
         let (ds_net_notifs_tx, ds_net_notifs_rx) = channel::new(
@@ -395,6 +499,8 @@ impl NetworkBuilder {
             ds_net_notifs_tx,
             pm_ds_notifs_rx,
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
+            peer_activity.clone(),
+            self.rate_limits.clone(),
         );
         self.executor.spawn(ds.start());
         debug!("Started direct send actor");
@@ -407,6 +513,7 @@ impl NetworkBuilder {
         let rpc_handlers = self
             .rpc_protocols
             .iter()
+            .chain(&self.streaming_rpc_protocols)
             .map(|p| (p.clone(), pm_rpc_notifs_tx.clone()));
         protocol_handlers.extend(rpc_handlers);
         let (rpc_net_notifs_tx, rpc_net_notifs_rx) =
@@ -419,9 +526,13 @@ impl NetworkBuilder {
             pm_rpc_notifs_rx,
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
             rpc_net_notifs_tx,
+            self.streaming_rpc_protocols.iter().cloned().collect(),
+            peer_scores.clone(),
             Duration::from_millis(self.inbound_rpc_timeout_ms),
             self.max_concurrent_outbound_rpcs,
             self.max_concurrent_inbound_rpcs,
+            self.max_concurrent_streams,
+            Duration::from_millis(self.stream_idle_timeout_ms),
         );
         self.executor.spawn(rpc.start());
         debug!("Started RPC actor");
@@ -445,9 +556,13 @@ impl NetworkBuilder {
             let max_connection_delay_ms = self.max_connection_delay_ms;
             let connectivity_check_interval_ms = self.connectivity_check_interval_ms;
             let pm_reqs_tx = pm_reqs_tx.clone();
+            let consensus_epoch = consensus_epoch.clone();
+            let peer_scores = peer_scores.clone();
             let f = async move {
                 let conn_mgr = ConnectivityManager::new(
                     trusted_peers,
+                    consensus_epoch,
+                    peer_scores,
                     interval(Duration::from_millis(connectivity_check_interval_ms)).fuse(),
                     PeerManagerRequestSender::new(pm_reqs_tx.clone()),
                     pm_conn_mgr_notifs_rx,
@@ -471,12 +586,14 @@ impl NetworkBuilder {
             transport,
             self.executor.clone(),
             self.peer_id,
-            self.addr.clone(),
+            self.listen_addrs.clone(),
             pm_reqs_rx,
             protocol_handlers,
             peer_event_handlers,
+            peer_activity.clone(),
+            peer_scores.clone(),
         );
-        let listen_addr = peer_mgr.listen_addr().clone();
+        let listen_addrs = peer_mgr.listen_addrs().to_vec();
         self.executor.spawn(peer_mgr.start());
         debug!("Started peer manager");
 
@@ -504,14 +621,23 @@ impl NetworkBuilder {
                 .add_health_checker(vec![ProtocolId::from_static(HEALTH_CHECKER_RPC_PROTOCOL)]);
             let ping_interval_ms = self.ping_interval_ms;
             let ping_timeout_ms = self.ping_timeout_ms;
-            let ping_failures_tolerated = self.ping_failures_tolerated;
+            let health_policy =
+                self.health_policy
+                    .take()
+                    .unwrap_or(HealthPolicyConfig::ConsecutiveFailures {
+                        tolerated: self.ping_failures_tolerated,
+                    });
+            let health_policy = policy::from_config(&health_policy);
+            let peer_scores = peer_scores.clone();
             let f = async move {
                 let health_checker = HealthChecker::new(
                     interval(Duration::from_millis(ping_interval_ms)).fuse(),
                     hc_network_tx,
                     hc_network_rx,
+                    peer_scores,
+                    peer_activity,
                     Duration::from_millis(ping_timeout_ms),
-                    ping_failures_tolerated,
+                    health_policy,
                 );
                 health_checker.start().await
             };
@@ -524,20 +650,18 @@ impl NetworkBuilder {
         // discovery module or not. We should make this more explicit eventually.
         if self.enable_remote_authentication {
             // Initialize and start Discovery actor.
-            let (signing_private_key, _signing_public_key) =
-                self.signing_keys.take().expect("Signing keys not set");
-            // Setup signer from keys.
-            let signer = ValidatorSigner::new(self.peer_id, signing_private_key);
+            let signer = self.signing_storage.take().expect("Signing keys not set");
             // Get handles for network events and sender.
             let (discovery_network_tx, discovery_network_rx) = network_provider.add_discovery(
                 vec![ProtocolId::from_static(DISCOVERY_DIRECT_SEND_PROTOCOL)],
             );
             let peer_id = self.peer_id;
             let role = self.role;
-            let addrs = vec![self
-                .advertised_address
-                .clone()
-                .unwrap_or_else(|| self.addr.clone())];
+            let addrs = if self.advertised_addrs.is_empty() {
+                self.listen_addrs.clone()
+            } else {
+                self.advertised_addrs.clone()
+            };
             let seed_peers = self.seed_peers.clone();
             let trusted_peers = self.trusted_peers.clone();
             let discovery_interval_ms = self.discovery_interval_ms;
@@ -550,6 +674,8 @@ impl NetworkBuilder {
                     signer,
                     seed_peers,
                     trusted_peers,
+                    consensus_epoch,
+                    peer_scores,
                     interval(Duration::from_millis(discovery_interval_ms)).fuse(),
                     discovery_network_tx,
                     discovery_network_rx,
@@ -561,6 +687,6 @@ impl NetworkBuilder {
             self.executor.spawn(f);
             debug!("Started discovery protocol actor");
         }
-        (listen_addr, Box::new(network_provider))
+        (listen_addrs, Box::new(network_provider))
     }
 }
@@ -14,7 +14,12 @@ use crate::{
 };
 use futures::{future::join, StreamExt};
 use libra_config::config::RoleType;
-use libra_crypto::{ed25519::compat, test_utils::TEST_SEED, traits::ValidKey, x25519};
+use libra_crypto::{
+    ed25519::{compat, Ed25519PrivateKey},
+    test_utils::TEST_SEED,
+    traits::ValidKey,
+    x25519,
+};
 use libra_types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     proto::types::SignedTransaction,
@@ -42,28 +47,32 @@ fn test_network_builder() {
     let (signing_private_key, signing_public_key) = compat::generate_keypair(&mut rng);
     let (_identity_private_key, identity_public_key) = x25519::compat::generate_keypair(&mut rng);
 
-    let (_listen_addr, mut network_provider) =
-        NetworkBuilder::new(runtime.handle().clone(), peer_id, addr, RoleType::Validator)
-            .transport(TransportType::Memory)
-            .signing_keys((signing_private_key, signing_public_key.clone()))
-            .trusted_peers(
-                vec![(
-                    peer_id,
-                    NetworkPublicKeys {
-                        signing_public_key,
-                        identity_public_key,
-                    },
-                )]
-                .into_iter()
-                .collect(),
-            )
-            .channel_size(8)
-            .direct_send_protocols(vec![mempool_sync_protocol.clone()])
-            .rpc_protocols(vec![
-                consensus_get_blocks_protocol.clone(),
-                synchronizer_get_chunks_protocol.clone(),
-            ])
-            .build();
+    let (_listen_addrs, mut network_provider) = NetworkBuilder::new(
+        runtime.handle().clone(),
+        peer_id,
+        vec![addr],
+        RoleType::Validator,
+    )
+    .transport(TransportType::Memory)
+    .signing_keys((signing_private_key, signing_public_key.clone()))
+    .trusted_peers(
+        vec![(
+            peer_id,
+            NetworkPublicKeys {
+                signing_public_key,
+                identity_public_key,
+            },
+        )]
+        .into_iter()
+        .collect(),
+    )
+    .channel_size(8)
+    .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    .rpc_protocols(vec![
+        consensus_get_blocks_protocol.clone(),
+        synchronizer_get_chunks_protocol.clone(),
+    ])
+    .build();
     let (_mempool_network_sender, _mempool_network_events) =
         network_provider.add_mempool(vec![mempool_sync_protocol]);
     let (_consensus_network_sender, _consensus_network_events) =
@@ -115,10 +124,10 @@ fn test_mempool_sync() {
 
     // Set up the listener network
     let listener_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (listener_addr, mut network_provider) = NetworkBuilder::new(
+    let (listener_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         listener_peer_id,
-        listener_addr,
+        vec![listener_addr],
         RoleType::Validator,
     )
     .signing_keys((listener_signing_private_key, listener_signing_public_key))
@@ -133,17 +142,17 @@ fn test_mempool_sync() {
 
     // Set up the dialer network
     let dialer_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         dialer_peer_id,
-        dialer_addr,
+        vec![dialer_addr],
         RoleType::Validator,
     )
     .transport(TransportType::Memory)
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
     .trusted_peers(trusted_peers)
     .seed_peers(
-        [(listener_peer_id, vec![listener_addr])]
+        [(listener_peer_id, listener_addrs)]
             .iter()
             .cloned()
             .collect(),
@@ -250,10 +259,10 @@ fn test_unauthenticated_remote_mempool_sync() {
 
     // Set up the listener network
     let listener_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (listener_addr, mut network_provider) = NetworkBuilder::new(
+    let (listener_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         listener_peer_id,
-        listener_addr,
+        vec![listener_addr],
         RoleType::Validator,
     )
     .signing_keys((listener_signing_private_key, listener_signing_public_key))
@@ -271,10 +280,10 @@ fn test_unauthenticated_remote_mempool_sync() {
 
     // Set up the dialer network
     let dialer_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         dialer_peer_id,
-        dialer_addr,
+        vec![dialer_addr],
         RoleType::Validator,
     )
     .transport(TransportType::MemoryNoise(Some((
@@ -284,7 +293,7 @@ fn test_unauthenticated_remote_mempool_sync() {
     .trusted_peers(trusted_peers)
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
     .seed_peers(
-        [(listener_peer_id, vec![listener_addr])]
+        [(listener_peer_id, listener_addrs)]
             .iter()
             .cloned()
             .collect(),
@@ -348,6 +357,151 @@ fn test_unauthenticated_remote_mempool_sync() {
     runtime.block_on(join(f_dialer, f_listener));
 }
 
+// Test that two end-points connect over QUIC on loopback, authenticating each other with their
+// signing keys, and that messages flow over the QUIC streams.
+#[test]
+fn test_quic_mempool_sync() {
+    ::libra_logger::try_init_for_testing();
+    let mut runtime = Runtime::new().unwrap();
+    let mempool_sync_protocol = ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL);
+
+    // Setup peer ids.
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    // Setup signing keys, which are used by both QUIC and discovery.
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (listener_signing_private_key, listener_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    let (dialer_signing_private_key, dialer_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    let copy_key = |private_key: &Ed25519PrivateKey| {
+        Ed25519PrivateKey::try_from(&private_key.to_bytes()[..]).unwrap()
+    };
+    let listener_quic_private_key = copy_key(&listener_signing_private_key);
+    let dialer_quic_private_key = copy_key(&dialer_signing_private_key);
+    // Setup identity public keys.
+    let (_listener_identity_private_key, listener_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+    let (_dialer_identity_private_key, dialer_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+
+    let trusted_peers: HashMap<_, _> = vec![
+        (
+            listener_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: listener_signing_public_key.clone(),
+                identity_public_key: listener_identity_public_key,
+            },
+        ),
+        (
+            dialer_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: dialer_signing_public_key.clone(),
+                identity_public_key: dialer_identity_public_key,
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    // Set up the listener network
+    let listener_addr: Multiaddr = "/ip4/127.0.0.1/udp/0/quic".parse().unwrap();
+    let (listener_addrs, mut network_provider) = NetworkBuilder::new(
+        runtime.handle().clone(),
+        listener_peer_id,
+        vec![listener_addr],
+        RoleType::Validator,
+    )
+    .transport(TransportType::Quic(Some((
+        listener_quic_private_key,
+        listener_signing_public_key.clone(),
+    ))))
+    .signing_keys((listener_signing_private_key, listener_signing_public_key))
+    .trusted_peers(trusted_peers.clone())
+    .channel_size(8)
+    .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    .build();
+    let (_, mut listener_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
+    runtime.handle().spawn(network_provider.start());
+
+    // Set up the dialer network
+    let dialer_addr: Multiaddr = "/ip4/127.0.0.1/udp/0/quic".parse().unwrap();
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
+        runtime.handle().clone(),
+        dialer_peer_id,
+        vec![dialer_addr],
+        RoleType::Validator,
+    )
+    .transport(TransportType::Quic(Some((
+        dialer_quic_private_key,
+        dialer_signing_public_key.clone(),
+    ))))
+    .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
+    .trusted_peers(trusted_peers)
+    .seed_peers(
+        [(listener_peer_id, listener_addrs)]
+            .iter()
+            .cloned()
+            .collect(),
+    )
+    .channel_size(8)
+    .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    .build();
+    let (mut dialer_mp_net_sender, mut dialer_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol]);
+    runtime.handle().spawn(network_provider.start());
+
+    // The dialer dials the listener and sends a mempool sync message
+    let mut mempool_msg = MempoolSyncMsg::default();
+    mempool_msg.peer_id = dialer_peer_id.into();
+    let sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    let keypair = compat::generate_keypair(&mut rng);
+    let txn: SignedTransaction = get_test_signed_txn(sender, 0, &keypair.0, keypair.1, None)
+        .try_into()
+        .unwrap();
+    mempool_msg.transactions.push(txn.clone());
+
+    let f_dialer = async move {
+        // Wait until dialing finished and NewPeer event received
+        match dialer_mp_net_events.next().await.unwrap().unwrap() {
+            Event::NewPeer(peer_id) => {
+                assert_eq!(peer_id, listener_peer_id);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        // Dialer sends a mempool sync message
+        dialer_mp_net_sender
+            .send_to(listener_peer_id, mempool_msg)
+            .await
+            .unwrap();
+    };
+
+    // The listener receives a mempool sync message
+    let f_listener = async move {
+        // The listener receives a NewPeer event first
+        match listener_mp_net_events.next().await.unwrap().unwrap() {
+            Event::NewPeer(peer_id) => {
+                assert_eq!(peer_id, dialer_peer_id);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        // The listener then receives the mempool sync message
+        match listener_mp_net_events.next().await.unwrap().unwrap() {
+            Event::Message((peer_id, msg)) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                let transactions: Vec<SignedTransaction> = msg.transactions;
+                assert_eq!(transactions, vec![txn]);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    };
+
+    runtime.block_on(join(f_dialer, f_listener));
+}
+
 #[test]
 fn test_consensus_rpc() {
     ::libra_logger::try_init_for_testing();
@@ -390,10 +544,10 @@ fn test_consensus_rpc() {
 
     // Set up the listener network
     let listener_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (listener_addr, mut network_provider) = NetworkBuilder::new(
+    let (listener_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         listener_peer_id,
-        listener_addr,
+        vec![listener_addr],
         RoleType::Validator,
     )
     .signing_keys((listener_signing_private_key, listener_signing_public_key))
@@ -408,17 +562,17 @@ fn test_consensus_rpc() {
 
     // Set up the dialer network
     let dialer_addr: Multiaddr = "/memory/0".parse().unwrap();
-    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+    let (_dialer_addrs, mut network_provider) = NetworkBuilder::new(
         runtime.handle().clone(),
         dialer_peer_id,
-        dialer_addr,
+        vec![dialer_addr],
         RoleType::Validator,
     )
     .transport(TransportType::Memory)
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
     .trusted_peers(trusted_peers)
     .seed_peers(
-        [(listener_peer_id, vec![listener_addr])]
+        [(listener_peer_id, listener_addrs)]
             .iter()
             .cloned()
             .collect(),
@@ -12,20 +12,63 @@
 //!
 //! Internally both the client and server leverage a NetworkStream that communications in blocks
 //! where a block is a length prefixed array of bytes.
+//!
+//! Optionally, the client and the server run a Noise IX handshake when connecting, using the
+//! static keys of both ends. The traffic is then encrypted, and each end rejects a peer that does
+//! not present the key it expects.
 
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
+use futures::{
+    executor::block_on,
+    io::{AllowStdIo, AsyncReadExt, AsyncWriteExt},
+};
+use libra_crypto::{
+    x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
+    PrivateKey, ValidKey,
+};
+use netcore::transport::ConnectionOrigin;
+use noise::{NoiseConfig, NoiseSocket};
 use std::{
-    io::{Read, Write},
+    io::{self, Read, Write},
     net::{SocketAddr, TcpListener, TcpStream},
     thread, time,
 };
 
+/// The time a peer is given to complete the Noise handshake, so that a connection that stays
+/// silent can not block the server.
+const HANDSHAKE_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// The static keys of a Noise channel: the key of this end and the only key accepted from the
+/// remote end.
+#[derive(Clone, Debug)]
+pub struct NoiseKeys {
+    pub private_key: X25519StaticPrivateKey,
+    pub remote_public_key: X25519StaticPublicKey,
+}
+
 pub struct NetworkClient {
     stream: NetworkStream,
 }
 
 impl NetworkClient {
     pub fn connect(server: SocketAddr) -> Result<Self> {
+        let stream = Self::connect_tcp(server)?;
+        Ok(Self {
+            stream: NetworkStream::new(Box::new(stream)),
+        })
+    }
+
+    /// Connects to the server and authenticates it with Noise, failing if the server does not
+    /// present the expected key.
+    pub fn connect_with_noise(server: SocketAddr, keys: &NoiseKeys) -> Result<Self> {
+        let stream = Self::connect_tcp(server)?;
+        let stream = NoiseStream::handshake(stream, keys, ConnectionOrigin::Outbound)?;
+        Ok(Self {
+            stream: NetworkStream::new(Box::new(stream)),
+        })
+    }
+
+    fn connect_tcp(server: SocketAddr) -> Result<TcpStream> {
         let mut stream = TcpStream::connect(server);
 
         let mut attempts = 0;
@@ -38,10 +81,7 @@ impl NetworkClient {
         }
         let stream = stream?;
         stream.set_nodelay(true)?;
-
-        Ok(Self {
-            stream: NetworkStream::new(stream),
-        })
+        Ok(stream)
     }
 
     /// Blocking read until able to successfully read an entire message
@@ -58,6 +98,7 @@ impl NetworkClient {
 pub struct NetworkServer {
     listener: TcpListener,
     stream: Option<NetworkStream>,
+    noise_keys: Option<NoiseKeys>,
 }
 
 impl NetworkServer {
@@ -66,25 +107,49 @@ impl NetworkServer {
         Self {
             listener,
             stream: None,
+            noise_keys: None,
         }
     }
 
+    /// Creates a server that only accepts clients authenticating with the expected Noise key.
+    pub fn new_with_noise(listen: SocketAddr, keys: NoiseKeys) -> Self {
+        let mut server = Self::new(listen);
+        server.noise_keys = Some(keys);
+        server
+    }
+
     /// If there isn't already a downstream client, it accepts. Otherwise it
-    /// blocks until able to successfully read an entire message
+    /// blocks until able to successfully read an entire message. The client is dropped upon
+    /// failure, so that the next call waits for a new client.
     pub fn read(&mut self) -> Result<Vec<u8>> {
-        let stream = self.client()?;
-        stream.read()
+        let result = self.client()?.read();
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
     }
 
     pub fn write(&mut self, data: &[u8]) -> Result<()> {
-        let stream = self.client()?;
-        stream.write(data)
+        let result = self.client()?.write(data);
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
     }
 
     fn client(&mut self) -> Result<&mut NetworkStream> {
-        if self.stream.is_none() {
+        while self.stream.is_none() {
             let (stream, _stream_addr) = self.listener.accept()?;
             stream.set_nodelay(true)?;
+            let stream: Box<dyn Channel> = match &self.noise_keys {
+                None => Box::new(stream),
+                // Clients that fail to authenticate are dropped, wait for the next one.
+                Some(keys) => match NoiseStream::handshake(stream, keys, ConnectionOrigin::Inbound)
+                {
+                    Ok(stream) => Box::new(stream),
+                    Err(_) => continue,
+                },
+            };
             self.stream = Some(NetworkStream::new(stream));
         }
 
@@ -94,14 +159,59 @@ impl NetworkServer {
     }
 }
 
+/// A blocking, bidirectional byte stream.
+trait Channel: Read + Write + Send + Sync {}
+
+impl<T: Read + Write + Send + Sync> Channel for T {}
+
+/// A Noise session over a TCP stream, exposed as a blocking stream.
+struct NoiseStream {
+    socket: Box<NoiseSocket<AllowStdIo<TcpStream>>>,
+}
+
+impl NoiseStream {
+    /// Runs the Noise handshake over the stream, and fails unless the remote end presents the
+    /// expected static key.
+    fn handshake(stream: TcpStream, keys: &NoiseKeys, origin: ConnectionOrigin) -> Result<Self> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let config = NoiseConfig::new((keys.private_key.clone(), keys.private_key.public_key()));
+        let (remote_public_key, socket) =
+            block_on(config.upgrade_connection(AllowStdIo::new(stream.try_clone()?), origin))?;
+        ensure!(
+            remote_public_key == keys.remote_public_key.to_bytes(),
+            "Noise peer presented an unexpected static key"
+        );
+        stream.set_read_timeout(None)?;
+        Ok(Self {
+            socket: Box::new(socket),
+        })
+    }
+}
+
+impl Read for NoiseStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        block_on(self.socket.read(buf))
+    }
+}
+
+impl Write for NoiseStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        block_on(self.socket.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        block_on(self.socket.flush())
+    }
+}
+
 struct NetworkStream {
-    stream: TcpStream,
+    stream: Box<dyn Channel>,
     buffer: Vec<u8>,
     temp_buffer: [u8; 1024],
 }
 
 impl NetworkStream {
-    pub fn new(stream: TcpStream) -> Self {
+    pub fn new(stream: Box<dyn Channel>) -> Self {
         Self {
             stream,
             buffer: Vec::new(),
@@ -118,6 +228,9 @@ impl NetworkStream {
 
         loop {
             let read = self.stream.read(&mut self.temp_buffer)?;
+            if read == 0 {
+                bail!("Connection closed by the remote end");
+            }
             self.buffer.extend(self.temp_buffer[0..read].to_vec());
             let result = self.read_buffer();
             if !result.is_empty() {
@@ -137,6 +250,7 @@ impl NetworkStream {
         let data_len = data.len() as u32;
         self.write_all(&data_len.to_le_bytes())?;
         self.write_all(data)?;
+        self.stream.flush()?;
         Ok(())
     }
 
@@ -185,8 +299,25 @@ impl NetworkStream {
 mod test {
     use super::*;
     use libra_config::utils;
+    use libra_crypto::Uniform;
+    use rand::{rngs::StdRng, SeedableRng};
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+    fn noise_keys(rng: &mut StdRng) -> (NoiseKeys, NoiseKeys) {
+        let client_key = X25519StaticPrivateKey::generate_for_testing(rng);
+        let server_key = X25519StaticPrivateKey::generate_for_testing(rng);
+        (
+            NoiseKeys {
+                remote_public_key: server_key.public_key(),
+                private_key: client_key.clone(),
+            },
+            NoiseKeys {
+                remote_public_key: client_key.public_key(),
+                private_key: server_key,
+            },
+        )
+    }
+
     #[test]
     fn test_ping() {
         let server_port = utils::get_available_port();
@@ -204,4 +335,49 @@ mod test {
         let result = client.read().unwrap();
         assert_eq!(data, result);
     }
+
+    #[test]
+    fn test_noise_ping() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let (client_keys, server_keys) = noise_keys(&mut rng);
+        let (other_client_keys, _) = noise_keys(&mut rng);
+
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = NetworkServer::new_with_noise(server_addr, server_keys.clone());
+        thread::spawn(move || loop {
+            if let Ok(data) = server.read() {
+                let _ = server.write(&data);
+            }
+        });
+
+        // A client with an unknown key is rejected by the server.
+        let mut unknown_client = NetworkClient::connect_with_noise(
+            server_addr,
+            &NoiseKeys {
+                remote_public_key: server_keys.private_key.public_key(),
+                private_key: other_client_keys.private_key,
+            },
+        )
+        .unwrap();
+        unknown_client.write(&[0, 1, 2, 3]).unwrap();
+        assert!(unknown_client.read().is_err());
+
+        // A server with an unexpected key is rejected by the client.
+        let wrong_server_keys = NoiseKeys {
+            remote_public_key: client_keys.private_key.public_key(),
+            private_key: client_keys.private_key.clone(),
+        };
+        assert!(NetworkClient::connect_with_noise(server_addr, &wrong_server_keys).is_err());
+
+        let mut client = NetworkClient::connect_with_noise(server_addr, &client_keys).unwrap();
+        let data = vec![4, 5, 6, 7];
+        client.write(&data).unwrap();
+        assert_eq!(data, client.read().unwrap());
+
+        // Messages larger than a single Noise frame go through.
+        let data = vec![8; 100_000];
+        client.write(&data).unwrap();
+        assert_eq!(data, client.read().unwrap());
+    }
 }
@@ -0,0 +1,142 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue, PrivateKey, SigningKey, Uniform,
+};
+use rand::{
+    rngs::{OsRng, StdRng},
+    Rng, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Libra interface into signing keys held by a storage backend. Rather than handing out private
+/// keys, the backend generates or imports them and signs on behalf of the caller, so that key
+/// material never leaves the backend. Each named key keeps all of its versions: rotating a key
+/// adds a new version that becomes the current one, while older versions remain available to sign
+/// with until the new public key is known to everyone, e.g. until the next reconfiguration.
+/// Versions are numbered from 0 in the order they were created.
+pub trait CryptoStorage: Send + Sync {
+    /// Generates a new key under the given name and fails if it already exists.
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
+    /// Stores an existing private key under the given name and fails if it already exists. This
+    /// only exists to bootstrap a backend with the keys of a configuration.
+    fn import_key(&mut self, name: &str, private_key: Ed25519PrivateKey) -> Result<(), Error>;
+    /// Returns the public key of the current version of the key.
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error>;
+    /// Returns the public key of the given version of the key.
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error>;
+    /// Generates a new version of the key and returns its public key.
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
+    /// Signs the message with the current version of the key.
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error>;
+    /// Signs the message with the given version of the key.
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error>;
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PublicKeyResponse {
+    pub public_key: Ed25519PublicKey,
+    pub version: u64,
+}
+
+/// The keys of a backend that stores private keys itself, such as InMemoryStorage. Each name maps
+/// to all the versions of the key, the last one being the current version.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub(crate) struct KeyStore {
+    keys: HashMap<String, Vec<Ed25519PrivateKey>>,
+}
+
+impl KeyStore {
+    fn versions(&self, name: &str) -> Result<&[Ed25519PrivateKey], Error> {
+        self.keys
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::KeyNotSet(name.to_string()))
+    }
+
+    fn version(&self, name: &str, version: u64) -> Result<&Ed25519PrivateKey, Error> {
+        self.versions(name)?
+            .get(version as usize)
+            .ok_or_else(|| Error::KeyVersionNotFound(format!("{}:{}", name, version)))
+    }
+
+    fn generate_private_key() -> Ed25519PrivateKey {
+        let mut seed_rng = OsRng::new().expect("can't access OsRng");
+        let mut rng = StdRng::from_seed(seed_rng.gen());
+        Ed25519PrivateKey::generate_for_testing(&mut rng)
+    }
+}
+
+impl CryptoStorage for KeyStore {
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let private_key = Self::generate_private_key();
+        let public_key = private_key.public_key();
+        self.import_key(name, private_key)?;
+        Ok(public_key)
+    }
+
+    fn import_key(&mut self, name: &str, private_key: Ed25519PrivateKey) -> Result<(), Error> {
+        if self.keys.contains_key(name) {
+            return Err(Error::KeyAlreadyExists(name.to_string()));
+        }
+        self.keys.insert(name.to_string(), vec![private_key]);
+        Ok(())
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        let versions = self.versions(name)?;
+        // Keys are never stored without a version.
+        let version = versions.len() - 1;
+        Ok(PublicKeyResponse {
+            public_key: versions[version].public_key(),
+            version: version as u64,
+        })
+    }
+
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        Ok(self.version(name, version)?.public_key())
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let versions = self
+            .keys
+            .get_mut(name)
+            .ok_or_else(|| Error::KeyNotSet(name.to_string()))?;
+        let private_key = Self::generate_private_key();
+        let public_key = private_key.public_key();
+        versions.push(private_key);
+        Ok(public_key)
+    }
+
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        let version = self.get_public_key(name)?.version;
+        self.sign_message_using_version(name, version, message)
+    }
+
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        Ok(self.version(name, version)?.sign_message(message))
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    crypto_storage::{CryptoStorage, KeyStore, PublicKeyResponse},
+    error::Error,
+    permissions::{Id, Permissions},
+    storage::Storage,
+    value::Value,
+};
+use hmac::Hmac;
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hkdf::Hkdf,
+    HashValue,
+};
+use libra_temppath::TempPath;
+use rand::{rngs::OsRng, RngCore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Domain separation of the key derivation, which also authenticates the format of the file.
+const KEY_INFO: &[u8] = b"LIBRA_SECURE_STORAGE_ENCRYPTED_ON_DISK_V1";
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 32;
+/// Passphrases are stretched with PBKDF2-HMAC-SHA256 before HKDF, to slow down guessing.
+const PBKDF2_ITERATIONS: usize = 100_000;
+
+/// How the encryption key of an EncryptedOnDiskStorage is obtained.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockKey {
+    /// A passphrase, stretched by a password-based key derivation function.
+    Passphrase(String),
+    /// A file holding a hex encoded key of 32 random bytes, see `generate_key_file`.
+    KeyFile(PathBuf),
+}
+
+impl UnlockKey {
+    /// Writes a new random key to a key file, which must not exist yet.
+    pub fn generate_key_file(path: &Path) -> Result<(), Error> {
+        let mut key = [0u8; KEY_LENGTH];
+        OsRng::new()?.fill_bytes(&mut key);
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        file.write_all(hex::encode(key).as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Derives the key wrapping the file key for `id` from the passphrase or the content of the key
+    /// file.
+    fn derive(&self, salt: &[u8], id: &str) -> Result<Vec<u8>, Error> {
+        let input_key = match self {
+            UnlockKey::Passphrase(passphrase) => {
+                let mut stretched = [0u8; KEY_LENGTH];
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(
+                    passphrase.as_bytes(),
+                    salt,
+                    PBKDF2_ITERATIONS,
+                    &mut stretched,
+                );
+                stretched.to_vec()
+            }
+            UnlockKey::KeyFile(path) => {
+                let mut contents = String::new();
+                File::open(path)?.read_to_string(&mut contents)?;
+                let key = hex::decode(contents.trim())
+                    .map_err(|e| Error::SerializationError(e.to_string()))?;
+                if key.len() != KEY_LENGTH {
+                    return Err(Error::SerializationError(format!(
+                        "Expected a key of {} bytes in {:?}",
+                        KEY_LENGTH, path
+                    )));
+                }
+                key
+            }
+        };
+        let info = [KEY_INFO, id.as_bytes()].concat();
+        Hkdf::<Sha256>::extract_then_expand(Some(salt), &input_key, Some(&info), KEY_LENGTH)
+            .map_err(|e| Error::InternalError(e.to_string()))
+    }
+}
+
+/// EncryptedOnDiskStorage is a file based key value store that, unlike OnDiskStorage, encrypts
+/// its contents with ChaCha20-Poly1305 under a random file key. Each write uses a fresh nonce and
+/// atomically replaces the file, syncing it to disk before renaming it over the previous version.
+///
+/// Every Id has its own unlock key: the file keeps a slot per Id holding the file key wrapped with
+/// a key derived with HKDF from the Id and either a stretched passphrase or a key file, salted by
+/// a random value kept in the slot. The storage is opened on behalf of an Id only if its slot can
+/// be unwrapped, the permissions of each value are then enforced for that Id: only writers may
+/// create or set a value and only readers may get it. The Id creating the file owns it and is the
+/// only one allowed to add the slots of other Ids, see `add_id`.
+///
+/// Signing keys are not subject to permissions and may be used by any Id. The permissions are
+/// enforced by the storage, an Id able to unlock the file and to write it outside of the storage
+/// is trusted with all of its contents.
+pub struct EncryptedOnDiskStorage {
+    file_path: PathBuf,
+    temp_path: TempPath,
+    id: Id,
+    file_key: Vec<u8>,
+}
+
+/// The unencrypted layout of the file.
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    slots: Vec<KeySlot>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+/// The file key wrapped with the unlock key of an Id.
+#[derive(Clone, Deserialize, Serialize)]
+struct KeySlot {
+    id: Id,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    wrapped_key: Vec<u8>,
+}
+
+impl KeySlot {
+    fn new(id: Id, unlock_key: &UnlockKey, file_key: &[u8]) -> Result<Self, Error> {
+        let mut salt = vec![0u8; SALT_LENGTH];
+        OsRng::new()?.fill_bytes(&mut salt);
+        let key = unlock_key.derive(&salt, &id)?;
+        let (nonce, wrapped_key) = seal(&key, id.as_bytes(), file_key.to_vec())?;
+        Ok(Self {
+            id,
+            salt,
+            nonce,
+            wrapped_key,
+        })
+    }
+
+    fn unwrap_key(&self, unlock_key: &UnlockKey) -> Result<Vec<u8>, Error> {
+        let key = unlock_key.derive(&self.salt, &self.id)?;
+        open(
+            &key,
+            self.id.as_bytes(),
+            &self.nonce,
+            self.wrapped_key.clone(),
+        )
+        .ok_or_else(|| {
+            Error::InternalError(format!("Unable to unlock the file key of {}", self.id))
+        })
+    }
+}
+
+/// The decrypted contents of the file.
+#[derive(Deserialize, Serialize)]
+struct Contents {
+    owner: Id,
+    #[serde(default)]
+    data: HashMap<String, Entry>,
+    #[serde(default)]
+    keys: KeyStore,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    value: Value,
+    permissions: Permissions,
+}
+
+impl EncryptedOnDiskStorage {
+    /// Opens the storage at `file_path` on behalf of `id`, creating an empty one owned by `id` if
+    /// the file does not exist. Fails if the file has no slot for `id` or if the slot cannot be
+    /// unwrapped with the unlock key.
+    pub fn new(file_path: PathBuf, unlock_key: &UnlockKey, id: Id) -> Result<Self, Error> {
+        // The parent will be one when only a filename is supplied. Therefore use the current
+        // working directory provided by PathBuf::new().
+        let file_dir = file_path
+            .parent()
+            .map_or(PathBuf::new(), |p| p.to_path_buf());
+        let temp_path = TempPath::new_with_temp_dir(file_dir);
+
+        if file_path.exists() {
+            let file_key = Self::read_file(&file_path)?
+                .slots
+                .iter()
+                .find(|slot| slot.id == id)
+                .ok_or(Error::PermissionDenied)?
+                .unwrap_key(unlock_key)?;
+            let storage = Self {
+                file_path,
+                temp_path,
+                id,
+                file_key,
+            };
+            // Checks the integrity of the contents.
+            storage.read()?;
+            Ok(storage)
+        } else {
+            let mut file_key = vec![0u8; KEY_LENGTH];
+            OsRng::new()?.fill_bytes(&mut file_key);
+            let slots = vec![KeySlot::new(id.clone(), unlock_key, &file_key)?];
+            let contents = Contents {
+                owner: id.clone(),
+                data: HashMap::new(),
+                keys: KeyStore::default(),
+            };
+            let storage = Self {
+                file_path,
+                temp_path,
+                id,
+                file_key,
+            };
+            storage.write(&slots, &contents)?;
+            Ok(storage)
+        }
+    }
+
+    /// Allows `id` to open the storage with `unlock_key`. Only the owner of the storage may add
+    /// Ids and an Id cannot be added twice, so that its unlock key cannot be replaced.
+    pub fn add_id(&mut self, id: Id, unlock_key: &UnlockKey) -> Result<(), Error> {
+        let (mut slots, contents) = self.read_all()?;
+        if contents.owner != self.id {
+            return Err(Error::PermissionDenied);
+        }
+        if slots.iter().any(|slot| slot.id == id) {
+            return Err(Error::KeyAlreadyExists(id));
+        }
+        slots.push(KeySlot::new(id, unlock_key, &self.file_key)?);
+        self.write(&slots, &contents)
+    }
+
+    fn read_file(file_path: &Path) -> Result<EncryptedFile, Error> {
+        let mut file = File::open(file_path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(lcs::from_bytes(&bytes)?)
+    }
+
+    fn read(&self) -> Result<Contents, Error> {
+        Ok(self.read_all()?.1)
+    }
+
+    fn read_all(&self) -> Result<(Vec<KeySlot>, Contents), Error> {
+        let file = Self::read_file(&self.file_path)?;
+        let plaintext =
+            open(&self.file_key, KEY_INFO, &file.nonce, file.ciphertext).ok_or_else(|| {
+                Error::InternalError(format!("Unable to decrypt {:?}", self.file_path))
+            })?;
+        Ok((file.slots, serde_json::from_slice(&plaintext)?))
+    }
+
+    fn write(&self, slots: &[KeySlot], contents: &Contents) -> Result<(), Error> {
+        let (nonce, ciphertext) = seal(&self.file_key, KEY_INFO, serde_json::to_vec(contents)?)?;
+        let bytes = lcs::to_bytes(&EncryptedFile {
+            slots: slots.to_vec(),
+            nonce,
+            ciphertext,
+        })?;
+
+        let mut file = File::create(self.temp_path.path())?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&self.temp_path, &self.file_path)?;
+        // Persists the rename itself.
+        if let Some(parent) = self.file_path.parent() {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            File::open(parent)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Applies a change to the keys and persists them.
+    fn update_keys<T>(
+        &self,
+        update: impl FnOnce(&mut KeyStore) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let (slots, mut contents) = self.read_all()?;
+        let result = update(&mut contents.keys)?;
+        self.write(&slots, &contents)?;
+        Ok(result)
+    }
+}
+
+/// Encrypts `plaintext` with a fresh nonce, returning the nonce and the ciphertext.
+fn seal(key: &[u8], aad: &[u8], mut plaintext: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng::new()?.fill_bytes(&mut nonce);
+    aead_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut plaintext,
+        )
+        .map_err(|_| Error::InternalError("Unable to encrypt storage".into()))?;
+    Ok((nonce.to_vec(), plaintext))
+}
+
+/// Decrypts `ciphertext`, returning None if it was not sealed with `key` and `aad`.
+fn open(key: &[u8], aad: &[u8], nonce: &[u8], mut ciphertext: Vec<u8>) -> Option<Vec<u8>> {
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let plaintext = aead_key(key)
+        .ok()?
+        .open_in_place(nonce, Aad::from(aad), &mut ciphertext)
+        .ok()?;
+    Some(plaintext.to_vec())
+}
+
+fn aead_key(key: &[u8]) -> Result<LessSafeKey, Error> {
+    let key = UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map_err(|_| Error::InternalError("Invalid encryption key".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+impl Storage for EncryptedOnDiskStorage {
+    fn create(&mut self, key: &str, value: Value, permissions: &Permissions) -> Result<(), Error> {
+        if !permissions.writers.allows(&self.id) {
+            return Err(Error::PermissionDenied);
+        }
+        let (slots, mut contents) = self.read_all()?;
+        if contents.data.contains_key(key) {
+            return Err(Error::KeyAlreadyExists(key.to_string()));
+        }
+        let entry = Entry {
+            value,
+            permissions: permissions.clone(),
+        };
+        contents.data.insert(key.to_string(), entry);
+        self.write(&slots, &contents)
+    }
+
+    fn get(&self, key: &str) -> Result<Value, Error> {
+        let mut contents = self.read()?;
+        let entry = contents
+            .data
+            .remove(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+        if !entry.permissions.readers.allows(&self.id) {
+            return Err(Error::PermissionDenied);
+        }
+        Ok(entry.value)
+    }
+
+    fn set(&mut self, key: &str, value: Value) -> Result<(), Error> {
+        let (slots, mut contents) = self.read_all()?;
+        let entry = contents
+            .data
+            .get_mut(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+        if !entry.permissions.writers.allows(&self.id) {
+            return Err(Error::PermissionDenied);
+        }
+        entry.value = value;
+        self.write(&slots, &contents)
+    }
+}
+
+impl CryptoStorage for EncryptedOnDiskStorage {
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.update_keys(|keys| keys.generate_key(name))
+    }
+
+    fn import_key(&mut self, name: &str, private_key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.update_keys(|keys| keys.import_key(name, private_key))
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.read()?.keys.get_public_key(name)
+    }
+
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        self.read()?.keys.get_public_key_for_version(name, version)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.update_keys(|keys| keys.rotate_key(name))
+    }
+
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        self.read()?.keys.sign_message(name, message)
+    }
+
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        self.read()?
+            .keys
+            .sign_message_using_version(name, version, message)
+    }
+}
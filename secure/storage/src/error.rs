@@ -14,6 +14,12 @@ pub enum Error {
     KeyAlreadyExists(String),
     #[error("Key already exists: {}", 0)]
     KeyNotSet(String),
+    #[error("Key import not supported: {0}")]
+    KeyImportNotSupported(String),
+    #[error("Key version not found: {0}")]
+    KeyVersionNotFound(String),
+    #[error("Permission denied")]
+    PermissionDenied,
     #[error("Serialization error: {0}")]
     SerializationError(String),
     #[error("Unexpected value type")]
@@ -43,3 +49,21 @@ impl From<lcs::Error> for Error {
         Self::SerializationError(format!("{}", error))
     }
 }
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Self::InternalError(format!("{}", error))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<rand::Error> for Error {
+    fn from(error: rand::Error) -> Self {
+        Self::InternalError(format!("{}", error))
+    }
+}
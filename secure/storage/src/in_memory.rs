@@ -1,7 +1,17 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{error::Error, permissions::Permissions, storage::Storage, value::Value};
+use crate::{
+    crypto_storage::{CryptoStorage, KeyStore, PublicKeyResponse},
+    error::Error,
+    permissions::Permissions,
+    storage::Storage,
+    value::Value,
+};
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue,
+};
 use std::collections::HashMap;
 
 /// InMemoryStorage represents a key value store that is purely in memory and intended for single
@@ -12,12 +22,14 @@ use std::collections::HashMap;
 /// securely handle key material. This should not be used in production.
 pub struct InMemoryStorage {
     data: HashMap<String, Value>,
+    keys: KeyStore,
 }
 
 impl InMemoryStorage {
     pub fn new() -> Self {
         Self {
             data: HashMap::new(),
+            keys: KeyStore::default(),
         }
     }
 }
@@ -32,22 +44,10 @@ impl Storage for InMemoryStorage {
     }
 
     fn get(&self, key: &str) -> Result<Value, Error> {
-        let value = self
-            .data
+        self.data
             .get(key)
-            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
-
-        let value = match value {
-            Value::Ed25519PrivateKey(value) => {
-                // Hack because Ed25519PrivateKey does not support clone / copy
-                let bytes = lcs::to_bytes(value)?;
-                let key = lcs::from_bytes(&bytes)?;
-                Value::Ed25519PrivateKey(key)
-            }
-            Value::U64(value) => Value::U64(*value),
-        };
-
-        Ok(value)
+            .cloned()
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))
     }
 
     fn set(&mut self, key: &str, value: Value) -> Result<(), Error> {
@@ -58,3 +58,42 @@ impl Storage for InMemoryStorage {
         Ok(())
     }
 }
+
+impl CryptoStorage for InMemoryStorage {
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.keys.generate_key(name)
+    }
+
+    fn import_key(&mut self, name: &str, private_key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.keys.import_key(name, private_key)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.keys.get_public_key(name)
+    }
+
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        self.keys.get_public_key_for_version(name, version)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.keys.rotate_key(name)
+    }
+
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        self.keys.sign_message(name, message)
+    }
+
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        self.keys.sign_message_using_version(name, version, message)
+    }
+}
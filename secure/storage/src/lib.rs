@@ -3,20 +3,29 @@
 
 #![forbid(unsafe_code)]
 
+mod crypto_storage;
+mod encrypted_on_disk;
 mod error;
 mod in_memory;
 mod on_disk;
 mod permissions;
 mod storage;
 mod value;
+mod vault;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_vault;
 
 pub use crate::{
+    crypto_storage::{CryptoStorage, PublicKeyResponse},
+    encrypted_on_disk::{EncryptedOnDiskStorage, UnlockKey},
     error::Error,
     in_memory::InMemoryStorage,
     on_disk::OnDiskStorage,
     permissions::{Id, Permission, Permissions},
     storage::Storage,
     value::Value,
+    vault::VaultStorage,
 };
 
 #[cfg(test)]
@@ -0,0 +1,282 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A stand-in for the subset of the Vault HTTP API used by VaultStorage, so that it can be tested
+//! without a Vault server. It keeps everything in memory, enforces the policies attached to the
+//! tokens the same way Vault does for exact paths, and serves one request per connection.
+
+use libra_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::{json, Value as JsonValue};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+pub const ROOT_TOKEN: &str = "root";
+
+struct Secret {
+    data: JsonValue,
+    version: u64,
+}
+
+struct State {
+    /// The policies attached to each token, the root token bypasses all of them.
+    tokens: HashMap<String, Vec<String>>,
+    policies: HashMap<String, String>,
+    secrets: HashMap<String, Secret>,
+    /// The versions of each transit key, Vault numbering them from 1.
+    transit_keys: HashMap<String, Vec<Ed25519PrivateKey>>,
+    rng: StdRng,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    token: String,
+    body: JsonValue,
+}
+
+/// Starts the stand-in on a random local port and returns its address, e.g. http://127.0.0.1:1234
+pub fn start() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    let state = Arc::new(Mutex::new(State {
+        tokens: HashMap::new(),
+        policies: HashMap::new(),
+        secrets: HashMap::new(),
+        transit_keys: HashMap::new(),
+        rng: StdRng::from_seed([7u8; 32]),
+    }));
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let state = state.clone();
+            thread::spawn(move || serve(stream, &state));
+        }
+    });
+    host
+}
+
+fn serve(mut stream: TcpStream, state: &Mutex<State>) {
+    let request = match read_request(&stream) {
+        Some(request) => request,
+        None => return,
+    };
+    let (status, body) = handle(&mut state.lock().unwrap(), request);
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.trim_start_matches("/v1/").to_string();
+
+    let mut token = String::new();
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let mut split = header.splitn(2, ':');
+        let name = split.next()?.to_lowercase();
+        let value = split.next()?.trim();
+        match name.as_str() {
+            "x-vault-token" => token = value.to_string(),
+            "content-length" => content_length = value.parse().ok()?,
+            _ => (),
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+    let body = if body.is_empty() {
+        JsonValue::Null
+    } else {
+        serde_json::from_slice(&body).ok()?
+    };
+    Some(Request {
+        method,
+        path,
+        token,
+        body,
+    })
+}
+
+fn handle(state: &mut State, request: Request) -> (&'static str, JsonValue) {
+    let segments: Vec<&str> = request.path.split('/').collect();
+    let capability = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", _) => "read",
+        ("POST", ["secret", "data", key]) if !state.secrets.contains_key(*key) => "create",
+        _ => "update",
+    };
+    if !state.allowed(&request.token, &request.path, capability) {
+        return forbidden();
+    }
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["auth", "token", "create"]) => {
+            let token = format!("token-{}", state.tokens.len());
+            let policies = serde_json::from_value(request.body["policies"].clone()).unwrap();
+            state.tokens.insert(token.clone(), policies);
+            ok(json!({ "auth": { "client_token": token } }))
+        }
+        ("GET", ["sys", "policy", name]) => match state.policies.get(*name) {
+            Some(rules) => ok(json!({ "name": name, "rules": rules })),
+            None => not_found(),
+        },
+        ("PUT", ["sys", "policy", name]) => {
+            let rules = request.body["policy"].as_str().unwrap().to_string();
+            state.policies.insert(name.to_string(), rules);
+            no_content()
+        }
+        ("GET", ["secret", "data", key]) => match state.secrets.get(*key) {
+            Some(secret) => ok(json!({
+                "data": { "data": secret.data, "metadata": { "version": secret.version } }
+            })),
+            None => not_found(),
+        },
+        ("POST", ["secret", "data", key]) => {
+            let current = state.secrets.get(*key).map_or(0, |secret| secret.version);
+            if let Some(cas) = request.body["options"]["cas"].as_u64() {
+                if cas != current {
+                    return (
+                        "400 Bad Request",
+                        json!({ "errors": [
+                            "check-and-set parameter did not match the current version"
+                        ] }),
+                    );
+                }
+            }
+            let secret = Secret {
+                data: request.body["data"].clone(),
+                version: current + 1,
+            };
+            state.secrets.insert(key.to_string(), secret);
+            ok(json!({ "data": { "version": current + 1 } }))
+        }
+        ("POST", ["transit", "keys", name]) => {
+            if !state.transit_keys.contains_key(*name) {
+                let private_key = Ed25519PrivateKey::generate_for_testing(&mut state.rng);
+                state
+                    .transit_keys
+                    .insert(name.to_string(), vec![private_key]);
+            }
+            no_content()
+        }
+        ("POST", ["transit", "keys", name, "rotate"]) => {
+            let private_key = Ed25519PrivateKey::generate_for_testing(&mut state.rng);
+            match state.transit_keys.get_mut(*name) {
+                Some(versions) => {
+                    versions.push(private_key);
+                    no_content()
+                }
+                None => not_found(),
+            }
+        }
+        ("GET", ["transit", "keys", name]) => match state.transit_keys.get(*name) {
+            Some(versions) => {
+                let keys: HashMap<String, JsonValue> = versions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, private_key)| {
+                        let public_key = base64::encode(&private_key.public_key().to_bytes());
+                        ((index + 1).to_string(), json!({ "public_key": public_key }))
+                    })
+                    .collect();
+                ok(json!({ "data": { "latest_version": versions.len(), "keys": keys } }))
+            }
+            None => not_found(),
+        },
+        ("POST", ["transit", "sign", name]) => match state.transit_keys.get(*name) {
+            Some(versions) => {
+                // Without a version, the latest one signs.
+                let version = match request.body["key_version"].as_u64() {
+                    Some(version) if version > 0 => version as usize,
+                    _ => versions.len(),
+                };
+                let private_key = match versions.get(version - 1) {
+                    Some(private_key) => private_key,
+                    None => return ("400 Bad Request", json!({ "errors": ["invalid version"] })),
+                };
+                let input = base64::decode(request.body["input"].as_str().unwrap()).unwrap();
+                let message = HashValue::from_slice(&input).unwrap();
+                let signature = private_key.sign_message(&message);
+                let signature = format!(
+                    "vault:v{}:{}",
+                    version,
+                    base64::encode(&signature.to_bytes())
+                );
+                ok(json!({ "data": { "signature": signature } }))
+            }
+            None => not_found(),
+        },
+        _ => not_found(),
+    }
+}
+
+impl State {
+    fn allowed(&self, token: &str, path: &str, capability: &str) -> bool {
+        if token == ROOT_TOKEN {
+            return true;
+        }
+        let policies = match self.tokens.get(token) {
+            Some(policies) => policies,
+            None => return false,
+        };
+        policies
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once("default"))
+            .filter_map(|policy| self.policies.get(policy))
+            .any(|rules| Self::rules_allow(rules, path, capability))
+    }
+
+    /// Parses the `path "<path>" { capabilities = [...] }` stanzas of a policy.
+    fn rules_allow(rules: &str, path: &str, capability: &str) -> bool {
+        rules.split("path \"").skip(1).any(|stanza| {
+            let mut split = stanza.splitn(2, '"');
+            let stanza_path = split.next().unwrap_or("");
+            let capabilities = split.next().unwrap_or("");
+            stanza_path == path && capabilities.contains(&format!("\"{}\"", capability))
+        })
+    }
+}
+
+fn ok(body: JsonValue) -> (&'static str, JsonValue) {
+    ("200 OK", body)
+}
+
+fn no_content() -> (&'static str, JsonValue) {
+    ("204 No Content", JsonValue::Null)
+}
+
+fn not_found() -> (&'static str, JsonValue) {
+    ("404 Not Found", json!({ "errors": [] }))
+}
+
+fn forbidden() -> (&'static str, JsonValue) {
+    ("403 Forbidden", json!({ "errors": ["permission denied"] }))
+}
@@ -1,8 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{error::Error, permissions::Permissions, storage::Storage, value::Value};
+use crate::{
+    crypto_storage::{CryptoStorage, KeyStore, PublicKeyResponse},
+    error::Error,
+    permissions::Permissions,
+    storage::Storage,
+    value::Value,
+};
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue,
+};
 use libra_temppath::TempPath;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::{
     fs::{self, File},
@@ -22,6 +33,55 @@ pub struct OnDiskStorage {
     temp_path: TempPath,
 }
 
+/// A value of the files written before the signing keys were kept apart from the values.
+#[derive(Deserialize, Serialize)]
+#[serde(content = "value", rename_all = "snake_case", tag = "type")]
+pub(crate) enum LegacyValue {
+    Ed25519PrivateKey(Ed25519PrivateKey),
+    U64(u64),
+}
+
+/// The contents of the file: values are kept apart from signing keys, which can only be used
+/// through CryptoStorage.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Contents {
+    #[serde(default)]
+    data: HashMap<String, Value>,
+    #[serde(default)]
+    keys: KeyStore,
+}
+
+impl Contents {
+    /// Moves the private keys of a file written before keys were kept apart into the key store.
+    fn from_legacy(legacy: HashMap<String, LegacyValue>) -> Result<Self, Error> {
+        let mut contents = Contents {
+            data: HashMap::new(),
+            keys: KeyStore::default(),
+        };
+        for (key, value) in legacy {
+            match value {
+                LegacyValue::Ed25519PrivateKey(private_key) => {
+                    contents.keys.import_key(&key, private_key)?
+                }
+                LegacyValue::U64(value) => {
+                    contents.data.insert(key, Value::U64(value));
+                }
+            }
+        }
+        Ok(contents)
+    }
+}
+
+/// Older files hold a flat table of values, including private keys, so both layouts are accepted.
+/// They are rewritten in the current layout on the next change.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FileContents {
+    Current(Contents),
+    Legacy(HashMap<String, LegacyValue>),
+}
+
 impl OnDiskStorage {
     pub fn new(file_path: PathBuf) -> Self {
         if !file_path.exists() {
@@ -40,45 +100,101 @@ impl OnDiskStorage {
         }
     }
 
-    fn read(&self) -> Result<HashMap<String, Value>, Error> {
+    fn read(&self) -> Result<Contents, Error> {
         let mut file = File::open(&self.file_path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        let data = toml::from_str(&contents)?;
-        Ok(data)
+        match toml::from_str(&contents)? {
+            FileContents::Current(contents) => Ok(contents),
+            FileContents::Legacy(legacy) => Contents::from_legacy(legacy),
+        }
     }
 
-    fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
-        let contents = toml::to_vec(data)?;
+    fn write(&self, contents: &Contents) -> Result<(), Error> {
+        let contents = toml::to_vec(contents)?;
         let mut file = File::create(self.temp_path.path())?;
         file.write_all(&contents)?;
         fs::rename(&self.temp_path, &self.file_path)?;
         Ok(())
     }
+
+    /// Applies a change to the keys and persists them.
+    fn update_keys<T>(
+        &self,
+        update: impl FnOnce(&mut KeyStore) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut contents = self.read()?;
+        let result = update(&mut contents.keys)?;
+        self.write(&contents)?;
+        Ok(result)
+    }
 }
 
 impl Storage for OnDiskStorage {
     fn create(&mut self, key: &str, value: Value, _permissions: &Permissions) -> Result<(), Error> {
-        let mut data = self.read()?;
-        if data.contains_key(key) {
+        let mut contents = self.read()?;
+        if contents.data.contains_key(key) {
             return Err(Error::KeyAlreadyExists(key.to_string()));
         }
-        data.insert(key.to_string(), value);
-        self.write(&data)
+        contents.data.insert(key.to_string(), value);
+        self.write(&contents)
     }
 
     fn get(&self, key: &str) -> Result<Value, Error> {
-        let mut data = self.read()?;
-        data.remove(key)
+        let mut contents = self.read()?;
+        contents
+            .data
+            .remove(key)
             .ok_or_else(|| Error::KeyNotSet(key.to_string()))
     }
 
     fn set(&mut self, key: &str, value: Value) -> Result<(), Error> {
-        let mut data = self.read()?;
-        if !data.contains_key(key) {
+        let mut contents = self.read()?;
+        if !contents.data.contains_key(key) {
             return Err(Error::KeyNotSet(key.to_string()));
         }
-        data.insert(key.to_string(), value);
-        self.write(&data)
+        contents.data.insert(key.to_string(), value);
+        self.write(&contents)
+    }
+}
+
+impl CryptoStorage for OnDiskStorage {
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.update_keys(|keys| keys.generate_key(name))
+    }
+
+    fn import_key(&mut self, name: &str, private_key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.update_keys(|keys| keys.import_key(name, private_key))
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.read()?.keys.get_public_key(name)
+    }
+
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        self.read()?.keys.get_public_key_for_version(name, version)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.update_keys(|keys| keys.rotate_key(name))
+    }
+
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        self.read()?.keys.sign_message(name, message)
+    }
+
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        self.read()?
+            .keys
+            .sign_message_using_version(name, version, message)
     }
 }
@@ -4,14 +4,14 @@
 use serde::{Deserialize, Serialize};
 
 /// Permissions dictate which Ids may perform different operations.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Permissions {
     pub readers: Permission,
     pub writers: Permission,
 }
 
 /// Different possibilities for permissions, a set of Ids that are eligible, Any Id, or No Ids.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Permission {
     Users(Vec<Id>),
     Anyone,
@@ -33,3 +33,14 @@ impl Permissions {
         }
     }
 }
+
+impl Permission {
+    /// Returns whether the given Id is eligible.
+    pub fn allows(&self, id: &Id) -> bool {
+        match self {
+            Permission::Users(ids) => ids.contains(id),
+            Permission::Anyone => true,
+            Permission::NoOne => false,
+        }
+    }
+}
@@ -1,13 +1,14 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{Error, Permissions, Value};
+use crate::{CryptoStorage, Error, Permissions, Value};
 
 /// Libra interface into storage. Create takes a set of permissions that are enforced internally by
 /// the actual backend. The permissions contain public identities that the backend can translate
 /// into a unique and private token for another service. Hence get and set internally will pass the
-/// current service private token to the backend to gain its permissions.
-pub trait Storage: Send + Sync {
+/// current service private token to the backend to gain its permissions. Signing keys are not
+/// stored as values but through CryptoStorage, so that they cannot be read back.
+pub trait Storage: CryptoStorage + Send + Sync {
     /// Creates a new value if it does not exist fails only if there is some other issue.
     fn create_if_not_exists(
         &mut self,
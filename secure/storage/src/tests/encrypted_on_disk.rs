@@ -0,0 +1,157 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    encrypted_on_disk::{EncryptedOnDiskStorage, UnlockKey},
+    error::Error,
+    permissions::{Permission, Permissions},
+    storage::Storage,
+    tests::suite,
+    value::Value,
+};
+use libra_temppath::TempPath;
+use std::fs;
+
+const SAFETY_RULES: &str = "safety_rules";
+const KEY_MANAGER: &str = "key_manager";
+
+fn key_file() -> (TempPath, UnlockKey) {
+    let key_path = TempPath::new();
+    UnlockKey::generate_key_file(key_path.path()).unwrap();
+    let unlock_key = UnlockKey::KeyFile(key_path.path().to_path_buf());
+    (key_path, unlock_key)
+}
+
+#[test]
+fn encrypted_on_disk() {
+    let temp_path = TempPath::new();
+    let (_key_path, unlock_key) = key_file();
+    let storage = EncryptedOnDiskStorage::new(
+        temp_path.path().to_path_buf(),
+        &unlock_key,
+        suite::SUITE_ID.into(),
+    )
+    .unwrap();
+    suite::run_test_suite(Box::new(storage));
+}
+
+#[test]
+fn encrypted_on_disk_unlock() {
+    let temp_path = TempPath::new();
+    let unlock_key = UnlockKey::Passphrase("correct horse battery staple".into());
+    let mut storage =
+        EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), &unlock_key, "id".into())
+            .unwrap();
+    storage
+        .create("epoch", Value::U64(0xdead_beef), &Permissions::anyone())
+        .unwrap();
+
+    let contents = fs::read(temp_path.path()).unwrap();
+    let plaintext = 0xdead_beefu64.to_string();
+    assert!(!contents
+        .windows(plaintext.len())
+        .any(|window| window == plaintext.as_bytes()));
+
+    let storage =
+        EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), &unlock_key, "id".into())
+            .unwrap();
+    assert_eq!(storage.get("epoch").unwrap(), Value::U64(0xdead_beef));
+
+    assert_eq!(
+        EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), &unlock_key, "other".into())
+            .err()
+            .unwrap(),
+        Error::PermissionDenied
+    );
+    let wrong_key = UnlockKey::Passphrase("wrong".into());
+    EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), &wrong_key, "id".into())
+        .err()
+        .unwrap();
+    let (_key_path, wrong_key) = key_file();
+    EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), &wrong_key, "id".into())
+        .err()
+        .unwrap();
+}
+
+#[test]
+fn encrypted_on_disk_ids() {
+    let temp_path = TempPath::new();
+    let (_key_path, key_manager_key) = key_file();
+    let (_key_path, safety_rules_key) = key_file();
+    let open = |id: &str, unlock_key: &UnlockKey| {
+        EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), unlock_key, id.into())
+    };
+
+    let mut key_manager = open(KEY_MANAGER, &key_manager_key).unwrap();
+    assert_eq!(
+        open(SAFETY_RULES, &key_manager_key).err().unwrap(),
+        Error::PermissionDenied
+    );
+    key_manager
+        .add_id(SAFETY_RULES.into(), &safety_rules_key)
+        .unwrap();
+    assert_eq!(
+        key_manager
+            .add_id(SAFETY_RULES.into(), &key_manager_key)
+            .unwrap_err(),
+        Error::KeyAlreadyExists(SAFETY_RULES.into())
+    );
+
+    // Each Id is bound to its own unlock key
+    open(SAFETY_RULES, &key_manager_key).err().unwrap();
+    open(KEY_MANAGER, &safety_rules_key).err().unwrap();
+    let mut safety_rules = open(SAFETY_RULES, &safety_rules_key).unwrap();
+
+    // Only the owner adds Ids
+    let (_key_path, other_key) = key_file();
+    assert_eq!(
+        safety_rules.add_id("other".into(), &other_key).unwrap_err(),
+        Error::PermissionDenied
+    );
+}
+
+#[test]
+fn encrypted_on_disk_permissions() {
+    let temp_path = TempPath::new();
+    let (_key_path, key_manager_key) = key_file();
+    let (_key_path, safety_rules_key) = key_file();
+    let open = |id: &str, unlock_key: &UnlockKey| {
+        EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), unlock_key, id.into()).unwrap()
+    };
+
+    let mut key_manager = open(KEY_MANAGER, &key_manager_key);
+    key_manager
+        .add_id(SAFETY_RULES.into(), &safety_rules_key)
+        .unwrap();
+    let permissions = Permissions {
+        readers: Permission::Users(vec![SAFETY_RULES.into()]),
+        writers: Permission::Users(vec![KEY_MANAGER.into()]),
+    };
+    key_manager
+        .create("epoch", Value::U64(5), &permissions)
+        .unwrap();
+    assert_eq!(
+        key_manager.get("epoch").unwrap_err(),
+        Error::PermissionDenied
+    );
+    key_manager.set("epoch", Value::U64(6)).unwrap();
+
+    let mut safety_rules = open(SAFETY_RULES, &safety_rules_key);
+    assert_eq!(safety_rules.get("epoch").unwrap(), Value::U64(6));
+    assert_eq!(
+        safety_rules.set("epoch", Value::U64(7)).unwrap_err(),
+        Error::PermissionDenied
+    );
+
+    // Values are only created by their writers
+    assert_eq!(
+        safety_rules
+            .create("round", Value::U64(0), &permissions)
+            .unwrap_err(),
+        Error::PermissionDenied
+    );
+    assert_eq!(
+        safety_rules.get("round").unwrap_err(),
+        Error::KeyNotSet("round".into())
+    );
+}
@@ -1,6 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod encrypted_on_disk;
 mod in_memory;
 mod on_disk;
 mod suite;
+mod vault;
@@ -1,8 +1,14 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{on_disk::OnDiskStorage, tests::suite};
+use crate::{
+    crypto_storage::CryptoStorage, on_disk::LegacyValue, on_disk::OnDiskStorage,
+    permissions::Permissions, storage::Storage, tests::suite, value::Value,
+};
+use libra_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
 use libra_temppath::TempPath;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{collections::HashMap, fs};
 
 #[test]
 fn on_disk() {
@@ -10,3 +16,56 @@ fn on_disk() {
     let storage = Box::new(OnDiskStorage::new(temp_path.path().to_path_buf()));
     suite::run_test_suite(storage);
 }
+
+#[test]
+fn on_disk_keys_persist() {
+    let temp_path = TempPath::new();
+    let private_key = Ed25519PrivateKey::generate_for_testing(&mut StdRng::from_seed([5u8; 32]));
+    let public_key = private_key.public_key();
+
+    let mut storage = OnDiskStorage::new(temp_path.path().to_path_buf());
+    storage.import_key("consensus", private_key).unwrap();
+    storage.rotate_key("consensus").unwrap();
+
+    let storage = OnDiskStorage::new(temp_path.path().to_path_buf());
+    assert_eq!(storage.get_public_key("consensus").unwrap().version, 1);
+    assert_eq!(
+        storage.get_public_key_for_version("consensus", 0).unwrap(),
+        public_key
+    );
+}
+
+#[test]
+fn on_disk_legacy_file() {
+    let temp_path = TempPath::new();
+    let private_key = Ed25519PrivateKey::generate_for_testing(&mut StdRng::from_seed([5u8; 32]));
+    let public_key = private_key.public_key();
+
+    let mut legacy = HashMap::new();
+    legacy.insert("epoch".to_string(), LegacyValue::U64(5));
+    legacy.insert(
+        "consensus".to_string(),
+        LegacyValue::Ed25519PrivateKey(private_key),
+    );
+    fs::write(temp_path.path(), toml::to_vec(&legacy).unwrap()).unwrap();
+
+    let mut storage = OnDiskStorage::new(temp_path.path().to_path_buf());
+    assert_eq!(storage.get("epoch").unwrap(), Value::U64(5));
+    assert_eq!(
+        storage.get_public_key("consensus").unwrap().public_key,
+        public_key
+    );
+    storage.get("consensus").unwrap_err();
+
+    // The next change rewrites the file in the current layout.
+    storage
+        .create("round", Value::U64(1), &Permissions::anyone())
+        .unwrap();
+    let storage = OnDiskStorage::new(temp_path.path().to_path_buf());
+    assert_eq!(storage.get("epoch").unwrap(), Value::U64(5));
+    assert_eq!(storage.get("round").unwrap(), Value::U64(1));
+    assert_eq!(
+        storage.get_public_key("consensus").unwrap().public_key,
+        public_key
+    );
+}
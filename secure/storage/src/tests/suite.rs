@@ -2,44 +2,31 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    crypto_storage::CryptoStorage,
     error::Error,
     permissions::{Permission, Permissions},
     storage::Storage,
     value::Value,
 };
-use libra_crypto::{ed25519::Ed25519PrivateKey, Uniform};
-use rand::{rngs::StdRng, SeedableRng};
+use libra_crypto::{HashValue, Signature};
 
-const KEY_KEY: &str = "key";
+/// The Id the storages under test are accessed with.
+pub const SUITE_ID: &str = "safety_rules";
 const U64_KEY: &str = "u64";
+const CRYPTO_KEY: &str = "crypto";
 
 pub fn run_test_suite(mut storage: Box<dyn Storage>) {
     let no_perms = Permissions {
-        readers: Permission::Anyone,
-        writers: Permission::Anyone,
+        readers: Permission::Users(vec![SUITE_ID.into()]),
+        writers: Permission::Users(vec![SUITE_ID.into()]),
     };
     let u64_value_0 = 5;
     let u64_value_1 = 2322;
 
-    let mut rng = StdRng::from_seed([13u8; 32]);
-    let key_value_0 = Ed25519PrivateKey::generate_for_testing(&mut rng);
-    let key_value_1 = Ed25519PrivateKey::generate_for_testing(&mut rng);
-
-    assert_eq!(
-        storage.get(KEY_KEY).unwrap_err(),
-        Error::KeyNotSet(KEY_KEY.to_string())
-    );
     assert_eq!(
         storage.get(U64_KEY).unwrap_err(),
         Error::KeyNotSet(U64_KEY.to_string())
     );
-
-    assert_eq!(
-        storage
-            .set(KEY_KEY, Value::Ed25519PrivateKey(key_value_0.clone()))
-            .unwrap_err(),
-        Error::KeyNotSet(KEY_KEY.to_string())
-    );
     assert_eq!(
         storage.set(U64_KEY, Value::U64(u64_value_0)).unwrap_err(),
         Error::KeyNotSet(U64_KEY.to_string())
@@ -48,49 +35,78 @@ pub fn run_test_suite(mut storage: Box<dyn Storage>) {
     storage
         .create_if_not_exists(U64_KEY, Value::U64(u64_value_1), &no_perms)
         .unwrap();
-    storage
-        .create(
-            KEY_KEY,
-            Value::Ed25519PrivateKey(key_value_1.clone()),
-            &no_perms,
-        )
-        .unwrap();
-
     assert_eq!(storage.get(U64_KEY).unwrap().u64().unwrap(), u64_value_1);
     assert_eq!(
-        &storage.get(KEY_KEY).unwrap().ed25519_private_key().unwrap(),
-        &key_value_1
+        storage
+            .create(U64_KEY, Value::U64(u64_value_0), &no_perms)
+            .unwrap_err(),
+        Error::KeyAlreadyExists(U64_KEY.to_string())
     );
 
     storage.set(U64_KEY, Value::U64(u64_value_0)).unwrap();
+    assert_eq!(storage.get(U64_KEY).unwrap().u64().unwrap(), u64_value_0);
+
+    // Should not affect the above computation
     storage
-        .set(KEY_KEY, Value::Ed25519PrivateKey(key_value_0.clone()))
+        .create_if_not_exists(U64_KEY, Value::U64(u64_value_1), &no_perms)
         .unwrap();
+    assert_eq!(storage.get(U64_KEY).unwrap().u64().unwrap(), u64_value_0);
+
+    run_crypto_test_suite(storage.as_mut());
+}
 
-    assert_eq!(&storage.get(U64_KEY).unwrap().u64().unwrap(), &u64_value_0);
+fn run_crypto_test_suite(storage: &mut dyn Storage) {
+    let message = HashValue::random();
+    assert_eq!(
+        storage.get_public_key(CRYPTO_KEY).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
+    );
     assert_eq!(
-        &storage.get(KEY_KEY).unwrap().ed25519_private_key().unwrap(),
-        &key_value_0
+        storage.rotate_key(CRYPTO_KEY).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
+    );
+    assert_eq!(
+        storage.sign_message(CRYPTO_KEY, &message).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
     );
 
-    // Should not affect the above computation
-    storage
-        .create_if_not_exists(U64_KEY, Value::U64(u64_value_1), &no_perms)
-        .unwrap();
-    storage
-        .create_if_not_exists(KEY_KEY, Value::Ed25519PrivateKey(key_value_1), &no_perms)
-        .unwrap();
+    let public_key_0 = storage.generate_key(CRYPTO_KEY).unwrap();
+    assert_eq!(
+        storage.generate_key(CRYPTO_KEY).unwrap_err(),
+        Error::KeyAlreadyExists(CRYPTO_KEY.to_string())
+    );
+    let response = storage.get_public_key(CRYPTO_KEY).unwrap();
+    assert_eq!(response.public_key, public_key_0);
+    assert_eq!(response.version, 0);
+    let signature = storage.sign_message(CRYPTO_KEY, &message).unwrap();
+    signature.verify(&message, &public_key_0).unwrap();
 
+    // Signing keys are not exposed as values
     assert_eq!(
-        storage
-            .get(U64_KEY)
-            .unwrap()
-            .ed25519_private_key()
-            .unwrap_err(),
-        Error::UnexpectedValueType
+        storage.get(CRYPTO_KEY).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
     );
+
+    let public_key_1 = storage.rotate_key(CRYPTO_KEY).unwrap();
+    assert_ne!(public_key_0, public_key_1);
+    let response = storage.get_public_key(CRYPTO_KEY).unwrap();
+    assert_eq!(response.public_key, public_key_1);
+    assert_eq!(response.version, 1);
     assert_eq!(
-        storage.get(KEY_KEY).unwrap().u64().unwrap_err(),
-        Error::UnexpectedValueType
+        storage.get_public_key_for_version(CRYPTO_KEY, 0).unwrap(),
+        public_key_0
+    );
+
+    let signature = storage.sign_message(CRYPTO_KEY, &message).unwrap();
+    signature.verify(&message, &public_key_1).unwrap();
+    let signature = storage
+        .sign_message_using_version(CRYPTO_KEY, 0, &message)
+        .unwrap();
+    signature.verify(&message, &public_key_0).unwrap();
+    assert_eq!(
+        storage
+            .sign_message_using_version(CRYPTO_KEY, 2, &message)
+            .unwrap_err(),
+        Error::KeyVersionNotFound(format!("{}:{}", CRYPTO_KEY, 2))
     );
 }
@@ -0,0 +1,98 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    crypto_storage::CryptoStorage,
+    error::Error,
+    mock_vault,
+    permissions::{Permission, Permissions},
+    storage::Storage,
+    tests::suite,
+    value::Value,
+    vault::VaultStorage,
+};
+use libra_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use rand::{rngs::StdRng, SeedableRng};
+
+const SAFETY_RULES: &str = "safety_rules";
+const KEY_MANAGER: &str = "key_manager";
+
+#[test]
+fn vault() {
+    let host = mock_vault::start();
+    let storage = Box::new(VaultStorage::new(host, mock_vault::ROOT_TOKEN.into()));
+    suite::run_test_suite(storage);
+}
+
+#[test]
+fn vault_permissions() {
+    let host = mock_vault::start();
+    let mut root = VaultStorage::new(host.clone(), mock_vault::ROOT_TOKEN.into());
+    let permissions = Permissions {
+        readers: Permission::Users(vec![SAFETY_RULES.into()]),
+        writers: Permission::Users(vec![KEY_MANAGER.into()]),
+    };
+    root.create("epoch", Value::U64(5), &permissions).unwrap();
+    root.create(
+        "private",
+        Value::U64(7),
+        &Permissions {
+            readers: Permission::NoOne,
+            writers: Permission::NoOne,
+        },
+    )
+    .unwrap();
+
+    let token = root.create_token(vec![SAFETY_RULES]).unwrap();
+    let mut safety_rules = VaultStorage::new(host.clone(), token);
+    assert_eq!(safety_rules.get("epoch").unwrap(), Value::U64(5));
+    assert_eq!(
+        safety_rules.set("epoch", Value::U64(6)).unwrap_err(),
+        Error::PermissionDenied
+    );
+    assert_eq!(
+        safety_rules.get("private").unwrap_err(),
+        Error::PermissionDenied
+    );
+
+    let token = root.create_token(vec![KEY_MANAGER]).unwrap();
+    let mut key_manager = VaultStorage::new(host, token);
+    key_manager.set("epoch", Value::U64(6)).unwrap();
+    assert_eq!(safety_rules.get("epoch").unwrap(), Value::U64(6));
+    assert_eq!(
+        key_manager
+            .create("other", Value::U64(1), &permissions)
+            .unwrap_err(),
+        Error::PermissionDenied
+    );
+
+    // Granting to anyone would go through the default policy of every token.
+    assert!(root
+        .create("anyone", Value::U64(1), &Permissions::anyone())
+        .is_err());
+    assert_eq!(
+        root.get("anyone").unwrap_err(),
+        Error::KeyNotSet("anyone".into())
+    );
+}
+
+#[test]
+fn vault_import_key() {
+    let host = mock_vault::start();
+    let mut storage = VaultStorage::new(host, mock_vault::ROOT_TOKEN.into());
+    let private_key = Ed25519PrivateKey::generate_for_testing(&mut StdRng::from_seed([5u8; 32]));
+    assert!(match storage.import_key(SAFETY_RULES, private_key) {
+        Err(Error::KeyImportNotSupported(_)) => true,
+        _ => false,
+    });
+}
+
+/// Runs the test suite against a Vault server, e.g. one started with
+/// `vault server -dev -dev-root-token-id=root`. The address can be overridden with VAULT_ADDR.
+#[test]
+#[ignore]
+fn vault_dev_server() {
+    let host = std::env::var("VAULT_ADDR").unwrap_or_else(|_| "http://127.0.0.1:8200".into());
+    let storage = Box::new(VaultStorage::new(host, mock_vault::ROOT_TOKEN.into()));
+    suite::run_test_suite(storage);
+}
@@ -2,30 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
-use libra_crypto::ed25519::Ed25519PrivateKey;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+/// The values held by a storage. Private keys are not values: they are held by the CryptoStorage
+/// of the storage, which signs on behalf of its callers and never returns them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(content = "value", rename_all = "snake_case", tag = "type")]
 pub enum Value {
-    Ed25519PrivateKey(Ed25519PrivateKey),
     U64(u64),
 }
 
 impl Value {
     pub fn u64(self) -> Result<u64, Error> {
-        if let Value::U64(value) = self {
-            Ok(value)
-        } else {
-            Err(Error::UnexpectedValueType)
-        }
-    }
-
-    pub fn ed25519_private_key(self) -> Result<Ed25519PrivateKey, Error> {
-        if let Value::Ed25519PrivateKey(value) = self {
-            Ok(value)
-        } else {
-            Err(Error::UnexpectedValueType)
+        match self {
+            Value::U64(value) => Ok(value),
         }
     }
 }
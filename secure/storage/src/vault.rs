@@ -0,0 +1,338 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    crypto_storage::{CryptoStorage, PublicKeyResponse},
+    error::Error,
+    permissions::{Permission, Permissions},
+    storage::Storage,
+    value::Value,
+};
+use libra_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue,
+};
+use reqwest::{
+    blocking::{Client, Response},
+    StatusCode,
+};
+use serde_json::{json, Value as JsonValue};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+};
+
+/// The mount point of the KV secrets engine (version 2) holding the values.
+const KV_MOUNT: &str = "secret";
+/// The mount point of the transit secrets engine holding the signing keys.
+const TRANSIT_MOUNT: &str = "transit";
+
+/// VaultStorage stores values in the KV secrets engine (version 2) of a HashiCorp Vault server
+/// through its HTTP API, authenticating with a token. The permissions of a value are translated
+/// into ACL policies named after the Ids: readers may read the value, writers may also update it.
+/// `Anyone` is rejected rather than granted through the `default` policy, which every token
+/// carries. Tokens for an Id are created with `create_token`.
+///
+/// Signing keys live in the transit secrets engine, which signs on behalf of the caller so that
+/// the private keys never leave Vault.
+pub struct VaultStorage {
+    client: Client,
+    host: String,
+    token: String,
+}
+
+impl VaultStorage {
+    pub fn new(host: String, token: String) -> Self {
+        Self {
+            client: Client::new(),
+            host,
+            token,
+        }
+    }
+
+    /// Creates a token carrying the given policies, e.g. the Ids of a service.
+    pub fn create_token(&self, policies: Vec<&str>) -> Result<String, Error> {
+        let response = self.http_post("auth/token/create", &json!({ "policies": policies }))?;
+        let body = Self::json(response)?;
+        body["auth"]["client_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| Self::unexpected(&body))
+    }
+
+    /// Reads the description of a key of the transit engine, which lists its versions.
+    fn transit_key(&self, name: &str) -> Result<JsonValue, Error> {
+        let response = self.http_get(&format!("{}/keys/{}", TRANSIT_MOUNT, name))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::KeyNotSet(name.to_string()));
+        }
+        let mut body = Self::json(response)?;
+        Ok(body["data"].take())
+    }
+
+    /// Extracts the public key of a version, Vault numbering versions from 1 instead of 0.
+    fn transit_public_key(
+        name: &str,
+        key: &JsonValue,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        let public_key = key["keys"][(version + 1).to_string()]["public_key"]
+            .as_str()
+            .ok_or_else(|| Error::KeyVersionNotFound(format!("{}:{}", name, version)))?;
+        let public_key = Self::decode_base64(public_key)?;
+        Ed25519PublicKey::try_from(public_key.as_slice())
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    fn transit_sign(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        let path = format!("{}/sign/{}", TRANSIT_MOUNT, name);
+        let payload = json!({
+            "input": base64::encode(message.to_vec()),
+            "key_version": version + 1,
+        });
+        let response = self.http_post(&path, &payload)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(Error::KeyNotSet(name.to_string())),
+            StatusCode::BAD_REQUEST => {
+                return Err(Error::KeyVersionNotFound(format!("{}:{}", name, version)))
+            }
+            _ => (),
+        }
+        let body = Self::json(response)?;
+        // Signatures are formatted as vault:v<key version>:<base64 encoded signature>.
+        let signature = body["data"]["signature"]
+            .as_str()
+            .and_then(|signature| signature.rsplit(':').next())
+            .ok_or_else(|| Self::unexpected(&body))?;
+        let signature = Self::decode_base64(signature)?;
+        Ed25519Signature::try_from(signature.as_slice())
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Reads a value along with its version, which is needed to update it.
+    fn read(&self, key: &str) -> Result<(Value, u64), Error> {
+        let response = self.http_get(&format!("{}/data/{}", KV_MOUNT, key))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::KeyNotSet(key.to_string()));
+        }
+        let body = Self::json(response)?;
+        let version = body["data"]["metadata"]["version"]
+            .as_u64()
+            .ok_or_else(|| Self::unexpected(&body))?;
+        let value = serde_json::from_value(body["data"]["data"]["value"].clone())?;
+        Ok((value, version))
+    }
+
+    /// Writes a value with check-and-set: the write only succeeds if the current version of the
+    /// value is `version`, 0 meaning that the value must not exist yet.
+    fn write(&self, key: &str, value: &Value, version: u64) -> Result<(), Error> {
+        let payload = json!({ "options": { "cas": version }, "data": { "value": value } });
+        let response = self.http_post(&format!("{}/data/{}", KV_MOUNT, key), &payload)?;
+        if response.status() == StatusCode::BAD_REQUEST {
+            let body = Self::json(response)?;
+            if body["errors"].to_string().contains("check-and-set") {
+                return Err(if version == 0 {
+                    Error::KeyAlreadyExists(key.to_string())
+                } else {
+                    Error::InternalError(format!("Concurrent update of {}", key))
+                });
+            }
+            return Err(Self::unexpected(&body));
+        }
+        Self::check(response)
+    }
+
+    /// Translates a permission into the names of the policies it applies to.
+    fn policies(permission: &Permission) -> Result<Vec<&str>, Error> {
+        match permission {
+            Permission::Users(ids) => Ok(ids.iter().map(String::as_str).collect()),
+            Permission::Anyone => Err(Error::InternalError(
+                "Vault values can't be granted to anyone, the Ids must be listed".into(),
+            )),
+            Permission::NoOne => Ok(vec![]),
+        }
+    }
+
+    /// Adds the capabilities on the value of `key` to a policy, creating it if needed.
+    fn grant(&self, policy: &str, key: &str, capabilities: &BTreeSet<&str>) -> Result<(), Error> {
+        let rule = format!(
+            "path \"{}/data/{}\" {{\n  capabilities = {}\n}}\n",
+            KV_MOUNT,
+            key,
+            serde_json::to_string(capabilities)?
+        );
+        let path = format!("sys/policy/{}", policy);
+        let response = self.http_get(&path)?;
+        let mut rules = if response.status() == StatusCode::NOT_FOUND {
+            String::new()
+        } else {
+            let body = Self::json(response)?;
+            body["rules"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Self::unexpected(&body))?
+        };
+        if rules.contains(&rule) {
+            return Ok(());
+        }
+        rules.push_str(&rule);
+        Self::check(self.http_put(&path, &json!({ "policy": rules }))?)
+    }
+
+    fn http_get(&self, path: &str) -> Result<Response, Error> {
+        Ok(self
+            .client
+            .get(&self.url(path))
+            .header("X-Vault-Token", &self.token)
+            .send()?)
+    }
+
+    fn http_post(&self, path: &str, payload: &JsonValue) -> Result<Response, Error> {
+        Ok(self
+            .client
+            .post(&self.url(path))
+            .header("X-Vault-Token", &self.token)
+            .json(payload)
+            .send()?)
+    }
+
+    fn http_put(&self, path: &str, payload: &JsonValue) -> Result<Response, Error> {
+        Ok(self
+            .client
+            .put(&self.url(path))
+            .header("X-Vault-Token", &self.token)
+            .json(payload)
+            .send()?)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1/{}", self.host.trim_end_matches('/'), path)
+    }
+
+    fn check(response: Response) -> Result<(), Error> {
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::FORBIDDEN => Err(Error::PermissionDenied),
+            status => Err(Error::InternalError(format!(
+                "Unexpected Vault response: {}",
+                status
+            ))),
+        }
+    }
+
+    fn json(response: Response) -> Result<JsonValue, Error> {
+        match response.status() {
+            StatusCode::FORBIDDEN => Err(Error::PermissionDenied),
+            _ => Ok(response.json()?),
+        }
+    }
+
+    fn decode_base64(data: &str) -> Result<Vec<u8>, Error> {
+        base64::decode(data).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    fn unexpected(body: &JsonValue) -> Error {
+        Error::InternalError(format!("Unexpected Vault response: {}", body))
+    }
+}
+
+impl Storage for VaultStorage {
+    fn create(&mut self, key: &str, value: Value, permissions: &Permissions) -> Result<(), Error> {
+        let mut grants: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for policy in Self::policies(&permissions.readers)? {
+            grants.entry(policy).or_default().insert("read");
+        }
+        // Updates are done with check-and-set, which requires reading the current version.
+        for policy in Self::policies(&permissions.writers)? {
+            grants
+                .entry(policy)
+                .or_default()
+                .extend(&["read", "update"]);
+        }
+
+        self.write(key, &value, 0)?;
+        for (policy, capabilities) in grants {
+            self.grant(policy, key, &capabilities)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Value, Error> {
+        self.read(key).map(|(value, _version)| value)
+    }
+
+    fn set(&mut self, key: &str, value: Value) -> Result<(), Error> {
+        let (_, version) = self.read(key)?;
+        self.write(key, &value, version)
+    }
+}
+
+impl CryptoStorage for VaultStorage {
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        // Vault silently ignores the creation of a key that already exists.
+        match self.transit_key(name) {
+            Ok(_) => return Err(Error::KeyAlreadyExists(name.to_string())),
+            Err(Error::KeyNotSet(_)) => (),
+            Err(e) => return Err(e),
+        }
+        let path = format!("{}/keys/{}", TRANSIT_MOUNT, name);
+        Self::check(self.http_post(&path, &json!({ "type": "ed25519" }))?)?;
+        Ok(self.get_public_key(name)?.public_key)
+    }
+
+    fn import_key(&mut self, name: &str, _private_key: Ed25519PrivateKey) -> Result<(), Error> {
+        Err(Error::KeyImportNotSupported(format!(
+            "Unable to import {}, keys can only be generated by Vault",
+            name
+        )))
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        let key = self.transit_key(name)?;
+        let version = key["latest_version"]
+            .as_u64()
+            .and_then(|version| version.checked_sub(1))
+            .ok_or_else(|| Self::unexpected(&key))?;
+        Ok(PublicKeyResponse {
+            public_key: Self::transit_public_key(name, &key, version)?,
+            version,
+        })
+    }
+
+    fn get_public_key_for_version(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Ed25519PublicKey, Error> {
+        Self::transit_public_key(name, &self.transit_key(name)?, version)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let path = format!("{}/keys/{}/rotate", TRANSIT_MOUNT, name);
+        let response = self.http_post(&path, &JsonValue::Null)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::KeyNotSet(name.to_string()));
+        }
+        Self::check(response)?;
+        Ok(self.get_public_key(name)?.public_key)
+    }
+
+    fn sign_message(&self, name: &str, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        let version = self.get_public_key(name)?.version;
+        self.transit_sign(name, version, message)
+    }
+
+    fn sign_message_using_version(
+        &self,
+        name: &str,
+        version: u64,
+        message: &HashValue,
+    ) -> Result<Ed25519Signature, Error> {
+        self.transit_sign(name, version, message)
+    }
+}
@@ -31,16 +31,26 @@ pub struct GetChunkRequest {
     pub current_epoch: u64,
     /// Max size of a chunk response.
     pub limit: u64,
+    /// Whether the response should carry the write sets of the transactions, so that the
+    /// requester can apply them without re-execution.
+    pub fetch_outputs: bool,
     /// The target of the given request.
     target: TargetType,
 }
 
 impl GetChunkRequest {
-    pub fn new(known_version: Version, current_epoch: u64, limit: u64, target: TargetType) -> Self {
+    pub fn new(
+        known_version: Version,
+        current_epoch: u64,
+        limit: u64,
+        fetch_outputs: bool,
+        target: TargetType,
+    ) -> Self {
         Self {
             known_version,
             current_epoch,
             limit,
+            fetch_outputs,
             target,
         }
     }
@@ -54,10 +64,11 @@ impl fmt::Display for GetChunkRequest {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "[ChunkRequest: known version: {}, epoch: {}, limit: {}, target: {:?}]",
+            "[ChunkRequest: known version: {}, epoch: {}, limit: {}, outputs: {}, target: {:?}]",
             self.known_version,
             self.current_epoch,
             self.limit,
+            self.fetch_outputs,
             self.target(),
         )
     }
@@ -3,7 +3,8 @@
 
 use anyhow::{Error, Result};
 use libra_types::crypto_proxies::LedgerInfoWithSignatures;
-use libra_types::transaction::{TransactionListWithProof, Version};
+use libra_types::transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version};
+use libra_types::write_set::WriteSet;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
@@ -46,6 +47,9 @@ pub struct GetChunkResponse {
     pub response_li: ResponseLedgerInfo,
     /// chunk of transactions with proof corresponding to the ledger info carried by the response.
     pub txn_list_with_proof: TransactionListWithProof,
+    /// The write sets of the transactions in the chunk, if the outputs have been requested and
+    /// are available. The events are carried by `txn_list_with_proof` in that case.
+    pub write_sets: Option<Vec<WriteSet>>,
 }
 
 impl GetChunkResponse {
@@ -56,6 +60,18 @@ impl GetChunkResponse {
         Self {
             response_li,
             txn_list_with_proof,
+            write_sets: None,
+        }
+    }
+
+    pub fn new_with_outputs(
+        response_li: ResponseLedgerInfo,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+    ) -> Self {
+        Self {
+            response_li,
+            txn_list_with_proof: txn_output_list_with_proof.txn_list_with_proof,
+            write_sets: Some(txn_output_list_with_proof.write_sets),
         }
     }
 }
@@ -87,8 +103,10 @@ impl fmt::Display for GetChunkResponse {
         };
         write!(
             f,
-            "[ChunkResponse: response li: {}, txns: {}]",
-            response_li_repr, txns_repr,
+            "[ChunkResponse: response li: {}, txns: {}, outputs: {}]",
+            response_li_repr,
+            txns_repr,
+            self.write_sets.is_some(),
         )
     }
 }
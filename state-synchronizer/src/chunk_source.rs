@@ -0,0 +1,74 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chunk_response::{GetChunkResponse, ResponseLedgerInfo};
+use anyhow::{ensure, Result};
+use backup_restore::{
+    adapter::Adapter, read_transaction_backup_manifest, read_transaction_chunk, FileHandle,
+    TransactionBackupManifest,
+};
+use libra_types::transaction::Version;
+use std::marker::PhantomData;
+
+/// A source of chunks other than the upstream peers, e.g., a backup of the ledger. The coordinator
+/// applies a chunk from it on every tick for as long as it has the chunk following the local
+/// version, and switches to the network for the rest. The chunks go through the same verification
+/// as the ones received from the peers: until the node is initialized, the LI of a chunk must
+/// match the waypoint or end an epoch preceding it.
+#[async_trait::async_trait]
+pub trait ChunkSource: Send {
+    /// Returns the chunk that contains the transaction following `known_version`, or `None` if
+    /// the source doesn't have it.
+    async fn get_chunk(&mut self, known_version: Version) -> Result<Option<GetChunkResponse>>;
+}
+
+/// Reads the chunks of a transaction backup from the storage backend of adapter `A`.
+pub struct BackupChunkSource<A> {
+    manifest_handle: FileHandle,
+    // read upon the first chunk request
+    manifest: Option<TransactionBackupManifest>,
+    adapter: PhantomData<fn() -> A>,
+}
+
+impl<A: Adapter> BackupChunkSource<A> {
+    pub fn new(manifest_handle: FileHandle) -> Self {
+        Self {
+            manifest_handle,
+            manifest: None,
+            adapter: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: Adapter> ChunkSource for BackupChunkSource<A> {
+    async fn get_chunk(&mut self, known_version: Version) -> Result<Option<GetChunkResponse>> {
+        if self.manifest.is_none() {
+            self.manifest =
+                Some(read_transaction_backup_manifest::<A>(&self.manifest_handle).await?);
+        }
+        let chunk_info = match self
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.find_chunk(known_version + 1))
+        {
+            Some(chunk_info) => chunk_info.clone(),
+            None => return Ok(None),
+        };
+
+        let chunk = read_transaction_chunk::<A>(&chunk_info.file_handle).await?;
+        ensure!(
+            chunk.txn_list_with_proof.first_transaction_version == Some(chunk_info.first_version)
+                && chunk.txn_list_with_proof.len() as u64
+                    == chunk_info.last_version - chunk_info.first_version + 1,
+            "[state sync] backup chunk {} doesn't match the manifest: expected versions {}..={}",
+            chunk_info.file_handle,
+            chunk_info.first_version,
+            chunk_info.last_version,
+        );
+        Ok(Some(GetChunkResponse::new(
+            ResponseLedgerInfo::VerifiableLedgerInfo(chunk.ledger_info),
+            chunk.txn_list_with_proof,
+        )))
+    }
+}
@@ -4,9 +4,11 @@
 use crate::chunk_request::{GetChunkRequest, TargetType};
 use crate::chunk_response::{GetChunkResponse, ResponseLedgerInfo};
 use crate::{
+    chunk_source::ChunkSource,
     counters,
     executor_proxy::ExecutorProxyTrait,
     peer_manager::{PeerManager, PeerScoreUpdateType},
+    sync_status::{ProgressTracker, SyncStatus},
     PeerId, SynchronizerState,
 };
 use anyhow::{bail, ensure, format_err, Result};
@@ -21,17 +23,19 @@ use libra_logger::prelude::*;
 use libra_types::crypto_proxies::ValidatorChangeProof;
 use libra_types::transaction::Version;
 use libra_types::{
-    crypto_proxies::LedgerInfoWithSignatures, transaction::TransactionListWithProof,
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
     waypoint::Waypoint,
+    write_set::WriteSet,
 };
 use network::{
     proto::{StateSynchronizerMsg, StateSynchronizerMsg_oneof},
     validator_network::{Event, StateSynchronizerEvents, StateSynchronizerSender},
 };
 use std::{
-    collections::HashMap,
+    collections::{btree_map::Entry, BTreeMap, HashMap},
     convert::TryInto,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::time::interval;
 
@@ -55,6 +59,8 @@ pub(crate) enum CoordinatorMessage {
     // used to notify about new txn commit
     Commit,
     GetState(oneshot::Sender<SynchronizerState>),
+    // used to report the sync progress
+    GetSyncStatus(oneshot::Sender<SyncStatus>),
     // used to generate epoch proof
     GetEpochProof(EpochRetrievalRequest),
     // Receive a notification via a given channel when coordinator is initialized.
@@ -67,6 +73,7 @@ struct PendingRequestInfo {
     known_version: u64,
     request_epoch: u64,
     limit: u64,
+    fetch_outputs: bool,
 }
 
 /// Coordination of synchronization process is driven by SyncCoordinator, which `start()` function
@@ -90,6 +97,8 @@ pub(crate) struct SyncCoordinator<T> {
     config: StateSyncConfig,
     // role of node
     role: RoleType,
+    // full nodes only: whether a consensus observer commits the blocks it observes to the ledger
+    observer_mode: bool,
     // An initial waypoint: for as long as the local version is less than a version determined by
     // waypoint a node is not going to be abl
     waypoint: Option<Waypoint>,
@@ -103,6 +112,20 @@ pub(crate) struct SyncCoordinator<T> {
     // queue of incoming long polling requests
     // peer will be notified about new chunk of transactions if it's available before expiry time
     subscriptions: HashMap<PeerId, PendingRequestInfo>,
+    // chunks received ahead of the local version, keyed by their first version: they're applied
+    // once all the preceding chunks are applied
+    pending_chunks: BTreeMap<Version, (PeerId, GetChunkResponse)>,
+    // highest version (and its epoch) known to be available from the upstream peers, used to
+    // request chunks beyond the next one in parallel
+    sync_horizon: Option<(Version, u64)>,
+    // rate of the sync progress and time without progress while behind the sync target
+    progress: ProgressTracker,
+    // whether the stall alarm is raised
+    stalled: bool,
+    // most recent error that prevented the sync from making progress
+    last_error: Option<String>,
+    // source the chunks are read from (one per tick) before syncing from the network, if any
+    chunk_source: Option<Box<dyn ChunkSource>>,
     executor_proxy: T,
 }
 
@@ -110,10 +133,12 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
     pub fn new(
         client_events: mpsc::UnboundedReceiver<CoordinatorMessage>,
         role: RoleType,
+        observer_mode: bool,
         waypoint: Option<Waypoint>,
         config: StateSyncConfig,
         executor_proxy: T,
         initial_state: SynchronizerState,
+        chunk_source: Option<Box<dyn ChunkSource>>,
     ) -> Self {
         let upstream_peers = config.upstream_peers.upstream_peers.clone();
         let retry_timeout_val = match role {
@@ -121,15 +146,26 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             RoleType::Validator => 2 * config.tick_interval_ms,
         };
 
+        let progress = ProgressTracker::new(
+            initial_state.highest_version_in_local_storage(),
+            Instant::now(),
+        );
         Self {
             client_events,
             local_state: initial_state,
             retry_timeout: Duration::from_millis(retry_timeout_val),
             config,
             role,
+            observer_mode,
             waypoint,
             peer_manager: PeerManager::new(upstream_peers),
             subscriptions: HashMap::new(),
+            pending_chunks: BTreeMap::new(),
+            sync_horizon: None,
+            progress,
+            stalled: false,
+            last_error: None,
+            chunk_source,
             sync_request: None,
             initialization_listener: None,
             executor_proxy,
@@ -155,17 +191,20 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     match msg {
                         CoordinatorMessage::Request(request) => {
                             if let Err(e) = self.request_sync(request).await {
-                                error!("[state sync] request sync fail: {}", e);
+                                self.report_error(format!("request sync fail: {}", e));
                             }
                         }
                         CoordinatorMessage::Commit => {
                             if let Err(e) = self.process_commit().await {
-                                error!("[state sync] process commit fail: {}", e);
+                                self.report_error(format!("process commit fail: {}", e));
                             }
                         }
                         CoordinatorMessage::GetState(callback) => {
                             self.get_state(callback);
                         }
+                        CoordinatorMessage::GetSyncStatus(callback) => {
+                            self.get_sync_status(callback);
+                        }
                         CoordinatorMessage::GetEpochProof(request) => {
                             self.get_epoch_proof(request).await;
                         }
@@ -204,16 +243,10 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                                         StateSynchronizerMsg_oneof::ChunkResponse(response_msg) => {
                                             match response_msg.try_into() {
                                                 Ok(response) => {
-                                                    if let Err(err) = self.process_chunk_response(&peer_id, response).await {
-                                                        error!("[state sync] failed to process chunk response from {}: {}", peer_id, err);
-                                                        counters::APPLY_CHUNK_FAILURE.with_label_values(&[&*peer_id.to_string()]).inc();
-                                                    } else {
-                                                        self.peer_manager.update_score(&peer_id, PeerScoreUpdateType::Success);
-                                                        counters::APPLY_CHUNK_SUCCESS.with_label_values(&[&*peer_id.to_string()]).inc();
-                                                    }
+                                                    self.process_chunk_response(&peer_id, response).await;
                                                 }
                                                 Err(e) => {
-                                                    error!("[state sync] failed to parse response_msg: {}", e);
+                                                    self.report_error(format!("failed to parse response_msg: {}", e));
                                                     counters::APPLY_CHUNK_FAILURE.with_label_values(&[&*peer_id.to_string()]).inc();
                                                 }
                                             }
@@ -227,7 +260,11 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     }
                 },
                 _ = interval.select_next_some() => {
-                    self.check_progress().await;
+                    if self.chunk_source.is_some() {
+                        self.sync_from_chunk_source().await;
+                    } else {
+                        self.check_progress().await;
+                    }
                 }
             }
         }
@@ -304,12 +341,14 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
 
         self.peer_manager
             .set_peers(request.target.signatures().keys().copied().collect());
+        if request.target.ledger_info().epoch() == self.local_state.epoch() {
+            self.update_sync_horizon(&request.target);
+        }
         self.sync_request = Some(request);
-        self.send_chunk_request(
-            self.local_state.highest_version_in_local_storage(),
-            self.local_state.epoch(),
-        )
-        .await
+        let known_version = self.local_state.highest_version_in_local_storage();
+        let known_epoch = self.local_state.epoch();
+        self.send_chunk_request(known_version, known_epoch).await?;
+        self.send_chunk_requests(known_version, known_epoch).await
     }
 
     /// The function is called after new txns have been applied to the local storage.
@@ -322,6 +361,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         self.sync_state_with_local_storage().await?;
         let local_version = self.local_state.highest_version_in_local_storage();
         counters::COMMITTED_VERSION.set(local_version as i64);
+        self.progress.record(local_version, Instant::now());
 
         self.check_subscriptions().await;
         self.peer_manager.remove_requests(local_version);
@@ -370,6 +410,48 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         }
     }
 
+    fn get_sync_status(&self, callback: oneshot::Sender<SyncStatus>) {
+        let now = Instant::now();
+        let synced_version = self.local_state.highest_version_in_local_storage();
+        let target = self.sync_target();
+        let status = SyncStatus {
+            target,
+            committed_version: self.local_state.highest_local_li.ledger_info().version(),
+            synced_version,
+            epoch: self.local_state.epoch(),
+            txns_per_sec: self.progress.txns_per_sec(now),
+            eta: target.and_then(|(target_version, _)| {
+                self.progress
+                    .eta(target_version.saturating_sub(synced_version), now)
+            }),
+            stalled_for: self.progress.stalled_for(now),
+            stalled: self.stalled,
+            syncing_from: self.peer_manager.get_last_request_peer(synced_version + 1),
+            peers: self.peer_manager.peer_stats(),
+            last_error: self.last_error.clone(),
+        };
+        if callback.send(status).is_err() {
+            error!("[state sync] failed to send sync status");
+        }
+    }
+
+    /// The version and epoch the node is syncing to: the target of the pending sync request if
+    /// any, otherwise the highest version known to be available upstream.
+    fn sync_target(&self) -> Option<(Version, u64)> {
+        match self.sync_request.as_ref() {
+            Some(sync_req) => Some((
+                sync_req.target.ledger_info().version(),
+                sync_req.target.ledger_info().epoch(),
+            )),
+            None => self.sync_horizon,
+        }
+    }
+
+    fn report_error(&mut self, err: String) {
+        error!("[state sync] {}", err);
+        self.last_error = Some(err);
+    }
+
     /// There are two types of ChunkRequests:
     /// 1) Validator chunk requests are for a specific target LI and don't ask for long polling.
     /// 2) FullNode chunk requests don't specify a target LI and can allow long polling.
@@ -430,6 +512,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             request.known_version,
             ResponseLedgerInfo::VerifiableLedgerInfo(response_li),
             limit,
+            request.fetch_outputs,
             sender,
         )
         .await
@@ -463,6 +546,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     known_version: request.known_version,
                     request_epoch: request.current_epoch,
                     limit,
+                    fetch_outputs: request.fetch_outputs,
                 };
                 self.subscriptions.insert(peer_id, request_info);
             }
@@ -474,6 +558,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             request.known_version,
             ResponseLedgerInfo::VerifiableLedgerInfo(response_li),
             limit,
+            request.fetch_outputs,
             sender,
         )
         .await
@@ -537,6 +622,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 end_of_epoch_li,
             },
             limit,
+            request.fetch_outputs,
             sender,
         )
         .await
@@ -546,19 +632,42 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
     /// The chunk response contains transactions from the local storage with the proofs relative to
     /// the given target ledger info.
     /// In case target is None, the ledger info is set to the local highest ledger info.
+    /// If the outputs are requested but not available (e.g., the transactions were committed
+    /// before the write sets were persisted), the response carries the transactions only.
     async fn deliver_chunk(
         &self,
         peer_id: PeerId,
         known_version: u64,
         response_li: ResponseLedgerInfo,
         limit: u64,
+        fetch_outputs: bool,
         mut network_sender: StateSynchronizerSender,
     ) -> Result<()> {
-        let txns = self
-            .executor_proxy
-            .get_chunk(known_version, limit, response_li.version())
-            .await?;
-        let chunk_response = GetChunkResponse::new(response_li, txns);
+        let target_version = response_li.version();
+        let outputs = if fetch_outputs {
+            self.executor_proxy
+                .get_chunk_with_outputs(known_version, limit, target_version)
+                .await
+                .map_err(|e| {
+                    debug!(
+                        "[state sync] outputs after version {} not available: {}",
+                        known_version, e
+                    )
+                })
+                .ok()
+        } else {
+            None
+        };
+        let chunk_response = match outputs {
+            Some(txn_outputs) => GetChunkResponse::new_with_outputs(response_li, txn_outputs),
+            None => {
+                let txns = self
+                    .executor_proxy
+                    .get_chunk(known_version, limit, target_version)
+                    .await?;
+                GetChunkResponse::new(response_li, txns)
+            }
+        };
         let msg = StateSynchronizerMsg {
             message: Some(StateSynchronizerMsg_oneof::ChunkResponse(
                 chunk_response.try_into()?,
@@ -601,31 +710,77 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         Ok(target_li)
     }
 
-    /// * Issue a request for the next chunk.
-    /// * Validate and execute the transactions.
-    /// * Notify the clients in case a sync request has been completed.
-    async fn process_chunk_response(
-        &mut self,
-        peer_id: &PeerId,
-        response: GetChunkResponse,
-    ) -> Result<()> {
+    /// * Buffer the chunk until all the preceding chunks are applied.
+    /// * Validate and execute the buffered chunks that can be applied.
+    /// * Issue requests for the next chunks.
+    async fn process_chunk_response(&mut self, peer_id: &PeerId, response: GetChunkResponse) {
         counters::RESPONSES_RECEIVED
             .with_label_values(&[&*peer_id.to_string()])
             .inc();
         debug!("[state sync] Processing chunk response {}", response);
-        let txn_list_with_proof = response.txn_list_with_proof.clone();
+        if let Err(err) = self.buffer_chunk(peer_id, response) {
+            self.report_error(format!(
+                "failed to process chunk response from {}: {}",
+                peer_id, err
+            ));
+            counters::APPLY_CHUNK_FAILURE
+                .with_label_values(&[&*peer_id.to_string()])
+                .inc();
+            return;
+        }
 
+        let mut applied = false;
+        while let Some((peer_id, response)) = self.next_buffered_chunk() {
+            if let Err(err) = self.apply_chunk(&peer_id, response).await {
+                self.report_error(format!(
+                    "failed to process chunk response from {}: {}",
+                    peer_id, err
+                ));
+                counters::APPLY_CHUNK_FAILURE
+                    .with_label_values(&[&*peer_id.to_string()])
+                    .inc();
+                break;
+            }
+            self.peer_manager
+                .update_score(&peer_id, PeerScoreUpdateType::Success);
+            counters::APPLY_CHUNK_SUCCESS
+                .with_label_values(&[&*peer_id.to_string()])
+                .inc();
+            applied = true;
+        }
+
+        if applied {
+            if let Err(e) = self
+                .send_chunk_requests(
+                    self.local_state.highest_version_in_local_storage(),
+                    self.local_state.epoch(),
+                )
+                .await
+            {
+                self.report_error(format!("Failed to send chunk request: {}", e));
+            }
+        }
+    }
+
+    /// Chunks requested in parallel might arrive out of order: a chunk is accepted if it has been
+    /// requested from the peer that sent it, and kept only if no chunk starting with the same
+    /// version is buffered already.
+    fn buffer_chunk(&mut self, peer_id: &PeerId, response: GetChunkResponse) -> Result<()> {
         let known_version = self.local_state.highest_version_in_local_storage();
-        let chunk_start_version =
-            txn_list_with_proof
-                .first_transaction_version
-                .ok_or_else(|| {
-                    self.peer_manager
-                        .update_score(&peer_id, PeerScoreUpdateType::EmptyChunk);
-                    format_err!("[state sync] Empty chunk from {}", peer_id.short_str())
-                })?;
+        let chunk_start_version = response
+            .txn_list_with_proof
+            .first_transaction_version
+            .ok_or_else(|| {
+                self.peer_manager
+                    .update_score(&peer_id, PeerScoreUpdateType::EmptyChunk);
+                format_err!("[state sync] Empty chunk from {}", peer_id.short_str())
+            })?;
 
-        if chunk_start_version != known_version + 1 {
+        if chunk_start_version <= known_version
+            || !self
+                .peer_manager
+                .is_requested_from(chunk_start_version, peer_id)
+        {
             // Old / wrong chunk.
             self.peer_manager
                 .update_score(&peer_id, PeerScoreUpdateType::ChunkVersionCannotBeApplied);
@@ -637,26 +792,36 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             );
         }
 
-        let chunk_size = txn_list_with_proof.len() as u64;
-        let new_version = known_version + chunk_size;
-        match response.response_li {
-            ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_verifiable_li(txn_list_with_proof, li)
-                    .await
-            }
-            ResponseLedgerInfo::LedgerInfoForWaypoint {
-                waypoint_li,
-                end_of_epoch_li,
-            } => {
-                self.process_response_with_waypoint_li(
-                    txn_list_with_proof,
-                    waypoint_li,
-                    end_of_epoch_li,
-                )
-                .await
+        self.peer_manager
+            .process_response(chunk_start_version, peer_id);
+        match self.pending_chunks.entry(chunk_start_version) {
+            Entry::Occupied(_) => debug!(
+                "[state sync] Chunk from {} starting at version {} is buffered already",
+                peer_id.short_str(),
+                chunk_start_version
+            ),
+            Entry::Vacant(entry) => {
+                entry.insert((*peer_id, response));
             }
         }
-        .map_err(|e| {
+        Ok(())
+    }
+
+    /// Returns the buffered chunk directly following the local version, if any.
+    fn next_buffered_chunk(&mut self) -> Option<(PeerId, GetChunkResponse)> {
+        let known_version = self.local_state.highest_version_in_local_storage();
+        // chunks starting at or below the local version can't be applied anymore
+        self.pending_chunks = self.pending_chunks.split_off(&(known_version + 1));
+        self.pending_chunks.remove(&(known_version + 1))
+    }
+
+    /// * Validate and execute the transactions of a chunk following the local version.
+    /// * Notify the clients in case a sync request has been completed.
+    async fn apply_chunk(&mut self, peer_id: &PeerId, response: GetChunkResponse) -> Result<()> {
+        let known_version = self.local_state.highest_version_in_local_storage();
+        let chunk_size = response.txn_list_with_proof.len() as u64;
+        let new_version = known_version + chunk_size;
+        self.store_chunk(response).await.map_err(|e| {
             self.peer_manager
                 .update_score(peer_id, PeerScoreUpdateType::InvalidChunk);
             format_err!("[state sync] failed to apply chunk: {}", e)
@@ -679,11 +844,122 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         self.process_commit().await
     }
 
+    /// Applies the chunk following the local version from the chunk source, one chunk per tick.
+    /// The source is dropped once it's exhausted or fails, and the rest is synced from the
+    /// network.
+    async fn sync_from_chunk_source(&mut self) {
+        self.check_stall();
+        if self.syncs_on_demand() && self.sync_request.is_none() && self.is_initialized() {
+            return;
+        }
+        let mut chunk_source = match self.chunk_source.take() {
+            Some(chunk_source) => chunk_source,
+            None => return,
+        };
+        let known_version = self.local_state.highest_version_in_local_storage();
+        let response = match chunk_source.get_chunk(known_version).await {
+            Ok(Some(response)) => response,
+            Ok(None) => {
+                info!(
+                    "[state sync] chunk source exhausted at version {}, switching to network",
+                    known_version
+                );
+                return;
+            }
+            Err(e) => {
+                self.report_error(format!("failed to read chunk from chunk source: {}", e));
+                return;
+            }
+        };
+        let chunk_size = response.txn_list_with_proof.len() as u64;
+        if let Err(e) = self.store_source_chunk(response).await {
+            self.report_error(format!("failed to apply chunk from chunk source: {}", e));
+            return;
+        }
+        counters::STATE_SYNC_TXN_REPLAYED.inc_by(chunk_size as i64);
+        if let Err(e) = self.process_commit().await {
+            self.report_error(format!("process commit fail: {}", e));
+            return;
+        }
+        if self.local_state.highest_version_in_local_storage() <= known_version {
+            self.report_error(format!(
+                "no progress made with chunk source at version {}",
+                known_version
+            ));
+            return;
+        }
+        self.chunk_source = Some(chunk_source);
+    }
+
+    /// Stores a chunk read from the chunk source. Until initialized, the chunks up to the waypoint
+    /// are verified the way the responses with a waypoint LI are: the LI of a chunk must either
+    /// match the waypoint or end an epoch preceding it.
+    async fn store_source_chunk(&mut self, response: GetChunkResponse) -> Result<()> {
+        if self.is_initialized() {
+            return self.store_chunk(response).await;
+        }
+        let li = match &response.response_li {
+            ResponseLedgerInfo::VerifiableLedgerInfo(li) => li.clone(),
+            ResponseLedgerInfo::LedgerInfoForWaypoint { .. } => {
+                return self.store_chunk(response).await;
+            }
+        };
+        let waypoint_version = self
+            .waypoint
+            .as_ref()
+            .ok_or_else(|| format_err!("No waypoint found to verify a chunk from chunk source"))?
+            .version();
+        if li.ledger_info().version() >= waypoint_version {
+            return self
+                .process_response_with_waypoint_li(
+                    response.txn_list_with_proof,
+                    response.write_sets,
+                    li,
+                    None,
+                )
+                .await;
+        }
+        // The end of epoch LI of a waypoint response is verified by the local validator set.
+        ensure!(
+            li.ledger_info().next_validator_set().is_some(),
+            "Chunk LI at version {} below the waypoint doesn't end an epoch",
+            li.ledger_info().version()
+        );
+        li.verify(self.local_state.verifier())?;
+        self.validate_and_store_chunk(response.txn_list_with_proof, response.write_sets, li, None)
+            .await
+    }
+
+    /// Verifies the LI of the chunk and stores its transactions.
+    async fn store_chunk(&mut self, response: GetChunkResponse) -> Result<()> {
+        let txn_list_with_proof = response.txn_list_with_proof;
+        let write_sets = response.write_sets;
+        match response.response_li {
+            ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
+                self.process_response_with_verifiable_li(txn_list_with_proof, write_sets, li)
+                    .await
+            }
+            ResponseLedgerInfo::LedgerInfoForWaypoint {
+                waypoint_li,
+                end_of_epoch_li,
+            } => {
+                self.process_response_with_waypoint_li(
+                    txn_list_with_proof,
+                    write_sets,
+                    waypoint_li,
+                    end_of_epoch_li,
+                )
+                .await
+            }
+        }
+    }
+
     /// Processing chunk responses that carry a LedgerInfo that should be verified using the
     /// current local trusted validator set.
     async fn process_response_with_verifiable_li(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        write_sets: Option<Vec<WriteSet>>,
         response_li: LedgerInfoWithSignatures,
     ) -> Result<()> {
         ensure!(
@@ -700,26 +976,18 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 );
             }
         }
-        // Optimistically fetch the next chunk assuming the current chunk is going to be applied
-        // successfully.
-        let new_version =
-            self.local_state.highest_version_in_local_storage() + txn_list_with_proof.len() as u64;
-        let new_epoch = match response_li.ledger_info().next_validator_set() {
-            // This LI carries the validator set for the next epoch.
-            Some(_) => response_li.ledger_info().epoch() + 1,
-            None => response_li.ledger_info().epoch(),
-        };
-        self.send_chunk_request(new_version, new_epoch).await?;
-
         response_li.verify(self.local_state.verifier())?;
-        self.validate_and_store_chunk(txn_list_with_proof, response_li, None)
-            .await
+        self.validate_and_store_chunk(txn_list_with_proof, write_sets, response_li.clone(), None)
+            .await?;
+        self.update_sync_horizon(&response_li);
+        Ok(())
     }
 
     /// Processing chunk responses that carry a LedgerInfo corresponding to the waypoint.
     async fn process_response_with_waypoint_li(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        write_sets: Option<Vec<WriteSet>>,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<()> {
@@ -727,31 +995,55 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             !self.is_initialized(),
             "Response with a waypoint LI but we're already initialized"
         );
-        // Optimistically fetch the next chunk. The next chunk belongs to the next epoch if
-        // end_of_epoch_li is present.
-        let new_version =
-            self.local_state.highest_version_in_local_storage() + txn_list_with_proof.len() as u64;
-        let new_epoch = end_of_epoch_li
-            .as_ref()
-            .map_or(self.local_state.epoch(), |li| li.ledger_info().epoch() + 1);
-        if new_version < self.waypoint.as_ref().map_or(0, |w| w.version()) {
-            self.send_chunk_request(new_version, new_epoch).await?;
-        }
-
         self.waypoint
             .as_ref()
             .ok_or_else(|| {
                 format_err!("No waypoint found to process a response with a waypoint LI")
             })
             .and_then(|w| w.verify(waypoint_li.ledger_info()))?;
-        self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li)
-            .await
+        // The transactions up to the end of epoch LI (if present) belong to the local epoch.
+        let horizon_li = end_of_epoch_li
+            .clone()
+            .unwrap_or_else(|| waypoint_li.clone());
+        self.validate_and_store_chunk(
+            txn_list_with_proof,
+            write_sets,
+            waypoint_li,
+            end_of_epoch_li,
+        )
+        .await?;
+        self.update_sync_horizon(&horizon_li);
+        Ok(())
+    }
+
+    fn update_sync_horizon(&mut self, li: &LedgerInfoWithSignatures) {
+        let version = li.ledger_info().version();
+        if self
+            .sync_horizon
+            .map_or(true, |(horizon_version, _)| horizon_version < version)
+        {
+            self.sync_horizon = Some((version, li.ledger_info().epoch()));
+        }
+    }
+
+    /// Whether the ledger is written by consensus (or by the consensus observer of a full node)
+    /// once initialized, in which case the chunks are only requested to serve the sync requests:
+    /// there must be a single writer at any time.
+    fn syncs_on_demand(&self) -> bool {
+        self.role == RoleType::Validator || self.observer_mode
+    }
+
+    /// Whether the chunks are requested with their outputs to be applied without re-execution.
+    fn applies_outputs(&self) -> bool {
+        self.role == RoleType::FullNode && self.config.apply_transaction_outputs
     }
 
     // Assumes that the target LI has been already verified by the caller.
+    // The transactions are executed unless their write sets are present and we trust them.
     async fn validate_and_store_chunk(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        write_sets: Option<Vec<WriteSet>>,
         target: LedgerInfoWithSignatures,
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<()> {
@@ -768,25 +1060,80 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             return Ok(());
         }
 
-        self.executor_proxy
-            .execute_chunk(
-                txn_list_with_proof,
-                target,
-                intermediate_end_of_epoch_li,
-                &mut self.local_state.synced_trees,
-            )
-            .await?;
-        Ok(())
+        match write_sets {
+            Some(write_sets) if self.applies_outputs() => {
+                self.executor_proxy
+                    .apply_chunk(
+                        TransactionOutputListWithProof::new(txn_list_with_proof, write_sets),
+                        target,
+                        intermediate_end_of_epoch_li,
+                        &mut self.local_state.synced_trees,
+                    )
+                    .await
+            }
+            _ => {
+                self.executor_proxy
+                    .execute_chunk(
+                        txn_list_with_proof,
+                        target,
+                        intermediate_end_of_epoch_li,
+                        &mut self.local_state.synced_trees,
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Updates the progress gauges and raises the stall alarm in case no progress has been made
+    /// for `stall_alarm_timeout_ms` while behind the sync target.
+    fn check_stall(&mut self) {
+        let now = Instant::now();
+        let synced_version = self.local_state.highest_version_in_local_storage();
+        let remaining = self.sync_target().map_or(0, |(target_version, _)| {
+            target_version.saturating_sub(synced_version)
+        });
+        self.progress.set_behind(remaining > 0, now);
+        if let Some((target_version, _)) = self.sync_target() {
+            counters::TARGET_VERSION.set(target_version as i64);
+        }
+        counters::SYNC_RATE.set(self.progress.txns_per_sec(now).round() as i64);
+        counters::SYNC_ETA.set(
+            self.progress
+                .eta(remaining, now)
+                .map_or(-1, |eta| eta.as_secs() as i64),
+        );
+
+        let stall_timeout = Duration::from_millis(self.config.stall_alarm_timeout_ms);
+        let stalled_for = self.progress.stalled_for(now);
+        let stalled = stalled_for.map_or(false, |duration| duration >= stall_timeout);
+        if stalled && !self.stalled {
+            error!(
+                "[state sync] Stalled at version {} for {:?}: {} txns behind, \
+                 no upstream peers: {}, last error: {:?}",
+                synced_version,
+                stalled_for.unwrap_or_default(),
+                remaining,
+                self.peer_manager.is_empty(),
+                self.last_error,
+            );
+        } else if !stalled && self.stalled {
+            info!(
+                "[state sync] Progress resumed at version {}",
+                synced_version
+            );
+        }
+        self.stalled = stalled;
+        counters::STALLED.set(stalled as i64);
     }
 
     /// Ensures that StateSynchronizer is making progress:
     /// issue a new request if too much time passed since requesting highest_committed_version + 1.
     async fn check_progress(&mut self) {
+        self.check_stall();
         if self.peer_manager.is_empty() {
             return;
         }
-        if self.role == RoleType::Validator && self.sync_request.is_none() && self.is_initialized()
-        {
+        if self.syncs_on_demand() && self.sync_request.is_none() && self.is_initialized() {
             return;
         }
 
@@ -805,11 +1152,59 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     .send_chunk_request(known_version, self.local_state.epoch())
                     .await
                 {
-                    error!("[state sync] Failed to send chunk request: {}", e);
+                    self.report_error(format!("Failed to send chunk request: {}", e));
                 }
                 counters::TIMEOUT.inc();
             }
         }
+
+        // re-send the chunk requests beyond the next one that haven't been answered in time
+        let local_epoch = self.local_state.epoch();
+        if self
+            .sync_horizon
+            .map_or(true, |(_, horizon_epoch)| horizon_epoch != local_epoch)
+        {
+            return;
+        }
+        for version in self.peer_manager.get_timed_out_requests(self.retry_timeout) {
+            if version <= known_version + 1 {
+                continue;
+            }
+            self.peer_manager
+                .process_timeout(version, self.role == RoleType::Validator);
+            if let Err(e) = self.send_chunk_request(version - 1, local_epoch).await {
+                self.report_error(format!("Failed to send chunk request: {}", e));
+            }
+            counters::TIMEOUT.inc();
+        }
+    }
+
+    /// Sends a request for the chunk following `known_version` unless it has been requested
+    /// already, and then, as long as the chunks are known to be available within `known_epoch`,
+    /// requests the subsequent chunks in parallel, up to `max_in_flight_chunk_requests`.
+    async fn send_chunk_requests(&mut self, known_version: u64, known_epoch: u64) -> Result<()> {
+        if self.syncs_on_demand() && self.sync_request.is_none() && self.is_initialized() {
+            return Ok(());
+        }
+        if !self.peer_manager.has_request(known_version + 1) {
+            self.send_chunk_request(known_version, known_epoch).await?;
+        }
+
+        let horizon_version = match self.sync_horizon {
+            Some((version, epoch)) if epoch == known_epoch => version,
+            _ => return Ok(()),
+        };
+        let mut version = known_version;
+        while self.peer_manager.num_requests() < self.config.max_in_flight_chunk_requests {
+            version += self.config.chunk_limit;
+            if version >= horizon_version {
+                break;
+            }
+            if !self.peer_manager.has_request(version + 1) {
+                self.send_chunk_request(version, known_epoch).await?;
+            }
+        }
+        Ok(())
     }
 
     /// Sends a chunk request with a given `known_version` and `known_epoch`
@@ -844,7 +1239,13 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 }
             }
         };
-        let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
+        let req = GetChunkRequest::new(
+            known_version,
+            known_epoch,
+            self.config.chunk_limit,
+            self.applies_outputs(),
+            target,
+        );
         debug!(
             "[state sync] request next chunk. peer_id: {}, chunk req: {}",
             peer_id.short_str(),
@@ -877,6 +1278,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             request_info.known_version,
             ResponseLedgerInfo::VerifiableLedgerInfo(response_li),
             request_info.limit,
+            request_info.fetch_outputs,
             sender,
         )
         .await
@@ -98,6 +98,33 @@ pub static TARGET_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of transactions synced per second, averaged over the recent progress
+pub static SYNC_RATE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_state_sync_txns_per_second",
+        "Number of transactions synced per second"
+    )
+    .unwrap()
+});
+
+/// Estimated time until the target version is reached, -1 if unknown
+pub static SYNC_ETA: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_state_sync_eta_seconds",
+        "Estimated time in seconds until the target version is reached"
+    )
+    .unwrap()
+});
+
+/// Set to 1 if no progress has been made for too long while behind the target version
+pub static STALLED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_state_sync_stalled",
+        "Whether the state sync stall alarm is raised"
+    )
+    .unwrap()
+});
+
 /// Number of timeouts that occur during sync
 pub static TIMEOUT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -7,7 +7,7 @@ use executor::{ExecutedTrees, Executor};
 use libra_config::config::NodeConfig;
 use libra_types::{
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeProof},
-    transaction::TransactionListWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
 };
 use std::sync::Arc;
 use storage_client::{StorageRead, StorageReadServiceClient};
@@ -28,6 +28,15 @@ pub trait ExecutorProxyTrait: Sync + Send {
         synced_trees: &mut ExecutedTrees,
     ) -> Result<()>;
 
+    /// Apply the outputs of a batch of transactions without executing them and commit
+    async fn apply_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+        synced_trees: &mut ExecutedTrees,
+    ) -> Result<()>;
+
     /// Gets chunk of transactions given the known version, target version and the max limit.
     async fn get_chunk(
         &self,
@@ -36,6 +45,15 @@ pub trait ExecutorProxyTrait: Sync + Send {
         target_version: u64,
     ) -> Result<TransactionListWithProof>;
 
+    /// Gets chunk of transactions along with their outputs given the known version, target
+    /// version and the max limit.
+    async fn get_chunk_with_outputs(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof>;
+
     /// Get the epoch change ledger info for [start_epoch, end_epoch) so that we can move to end_epoch.
     async fn get_epoch_proof(
         &self,
@@ -101,6 +119,21 @@ impl ExecutorProxyTrait for ExecutorProxy {
         )
     }
 
+    async fn apply_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+        synced_trees: &mut ExecutedTrees,
+    ) -> Result<()> {
+        self.executor.apply_and_commit_chunk(
+            txn_output_list_with_proof,
+            verified_target_li,
+            intermediate_end_of_epoch_li,
+            synced_trees,
+        )
+    }
+
     async fn get_chunk(
         &self,
         known_version: u64,
@@ -112,6 +145,17 @@ impl ExecutorProxyTrait for ExecutorProxy {
             .await
     }
 
+    async fn get_chunk_with_outputs(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof> {
+        self.storage_read_client
+            .get_transaction_outputs(known_version + 1, limit, target_version)
+            .await
+    }
+
     async fn get_epoch_proof(
         &self,
         start_epoch: u64,
@@ -10,18 +10,23 @@
 #[macro_use]
 extern crate prometheus;
 
+pub use chunk_response::{GetChunkResponse, ResponseLedgerInfo};
+pub use chunk_source::{BackupChunkSource, ChunkSource};
 use executor::ExecutedTrees;
 use libra_types::crypto_proxies::{EpochInfo, ValidatorVerifier};
 use libra_types::{account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures};
 use std::sync::Arc;
+pub use sync_status::{PeerSyncStats, SyncStatus};
 pub use synchronizer::{StateSyncClient, StateSynchronizer};
 
 mod chunk_request;
 mod chunk_response;
+mod chunk_source;
 mod coordinator;
 mod counters;
 mod executor_proxy;
 mod peer_manager;
+mod sync_status;
 mod synchronizer;
 
 type PeerId = AccountAddress;
@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters, PeerId};
+use crate::{counters, sync_status::PeerSyncStats, PeerId};
 use libra_logger::prelude::*;
 use network::validator_network::StateSynchronizerSender;
 use rand::{
@@ -10,17 +10,25 @@ use rand::{
 };
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 const MAX_SCORE: f64 = 100.0;
 const MIN_SCORE: f64 = 1.0;
+// latency at which the chances of a peer to be picked are halved
+const HALF_WEIGHT_LATENCY_MS: f64 = 1000.0;
 
 #[derive(Default, Debug, Clone)]
 pub struct PeerInfo {
     is_alive: bool,
     is_upstream: bool,
     score: f64,
+    // moving average of the time it takes the peer to respond to a chunk request
+    avg_latency_ms: Option<f64>,
+    requests_sent: u64,
+    responses_received: u64,
+    // timeouts and chunks that could not be applied
+    failures: u64,
 }
 
 impl PeerInfo {
@@ -29,8 +37,18 @@ impl PeerInfo {
             is_alive,
             is_upstream,
             score,
+            avg_latency_ms: None,
+            requests_sent: 0,
+            responses_received: 0,
+            failures: 0,
         }
     }
+
+    /// Weight of the peer when picking a peer for a chunk request: peers with a low score
+    /// (timeouts, invalid chunks) or a high latency are picked less often.
+    fn weight(&self) -> f64 {
+        self.score / (1.0 + self.avg_latency_ms.unwrap_or(0.0) / HALF_WEIGHT_LATENCY_MS)
+    }
 }
 
 /// Basic metadata about the chunk request.
@@ -40,6 +58,10 @@ pub struct ChunkRequestInfo {
     first_request_time: SystemTime,
     last_request_time: SystemTime,
     last_request_peer: PeerId,
+    // peers the chunk has been requested from, a chunk is only accepted from one of them
+    request_peers: HashSet<PeerId>,
+    // set once a chunk starting with `version` is received, even if it's not applied yet
+    response_received: bool,
 }
 
 impl ChunkRequestInfo {
@@ -50,6 +72,8 @@ impl ChunkRequestInfo {
             first_request_time: now,
             last_request_time: now,
             last_request_peer: peer_id,
+            request_peers: vec![peer_id].into_iter().collect(),
+            response_received: false,
         }
     }
 }
@@ -69,7 +93,6 @@ pub struct PeerManager {
     peers: HashMap<PeerId, PeerInfo>,
     network_senders: HashMap<PeerId, StateSynchronizerSender>,
     requests: BTreeMap<u64, ChunkRequestInfo>,
-    weighted_index: Option<WeightedIndex<f64>>,
 }
 
 impl PeerManager {
@@ -82,7 +105,6 @@ impl PeerManager {
             peers,
             network_senders: HashMap::new(),
             requests: BTreeMap::new(),
-            weighted_index: None,
         }
     }
 
@@ -97,7 +119,7 @@ impl PeerManager {
                     .insert(*peer_id, PeerInfo::new(false, true, MAX_SCORE));
             }
         }
-        self.compute_weighted_index();
+        self.update_active_peers();
         debug!("[state sync] (set_peers) state: {:?}", self.peers);
     }
 
@@ -110,7 +132,7 @@ impl PeerManager {
             self.peers
                 .insert(peer_id, PeerInfo::new(true, false, MAX_SCORE));
         }
-        self.compute_weighted_index();
+        self.update_active_peers();
         debug!("[state sync] state after: {:?}", self.peers);
     }
 
@@ -119,7 +141,7 @@ impl PeerManager {
         if let Some(peer_info) = self.peers.get_mut(peer_id) {
             peer_info.is_alive = false;
         };
-        self.compute_weighted_index();
+        self.update_active_peers();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -129,6 +151,9 @@ impl PeerManager {
     pub fn update_score(&mut self, peer_id: &PeerId, update_type: PeerScoreUpdateType) {
         if let Some(peer_info) = self.peers.get_mut(peer_id) {
             let old_score = peer_info.score;
+            if update_type != PeerScoreUpdateType::Success {
+                peer_info.failures += 1;
+            }
             match update_type {
                 PeerScoreUpdateType::Success => {
                     let new_score = peer_info.score + 1.0;
@@ -144,50 +169,66 @@ impl PeerManager {
                     peer_info.score = new_score.max(MIN_SCORE);
                 }
             }
-            if (old_score - peer_info.score).abs() > std::f64::EPSILON {
-                self.compute_weighted_index();
-            }
+            debug!(
+                "[state sync] score of {} updated from {} to {}",
+                peer_id.short_str(),
+                old_score,
+                peer_info.score
+            );
         }
     }
 
-    fn compute_weighted_index(&mut self) {
+    fn update_active_peers(&mut self) {
         let active_peers = self.get_active_upstream_peers();
         counters::ACTIVE_UPSTREAM_PEERS.set(active_peers.len() as i64);
-
-        if !active_peers.is_empty() {
-            let weights: Vec<_> = active_peers
-                .iter()
-                .map(|(_, peer_info)| peer_info.score)
-                .collect();
-            match WeightedIndex::new(&weights) {
-                Ok(weighted_index) => {
-                    self.weighted_index = Some(weighted_index);
-                }
-                Err(e) => {
-                    error!(
-                        "[state sync] (pick_peer) failed to compute weighted index, {:?}",
-                        e
-                    );
-                }
-            }
-        }
     }
 
+    /// Picks a peer for a chunk request, randomly weighted by score and latency.
+    /// Peers that aren't serving another chunk request are preferred, so that chunks requested in
+    /// parallel are spread across peers.
     pub fn pick_peer(&self) -> Option<(PeerId, StateSynchronizerSender)> {
         let active_peers = self.get_active_upstream_peers();
         debug!("[state sync] (pick_peer) state: {:?}", self.peers);
 
-        if let Some(weighted_index) = &self.weighted_index {
-            let mut rng = thread_rng();
-            if let Some(peer) = active_peers.get(weighted_index.sample(&mut rng)) {
-                let peer_id = *peer.0;
-                if let Some(sender) = self.get_network_sender(&peer_id) {
-                    return Some((peer_id, sender));
-                } else {
-                    debug!("[state sync] (pick_peer) no sender for {}", peer_id);
-                }
+        let busy_peers: HashSet<_> = self
+            .requests
+            .values()
+            .filter(|req_info| !req_info.response_received)
+            .map(|req_info| req_info.last_request_peer)
+            .collect();
+        let idle_peers: Vec<_> = active_peers
+            .iter()
+            .filter(|(peer_id, _)| !busy_peers.contains(peer_id))
+            .cloned()
+            .collect();
+        let candidates = if idle_peers.is_empty() {
+            active_peers
+        } else {
+            idle_peers
+        };
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let weights: Vec<_> = candidates
+            .iter()
+            .map(|(_, peer_info)| peer_info.weight())
+            .collect();
+        let weighted_index = match WeightedIndex::new(&weights) {
+            Ok(weighted_index) => weighted_index,
+            Err(e) => {
+                error!(
+                    "[state sync] (pick_peer) failed to compute weighted index, {:?}",
+                    e
+                );
+                return None;
             }
+        };
+        let peer_id = *candidates[weighted_index.sample(&mut thread_rng())].0;
+        if let Some(sender) = self.get_network_sender(&peer_id) {
+            return Some((peer_id, sender));
         }
+        debug!("[state sync] (pick_peer) no sender for {}", peer_id);
         None
     }
 
@@ -203,15 +244,105 @@ impl PeerManager {
     }
 
     pub fn process_request(&mut self, version: u64, peer_id: PeerId) {
+        if let Some(peer_info) = self.peers.get_mut(&peer_id) {
+            peer_info.requests_sent += 1;
+        }
         if let Some(prev_request) = self.requests.get_mut(&version) {
             prev_request.last_request_peer = peer_id;
             prev_request.last_request_time = SystemTime::now();
+            prev_request.request_peers.insert(peer_id);
+            prev_request.response_received = false;
         } else {
             self.requests
                 .insert(version, ChunkRequestInfo::new(version, peer_id));
         }
     }
 
+    /// Records that a chunk starting with `version` was received from `peer_id`, and updates the
+    /// latency of the peer if it's the one the chunk was requested from.
+    pub fn process_response(&mut self, version: u64, peer_id: &PeerId) {
+        if let Some(peer_info) = self.peers.get_mut(peer_id) {
+            peer_info.responses_received += 1;
+        }
+        let req_info = match self.requests.get_mut(&version) {
+            Some(req_info) => req_info,
+            None => return,
+        };
+        if req_info.last_request_peer == *peer_id && !req_info.response_received {
+            if let (Ok(latency), Some(peer_info)) = (
+                SystemTime::now().duration_since(req_info.last_request_time),
+                self.peers.get_mut(peer_id),
+            ) {
+                let latency_ms = latency.as_millis() as f64;
+                peer_info.avg_latency_ms = Some(
+                    peer_info
+                        .avg_latency_ms
+                        .map_or(latency_ms, |avg| 0.8 * avg + 0.2 * latency_ms),
+                );
+            }
+        }
+        req_info.response_received = true;
+    }
+
+    pub fn has_request(&self, version: u64) -> bool {
+        self.requests.contains_key(&version)
+    }
+
+    /// Whether the chunk starting with `version` has been requested from `peer_id`
+    pub fn is_requested_from(&self, version: u64, peer_id: &PeerId) -> bool {
+        self.requests
+            .get(&version)
+            .map_or(false, |req_info| req_info.request_peers.contains(peer_id))
+    }
+
+    /// Number of chunks requested and not applied yet (either in flight or received)
+    pub fn num_requests(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Versions of the requests that haven't been answered within `timeout`
+    pub fn get_timed_out_requests(&self, timeout: Duration) -> Vec<u64> {
+        let now = SystemTime::now();
+        self.requests
+            .values()
+            .filter(|req_info| {
+                !req_info.response_received
+                    && req_info
+                        .last_request_time
+                        .checked_add(timeout)
+                        .map_or(false, |deadline| now.duration_since(deadline).is_ok())
+            })
+            .map(|req_info| req_info.version)
+            .collect()
+    }
+
+    /// Peer the chunk starting with `version` has been requested from most recently
+    pub fn get_last_request_peer(&self, version: u64) -> Option<PeerId> {
+        self.requests
+            .get(&version)
+            .map(|req_info| req_info.last_request_peer)
+    }
+
+    /// Request / response statistics of all the known peers, ordered by peer id
+    pub fn peer_stats(&self) -> Vec<PeerSyncStats> {
+        let mut stats: Vec<_> = self
+            .peers
+            .iter()
+            .map(|(peer_id, peer_info)| PeerSyncStats {
+                peer_id: *peer_id,
+                is_alive: peer_info.is_alive,
+                is_upstream: peer_info.is_upstream,
+                score: peer_info.score,
+                avg_latency_ms: peer_info.avg_latency_ms,
+                requests_sent: peer_info.requests_sent,
+                responses_received: peer_info.responses_received,
+                failures: peer_info.failures,
+            })
+            .collect();
+        stats.sort_by_key(|stats| stats.peer_id);
+        stats
+    }
+
     pub fn get_last_request_time(&self, version: u64) -> Option<SystemTime> {
         self.requests
             .get(&version)
@@ -246,4 +377,9 @@ impl PeerManager {
     pub fn peer_score(&self, peer_id: &PeerId) -> Option<f64> {
         self.peers.get(peer_id).map(|p| p.score)
     }
+
+    #[cfg(test)]
+    pub fn peer_latency_ms(&self, peer_id: &PeerId) -> Option<f64> {
+        self.peers.get(peer_id).and_then(|p| p.avg_latency_ms)
+    }
 }
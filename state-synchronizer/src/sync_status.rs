@@ -0,0 +1,161 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::PeerId;
+use debug_interface::proto::{GetSyncStatusResponse, PeerSyncStatus, SyncTarget};
+use libra_types::transaction::Version;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// the sync rate is computed over the progress made within this window
+const RATE_WINDOW: Duration = Duration::from_secs(30);
+
+/// Snapshot of the progress of state synchronization.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncStatus {
+    /// Version and epoch the node is syncing to: the target of the pending sync request, or the
+    /// highest version known to be available upstream.
+    pub target: Option<(Version, u64)>,
+    /// Version of the highest ledger info in the local storage.
+    pub committed_version: Version,
+    /// Highest version in the local storage, even if it's not covered by a ledger info yet.
+    pub synced_version: Version,
+    pub epoch: u64,
+    pub txns_per_sec: f64,
+    /// Estimated time until the target is reached, if the node is making progress.
+    pub eta: Option<Duration>,
+    /// Time without progress while behind the target.
+    pub stalled_for: Option<Duration>,
+    /// Whether `stalled_for` exceeds the configured stall alarm timeout.
+    pub stalled: bool,
+    /// Peer the chunk following `synced_version` has been requested from.
+    pub syncing_from: Option<PeerId>,
+    pub peers: Vec<PeerSyncStats>,
+    pub last_error: Option<String>,
+}
+
+/// Request / response statistics of a peer since the node started.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerSyncStats {
+    pub peer_id: PeerId,
+    pub is_alive: bool,
+    pub is_upstream: bool,
+    pub score: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub requests_sent: u64,
+    pub responses_received: u64,
+    /// Timeouts and chunks that could not be applied.
+    pub failures: u64,
+}
+
+impl From<SyncStatus> for GetSyncStatusResponse {
+    fn from(status: SyncStatus) -> Self {
+        Self {
+            target: status
+                .target
+                .map(|(version, epoch)| SyncTarget { version, epoch }),
+            committed_version: status.committed_version,
+            synced_version: status.synced_version,
+            epoch: status.epoch,
+            txns_per_second: status.txns_per_sec,
+            eta_secs: status.eta.map_or(-1, |eta| eta.as_secs() as i64),
+            stalled_secs: status.stalled_for.map_or(0, |d| d.as_secs()),
+            stalled: status.stalled,
+            syncing_from: status
+                .syncing_from
+                .map_or_else(String::new, |peer_id| peer_id.to_string()),
+            peers: status.peers.into_iter().map(Into::into).collect(),
+            last_error: status.last_error.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<PeerSyncStats> for PeerSyncStatus {
+    fn from(stats: PeerSyncStats) -> Self {
+        Self {
+            peer_id: stats.peer_id.to_string(),
+            is_alive: stats.is_alive,
+            is_upstream: stats.is_upstream,
+            score: stats.score,
+            avg_latency_ms: stats.avg_latency_ms.unwrap_or(0.0),
+            requests_sent: stats.requests_sent,
+            responses_received: stats.responses_received,
+            failures: stats.failures,
+        }
+    }
+}
+
+/// Keeps track of the rate at which the synced version advances and of how long the node has
+/// been behind its target without making progress.
+pub(crate) struct ProgressTracker {
+    // (time, version) samples within the rate window, the oldest first
+    samples: VecDeque<(Instant, Version)>,
+    // last time the version advanced or the node fell behind its target, whichever is later
+    last_progress: Instant,
+    behind: bool,
+}
+
+impl ProgressTracker {
+    pub fn new(version: Version, now: Instant) -> Self {
+        let mut samples = VecDeque::new();
+        samples.push_back((now, version));
+        Self {
+            samples,
+            last_progress: now,
+            behind: false,
+        }
+    }
+
+    pub fn record(&mut self, version: Version, now: Instant) {
+        let last_version = self.samples.back().map_or(0, |(_, version)| *version);
+        if version <= last_version {
+            return;
+        }
+        self.last_progress = now;
+        self.samples.push_back((now, version));
+        while self.samples.len() > 1 && now.duration_since(self.samples[0].0) > RATE_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn set_behind(&mut self, behind: bool, now: Instant) {
+        if behind && !self.behind {
+            self.last_progress = now;
+        }
+        self.behind = behind;
+    }
+
+    /// Number of transactions synced per second within the rate window.
+    pub fn txns_per_sec(&self, now: Instant) -> f64 {
+        let (oldest_time, oldest_version) = match self.samples.front() {
+            Some(sample) => sample,
+            None => return 0.0,
+        };
+        let (_, latest_version) = self.samples.back().expect("samples can't be empty");
+        let elapsed = now.duration_since(*oldest_time).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        (latest_version - oldest_version) as f64 / elapsed
+    }
+
+    /// Time it takes to sync `remaining` transactions at the current rate.
+    pub fn eta(&self, remaining: u64, now: Instant) -> Option<Duration> {
+        let rate = self.txns_per_sec(now);
+        if rate > 0.0 {
+            Some(Duration::from_secs_f64(remaining as f64 / rate))
+        } else {
+            None
+        }
+    }
+
+    pub fn stalled_for(&self, now: Instant) -> Option<Duration> {
+        if self.behind {
+            Some(now.duration_since(self.last_progress))
+        } else {
+            None
+        }
+    }
+}
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::coordinator::EpochRetrievalRequest;
 use crate::{
+    chunk_source::{BackupChunkSource, ChunkSource},
     coordinator::{CoordinatorMessage, SyncCoordinator, SyncRequest},
     executor_proxy::{ExecutorProxy, ExecutorProxyTrait},
-    SynchronizerState,
+    SyncStatus, SynchronizerState,
 };
 use anyhow::Result;
+use backup_restore::adapter::local_storage::LocalStorage;
+use debug_interface::{node_debug_service::SyncStatusProvider, proto::GetSyncStatusResponse};
 use executor::Executor;
 use futures::{
     channel::{mpsc, oneshot},
@@ -35,21 +38,35 @@ impl StateSynchronizer {
         config: &NodeConfig,
     ) -> Self {
         let executor_proxy = ExecutorProxy::new(executor, config);
+        let chunk_source = config.state_sync.backup_manifest.as_ref().map(|manifest| {
+            let manifest_handle = manifest.to_string_lossy().into_owned();
+            Box::new(BackupChunkSource::<LocalStorage>::new(manifest_handle))
+                as Box<dyn ChunkSource>
+        });
+        // The consensus observer of a full node (see `libra-node`) runs on its first full node
+        // network.
+        let observer_mode = config.base.role == RoleType::FullNode
+            && config.consensus.observer_mode
+            && !config.full_node_networks.is_empty();
         Self::bootstrap_with_executor_proxy(
             network,
             config.base.role,
+            observer_mode,
             config.base.waypoint,
             &config.state_sync,
             executor_proxy,
+            chunk_source,
         )
     }
 
     pub fn bootstrap_with_executor_proxy<E: ExecutorProxyTrait + 'static>(
         network: Vec<(StateSynchronizerSender, StateSynchronizerEvents)>,
         role: RoleType,
+        observer_mode: bool,
         waypoint: Option<Waypoint>,
         state_sync_config: &StateSyncConfig,
         executor_proxy: E,
+        chunk_source: Option<Box<dyn ChunkSource>>,
     ) -> Self {
         let mut runtime = Builder::new()
             .thread_name("state-sync-")
@@ -66,10 +83,12 @@ impl StateSynchronizer {
         let coordinator = SyncCoordinator::new(
             coordinator_receiver,
             role,
+            observer_mode,
             waypoint,
             state_sync_config.clone(),
             executor_proxy,
             initial_state,
+            chunk_source,
         );
         runtime.spawn(coordinator.start(network));
 
@@ -138,6 +157,19 @@ impl StateSyncClient {
         }
     }
 
+    /// Returns a report of the sync progress
+    pub fn get_sync_status(&self) -> impl Future<Output = Result<SyncStatus>> {
+        let mut sender = self.coordinator_sender.clone();
+        let (cb_sender, cb_receiver) = oneshot::channel();
+        async move {
+            sender
+                .send(CoordinatorMessage::GetSyncStatus(cb_sender))
+                .await?;
+            let status = cb_receiver.await?;
+            Ok(status)
+        }
+    }
+
     pub fn get_epoch_proof(
         &self,
         start_epoch: u64,
@@ -158,3 +190,10 @@ impl StateSyncClient {
         }
     }
 }
+
+#[async_trait::async_trait]
+impl SyncStatusProvider for StateSyncClient {
+    async fn sync_status(&self) -> Result<GetSyncStatusResponse> {
+        Ok(self.get_sync_status().await?.into())
+    }
+}
@@ -3,14 +3,14 @@
 
 use crate::tests::mock_storage::MockStorage;
 use crate::{
-    executor_proxy::ExecutorProxyTrait, PeerId, StateSyncClient, StateSynchronizer,
-    SynchronizerState,
+    executor_proxy::ExecutorProxyTrait, ChunkSource, GetChunkResponse, PeerId, ResponseLedgerInfo,
+    StateSyncClient, StateSynchronizer, SyncStatus, SynchronizerState,
 };
 use anyhow::{bail, Result};
 use config_builder;
 use executor::ExecutedTrees;
 use futures::executor::block_on;
-use libra_config::config::RoleType;
+use libra_config::config::{RoleType, StateSyncConfig};
 use libra_crypto::x25519::{X25519StaticPrivateKey, X25519StaticPublicKey};
 use libra_crypto::{ed25519::*, test_utils::TEST_SEED, x25519, HashValue};
 use libra_logger::set_simple_logger;
@@ -22,8 +22,9 @@ use libra_types::{
     },
     ledger_info::LedgerInfo,
     proof::TransactionListProof,
-    transaction::TransactionListWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
     waypoint::Waypoint,
+    write_set::WriteSet,
 };
 use network::{
     validator_network::{
@@ -39,8 +40,9 @@ use std::{
     collections::{BTreeMap, HashMap},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc,
+        Arc, Mutex,
     },
+    time::Duration,
 };
 use tokio::runtime::Runtime;
 
@@ -48,6 +50,24 @@ type MockRpcHandler = Box<
     dyn Fn(TransactionListWithProof) -> Result<TransactionListWithProof> + Send + Sync + 'static,
 >;
 
+struct MockChunkSource {
+    chunks: Vec<GetChunkResponse>,
+    num_served: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl ChunkSource for MockChunkSource {
+    async fn get_chunk(&mut self, known_version: u64) -> Result<Option<GetChunkResponse>> {
+        let chunk = self.chunks.iter().find(|chunk| {
+            chunk.txn_list_with_proof.first_transaction_version == Some(known_version + 1)
+        });
+        if chunk.is_some() {
+            self.num_served.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(chunk.cloned())
+    }
+}
+
 pub struct MockExecutorProxy {
     handler: MockRpcHandler,
     storage: Arc<RwLock<MockStorage>>,
@@ -80,6 +100,22 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 
+    async fn apply_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+        _synced_trees: &mut ExecutedTrees,
+    ) -> Result<()> {
+        self.storage.write().unwrap().apply_txns_with_li(
+            txn_output_list_with_proof.txn_list_with_proof.transactions,
+            txn_output_list_with_proof.write_sets,
+            ledger_info_with_sigs,
+            intermediate_end_of_epoch_li,
+        );
+        Ok(())
+    }
+
     async fn get_chunk(
         &self,
         known_version: u64,
@@ -101,6 +137,20 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         (self.handler)(txns_with_proof)
     }
 
+    async fn get_chunk_with_outputs(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof> {
+        let txns_with_proof = self.get_chunk(known_version, limit, target_version).await?;
+        let write_sets = vec![WriteSet::default(); txns_with_proof.len()];
+        Ok(TransactionOutputListWithProof::new(
+            txns_with_proof,
+            write_sets,
+        ))
+    }
+
     async fn get_epoch_proof(
         &self,
         start_epoch: u64,
@@ -123,7 +173,7 @@ struct SynchronizerEnv {
     network_signers: Vec<Ed25519PrivateKey>,
     public_keys: Vec<ValidatorPublicKeys>,
     peer_ids: Vec<PeerId>,
-    peer_addresses: Vec<Multiaddr>,
+    peer_addresses: Vec<Vec<Multiaddr>>,
 }
 
 impl SynchronizerEnv {
@@ -239,6 +289,36 @@ impl SynchronizerEnv {
         handler: MockRpcHandler,
         role: RoleType,
         waypoint: Option<Waypoint>,
+    ) {
+        let state_sync_config = config_builder::test_config().0.state_sync;
+        self.start_next_synchronizer_with_config(handler, role, waypoint, state_sync_config);
+    }
+
+    fn start_next_synchronizer_with_config(
+        &mut self,
+        handler: MockRpcHandler,
+        role: RoleType,
+        waypoint: Option<Waypoint>,
+        state_sync_config: StateSyncConfig,
+    ) {
+        self.start_next_synchronizer_with_options(
+            handler,
+            role,
+            false,
+            waypoint,
+            state_sync_config,
+            None,
+        );
+    }
+
+    fn start_next_synchronizer_with_options(
+        &mut self,
+        handler: MockRpcHandler,
+        role: RoleType,
+        observer_mode: bool,
+        waypoint: Option<Waypoint>,
+        state_sync_config: StateSyncConfig,
+        chunk_source: Option<Box<dyn ChunkSource>>,
     ) {
         let new_peer_idx = self.synchronizers.len();
         let trusted_peers: HashMap<_, _> = self
@@ -265,13 +345,13 @@ impl SynchronizerEnv {
         if new_peer_idx > 0 {
             seed_peers.insert(
                 self.peer_ids[new_peer_idx - 1],
-                vec![self.peer_addresses[new_peer_idx - 1].clone()],
+                self.peer_addresses[new_peer_idx - 1].clone(),
             );
         }
-        let (peer_addr, mut network_provider) = NetworkBuilder::new(
+        let (peer_addrs, mut network_provider) = NetworkBuilder::new(
             self.runtime.handle().clone(),
             self.peer_ids[new_peer_idx],
-            addr,
+            vec![addr],
             RoleType::Validator,
         )
         .signing_keys((
@@ -297,6 +377,7 @@ impl SynchronizerEnv {
             config.validator_network = None;
         }
         config.base.role = role;
+        config.state_sync = state_sync_config;
         if new_peer_idx > 0 {
             // set the upstream peer in the config
             config
@@ -314,15 +395,39 @@ impl SynchronizerEnv {
         let synchronizer = StateSynchronizer::bootstrap_with_executor_proxy(
             vec![(sender, events)],
             role,
+            observer_mode,
             waypoint,
             &config.state_sync,
             MockExecutorProxy::new(handler, storage_proxy.clone()),
+            chunk_source,
         );
         let client = synchronizer.create_client();
         self.synchronizers.push(synchronizer);
         self.clients.push(client);
         self.storage_proxies.push(storage_proxy);
-        self.peer_addresses.push(peer_addr);
+        self.peer_addresses.push(peer_addrs);
+    }
+
+    // Chunks of the transactions committed by the given peer, built against its latest LI.
+    fn chunks(&self, peer_id: usize, chunk_size: u64) -> Vec<GetChunkResponse> {
+        let storage = self.storage_proxies[peer_id].read().unwrap();
+        let li = storage.highest_local_li();
+        let li_version = li.ledger_info().version();
+        (1..=li_version)
+            .step_by(chunk_size as usize)
+            .map(|first_version| {
+                let txns = storage.get_chunk(first_version, chunk_size, li_version);
+                GetChunkResponse::new(
+                    ResponseLedgerInfo::VerifiableLedgerInfo(li.clone()),
+                    TransactionListWithProof::new(
+                        txns,
+                        None,
+                        Some(first_version),
+                        TransactionListProof::new_empty(),
+                    ),
+                )
+            })
+            .collect()
     }
 
     fn default_handler() -> MockRpcHandler {
@@ -369,6 +474,17 @@ impl SynchronizerEnv {
         false
     }
 
+    fn num_applied_txns(&self, peer_id: usize) -> u64 {
+        self.storage_proxies[peer_id]
+            .read()
+            .unwrap()
+            .num_applied_txns()
+    }
+
+    fn get_sync_status(&self, peer_id: usize) -> SyncStatus {
+        block_on(self.clients[peer_id].get_sync_status()).unwrap()
+    }
+
     fn wait_until_initialized(&self, peer_id: usize) -> Result<()> {
         block_on(self.synchronizers[peer_id].wait_until_initialized())
     }
@@ -431,6 +547,34 @@ fn test_flaky_peer_sync() {
     assert_eq!(env.latest_li(1).ledger_info().version(), 20);
 }
 
+#[test]
+fn test_pipelined_catch_up() {
+    // every third chunk request fails and has to be retried
+    let attempt = AtomicUsize::new(0);
+    let handler = Box::new(move |resp| -> Result<TransactionListWithProof> {
+        if attempt.fetch_add(1, Ordering::Relaxed) % 3 == 2 {
+            bail!("chunk fetch failed")
+        } else {
+            Ok(resp)
+        }
+    });
+    let mut env = SynchronizerEnv::new(2);
+    env.start_next_synchronizer(handler, RoleType::Validator, None);
+    let mut state_sync_config = config_builder::test_config().0.state_sync;
+    state_sync_config.chunk_limit = 10;
+    state_sync_config.max_in_flight_chunk_requests = 4;
+    env.start_next_synchronizer_with_config(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+        state_sync_config,
+    );
+
+    env.commit(0, 500);
+    env.sync_to(1, env.latest_li(0));
+    assert_eq!(env.latest_li(1).ledger_info().version(), 500);
+}
+
 #[test]
 fn test_full_node() {
     let mut env = SynchronizerEnv::new(2);
@@ -449,6 +593,185 @@ fn test_full_node() {
     assert!(env.wait_for_version(1, 20));
 }
 
+#[test]
+fn test_sync_status() {
+    let mut env = SynchronizerEnv::new(2);
+    env.start_next_synchronizer(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+    );
+    env.start_next_synchronizer(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+    );
+    let status = env.get_sync_status(1);
+    assert_eq!(status.target, None);
+    assert_eq!(status.synced_version, 0);
+    assert!(!status.stalled);
+
+    env.commit(0, 100);
+    env.sync_to(1, env.latest_li(0));
+    let status = env.get_sync_status(1);
+    assert_eq!(status.target, Some((100, 1)));
+    assert_eq!(status.committed_version, 100);
+    assert_eq!(status.synced_version, 100);
+    assert!(status.txns_per_sec > 0.0);
+    assert!(!status.stalled);
+    let upstream_stats = status
+        .peers
+        .iter()
+        .find(|stats| stats.peer_id == env.peer_ids[0])
+        .unwrap();
+    assert!(upstream_stats.requests_sent > 0);
+    assert!(upstream_stats.responses_received > 0);
+}
+
+#[test]
+fn test_full_node_applying_outputs() {
+    let mut env = SynchronizerEnv::new(2);
+    env.start_next_synchronizer(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+    );
+    let mut state_sync_config = config_builder::test_config().0.state_sync;
+    state_sync_config.apply_transaction_outputs = true;
+    env.start_next_synchronizer_with_config(
+        SynchronizerEnv::default_handler(),
+        RoleType::FullNode,
+        None,
+        state_sync_config,
+    );
+    env.commit(0, 10);
+    assert!(env.wait_for_version(1, 10));
+    env.commit(0, 20);
+    assert!(env.wait_for_version(1, 20));
+    // none of the transactions has been re-executed
+    assert_eq!(env.num_applied_txns(1), 20);
+    assert_eq!(env.num_applied_txns(0), 0);
+}
+
+#[test]
+fn test_sync_from_chunk_source() {
+    let mut env = SynchronizerEnv::new(2);
+    env.start_next_synchronizer(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+    );
+    env.commit(0, 60);
+    let chunk_source = MockChunkSource {
+        chunks: env.chunks(0, 25),
+        num_served: Arc::new(AtomicUsize::new(0)),
+    };
+    let num_served = chunk_source.num_served.clone();
+    env.commit(0, 100);
+
+    // the first 60 transactions are read from the chunk source, the tail from the network
+    env.start_next_synchronizer_with_options(
+        SynchronizerEnv::default_handler(),
+        RoleType::FullNode,
+        false,
+        None,
+        config_builder::test_config().0.state_sync,
+        Some(Box::new(chunk_source)),
+    );
+    assert!(env.wait_for_version(1, 100));
+    assert_eq!(num_served.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_sync_from_chunk_source_with_waypoint() {
+    let mut env = SynchronizerEnv::new(3);
+    env.start_next_synchronizer(
+        SynchronizerEnv::default_handler(),
+        RoleType::Validator,
+        None,
+    );
+    env.commit(0, 60);
+    let chunks = env.chunks(0, 25);
+    let waypoint = Waypoint::new(env.latest_li(0).ledger_info()).unwrap();
+    env.commit(0, 100);
+
+    // the chunks up to the waypoint carry the waypoint LI
+    let chunk_source = MockChunkSource {
+        chunks: chunks.clone(),
+        num_served: Arc::new(AtomicUsize::new(0)),
+    };
+    let num_served = chunk_source.num_served.clone();
+    env.start_next_synchronizer_with_options(
+        SynchronizerEnv::default_handler(),
+        RoleType::FullNode,
+        false,
+        Some(waypoint),
+        config_builder::test_config().0.state_sync,
+        Some(Box::new(chunk_source)),
+    );
+    env.wait_until_initialized(1).unwrap();
+    assert!(env.wait_for_version(1, 100));
+    assert_eq!(num_served.load(Ordering::Relaxed), 3);
+
+    // the chunks don't verify against a later waypoint: the node syncs from the network instead
+    let chunk_source = MockChunkSource {
+        chunks,
+        num_served: Arc::new(AtomicUsize::new(0)),
+    };
+    let num_served = chunk_source.num_served.clone();
+    let waypoint = Waypoint::new(env.latest_li(0).ledger_info()).unwrap();
+    env.start_next_synchronizer_with_options(
+        SynchronizerEnv::default_handler(),
+        RoleType::FullNode,
+        false,
+        Some(waypoint),
+        config_builder::test_config().0.state_sync,
+        Some(Box::new(chunk_source)),
+    );
+    env.wait_until_initialized(2).unwrap();
+    assert!(env.wait_for_version(2, 100));
+    assert_eq!(num_served.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_full_node_with_observer() {
+    let mut env = SynchronizerEnv::new(2);
+    // Peer 0 reports every chunk it serves.
+    let (served_sender, served_receiver) = std::sync::mpsc::channel();
+    let served_sender = Mutex::new(served_sender);
+    env.start_next_synchronizer(
+        Box::new(move |resp| -> Result<TransactionListWithProof> {
+            served_sender.lock().unwrap().send(resp.len()).unwrap();
+            Ok(resp)
+        }),
+        RoleType::Validator,
+        None,
+    );
+    let config = config_builder::test_config().0.state_sync;
+    // Long enough for the full node to request a chunk if it was going to.
+    let request_window = Duration::from_millis(config.tick_interval_ms * 10);
+    env.start_next_synchronizer_with_options(
+        SynchronizerEnv::default_handler(),
+        RoleType::FullNode,
+        true,
+        None,
+        config,
+        None,
+    );
+    env.commit(0, 10);
+    // The observer is the only writer of the ledger: no chunk is fetched on its behalf...
+    assert!(served_receiver.recv_timeout(request_window).is_err());
+    assert_eq!(env.latest_li(1).ledger_info().version(), 0);
+    // ... unless it falls behind and asks state sync to catch up.
+    env.sync_to(1, env.latest_li(0));
+    assert_eq!(env.latest_li(1).ledger_info().version(), 10);
+    assert!(served_receiver.try_iter().count() > 0);
+
+    env.commit(0, 30);
+    assert!(served_receiver.recv_timeout(request_window).is_err());
+    assert_eq!(env.latest_li(1).ledger_info().version(), 10);
+}
+
 #[test]
 fn catch_up_through_epochs_validators() {
     let mut env = SynchronizerEnv::new(2);
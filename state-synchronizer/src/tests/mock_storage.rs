@@ -12,7 +12,7 @@ use libra_types::crypto_proxies::{ValidatorChangeProof, ValidatorSigner, Validat
 use libra_types::{
     account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures,
     ledger_info::LedgerInfo, test_helpers::transaction_test_helpers::get_test_signed_txn,
-    transaction::Transaction,
+    transaction::Transaction, write_set::WriteSet,
 };
 use std::collections::{BTreeMap, HashMap};
 use transaction_builder::encode_transfer_script;
@@ -32,6 +32,8 @@ pub struct MockStorage {
     signer: ValidatorSigner,
     // A validator verifier of the latest epoch
     verifier: ValidatorVerifier,
+    // number of txns that have been added from their outputs instead of being executed
+    num_applied_txns: u64,
 }
 
 impl MockStorage {
@@ -51,6 +53,7 @@ impl MockStorage {
             epoch_num,
             signer,
             verifier,
+            num_applied_txns: 0,
         }
     }
 
@@ -144,6 +147,26 @@ impl MockStorage {
         }
     }
 
+    pub fn apply_txns_with_li(
+        &mut self,
+        transactions: Vec<Transaction>,
+        write_sets: Vec<WriteSet>,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) {
+        assert_eq!(transactions.len(), write_sets.len());
+        self.num_applied_txns += transactions.len() as u64;
+        self.add_txns_with_li(
+            transactions,
+            verified_target_li,
+            intermediate_end_of_epoch_li,
+        );
+    }
+
+    pub fn num_applied_txns(&self) -> u64 {
+        self.num_applied_txns
+    }
+
     // Generate new dummy txns and updates the LI
     // with the version corresponding to the new transactions, signed by this storage signer.
     pub fn commit_new_txns(&mut self, num_txns: u64) {
@@ -3,11 +3,15 @@
 
 use crate::{
     peer_manager::{PeerManager, PeerScoreUpdateType},
+    sync_status::ProgressTracker,
     PeerId,
 };
 use channel;
 use network::validator_network::StateSynchronizerSender;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 #[test]
 fn test_peer_manager() {
@@ -76,4 +80,137 @@ fn test_peer_manager_request_metadata() {
         peer_manager.get_first_request_time(1).unwrap()
             <= peer_manager.get_last_request_time(1).unwrap()
     );
+    assert!(peer_manager.is_requested_from(1, &peers[0]));
+    assert!(peer_manager.is_requested_from(1, &peers[1]));
+    assert!(!peer_manager.is_requested_from(1, &PeerId::random()));
+    assert!(!peer_manager.is_requested_from(11, &peers[0]));
+}
+
+#[test]
+fn test_pick_idle_peer() {
+    let peers = vec![PeerId::random(), PeerId::random()];
+    let mut peer_manager = PeerManager::new(peers.clone());
+    let (network_reqs_tx, _) = channel::new_test(8);
+    let sender = StateSynchronizerSender::new(network_reqs_tx);
+    for peer_id in peers.clone() {
+        peer_manager.enable_peer(peer_id, sender.clone());
+    }
+
+    // peer serving a chunk request is not picked while another peer is idle
+    peer_manager.process_request(1, peers[0]);
+    for _ in 0..100 {
+        assert_eq!(peer_manager.pick_peer().unwrap().0, peers[1]);
+    }
+
+    // all the peers are busy
+    peer_manager.process_request(11, peers[1]);
+    assert!(peer_manager.pick_peer().is_some());
+
+    peer_manager.process_response(1, &peers[0]);
+    for _ in 0..100 {
+        assert_eq!(peer_manager.pick_peer().unwrap().0, peers[0]);
+    }
+}
+
+#[test]
+fn test_peer_latency() {
+    let peers = vec![PeerId::random(), PeerId::random()];
+    let mut peer_manager = PeerManager::new(peers.clone());
+    let (network_reqs_tx, _) = channel::new_test(8);
+    let sender = StateSynchronizerSender::new(network_reqs_tx);
+    for peer_id in peers.clone() {
+        peer_manager.enable_peer(peer_id, sender.clone());
+    }
+    assert!(peer_manager.peer_latency_ms(&peers[0]).is_none());
+
+    // response from a peer the chunk hasn't been requested from doesn't count
+    peer_manager.process_request(1, peers[0]);
+    peer_manager.process_response(1, &peers[1]);
+    assert!(peer_manager.peer_latency_ms(&peers[1]).is_none());
+
+    peer_manager.process_request(11, peers[0]);
+    std::thread::sleep(Duration::from_millis(10));
+    peer_manager.process_response(11, &peers[0]);
+    assert!(peer_manager.peer_latency_ms(&peers[0]).unwrap() >= 10.0);
+    assert!(peer_manager.peer_latency_ms(&peers[1]).is_none());
+}
+
+#[test]
+fn test_timed_out_requests() {
+    let peers = vec![PeerId::random(), PeerId::random()];
+    let mut peer_manager = PeerManager::new(peers.clone());
+    peer_manager.process_request(1, peers[0]);
+    peer_manager.process_request(11, peers[1]);
+    peer_manager.process_request(21, peers[0]);
+    assert_eq!(peer_manager.num_requests(), 3);
+    assert!(peer_manager
+        .get_timed_out_requests(Duration::from_secs(60))
+        .is_empty());
+
+    // answered requests don't time out
+    peer_manager.process_response(11, &peers[1]);
+    assert_eq!(
+        peer_manager.get_timed_out_requests(Duration::from_secs(0)),
+        vec![1, 21]
+    );
+    assert!(peer_manager.has_request(11));
+    assert!(!peer_manager.has_request(31));
+}
+
+#[test]
+fn test_peer_stats() {
+    let peers = vec![PeerId::random(), PeerId::random()];
+    let mut peer_manager = PeerManager::new(peers.clone());
+    peer_manager.process_request(1, peers[0]);
+    peer_manager.process_request(11, peers[1]);
+    peer_manager.process_response(1, &peers[0]);
+    peer_manager.process_timeout(11, true);
+    assert_eq!(peer_manager.get_last_request_peer(11), Some(peers[1]));
+    assert_eq!(peer_manager.get_last_request_peer(21), None);
+
+    let stats: HashMap<_, _> = peer_manager
+        .peer_stats()
+        .into_iter()
+        .map(|stats| (stats.peer_id, stats))
+        .collect();
+    assert_eq!(stats[&peers[0]].requests_sent, 1);
+    assert_eq!(stats[&peers[0]].responses_received, 1);
+    assert_eq!(stats[&peers[0]].failures, 0);
+    assert_eq!(stats[&peers[1]].requests_sent, 1);
+    assert_eq!(stats[&peers[1]].responses_received, 0);
+    assert_eq!(stats[&peers[1]].failures, 1);
+}
+
+#[test]
+fn test_progress_tracker() {
+    let start = Instant::now();
+    let mut progress = ProgressTracker::new(100, start);
+    assert!(progress.txns_per_sec(start).abs() < std::f64::EPSILON);
+    assert_eq!(progress.eta(100, start), None);
+    assert_eq!(progress.stalled_for(start), None);
+
+    progress.record(200, start + Duration::from_secs(10));
+    let now = start + Duration::from_secs(10);
+    assert!((progress.txns_per_sec(now) - 10.0).abs() < std::f64::EPSILON);
+    assert_eq!(progress.eta(100, now), Some(Duration::from_secs(10)));
+
+    // the stall is measured from the moment the node falls behind
+    progress.set_behind(true, start + Duration::from_secs(20));
+    assert_eq!(
+        progress.stalled_for(start + Duration::from_secs(25)),
+        Some(Duration::from_secs(5))
+    );
+    progress.record(300, start + Duration::from_secs(30));
+    assert_eq!(
+        progress.stalled_for(start + Duration::from_secs(30)),
+        Some(Duration::from_secs(0))
+    );
+    progress.set_behind(false, start + Duration::from_secs(30));
+    assert_eq!(progress.stalled_for(start + Duration::from_secs(30)), None);
+
+    // old samples leave the rate window
+    progress.record(400, start + Duration::from_secs(100));
+    progress.record(500, start + Duration::from_secs(105));
+    let rate = progress.txns_per_sec(start + Duration::from_secs(110));
+    assert!((rate - 10.0).abs() < std::f64::EPSILON);
 }
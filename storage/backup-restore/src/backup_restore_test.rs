@@ -0,0 +1,41 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::adapter::local_storage::LocalStorage;
+use futures::executor::block_on;
+
+fn chunk_info(first_version: Version, last_version: Version) -> TransactionChunkInfo {
+    TransactionChunkInfo {
+        first_version,
+        last_version,
+        file_handle: format!("{}-{}", first_version, last_version),
+    }
+}
+
+#[test]
+fn test_find_chunk() {
+    let manifest = TransactionBackupManifest {
+        chunks: vec![chunk_info(1, 10), chunk_info(11, 11), chunk_info(12, 30)],
+    };
+    assert_eq!(manifest.find_chunk(0), None);
+    assert_eq!(manifest.find_chunk(1), Some(&manifest.chunks[0]));
+    assert_eq!(manifest.find_chunk(10), Some(&manifest.chunks[0]));
+    assert_eq!(manifest.find_chunk(11), Some(&manifest.chunks[1]));
+    assert_eq!(manifest.find_chunk(20), Some(&manifest.chunks[2]));
+    assert_eq!(manifest.find_chunk(31), None);
+    assert_eq!(TransactionBackupManifest::default().find_chunk(1), None);
+}
+
+#[test]
+fn test_manifest_round_trip() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let adapter = LocalStorage::new(tmpdir.path().to_path_buf());
+    let manifest = TransactionBackupManifest {
+        chunks: vec![chunk_info(1, 100), chunk_info(101, 150)],
+    };
+
+    let handle = block_on(write_lcs(&adapter, &manifest)).unwrap();
+    let read = block_on(read_transaction_backup_manifest::<LocalStorage>(&handle)).unwrap();
+    assert_eq!(read, manifest);
+}
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use backup_restore::{adapter::local_storage::LocalStorage, backup_account_state};
+use backup_restore::{
+    adapter::local_storage::LocalStorage, backup_account_state, backup_transactions,
+};
+use libra_types::transaction::Version;
 use std::path::PathBuf;
 use storage_client::{StorageRead, StorageReadServiceClient};
 use structopt::StructOpt;
@@ -12,6 +15,14 @@ struct Opt {
     #[structopt(long, default_value = "4194304")]
     state_chunk_size: usize,
 
+    /// Also back up the transactions from this version on, so that state sync can replay them.
+    #[structopt(long)]
+    txn_start_version: Option<Version>,
+
+    /// Maximum number of transactions per chunk of the transaction backup.
+    #[structopt(long, default_value = "1000")]
+    txn_chunk_size: u64,
+
     /// Where the backup is stored.
     #[structopt(long, parse(from_os_str))]
     local_dir: PathBuf,
@@ -44,4 +55,11 @@ async fn main() {
         println!("{}", account_state_file);
         println!("{}", proof_file);
     }
+
+    if let Some(start_version) = opt.txn_start_version {
+        let manifest = backup_transactions(&client, start_version, &adapter, opt.txn_chunk_size)
+            .await
+            .expect("Failed to backup transactions.");
+        println!("Transaction backup manifest: {}", manifest);
+    }
 }
@@ -3,15 +3,66 @@
 
 pub mod adapter;
 
+#[cfg(test)]
+mod backup_restore_test;
+
 use crate::adapter::Adapter;
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use futures::{stream, StreamExt};
 use libra_crypto::HashValue;
-use libra_types::transaction::Version;
+use libra_types::{
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, Version},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use storage_client::{StorageRead, StorageReadServiceClient};
 
 pub type FileHandle = String;
 
+/// A chunk of consecutive transactions along with the ledger info their proof is relative to.
+///
+/// The chunks of an epoch are proven against the ledger info that ends the epoch (or the latest
+/// ledger info for the last epoch), so that they can be verified with the validator set of the
+/// epoch alone.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionChunk {
+    pub ledger_info: LedgerInfoWithSignatures,
+    pub txn_list_with_proof: TransactionListWithProof,
+}
+
+/// Describes where to find a chunk of a transaction backup.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionChunkInfo {
+    pub first_version: Version,
+    pub last_version: Version,
+    pub file_handle: FileHandle,
+}
+
+/// Lists the chunks of a transaction backup, ordered by version.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionBackupManifest {
+    pub chunks: Vec<TransactionChunkInfo>,
+}
+
+impl TransactionBackupManifest {
+    /// Returns the chunk that contains `version`, if any.
+    pub fn find_chunk(&self, version: Version) -> Option<&TransactionChunkInfo> {
+        let idx = self
+            .chunks
+            .binary_search_by(|chunk| {
+                if chunk.last_version < version {
+                    std::cmp::Ordering::Less
+                } else if chunk.first_version > version {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()?;
+        Some(&self.chunks[idx])
+    }
+}
+
 pub async fn backup_account_state(
     client: &StorageReadServiceClient,
     version: Version,
@@ -83,3 +134,108 @@ async fn get_proof_and_write(
         .await?;
     Ok(file)
 }
+
+/// Backs up the transactions from `start_version` up to the latest ledger info in chunks of at
+/// most `max_chunk_txns` transactions that never cross an epoch boundary. Returns the handle of
+/// the manifest listing the chunks.
+pub async fn backup_transactions(
+    client: &StorageReadServiceClient,
+    start_version: Version,
+    adapter: &impl Adapter,
+    max_chunk_txns: u64,
+) -> Result<FileHandle> {
+    ensure!(max_chunk_txns > 0, "max_chunk_txns should be positive.");
+    let (_, latest_li, _, _) = client.update_to_latest_ledger(0, vec![]).await?;
+    let latest_epoch = latest_li.ledger_info().epoch();
+
+    // The ledger infos ending each epoch before the latest one, followed by the latest one.
+    let mut target_lis = vec![];
+    while (target_lis.len() as u64) < latest_epoch {
+        let proof = client
+            .get_epoch_change_ledger_infos(target_lis.len() as u64, latest_epoch)
+            .await?;
+        ensure!(
+            !proof.ledger_info_with_sigs.is_empty(),
+            "Missing epoch change ledger info for epoch {}.",
+            target_lis.len(),
+        );
+        target_lis.extend(proof.ledger_info_with_sigs);
+    }
+    target_lis.push(latest_li);
+
+    let mut manifest = TransactionBackupManifest::default();
+    let mut next_version = start_version;
+    for li in target_lis {
+        let li_version = li.ledger_info().version();
+        while next_version <= li_version {
+            let limit = std::cmp::min(max_chunk_txns, li_version - next_version + 1);
+            println!(
+                "Backing up transactions {}..={} against ledger info at version {}",
+                next_version,
+                next_version + limit - 1,
+                li_version,
+            );
+            let txn_list_with_proof = client
+                .get_transactions(next_version, limit, li_version, false)
+                .await?;
+            let num_txns = txn_list_with_proof.len() as u64;
+            ensure!(
+                num_txns == limit,
+                "Expected {} transactions from version {}, got {}.",
+                limit,
+                next_version,
+                num_txns,
+            );
+            let chunk = TransactionChunk {
+                ledger_info: li.clone(),
+                txn_list_with_proof,
+            };
+            let file_handle = write_lcs(adapter, &chunk).await?;
+            manifest.chunks.push(TransactionChunkInfo {
+                first_version: next_version,
+                last_version: next_version + num_txns - 1,
+                file_handle,
+            });
+            next_version += num_txns;
+        }
+    }
+
+    write_lcs(adapter, &manifest).await
+}
+
+/// Reads the manifest of a transaction backup written by [`backup_transactions`].
+///
+/// [`backup_transactions`]: fn.backup_transactions.html
+#[allow(clippy::ptr_arg)]
+pub async fn read_transaction_backup_manifest<A: Adapter>(
+    file_handle: &FileHandle,
+) -> Result<TransactionBackupManifest> {
+    read_lcs::<A, _>(file_handle).await
+}
+
+/// Reads a chunk of a transaction backup written by [`backup_transactions`].
+///
+/// [`backup_transactions`]: fn.backup_transactions.html
+#[allow(clippy::ptr_arg)]
+pub async fn read_transaction_chunk<A: Adapter>(
+    file_handle: &FileHandle,
+) -> Result<TransactionChunk> {
+    read_lcs::<A, _>(file_handle).await
+}
+
+async fn write_lcs<T: Serialize>(adapter: &impl Adapter, value: &T) -> Result<FileHandle> {
+    let bytes = lcs::to_bytes(value)?;
+    adapter
+        .write_new_file(stream::once(async move { bytes }))
+        .await
+}
+
+#[allow(clippy::ptr_arg)]
+async fn read_lcs<A: Adapter, T: DeserializeOwned>(file_handle: &FileHandle) -> Result<T> {
+    let mut content = A::read_file_content(file_handle);
+    let mut bytes = vec![];
+    while let Some(chunk) = content.next().await {
+        bytes.extend(chunk?);
+    }
+    Ok(lcs::from_bytes(&bytes)?)
+}
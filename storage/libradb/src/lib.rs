@@ -61,8 +61,8 @@ use libra_types::{
         SparseMerkleRangeProof, TransactionListProof, TransactionProof,
     },
     transaction::{
-        TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
-        Version,
+        TransactionInfo, TransactionListWithProof, TransactionOutputListWithProof,
+        TransactionToCommit, TransactionWithProof, Version,
     },
 };
 use once_cell::sync::Lazy;
@@ -160,6 +160,7 @@ impl LibraDB {
                 ColumnFamilyOptions::default(),
             ),
             (TRANSACTION_INFO_CF_NAME, ColumnFamilyOptions::default()),
+            (WRITE_SET_CF_NAME, ColumnFamilyOptions::default()),
         ]
         .iter()
         .cloned()
@@ -455,8 +456,13 @@ impl LibraDB {
         // Transaction updates. Gather transaction hashes.
         zip_eq(first_version..=last_version, txns_to_commit)
             .map(|(ver, txn_to_commit)| {
+                self.transaction_store.put_transaction(
+                    ver,
+                    txn_to_commit.transaction(),
+                    &mut cs,
+                )?;
                 self.transaction_store
-                    .put_transaction(ver, txn_to_commit.transaction(), &mut cs)
+                    .put_write_set(ver, txn_to_commit.write_set(), &mut cs)
             })
             .collect::<Result<()>>()?;
 
@@ -765,6 +771,34 @@ impl LibraDB {
         ))
     }
 
+    /// Gets a batch of transactions along with their outputs, for the purpose of synchronizing
+    /// state to another node that applies the outputs instead of executing the transactions.
+    /// Outputs are pruned along with the rest of history, so they may not be available for old
+    /// transactions.
+    ///
+    /// This is used by the State Synchronizer module internally.
+    pub fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        let txn_list_with_proof = self.get_transactions(
+            start_version,
+            limit,
+            ledger_version,
+            true, /* fetch_events */
+        )?;
+        let write_sets = (start_version..start_version + txn_list_with_proof.len() as u64)
+            .map(|version| Ok(self.transaction_store.get_write_set(version)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(TransactionOutputListWithProof::new(
+            txn_list_with_proof,
+            write_sets,
+        ))
+    }
+
     // ================================== Backup APIs ===================================
     /// Gets an iterator which can yield all accounts in the state tree.
     pub fn get_account_iter(
@@ -345,6 +345,17 @@ fn verify_committed_transactions(
             .verify(ledger_info, Some(cur_ver))
             .unwrap();
 
+        let txn_output_list_with_proof = db
+            .get_transaction_outputs(cur_ver, 1, ledger_version)
+            .unwrap();
+        txn_output_list_with_proof
+            .verify(ledger_info, Some(cur_ver))
+            .unwrap();
+        assert_eq!(
+            txn_output_list_with_proof.write_sets,
+            vec![txn_to_commit.write_set().clone()]
+        );
+
         // Fetch and verify account states.
         for (addr, expected_blob) in txn_to_commit.account_states() {
             let account_state_with_proof = db
@@ -417,6 +428,7 @@ fn test_too_many_requested() {
         )
         .is_err());
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
+    assert!(db.get_transaction_outputs(0, 1001 /* limit */, 0).is_err());
     assert!(db
         .get_events_by_query_path(
             &AccessPath::new_for_sent_event(AccountAddress::random()),
@@ -7,6 +7,7 @@
 use crate::{
     schema::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        write_set::WriteSetSchema,
     },
     OP_COUNTER,
 };
@@ -185,9 +186,12 @@ impl Worker {
                         least_readable_version as usize,
                     );
 
-                    // Try to purge the log.
+                    // Try to purge the log and the write sets.
                     if let Err(e) = self.maybe_purge_index() {
-                        crit!("Failed purging state state node index, ignored. Err: {}", e);
+                        crit!(
+                            "Failed purging state node index and write sets, ignored. Err: {}",
+                            e
+                        );
                     }
                 }
                 Err(e) => {
@@ -241,6 +245,9 @@ impl Worker {
     /// Purge the stale node index so that after restart not too much already pruned stuff is dealt
     /// with again (although no harm is done deleting those then non-existent things.)
     ///
+    /// The write sets of the transactions before the least readable version are purged along with
+    /// it, so that they are kept only as long as the rest of the history.
+    ///
     /// We issue (range) deletes on the index only periodically instead of after every pruning batch
     /// to avoid sending too many deletions to the DB, which takes disk space and slows it down.
     fn maybe_purge_index(&mut self) -> Result<()> {
@@ -260,6 +267,10 @@ impl Worker {
                     &self.index_min_nonpurged_version,
                     &new_min_non_purged_version, // end is exclusive
                 )?;
+                self.db.range_delete::<WriteSetSchema, Version>(
+                    &self.index_min_nonpurged_version,
+                    &least_readable_version, // end is exclusive
+                )?;
                 self.index_min_nonpurged_version = new_min_non_purged_version;
                 self.index_purged_at = now;
             }
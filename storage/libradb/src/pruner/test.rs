@@ -8,6 +8,7 @@ use libra_temppath::TempPath;
 use libra_types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     account_state_blob::AccountStateBlob,
+    write_set::WriteSet,
 };
 use std::collections::HashMap;
 
@@ -161,3 +162,34 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+#[test]
+fn test_purge_write_sets() {
+    let tmp_dir = TempPath::new();
+    let libradb = LibraDB::new(&tmp_dir);
+    let db = Arc::clone(&libradb.db);
+    let transaction_store = &libradb.transaction_store;
+
+    let versions = [0, 59_999, 60_000];
+    let mut cs = ChangeSet::new();
+    for version in versions.iter() {
+        transaction_store
+            .put_write_set(*version, &WriteSet::default(), &mut cs)
+            .unwrap();
+    }
+    db.write_schemas(cs.batch).unwrap();
+
+    let (_command_sender, command_receiver) = channel();
+    let mut worker = Worker::new(
+        Arc::clone(&db),
+        command_receiver,
+        Arc::new(AtomicU64::new(60_000)), /* progress */
+    );
+    worker.index_purged_at = Instant::now() - Duration::from_secs(61);
+    worker.maybe_purge_index().unwrap();
+
+    // write sets of the versions that are no longer readable are gone
+    assert!(transaction_store.get_write_set(0).is_err());
+    assert!(transaction_store.get_write_set(59_999).is_err());
+    assert!(transaction_store.get_write_set(60_000).is_ok());
+}
@@ -5,13 +5,15 @@
 
 use crate::schema::transaction::TransactionSchema;
 use crate::{
-    change_set::ChangeSet, errors::LibraDbError,
-    schema::transaction_by_account::TransactionByAccountSchema,
+    change_set::ChangeSet,
+    errors::LibraDbError,
+    schema::{transaction_by_account::TransactionByAccountSchema, write_set::WriteSetSchema},
 };
 use anyhow::Result;
 use libra_types::{
     account_address::AccountAddress,
     transaction::{Transaction, Version},
+    write_set::WriteSet,
 };
 use schemadb::DB;
 use std::sync::Arc;
@@ -68,6 +70,23 @@ impl TransactionStore {
 
         Ok(())
     }
+
+    /// Get the write set produced by the transaction at `version`
+    pub fn get_write_set(&self, version: Version) -> Result<WriteSet> {
+        self.db
+            .get::<WriteSetSchema>(&version)?
+            .ok_or_else(|| LibraDbError::NotFound(format!("WriteSet {}", version)).into())
+    }
+
+    /// Save the write set produced by the transaction at `version`
+    pub fn put_write_set(
+        &self,
+        version: Version,
+        write_set: &WriteSet,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        cs.batch.put::<WriteSetSchema>(&version, write_set)
+    }
 }
 
 #[cfg(test)]
@@ -58,4 +58,26 @@ proptest! {
 
         prop_assert!(store.get_transaction(ledger_version + 1).is_err());
     }
+
+    #[test]
+    fn test_put_get_write_set(write_sets in vec(any::<WriteSet>(), 1..10)) {
+        let tmp_dir = TempPath::new();
+        let db = LibraDB::new(&tmp_dir);
+        let store = &db.transaction_store;
+
+        prop_assert!(store.get_write_set(0).is_err());
+
+        let mut cs = ChangeSet::new();
+        for (ver, write_set) in write_sets.iter().enumerate() {
+            store
+                .put_write_set(ver as Version, write_set, &mut cs)
+                .unwrap();
+        }
+        store.db.write_schemas(cs.batch).unwrap();
+
+        for (ver, write_set) in write_sets.iter().enumerate() {
+            prop_assert_eq!(&store.get_write_set(ver as Version).unwrap(), write_set);
+        }
+        prop_assert!(store.get_write_set(write_sets.len() as Version).is_err());
+    }
 }
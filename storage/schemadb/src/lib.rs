@@ -24,7 +24,7 @@ use rocksdb::{
     rocksdb_options::ColumnFamilyDescriptor, CFHandle, DBOptions, Writable, WriteOptions,
 };
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     iter::Iterator,
     marker::PhantomData,
     path::Path,
@@ -175,6 +175,17 @@ fn db_exists(path: &Path) -> bool {
     rocksdb_current_file.is_file()
 }
 
+/// The names of the column families of the existing db at `path`.
+fn list_cfs(path: &Path) -> Result<HashSet<String>> {
+    let cf_names = rocksdb::DB::list_column_families(
+        &DBOptions::new(),
+        path.to_str()
+            .ok_or_else(|| format_err!("Path {:?} can not be converted to string.", path))?,
+    )
+    .map_err(convert_rocksdb_err)?;
+    Ok(cf_names.into_iter().collect())
+}
+
 /// All the RocksDB methods return `std::result::Result<T, String>`. Since our methods return
 /// `anyhow::Result<T>`, manual conversion is needed.
 fn convert_rocksdb_err(msg: String) -> anyhow::Error {
@@ -190,7 +201,7 @@ pub struct DB {
 
 impl DB {
     /// Create db with all the column families provided if it doesn't exist at `path`; Otherwise,
-    /// try to open it with all the column families.
+    /// try to open it with all the column families, creating the ones it doesn't have yet.
     pub fn open<P: AsRef<Path>>(path: P, mut cf_opts_map: ColumnFamilyOptionsMap) -> Result<Self> {
         let mut db_opts = DBOptions::new();
 
@@ -198,9 +209,18 @@ impl DB {
         // families are updated at non-uniform frequencies.
         db_opts.set_max_total_wal_size(1 << 30);
 
-        // If db exists, just open it with all cfs.
+        // If db exists, open it with all the cfs it has and create the new ones.
         if db_exists(path.as_ref()) {
-            return DB::open_cf(db_opts, &path, cf_opts_map.into_iter().collect());
+            let existing_cfs = list_cfs(path.as_ref())?;
+            let (existing, missing): (Vec<_>, Vec<_>) = cf_opts_map
+                .into_iter()
+                .partition(|(cf_name, _)| existing_cfs.contains(*cf_name));
+            let mut db = DB::open_cf(db_opts, &path, existing)?;
+            missing
+                .into_iter()
+                .map(|cfd| db.create_cf(cfd))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(db);
         }
 
         // If db doesn't exist, create a db first with all column families.
@@ -220,6 +240,32 @@ impl DB {
         Ok(db)
     }
 
+    /// Open an existing db at `path` with the given column families without taking the write
+    /// lock, so that it can be inspected while another process owns it. All writes fail.
+    /// The column families the db doesn't have (e.g., written by an older version) are skipped.
+    pub fn open_readonly<P: AsRef<Path>>(path: P, cf_names: Vec<ColumnFamilyName>) -> Result<Self> {
+        if !db_exists(path.as_ref()) {
+            return Err(format_err!("No db found at {:?}", path.as_ref()));
+        }
+        let existing_cfs = list_cfs(path.as_ref())?;
+        let cfds: Vec<ColumnFamilyDescriptor> = cf_names
+            .into_iter()
+            .filter(|cf_name| existing_cfs.contains(*cf_name))
+            .map(|cf_name| (cf_name, ColumnFamilyOptions::default()).into())
+            .collect();
+        let inner = rocksdb::DB::open_cf_for_read_only(
+            DBOptions::new(),
+            path.as_ref().to_str().ok_or_else(|| {
+                format_err!("Path {:?} can not be converted to string.", path.as_ref())
+            })?,
+            cfds,
+            /* error_if_log_file_exist = */ false,
+        )
+        .map_err(convert_rocksdb_err)?;
+
+        Ok(DB { inner })
+    }
+
     fn open_cf<'a, P, T>(opts: DBOptions, path: P, cfds: Vec<T>) -> Result<DB>
     where
         P: AsRef<Path>,
@@ -330,6 +376,11 @@ impl DB {
         Ok(())
     }
 
+    /// Whether the db has the column family, see [`open_readonly`](DB::open_readonly).
+    pub fn has_cf(&self, cf_name: ColumnFamilyName) -> bool {
+        self.inner.cf_handle(cf_name).is_some()
+    }
+
     fn get_cf_handle(&self, cf_name: &str) -> Result<&CFHandle> {
         self.inner.cf_handle(cf_name).ok_or_else(|| {
             format_err!(
@@ -301,6 +301,31 @@ fn test_reopen() {
     }
 }
 
+#[test]
+fn test_open_readonly() {
+    let tmpdir = libra_temppath::TempPath::new();
+    let cf_names = vec![
+        DEFAULT_CF_NAME,
+        TestSchema1::COLUMN_FAMILY_NAME,
+        TestSchema2::COLUMN_FAMILY_NAME,
+    ];
+    assert!(DB::open_readonly(&tmpdir.path(), cf_names.clone()).is_err());
+
+    let db = open_db(&tmpdir);
+    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+    db.flush_all(/* sync = */ true).unwrap();
+
+    // The read-only instance can be opened while the db is still open for writes.
+    let readonly_db = DB::open_readonly(&tmpdir.path(), cf_names).unwrap();
+    assert_eq!(
+        readonly_db.get::<TestSchema1>(&TestField(0)).unwrap(),
+        Some(TestField(0)),
+    );
+    assert!(readonly_db
+        .put::<TestSchema1>(&TestField(1), &TestField(1))
+        .is_err());
+}
+
 #[test]
 fn test_report_size() {
     let db = TestDB::new();
@@ -23,7 +23,9 @@ use libra_types::{
         RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
     proof::{AccumulatorConsistencyProof, SparseMerkleProof, SparseMerkleRangeProof},
-    transaction::{TransactionListWithProof, TransactionToCommit, Version},
+    transaction::{
+        TransactionListWithProof, TransactionOutputListWithProof, TransactionToCommit, Version,
+    },
 };
 use std::convert::TryFrom;
 use std::net::SocketAddr;
@@ -36,8 +38,8 @@ use storage_proto::{
     GetAccountStateRangeProofResponse, GetAccountStateWithProofByVersionRequest,
     GetAccountStateWithProofByVersionResponse, GetEpochChangeLedgerInfosRequest,
     GetLatestAccountStateRequest, GetLatestAccountStateResponse, GetLatestStateRootResponse,
-    GetStartupInfoResponse, GetTransactionsRequest, GetTransactionsResponse,
-    SaveTransactionsRequest, StartupInfo,
+    GetStartupInfoResponse, GetTransactionOutputsRequest, GetTransactionOutputsResponse,
+    GetTransactionsRequest, GetTransactionsResponse, SaveTransactionsRequest, StartupInfo,
 };
 
 /// This provides storage read interfaces backed by real storage service.
@@ -123,6 +125,24 @@ impl StorageRead for StorageReadServiceClient {
         Ok(rust_resp.txn_list_with_proof)
     }
 
+    async fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        let req: storage_proto::proto::storage::GetTransactionOutputsRequest =
+            GetTransactionOutputsRequest::new(start_version, batch_size, ledger_version).into();
+        let resp = self
+            .client()
+            .await?
+            .get_transaction_outputs(req)
+            .await?
+            .into_inner();
+        let rust_resp = GetTransactionOutputsResponse::try_from(resp)?;
+        Ok(rust_resp.txn_output_list_with_proof)
+    }
+
     async fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
         let req = GetLatestStateRootRequest::default();
         let resp = self
@@ -314,6 +334,17 @@ pub trait StorageRead: Send + Sync {
         fetch_events: bool,
     ) -> Result<TransactionListWithProof>;
 
+    /// See [`LibraDB::get_transaction_outputs`].
+    ///
+    /// [`LibraDB::get_transaction_outputs`]:
+    /// ../libradb/struct.LibraDB.html#method.get_transaction_outputs
+    async fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof>;
+
     /// See [`LibraDB::get_latest_state_root`].
     ///
     /// [`LibraDB::get_latest_state_root`]:
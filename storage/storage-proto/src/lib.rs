@@ -34,7 +34,9 @@ use libra_types::{
     account_state_blob::AccountStateBlob,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorSet},
     proof::{SparseMerkleProof, SparseMerkleRangeProof},
-    transaction::{TransactionListWithProof, TransactionToCommit, Version},
+    transaction::{
+        TransactionListWithProof, TransactionOutputListWithProof, TransactionToCommit, Version,
+    },
 };
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest::prelude::*;
@@ -487,6 +489,87 @@ impl From<GetTransactionsResponse> for crate::proto::storage::GetTransactionsRes
     }
 }
 
+/// Helper to construct and parse [`proto::storage::GetTransactionOutputsRequest`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct GetTransactionOutputsRequest {
+    pub start_version: Version,
+    pub batch_size: u64,
+    pub ledger_version: Version,
+}
+
+impl GetTransactionOutputsRequest {
+    /// Constructor.
+    pub fn new(start_version: Version, batch_size: u64, ledger_version: Version) -> Self {
+        GetTransactionOutputsRequest {
+            start_version,
+            batch_size,
+            ledger_version,
+        }
+    }
+}
+
+impl TryFrom<crate::proto::storage::GetTransactionOutputsRequest> for GetTransactionOutputsRequest {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::GetTransactionOutputsRequest) -> Result<Self> {
+        Ok(GetTransactionOutputsRequest {
+            start_version: proto.start_version,
+            batch_size: proto.batch_size,
+            ledger_version: proto.ledger_version,
+        })
+    }
+}
+
+impl From<GetTransactionOutputsRequest> for crate::proto::storage::GetTransactionOutputsRequest {
+    fn from(request: GetTransactionOutputsRequest) -> Self {
+        Self {
+            start_version: request.start_version,
+            batch_size: request.batch_size,
+            ledger_version: request.ledger_version,
+        }
+    }
+}
+
+/// Helper to construct and parse [`proto::storage::GetTransactionOutputsResponse`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct GetTransactionOutputsResponse {
+    pub txn_output_list_with_proof: TransactionOutputListWithProof,
+}
+
+impl GetTransactionOutputsResponse {
+    /// Constructor.
+    pub fn new(txn_output_list_with_proof: TransactionOutputListWithProof) -> Self {
+        GetTransactionOutputsResponse {
+            txn_output_list_with_proof,
+        }
+    }
+}
+
+impl TryFrom<crate::proto::storage::GetTransactionOutputsResponse>
+    for GetTransactionOutputsResponse
+{
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::GetTransactionOutputsResponse) -> Result<Self> {
+        Ok(GetTransactionOutputsResponse {
+            txn_output_list_with_proof: proto
+                .txn_output_list_with_proof
+                .ok_or_else(|| format_err!("Missing txn_output_list_with_proof"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<GetTransactionOutputsResponse> for crate::proto::storage::GetTransactionOutputsResponse {
+    fn from(response: GetTransactionOutputsResponse) -> Self {
+        Self {
+            txn_output_list_with_proof: Some(response.txn_output_list_with_proof.into()),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct TreeState {
@@ -41,6 +41,14 @@ test_conversion!(
 test_conversion!(test_save_transactions_request, SaveTransactionsRequest);
 test_conversion!(test_get_transactions_request, GetTransactionsRequest);
 test_conversion!(test_get_transactions_response, GetTransactionsResponse);
+test_conversion!(
+    test_get_transaction_outputs_request,
+    GetTransactionOutputsRequest,
+);
+test_conversion!(
+    test_get_transaction_outputs_response,
+    GetTransactionOutputsResponse,
+);
 test_conversion!(test_tree_state, TreeState);
 test_conversion!(test_startup_info, StartupInfo);
 test_conversion!(test_get_startup_info_response, GetStartupInfoResponse);
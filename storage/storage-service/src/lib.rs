@@ -29,8 +29,8 @@ use storage_proto::proto::storage::{
     GetAccountStateWithProofByVersionResponse, GetEpochChangeLedgerInfosRequest,
     GetLatestAccountStateRequest, GetLatestAccountStateResponse, GetLatestStateRootRequest,
     GetLatestStateRootResponse, GetStartupInfoRequest, GetStartupInfoResponse,
-    GetTransactionsRequest, GetTransactionsResponse, SaveTransactionsRequest,
-    SaveTransactionsResponse,
+    GetTransactionOutputsRequest, GetTransactionOutputsResponse, GetTransactionsRequest,
+    GetTransactionsResponse, SaveTransactionsRequest, SaveTransactionsResponse,
 };
 use tokio::runtime::Runtime;
 
@@ -109,6 +109,24 @@ impl StorageService {
         Ok(rust_resp.into())
     }
 
+    fn get_transaction_outputs_inner(
+        &self,
+        req: GetTransactionOutputsRequest,
+    ) -> Result<GetTransactionOutputsResponse> {
+        let rust_req = storage_proto::GetTransactionOutputsRequest::try_from(req)?;
+
+        let txn_output_list_with_proof = self.db.get_transaction_outputs(
+            rust_req.start_version,
+            rust_req.batch_size,
+            rust_req.ledger_version,
+        )?;
+
+        let rust_resp =
+            storage_proto::GetTransactionOutputsResponse::new(txn_output_list_with_proof);
+
+        Ok(rust_resp.into())
+    }
+
     fn get_latest_state_root_inner(
         &self,
         _req: GetLatestStateRootRequest,
@@ -229,6 +247,18 @@ impl Storage for StorageService {
         Ok(tonic::Response::new(resp))
     }
 
+    async fn get_transaction_outputs(
+        &self,
+        request: tonic::Request<GetTransactionOutputsRequest>,
+    ) -> Result<tonic::Response<GetTransactionOutputsResponse>, tonic::Status> {
+        debug!("[GRPC] Storage::get_transaction_outputs");
+        let req = request.into_inner();
+        let resp = self
+            .get_transaction_outputs_inner(req)
+            .map_err(|e| tonic::Status::new(tonic::Code::InvalidArgument, e.to_string()))?;
+        Ok(tonic::Response::new(resp))
+    }
+
     async fn get_latest_state_root(
         &self,
         request: tonic::Request<GetLatestStateRootRequest>,
@@ -79,6 +79,15 @@ impl StorageRead for MockStorageReadClient {
         unimplemented!()
     }
 
+    async fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+        _ledger_version: Version,
+    ) -> Result<libra_types::transaction::TransactionOutputListWithProof> {
+        unimplemented!()
+    }
+
     async fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
         unimplemented!()
     }
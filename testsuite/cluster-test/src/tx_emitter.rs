@@ -15,7 +15,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use admission_control_proto::{AdmissionControlStatus, SubmitTransactionResponse};
+use admission_control_proto::SubmitTransactionResponse;
 use anyhow::{bail, format_err, Result};
 use itertools::zip;
 use libra_crypto::{
@@ -581,7 +581,7 @@ struct AccountData {
 
 fn is_accepted(resp: &SubmitTransactionResponse) -> bool {
     if let Some(ref status) = resp.ac_status {
-        return *status == AdmissionControlStatus::Accepted;
+        return status.is_accepted();
     }
     false
 }
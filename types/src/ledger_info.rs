@@ -5,6 +5,7 @@ use crate::block_info::{BlockInfo, Round};
 use crate::{
     account_address::AccountAddress,
     crypto_proxies::ValidatorSet,
+    multi_signature::MultiSignature,
     transaction::Version,
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
@@ -226,6 +227,58 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
     }
 }
 
+impl<Sig: AggregatableSignature> LedgerInfoWithSignatures<Sig> {
+    /// Aggregates the signatures into a single multi-signature, the compact form of the
+    /// certificate for the signature schemes that support aggregation.
+    pub fn aggregate(
+        &self,
+        validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
+    ) -> ::std::result::Result<LedgerInfoWithMultiSignature<Sig>, VerifyError> {
+        Ok(LedgerInfoWithMultiSignature::new(
+            self.ledger_info.clone(),
+            validator.aggregate_signatures(self.signatures())?,
+        ))
+    }
+}
+
+/// A LedgerInfo certified by the aggregated signature of the validators: its size and its
+/// verification cost do not depend on the number of signers.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LedgerInfoWithMultiSignature<Sig> {
+    ledger_info: LedgerInfo,
+    signature: MultiSignature<Sig>,
+}
+
+impl<Sig> Display for LedgerInfoWithMultiSignature<Sig> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.ledger_info)
+    }
+}
+
+impl<Sig: AggregatableSignature> LedgerInfoWithMultiSignature<Sig> {
+    pub fn new(ledger_info: LedgerInfo, signature: MultiSignature<Sig>) -> Self {
+        LedgerInfoWithMultiSignature {
+            ledger_info,
+            signature,
+        }
+    }
+
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        &self.ledger_info
+    }
+
+    pub fn signature(&self) -> &MultiSignature<Sig> {
+        &self.signature
+    }
+
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
+    ) -> ::std::result::Result<(), VerifyError> {
+        validator.verify_multi_signature(self.ledger_info().hash(), &self.signature)
+    }
+}
+
 impl<Sig: Signature> TryFrom<crate::proto::types::LedgerInfoWithSignatures>
     for LedgerInfoWithSignatures<Sig>
 {
@@ -288,7 +341,10 @@ mod tests {
     use crate::block_info::BlockInfo;
     use crate::ledger_info::{LedgerInfo, LedgerInfoWithSignatures};
     use crate::validator_signer::ValidatorSigner;
-    use libra_crypto::{ed25519::*, HashValue};
+    use crate::validator_verifier::{ValidatorInfo, ValidatorVerifier, VerifyError};
+    use libra_crypto::{
+        bls12381::*, ed25519::*, hash::CryptoHash, traits::AggregatableSignature, HashValue,
+    };
     use std::collections::BTreeMap;
 
     #[test]
@@ -335,4 +391,60 @@ mod tests {
             ledger_info_with_signatures_reversed_bytes
         );
     }
+
+    #[test]
+    fn test_aggregate_signatures() {
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        let validator_signers: Vec<ValidatorSigner<BLS12381PrivateKey>> =
+            (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+        let validator_verifier = ValidatorVerifier::new_with_proofs_of_possession(
+            validator_signers
+                .iter()
+                .map(|signer| (signer.author(), ValidatorInfo::new(signer.public_key(), 1)))
+                .collect(),
+            &validator_signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        BLS12381Signature::prove_possession(signer.private_key()),
+                    )
+                })
+                .collect(),
+        )
+        .unwrap();
+
+        let mut ledger_info_with_signatures =
+            LedgerInfoWithSignatures::new(ledger_info.clone(), BTreeMap::new());
+        for validator in validator_signers.iter().take(3) {
+            ledger_info_with_signatures.add_signature(
+                validator.author(),
+                validator.sign_message(ledger_info.hash()).unwrap(),
+            );
+        }
+        let ledger_info_with_multi_signature = ledger_info_with_signatures
+            .aggregate(&validator_verifier)
+            .unwrap();
+        assert_eq!(
+            ledger_info_with_multi_signature.signature().num_signers(),
+            3
+        );
+        assert_eq!(
+            ledger_info_with_multi_signature.verify(&validator_verifier),
+            Ok(())
+        );
+
+        // Two signers out of four do not make a quorum.
+        ledger_info_with_signatures.remove_signature(validator_signers[0].author());
+        assert_eq!(
+            ledger_info_with_signatures
+                .aggregate(&validator_verifier)
+                .unwrap()
+                .verify(&validator_verifier),
+            Err(VerifyError::TooLittleVotingPower {
+                voting_power: 2,
+                quorum_voting_power: 3,
+            })
+        );
+    }
 }
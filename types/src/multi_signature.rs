@@ -0,0 +1,64 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// The signature of a message by a subset of the validators of an epoch, aggregated into a single
+/// signature. The signers are identified by a bitmap over the validators ordered by account
+/// address, so that the size of a certificate does not grow with the number of signers.
+/// Votes, quorum certificates and timeout certificates do not use it yet: they keep the
+/// individual Ed25519 signatures until the validator set publishes BLS keys with their proofs of
+/// possession.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MultiSignature<Sig> {
+    /// Bit `i` (in big-endian bit order) is set when the `i`-th validator signed.
+    signers: Vec<u8>,
+    /// The aggregated signature, only absent when nobody signed.
+    signature: Option<Sig>,
+}
+
+impl<Sig> MultiSignature<Sig> {
+    pub fn new(signers: Vec<u8>, signature: Option<Sig>) -> Self {
+        Self { signers, signature }
+    }
+
+    /// A multi-signature without any signer, as carried by implicitly agreed certificates.
+    pub fn empty() -> Self {
+        Self::new(vec![], None)
+    }
+
+    pub fn signers(&self) -> &[u8] {
+        &self.signers
+    }
+
+    pub fn signature(&self) -> Option<&Sig> {
+        self.signature.as_ref()
+    }
+
+    /// Returns whether the validator at `index` is a signer.
+    pub fn is_signer(&self, index: usize) -> bool {
+        self.signers
+            .get(index / 8)
+            .map_or(false, |byte| byte & (0x80 >> (index % 8)) != 0)
+    }
+
+    pub fn num_signers(&self) -> usize {
+        self.signers
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_signers() == 0
+    }
+}
+
+/// Builds the bitmap of the given validator indexes, for a set of `num_validators` validators.
+pub fn signers_bitmap(indexes: impl Iterator<Item = usize>, num_validators: usize) -> Vec<u8> {
+    let mut signers = vec![0u8; (num_validators + 7) / 8];
+    for index in indexes {
+        signers[index / 8] |= 0x80 >> (index % 8);
+    }
+    signers
+}
@@ -20,8 +20,8 @@ use crate::{
     proof::{AccumulatorConsistencyProof, TransactionListProof},
     transaction::{
         ChangeSet, Module, RawTransaction, Script, SignatureCheckedTransaction, SignedTransaction,
-        Transaction, TransactionArgument, TransactionListWithProof, TransactionPayload,
-        TransactionStatus, TransactionToCommit, Version,
+        Transaction, TransactionArgument, TransactionListWithProof, TransactionOutputListWithProof,
+        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
     vm_error::{StatusCode, VMStatus},
     write_set::{WriteOp, WriteSet, WriteSetMut},
@@ -804,6 +804,8 @@ pub struct TransactionToCommitGen {
     /// N.B. the transaction sender and event owners must be updated to reflect information such as
     /// sequence numbers so that test data generated through this is more realistic and logical.
     account_state_gens: Vec<(Index, AccountStateBlobGen)>,
+    /// Write set.
+    write_set: WriteSet,
     /// Gas used.
     gas_used: u64,
     /// Transaction status
@@ -837,6 +839,7 @@ impl TransactionToCommitGen {
         TransactionToCommit::new(
             Transaction::UserTransaction(transaction),
             account_states,
+            self.write_set,
             events,
             self.gas_used,
             self.major_status,
@@ -863,11 +866,19 @@ impl Arbitrary for TransactionToCommitGen {
                 0..=2,
             ),
             vec((any::<Index>(), any::<AccountStateBlobGen>()), 0..=1),
+            any::<WriteSet>(),
             any::<u64>(),
             any::<StatusCode>(),
         )
             .prop_map(
-                |(sender, event_emitters, mut touched_accounts, gas_used, major_status)| {
+                |(
+                    sender,
+                    event_emitters,
+                    mut touched_accounts,
+                    write_set,
+                    gas_used,
+                    major_status,
+                )| {
                     // To reflect change of account/event sequence numbers, txn sender account and
                     // event emitter accounts must be updated.
                     let (sender_index, sender_blob_gen, txn_gen) = sender;
@@ -883,6 +894,7 @@ impl Arbitrary for TransactionToCommitGen {
                         transaction_gen: (sender_index, txn_gen),
                         event_gens,
                         account_state_gens: touched_accounts,
+                        write_set,
                         gas_used,
                         major_status,
                     }
@@ -941,6 +953,26 @@ impl Arbitrary for TransactionListWithProof {
     type Strategy = BoxedStrategy<Self>;
 }
 
+impl Arbitrary for TransactionOutputListWithProof {
+    type Parameters = ();
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_transaction_list_with_proof()
+            .prop_flat_map(|txn_list_with_proof| {
+                let num_txns = txn_list_with_proof.len();
+                (
+                    Just(txn_list_with_proof),
+                    vec(any::<WriteSet>(), num_txns..=num_txns),
+                )
+            })
+            .prop_map(|(txn_list_with_proof, write_sets)| {
+                TransactionOutputListWithProof::new(txn_list_with_proof, write_sets)
+            })
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
 impl Arbitrary for BlockMetadata {
     type Parameters = SizeRange;
     fn arbitrary_with(num_validators_range: Self::Parameters) -> Self::Strategy {
@@ -770,6 +770,7 @@ impl CryptoHash for TransactionInfo {
 pub struct TransactionToCommit {
     transaction: Transaction,
     account_states: HashMap<AccountAddress, AccountStateBlob>,
+    write_set: WriteSet,
     events: Vec<ContractEvent>,
     gas_used: u64,
     major_status: StatusCode,
@@ -779,6 +780,7 @@ impl TransactionToCommit {
     pub fn new(
         transaction: Transaction,
         account_states: HashMap<AccountAddress, AccountStateBlob>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         gas_used: u64,
         major_status: StatusCode,
@@ -786,6 +788,7 @@ impl TransactionToCommit {
         TransactionToCommit {
             transaction,
             account_states,
+            write_set,
             events,
             gas_used,
             major_status,
@@ -800,6 +803,10 @@ impl TransactionToCommit {
         &self.account_states
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -836,6 +843,10 @@ impl TryFrom<crate::proto::types::TransactionToCommit> for TransactionToCommit {
             account_states.len() == num_account_states,
             "account_states should have no duplication."
         );
+        let write_set = proto
+            .write_set
+            .ok_or_else(|| format_err!("Missing write_set"))?
+            .try_into()?;
         let events = proto
             .events
             .into_iter()
@@ -848,6 +859,7 @@ impl TryFrom<crate::proto::types::TransactionToCommit> for TransactionToCommit {
         Ok(TransactionToCommit {
             transaction,
             account_states,
+            write_set,
             events,
             gas_used,
             major_status,
@@ -870,6 +882,7 @@ impl From<TransactionToCommit> for crate::proto::types::TransactionToCommit {
             events: txn.events.into_iter().map(Into::into).collect(),
             gas_used: txn.gas_used,
             major_status: txn.major_status.into(),
+            write_set: Some(txn.write_set.into()),
         }
     }
 }
@@ -1046,6 +1059,113 @@ impl From<TransactionListWithProof> for crate::proto::types::TransactionListWith
     }
 }
 
+/// A list of consecutive transactions along with the outputs of executing them, so that the
+/// outputs can be applied to the state without executing the transactions. The events and the gas
+/// used / status of the transactions are respectively carried by `txn_list_with_proof` and the
+/// transaction infos in its proof, only the write sets are carried separately.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionOutputListWithProof {
+    pub txn_list_with_proof: TransactionListWithProof,
+    pub write_sets: Vec<WriteSet>,
+}
+
+impl TransactionOutputListWithProof {
+    /// Constructor.
+    pub fn new(txn_list_with_proof: TransactionListWithProof, write_sets: Vec<WriteSet>) -> Self {
+        Self {
+            txn_list_with_proof,
+            write_sets,
+        }
+    }
+
+    /// Creates an empty list.
+    pub fn new_empty() -> Self {
+        Self::new(TransactionListWithProof::new_empty(), vec![])
+    }
+
+    /// Verifies the transactions and their events with the proofs (see
+    /// `TransactionListWithProof::verify`) and ensures that every transaction comes with its
+    /// events and write set.
+    ///
+    /// Note that the write sets can't be verified on their own: they have to be applied to the
+    /// state first, after which the resulting state root hashes are checked against the ones
+    /// carried by the transaction infos.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        first_transaction_version: Option<Version>,
+    ) -> Result<()> {
+        ensure!(
+            self.txn_list_with_proof.events.is_some() || self.txn_list_with_proof.is_empty(),
+            "The events of the transactions are missing.",
+        );
+        ensure!(
+            self.write_sets.len() == self.txn_list_with_proof.len(),
+            "The number of write sets ({}) does not match the number of transactions ({}).",
+            self.write_sets.len(),
+            self.txn_list_with_proof.len(),
+        );
+        self.txn_list_with_proof
+            .verify(ledger_info, first_transaction_version)
+    }
+
+    /// Returns the outputs of the transactions, assuming the list has been verified.
+    pub fn outputs(&self) -> Vec<TransactionOutput> {
+        let events = self.txn_list_with_proof.events.clone().unwrap_or_default();
+        itertools::zip_eq(
+            itertools::zip_eq(self.write_sets.iter(), events),
+            self.txn_list_with_proof.proof.transaction_infos(),
+        )
+        .map(|((write_set, events), txn_info)| {
+            // Only the transactions that are kept make it to the ledger.
+            TransactionOutput::new(
+                write_set.clone(),
+                events,
+                txn_info.gas_used(),
+                TransactionStatus::Keep(VMStatus::new(txn_info.major_status())),
+            )
+        })
+        .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txn_list_with_proof.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.txn_list_with_proof.len()
+    }
+}
+
+impl TryFrom<crate::proto::types::TransactionOutputListWithProof>
+    for TransactionOutputListWithProof
+{
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::types::TransactionOutputListWithProof) -> Result<Self> {
+        let txn_list_with_proof = proto
+            .txn_list_with_proof
+            .ok_or_else(|| format_err!("Missing txn_list_with_proof"))?
+            .try_into()?;
+        let write_sets = proto
+            .write_sets
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::new(txn_list_with_proof, write_sets))
+    }
+}
+
+impl From<TransactionOutputListWithProof> for crate::proto::types::TransactionOutputListWithProof {
+    fn from(output_list: TransactionOutputListWithProof) -> Self {
+        Self {
+            txn_list_with_proof: Some(output_list.txn_list_with_proof.into()),
+            write_sets: output_list.write_sets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 /// `Transaction` will be the transaction type used internally in the libra node to represent the
 /// transaction to be processed and persisted.
 ///
@@ -39,4 +39,9 @@ proptest! {
     fn test_transaction_list_with_proof(txn_list in any::<TransactionListWithProof>()) {
         assert_protobuf_encode_decode::<crate::proto::types::TransactionListWithProof, TransactionListWithProof>(&txn_list);
     }
+
+    #[test]
+    fn test_transaction_output_list_with_proof(output_list in any::<TransactionOutputListWithProof>()) {
+        assert_protobuf_encode_decode::<crate::proto::types::TransactionOutputListWithProof, TransactionOutputListWithProof>(&output_list);
+    }
 }
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::AccountAddress;
+use crate::multi_signature::{signers_bitmap, MultiSignature};
 use crate::validator_set::ValidatorSet;
-use anyhow::{ensure, Result};
+use anyhow::{ensure, format_err, Result};
 use libra_crypto::*;
 use mirai_annotations::*;
 use std::collections::BTreeMap;
@@ -37,6 +38,12 @@ pub enum VerifyError {
     #[error("Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[error("The bitmap of signers does not match the validator set")]
+    /// The bitmap of a multi-signature does not have one bit per known author.
+    InvalidBitmap,
+    #[error("The public keys have not been registered with proofs of possession")]
+    /// Aggregating public keys without proofs of possession exposes to rogue key attacks.
+    MissingProofsOfPossession,
 }
 
 /// Helper struct to manage validator information for validation
@@ -75,6 +82,9 @@ pub struct ValidatorVerifier<PublicKey> {
     quorum_voting_power: u64,
     /// Total voting power of all validators (cached from address_to_validator_info)
     total_voting_power: u64,
+    /// Whether every public key came with a valid proof of possession of its private key, which
+    /// is required to aggregate them.
+    proofs_of_possession_verified: bool,
 }
 
 impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
@@ -96,6 +106,7 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
             address_to_validator_info,
             quorum_voting_power,
             total_voting_power,
+            proofs_of_possession_verified: false,
         }
     }
 
@@ -120,6 +131,7 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
             address_to_validator_info,
             quorum_voting_power,
             total_voting_power,
+            proofs_of_possession_verified: false,
         })
     }
 
@@ -276,6 +288,123 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     }
 }
 
+impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey>
+where
+    PublicKey::SignatureMaterial: AggregatableSignature,
+{
+    /// Initializes a validator verifier able to aggregate signatures, like `new`. Every public key
+    /// must come with a valid proof of possession of its private key, otherwise a validator could
+    /// register a rogue key cancelling out the keys of the others in the aggregated public key.
+    pub fn new_with_proofs_of_possession(
+        address_to_validator_info: BTreeMap<AccountAddress, ValidatorInfo<PublicKey>>,
+        proofs_of_possession: &BTreeMap<AccountAddress, PublicKey::SignatureMaterial>,
+    ) -> Result<Self> {
+        for (author, validator_info) in &address_to_validator_info {
+            let proof = proofs_of_possession
+                .get(author)
+                .ok_or_else(|| format_err!("Missing proof of possession for {}", author))?;
+            PublicKey::SignatureMaterial::verify_possession(&validator_info.public_key, proof)
+                .map_err(|_| format_err!("Invalid proof of possession for {}", author))?;
+        }
+        let mut verifier = Self::new(address_to_validator_info);
+        verifier.proofs_of_possession_verified = true;
+        Ok(verifier)
+    }
+
+    /// Aggregates the signatures of a hash by known authors into a single multi-signature.
+    pub fn aggregate_signatures<T>(
+        &self,
+        signatures: &BTreeMap<AccountAddress, T>,
+    ) -> std::result::Result<MultiSignature<PublicKey::SignatureMaterial>, VerifyError>
+    where
+        T: Into<PublicKey::SignatureMaterial> + Clone,
+    {
+        self.check_proofs_of_possession()?;
+        self.check_num_of_signatures(signatures)?;
+        let mut indexes = vec![];
+        let mut raw_signatures = vec![];
+        // Both maps are ordered by address: the validators are walked only once.
+        let mut validators = self.address_to_validator_info.keys().enumerate();
+        for (author, signature) in signatures {
+            let index = validators
+                .find(|(_, address)| *address >= author)
+                .filter(|(_, address)| *address == author)
+                .map(|(index, _)| index)
+                .ok_or(VerifyError::UnknownAuthor)?;
+            indexes.push(index);
+            raw_signatures.push(signature.clone().into());
+        }
+        let signature = if raw_signatures.is_empty() {
+            None
+        } else {
+            Some(
+                PublicKey::SignatureMaterial::aggregate(raw_signatures.iter().collect())
+                    .map_err(|_| VerifyError::InvalidSignature)?,
+            )
+        };
+        Ok(MultiSignature::new(
+            signers_bitmap(indexes.into_iter(), self.len()),
+            signature,
+        ))
+    }
+
+    /// Returns the authors who signed a multi-signature, or an error if its bitmap refers to
+    /// authors that are not known.
+    pub fn get_signers(
+        &self,
+        multi_signature: &MultiSignature<PublicKey::SignatureMaterial>,
+    ) -> std::result::Result<Vec<AccountAddress>, VerifyError> {
+        let bitmap_len = multi_signature.signers().len();
+        if bitmap_len != (self.len() + 7) / 8
+            || (self.len()..bitmap_len * 8).any(|index| multi_signature.is_signer(index))
+        {
+            return Err(VerifyError::InvalidBitmap);
+        }
+        Ok(self
+            .get_ordered_account_addresses_iter()
+            .enumerate()
+            .filter(|(index, _)| multi_signature.is_signer(*index))
+            .map(|(_, address)| address)
+            .collect())
+    }
+
+    /// This function will successfully return when the signers of the multi-signature have at
+    /// least quorum voting power, and the multi-signature is valid for the aggregation of their
+    /// public keys. Verifying it costs a single signature verification whatever the number of
+    /// signers.
+    pub fn verify_multi_signature(
+        &self,
+        hash: HashValue,
+        multi_signature: &MultiSignature<PublicKey::SignatureMaterial>,
+    ) -> std::result::Result<(), VerifyError> {
+        self.check_proofs_of_possession()?;
+        let signers = self.get_signers(multi_signature)?;
+        self.check_voting_power(signers.iter())?;
+        let signature = match multi_signature.signature() {
+            Some(signature) => signature,
+            None if signers.is_empty() => return Ok(()),
+            None => return Err(VerifyError::InvalidSignature),
+        };
+        let public_keys: Vec<_> = signers
+            .iter()
+            .map(|signer| &self.address_to_validator_info[signer].public_key)
+            .collect();
+        let public_key = PublicKey::SignatureMaterial::aggregate_public_keys(public_keys)
+            .map_err(|_| VerifyError::InvalidSignature)?;
+        public_key
+            .verify_signature(&hash, signature)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
+    fn check_proofs_of_possession(&self) -> std::result::Result<(), VerifyError> {
+        if self.proofs_of_possession_verified {
+            Ok(())
+        } else {
+            Err(VerifyError::MissingProofsOfPossession)
+        }
+    }
+}
+
 impl<PublicKey> fmt::Display for ValidatorVerifier<PublicKey> {
     fn fmt(&self, f: &mut fmt::Formatter) -> std::fmt::Result {
         write!(f, "ValidatorSet: [")?;
@@ -323,12 +452,15 @@ impl<PublicKey: VerifyingKey> From<&ValidatorVerifier<PublicKey>> for ValidatorS
 #[cfg(test)]
 mod tests {
     use crate::crypto_proxies::random_validator_verifier;
+    use crate::multi_signature::MultiSignature;
     use crate::validator_verifier::VerifyError::TooLittleVotingPower;
     use crate::{
         validator_signer::ValidatorSigner,
         validator_verifier::{ValidatorInfo, ValidatorVerifier, VerifyError},
     };
-    use libra_crypto::{ed25519::*, test_utils::TEST_SEED, HashValue};
+    use libra_crypto::{
+        bls12381::*, ed25519::*, test_utils::TEST_SEED, traits::AggregatableSignature, HashValue,
+    };
     use std::collections::BTreeMap;
 
     #[test]
@@ -598,4 +730,129 @@ mod tests {
             Err(VerifyError::UnknownAuthor)
         );
     }
+
+    #[test]
+    fn test_multi_signature() {
+        const NUM_SIGNERS: u8 = 10;
+        let validator_signers: Vec<ValidatorSigner<BLS12381PrivateKey>> = (0..NUM_SIGNERS)
+            .map(|i| ValidatorSigner::random([i; 32]))
+            .collect();
+        let validator_infos: BTreeMap<_, _> = validator_signers
+            .iter()
+            .map(|signer| (signer.author(), ValidatorInfo::new(signer.public_key(), 1)))
+            .collect();
+        let mut proofs_of_possession: BTreeMap<_, _> = validator_signers
+            .iter()
+            .map(|signer| {
+                (
+                    signer.author(),
+                    BLS12381Signature::prove_possession(signer.private_key()),
+                )
+            })
+            .collect();
+        let validator_verifier = ValidatorVerifier::new_with_proofs_of_possession(
+            validator_infos.clone(),
+            &proofs_of_possession,
+        )
+        .unwrap();
+        let random_hash = HashValue::random();
+        let author_to_signature_map: BTreeMap<_, _> = validator_signers
+            .iter()
+            .take(7)
+            .map(|validator| {
+                (
+                    validator.author(),
+                    validator.sign_message(random_hash).unwrap(),
+                )
+            })
+            .collect();
+
+        let multi_signature = validator_verifier
+            .aggregate_signatures(&author_to_signature_map)
+            .unwrap();
+        assert_eq!(multi_signature.signers().len(), 2);
+        assert_eq!(
+            validator_verifier.get_signers(&multi_signature).unwrap(),
+            author_to_signature_map.keys().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            validator_verifier.verify_multi_signature(random_hash, &multi_signature),
+            Ok(())
+        );
+        assert_eq!(
+            validator_verifier.verify_multi_signature(HashValue::random(), &multi_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Claiming an extra signer invalidates the signature.
+        let mut signers = multi_signature.signers().to_vec();
+        let missing_signer = validator_verifier
+            .get_ordered_account_addresses_iter()
+            .position(|author| !author_to_signature_map.contains_key(&author))
+            .unwrap();
+        signers[missing_signer / 8] |= 0x80 >> (missing_signer % 8);
+        let forged = MultiSignature::new(signers, multi_signature.signature().cloned());
+        assert_eq!(
+            validator_verifier.verify_multi_signature(random_hash, &forged),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Bits beyond the validator set and bitmaps of the wrong size are rejected.
+        let mut signers = multi_signature.signers().to_vec();
+        signers[1] |= 0x01;
+        let forged = MultiSignature::new(signers, multi_signature.signature().cloned());
+        assert_eq!(
+            validator_verifier.verify_multi_signature(random_hash, &forged),
+            Err(VerifyError::InvalidBitmap)
+        );
+        let forged = MultiSignature::new(vec![0xff], multi_signature.signature().cloned());
+        assert_eq!(
+            validator_verifier.verify_multi_signature(random_hash, &forged),
+            Err(VerifyError::InvalidBitmap)
+        );
+
+        // An unknown author can not be aggregated.
+        let mut author_to_signature_map = author_to_signature_map;
+        let unknown_validator_signer = ValidatorSigner::<BLS12381PrivateKey>::random([100; 32]);
+        author_to_signature_map.insert(
+            unknown_validator_signer.author(),
+            unknown_validator_signer.sign_message(random_hash).unwrap(),
+        );
+        assert_eq!(
+            validator_verifier
+                .aggregate_signatures(&author_to_signature_map)
+                .unwrap_err(),
+            VerifyError::UnknownAuthor
+        );
+
+        // Without proofs of possession, the keys can not be aggregated.
+        let unproven_verifier = ValidatorVerifier::new(validator_infos.clone());
+        assert_eq!(
+            unproven_verifier.verify_multi_signature(random_hash, &multi_signature),
+            Err(VerifyError::MissingProofsOfPossession)
+        );
+        let author = validator_signers[0].author();
+        let proof = proofs_of_possession.remove(&author).unwrap();
+        assert!(ValidatorVerifier::new_with_proofs_of_possession(
+            validator_infos.clone(),
+            &proofs_of_possession
+        )
+        .is_err());
+        // The proof of possession of another key does not do either.
+        proofs_of_possession.insert(
+            author,
+            proofs_of_possession.values().next().unwrap().clone(),
+        );
+        assert!(ValidatorVerifier::new_with_proofs_of_possession(
+            validator_infos.clone(),
+            &proofs_of_possession
+        )
+        .is_err());
+        proofs_of_possession.insert(author, proof);
+        assert!(ValidatorVerifier::new_with_proofs_of_possession(
+            validator_infos,
+            &proofs_of_possession
+        )
+        .is_ok());
+    }
 }
@@ -5,8 +5,9 @@
 //! path it updates. For each access path, the VM can either give its new value or delete it.
 
 use crate::access_path::AccessPath;
-use anyhow::Result;
+use anyhow::{Error, Result};
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 #[derive(Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum WriteOp {
@@ -128,3 +129,18 @@ impl ::std::iter::IntoIterator for WriteSet {
         self.0.write_set.into_iter()
     }
 }
+
+impl TryFrom<crate::proto::types::WriteSet> for WriteSet {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::types::WriteSet) -> Result<Self> {
+        lcs::from_bytes(&proto.write_set).map_err(Into::into)
+    }
+}
+
+impl From<WriteSet> for crate::proto::types::WriteSet {
+    fn from(write_set: WriteSet) -> Self {
+        let bytes = lcs::to_bytes(&write_set).expect("Serialization should not fail.");
+        Self { write_set: bytes }
+    }
+}